@@ -36,6 +36,102 @@ fn main() -> Result<(), translate::Error> {
                 .action(ArgAction::Set)
                 .help("Specify an external utility class to use"),
         )
+        .arg(
+            Arg::new("tail-calls")
+                .value_parser(["trampoline", "plain"])
+                .long("tail-calls")
+                .value_name("STRATEGY")
+                .default_value("trampoline")
+                .required(false)
+                .action(ArgAction::Set)
+                .help("How to translate tail calls (trampolined thunks or plain calls)"),
+        )
+        .arg(
+            Arg::new("memory-backend")
+                .value_parser(["heap", "direct", "array"])
+                .long("memory-backend")
+                .value_name("BACKEND")
+                .default_value("heap")
+                .required(false)
+                .action(ArgAction::Set)
+                .help("Representation of memories (heap/direct `ByteBuffer` or `byte[]`)"),
+        )
+        .arg(
+            Arg::new("unchecked-address-overflow")
+                .long("unchecked-address-overflow")
+                .required(false)
+                .action(ArgAction::SetTrue)
+                .help("Don't trap when address plus offset overflows (only for trusted modules)"),
+        )
+        .arg(
+            Arg::new("optimization-level")
+                .value_parser(clap::value_parser!(u8).range(0..=2))
+                .short('O')
+                .long("optimization-level")
+                .value_name("LEVEL")
+                .default_value("2")
+                .required(false)
+                .action(ArgAction::Set)
+                .help("Optimization level for function bodies (0 to 2)"),
+        )
+        .arg(
+            Arg::new("ssa")
+                .long("ssa")
+                .required(false)
+                .action(ArgAction::SetTrue)
+                .help("Translate function bodies through their SSA form when possible"),
+        )
+        .arg(
+            Arg::new("no-peephole")
+                .long("no-peephole")
+                .required(false)
+                .action(ArgAction::SetTrue)
+                .help("Skip the peephole pass over generated bytecode"),
+        )
+        .arg(
+            Arg::new("call-indirect-cache")
+                .value_parser(clap::value_parser!(u8))
+                .long("call-indirect-cache")
+                .value_name("SIZE")
+                .default_value("4")
+                .required(false)
+                .action(ArgAction::Set)
+                .help("How many targets each `call_indirect` caches (0 disables caching)"),
+        )
+        .arg(
+            Arg::new("inline-max-operators")
+                .value_parser(clap::value_parser!(usize))
+                .long("inline-max-operators")
+                .value_name("OPERATORS")
+                .default_value("0")
+                .required(false)
+                .action(ArgAction::Set)
+                .help("Size (in operators) up to which leaf functions get inlined (0 disables inlining)"),
+        )
+        .arg(
+            Arg::new("no-dead-code-elimination")
+                .long("no-dead-code-elimination")
+                .required(false)
+                .action(ArgAction::SetTrue)
+                .help("Translate functions and globals even if they can't be reached"),
+        )
+        .arg(
+            Arg::new("no-function-deduplication")
+                .long("no-function-deduplication")
+                .required(false)
+                .action(ArgAction::SetTrue)
+                .help("Translate identical functions into separate methods"),
+        )
+        .arg(
+            Arg::new("data-storage")
+                .value_parser(["strings", "resources", "compressed-resources"])
+                .long("data-storage")
+                .value_name("STORAGE")
+                .default_value("strings")
+                .required(false)
+                .action(ArgAction::Set)
+                .help("Where to store the bytes of data segments (string constants or resource files)"),
+        )
         .arg(
             Arg::new("INPUT")
                 .value_parser(clap::value_parser!(PathBuf))
@@ -45,10 +141,36 @@ fn main() -> Result<(), translate::Error> {
         )
         .get_matches();
 
-    let settings = translate::Settings::new(
+    let mut settings = translate::Settings::new(
         matches.get_one::<String>("class").unwrap(),
         matches.get_one::<String>("utils").map(|x| &**x),
     )?;
+    settings.tail_call_strategy = match matches.get_one::<String>("tail-calls").unwrap().as_str() {
+        "plain" => translate::TailCallStrategy::PlainCall,
+        _ => translate::TailCallStrategy::Trampoline,
+    };
+    settings.memory_backend = match matches
+        .get_one::<String>("memory-backend")
+        .unwrap()
+        .as_str()
+    {
+        "direct" => translate::MemoryBackend::DirectByteBuffer,
+        "array" => translate::MemoryBackend::ByteArray,
+        _ => translate::MemoryBackend::HeapByteBuffer,
+    };
+    settings.optimization_level = *matches.get_one::<u8>("optimization-level").unwrap();
+    settings.trap_address_overflow = !matches.get_flag("unchecked-address-overflow");
+    settings.ssa_translation = matches.get_flag("ssa");
+    settings.peephole_optimization = !matches.get_flag("no-peephole");
+    settings.call_indirect_cache_size = *matches.get_one::<u8>("call-indirect-cache").unwrap();
+    settings.inline_max_operators = *matches.get_one::<usize>("inline-max-operators").unwrap();
+    settings.dead_code_elimination = !matches.get_flag("no-dead-code-elimination");
+    settings.deduplicate_functions = !matches.get_flag("no-function-deduplication");
+    settings.data_storage = match matches.get_one::<String>("data-storage").unwrap().as_str() {
+        "resources" => translate::DataStorage::Resources,
+        "compressed-resources" => translate::DataStorage::CompressedResources,
+        _ => translate::DataStorage::StringConstants,
+    };
 
     let class_graph_arenas = jvm::class_graph::ClassGraphArenas::new();
     let class_graph = jvm::class_graph::ClassGraph::new(&class_graph_arenas);
@@ -62,7 +184,8 @@ fn main() -> Result<(), translate::Error> {
 
     // Write out the results
     let mut output_files = vec![];
-    for (class_name, class) in translator.result()? {
+    let translation = translator.result()?;
+    for (class_name, class) in translation.classes {
         let class_file = format!("{}.class", class_name.as_str());
         log::info!("Writing '{}'", &class_file);
         class
@@ -70,6 +193,15 @@ fn main() -> Result<(), translate::Error> {
             .map_err(jvm::Error::IoError)?;
         output_files.push(class_file);
     }
+    for (resource_name, contents) in translation.resources {
+        log::info!("Writing '{}'", &resource_name);
+        let resource_path = PathBuf::from(&resource_name);
+        if let Some(parent) = resource_path.parent() {
+            fs::create_dir_all(parent).map_err(jvm::Error::IoError)?;
+        }
+        fs::write(&resource_path, contents).map_err(jvm::Error::IoError)?;
+        output_files.push(resource_name);
+    }
 
     // Package the results in a JAR
     if let Some(jar_name) = matches.get_one::<PathBuf>("jar") {
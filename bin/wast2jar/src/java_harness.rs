@@ -48,7 +48,7 @@ use crate::java_writer::JavaWriter;
 use crate::wat_translator::WatTranslator;
 use wasm2jar::translate;
 use wast::component::Component;
-use wast::core::{Module, NanPattern, WastArgCore, WastRetCore};
+use wast::core::{Module, NanPattern, V128Pattern, WastArgCore, WastRetCore};
 use wast::lexer::Lexer;
 use wast::parser::{parse, ParseBuffer};
 use wast::token::{Float32, Float64, Id, Span};
@@ -199,6 +199,15 @@ impl<'a, P: Display, W: Write, T: WatTranslator> JavaHarness<'a, P, W, T> {
             self.writer,
             "spectestWriter = new PrintWriter(\"spectest.log\");"
         )?;
+        let spectest_memory = match self.translator.memory_backend() {
+            translate::MemoryBackend::HeapByteBuffer => {
+                "new Memory(java.nio.ByteBuffer.allocate(65536))"
+            }
+            translate::MemoryBackend::DirectByteBuffer => {
+                "new Memory(java.nio.ByteBuffer.allocateDirect(65536))"
+            }
+            translate::MemoryBackend::ByteArray => "new Memory(new byte[65536])",
+        };
         for (name, value) in [
             ("print", "new Function(lookup.findStatic(JavaHarness.class, \"print\", MethodType.methodType(void.class)))"),
             ("print_i32", "new Function(lookup.findStatic(JavaHarness.class, \"printI32\", MethodType.methodType(void.class, int.class)))"),
@@ -211,7 +220,7 @@ impl<'a, P: Display, W: Write, T: WatTranslator> JavaHarness<'a, P, W, T> {
             ("global_i64", "new org.wasm2jar.Global(666L, false)"),
             ("global_f32", "new org.wasm2jar.Global(666f, false)"),
             ("global_f64", "new org.wasm2jar.Global(666d, false)"),
-            ("memory", "new Memory(java.nio.ByteBuffer.allocate(65536))"),
+            ("memory", spectest_memory),
             ("table", "new FunctionTable(new MethodHandle[10])"),
         ] {
             writeln!(self.writer, "spectest.put(\"{}\", {});", name, value)?;
@@ -402,9 +411,9 @@ impl<'a, P: Display, W: Write, T: WatTranslator> JavaHarness<'a, P, W, T> {
                         self.writer.close_curly_block()?;
                     }
                     _ => {
-                        write!(self.writer, "Object[] result = (Object[])")?;
+                        write!(self.writer, "Object[] result = ((Tuple)")?;
                         self.visit_wast_execute(exec)?;
-                        writeln!(self.writer, ";")?;
+                        writeln!(self.writer, ").toArray();")?;
 
                         // Check the arguments match
                         for (i, result) in results.iter().enumerate() {
@@ -586,8 +595,38 @@ impl<'a, P: Display, W: Write, T: WatTranslator> JavaHarness<'a, P, W, T> {
 
                 true
             }
-            WastDirective::AssertException { .. } => {
-                return Err(TestError::IncompleteHarness("assert_exception"))
+            WastDirective::AssertException { span, exec } => {
+                let span_str = self.pretty_span(span);
+
+                write!(self.writer, "try")?;
+                self.writer.open_curly_block()?;
+
+                self.visit_wast_execute(exec)?;
+                writeln!(self.writer, ";")?;
+                writeln!(self.writer, "somethingFailed = true;")?;
+                writeln!(
+                    self.writer,
+                    "System.out.println(\"Unexpected success at {}\");",
+                    &span_str
+                )?;
+
+                self.writer.close_curly_block()?;
+
+                write!(self.writer, "catch (org.wasm2jar.WasmException e)")?;
+                self.writer.open_curly_block()?;
+                self.writer.close_curly_block()?;
+
+                write!(self.writer, "catch (Throwable e)")?;
+                self.writer.open_curly_block()?;
+                writeln!(self.writer, "somethingFailed = true;")?;
+                writeln!(
+                    self.writer,
+                    "System.out.println(\"Unexpected error at {}: \" + e.toString());",
+                    &span_str
+                )?;
+                self.writer.close_curly_block()?;
+
+                true
             }
         })
     }
@@ -750,8 +789,14 @@ impl<'a, P: Display, W: Write, T: WatTranslator> JavaHarness<'a, P, W, T> {
             WastArg::Core(WastArgCore::RefExtern(idx)) => {
                 write!(self.writer, "Integer.valueOf({})", idx)
             }
-            WastArg::Core(WastArgCore::V128(_)) => {
-                return Err(TestError::IncompleteHarness("visit_wast_arg: V128"))
+            WastArg::Core(WastArgCore::V128(v128)) => {
+                let bits = u128::from_le_bytes(v128.to_le_bytes());
+                write!(
+                    self.writer,
+                    "new long[] {{ {:#x}L, {:#x}L }}",
+                    bits as u64,
+                    (bits >> 64) as u64
+                )
             }
             WastArg::Component(_) => {
                 return Err(TestError::IncompleteHarness("visit_wast_arg: Component"))
@@ -813,8 +858,55 @@ impl<'a, P: Display, W: Write, T: WatTranslator> JavaHarness<'a, P, W, T> {
             WastRet::Core(WastRetCore::RefFunc(_)) => {
                 Err(TestError::IncompleteHarness("visit_wast_ret: RefFunc"))
             }
-            WastRet::Core(WastRetCore::V128(_)) => {
-                Err(TestError::IncompleteHarness("visit_wast_ret: V128"))
+            WastRet::Core(WastRetCore::V128(pattern)) => {
+                // Floating point lanes may be NaN patterns, so they get checked lane by lane
+                let lane_checks: Vec<String> = match pattern {
+                    V128Pattern::F32x4(lanes) => lanes
+                        .iter()
+                        .enumerate()
+                        .map(|(i, lane)| {
+                            let lane_bits = format!("(int) (v128[{}] >>> {})", i / 2, i % 2 * 32);
+                            match lane {
+                                NanPattern::CanonicalNan | NanPattern::ArithmeticNan => {
+                                    format!("Float.isNaN(Float.intBitsToFloat({}))", lane_bits)
+                                }
+                                NanPattern::Value(Float32 { bits }) => {
+                                    format!("{} == {:#x}", lane_bits, bits)
+                                }
+                            }
+                        })
+                        .collect(),
+                    V128Pattern::F64x2(lanes) => lanes
+                        .iter()
+                        .enumerate()
+                        .map(|(i, lane)| match lane {
+                            NanPattern::CanonicalNan | NanPattern::ArithmeticNan => {
+                                format!("Double.isNaN(Double.longBitsToDouble(v128[{}]))", i)
+                            }
+                            NanPattern::Value(Float64 { bits }) => {
+                                format!("v128[{}] == {:#x}L", i, bits)
+                            }
+                        })
+                        .collect(),
+                    V128Pattern::I8x16(lanes) => {
+                        Self::v128_equals_check(lanes.map(|lane| lane as u8))
+                    }
+                    V128Pattern::I16x8(lanes) => {
+                        Self::v128_equals_check(lanes.iter().flat_map(|lane| lane.to_le_bytes()))
+                    }
+                    V128Pattern::I32x4(lanes) => {
+                        Self::v128_equals_check(lanes.iter().flat_map(|lane| lane.to_le_bytes()))
+                    }
+                    V128Pattern::I64x2(lanes) => {
+                        Self::v128_equals_check(lanes.iter().flat_map(|lane| lane.to_le_bytes()))
+                    }
+                };
+                write!(
+                    self.writer,
+                    "((java.util.function.Predicate<long[]>) v128 -> {}).test(",
+                    lane_checks.join(" && ")
+                )?;
+                Ok(")")
             }
             WastRet::Core(WastRetCore::Either(_)) => {
                 Err(TestError::IncompleteHarness("visit_wast_ret: Either"))
@@ -843,9 +935,7 @@ impl<'a, P: Display, W: Write, T: WatTranslator> JavaHarness<'a, P, W, T> {
             WastRet::Core(WastRetCore::RefFunc(_)) => {
                 Err(TestError::IncompleteHarness("java_assert_type: RefFunc"))
             }
-            WastRet::Core(WastRetCore::V128(_)) => {
-                Err(TestError::IncompleteHarness("java_assert_type: V128"))
-            }
+            WastRet::Core(WastRetCore::V128(_)) => Ok(("long[]", "long[]", "")),
             WastRet::Core(WastRetCore::Either(_)) => {
                 Err(TestError::IncompleteHarness("visit_wast_ret: Either"))
             }
@@ -855,6 +945,20 @@ impl<'a, P: Display, W: Write, T: WatTranslator> JavaHarness<'a, P, W, T> {
         }
     }
 
+    /// Check that a `v128` (named `v128`) has exactly the specified little-endian bytes
+    fn v128_equals_check(bytes: impl IntoIterator<Item = u8>) -> Vec<String> {
+        let mut le_bytes = [0u8; 16];
+        for (byte, expected) in le_bytes.iter_mut().zip(bytes) {
+            *byte = expected;
+        }
+        let bits = u128::from_le_bytes(le_bytes);
+        vec![format!(
+            "java.util.Arrays.equals(new long[] {{ {:#x}L, {:#x}L }}, v128)",
+            bits as u64,
+            (bits >> 64) as u64
+        )]
+    }
+
     /// Pretty-print a span
     fn pretty_span(&self, span: Span) -> String {
         let (line, col) = span.linecol_in(self.wast_source);
@@ -879,6 +983,10 @@ mod test {
         ) -> Result<(), TestError> {
             Ok(())
         }
+
+        fn memory_backend(&self) -> translate::MemoryBackend {
+            translate::MemoryBackend::HeapByteBuffer
+        }
     }
 
     /// Generates a "renderer" for a parseable type
@@ -951,7 +1059,7 @@ mod test {
             .collect::<Vec<_>>(),
             vec![
                 r#"try {"#,
-                r#"    Object[] result = (Object[])getFunc(null, "baz").invoke(10);"#,
+                r#"    Object[] result = ((Tuple)getFunc(null, "baz").invoke(10)).toArray();"#,
                 r#"    long result0 = ((Long) result[0]).longValue();"#,
                 r#"    if (!(20L == result0)) {"#,
                 r#"        System.out.println("Incorrect return #0 at test:1:1: found " + result0);"#,
@@ -1161,6 +1269,11 @@ mod test {
         assert_eq!(render_wast_arg("ref.extern 42")?, "Integer.valueOf(42)");
         assert_eq!(render_wast_arg("ref.extern 21")?, "Integer.valueOf(21)");
 
+        assert_eq!(
+            render_wast_arg("v128.const i32x4 1 2 3 -1")?,
+            "new long[] { 0x200000001L, 0xffffffff00000003L }"
+        );
+
         Ok(())
     }
 
@@ -1251,6 +1364,15 @@ mod test {
             "Integer.valueOf(21).equals(x)"
         );
 
+        assert_eq!(
+            render_wast_ret("v128.const i64x2 1 -1")?,
+            "((java.util.function.Predicate<long[]>) v128 -> java.util.Arrays.equals(new long[] { 0x1L, 0xffffffffffffffffL }, v128)).test(x)"
+        );
+        assert_eq!(
+            render_wast_ret("v128.const f64x2 nan:canonical 1.0")?,
+            "((java.util.function.Predicate<long[]>) v128 -> Double.isNaN(Double.longBitsToDouble(v128[0])) && v128[1] == 0x3ff0000000000000L).test(x)"
+        );
+
         Ok(())
     }
 }
@@ -18,7 +18,8 @@ use std::process::exit;
 use std::{fs, io};
 use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
 use walkdir::WalkDir;
-use wat_translator::Wasm2JarTranslator;
+use wasm2jar::translate::{DataStorage, MemoryBackend, TailCallStrategy};
+use wat_translator::{TranslationOptions, Wasm2JarTranslator};
 
 fn main() -> io::Result<()> {
     use clap::{Arg, ArgAction, Command};
@@ -58,6 +59,112 @@ fn main() -> io::Result<()> {
                 .action(ArgAction::Set)
                 .help("Sets the `javac` executable to use"),
         )
+        .arg(
+            Arg::new("tail-calls")
+                .value_parser(["trampoline", "plain"])
+                .long("tail-calls")
+                .value_name("STRATEGY")
+                .default_value("trampoline")
+                .required(false)
+                .action(ArgAction::Set)
+                .help("Sets how tail calls are translated (trampolined thunks or plain calls)"),
+        )
+        .arg(
+            Arg::new("memory-backend")
+                .value_parser(["heap", "direct", "array"])
+                .long("memory-backend")
+                .value_name("BACKEND")
+                .default_value("heap")
+                .required(false)
+                .action(ArgAction::Set)
+                .help("Sets the representation of memories (heap/direct `ByteBuffer` or `byte[]`)"),
+        )
+        .arg(
+            Arg::new("unchecked-address-overflow")
+                .long("unchecked-address-overflow")
+                .required(false)
+                .action(ArgAction::SetTrue)
+                .help("Don't trap when address plus offset overflows"),
+        )
+        .arg(
+            Arg::new("optimization-level")
+                .value_parser(clap::value_parser!(u8).range(0..=2))
+                .short('O')
+                .long("optimization-level")
+                .value_name("LEVEL")
+                .default_value("2")
+                .required(false)
+                .action(ArgAction::Set)
+                .help("Sets the optimization level for function bodies (0 to 2)"),
+        )
+        .arg(
+            Arg::new("ssa")
+                .long("ssa")
+                .required(false)
+                .action(ArgAction::SetTrue)
+                .help("Translates function bodies through their SSA form when possible"),
+        )
+        .arg(
+            Arg::new("no-peephole")
+                .long("no-peephole")
+                .required(false)
+                .action(ArgAction::SetTrue)
+                .help("Skips the peephole pass over generated bytecode"),
+        )
+        .arg(
+            Arg::new("call-indirect-cache")
+                .value_parser(clap::value_parser!(u8))
+                .long("call-indirect-cache")
+                .value_name("SIZE")
+                .default_value("4")
+                .required(false)
+                .action(ArgAction::Set)
+                .help("Sets how many targets each `call_indirect` caches (0 disables caching)"),
+        )
+        .arg(
+            Arg::new("inline-max-operators")
+                .value_parser(clap::value_parser!(usize))
+                .long("inline-max-operators")
+                .value_name("OPERATORS")
+                .default_value("0")
+                .required(false)
+                .action(ArgAction::Set)
+                .help("Sets the size (in operators) up to which leaf functions get inlined (0 disables inlining)"),
+        )
+        .arg(
+            Arg::new("no-dead-code-elimination")
+                .long("no-dead-code-elimination")
+                .required(false)
+                .action(ArgAction::SetTrue)
+                .help("Translates functions and globals even if they can't be reached"),
+        )
+        .arg(
+            Arg::new("no-function-deduplication")
+                .long("no-function-deduplication")
+                .required(false)
+                .action(ArgAction::SetTrue)
+                .help("Translates identical functions into separate methods"),
+        )
+        .arg(
+            Arg::new("data-storage")
+                .value_parser(["strings", "resources", "compressed-resources"])
+                .long("data-storage")
+                .value_name("STORAGE")
+                .default_value("strings")
+                .required(false)
+                .action(ArgAction::Set)
+                .help("Sets where the bytes of data segments are stored (string constants or resource files)"),
+        )
+        .arg(
+            Arg::new("part-max-constants")
+                .value_parser(clap::value_parser!(usize))
+                .long("part-max-constants")
+                .value_name("CONSTANTS")
+                .default_value("48000")
+                .required(false)
+                .action(ArgAction::Set)
+                .help("Sets the number of constants above which functions go in a new part class"),
+        )
         .arg(
             Arg::new("INPUT")
                 .value_parser(clap::value_parser!(PathBuf))
@@ -71,6 +178,35 @@ fn main() -> io::Result<()> {
     let output_path: &PathBuf = matches.get_one("output").unwrap();
     let java: &PathBuf = matches.get_one("java").unwrap();
     let javac: &PathBuf = matches.get_one("javac").unwrap();
+    let options = TranslationOptions {
+        tail_call_strategy: match matches.get_one::<String>("tail-calls").unwrap().as_str() {
+            "plain" => TailCallStrategy::PlainCall,
+            _ => TailCallStrategy::Trampoline,
+        },
+        memory_backend: match matches
+            .get_one::<String>("memory-backend")
+            .unwrap()
+            .as_str()
+        {
+            "direct" => MemoryBackend::DirectByteBuffer,
+            "array" => MemoryBackend::ByteArray,
+            _ => MemoryBackend::HeapByteBuffer,
+        },
+        trap_address_overflow: !matches.get_flag("unchecked-address-overflow"),
+        optimization_level: *matches.get_one("optimization-level").unwrap(),
+        ssa_translation: matches.get_flag("ssa"),
+        peephole_optimization: !matches.get_flag("no-peephole"),
+        call_indirect_cache_size: *matches.get_one("call-indirect-cache").unwrap(),
+        inline_max_operators: *matches.get_one("inline-max-operators").unwrap(),
+        dead_code_elimination: !matches.get_flag("no-dead-code-elimination"),
+        deduplicate_functions: !matches.get_flag("no-function-deduplication"),
+        data_storage: match matches.get_one::<String>("data-storage").unwrap().as_str() {
+            "resources" => DataStorage::Resources,
+            "compressed-resources" => DataStorage::CompressedResources,
+            _ => DataStorage::StringConstants,
+        },
+        part_max_constants: *matches.get_one("part-max-constants").unwrap(),
+    };
 
     // Find all of the test cases
     let tests: Vec<PathBuf> = if input_path.is_file() {
@@ -124,7 +260,7 @@ fn main() -> io::Result<()> {
         fs::create_dir_all(output_subdirectory)?;
 
         // Run the test
-        let outcome: TestOutcome = run_test(java, javac, &test, output_subdirectory)
+        let outcome: TestOutcome = run_test(java, javac, options, &test, output_subdirectory)
             .map_or_else(TestOutcome::from, |_| TestOutcome::Ok);
 
         let (color, summary, message) = match outcome {
@@ -192,6 +328,7 @@ fn main() -> io::Result<()> {
 fn run_test(
     java: impl AsRef<OsStr>,
     javac: impl AsRef<OsStr>,
+    options: TranslationOptions,
     wast_file: impl AsRef<Path>,
     output_directory: impl AsRef<Path>,
 ) -> Result<(), TestError> {
@@ -208,7 +345,10 @@ fn run_test(
         wast_file.display(),
         wast_source,
         fs::File::create(&java_harness_file)?,
-        Wasm2JarTranslator { output_directory },
+        Wasm2JarTranslator {
+            output_directory,
+            options,
+        },
     )?;
 
     // Compile and run the harness (calls out to `javac` and `java`)
@@ -16,10 +16,54 @@ pub trait WatTranslator {
         dry_run: bool,
         module: QuoteWat,
     ) -> Result<(), TestError>;
+
+    /// Representation of memory bytes in the translated modules
+    ///
+    /// Memories created by the harness (eg. `spectest.memory`) must match this.
+    fn memory_backend(&self) -> translate::MemoryBackend;
+}
+
+/// Translation settings picked on the command line
+#[derive(Copy, Clone)]
+pub struct TranslationOptions {
+    pub tail_call_strategy: translate::TailCallStrategy,
+    pub memory_backend: translate::MemoryBackend,
+    pub trap_address_overflow: bool,
+    pub optimization_level: u8,
+    pub ssa_translation: bool,
+    pub peephole_optimization: bool,
+    pub call_indirect_cache_size: u8,
+    pub inline_max_operators: usize,
+    pub dead_code_elimination: bool,
+    pub deduplicate_functions: bool,
+    pub data_storage: translate::DataStorage,
+    pub part_max_constants: usize,
+}
+
+impl TranslationOptions {
+    /// Settings for translating a module into the class with the given name
+    fn settings(&self, name: &str) -> Result<translate::Settings, translate::Error> {
+        let mut settings = translate::Settings::new(name, None)?;
+        settings.methods_for_function_exports = false;
+        settings.tail_call_strategy = self.tail_call_strategy;
+        settings.memory_backend = self.memory_backend;
+        settings.trap_address_overflow = self.trap_address_overflow;
+        settings.optimization_level = self.optimization_level;
+        settings.ssa_translation = self.ssa_translation;
+        settings.peephole_optimization = self.peephole_optimization;
+        settings.call_indirect_cache_size = self.call_indirect_cache_size;
+        settings.inline_max_operators = self.inline_max_operators;
+        settings.dead_code_elimination = self.dead_code_elimination;
+        settings.deduplicate_functions = self.deduplicate_functions;
+        settings.data_storage = self.data_storage;
+        settings.part_max_constants = self.part_max_constants;
+        Ok(settings)
+    }
 }
 
 pub struct Wasm2JarTranslator<P: AsRef<Path>> {
     pub output_directory: P,
+    pub options: TranslationOptions,
 }
 
 impl<P: AsRef<Path>> WatTranslator for Wasm2JarTranslator<P> {
@@ -30,25 +74,22 @@ impl<P: AsRef<Path>> WatTranslator for Wasm2JarTranslator<P> {
         module: QuoteWat,
     ) -> Result<(), TestError> {
         // Translate the module
-        let mut settings = translate::Settings::new(name, None)?;
-        settings.methods_for_function_exports = false;
+        let settings = self.options.settings(name)?;
         let mut module = module;
         let wasm_bytes: Vec<u8> = module.encode()?;
 
-        let translation_result =
-            || -> Result<Vec<(jvm::BinaryName, jvm::class_file::ClassFile)>, translate::Error> {
-                let class_graph_arenas = jvm::class_graph::ClassGraphArenas::new();
-                let class_graph = jvm::class_graph::ClassGraph::new(&class_graph_arenas);
-                let java = class_graph.insert_java_library_types();
+        let translation_result = || -> Result<translate::Translation, translate::Error> {
+            let class_graph_arenas = jvm::class_graph::ClassGraphArenas::new();
+            let class_graph = jvm::class_graph::ClassGraph::new(&class_graph_arenas);
+            let java = class_graph.insert_java_library_types();
 
-                let mut translator =
-                    translate::ModuleTranslator::new(settings, &class_graph, &java)?;
-                let _types = translator.parse_module(&wasm_bytes)?;
-                translator.result()
-            };
+            let mut translator = translate::ModuleTranslator::new(settings, &class_graph, &java)?;
+            let _types = translator.parse_module(&wasm_bytes)?;
+            translator.result()
+        };
 
         // TODO: catch should be removed once `wasm2jar` doesn't use `todo`
-        let classes = match std::panic::catch_unwind(translation_result) {
+        let translation = match std::panic::catch_unwind(translation_result) {
             Ok(res) => res?,
             Err(e) => {
                 let message: String = if let Some(e) = e.downcast_ref::<&'static str>() {
@@ -62,9 +103,9 @@ impl<P: AsRef<Path>> WatTranslator for Wasm2JarTranslator<P> {
             }
         };
 
-        // Save classfiles
+        // Save classfiles and resources
         if !dry_run {
-            for (class_name, class) in classes {
+            for (class_name, class) in translation.classes {
                 let class_file = self
                     .output_directory
                     .as_ref()
@@ -73,8 +114,21 @@ impl<P: AsRef<Path>> WatTranslator for Wasm2JarTranslator<P> {
                     .save_to_path(&class_file, true)
                     .map_err(|err| translate::Error::BytecodeGen(jvm::Error::IoError(err)))?;
             }
+            for (resource_name, contents) in translation.resources {
+                let resource_file = self.output_directory.as_ref().join(resource_name);
+                if let Some(parent) = resource_file.parent() {
+                    std::fs::create_dir_all(parent)
+                        .map_err(|err| translate::Error::BytecodeGen(jvm::Error::IoError(err)))?;
+                }
+                std::fs::write(&resource_file, contents)
+                    .map_err(|err| translate::Error::BytecodeGen(jvm::Error::IoError(err)))?;
+            }
         }
 
         Ok(())
     }
+
+    fn memory_backend(&self) -> translate::MemoryBackend {
+        self.options.memory_backend
+    }
 }
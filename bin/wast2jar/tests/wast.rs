@@ -0,0 +1,812 @@
+//! Run the WAST files in `tests/wast` through `wast2jar`
+//!
+//! These need `java` and `javac` on the path, so they get skipped if those aren't available.
+
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn java_available() -> bool {
+    ["java", "javac"].iter().all(|executable| {
+        Command::new(executable)
+            .arg("-version")
+            .output()
+            .map_or(false, |output| output.status.success())
+    })
+}
+
+/// Run `wast2jar` on a file from `tests/wast` with some extra command line flags, returning the
+/// directory holding the translated classes and the Java harness
+fn run_wast(file_name: &str, flags: &[&str]) -> PathBuf {
+    let input = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("wast")
+        .join(file_name);
+    run_wast_path(&input, flags)
+}
+
+/// Run `wast2jar` on WAST generated by a test (for inputs too big to check in), returning the
+/// directory holding the translated classes and the Java harness
+fn run_generated_wast(name: &str, wast: &str, flags: &[&str]) -> PathBuf {
+    let directory = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("generated");
+    fs::create_dir_all(&directory).unwrap();
+    let input = directory.join(format!("{}.wast", name));
+    fs::write(&input, wast).unwrap();
+    run_wast_path(&input, flags)
+}
+
+fn run_wast_path(input: &Path, flags: &[&str]) -> PathBuf {
+    let file_name = input.file_name().unwrap().to_string_lossy();
+    let output_directory = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("wast");
+    let output = Command::new(env!("CARGO_BIN_EXE_wast2jar"))
+        .args(flags)
+        .arg("--output-directory")
+        .arg(&output_directory)
+        .arg(&input)
+        .output()
+        .expect("Failed to run wast2jar");
+
+    assert!(
+        output.status.success(),
+        "{} failed with {:?}:\n{}{}",
+        file_name,
+        flags,
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr),
+    );
+    output_directory.join(input.file_stem().unwrap())
+}
+
+#[test]
+fn simd() {
+    if !java_available() {
+        eprintln!("Skipping since `java` or `javac` is missing");
+        return;
+    }
+    for memory_backend in ["heap", "direct", "array"] {
+        run_wast("simd.wast", &["--memory-backend", memory_backend]);
+    }
+}
+
+#[test]
+fn tail_calls() {
+    if !java_available() {
+        eprintln!("Skipping since `java` or `javac` is missing");
+        return;
+    }
+    run_wast("tail_calls.wast", &[]);
+    run_wast("tail_calls.wast", &["--tail-calls", "plain"]);
+}
+
+#[test]
+fn return_call_indirect() {
+    if !java_available() {
+        eprintln!("Skipping since `java` or `javac` is missing");
+        return;
+    }
+    run_wast("return_call_indirect.wast", &[]);
+    run_wast(
+        "return_call_indirect.wast",
+        &["-O", "0", "--no-peephole", "--call-indirect-cache", "0"],
+    );
+}
+
+#[test]
+fn memory_backends() {
+    if !java_available() {
+        eprintln!("Skipping since `java` or `javac` is missing");
+        return;
+    }
+    for memory_backend in ["heap", "direct", "array"] {
+        run_wast(
+            "memory_backends.wast",
+            &["--memory-backend", memory_backend],
+        );
+    }
+}
+
+#[test]
+fn atomics() {
+    if !java_available() {
+        eprintln!("Skipping since `java` or `javac` is missing");
+        return;
+    }
+    for memory_backend in ["heap", "direct"] {
+        run_wast("atomics.wast", &["--memory-backend", memory_backend]);
+    }
+}
+
+#[test]
+fn shared_memory_threads() {
+    if !java_available() {
+        eprintln!("Skipping since `java` or `javac` is missing");
+        return;
+    }
+    let harness_directory = run_wast("shared_memory_threads.wast", &[]);
+
+    // Increments and grows from other threads must not get lost
+    let driver = r#"
+        import java.util.HashMap;
+        import java.util.Map;
+        import org.wasm2jar.Function;
+
+        public class ThreadsDriver {
+            public static void main(String[] args) throws Throwable {
+                Map<String, Object> exports = new Anonymous1(new HashMap<>()).exports;
+                Function work = (Function) exports.get("work");
+                Thread[] threads = new Thread[8];
+                for (int i = 0; i < threads.length; i++) {
+                    threads[i] = new Thread(() -> {
+                        try {
+                            int unused = (int) work.handle.invoke(100000);
+                        } catch (Throwable throwable) {
+                            throw new RuntimeException(throwable);
+                        }
+                    });
+                    threads[i].start();
+                }
+                for (Thread thread : threads) {
+                    thread.join();
+                }
+                int count = (int) ((Function) exports.get("count")).handle.invoke();
+                int size = (int) ((Function) exports.get("size")).handle.invoke();
+                System.out.println(count + " " + size);
+            }
+        }
+    "#;
+    fs::write(harness_directory.join("ThreadsDriver.java"), driver).unwrap();
+    let output = Command::new("javac")
+        .current_dir(&harness_directory)
+        .arg("ThreadsDriver.java")
+        .output()
+        .expect("Failed to run javac");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let output = Command::new("java")
+        .current_dir(&harness_directory)
+        .arg("ThreadsDriver")
+        .output()
+        .expect("Failed to run java");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "800000 64");
+}
+
+#[test]
+fn exceptions() {
+    if !java_available() {
+        eprintln!("Skipping since `java` or `javac` is missing");
+        return;
+    }
+    run_wast("exceptions.wast", &[]);
+    run_wast("exceptions.wast", &["-O", "0", "--no-peephole"]);
+}
+
+#[test]
+fn multi_memory() {
+    if !java_available() {
+        eprintln!("Skipping since `java` or `javac` is missing");
+        return;
+    }
+    for memory_backend in ["heap", "direct", "array"] {
+        run_wast("multi_memory.wast", &["--memory-backend", memory_backend]);
+    }
+}
+
+#[test]
+fn memory64() {
+    if !java_available() {
+        eprintln!("Skipping since `java` or `javac` is missing");
+        return;
+    }
+    run_wast("memory64.wast", &[]);
+    run_wast("memory64.wast", &["-O", "0", "--no-peephole"]);
+}
+
+#[test]
+fn extended_const() {
+    if !java_available() {
+        eprintln!("Skipping since `java` or `javac` is missing");
+        return;
+    }
+    run_wast("extended_const.wast", &[]);
+}
+
+#[test]
+fn wide_functions() {
+    if !java_available() {
+        eprintln!("Skipping since `java` or `javac` is missing");
+        return;
+    }
+    run_wast("wide_functions.wast", &[]);
+    run_wast("wide_functions.wast", &["-O", "0", "--no-peephole"]);
+    run_wast("wide_functions.wast", &["--tail-calls", "plain"]);
+}
+
+#[test]
+fn address_overflow() {
+    if !java_available() {
+        eprintln!("Skipping since `java` or `javac` is missing");
+        return;
+    }
+    run_wast("address_overflow.wast", &[]);
+    run_wast("address_overflow.wast", &["-O", "0", "--no-peephole"]);
+    run_wast(
+        "address_overflow_unchecked.wast",
+        &["--unchecked-address-overflow"],
+    );
+}
+
+#[test]
+fn ssa_loops() {
+    if !java_available() {
+        eprintln!("Skipping since `java` or `javac` is missing");
+        return;
+    }
+    run_wast("ssa_loops.wast", &["--ssa"]);
+    run_wast("ssa_loops.wast", &["--ssa", "-O", "0", "--no-peephole"]);
+}
+
+#[test]
+fn multi_value() {
+    if !java_available() {
+        eprintln!("Skipping since `java` or `javac` is missing");
+        return;
+    }
+    run_wast("multi_value.wast", &[]);
+    run_wast("multi_value.wast", &["-O", "0", "--no-peephole"]);
+    run_wast("multi_value.wast", &["--ssa"]);
+}
+
+#[test]
+fn memory_cache() {
+    if !java_available() {
+        eprintln!("Skipping since `java` or `javac` is missing");
+        return;
+    }
+    for memory_backend in ["heap", "direct", "array"] {
+        run_wast("memory_cache.wast", &["--memory-backend", memory_backend]);
+    }
+    run_wast("memory_cache.wast", &["-O", "0", "--no-peephole"]);
+}
+
+#[test]
+fn identical_functions() {
+    if !java_available() {
+        eprintln!("Skipping since `java` or `javac` is missing");
+        return;
+    }
+    run_wast("identical_functions.wast", &[]);
+    run_wast("identical_functions.wast", &["-O", "0", "--no-peephole"]);
+    run_wast("identical_functions.wast", &["--no-function-deduplication"]);
+}
+
+#[test]
+fn large_switch() {
+    if !java_available() {
+        eprintln!("Skipping since `java` or `javac` is missing");
+        return;
+    }
+
+    // Each case updates the accumulator with its own multipliers and increments
+    let cases = 120;
+    let step = |case: i32, statement: i32| (3 + (case + statement) % 7, case * 1000 + statement);
+    let mut wast = String::from(
+        "(module\n(func (export \"switch\") (param $x i32) (result i32) (local $acc i32)\n\
+         (local.set $acc (local.get $x))\nblock $exit\nblock $default\n",
+    );
+    for case in (0..cases).rev() {
+        writeln!(wast, "block $c{}", case).unwrap();
+    }
+    wast.push_str("local.get $x\nbr_table");
+    for case in 0..cases {
+        write!(wast, " $c{}", case).unwrap();
+    }
+    wast.push_str(" $default\n");
+    for case in 0..cases {
+        wast.push_str("end\n");
+        for statement in 0..80 {
+            let (factor, increment) = step(case, statement);
+            writeln!(
+                wast,
+                "(local.set $acc (i32.add (i32.mul (local.get $acc) (i32.const {})) (i32.const {})))",
+                factor, increment
+            )
+            .unwrap();
+        }
+        wast.push_str("br $exit\n");
+    }
+    wast.push_str("end\n(local.set $acc (i32.const -1))\nend\nlocal.get $acc))\n");
+
+    for x in [0, 1, 57, 119, 120, 1000, -1] {
+        let mut acc = x;
+        if (0..cases).contains(&x) {
+            for statement in 0..80 {
+                let (factor, increment) = step(x, statement);
+                acc = acc.wrapping_mul(factor).wrapping_add(increment);
+            }
+        } else {
+            acc = -1;
+        }
+        writeln!(
+            wast,
+            "(assert_return (invoke \"switch\" (i32.const {})) (i32.const {}))",
+            x, acc
+        )
+        .unwrap();
+    }
+
+    run_generated_wast("large_switch", &wast, &[]);
+    run_generated_wast("large_switch", &wast, &["-O", "0", "--no-peephole"]);
+}
+
+#[test]
+fn large_straight_line_function() {
+    if !java_available() {
+        eprintln!("Skipping since `java` or `javac` is missing");
+        return;
+    }
+
+    // Thousands of statements outside of any block, updating locals of every kind
+    let statements = 6000;
+    let mut wast = String::from(
+        "(module\n(func (export \"straight\") (param $p i32) (param $q i64) (result i64)\n\
+         (local $a i32) (local $b i64) (local $c f64) (local $v v128)\n",
+    );
+    for statement in 0..statements {
+        wast.push_str(match statement % 4 {
+            0 => "(local.set $a (i32.add (i32.mul (local.get $a) (i32.const 31)) (local.get $p)))\n",
+            1 => "(local.set $b (i64.xor (i64.mul (local.get $b) (i64.const 1000003)) (i64.extend_i32_s (local.get $a))))\n",
+            2 => "(local.set $c (f64.add (f64.mul (local.get $c) (f64.const 0.5)) (f64.convert_i32_s (local.get $a))))\n",
+            _ => "(local.set $v (i32x4.add (local.get $v) (i32x4.splat (local.get $a))))\n",
+        });
+    }
+    wast.push_str(
+        "(i64.add (i64.add (local.get $b) (local.get $q))\n\
+         (i64.add (i64.trunc_f64_s (local.get $c)) (i64.extend_i32_s (i32x4.extract_lane 3 (local.get $v))))))\n)\n",
+    );
+
+    for (p, q) in [(0, 0), (1, 5), (-7, 1 << 40), (123456, -1)] {
+        let (mut a, mut b, mut c, mut v) = (0i32, 0i64, 0f64, 0i32);
+        for statement in 0..statements {
+            match statement % 4 {
+                0 => a = a.wrapping_mul(31).wrapping_add(p),
+                1 => b = b.wrapping_mul(1000003) ^ a as i64,
+                2 => c = c * 0.5 + a as f64,
+                _ => v = v.wrapping_add(a),
+            }
+        }
+        let result = b
+            .wrapping_add(q)
+            .wrapping_add((c as i64).wrapping_add(v as i64));
+        writeln!(
+            wast,
+            "(assert_return (invoke \"straight\" (i32.const {}) (i64.const {})) (i64.const {}))",
+            p, q, result
+        )
+        .unwrap();
+    }
+
+    run_generated_wast("large_straight_line_function", &wast, &[]);
+    run_generated_wast("large_straight_line_function", &wast, &["--ssa"]);
+}
+
+#[test]
+fn many_large_blocks() {
+    if !java_available() {
+        eprintln!("Skipping since `java` or `javac` is missing");
+        return;
+    }
+
+    // Blocks which can each be left halfway through, depending on a bit of the parameter
+    let (blocks, statements) = (100, 200);
+    let mut wast = String::from(
+        "(module\n(func (export \"blocks\") (param $p i32) (result i32) (local $a i32)\n",
+    );
+    for block in 0..blocks {
+        wast.push_str("block\n");
+        for statement in 0..statements {
+            if statement == statements / 2 {
+                writeln!(
+                    wast,
+                    "(br_if 0 (i32.and (local.get $p) (i32.const {})))",
+                    1 << (block % 31)
+                )
+                .unwrap();
+            }
+            writeln!(
+                wast,
+                "(local.set $a (i32.add (i32.mul (local.get $a) (i32.const 7)) (i32.const {})))",
+                block * statements + statement
+            )
+            .unwrap();
+        }
+        wast.push_str("end\n");
+    }
+    wast.push_str("local.get $a))\n");
+
+    for p in [0, -1, 0x5555_5555, 1, 1 << 30] {
+        let mut a = 0i32;
+        for block in 0..blocks {
+            for statement in 0..statements {
+                if statement == statements / 2 && p & (1 << (block % 31)) != 0 {
+                    break;
+                }
+                a = a
+                    .wrapping_mul(7)
+                    .wrapping_add(block * statements + statement);
+            }
+        }
+        writeln!(
+            wast,
+            "(assert_return (invoke \"blocks\" (i32.const {})) (i32.const {}))",
+            p, a
+        )
+        .unwrap();
+    }
+
+    run_generated_wast("many_large_blocks", &wast, &[]);
+    run_generated_wast("many_large_blocks", &wast, &["-O", "0", "--no-peephole"]);
+}
+
+#[test]
+fn large_try_body() {
+    if !java_available() {
+        eprintln!("Skipping since `java` or `javac` is missing");
+        return;
+    }
+
+    // Exceptions thrown in split methods get caught by the `try` in the function
+    let statements = 12000;
+    let mut wast = String::from(
+        "(module\n(tag $e (param i32))\n\
+         (func $check (param i32) (result i32)\n\
+         (if (i32.eq (local.get 0) (i32.const 7777)) (then (throw $e (i32.const 5))))\n\
+         (local.get 0))\n\
+         (func (export \"try\") (param $p i32) (result i32) (local $a i32)\n\
+         (i32.const 1000)\ntry (result i32)\n",
+    );
+    for statement in 0..statements {
+        writeln!(
+            wast,
+            "(local.set $a (i32.add (i32.mul (local.get $a) (i32.const 3)) (call $check (i32.add (local.get $p) (i32.const {})))))",
+            statement % 5
+        )
+        .unwrap();
+    }
+    wast.push_str("(local.get $a)\ncatch $e\n(i32.add (local.get $a))\nend\ni32.add))\n");
+
+    for p in [0, 1, 7770, 7776] {
+        let mut a = 0i32;
+        let mut result = None;
+        for statement in 0..statements {
+            let value = p + statement % 5;
+            if value == 7777 {
+                result = Some(1005i32.wrapping_add(a));
+                break;
+            }
+            a = a.wrapping_mul(3).wrapping_add(value);
+        }
+        writeln!(
+            wast,
+            "(assert_return (invoke \"try\" (i32.const {})) (i32.const {}))",
+            p,
+            result.unwrap_or(1000i32.wrapping_add(a))
+        )
+        .unwrap();
+    }
+
+    run_generated_wast("large_try_body", &wast, &[]);
+}
+
+#[test]
+fn many_wide_locals() {
+    if !java_available() {
+        eprintln!("Skipping since `java` or `javac` is missing");
+        return;
+    }
+
+    // Each of the 40000 `i64` locals (so 80000 JVM slots) is derived from the previous one
+    let locals = 40000;
+    let mut wast =
+        String::from("(module\n(func (export \"locals\") (param $p i64) (result i64)\n(local");
+    for _ in 0..locals {
+        wast.push_str(" i64");
+    }
+    wast.push_str(")\n(local.set 1 (local.get 0))\n");
+    for local in 2..=locals {
+        writeln!(
+            wast,
+            "(local.set {} (i64.add (local.get {}) (i64.const {})))",
+            local,
+            local - 1,
+            local
+        )
+        .unwrap();
+    }
+    writeln!(
+        wast,
+        "(i64.add (local.get {}) (i64.add (local.get 1) (local.get {}))))\n)",
+        locals,
+        locals / 2
+    )
+    .unwrap();
+
+    for p in [0i64, -5, 1 << 50] {
+        let value = |local: i64| p + (2..=local).sum::<i64>();
+        writeln!(
+            wast,
+            "(assert_return (invoke \"locals\" (i64.const {})) (i64.const {}))",
+            p,
+            value(locals) + value(1) + value(locals / 2)
+        )
+        .unwrap();
+    }
+
+    run_generated_wast("many_wide_locals", &wast, &[]);
+}
+
+#[test]
+fn many_live_wide_locals() {
+    if !java_available() {
+        eprintln!("Skipping since `java` or `javac` is missing");
+        return;
+    }
+
+    // All of the 36000 `i64` and `f64` locals (so 72000 JVM slots) are live until the end, so
+    // some of them have to get spilled
+    let locals = 36000;
+    let mut wast =
+        String::from("(module\n(func (export \"locals\") (param $p i64) (result i64)\n(local");
+    for local in 1..=locals {
+        wast.push_str(if local % 2 == 0 { " i64" } else { " f64" });
+    }
+    wast.push_str(" i64 f64)\n");
+    for local in 1..=locals {
+        if local % 2 == 0 {
+            writeln!(
+                wast,
+                "(local.set {} (i64.add (local.get 0) (i64.const {})))",
+                local, local
+            )
+        } else {
+            writeln!(
+                wast,
+                "(local.set {} (f64.add (f64.convert_i64_s (local.get 0)) (f64.const {})))",
+                local, local
+            )
+        }
+        .unwrap();
+    }
+
+    // Sum all of the locals into two more locals
+    let (sum, float_sum) = (locals + 1, locals + 2);
+    for local in 1..=locals {
+        if local % 2 == 0 {
+            writeln!(
+                wast,
+                "(local.set {} (i64.add (local.get {}) (local.get {})))",
+                sum, sum, local
+            )
+        } else {
+            writeln!(
+                wast,
+                "(local.set {} (f64.add (local.get {}) (local.get {})))",
+                float_sum, float_sum, local
+            )
+        }
+        .unwrap();
+    }
+    writeln!(
+        wast,
+        "(i64.add (local.get {}) (i64.trunc_f64_s (local.get {}))))\n)",
+        sum, float_sum
+    )
+    .unwrap();
+
+    for p in [0i64, -5, 1 << 20] {
+        let expected: i64 = (1..=locals).map(|local| p + local).sum();
+        writeln!(
+            wast,
+            "(assert_return (invoke \"locals\" (i64.const {})) (i64.const {}))",
+            p, expected
+        )
+        .unwrap();
+    }
+
+    run_generated_wast("many_live_wide_locals", &wast, &[]);
+    run_generated_wast("many_live_wide_locals", &wast, &["--ssa"]);
+}
+
+#[test]
+fn large_function_table() {
+    if !java_available() {
+        eprintln!("Skipping since `java` or `javac` is missing");
+        return;
+    }
+
+    // Too many functions for one part, all in a table (some also calling others in other parts)
+    let functions = 24000;
+    let callee = |func_idx: usize| (func_idx * 7 + 13) % functions;
+    let mut wast = String::from("(module\n(type $t (func (param i32) (result i32)))\n");
+    for func_idx in 0..functions {
+        if func_idx % 1000 == 0 {
+            writeln!(
+                wast,
+                "(func (type $t) (i32.add (call {} (local.get 0)) (i32.const {})))",
+                callee(func_idx),
+                func_idx
+            )
+            .unwrap();
+        } else {
+            writeln!(
+                wast,
+                "(func (type $t) (i32.add (local.get 0) (i32.const {})))",
+                func_idx
+            )
+            .unwrap();
+        }
+    }
+    writeln!(
+        wast,
+        "(table {} funcref)\n(elem (i32.const 0) func",
+        functions
+    )
+    .unwrap();
+    for func_idx in 0..functions {
+        write!(wast, " {}", func_idx).unwrap();
+    }
+    wast.push_str(
+        ")\n(func (export \"call\") (param $idx i32) (param $x i32) (result i32)\n\
+         (call_indirect (type $t) (local.get $x) (local.get $idx)))\n)\n",
+    );
+
+    let result = |func_idx: usize, x: i32| {
+        let mut result = x.wrapping_add(func_idx as i32);
+        if func_idx % 1000 == 0 {
+            result = result.wrapping_add(callee(func_idx) as i32);
+        }
+        result
+    };
+    for func_idx in [0, 1, 999, 1000, 12345, 20000, 23000, functions - 1] {
+        writeln!(
+            wast,
+            "(assert_return (invoke \"call\" (i32.const {}) (i32.const 5)) (i32.const {}))",
+            func_idx,
+            result(func_idx, 5)
+        )
+        .unwrap();
+    }
+    writeln!(
+        wast,
+        "(assert_trap (invoke \"call\" (i32.const {}) (i32.const 5)) \"undefined element\")",
+        functions
+    )
+    .unwrap();
+
+    run_generated_wast("large_function_table", &wast, &[]);
+}
+
+#[test]
+fn parts_filling_up() {
+    if !java_available() {
+        eprintln!("Skipping since `java` or `javac` is missing");
+        return;
+    }
+
+    // Memory accesses, divisions, and tail calls use up constants which the estimates made when
+    // picking parts don't account for, so functions end up being moved to new parts
+    let functions = 300;
+    let mut wast = String::from("(module\n(type $t (func (param i32) (result i32)))\n(memory 1)\n");
+    for func_idx in 0..functions {
+        if func_idx == functions - 1 {
+            wast.push_str("(func (type $t) (local.get 0))\n");
+        } else if func_idx % 10 == 5 {
+            writeln!(
+                wast,
+                "(func (type $t) (return_call {} (i32.add (local.get 0) (i32.const 1))))",
+                func_idx + 1
+            )
+            .unwrap();
+        } else {
+            let address = func_idx * 4;
+            writeln!(
+                wast,
+                "(func (type $t)\n\
+                 (i32.store (i32.const {}) (local.get 0))\n\
+                 (i32.add (i32.div_s (i32.load (i32.const {})) (i32.const 3)) (call {} (local.get 0))))",
+                address,
+                address,
+                func_idx + 1
+            )
+            .unwrap();
+        }
+    }
+    writeln!(
+        wast,
+        "(table {} funcref)\n(elem (i32.const 0) func",
+        functions
+    )
+    .unwrap();
+    for func_idx in 0..functions {
+        write!(wast, " {}", func_idx).unwrap();
+    }
+    wast.push_str(
+        ")\n(func (export \"call\") (param $idx i32) (param $x i32) (result i32)\n\
+         (call_indirect (type $t) (local.get $x) (local.get $idx)))\n)\n",
+    );
+
+    let result = |func_idx: usize, x: i32| {
+        let mut x = x;
+        let mut result = 0i32;
+        for func_idx in func_idx..functions - 1 {
+            if func_idx % 10 == 5 {
+                x = x.wrapping_add(1);
+            } else {
+                result = result.wrapping_add(x / 3);
+            }
+        }
+        result.wrapping_add(x)
+    };
+    for func_idx in [0, 5, 6, 150, 151, functions - 1] {
+        for x in [7, -100] {
+            writeln!(
+                wast,
+                "(assert_return (invoke \"call\" (i32.const {}) (i32.const {})) (i32.const {}))",
+                func_idx,
+                x,
+                result(func_idx, x)
+            )
+            .unwrap();
+        }
+    }
+
+    for flags in [
+        &["--part-max-constants", "1200"][..],
+        &["--part-max-constants", "1200", "--tail-calls", "plain"],
+    ] {
+        run_generated_wast("parts_filling_up", &wast, flags);
+    }
+}
+
+#[test]
+fn data_resources() {
+    if !java_available() {
+        eprintln!("Skipping since `java` or `javac` is missing");
+        return;
+    }
+    for data_storage in ["strings", "resources", "compressed-resources"] {
+        run_wast("data_resources.wast", &["--data-storage", data_storage]);
+    }
+
+    // Without the resource files, the module can't be instantiated
+    let harness_directory = run_wast("data_resources.wast", &["--data-storage", "resources"]);
+    for entry in fs::read_dir(&harness_directory).unwrap() {
+        let path = entry.unwrap().path();
+        let is_data = path.extension().map_or(false, |extension| {
+            extension.to_string_lossy().starts_with("data")
+        });
+        if is_data {
+            fs::remove_file(path).unwrap();
+        }
+    }
+    let output = Command::new("java")
+        .current_dir(&harness_directory)
+        .arg("JavaHarness")
+        .output()
+        .expect("Failed to run java");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(
+        stderr.contains("IllegalArgumentException: Could not find resource"),
+        "{}",
+        stderr
+    );
+}
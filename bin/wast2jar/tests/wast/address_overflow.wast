@@ -0,0 +1,49 @@
+;; Address plus static offset overflowing the address type traps (instead of wrapping around)
+
+(module
+  (memory 1)
+  (data (i32.const 0) "\01\02\03\04\05\06\07\08")
+
+  (func (export "load") (param i32) (result i32) (i32.load offset=4 (local.get 0)))
+  (func (export "load8_u") (param i32) (result i32) (i32.load8_u offset=1 (local.get 0)))
+  (func (export "load_max") (param i32) (result i32)
+    (i32.load offset=0xffffffff (local.get 0)))
+  (func (export "store") (param i32) (i32.store offset=4 (local.get 0) (i32.const -1)))
+  (func (export "load64") (param i32) (result i64) (i64.load offset=8 (local.get 0)))
+  (func (export "load_v128") (param i32) (result i64)
+    (i64x2.extract_lane 0 (v128.load offset=4 (local.get 0))))
+  (func (export "load_atomic") (param i32) (result i32)
+    (i32.atomic.load offset=4 (local.get 0)))
+)
+
+;; In bounds accesses are unaffected
+(assert_return (invoke "load" (i32.const 0)) (i32.const 0x08070605))
+(assert_return (invoke "load8_u" (i32.const 0)) (i32.const 2))
+(assert_return (invoke "load64" (i32.const 0)) (i64.const 0))
+
+;; Sums which would wrap around to a valid address
+(assert_trap (invoke "load" (i32.const -4)) "out of bounds memory access")
+(assert_trap (invoke "load" (i32.const -3)) "out of bounds memory access")
+(assert_trap (invoke "load8_u" (i32.const -1)) "out of bounds memory access")
+(assert_trap (invoke "load_max" (i32.const 1)) "out of bounds memory access")
+(assert_trap (invoke "store" (i32.const -4)) "out of bounds memory access")
+(assert_trap (invoke "load64" (i32.const -8)) "out of bounds memory access")
+(assert_trap (invoke "load_v128" (i32.const -4)) "out of bounds memory access")
+(assert_trap (invoke "load_atomic" (i32.const -4)) "out of bounds memory access")
+(assert_return (invoke "load" (i32.const 0)) (i32.const 0x08070605))
+
+;; Sums which don't overflow, but are out of bounds anyway
+(assert_trap (invoke "load" (i32.const 0x7ffffffc)) "out of bounds memory access")
+(assert_trap (invoke "load_max" (i32.const 0)) "out of bounds memory access")
+
+(module
+  (memory i64 1)
+  (data (i64.const 0) "\01\02\03\04")
+  (func (export "load") (param i64) (result i32) (i32.load offset=4 (local.get 0)))
+  (func (export "load_max") (param i64) (result i32)
+    (i32.load offset=0xffffffff (local.get 0)))
+)
+
+(assert_return (invoke "load" (i64.const 0)) (i32.const 0))
+(assert_trap (invoke "load" (i64.const -4)) "out of bounds memory access")
+(assert_trap (invoke "load_max" (i64.const 0xffffffff00000001)) "out of bounds memory access")
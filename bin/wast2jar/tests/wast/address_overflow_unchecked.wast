@@ -0,0 +1,28 @@
+;; With `--unchecked-address-overflow`, address plus static offset wraps around like the JVM's
+;; integer addition (which is only safe for trusted modules)
+
+(module
+  (memory 1)
+  (data (i32.const 0) "\01\02\03\04\05\06\07\08")
+
+  (func (export "load") (param i32) (result i32) (i32.load offset=4 (local.get 0)))
+  (func (export "load8_u") (param i32) (result i32) (i32.load8_u offset=1 (local.get 0)))
+  (func (export "store") (param i32) (i32.store offset=4 (local.get 0) (i32.const -1)))
+)
+
+(assert_return (invoke "load" (i32.const 0)) (i32.const 0x08070605))
+(assert_return (invoke "load8_u" (i32.const 0)) (i32.const 2))
+(assert_return (invoke "load" (i32.const -4)) (i32.const 0x04030201))
+(assert_return (invoke "load8_u" (i32.const -1)) (i32.const 1))
+(invoke "store" (i32.const -4))
+(assert_return (invoke "load" (i32.const -4)) (i32.const -1))
+(assert_return (invoke "load" (i32.const 0)) (i32.const 0x08070605))
+(assert_trap (invoke "load" (i32.const 0x7ffffffc)) "out of bounds memory access")
+
+(module
+  (memory i64 1)
+  (data (i64.const 0) "\01\02\03\04")
+  (func (export "load") (param i64) (result i32) (i32.load offset=4 (local.get 0)))
+)
+
+(assert_return (invoke "load" (i64.const -4)) (i32.const 0x04030201))
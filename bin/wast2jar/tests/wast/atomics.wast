@@ -0,0 +1,140 @@
+;; Atomic operators on a shared memory, which is allocated up front and grows in place
+
+(module
+  (memory (export "memory") 1 3 shared)
+
+  (func (export "load") (param i32) (result i32) (i32.atomic.load (local.get 0)))
+  (func (export "load64") (param i32) (result i64) (i64.atomic.load (local.get 0)))
+  (func (export "load8_u") (param i32) (result i32) (i32.atomic.load8_u (local.get 0)))
+  (func (export "load16_u") (param i32) (result i32) (i32.atomic.load16_u (local.get 0)))
+  (func (export "store") (param i32 i32) (i32.atomic.store (local.get 0) (local.get 1)))
+  (func (export "store64") (param i32 i64) (i64.atomic.store (local.get 0) (local.get 1)))
+  (func (export "store8") (param i32 i32) (i32.atomic.store8 (local.get 0) (local.get 1)))
+  (func (export "store16") (param i32 i32) (i32.atomic.store16 (local.get 0) (local.get 1)))
+
+  (func (export "add") (param i32 i32) (result i32)
+    (i32.atomic.rmw.add (local.get 0) (local.get 1)))
+  (func (export "sub") (param i32 i32) (result i32)
+    (i32.atomic.rmw.sub (local.get 0) (local.get 1)))
+  (func (export "and") (param i32 i32) (result i32)
+    (i32.atomic.rmw.and (local.get 0) (local.get 1)))
+  (func (export "or") (param i32 i32) (result i32)
+    (i32.atomic.rmw.or (local.get 0) (local.get 1)))
+  (func (export "xor") (param i32 i32) (result i32)
+    (i32.atomic.rmw.xor (local.get 0) (local.get 1)))
+  (func (export "xchg") (param i32 i32) (result i32)
+    (i32.atomic.rmw.xchg (local.get 0) (local.get 1)))
+  (func (export "add64") (param i32 i64) (result i64)
+    (i64.atomic.rmw.add (local.get 0) (local.get 1)))
+  (func (export "cmpxchg") (param i32 i32 i32) (result i32)
+    (i32.atomic.rmw.cmpxchg (local.get 0) (local.get 1) (local.get 2)))
+  (func (export "cmpxchg64") (param i32 i64 i64) (result i64)
+    (i64.atomic.rmw.cmpxchg (local.get 0) (local.get 1) (local.get 2)))
+
+  (func (export "add8_u") (param i32 i32) (result i32)
+    (i32.atomic.rmw8.add_u (local.get 0) (local.get 1)))
+  (func (export "xchg16_u") (param i32 i32) (result i32)
+    (i32.atomic.rmw16.xchg_u (local.get 0) (local.get 1)))
+  (func (export "cmpxchg8_u") (param i32 i32 i32) (result i32)
+    (i32.atomic.rmw8.cmpxchg_u (local.get 0) (local.get 1) (local.get 2)))
+  (func (export "cmpxchg16_u") (param i32 i32 i32) (result i32)
+    (i32.atomic.rmw16.cmpxchg_u (local.get 0) (local.get 1) (local.get 2)))
+  (func (export "sub32_u") (param i32 i64) (result i64)
+    (i64.atomic.rmw32.sub_u (local.get 0) (local.get 1)))
+
+  (func (export "wait32") (param i32 i32 i64) (result i32)
+    (memory.atomic.wait32 (local.get 0) (local.get 1) (local.get 2)))
+  (func (export "wait64") (param i32 i64 i64) (result i32)
+    (memory.atomic.wait64 (local.get 0) (local.get 1) (local.get 2)))
+  (func (export "notify") (param i32 i32) (result i32)
+    (memory.atomic.notify (local.get 0) (local.get 1)))
+  (func (export "fence") (atomic.fence))
+
+  (func (export "size") (result i32) (memory.size))
+  (func (export "grow") (param i32) (result i32) (memory.grow (local.get 0)))
+  (func (export "fill") (param i32 i32 i32)
+    (memory.fill (local.get 0) (local.get 1) (local.get 2)))
+)
+
+;; Read-modify-write operators return the old value
+(invoke "store" (i32.const 0) (i32.const 10))
+(assert_return (invoke "add" (i32.const 0) (i32.const 5)) (i32.const 10))
+(assert_return (invoke "sub" (i32.const 0) (i32.const 3)) (i32.const 15))
+(assert_return (invoke "and" (i32.const 0) (i32.const 6)) (i32.const 12))
+(assert_return (invoke "or" (i32.const 0) (i32.const 9)) (i32.const 4))
+(assert_return (invoke "xor" (i32.const 0) (i32.const 1)) (i32.const 13))
+(assert_return (invoke "xchg" (i32.const 0) (i32.const -1)) (i32.const 12))
+(assert_return (invoke "load" (i32.const 0)) (i32.const -1))
+(invoke "store64" (i32.const 8) (i64.const 0xffffffff))
+(assert_return (invoke "add64" (i32.const 8) (i64.const 1)) (i64.const 0xffffffff))
+(assert_return (invoke "load64" (i32.const 8)) (i64.const 0x100000000))
+
+;; Compare-exchange only writes when the expected value matches
+(assert_return (invoke "cmpxchg" (i32.const 0) (i32.const 0) (i32.const 7)) (i32.const -1))
+(assert_return (invoke "load" (i32.const 0)) (i32.const -1))
+(assert_return (invoke "cmpxchg" (i32.const 0) (i32.const -1) (i32.const 7)) (i32.const -1))
+(assert_return (invoke "load" (i32.const 0)) (i32.const 7))
+(assert_return (invoke "cmpxchg64" (i32.const 8) (i64.const 0x100000000) (i64.const -2))
+  (i64.const 0x100000000))
+(assert_return (invoke "load64" (i32.const 8)) (i64.const -2))
+
+;; Narrow accesses only touch their own bytes, and zero-extend what they return
+(invoke "store" (i32.const 16) (i32.const 0x11223344))
+(assert_return (invoke "add8_u" (i32.const 17) (i32.const 0xff)) (i32.const 0x33))
+(assert_return (invoke "load" (i32.const 16)) (i32.const 0x11223244))
+(assert_return (invoke "xchg16_u" (i32.const 18) (i32.const 0xabcd)) (i32.const 0x1122))
+(assert_return (invoke "load" (i32.const 16)) (i32.const 0xabcd3244))
+(assert_return (invoke "cmpxchg8_u" (i32.const 19) (i32.const 0x12) (i32.const 0)) (i32.const 0xab))
+(assert_return (invoke "cmpxchg8_u" (i32.const 19) (i32.const 0xab) (i32.const 0x12))
+  (i32.const 0xab))
+(assert_return (invoke "cmpxchg16_u" (i32.const 16) (i32.const 0x3244) (i32.const 0xffff))
+  (i32.const 0x3244))
+(assert_return (invoke "load" (i32.const 16)) (i32.const 0x12cdffff))
+(invoke "store8" (i32.const 20) (i32.const 0x1ff))
+(invoke "store16" (i32.const 22) (i32.const 0x18001))
+(assert_return (invoke "load8_u" (i32.const 20)) (i32.const 0xff))
+(assert_return (invoke "load16_u" (i32.const 22)) (i32.const 0x8001))
+(assert_return (invoke "load" (i32.const 20)) (i32.const 0x800100ff))
+(assert_return (invoke "sub32_u" (i32.const 20) (i64.const 0x100000100)) (i64.const 0x800100ff))
+(assert_return (invoke "load" (i32.const 20)) (i32.const 0x8000ffff))
+(assert_trap (invoke "load" (i32.const 1)) "unaligned atomic")
+(assert_trap (invoke "load" (i32.const 65536)) "out of bounds memory access")
+
+;; Waiting without a matching value returns immediately, as does running out of time
+(assert_return (invoke "wait32" (i32.const 0) (i32.const 0) (i64.const -1)) (i32.const 1))
+(assert_return (invoke "wait32" (i32.const 0) (i32.const 7) (i64.const 1000)) (i32.const 2))
+(assert_return (invoke "wait64" (i32.const 8) (i64.const 0) (i64.const -1)) (i32.const 1))
+(assert_return (invoke "wait64" (i32.const 8) (i64.const -2) (i64.const 0)) (i32.const 2))
+(assert_return (invoke "notify" (i32.const 0) (i32.const 1)) (i32.const 0))
+(invoke "fence")
+
+;; Growing keeps the contents, and never goes past the maximum
+(assert_return (invoke "size") (i32.const 1))
+(assert_trap (invoke "fill" (i32.const 65530) (i32.const 1) (i32.const 7)) "out of bounds memory access")
+(assert_return (invoke "grow" (i32.const 1)) (i32.const 1))
+(assert_return (invoke "size") (i32.const 2))
+(assert_return (invoke "load" (i32.const 0)) (i32.const 7))
+(invoke "store" (i32.const 65536) (i32.const 42))
+(assert_return (invoke "add" (i32.const 65536) (i32.const 1)) (i32.const 42))
+(invoke "fill" (i32.const 65530) (i32.const 1) (i32.const 7))
+(assert_return (invoke "load8_u" (i32.const 65536)) (i32.const 1))
+(assert_trap (invoke "load" (i32.const 131072)) "out of bounds memory access")
+(assert_return (invoke "grow" (i32.const 2)) (i32.const -1))
+(assert_return (invoke "grow" (i32.const -1)) (i32.const -1))
+(assert_return (invoke "grow" (i32.const 1)) (i32.const 2))
+(assert_return (invoke "size") (i32.const 3))
+(assert_return (invoke "grow" (i32.const 0)) (i32.const 3))
+(assert_return (invoke "grow" (i32.const 1)) (i32.const -1))
+(assert_return (invoke "load" (i32.const 131072)) (i32.const 0))
+
+;; Unshared memories can be waited on, but trap instead of blocking
+(module
+  (memory 1)
+  (func (export "wait32") (param i32 i32 i64) (result i32)
+    (memory.atomic.wait32 (local.get 0) (local.get 1) (local.get 2)))
+  (func (export "notify") (param i32 i32) (result i32)
+    (memory.atomic.notify (local.get 0) (local.get 1)))
+)
+
+(assert_trap (invoke "wait32" (i32.const 0) (i32.const 0) (i64.const 0)) "expected shared memory")
+(assert_return (invoke "notify" (i32.const 0) (i32.const 1)) (i32.const 0))
@@ -0,0 +1,12 @@
+(module
+  (memory 1)
+  (data (i32.const 0) "hello")
+  (data $passive "\00\01\02\ff")
+  (func (export "load") (param i32) (result i32) local.get 0 i32.load8_u)
+  (func (export "init") (param i32 i32 i32) local.get 0 local.get 1 local.get 2 memory.init $passive))
+
+(assert_return (invoke "load" (i32.const 1)) (i32.const 101))
+(invoke "init" (i32.const 16) (i32.const 1) (i32.const 3))
+(assert_return (invoke "load" (i32.const 16)) (i32.const 1))
+(assert_return (invoke "load" (i32.const 18)) (i32.const 255))
+(assert_trap (invoke "init" (i32.const 16) (i32.const 2) (i32.const 3)) "out of bounds memory access")
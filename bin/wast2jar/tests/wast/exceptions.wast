@@ -0,0 +1,127 @@
+;; Exception handling with `try`, `catch`, `catch_all`, `rethrow` and `delegate`
+
+(module
+  (tag $e0)
+  (tag $e1 (param i32))
+  (tag $e2 (param i64 f64))
+
+  (func $throw_if (param i32)
+    (if (i32.eqz (local.get 0)) (then (throw $e0)))
+    (if (i32.eq (local.get 0) (i32.const 1)) (then (throw $e1 (i32.const 42))))
+    (if (i32.eq (local.get 0) (i32.const 2)) (then (throw $e2 (i64.const -1) (f64.const 0.5)))))
+
+  ;; Catches are matched by tag, with `catch_all` as a fallback
+  (func (export "catch") (param i32) (result i32)
+    (try (result i32)
+      (do (call $throw_if (local.get 0)) (i32.const -1))
+      (catch $e0 (i32.const 0))
+      (catch $e1)
+      (catch_all (i32.const 100))))
+
+  ;; Multiple payload values
+  (func (export "payload") (result f64)
+    (local f64)
+    (try (result f64)
+      (do (call $throw_if (i32.const 2)) (f64.const 0))
+      (catch $e2 (local.set 0) (drop) (local.get 0))))
+  (func (export "payload2") (result i64)
+    (try (result i64)
+      (do (call $throw_if (i32.const 2)) (i64.const 0))
+      (catch $e2 (drop))))
+
+  ;; Uncaught tags escape
+  (func (export "uncaught") (param i32) (result i32)
+    (try (result i32)
+      (do (call $throw_if (local.get 0)) (i32.const -1))
+      (catch $e1)))
+
+  ;; `rethrow` from nested catches, picking which exception to rethrow by depth
+  (func (export "rethrow") (param i32) (result i32)
+    (try (result i32)
+      (do
+        (try
+          (do (throw $e1 (i32.const 7)))
+          (catch $e1
+            (drop)
+            (try
+              (do (throw $e0))
+              (catch $e0
+                (if (local.get 0) (then (rethrow 2)) (else (rethrow 1)))))))
+        (i32.const -1))
+      (catch $e0 (i32.const 0))
+      (catch $e1)))
+
+  ;; `delegate` passes exceptions on to an outer handler, skipping the ones in between
+  (func (export "delegate") (param i32) (result i32)
+    (try (result i32)
+      (do
+        (try (result i32)
+          (do
+            (try (result i32)
+              (do (call $throw_if (local.get 0)) (i32.const -1))
+              (delegate 1)))
+          (catch_all (i32.const 2))))
+      (catch $e1)
+      (catch_all (i32.const 1))))
+  (func (export "delegate_to_caller") (result i32)
+    (try (result i32)
+      (do
+        (try (result i32)
+          (do (throw $e1 (i32.const 5)))
+          (delegate 1)))
+      (catch_all (i32.const 0))))
+
+  ;; Locals and the operand stack survive a catch
+  (func (export "state") (result i32)
+    (local i32)
+    (local.set 0 (i32.const 10))
+    (i32.add
+      (i32.const 1000)
+      (try (result i32)
+        (do (local.set 0 (i32.const 20)) (call $throw_if (i32.const 0)) (i32.const 0))
+        (catch_all (local.get 0)))))
+
+  ;; Traps are not caught
+  (func (export "trap") (result i32)
+    (try (result i32)
+      (do (unreachable))
+      (catch_all (i32.const 0))))
+)
+
+(assert_return (invoke "catch" (i32.const 0)) (i32.const 0))
+(assert_return (invoke "catch" (i32.const 1)) (i32.const 42))
+(assert_return (invoke "catch" (i32.const 2)) (i32.const 100))
+(assert_return (invoke "catch" (i32.const 3)) (i32.const -1))
+(assert_return (invoke "payload") (f64.const 0.5))
+(assert_return (invoke "payload2") (i64.const -1))
+(assert_return (invoke "uncaught" (i32.const 1)) (i32.const 42))
+(assert_exception (invoke "uncaught" (i32.const 0)))
+(assert_exception (invoke "uncaught" (i32.const 2)))
+(assert_return (invoke "rethrow" (i32.const 0)) (i32.const 0))
+(assert_return (invoke "rethrow" (i32.const 1)) (i32.const 7))
+(assert_return (invoke "delegate" (i32.const 1)) (i32.const 42))
+(assert_return (invoke "delegate" (i32.const 0)) (i32.const 1))
+(assert_return (invoke "delegate" (i32.const 3)) (i32.const -1))
+(assert_exception (invoke "delegate_to_caller"))
+(assert_return (invoke "state") (i32.const 1020))
+(assert_trap (invoke "trap") "unreachable")
+
+;; Tags can be exported and imported, and exceptions keep their identity across modules
+(module $thrower
+  (tag $e (export "e") (param i32))
+  (func (export "throw") (param i32) (throw $e (local.get 0)))
+)
+(register "thrower" $thrower)
+
+(module
+  (tag $e (import "thrower" "e") (param i32))
+  (tag $other (param i32))
+  (func $throw (import "thrower" "throw") (param i32))
+  (func (export "catch_imported") (param i32) (result i32)
+    (try (result i32)
+      (do (call $throw (local.get 0)) (i32.const -1))
+      (catch $other (drop) (i32.const -2))
+      (catch $e)))
+)
+
+(assert_return (invoke "catch_imported" (i32.const 9)) (i32.const 9))
@@ -0,0 +1,56 @@
+;; Extended constant expressions in globals, data and element segment offsets
+
+(module $base
+  (global (export "base") i32 (i32.const 100))
+  (global (export "base64") i64 (i64.const 0x100000000))
+)
+(register "base" $base)
+
+(module
+  (global $base (import "base" "base") i32)
+  (global $base64 (import "base" "base64") i64)
+
+  (global $sum i32 (i32.add (global.get $base) (i32.const 5)))
+  (global $difference i32 (i32.sub (global.get $base) (i32.const 150)))
+  (global $product i32 (i32.mul (global.get $base) (global.get $base)))
+  (global $wrapping i32 (i32.mul (i32.const 0x10000) (i32.const 0x10000)))
+  (global $nested i32
+    (i32.add (i32.mul (global.get $base) (i32.const 3)) (i32.sub (i32.const 1) (i32.const 2))))
+  (global $wide i64 (i64.add (i64.mul (global.get $base64) (i64.const 3)) (i64.const -1)))
+  (global $mutable (mut i64) (i64.sub (i64.const 0) (global.get $base64)))
+
+  (memory 1)
+  (data (offset (i32.add (global.get $base) (i32.const 28))) "\2a")
+
+  (table 10 funcref)
+  (elem (offset (i32.sub (global.get $base) (i32.const 97))) func $seven)
+  (func $seven (result i32) (i32.const 7))
+
+  (func (export "sum") (result i32) (global.get $sum))
+  (func (export "difference") (result i32) (global.get $difference))
+  (func (export "product") (result i32) (global.get $product))
+  (func (export "wrapping") (result i32) (global.get $wrapping))
+  (func (export "nested") (result i32) (global.get $nested))
+  (func (export "wide") (result i64) (global.get $wide))
+  (func (export "mutable") (result i64) (global.get $mutable))
+  (func (export "load") (param i32) (result i32) (i32.load8_u (local.get 0)))
+  (func (export "call") (param i32) (result i32) (call_indirect (result i32) (local.get 0)))
+)
+
+(assert_return (invoke "sum") (i32.const 105))
+(assert_return (invoke "difference") (i32.const -50))
+(assert_return (invoke "product") (i32.const 10000))
+(assert_return (invoke "wrapping") (i32.const 0))
+(assert_return (invoke "nested") (i32.const 299))
+(assert_return (invoke "wide") (i64.const 0x2ffffffff))
+(assert_return (invoke "mutable") (i64.const -0x100000000))
+(assert_return (invoke "load" (i32.const 128)) (i32.const 42))
+(assert_return (invoke "call" (i32.const 3)) (i32.const 7))
+(assert_trap (invoke "call" (i32.const 2)) "uninitialized element")
+
+;; Offsets computed from extended constants are still bounds checked
+(assert_trap
+  (module
+    (memory 1)
+    (data (offset (i32.add (i32.const 65535) (i32.const 1))) "\00"))
+  "out of bounds memory access")
@@ -0,0 +1,101 @@
+;; Functions with identical types and bodies, which get translated only once but must stay
+;; reachable through every export, table slot, call and tail call naming them
+
+(module
+  (type $ii (func (param i32) (result i32)))
+  (type $ii_again (func (param i32) (result i32)))
+  (table 6 funcref)
+  (elem (i32.const 0) $a $b $c $d $t1 $t2)
+
+  (func $a (type $ii) (i32.add (local.get 0) (i32.const 1)))
+  (func $b (type $ii_again) (i32.add (local.get 0) (i32.const 1)))
+  (func $c (param i64) (result i64) (i64.const 7))
+  (func $d (param i32) (result i32) (i32.add (local.get 0) (i32.const 1)))
+
+  ;; Same body, different types
+  (func $f (param f32) (result f32) (local.get 0))
+  (func $g (param f64) (result f64) (local.get 0))
+
+  ;; Same type, same instructions, different declared locals
+  (func $l1 (param i32) (result i32) (local i32) (local.get 0))
+  (func $l2 (param i32) (result i32) (local i64) (local.get 0))
+
+  ;; Identical tail calls into a duplicate
+  (func $t1 (param i32) (result i32) (return_call $d (local.get 0)))
+  (func $t2 (param i32) (result i32) (return_call $d (local.get 0)))
+  (func $t3 (param i32) (result i32) (return_call $a (local.get 0)))
+
+  ;; Mutual tail recursion going through two identical functions
+  (func $even (param i32) (result i32)
+    (if (result i32) (i32.eqz (local.get 0))
+      (then (i32.const 1))
+      (else (return_call $odd (i32.sub (local.get 0) (i32.const 1))))))
+  (func $even_again (param i32) (result i32)
+    (if (result i32) (i32.eqz (local.get 0))
+      (then (i32.const 1))
+      (else (return_call $odd (i32.sub (local.get 0) (i32.const 1))))))
+  (func $odd (param i32) (result i32)
+    (if (result i32) (i32.eqz (local.get 0))
+      (then (i32.const 0))
+      (else
+        (if (result i32) (i32.and (local.get 0) (i32.const 2))
+          (then (return_call $even (i32.sub (local.get 0) (i32.const 1))))
+          (else (return_call $even_again (i32.sub (local.get 0) (i32.const 1))))))))
+
+  (func (export "a") (param i32) (result i32) (call $a (local.get 0)))
+  (func (export "b") (param i32) (result i32) (call $b (local.get 0)))
+  (func (export "d") (param i32) (result i32) (call $d (local.get 0)))
+  (func (export "indirect") (param i32 i32) (result i32)
+    (call_indirect (type $ii) (local.get 0) (local.get 1)))
+  (func (export "tail_indirect") (param i32 i32) (result i32)
+    (return_call_indirect (type $ii_again) (local.get 0) (local.get 1)))
+  (func (export "tails") (param i32) (result i32)
+    (i32.add
+      (call $t1 (local.get 0))
+      (i32.add (call $t2 (local.get 0)) (call $t3 (local.get 0)))))
+  (func (export "f") (param f32) (result f32) (call $f (local.get 0)))
+  (func (export "g") (param f64) (result f64) (call $g (local.get 0)))
+  (func (export "locals") (param i32) (result i32)
+    (i32.add (call $l1 (local.get 0)) (call $l2 (local.get 0))))
+  (func (export "even") (param i32) (result i32) (call $even_again (local.get 0)))
+  (export "a_directly" (func $a))
+  (export "b_directly" (func $b))
+  (export "d_directly" (func $d))
+  (export "t1_directly" (func $t1))
+  (export "t2_directly" (func $t2))
+  (export "even_directly" (func $even))
+  (export "even_again_directly" (func $even_again))
+)
+
+(assert_return (invoke "a" (i32.const 1)) (i32.const 2))
+(assert_return (invoke "b" (i32.const 2)) (i32.const 3))
+(assert_return (invoke "d" (i32.const 3)) (i32.const 4))
+(assert_return (invoke "a_directly" (i32.const 4)) (i32.const 5))
+(assert_return (invoke "b_directly" (i32.const 5)) (i32.const 6))
+(assert_return (invoke "d_directly" (i32.const 6)) (i32.const 7))
+(assert_return (invoke "indirect" (i32.const 5) (i32.const 0)) (i32.const 6))
+(assert_return (invoke "indirect" (i32.const 5) (i32.const 1)) (i32.const 6))
+(assert_return (invoke "indirect" (i32.const 5) (i32.const 3)) (i32.const 6))
+(assert_return (invoke "indirect" (i32.const 5) (i32.const 4)) (i32.const 6))
+(assert_return (invoke "indirect" (i32.const 5) (i32.const 5)) (i32.const 6))
+(assert_trap (invoke "indirect" (i32.const 5) (i32.const 2)) "indirect call type mismatch")
+(assert_return (invoke "tail_indirect" (i32.const 7) (i32.const 1)) (i32.const 8))
+(assert_return (invoke "tail_indirect" (i32.const 7) (i32.const 5)) (i32.const 8))
+(assert_trap (invoke "tail_indirect" (i32.const 7) (i32.const 2)) "indirect call type mismatch")
+(assert_return (invoke "tails" (i32.const 1)) (i32.const 6))
+(assert_return (invoke "t1_directly" (i32.const 1)) (i32.const 2))
+(assert_return (invoke "t2_directly" (i32.const 2)) (i32.const 3))
+(assert_return (invoke "f" (f32.const 1.5)) (f32.const 1.5))
+(assert_return (invoke "g" (f64.const 2.5)) (f64.const 2.5))
+(assert_return (invoke "locals" (i32.const 4)) (i32.const 8))
+(assert_return (invoke "even" (i32.const 100000)) (i32.const 1))
+(assert_return (invoke "even" (i32.const 100001)) (i32.const 0))
+(assert_return (invoke "even_directly" (i32.const 99999)) (i32.const 0))
+(assert_return (invoke "even_again_directly" (i32.const 99998)) (i32.const 1))
+
+(assert_invalid
+  (module
+    (func (result i32) (i32.const 1))
+    (func (result i32) (i32.const 1))
+    (func (export "f") (result i64) (i32.const 1)))
+  "type mismatch")
@@ -0,0 +1,104 @@
+;; 64-bit memories, which are split into one `ByteBuffer` chunk per page
+
+(module
+  (memory i64 2 4)
+  (data (i64.const 65532) "\01\02\03\04\05\06\07\08")
+
+  (func (export "load8_u") (param i64) (result i32) (i32.load8_u (local.get 0)))
+  (func (export "load16_s") (param i64) (result i32) (i32.load16_s (local.get 0)))
+  (func (export "load32") (param i64) (result i32) (i32.load (local.get 0)))
+  (func (export "load64") (param i64) (result i64) (i64.load (local.get 0)))
+  (func (export "loadf64") (param i64) (result f64) (f64.load offset=1 (local.get 0)))
+  (func (export "store16") (param i64 i32) (i32.store16 (local.get 0) (local.get 1)))
+  (func (export "store32") (param i64 i32) (i32.store (local.get 0) (local.get 1)))
+  (func (export "store64") (param i64 i64) (i64.store (local.get 0) (local.get 1)))
+  (func (export "storef64") (param i64 f64) (f64.store offset=1 (local.get 0) (local.get 1)))
+
+  (func (export "size") (result i64) (memory.size))
+  (func (export "grow") (param i64) (result i64) (memory.grow (local.get 0)))
+  (func (export "fill") (param i64 i32 i64)
+    (memory.fill (local.get 0) (local.get 1) (local.get 2)))
+  (func (export "copy") (param i64 i64 i64)
+    (memory.copy (local.get 0) (local.get 1) (local.get 2)))
+  (func (export "init") (param i64 i32 i32)
+    (memory.init $d (local.get 0) (local.get 1) (local.get 2)))
+  (data $d "abcdef")
+)
+
+;; Data segments and loads which straddle a chunk boundary
+(assert_return (invoke "load8_u" (i64.const 65535)) (i32.const 4))
+(assert_return (invoke "load8_u" (i64.const 65536)) (i32.const 5))
+(assert_return (invoke "load16_s" (i64.const 65535)) (i32.const 0x0504))
+(assert_return (invoke "load32" (i64.const 65534)) (i32.const 0x06050403))
+(assert_return (invoke "load64" (i64.const 65532)) (i64.const 0x0807060504030201))
+(assert_return (invoke "load64" (i64.const 65535)) (i64.const 0x0000000807060504))
+
+;; Stores which straddle a chunk boundary
+(invoke "store16" (i64.const 65535) (i32.const 0xbeef))
+(assert_return (invoke "load8_u" (i64.const 65535)) (i32.const 0xef))
+(assert_return (invoke "load8_u" (i64.const 65536)) (i32.const 0xbe))
+(invoke "store64" (i64.const 65533) (i64.const 0x1122334455667788))
+(assert_return (invoke "load32" (i64.const 65533)) (i32.const 0x55667788))
+(assert_return (invoke "load32" (i64.const 65537)) (i32.const 0x11223344))
+(assert_return (invoke "load64" (i64.const 65532)) (i64.const 0x2233445566778801))
+(invoke "storef64" (i64.const 65530) (f64.const -1.5))
+(assert_return (invoke "loadf64" (i64.const 65530)) (f64.const -1.5))
+(invoke "store64" (i64.const 131064) (i64.const -1))
+(assert_return (invoke "load8_u" (i64.const 131071)) (i32.const 0xff))
+
+;; Accesses past the end trap, without writing anything
+(assert_trap (invoke "load64" (i64.const 131065)) "out of bounds memory access")
+(assert_trap (invoke "store32" (i64.const 131070) (i32.const 0)) "out of bounds memory access")
+(assert_return (invoke "load16_s" (i64.const 131070)) (i32.const -1))
+(assert_trap (invoke "load8_u" (i64.const 131072)) "out of bounds memory access")
+(assert_trap (invoke "load8_u" (i64.const -1)) "out of bounds memory access")
+(assert_trap (invoke "load8_u" (i64.const 0x100000000)) "out of bounds memory access")
+
+;; Bulk operators spanning chunks
+(invoke "fill" (i64.const 60000) (i32.const 0x5a) (i64.const 10000))
+(assert_return (invoke "load8_u" (i64.const 59999)) (i32.const 0))
+(assert_return (invoke "load8_u" (i64.const 60000)) (i32.const 0x5a))
+(assert_return (invoke "load32" (i64.const 65534)) (i32.const 0x5a5a5a5a))
+(assert_return (invoke "load8_u" (i64.const 69999)) (i32.const 0x5a))
+(assert_return (invoke "load8_u" (i64.const 70000)) (i32.const 0))
+(invoke "copy" (i64.const 131000) (i64.const 65530) (i64.const 12))
+(assert_return (invoke "load32" (i64.const 131004)) (i32.const 0x5a5a5a5a))
+(invoke "init" (i64.const 65534) (i32.const 1) (i32.const 4))
+(assert_return (invoke "load32" (i64.const 65534)) (i32.const 0x65646362))
+(invoke "copy" (i64.const 65535) (i64.const 65534) (i64.const 4))
+(assert_return (invoke "load64" (i64.const 65534)) (i64.const 0x5a5a5a6564636262))
+(assert_trap (invoke "fill" (i64.const 131000) (i32.const 0) (i64.const 100))
+  "out of bounds memory access")
+(assert_trap (invoke "copy" (i64.const 0) (i64.const 131070) (i64.const 4))
+  "out of bounds memory access")
+(assert_trap (invoke "init" (i64.const 131070) (i32.const 0) (i32.const 4))
+  "out of bounds memory access")
+
+;; Growing adds zeroed chunks, up to the maximum
+(assert_return (invoke "size") (i64.const 2))
+(assert_return (invoke "grow" (i64.const 1)) (i64.const 2))
+(assert_return (invoke "size") (i64.const 3))
+(assert_return (invoke "load64" (i64.const 131068)) (i64.const 0xffffffff))
+(invoke "store64" (i64.const 131068) (i64.const 0x0102030405060708))
+(assert_return (invoke "load32" (i64.const 131072)) (i32.const 0x01020304))
+(assert_return (invoke "load8_u" (i64.const 196607)) (i32.const 0))
+(assert_trap (invoke "load8_u" (i64.const 196608)) "out of bounds memory access")
+(assert_return (invoke "grow" (i64.const 2)) (i64.const -1))
+(assert_return (invoke "grow" (i64.const 0x100000000)) (i64.const -1))
+(assert_return (invoke "grow" (i64.const 1)) (i64.const 3))
+(assert_return (invoke "grow" (i64.const 0)) (i64.const 4))
+(assert_return (invoke "size") (i64.const 4))
+
+;; Unbounded 64-bit memories grow as well
+(module
+  (memory i64 0)
+  (func (export "grow") (param i64) (result i64) (memory.grow (local.get 0)))
+  (func (export "store") (param i64 i64) (i64.store (local.get 0) (local.get 1)))
+  (func (export "load") (param i64) (result i64) (i64.load (local.get 0)))
+)
+
+(assert_trap (invoke "load" (i64.const 0)) "out of bounds memory access")
+(assert_return (invoke "grow" (i64.const 3)) (i64.const 0))
+(invoke "store" (i64.const 131070) (i64.const 0x7766554433221100))
+(assert_return (invoke "load" (i64.const 131070)) (i64.const 0x7766554433221100))
+(assert_return (invoke "grow" (i64.const -1)) (i64.const -1))
@@ -0,0 +1,82 @@
+;; Memory operators, which get translated differently for each memory backend
+
+(module
+  (memory (export "memory") 1 4)
+  (data (i32.const 16) "\01\02\03\04\05\06\07\08")
+
+  (func (export "load8_u") (param i32) (result i32) (i32.load8_u (local.get 0)))
+  (func (export "load8_s") (param i32) (result i32) (i32.load8_s (local.get 0)))
+  (func (export "load16_s") (param i32) (result i32) (i32.load16_s offset=1 (local.get 0)))
+  (func (export "load32") (param i32) (result i32) (i32.load (local.get 0)))
+  (func (export "load64") (param i32) (result i64) (i64.load (local.get 0)))
+  (func (export "load32_u") (param i32) (result i64) (i64.load32_u offset=4 (local.get 0)))
+  (func (export "loadf32") (param i32) (result f32) (f32.load (local.get 0)))
+  (func (export "loadf64") (param i32) (result f64) (f64.load (local.get 0)))
+
+  (func (export "store8") (param i32 i32) (i32.store8 (local.get 0) (local.get 1)))
+  (func (export "store16") (param i32 i32) (i32.store16 offset=2 (local.get 0) (local.get 1)))
+  (func (export "store32") (param i32 i32) (i32.store (local.get 0) (local.get 1)))
+  (func (export "store64") (param i32 i64) (i64.store (local.get 0) (local.get 1)))
+  (func (export "storef32") (param i32 f32) (f32.store (local.get 0) (local.get 1)))
+  (func (export "storef64") (param i32 f64) (f64.store (local.get 0) (local.get 1)))
+
+  (func (export "size") (result i32) (memory.size))
+  (func (export "grow") (param i32) (result i32) (memory.grow (local.get 0)))
+  (func (export "fill") (param i32 i32 i32)
+    (memory.fill (local.get 0) (local.get 1) (local.get 2)))
+  (func (export "copy") (param i32 i32 i32)
+    (memory.copy (local.get 0) (local.get 1) (local.get 2)))
+)
+
+(assert_return (invoke "load8_u" (i32.const 16)) (i32.const 1))
+(assert_return (invoke "load32" (i32.const 16)) (i32.const 0x04030201))
+(assert_return (invoke "load64" (i32.const 16)) (i64.const 0x0807060504030201))
+(assert_return (invoke "load32_u" (i32.const 16)) (i64.const 0x08070605))
+(assert_return (invoke "load16_s" (i32.const 16)) (i32.const 0x0302))
+
+(invoke "store8" (i32.const 0) (i32.const 0xff))
+(assert_return (invoke "load8_u" (i32.const 0)) (i32.const 255))
+(assert_return (invoke "load8_s" (i32.const 0)) (i32.const -1))
+(invoke "store16" (i32.const 0) (i32.const 0x8001))
+(assert_return (invoke "load16_s" (i32.const 1)) (i32.const -32767))
+(invoke "store32" (i32.const 32) (i32.const -2))
+(assert_return (invoke "load32" (i32.const 32)) (i32.const -2))
+(invoke "store64" (i32.const 40) (i64.const -3))
+(assert_return (invoke "load64" (i32.const 40)) (i64.const -3))
+(invoke "storef32" (i32.const 48) (f32.const 1.5))
+(assert_return (invoke "loadf32" (i32.const 48)) (f32.const 1.5))
+(invoke "storef64" (i32.const 56) (f64.const -0.25))
+(assert_return (invoke "loadf64" (i32.const 56)) (f64.const -0.25))
+
+;; The last bytes of memory, and just past them
+(invoke "store32" (i32.const 65532) (i32.const 0x11223344))
+(assert_return (invoke "load32" (i32.const 65532)) (i32.const 0x11223344))
+(assert_trap (invoke "load32" (i32.const 65533)) "out of bounds memory access")
+(assert_trap (invoke "store8" (i32.const 65536) (i32.const 0)) "out of bounds memory access")
+(assert_trap (invoke "load64" (i32.const -1)) "out of bounds memory access")
+
+;; Growing keeps the contents and zeroes the new pages
+(assert_return (invoke "size") (i32.const 1))
+(assert_return (invoke "grow" (i32.const 2)) (i32.const 1))
+(assert_return (invoke "size") (i32.const 3))
+(assert_return (invoke "load32" (i32.const 65532)) (i32.const 0x11223344))
+(assert_return (invoke "load32" (i32.const 65536)) (i32.const 0))
+(invoke "store32" (i32.const 196604) (i32.const 7))
+(assert_return (invoke "load32" (i32.const 196604)) (i32.const 7))
+(assert_trap (invoke "load32" (i32.const 196605)) "out of bounds memory access")
+(assert_return (invoke "grow" (i32.const 2)) (i32.const -1))
+(assert_return (invoke "grow" (i32.const 1)) (i32.const 3))
+(assert_return (invoke "grow" (i32.const 0)) (i32.const 4))
+
+;; Bulk operations
+(invoke "fill" (i32.const 100) (i32.const 0xaa) (i32.const 8))
+(assert_return (invoke "load64" (i32.const 100)) (i64.const 0xaaaaaaaaaaaaaaaa))
+(assert_return (invoke "load8_u" (i32.const 108)) (i32.const 0))
+(invoke "copy" (i32.const 102) (i32.const 16) (i32.const 4))
+(assert_return (invoke "load64" (i32.const 100)) (i64.const 0xaaaa04030201aaaa))
+(invoke "copy" (i32.const 17) (i32.const 16) (i32.const 4))
+(assert_return (invoke "load64" (i32.const 16)) (i64.const 0x0807060403020101))
+(assert_trap (invoke "fill" (i32.const 262140) (i32.const 0) (i32.const 5))
+  "out of bounds memory access")
+(assert_trap (invoke "copy" (i32.const 0) (i32.const 262140) (i32.const 5))
+  "out of bounds memory access")
@@ -0,0 +1,124 @@
+;; Functions keep the bytes of their memories in a local, which must get reloaded whenever
+;; something they call might have grown (and so replaced) the memory
+
+(module
+  (memory 1)
+  (tag $t)
+  (type $grow_type (func (result i32)))
+  (table funcref (elem $grow $no_grow))
+  (func $grow (result i32) (memory.grow (i32.const 1)))
+  (func $no_grow (result i32) (i32.const -1))
+  (func $grow_twice (result i32)
+    (drop (call $grow))
+    (call $grow))
+  (func $grow_throw (drop (memory.grow (i32.const 1))) (throw $t))
+
+  (func (export "after_call") (result i32)
+    (i32.store (i32.const 0) (i32.const 7))
+    (drop (call $grow))
+    (i32.store (i32.const 65540) (i32.const 9))
+    (i32.add (i32.load (i32.const 0)) (i32.load (i32.const 65540))))
+
+  (func (export "after_nested_call") (result i32)
+    (local $top i32)
+    (local.set $top (i32.mul (call $grow_twice) (i32.const 0x10000)))
+    (i32.store (local.get $top) (i32.const 11))
+    (i32.load (local.get $top)))
+
+  (func (export "after_call_indirect") (param $i i32) (result i32)
+    (local $pages i32)
+    (local.set $pages (memory.size))
+    (drop (call_indirect (type $grow_type) (local.get $i)))
+    (i32.store (i32.mul (local.get $pages) (i32.const 0x10000)) (i32.const 13))
+    (i32.load (i32.mul (local.get $pages) (i32.const 0x10000))))
+
+  (func (export "after_grow") (result i32)
+    (local $top i32)
+    (local.set $top (i32.mul (memory.size) (i32.const 0x10000)))
+    (i32.store (i32.const 4) (i32.const 3))
+    (drop (memory.grow (i32.const 1)))
+    (i32.store (i32.add (local.get $top) (i32.const 4)) (i32.const 5))
+    (i32.add (i32.load (i32.const 4)) (i32.load (i32.add (local.get $top) (i32.const 4)))))
+
+  (func (export "after_catch") (result i32)
+    (local $top i32)
+    (local.set $top (i32.mul (memory.size) (i32.const 0x10000)))
+    (i32.store (i32.const 8) (i32.const 1))
+    (try (do (call $grow_throw)) (catch $t))
+    (i32.store (i32.add (local.get $top) (i32.const 4)) (i32.const 2))
+    (i32.add (i32.load (i32.const 8)) (i32.load (i32.add (local.get $top) (i32.const 4)))))
+
+  (func (export "after_catch_all") (result i32)
+    (local $top i32)
+    (local.set $top (i32.mul (memory.size) (i32.const 0x10000)))
+    (try (do (call $grow_throw)) (catch_all))
+    (i32.store (i32.add (local.get $top) (i32.const 4)) (i32.const 21))
+    (i32.load (i32.add (local.get $top) (i32.const 4))))
+
+  ;; Grows on every iteration, touching the newest page each time
+  (func (export "grow_in_loop") (param $n i32) (result i32)
+    (local $sum i32) (local $page i32)
+    (loop $l
+      (local.set $page (call $grow))
+      (i32.store (i32.mul (local.get $page) (i32.const 0x10000)) (local.get $n))
+      (local.set $sum
+        (i32.add (local.get $sum) (i32.load (i32.mul (local.get $page) (i32.const 0x10000)))))
+      (br_if $l (local.tee $n (i32.sub (local.get $n) (i32.const 1)))))
+    (local.get $sum))
+
+  ;; Bulk memory operations after the grow use the new buffer too
+  (func (export "fill_after_call") (result i32)
+    (local $top i32)
+    (local.set $top (i32.mul (call $grow) (i32.const 0x10000)))
+    (memory.fill (local.get $top) (i32.const 0x2a) (i32.const 0x10000))
+    (i32.load8_u (i32.add (local.get $top) (i32.const 0xffff))))
+
+  (func (export "size") (result i32) (memory.size))
+)
+
+(assert_return (invoke "after_call") (i32.const 16))
+(assert_return (invoke "after_nested_call") (i32.const 11))
+(assert_return (invoke "after_call_indirect" (i32.const 0)) (i32.const 13))
+(assert_trap (invoke "after_call_indirect" (i32.const 1)) "out of bounds memory access")
+(assert_return (invoke "after_grow") (i32.const 8))
+(assert_return (invoke "after_catch") (i32.const 3))
+(assert_return (invoke "after_catch_all") (i32.const 21))
+(assert_return (invoke "grow_in_loop" (i32.const 10)) (i32.const 55))
+(assert_return (invoke "fill_after_call") (i32.const 0x2a))
+(assert_return (invoke "size") (i32.const 19))
+
+;; Growing an imported memory from the module that exports it
+(module $exporter
+  (memory (export "mem") 1)
+  (func (export "grow") (drop (memory.grow (i32.const 1))))
+)
+(register "exporter" $exporter)
+
+(module
+  (import "exporter" "mem" (memory 1))
+  (import "exporter" "grow" (func $grow))
+  (func (export "after_import") (result i32)
+    (i32.store (i32.const 0) (i32.const 4))
+    (call $grow)
+    (i32.store (i32.const 65536) (i32.const 6))
+    (i32.add (i32.load (i32.const 0)) (i32.load (i32.const 65536))))
+)
+
+(assert_return (invoke "after_import") (i32.const 10))
+
+;; Growing one memory while another one stays cached
+(module
+  (memory $a 1)
+  (memory $b 1)
+  (func $grow_b (result i32) (memory.grow $b (i32.const 1)))
+  (func (export "two_memories") (result i32)
+    (i32.store $a (i32.const 0) (i32.const 1))
+    (i32.store $b (i32.const 0) (i32.const 2))
+    (drop (call $grow_b))
+    (i32.store $b (i32.const 65536) (i32.const 3))
+    (i32.add
+      (i32.load $a (i32.const 0))
+      (i32.add (i32.load $b (i32.const 0)) (i32.load $b (i32.const 65536)))))
+)
+
+(assert_return (invoke "two_memories") (i32.const 6))
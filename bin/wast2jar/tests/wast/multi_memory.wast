@@ -0,0 +1,82 @@
+;; Several memories in one module, with `memory.copy` between them
+
+(module
+  (memory $a 1)
+  (memory $b 1 2)
+  (data (memory $a) (i32.const 0) "\01\02\03\04\05\06\07\08")
+  (data (memory $b) (i32.const 0) "\f1\f2\f3\f4")
+
+  (func (export "load_a") (param i32) (result i32) (i32.load8_u $a (local.get 0)))
+  (func (export "load_b") (param i32) (result i32) (i32.load8_u $b (local.get 0)))
+  (func (export "store_b") (param i32 i32) (i32.store8 $b (local.get 0) (local.get 1)))
+  (func (export "copy_a_to_b") (param i32 i32 i32)
+    (memory.copy $b $a (local.get 0) (local.get 1) (local.get 2)))
+  (func (export "copy_b_to_a") (param i32 i32 i32)
+    (memory.copy $a $b (local.get 0) (local.get 1) (local.get 2)))
+  (func (export "copy_b_to_b") (param i32 i32 i32)
+    (memory.copy $b $b (local.get 0) (local.get 1) (local.get 2)))
+  (func (export "size_a") (result i32) (memory.size $a))
+  (func (export "size_b") (result i32) (memory.size $b))
+  (func (export "grow_b") (param i32) (result i32) (memory.grow $b (local.get 0)))
+)
+
+;; Data segments land in their own memories
+(assert_return (invoke "load_a" (i32.const 0)) (i32.const 1))
+(assert_return (invoke "load_b" (i32.const 0)) (i32.const 0xf1))
+
+;; Copies go from the source memory into the destination memory only
+(invoke "copy_a_to_b" (i32.const 100) (i32.const 2) (i32.const 4))
+(assert_return (invoke "load_b" (i32.const 100)) (i32.const 3))
+(assert_return (invoke "load_b" (i32.const 103)) (i32.const 6))
+(assert_return (invoke "load_b" (i32.const 104)) (i32.const 0))
+(assert_return (invoke "load_a" (i32.const 100)) (i32.const 0))
+(invoke "copy_b_to_a" (i32.const 6) (i32.const 1) (i32.const 2))
+(assert_return (invoke "load_a" (i32.const 5)) (i32.const 6))
+(assert_return (invoke "load_a" (i32.const 6)) (i32.const 0xf2))
+(assert_return (invoke "load_a" (i32.const 7)) (i32.const 0xf3))
+(assert_return (invoke "load_b" (i32.const 6)) (i32.const 0))
+
+;; Overlapping copies within one memory behave like `memmove`
+(invoke "copy_b_to_b" (i32.const 1) (i32.const 0) (i32.const 4))
+(assert_return (invoke "load_b" (i32.const 0)) (i32.const 0xf1))
+(assert_return (invoke "load_b" (i32.const 1)) (i32.const 0xf1))
+(assert_return (invoke "load_b" (i32.const 4)) (i32.const 0xf4))
+(invoke "copy_b_to_b" (i32.const 0) (i32.const 1) (i32.const 4))
+(assert_return (invoke "load_b" (i32.const 0)) (i32.const 0xf1))
+(assert_return (invoke "load_b" (i32.const 3)) (i32.const 0xf4))
+
+;; Bounds are checked against the memory each side refers to
+(assert_trap (invoke "copy_a_to_b" (i32.const 65535) (i32.const 0) (i32.const 2))
+  "out of bounds memory access")
+(assert_trap (invoke "copy_b_to_a" (i32.const 0) (i32.const 65535) (i32.const 2))
+  "out of bounds memory access")
+
+;; Growing one memory leaves the other one alone
+(assert_return (invoke "grow_b" (i32.const 1)) (i32.const 1))
+(assert_return (invoke "size_a") (i32.const 1))
+(assert_return (invoke "size_b") (i32.const 2))
+(invoke "copy_a_to_b" (i32.const 65535) (i32.const 0) (i32.const 2))
+(assert_return (invoke "load_b" (i32.const 65536)) (i32.const 2))
+(assert_trap (invoke "copy_b_to_a" (i32.const 65535) (i32.const 65535) (i32.const 2))
+  "out of bounds memory access")
+(invoke "copy_b_to_a" (i32.const 65534) (i32.const 65535) (i32.const 2))
+(assert_return (invoke "load_a" (i32.const 65535)) (i32.const 2))
+
+;; Memories can be imported alongside the module's own
+(module $exporter
+  (memory (export "mem") 1)
+  (data (i32.const 0) "hello")
+)
+(register "exporter" $exporter)
+
+(module
+  (memory $imported (import "exporter" "mem") 1)
+  (memory $own 1)
+  (func (export "copy_in") (param i32 i32 i32)
+    (memory.copy $own $imported (local.get 0) (local.get 1) (local.get 2)))
+  (func (export "load") (param i32) (result i32) (i32.load8_u $own (local.get 0)))
+)
+
+(invoke "copy_in" (i32.const 10) (i32.const 0) (i32.const 5))
+(assert_return (invoke "load" (i32.const 10)) (i32.const 0x68))
+(assert_return (invoke "load" (i32.const 14)) (i32.const 0x6f))
@@ -0,0 +1,115 @@
+;; Functions returning several values, called directly, indirectly, through tail calls and
+;; across module boundaries
+
+(module $values
+  (type $pt (func (param i32 i64) (result i64 i32)))
+  (func $pair (export "pair") (type $pt)
+    (local.get 1) (local.get 0))
+  (func $four (export "four") (param f32 f64) (result f64 f32 i32 i64)
+    (local.get 1) (local.get 0) (i32.const 7) (i64.const -9))
+  (func $refs (export "refs") (param externref) (result externref i32 externref)
+    (local.get 0) (i32.const 3) (ref.null extern))
+  (table $table (export "table") funcref (elem $pair $four))
+
+  (func (export "call_pair") (param i32 i64) (result i64)
+    (call $pair (local.get 0) (local.get 1))
+    (i64.extend_i32_s)
+    (i64.sub))
+  (func (export "call_four") (result f64)
+    (call $four (f32.const 1.5) (f64.const 2.25))
+    (drop) (drop) (f64.promote_f32) (f64.add))
+  (func (export "call_refs") (param externref) (result i32)
+    (local $sum i32)
+    (call $refs (local.get 0))
+    (ref.is_null)
+    (i32.add)
+    (local.set $sum)
+    (ref.is_null)
+    (local.get $sum)
+    (i32.add))
+
+  (func (export "call_indirect") (param i32 i64 i32) (result i64 i32)
+    (call_indirect (type $pt) (local.get 0) (local.get 1) (local.get 2)))
+  (func (export "call_indirect_sum") (param i32 i64) (result i64)
+    (call_indirect (type $pt) (local.get 0) (local.get 1) (i32.const 0))
+    (i64.extend_i32_u)
+    (i64.add))
+
+  (func $countdown (export "countdown") (param i32 i64) (result i64 i32)
+    (if (result i64 i32) (i32.eqz (local.get 0))
+      (then (return_call $pair (i32.const 9) (local.get 1)))
+      (else
+        (return_call $countdown
+          (i32.sub (local.get 0) (i32.const 1))
+          (i64.add (local.get 1) (i64.const 2))))))
+  (func (export "call_countdown") (result i64)
+    (call $countdown (i32.const 3) (i64.const 0))
+    (drop))
+  (func (export "return_call_indirect") (param i32 i64 i32) (result i64 i32)
+    (return_call_indirect (type $pt) (local.get 0) (local.get 1) (local.get 2)))
+
+  (func (export "block") (param i32) (result i32 i64)
+    (block (result i32 i64)
+      (i32.const 1) (i64.const 2)
+      (br_if 0 (local.get 0))
+      (drop) (drop) (i32.const 3) (i64.const 4)))
+)
+
+(assert_return (invoke "pair" (i32.const 5) (i64.const 6)) (i64.const 6) (i32.const 5))
+(assert_return (invoke "four" (f32.const 1.5) (f64.const 2.25))
+  (f64.const 2.25) (f32.const 1.5) (i32.const 7) (i64.const -9))
+(assert_return (invoke "refs" (ref.extern 1)) (ref.extern 1) (i32.const 3) (ref.null extern))
+(assert_return (invoke "call_pair" (i32.const 5) (i64.const 16)) (i64.const 11))
+(assert_return (invoke "call_four") (f64.const 3.75))
+(assert_return (invoke "call_refs" (ref.extern 1)) (i32.const 4))
+(assert_return (invoke "call_refs" (ref.null extern)) (i32.const 5))
+(assert_return (invoke "call_indirect" (i32.const 5) (i64.const 16) (i32.const 0))
+  (i64.const 16) (i32.const 5))
+(assert_trap (invoke "call_indirect" (i32.const 5) (i64.const 16) (i32.const 1))
+  "indirect call type mismatch")
+(assert_trap (invoke "call_indirect" (i32.const 5) (i64.const 16) (i32.const 2))
+  "undefined element")
+(assert_return (invoke "call_indirect_sum" (i32.const 5) (i64.const 16)) (i64.const 21))
+(assert_return (invoke "countdown" (i32.const 5) (i64.const 1)) (i64.const 11) (i32.const 9))
+(assert_return (invoke "countdown" (i32.const 100000) (i64.const 0))
+  (i64.const 200000) (i32.const 9))
+(assert_return (invoke "call_countdown") (i64.const 6))
+(assert_return (invoke "return_call_indirect" (i32.const 5) (i64.const 1) (i32.const 0))
+  (i64.const 1) (i32.const 5))
+(assert_trap (invoke "return_call_indirect" (i32.const 5) (i64.const 1) (i32.const 1))
+  "indirect call type mismatch")
+(assert_return (invoke "block" (i32.const 1)) (i32.const 1) (i64.const 2))
+(assert_return (invoke "block" (i32.const 0)) (i32.const 3) (i64.const 4))
+
+(register "values" $values)
+
+;; Imported functions and tables returning several values
+(module
+  (type $pt (func (param i32 i64) (result i64 i32)))
+  (import "values" "pair" (func $pair (type $pt)))
+  (import "values" "four" (func $four (param f32 f64) (result f64 f32 i32 i64)))
+  (import "values" "table" (table $imported 2 funcref))
+
+  (func (export "call_imported") (param i32 i64) (result i64 i32)
+    (call $pair (local.get 0) (local.get 1)))
+  (func (export "call_imported_four") (result i64)
+    (local $last i64)
+    (call $four (f32.const 0) (f64.const 0))
+    (local.set $last)
+    (drop) (drop) (drop)
+    (local.get $last))
+  (func (export "tail_imported") (param i32 i64) (result i64 i32)
+    (return_call $pair (local.get 0) (local.get 1)))
+  (func (export "indirect_imported") (param i32 i64) (result i64 i32)
+    (call_indirect $imported (type $pt) (local.get 0) (local.get 1) (i32.const 0)))
+  (func (export "tail_indirect_imported") (param i32 i64) (result i64 i32)
+    (return_call_indirect $imported (type $pt) (local.get 0) (local.get 1) (i32.const 0)))
+)
+
+(assert_return (invoke "call_imported" (i32.const 1) (i64.const 2)) (i64.const 2) (i32.const 1))
+(assert_return (invoke "call_imported_four") (i64.const -9))
+(assert_return (invoke "tail_imported" (i32.const 3) (i64.const 4)) (i64.const 4) (i32.const 3))
+(assert_return (invoke "indirect_imported" (i32.const 5) (i64.const 6))
+  (i64.const 6) (i32.const 5))
+(assert_return (invoke "tail_indirect_imported" (i32.const 7) (i64.const 8))
+  (i64.const 8) (i32.const 7))
@@ -0,0 +1,75 @@
+;; Indirect tail calls shouldn't grow the stack, even between functions which contain tail calls
+
+(module
+  (type $i32_i32 (func (param i32) (result i32)))
+  (table 2 funcref)
+  (elem (i32.const 0) $even $odd)
+  (func $even (export "even") (type $i32_i32)
+    (if (result i32) (i32.eqz (local.get 0))
+      (then (i32.const 1))
+      (else (return_call_indirect (type $i32_i32) (i32.sub (local.get 0) (i32.const 1)) (i32.const 1)))))
+  (func $odd (export "odd") (type $i32_i32)
+    (if (result i32) (i32.eqz (local.get 0))
+      (then (i32.const 0))
+      (else (return_call_indirect (type $i32_i32) (i32.sub (local.get 0) (i32.const 1)) (i32.const 0)))))
+
+  ;; Same, but the function references come from `ref.func` and a table set at runtime
+  (table $t 2 funcref)
+  (elem declare func $even2 $odd2)
+  (func $setup (export "setup")
+    (table.set $t (i32.const 0) (ref.func $even2))
+    (table.set $t (i32.const 1) (ref.func $odd2)))
+  (func $even2 (export "even2") (param i64) (result i32)
+    (if (result i32) (i64.eqz (local.get 0))
+      (then (i32.const 1))
+      (else (return_call_indirect $t (param i64) (result i32) (i64.sub (local.get 0) (i64.const 1)) (i32.const 1)))))
+  (func $odd2 (param i64) (result i32)
+    (if (result i32) (i64.eqz (local.get 0))
+      (then (i32.const 0))
+      (else (return_call_indirect $t (param i64) (result i32) (i64.sub (local.get 0) (i64.const 1)) (i32.const 0)))))
+
+  ;; Wrong type in the table still traps
+  (func $bad (export "bad") (result i32)
+    (return_call_indirect (type $i32_i32) (i32.const 0) (i32.const 0) (i32.const 2)))
+  (func $bad2 (export "bad2") (result i64)
+    (return_call_indirect (param i32) (result i64) (i32.const 0) (i32.const 0)))
+)
+
+(assert_return (invoke "even" (i32.const 0)) (i32.const 1))
+(assert_return (invoke "even" (i32.const 7)) (i32.const 0))
+(assert_return (invoke "odd" (i32.const 7)) (i32.const 1))
+(assert_return (invoke "even" (i32.const 1000000)) (i32.const 1))
+(assert_return (invoke "odd" (i32.const 1000001)) (i32.const 1))
+(invoke "setup")
+(assert_return (invoke "even2" (i64.const 1000000)) (i32.const 1))
+(assert_return (invoke "even2" (i64.const 999999)) (i32.const 0))
+(assert_trap (invoke "bad") "undefined element")
+(assert_trap (invoke "bad2") "indirect call type mismatch")
+
+;; Function references to functions containing tail calls still work with regular calls
+(module
+  (type $t2 (func (param i32) (result i32 i64)))
+  (type $tv (func (param i32)))
+  (table 4 funcref)
+  (elem (i32.const 0) $pair $pair_tail $void $void_tail)
+  (global $g (mut i32) (i32.const 0))
+  (func $pair (type $t2) (i32.add (local.get 0) (i32.const 1)) (i64.const 7))
+  (func $pair_tail (type $t2)
+    (if (i32.eqz (local.get 0)) (then (return (i32.const 42) (i64.const 9))))
+    (return_call_indirect (type $t2) (i32.sub (local.get 0) (i32.const 1)) (i32.const 1)))
+  (func $void (type $tv) (global.set $g (local.get 0)))
+  (func $void_tail (type $tv)
+    (if (i32.eqz (local.get 0)) (then (return)))
+    (global.set $g (i32.add (global.get $g) (i32.const 1)))
+    (return_call_indirect (type $tv) (i32.sub (local.get 0) (i32.const 1)) (i32.const 3)))
+  (func (export "call_pair") (param i32 i32) (result i32 i64)
+    (call_indirect (type $t2) (local.get 0) (local.get 1)))
+  (func (export "call_void") (param i32 i32) (result i32)
+    (global.set $g (i32.const 0))
+    (call_indirect (type $tv) (local.get 0) (local.get 1))
+    (global.get $g))
+)
+(assert_return (invoke "call_pair" (i32.const 5) (i32.const 0)) (i32.const 6) (i64.const 7))
+(assert_return (invoke "call_pair" (i32.const 100000) (i32.const 1)) (i32.const 42) (i64.const 9))
+(assert_return (invoke "call_void" (i32.const 100000) (i32.const 3)) (i32.const 100000))
+(assert_return (invoke "call_void" (i32.const 5) (i32.const 2)) (i32.const 5))
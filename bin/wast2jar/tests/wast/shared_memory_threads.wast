@@ -0,0 +1,20 @@
+;; Shared memory grown by several threads at once (see `shared_memory_threads` in `wast.rs`)
+
+(module
+  (memory 1 64 shared)
+
+  ;; Atomically increment a counter `n` times, growing the memory every 5000 increments
+  (func (export "work") (param $n i32)
+    (loop $continue
+      (drop (i32.atomic.rmw.add (i32.const 0) (i32.const 1)))
+      (if (i32.eqz (i32.rem_u (local.get $n) (i32.const 5000)))
+        (then (drop (memory.grow (i32.const 1)))))
+      (local.tee $n (i32.sub (local.get $n) (i32.const 1)))
+      (br_if $continue)))
+  (func (export "count") (result i32) (i32.atomic.load (i32.const 0)))
+  (func (export "size") (result i32) (memory.size))
+)
+
+(invoke "work" (i32.const 5000))
+(assert_return (invoke "count") (i32.const 5000))
+(assert_return (invoke "size") (i32.const 2))
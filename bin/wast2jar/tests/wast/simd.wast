@@ -0,0 +1,247 @@
+;; SIMD operators on `v128` values
+
+(module
+  (memory 1)
+  (data (i32.const 0) "\00\01\02\03\04\05\06\07\08\09\0a\0b\0c\0d\0e\0f")
+  (data (i32.const 16) "\ff\fe\fd\fc\fb\fa\f9\f8\f7\f6\f5\f4\f3\f2\f1\f0")
+
+  ;; Lanes
+  (func (export "i8x16.extract_lane_s") (param v128) (result i32)
+    (i8x16.extract_lane_s 15 (local.get 0)))
+  (func (export "i8x16.extract_lane_u") (param v128) (result i32)
+    (i8x16.extract_lane_u 15 (local.get 0)))
+  (func (export "i16x8.extract_lane_s") (param v128) (result i32)
+    (i16x8.extract_lane_s 3 (local.get 0)))
+  (func (export "i64x2.extract_lane") (param v128) (result i64)
+    (i64x2.extract_lane 1 (local.get 0)))
+  (func (export "f32x4.extract_lane") (param v128) (result f32)
+    (f32x4.extract_lane 2 (local.get 0)))
+  (func (export "i8x16.replace_lane") (param v128 i32) (result v128)
+    (i8x16.replace_lane 7 (local.get 0) (local.get 1)))
+  (func (export "i32x4.replace_lane") (param v128 i32) (result v128)
+    (i32x4.replace_lane 0 (local.get 0) (local.get 1)))
+  (func (export "f64x2.replace_lane") (param v128 f64) (result v128)
+    (f64x2.replace_lane 1 (local.get 0) (local.get 1)))
+  (func (export "i16x8.splat") (param i32) (result v128) (i16x8.splat (local.get 0)))
+  (func (export "f32x4.splat") (param f32) (result v128) (f32x4.splat (local.get 0)))
+
+  ;; Shuffles and swizzles
+  (func (export "i8x16.shuffle") (param v128 v128) (result v128)
+    (i8x16.shuffle 31 0 30 1 29 2 28 3 27 4 26 5 25 6 24 7 (local.get 0) (local.get 1)))
+  (func (export "i8x16.swizzle") (param v128 v128) (result v128)
+    (i8x16.swizzle (local.get 0) (local.get 1)))
+
+  ;; Saturating arithmetic
+  (func (export "i8x16.add_sat_s") (param v128 v128) (result v128)
+    (i8x16.add_sat_s (local.get 0) (local.get 1)))
+  (func (export "i8x16.add_sat_u") (param v128 v128) (result v128)
+    (i8x16.add_sat_u (local.get 0) (local.get 1)))
+  (func (export "i16x8.sub_sat_s") (param v128 v128) (result v128)
+    (i16x8.sub_sat_s (local.get 0) (local.get 1)))
+  (func (export "i16x8.sub_sat_u") (param v128 v128) (result v128)
+    (i16x8.sub_sat_u (local.get 0) (local.get 1)))
+  (func (export "i16x8.q15mulr_sat_s") (param v128 v128) (result v128)
+    (i16x8.q15mulr_sat_s (local.get 0) (local.get 1)))
+  (func (export "i8x16.narrow_i16x8_s") (param v128 v128) (result v128)
+    (i8x16.narrow_i16x8_s (local.get 0) (local.get 1)))
+  (func (export "i8x16.narrow_i16x8_u") (param v128 v128) (result v128)
+    (i8x16.narrow_i16x8_u (local.get 0) (local.get 1)))
+
+  ;; Floating point min/max (which propagate NaN and order the zeros)
+  (func (export "f32x4.min") (param v128 v128) (result v128)
+    (f32x4.min (local.get 0) (local.get 1)))
+  (func (export "f32x4.max") (param v128 v128) (result v128)
+    (f32x4.max (local.get 0) (local.get 1)))
+  (func (export "f64x2.min") (param v128 v128) (result v128)
+    (f64x2.min (local.get 0) (local.get 1)))
+  (func (export "f64x2.max") (param v128 v128) (result v128)
+    (f64x2.max (local.get 0) (local.get 1)))
+  (func (export "f32x4.pmin") (param v128 v128) (result v128)
+    (f32x4.pmin (local.get 0) (local.get 1)))
+  (func (export "f64x2.pmax") (param v128 v128) (result v128)
+    (f64x2.pmax (local.get 0) (local.get 1)))
+
+  ;; Conversions
+  (func (export "i32x4.trunc_sat_f32x4_s") (param v128) (result v128)
+    (i32x4.trunc_sat_f32x4_s (local.get 0)))
+  (func (export "i32x4.trunc_sat_f32x4_u") (param v128) (result v128)
+    (i32x4.trunc_sat_f32x4_u (local.get 0)))
+  (func (export "i32x4.trunc_sat_f64x2_s_zero") (param v128) (result v128)
+    (i32x4.trunc_sat_f64x2_s_zero (local.get 0)))
+  (func (export "f32x4.convert_i32x4_u") (param v128) (result v128)
+    (f32x4.convert_i32x4_u (local.get 0)))
+  (func (export "f64x2.convert_low_i32x4_s") (param v128) (result v128)
+    (f64x2.convert_low_i32x4_s (local.get 0)))
+  (func (export "f32x4.demote_f64x2_zero") (param v128) (result v128)
+    (f32x4.demote_f64x2_zero (local.get 0)))
+  (func (export "f64x2.promote_low_f32x4") (param v128) (result v128)
+    (f64x2.promote_low_f32x4 (local.get 0)))
+  (func (export "i16x8.extend_high_i8x16_s") (param v128) (result v128)
+    (i16x8.extend_high_i8x16_s (local.get 0)))
+  (func (export "i64x2.extend_low_i32x4_u") (param v128) (result v128)
+    (i64x2.extend_low_i32x4_u (local.get 0)))
+
+  ;; Loads and stores
+  (func (export "v128.load") (param i32) (result v128) (v128.load (local.get 0)))
+  (func (export "v128.store") (param i32 v128) (v128.store (local.get 0) (local.get 1)))
+  (func (export "v128.load8x8_s") (param i32) (result v128) (v128.load8x8_s (local.get 0)))
+  (func (export "v128.load16x4_u") (param i32) (result v128) (v128.load16x4_u (local.get 0)))
+  (func (export "v128.load8_splat") (param i32) (result v128) (v128.load8_splat (local.get 0)))
+  (func (export "v128.load32_splat") (param i32) (result v128)
+    (v128.load32_splat offset=1 (local.get 0)))
+  (func (export "v128.load32_zero") (param i32) (result v128) (v128.load32_zero (local.get 0)))
+  (func (export "v128.load64_zero") (param i32) (result v128) (v128.load64_zero (local.get 0)))
+  (func (export "v128.load16_lane") (param i32 v128) (result v128)
+    (v128.load16_lane 5 (local.get 0) (local.get 1)))
+  (func (export "v128.load64_lane") (param i32 v128) (result v128)
+    (v128.load64_lane 1 (local.get 0) (local.get 1)))
+  (func (export "v128.store8_lane") (param i32 v128)
+    (v128.store8_lane 15 (local.get 0) (local.get 1)))
+  (func (export "v128.store32_lane") (param i32 v128)
+    (v128.store32_lane 2 (local.get 0) (local.get 1)))
+)
+
+(assert_return (invoke "i8x16.extract_lane_s" (v128.const i8x16 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 -2))
+  (i32.const -2))
+(assert_return (invoke "i8x16.extract_lane_u" (v128.const i8x16 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 -2))
+  (i32.const 254))
+(assert_return (invoke "i16x8.extract_lane_s" (v128.const i16x8 0 0 0 -32768 0 0 0 0))
+  (i32.const -32768))
+(assert_return (invoke "i64x2.extract_lane" (v128.const i64x2 1 -9)) (i64.const -9))
+(assert_return (invoke "f32x4.extract_lane" (v128.const f32x4 0 1 -2.5 3)) (f32.const -2.5))
+(assert_return (invoke "i8x16.replace_lane" (v128.const i8x16 0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15)
+    (i32.const 0x1ff))
+  (v128.const i8x16 0 1 2 3 4 5 6 -1 8 9 10 11 12 13 14 15))
+(assert_return (invoke "i32x4.replace_lane" (v128.const i32x4 1 2 3 4) (i32.const -1))
+  (v128.const i32x4 -1 2 3 4))
+(assert_return (invoke "f64x2.replace_lane" (v128.const f64x2 1 2) (f64.const nan))
+  (v128.const f64x2 1 nan:canonical))
+(assert_return (invoke "i16x8.splat" (i32.const 0x12345)) (v128.const i16x8 0x2345 0x2345 0x2345 0x2345 0x2345 0x2345 0x2345 0x2345))
+(assert_return (invoke "f32x4.splat" (f32.const -0.5)) (v128.const f32x4 -0.5 -0.5 -0.5 -0.5))
+
+(assert_return
+  (invoke "i8x16.shuffle"
+    (v128.const i8x16 0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15)
+    (v128.const i8x16 16 17 18 19 20 21 22 23 24 25 26 27 28 29 30 31))
+  (v128.const i8x16 31 0 30 1 29 2 28 3 27 4 26 5 25 6 24 7))
+(assert_return
+  (invoke "i8x16.swizzle"
+    (v128.const i8x16 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24 25)
+    (v128.const i8x16 15 14 0 1 16 -1 128 255 2 2 3 3 7 8 9 17))
+  (v128.const i8x16 25 24 10 11 0 0 0 0 12 12 13 13 17 18 19 0))
+
+(assert_return
+  (invoke "i8x16.add_sat_s"
+    (v128.const i8x16 127 -128 100 -100 1 -1 0 0 0 0 0 0 0 0 0 0)
+    (v128.const i8x16 1 -1 100 -100 1 -1 0 0 0 0 0 0 0 0 0 0))
+  (v128.const i8x16 127 -128 127 -128 2 -2 0 0 0 0 0 0 0 0 0 0))
+(assert_return
+  (invoke "i8x16.add_sat_u"
+    (v128.const i8x16 255 200 1 0 0 0 0 0 0 0 0 0 0 0 0 0)
+    (v128.const i8x16 1 100 1 0 0 0 0 0 0 0 0 0 0 0 0 0))
+  (v128.const i8x16 255 255 2 0 0 0 0 0 0 0 0 0 0 0 0 0))
+(assert_return
+  (invoke "i16x8.sub_sat_s"
+    (v128.const i16x8 -32768 32767 0 5 0 0 0 0)
+    (v128.const i16x8 1 -1 -32768 10 0 0 0 0))
+  (v128.const i16x8 -32768 32767 32767 -5 0 0 0 0))
+(assert_return
+  (invoke "i16x8.sub_sat_u"
+    (v128.const i16x8 0 65535 10 0 0 0 0 0)
+    (v128.const i16x8 1 1 20 0 0 0 0 0))
+  (v128.const i16x8 0 65534 0 0 0 0 0 0))
+(assert_return
+  (invoke "i16x8.q15mulr_sat_s"
+    (v128.const i16x8 -32768 16384 -16384 1 0 0 0 0)
+    (v128.const i16x8 -32768 16384 16384 1 0 0 0 0))
+  (v128.const i16x8 32767 8192 -8192 0 0 0 0 0))
+(assert_return
+  (invoke "i8x16.narrow_i16x8_s"
+    (v128.const i16x8 300 -300 127 -128 0 1 -1 5)
+    (v128.const i16x8 0 0 0 0 0 0 0 -32768))
+  (v128.const i8x16 127 -128 127 -128 0 1 -1 5 0 0 0 0 0 0 0 -128))
+(assert_return
+  (invoke "i8x16.narrow_i16x8_u"
+    (v128.const i16x8 300 -300 255 128 0 1 -1 5)
+    (v128.const i16x8 0 0 0 0 0 0 0 32767))
+  (v128.const i8x16 255 0 255 128 0 1 0 5 0 0 0 0 0 0 0 255))
+
+(assert_return
+  (invoke "f32x4.min" (v128.const f32x4 nan 0 -0 1) (v128.const f32x4 1 -0 0 nan))
+  (v128.const f32x4 nan:canonical -0 -0 nan:canonical))
+(assert_return
+  (invoke "f32x4.max" (v128.const f32x4 nan 0 -0 1) (v128.const f32x4 1 -0 0 nan))
+  (v128.const f32x4 nan:canonical 0 0 nan:canonical))
+(assert_return
+  (invoke "f64x2.min" (v128.const f64x2 -0 nan) (v128.const f64x2 0 -inf))
+  (v128.const f64x2 -0 nan:canonical))
+(assert_return
+  (invoke "f64x2.max" (v128.const f64x2 -0 inf) (v128.const f64x2 0 nan))
+  (v128.const f64x2 0 nan:canonical))
+(assert_return
+  (invoke "f32x4.pmin" (v128.const f32x4 nan 0 -0 2) (v128.const f32x4 1 -0 0 nan))
+  (v128.const f32x4 nan:canonical 0 -0 2))
+(assert_return
+  (invoke "f64x2.pmax" (v128.const f64x2 -0 1) (v128.const f64x2 0 nan))
+  (v128.const f64x2 -0 1))
+
+(assert_return
+  (invoke "i32x4.trunc_sat_f32x4_s" (v128.const f32x4 nan 3e9 -3e9 -2.7))
+  (v128.const i32x4 0 2147483647 -2147483648 -2))
+(assert_return
+  (invoke "i32x4.trunc_sat_f32x4_u" (v128.const f32x4 nan 5e9 -1 3.9))
+  (v128.const i32x4 0 -1 0 3))
+(assert_return
+  (invoke "i32x4.trunc_sat_f64x2_s_zero" (v128.const f64x2 -1e10 7.5))
+  (v128.const i32x4 -2147483648 7 0 0))
+(assert_return
+  (invoke "f32x4.convert_i32x4_u" (v128.const i32x4 -1 0 1 0x80000000))
+  (v128.const f32x4 4294967296 0 1 2147483648))
+(assert_return
+  (invoke "f64x2.convert_low_i32x4_s" (v128.const i32x4 -1 2147483647 5 6))
+  (v128.const f64x2 -1 2147483647))
+(assert_return
+  (invoke "f32x4.demote_f64x2_zero" (v128.const f64x2 1.5 1e300))
+  (v128.const f32x4 1.5 inf 0 0))
+(assert_return
+  (invoke "f64x2.promote_low_f32x4" (v128.const f32x4 -0.25 nan 7 8))
+  (v128.const f64x2 -0.25 nan:canonical))
+(assert_return
+  (invoke "i16x8.extend_high_i8x16_s"
+    (v128.const i8x16 0 0 0 0 0 0 0 0 -1 1 -128 127 0 0 0 0))
+  (v128.const i16x8 -1 1 -128 127 0 0 0 0))
+(assert_return
+  (invoke "i64x2.extend_low_i32x4_u" (v128.const i32x4 -1 7 0 0))
+  (v128.const i64x2 0xffffffff 7))
+
+(assert_return (invoke "v128.load" (i32.const 0))
+  (v128.const i8x16 0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15))
+(assert_return (invoke "v128.load8x8_s" (i32.const 12))
+  (v128.const i16x8 12 13 14 15 -1 -2 -3 -4))
+(assert_return (invoke "v128.load16x4_u" (i32.const 16))
+  (v128.const i32x4 0xfeff 0xfcfd 0xfafb 0xf8f9))
+(assert_return (invoke "v128.load8_splat" (i32.const 17))
+  (v128.const i8x16 -2 -2 -2 -2 -2 -2 -2 -2 -2 -2 -2 -2 -2 -2 -2 -2))
+(assert_return (invoke "v128.load32_splat" (i32.const 3))
+  (v128.const i32x4 0x07060504 0x07060504 0x07060504 0x07060504))
+(assert_return (invoke "v128.load32_zero" (i32.const 4))
+  (v128.const i32x4 0x07060504 0 0 0))
+(assert_return (invoke "v128.load64_zero" (i32.const 8))
+  (v128.const i64x2 0x0f0e0d0c0b0a0908 0))
+(assert_return (invoke "v128.load16_lane" (i32.const 2) (v128.const i16x8 0 0 0 0 0 0 0 0))
+  (v128.const i16x8 0 0 0 0 0 0x0302 0 0))
+(assert_return (invoke "v128.load64_lane" (i32.const 0) (v128.const i64x2 -1 -1))
+  (v128.const i64x2 -1 0x0706050403020100))
+(assert_trap (invoke "v128.load" (i32.const 65521)) "out of bounds memory access")
+(assert_trap (invoke "v128.load32_zero" (i32.const 65533)) "out of bounds memory access")
+
+(invoke "v128.store" (i32.const 32) (v128.const i32x4 1 2 3 4))
+(assert_return (invoke "v128.load" (i32.const 32)) (v128.const i32x4 1 2 3 4))
+(invoke "v128.store8_lane" (i32.const 32) (v128.const i8x16 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 9))
+(assert_return (invoke "v128.load" (i32.const 32)) (v128.const i32x4 9 2 3 4))
+(invoke "v128.store32_lane" (i32.const 44) (v128.const i32x4 0 0 -7 0))
+(assert_return (invoke "v128.load" (i32.const 32)) (v128.const i32x4 9 2 3 -7))
+(assert_trap (invoke "v128.store32_lane" (i32.const 65533) (v128.const i32x4 0 0 0 0))
+  "out of bounds memory access")
+(assert_trap (invoke "v128.store" (i32.const -1) (v128.const i32x4 0 0 0 0))
+  "out of bounds memory access")
@@ -0,0 +1,128 @@
+;; Loop-carried values that swap or rotate between locals on every iteration,
+;; which the SSA lowering must resolve as parallel copies
+
+(module
+  (func (export "fib") (param $n i32) (result i64)
+    (local $a i64) (local $b i64) (local $t i64)
+    (local.set $b (i64.const 1))
+    (block $done
+      (loop $l
+        (br_if $done (i32.eqz (local.get $n)))
+        (local.set $t (i64.add (local.get $a) (local.get $b)))
+        (local.set $a (local.get $b))
+        (local.set $b (local.get $t))
+        (local.set $n (i32.sub (local.get $n) (i32.const 1)))
+        (br $l)))
+    (local.get $a))
+
+  ;; Swap through the operand stack, without a temporary local
+  (func (export "swap") (param $n i32) (param $x i32) (param $y i32) (result i32)
+    (loop $l
+      (local.get $y) (local.get $x)
+      (local.set $y) (local.set $x)
+      (local.tee $n (i32.sub (local.get $n) (i32.const 1)))
+      (br_if $l))
+    (i32.sub (i32.mul (local.get $x) (i32.const 10)) (local.get $y)))
+
+  (func (export "swap_f64") (param $n i32) (param $x f64) (param $y f64) (result f64)
+    (loop $l
+      (local.get $y) (local.get $x)
+      (local.set $y) (local.set $x)
+      (local.tee $n (i32.sub (local.get $n) (i32.const 1)))
+      (br_if $l))
+    (f64.sub (local.get $x) (local.get $y)))
+
+  ;; (a, b, c) <- (b, c, a)
+  (func (export "rotate") (param $n i32) (param $a i32) (param $b i32) (param $c i32) (result i32)
+    (loop $l
+      (local.get $b) (local.get $c) (local.get $a)
+      (local.set $c) (local.set $b) (local.set $a)
+      (local.tee $n (i32.sub (local.get $n) (i32.const 1)))
+      (br_if $l))
+    (i32.add
+      (i32.mul (local.get $a) (i32.const 100))
+      (i32.add (i32.mul (local.get $b) (i32.const 10)) (local.get $c))))
+
+  ;; (a, b) <- (b, a % b)
+  (func (export "gcd") (param $a i64) (param $b i64) (result i64)
+    (block $done
+      (loop $l
+        (br_if $done (i64.eqz (local.get $b)))
+        (local.get $b) (i64.rem_u (local.get $a) (local.get $b))
+        (local.set $b) (local.set $a)
+        (br $l)))
+    (local.get $a))
+
+  ;; The old value of a swapped local is still live after the swap
+  (func (export "swap_keep") (param $n i32) (param $x i32) (param $y i32) (result i32)
+    (local $sum i32)
+    (loop $l
+      (local.get $y) (local.get $x)
+      (local.set $y) (local.tee $x)
+      (local.set $sum (i32.add (local.get $sum) (i32.mul (local.get $y) (i32.const 10))))
+      (local.set $sum (i32.add (local.get $sum)))
+      (local.tee $n (i32.sub (local.get $n) (i32.const 1)))
+      (br_if $l))
+    (local.get $sum))
+
+  ;; Swaps in both an inner and an outer loop
+  (func (export "nested") (param $n i32) (param $x i32) (param $y i32) (result i32)
+    (local $m i32)
+    (loop $outer
+      (local.set $m (i32.const 3))
+      (loop $inner
+        (local.get $y) (local.get $x)
+        (local.set $y) (local.set $x)
+        (local.tee $m (i32.sub (local.get $m) (i32.const 1)))
+        (br_if $inner))
+      (local.set $x (i32.add (local.get $x) (local.get $y)))
+      (local.get $y) (local.get $x)
+      (local.set $y) (local.set $x)
+      (local.tee $n (i32.sub (local.get $n) (i32.const 1)))
+      (br_if $outer))
+    (i32.sub (i32.mul (local.get $x) (i32.const 1000)) (local.get $y)))
+
+  ;; A swap that only happens on some iterations
+  (func (export "cond_swap") (param $n i32) (param $x i32) (param $y i32) (result i32)
+    (loop $l
+      (if (i32.and (local.get $n) (i32.const 1))
+        (then
+          (local.get $y) (local.get $x)
+          (local.set $y) (local.set $x)))
+      (local.set $x (i32.add (local.get $x) (i32.const 1)))
+      (local.tee $n (i32.sub (local.get $n) (i32.const 1)))
+      (br_if $l))
+    (i32.sub (i32.mul (local.get $x) (i32.const 100)) (local.get $y)))
+
+  ;; A loop with block parameters carrying the swapped pair
+  (func (export "loop_params") (param $n i32) (result i32)
+    (local $a i32) (local $b i32)
+    (i32.const 1) (i32.const 2)
+    (loop $l (param i32 i32) (result i32 i32)
+      (local.set $b) (local.set $a)
+      (local.get $b) (local.get $a)
+      (br_if $l (local.tee $n (i32.sub (local.get $n) (i32.const 1)))))
+    (local.set $b)
+    (i32.sub (i32.mul (i32.const 10)) (local.get $b)))
+)
+
+(assert_return (invoke "fib" (i32.const 10)) (i64.const 55))
+(assert_return (invoke "fib" (i32.const 0)) (i64.const 0))
+(assert_return (invoke "fib" (i32.const 90)) (i64.const 2880067194370816120))
+(assert_return (invoke "swap" (i32.const 3) (i32.const 1) (i32.const 2)) (i32.const 19))
+(assert_return (invoke "swap" (i32.const 2) (i32.const 1) (i32.const 2)) (i32.const 8))
+(assert_return (invoke "swap_f64" (i32.const 1) (f64.const 0.5) (f64.const 2)) (f64.const 1.5))
+(assert_return (invoke "swap_f64" (i32.const 4) (f64.const 0.5) (f64.const 2)) (f64.const -1.5))
+(assert_return (invoke "rotate" (i32.const 1) (i32.const 1) (i32.const 2) (i32.const 3)) (i32.const 231))
+(assert_return (invoke "rotate" (i32.const 2) (i32.const 1) (i32.const 2) (i32.const 3)) (i32.const 312))
+(assert_return (invoke "rotate" (i32.const 3) (i32.const 1) (i32.const 2) (i32.const 3)) (i32.const 123))
+(assert_return (invoke "gcd" (i64.const 1071) (i64.const 462)) (i64.const 21))
+(assert_return (invoke "gcd" (i64.const 17) (i64.const 0)) (i64.const 17))
+(assert_return (invoke "swap_keep" (i32.const 1) (i32.const 1) (i32.const 2)) (i32.const 12))
+(assert_return (invoke "swap_keep" (i32.const 2) (i32.const 1) (i32.const 2)) (i32.const 33))
+(assert_return (invoke "nested" (i32.const 1) (i32.const 1) (i32.const 2)) (i32.const 997))
+(assert_return (invoke "nested" (i32.const 2) (i32.const 1) (i32.const 2)) (i32.const 996))
+(assert_return (invoke "cond_swap" (i32.const 1) (i32.const 1) (i32.const 2)) (i32.const 299))
+(assert_return (invoke "cond_swap" (i32.const 2) (i32.const 1) (i32.const 2)) (i32.const 298))
+(assert_return (invoke "loop_params" (i32.const 1)) (i32.const 19))
+(assert_return (invoke "loop_params" (i32.const 2)) (i32.const 8))
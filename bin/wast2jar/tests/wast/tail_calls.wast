@@ -0,0 +1,39 @@
+;; Direct and indirect tail calls, shallow enough to also pass as plain calls
+
+(module
+  (type $i32_to_i32 (func (param i32) (result i32)))
+  (table 2 funcref)
+  (elem (i32.const 0) $even $odd)
+
+  (func $even (export "even") (param i32) (result i32)
+    (if (result i32) (i32.eqz (local.get 0))
+      (then (i32.const 1))
+      (else (return_call $odd (i32.sub (local.get 0) (i32.const 1))))))
+  (func $odd (export "odd") (param i32) (result i32)
+    (if (result i32) (i32.eqz (local.get 0))
+      (then (i32.const 0))
+      (else
+        (return_call_indirect (type $i32_to_i32)
+          (i32.sub (local.get 0) (i32.const 1))
+          (i32.const 0)))))
+
+  (func $sum (param i64 i64) (result i64)
+    (if (result i64) (i64.eqz (local.get 0))
+      (then (local.get 1))
+      (else
+        (return_call $sum
+          (i64.sub (local.get 0) (i64.const 1))
+          (i64.add (local.get 1) (local.get 0))))))
+  (func (export "sum") (param i64) (result i64)
+    (return_call $sum (local.get 0) (i64.const 0)))
+
+  (func (export "bad_type") (result i32)
+    (return_call_indirect (type $i32_to_i32) (i32.const 0) (i32.const 2)))
+)
+
+(assert_return (invoke "even" (i32.const 0)) (i32.const 1))
+(assert_return (invoke "even" (i32.const 9)) (i32.const 0))
+(assert_return (invoke "odd" (i32.const 9)) (i32.const 1))
+(assert_return (invoke "odd" (i32.const 100)) (i32.const 0))
+(assert_return (invoke "sum" (i64.const 100)) (i64.const 5050))
+(assert_trap (invoke "bad_type") "undefined element")
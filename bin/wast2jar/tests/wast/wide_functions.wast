@@ -0,0 +1,206 @@
+;; Functions with more parameters than fit in the JVM's limit of 255 slots
+;;
+;; These have 130 `i64` parameters, along with an `f64` and an `i32`, for a total of 263 slots.
+;; Exported functions this wide can't be invoked from the Java harness (since its call sites would
+;; be just as wide), so they are called from within modules instead.
+
+(module $wide
+  (type $wide (func
+    (param
+      i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64
+      i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64
+      i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64
+      i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64
+      i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64
+      i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64
+      i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 f64 i32)
+    (result i64)))
+  (table 2 funcref)
+  (elem (i32.const 0) $wide)
+
+  ;; Combine the first, middle and last parameters (so the packed ones are read too)
+  (func $wide (export "wide") (type $wide)
+    (local $scale i64)
+    (local.set $scale (i64.trunc_f64_s (f64.mul (local.get 130) (f64.const 4))))
+    (i64.add
+      (i64.add (local.get 0) (i64.mul (local.get 64) (local.get $scale)))
+      (i64.add (local.get 129) (i64.extend_i32_s (local.get 131)))))
+
+  (func (export "direct") (result i64)
+    (call $wide
+      (i64.const 0) (i64.const 1) (i64.const 2) (i64.const 3) (i64.const 4) (i64.const 5)
+      (i64.const 6) (i64.const 7) (i64.const 8) (i64.const 9) (i64.const 10) (i64.const 11)
+      (i64.const 12) (i64.const 13) (i64.const 14) (i64.const 15) (i64.const 16) (i64.const 17)
+      (i64.const 18) (i64.const 19) (i64.const 20) (i64.const 21) (i64.const 22) (i64.const 23)
+      (i64.const 24) (i64.const 25) (i64.const 26) (i64.const 27) (i64.const 28) (i64.const 29)
+      (i64.const 30) (i64.const 31) (i64.const 32) (i64.const 33) (i64.const 34) (i64.const 35)
+      (i64.const 36) (i64.const 37) (i64.const 38) (i64.const 39) (i64.const 40) (i64.const 41)
+      (i64.const 42) (i64.const 43) (i64.const 44) (i64.const 45) (i64.const 46) (i64.const 47)
+      (i64.const 48) (i64.const 49) (i64.const 50) (i64.const 51) (i64.const 52) (i64.const 53)
+      (i64.const 54) (i64.const 55) (i64.const 56) (i64.const 57) (i64.const 58) (i64.const 59)
+      (i64.const 60) (i64.const 61) (i64.const 62) (i64.const 63) (i64.const 64) (i64.const 65)
+      (i64.const 66) (i64.const 67) (i64.const 68) (i64.const 69) (i64.const 70) (i64.const 71)
+      (i64.const 72) (i64.const 73) (i64.const 74) (i64.const 75) (i64.const 76) (i64.const 77)
+      (i64.const 78) (i64.const 79) (i64.const 80) (i64.const 81) (i64.const 82) (i64.const 83)
+      (i64.const 84) (i64.const 85) (i64.const 86) (i64.const 87) (i64.const 88) (i64.const 89)
+      (i64.const 90) (i64.const 91) (i64.const 92) (i64.const 93) (i64.const 94) (i64.const 95)
+      (i64.const 96) (i64.const 97) (i64.const 98) (i64.const 99) (i64.const 100) (i64.const 101)
+      (i64.const 102) (i64.const 103) (i64.const 104) (i64.const 105) (i64.const 106) (i64.const 107)
+      (i64.const 108) (i64.const 109) (i64.const 110) (i64.const 111) (i64.const 112) (i64.const 113)
+      (i64.const 114) (i64.const 115) (i64.const 116) (i64.const 117) (i64.const 118) (i64.const 119)
+      (i64.const 120) (i64.const 121) (i64.const 122) (i64.const 123) (i64.const 124) (i64.const 125)
+      (i64.const 126) (i64.const 127) (i64.const 128) (i64.const 129) (f64.const 0.5) (i32.const 1)))
+
+  (func (export "indirect") (result i64)
+    (call_indirect (type $wide)
+      (i64.const 1000) (i64.const 1001) (i64.const 1002) (i64.const 1003) (i64.const 1004) (i64.const 1005)
+      (i64.const 1006) (i64.const 1007) (i64.const 1008) (i64.const 1009) (i64.const 1010) (i64.const 1011)
+      (i64.const 1012) (i64.const 1013) (i64.const 1014) (i64.const 1015) (i64.const 1016) (i64.const 1017)
+      (i64.const 1018) (i64.const 1019) (i64.const 1020) (i64.const 1021) (i64.const 1022) (i64.const 1023)
+      (i64.const 1024) (i64.const 1025) (i64.const 1026) (i64.const 1027) (i64.const 1028) (i64.const 1029)
+      (i64.const 1030) (i64.const 1031) (i64.const 1032) (i64.const 1033) (i64.const 1034) (i64.const 1035)
+      (i64.const 1036) (i64.const 1037) (i64.const 1038) (i64.const 1039) (i64.const 1040) (i64.const 1041)
+      (i64.const 1042) (i64.const 1043) (i64.const 1044) (i64.const 1045) (i64.const 1046) (i64.const 1047)
+      (i64.const 1048) (i64.const 1049) (i64.const 1050) (i64.const 1051) (i64.const 1052) (i64.const 1053)
+      (i64.const 1054) (i64.const 1055) (i64.const 1056) (i64.const 1057) (i64.const 1058) (i64.const 1059)
+      (i64.const 1060) (i64.const 1061) (i64.const 1062) (i64.const 1063) (i64.const 1064) (i64.const 1065)
+      (i64.const 1066) (i64.const 1067) (i64.const 1068) (i64.const 1069) (i64.const 1070) (i64.const 1071)
+      (i64.const 1072) (i64.const 1073) (i64.const 1074) (i64.const 1075) (i64.const 1076) (i64.const 1077)
+      (i64.const 1078) (i64.const 1079) (i64.const 1080) (i64.const 1081) (i64.const 1082) (i64.const 1083)
+      (i64.const 1084) (i64.const 1085) (i64.const 1086) (i64.const 1087) (i64.const 1088) (i64.const 1089)
+      (i64.const 1090) (i64.const 1091) (i64.const 1092) (i64.const 1093) (i64.const 1094) (i64.const 1095)
+      (i64.const 1096) (i64.const 1097) (i64.const 1098) (i64.const 1099) (i64.const 1100) (i64.const 1101)
+      (i64.const 1102) (i64.const 1103) (i64.const 1104) (i64.const 1105) (i64.const 1106) (i64.const 1107)
+      (i64.const 1108) (i64.const 1109) (i64.const 1110) (i64.const 1111) (i64.const 1112) (i64.const 1113)
+      (i64.const 1114) (i64.const 1115) (i64.const 1116) (i64.const 1117) (i64.const 1118) (i64.const 1119)
+      (i64.const 1120) (i64.const 1121) (i64.const 1122) (i64.const 1123) (i64.const 1124) (i64.const 1125)
+      (i64.const 1126) (i64.const 1127) (i64.const 1128) (i64.const 1129) (f64.const 0.5) (i32.const 1)
+      (i32.const 0)))
+
+  (func (export "tail") (result i64)
+    (return_call $wide
+      (i64.const 2000) (i64.const 2001) (i64.const 2002) (i64.const 2003) (i64.const 2004) (i64.const 2005)
+      (i64.const 2006) (i64.const 2007) (i64.const 2008) (i64.const 2009) (i64.const 2010) (i64.const 2011)
+      (i64.const 2012) (i64.const 2013) (i64.const 2014) (i64.const 2015) (i64.const 2016) (i64.const 2017)
+      (i64.const 2018) (i64.const 2019) (i64.const 2020) (i64.const 2021) (i64.const 2022) (i64.const 2023)
+      (i64.const 2024) (i64.const 2025) (i64.const 2026) (i64.const 2027) (i64.const 2028) (i64.const 2029)
+      (i64.const 2030) (i64.const 2031) (i64.const 2032) (i64.const 2033) (i64.const 2034) (i64.const 2035)
+      (i64.const 2036) (i64.const 2037) (i64.const 2038) (i64.const 2039) (i64.const 2040) (i64.const 2041)
+      (i64.const 2042) (i64.const 2043) (i64.const 2044) (i64.const 2045) (i64.const 2046) (i64.const 2047)
+      (i64.const 2048) (i64.const 2049) (i64.const 2050) (i64.const 2051) (i64.const 2052) (i64.const 2053)
+      (i64.const 2054) (i64.const 2055) (i64.const 2056) (i64.const 2057) (i64.const 2058) (i64.const 2059)
+      (i64.const 2060) (i64.const 2061) (i64.const 2062) (i64.const 2063) (i64.const 2064) (i64.const 2065)
+      (i64.const 2066) (i64.const 2067) (i64.const 2068) (i64.const 2069) (i64.const 2070) (i64.const 2071)
+      (i64.const 2072) (i64.const 2073) (i64.const 2074) (i64.const 2075) (i64.const 2076) (i64.const 2077)
+      (i64.const 2078) (i64.const 2079) (i64.const 2080) (i64.const 2081) (i64.const 2082) (i64.const 2083)
+      (i64.const 2084) (i64.const 2085) (i64.const 2086) (i64.const 2087) (i64.const 2088) (i64.const 2089)
+      (i64.const 2090) (i64.const 2091) (i64.const 2092) (i64.const 2093) (i64.const 2094) (i64.const 2095)
+      (i64.const 2096) (i64.const 2097) (i64.const 2098) (i64.const 2099) (i64.const 2100) (i64.const 2101)
+      (i64.const 2102) (i64.const 2103) (i64.const 2104) (i64.const 2105) (i64.const 2106) (i64.const 2107)
+      (i64.const 2108) (i64.const 2109) (i64.const 2110) (i64.const 2111) (i64.const 2112) (i64.const 2113)
+      (i64.const 2114) (i64.const 2115) (i64.const 2116) (i64.const 2117) (i64.const 2118) (i64.const 2119)
+      (i64.const 2120) (i64.const 2121) (i64.const 2122) (i64.const 2123) (i64.const 2124) (i64.const 2125)
+      (i64.const 2126) (i64.const 2127) (i64.const 2128) (i64.const 2129) (f64.const 0.5) (i32.const 1)))
+
+  (func (export "tail_indirect") (result i64)
+    (return_call_indirect (type $wide)
+      (i64.const 3000) (i64.const 3001) (i64.const 3002) (i64.const 3003) (i64.const 3004) (i64.const 3005)
+      (i64.const 3006) (i64.const 3007) (i64.const 3008) (i64.const 3009) (i64.const 3010) (i64.const 3011)
+      (i64.const 3012) (i64.const 3013) (i64.const 3014) (i64.const 3015) (i64.const 3016) (i64.const 3017)
+      (i64.const 3018) (i64.const 3019) (i64.const 3020) (i64.const 3021) (i64.const 3022) (i64.const 3023)
+      (i64.const 3024) (i64.const 3025) (i64.const 3026) (i64.const 3027) (i64.const 3028) (i64.const 3029)
+      (i64.const 3030) (i64.const 3031) (i64.const 3032) (i64.const 3033) (i64.const 3034) (i64.const 3035)
+      (i64.const 3036) (i64.const 3037) (i64.const 3038) (i64.const 3039) (i64.const 3040) (i64.const 3041)
+      (i64.const 3042) (i64.const 3043) (i64.const 3044) (i64.const 3045) (i64.const 3046) (i64.const 3047)
+      (i64.const 3048) (i64.const 3049) (i64.const 3050) (i64.const 3051) (i64.const 3052) (i64.const 3053)
+      (i64.const 3054) (i64.const 3055) (i64.const 3056) (i64.const 3057) (i64.const 3058) (i64.const 3059)
+      (i64.const 3060) (i64.const 3061) (i64.const 3062) (i64.const 3063) (i64.const 3064) (i64.const 3065)
+      (i64.const 3066) (i64.const 3067) (i64.const 3068) (i64.const 3069) (i64.const 3070) (i64.const 3071)
+      (i64.const 3072) (i64.const 3073) (i64.const 3074) (i64.const 3075) (i64.const 3076) (i64.const 3077)
+      (i64.const 3078) (i64.const 3079) (i64.const 3080) (i64.const 3081) (i64.const 3082) (i64.const 3083)
+      (i64.const 3084) (i64.const 3085) (i64.const 3086) (i64.const 3087) (i64.const 3088) (i64.const 3089)
+      (i64.const 3090) (i64.const 3091) (i64.const 3092) (i64.const 3093) (i64.const 3094) (i64.const 3095)
+      (i64.const 3096) (i64.const 3097) (i64.const 3098) (i64.const 3099) (i64.const 3100) (i64.const 3101)
+      (i64.const 3102) (i64.const 3103) (i64.const 3104) (i64.const 3105) (i64.const 3106) (i64.const 3107)
+      (i64.const 3108) (i64.const 3109) (i64.const 3110) (i64.const 3111) (i64.const 3112) (i64.const 3113)
+      (i64.const 3114) (i64.const 3115) (i64.const 3116) (i64.const 3117) (i64.const 3118) (i64.const 3119)
+      (i64.const 3120) (i64.const 3121) (i64.const 3122) (i64.const 3123) (i64.const 3124) (i64.const 3125)
+      (i64.const 3126) (i64.const 3127) (i64.const 3128) (i64.const 3129) (f64.const 0.5) (i32.const 1)
+      (i32.const 0)))
+
+  (func (export "via_table_set") (result i64)
+    (table.set (i32.const 1) (ref.func $wide))
+    (call_indirect (type $wide)
+      (i64.const 4000) (i64.const 4001) (i64.const 4002) (i64.const 4003) (i64.const 4004) (i64.const 4005)
+      (i64.const 4006) (i64.const 4007) (i64.const 4008) (i64.const 4009) (i64.const 4010) (i64.const 4011)
+      (i64.const 4012) (i64.const 4013) (i64.const 4014) (i64.const 4015) (i64.const 4016) (i64.const 4017)
+      (i64.const 4018) (i64.const 4019) (i64.const 4020) (i64.const 4021) (i64.const 4022) (i64.const 4023)
+      (i64.const 4024) (i64.const 4025) (i64.const 4026) (i64.const 4027) (i64.const 4028) (i64.const 4029)
+      (i64.const 4030) (i64.const 4031) (i64.const 4032) (i64.const 4033) (i64.const 4034) (i64.const 4035)
+      (i64.const 4036) (i64.const 4037) (i64.const 4038) (i64.const 4039) (i64.const 4040) (i64.const 4041)
+      (i64.const 4042) (i64.const 4043) (i64.const 4044) (i64.const 4045) (i64.const 4046) (i64.const 4047)
+      (i64.const 4048) (i64.const 4049) (i64.const 4050) (i64.const 4051) (i64.const 4052) (i64.const 4053)
+      (i64.const 4054) (i64.const 4055) (i64.const 4056) (i64.const 4057) (i64.const 4058) (i64.const 4059)
+      (i64.const 4060) (i64.const 4061) (i64.const 4062) (i64.const 4063) (i64.const 4064) (i64.const 4065)
+      (i64.const 4066) (i64.const 4067) (i64.const 4068) (i64.const 4069) (i64.const 4070) (i64.const 4071)
+      (i64.const 4072) (i64.const 4073) (i64.const 4074) (i64.const 4075) (i64.const 4076) (i64.const 4077)
+      (i64.const 4078) (i64.const 4079) (i64.const 4080) (i64.const 4081) (i64.const 4082) (i64.const 4083)
+      (i64.const 4084) (i64.const 4085) (i64.const 4086) (i64.const 4087) (i64.const 4088) (i64.const 4089)
+      (i64.const 4090) (i64.const 4091) (i64.const 4092) (i64.const 4093) (i64.const 4094) (i64.const 4095)
+      (i64.const 4096) (i64.const 4097) (i64.const 4098) (i64.const 4099) (i64.const 4100) (i64.const 4101)
+      (i64.const 4102) (i64.const 4103) (i64.const 4104) (i64.const 4105) (i64.const 4106) (i64.const 4107)
+      (i64.const 4108) (i64.const 4109) (i64.const 4110) (i64.const 4111) (i64.const 4112) (i64.const 4113)
+      (i64.const 4114) (i64.const 4115) (i64.const 4116) (i64.const 4117) (i64.const 4118) (i64.const 4119)
+      (i64.const 4120) (i64.const 4121) (i64.const 4122) (i64.const 4123) (i64.const 4124) (i64.const 4125)
+      (i64.const 4126) (i64.const 4127) (i64.const 4128) (i64.const 4129) (f64.const 0.5) (i32.const 1)
+      (i32.const 1)))
+)
+
+(assert_return (invoke "direct") (i64.const 258))
+(assert_return (invoke "indirect") (i64.const 4258))
+(assert_return (invoke "tail") (i64.const 8258))
+(assert_return (invoke "tail_indirect") (i64.const 12258))
+(assert_return (invoke "via_table_set") (i64.const 16258))
+
+;; Wide functions can be imported from other modules
+(register "wide" $wide)
+
+(module
+  (type $wide (func
+    (param
+      i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64
+      i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64
+      i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64
+      i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64
+      i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64
+      i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64
+      i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 f64 i32)
+    (result i64)))
+  (import "wide" "wide" (func $wide (type $wide)))
+  (func (export "imported") (result i64)
+    (call $wide
+      (i64.const 6000) (i64.const 6001) (i64.const 6002) (i64.const 6003) (i64.const 6004) (i64.const 6005)
+      (i64.const 6006) (i64.const 6007) (i64.const 6008) (i64.const 6009) (i64.const 6010) (i64.const 6011)
+      (i64.const 6012) (i64.const 6013) (i64.const 6014) (i64.const 6015) (i64.const 6016) (i64.const 6017)
+      (i64.const 6018) (i64.const 6019) (i64.const 6020) (i64.const 6021) (i64.const 6022) (i64.const 6023)
+      (i64.const 6024) (i64.const 6025) (i64.const 6026) (i64.const 6027) (i64.const 6028) (i64.const 6029)
+      (i64.const 6030) (i64.const 6031) (i64.const 6032) (i64.const 6033) (i64.const 6034) (i64.const 6035)
+      (i64.const 6036) (i64.const 6037) (i64.const 6038) (i64.const 6039) (i64.const 6040) (i64.const 6041)
+      (i64.const 6042) (i64.const 6043) (i64.const 6044) (i64.const 6045) (i64.const 6046) (i64.const 6047)
+      (i64.const 6048) (i64.const 6049) (i64.const 6050) (i64.const 6051) (i64.const 6052) (i64.const 6053)
+      (i64.const 6054) (i64.const 6055) (i64.const 6056) (i64.const 6057) (i64.const 6058) (i64.const 6059)
+      (i64.const 6060) (i64.const 6061) (i64.const 6062) (i64.const 6063) (i64.const 6064) (i64.const 6065)
+      (i64.const 6066) (i64.const 6067) (i64.const 6068) (i64.const 6069) (i64.const 6070) (i64.const 6071)
+      (i64.const 6072) (i64.const 6073) (i64.const 6074) (i64.const 6075) (i64.const 6076) (i64.const 6077)
+      (i64.const 6078) (i64.const 6079) (i64.const 6080) (i64.const 6081) (i64.const 6082) (i64.const 6083)
+      (i64.const 6084) (i64.const 6085) (i64.const 6086) (i64.const 6087) (i64.const 6088) (i64.const 6089)
+      (i64.const 6090) (i64.const 6091) (i64.const 6092) (i64.const 6093) (i64.const 6094) (i64.const 6095)
+      (i64.const 6096) (i64.const 6097) (i64.const 6098) (i64.const 6099) (i64.const 6100) (i64.const 6101)
+      (i64.const 6102) (i64.const 6103) (i64.const 6104) (i64.const 6105) (i64.const 6106) (i64.const 6107)
+      (i64.const 6108) (i64.const 6109) (i64.const 6110) (i64.const 6111) (i64.const 6112) (i64.const 6113)
+      (i64.const 6114) (i64.const 6115) (i64.const 6116) (i64.const 6117) (i64.const 6118) (i64.const 6119)
+      (i64.const 6120) (i64.const 6121) (i64.const 6122) (i64.const 6123) (i64.const 6124) (i64.const 6125)
+      (i64.const 6126) (i64.const 6127) (i64.const 6128) (i64.const 6129) (f64.const 0.5) (i32.const 1)))
+)
+
+(assert_return (invoke "imported") (i64.const 24258))
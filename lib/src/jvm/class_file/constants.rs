@@ -30,6 +30,9 @@ pub struct ConstantsPool<'g> {
     method_handles: HashMap<(HandleKind, ConstantIndex), ConstantIndex>,
     method_types: HashMap<Utf8ConstantIndex, ConstantIndex>,
     invoke_dynamics: HashMap<(u16, NameAndTypeConstantIndex), InvokeDynamicConstantIndex>,
+
+    /// Number of bytes the constants take up once serialized
+    serialized_len: usize,
 }
 
 impl<'g> ConstantsPool<'g> {
@@ -50,9 +53,24 @@ impl<'g> ConstantsPool<'g> {
             method_handles: HashMap::new(),
             method_types: HashMap::new(),
             invoke_dynamics: HashMap::new(),
+            serialized_len: 0,
         }
     }
 
+    /// Number of entries in the pool (8-byte constants count for two entries)
+    pub fn len(&self) -> usize {
+        self.constants.offset_len().0 - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of bytes the constants take up in the class file
+    pub fn serialized_len(&self) -> usize {
+        self.serialized_len
+    }
+
     /// List out all of the classes referenced in the constant pool
     pub fn referenced_classes(&self) -> impl Iterator<Item = ClassId<'g>> + '_ {
         self.classes
@@ -79,6 +97,7 @@ impl<'g> ConstantsPool<'g> {
             return Err(ConstantPoolOverflow { constant, offset });
         }
 
+        self.serialized_len += constant.serialized_len();
         self.constants.push(constant);
         Ok(ConstantIndex(offset))
     }
@@ -254,6 +273,24 @@ pub enum Constant {
     },
 }
 
+impl Constant {
+    /// Number of bytes the constant takes up once serialized
+    fn serialized_len(&self) -> usize {
+        match self {
+            Constant::Utf8(string) => 3 + encode_modified_utf8(string).len(),
+            Constant::Class(_) | Constant::String(_) | Constant::MethodType { .. } => 3,
+            Constant::MethodHandle { .. } => 4,
+            Constant::FieldRef(..)
+            | Constant::MethodRef { .. }
+            | Constant::Integer(_)
+            | Constant::Float(_)
+            | Constant::NameAndType { .. }
+            | Constant::InvokeDynamic { .. } => 5,
+            Constant::Long(_) | Constant::Double(_) => 9,
+        }
+    }
+}
+
 impl Serialize for Constant {
     fn serialize<W: WriteBytesExt>(&self, writer: &mut W) -> std::io::Result<()> {
         match self {
@@ -878,6 +915,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn pool_size() {
+        let mut pool = ConstantsPool::new();
+        assert!(pool.is_empty());
+        ConstantData::Long(123).constant_index(&mut pool).unwrap();
+        ConstantData::String("h\u{e9}llo \u{0}".into())
+            .constant_index(&mut pool)
+            .unwrap();
+        ConstantData::Integer(123)
+            .constant_index(&mut pool)
+            .unwrap();
+        assert_eq!(pool.len(), 5);
+
+        // Sizes are tracked as the constants get added
+        let serialized_len = pool.serialized_len();
+        let mut serialized = vec![];
+        for (_, _, constant) in pool.into_offset_vec() {
+            constant.serialize(&mut serialized).unwrap();
+        }
+        assert_eq!(serialized_len, serialized.len());
+    }
+
     #[test]
     fn classgraph_id_constants() {
         let class_graph_arenas = ClassGraphArenas::new();
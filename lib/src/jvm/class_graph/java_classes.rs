@@ -7,6 +7,7 @@ use elsa::FrozenVec;
 /// Classes inside `java.*`
 pub struct JavaClasses<'g> {
     pub lang: LangClasses<'g>,
+    pub io: IoClasses<'g>,
     pub nio: NioClasses<'g>,
     pub util: UtilClasses<'g>,
 }
@@ -17,6 +18,7 @@ pub struct LangClasses<'g> {
     pub char_sequence: ClassId<'g>,
     pub string: ClassId<'g>,
     pub class: ClassId<'g>,
+    pub class_loader: ClassId<'g>,
     pub number: ClassId<'g>,
     pub integer: ClassId<'g>,
     pub float: ClassId<'g>,
@@ -26,7 +28,9 @@ pub struct LangClasses<'g> {
     pub boolean: ClassId<'g>,
     pub math: ClassId<'g>,
     pub system: ClassId<'g>,
+    pub thread: ClassId<'g>,
     pub invoke: InvokeClasses<'g>,
+    pub r#ref: RefClasses<'g>,
     pub throwable: ClassId<'g>,
     pub error: ClassId<'g>,
     pub assertion_error: ClassId<'g>,
@@ -34,6 +38,7 @@ pub struct LangClasses<'g> {
     pub runtime_exception: ClassId<'g>,
     pub arithmetic_exception: ClassId<'g>,
     pub illegal_argument_exception: ClassId<'g>,
+    pub index_out_of_bounds_exception: ClassId<'g>,
 }
 
 /// Classes inside `java.lang.invoke.*`
@@ -45,6 +50,18 @@ pub struct InvokeClasses<'g> {
     pub call_site: ClassId<'g>,
     pub constant_call_site: ClassId<'g>,
     pub mutable_call_site: ClassId<'g>,
+    pub var_handle: ClassId<'g>,
+    pub var_handle_access_mode: ClassId<'g>,
+}
+
+/// Classes inside `java.lang.ref.*`
+pub struct RefClasses<'g> {
+    pub weak_reference: ClassId<'g>,
+}
+
+/// Classes inside `java.io.*`
+pub struct IoClasses<'g> {
+    pub input_stream: ClassId<'g>,
 }
 
 /// Classes inside `java.nio.*`
@@ -57,17 +74,39 @@ pub struct NioClasses<'g> {
 /// Classes inside `java.util.*`
 pub struct UtilClasses<'g> {
     pub arrays: ClassId<'g>,
+    pub collections: ClassId<'g>,
     pub map: ClassId<'g>,
     pub hash_map: ClassId<'g>,
+    pub weak_hash_map: ClassId<'g>,
+    pub concurrent: ConcurrentClasses<'g>,
+    pub zip: ZipClasses<'g>,
+}
+
+/// Classes inside `java.util.concurrent.*`
+pub struct ConcurrentClasses<'g> {
+    pub concurrent_hash_map: ClassId<'g>,
+    pub concurrent_linked_queue: ClassId<'g>,
+    pub lock_support: ClassId<'g>,
+}
+
+/// Classes inside `java.util.zip.*`
+pub struct ZipClasses<'g> {
+    pub inflater_input_stream: ClassId<'g>,
 }
 
 impl<'g> JavaClasses<'g> {
     pub fn add_to_graph(class_graph: &ClassGraph<'g>) -> JavaClasses<'g> {
         let lang = LangClasses::add_to_graph(class_graph);
+        let io = IoClasses::add_to_graph(class_graph, lang.object);
         let nio = NioClasses::add_to_graph(class_graph, lang.object);
-        let util = UtilClasses::add_to_graph(class_graph, lang.object);
+        let util = UtilClasses::add_to_graph(class_graph, lang.object, io.input_stream);
 
-        JavaClasses { lang, nio, util }
+        JavaClasses {
+            lang,
+            io,
+            nio,
+            util,
+        }
     }
 }
 
@@ -103,6 +142,12 @@ impl<'g> LangClasses<'g> {
             ClassAccessFlags::SUPER | ClassAccessFlags::PUBLIC | ClassAccessFlags::FINAL,
             None,
         ));
+        let class_loader = class_graph.add_class(ClassData::new(
+            BinaryName::CLASSLOADER,
+            object,
+            ClassAccessFlags::SUPER | ClassAccessFlags::PUBLIC | ClassAccessFlags::ABSTRACT,
+            None,
+        ));
         let number = class_graph.add_class(ClassData::new(
             BinaryName::NUMBER,
             object,
@@ -157,7 +202,14 @@ impl<'g> LangClasses<'g> {
             ClassAccessFlags::SUPER | ClassAccessFlags::PUBLIC | ClassAccessFlags::FINAL,
             None,
         ));
+        let thread = class_graph.add_class(ClassData::new(
+            BinaryName::THREAD,
+            object,
+            ClassAccessFlags::SUPER | ClassAccessFlags::PUBLIC,
+            None,
+        ));
         let invoke = InvokeClasses::add_to_graph(class_graph, object);
+        let r#ref = RefClasses::add_to_graph(class_graph, object);
         let throwable = class_graph.add_class(ClassData::new(
             BinaryName::THROWABLE,
             object,
@@ -200,6 +252,12 @@ impl<'g> LangClasses<'g> {
             ClassAccessFlags::SUPER | ClassAccessFlags::PUBLIC,
             None,
         ));
+        let index_out_of_bounds_exception = class_graph.add_class(ClassData::new(
+            BinaryName::INDEXOUTOFBOUNDSEXCEPTION,
+            runtime_exception,
+            ClassAccessFlags::SUPER | ClassAccessFlags::PUBLIC,
+            None,
+        ));
 
         string.interfaces.push(char_sequence);
 
@@ -208,6 +266,7 @@ impl<'g> LangClasses<'g> {
             char_sequence,
             string,
             class,
+            class_loader,
             number,
             integer,
             float,
@@ -217,7 +276,9 @@ impl<'g> LangClasses<'g> {
             boolean,
             math,
             system,
+            thread,
             invoke,
+            r#ref,
             throwable,
             error,
             assertion_error,
@@ -225,6 +286,7 @@ impl<'g> LangClasses<'g> {
             runtime_exception,
             arithmetic_exception,
             illegal_argument_exception,
+            index_out_of_bounds_exception,
         }
     }
 }
@@ -277,6 +339,28 @@ impl<'g> InvokeClasses<'g> {
             ClassAccessFlags::SUPER | ClassAccessFlags::PUBLIC,
             None,
         ));
+        let var_handle = class_graph.add_class(ClassData::new(
+            BinaryName::VARHANDLE,
+            object,
+            ClassAccessFlags::SUPER | ClassAccessFlags::PUBLIC | ClassAccessFlags::ABSTRACT,
+            None,
+        ));
+        let var_handle_access_mode = class_graph.add_class(ClassData::new(
+            BinaryName::VARHANDLE_ACCESSMODE,
+            object,
+            ClassAccessFlags::SUPER
+                | ClassAccessFlags::PUBLIC
+                | ClassAccessFlags::FINAL
+                | ClassAccessFlags::ENUM,
+            Some(NestedClassData {
+                access_flags: InnerClassAccessFlags::PUBLIC
+                    | InnerClassAccessFlags::STATIC
+                    | InnerClassAccessFlags::FINAL
+                    | InnerClassAccessFlags::ENUM,
+                simple_name: Some(UnqualifiedName::ACCESSMODE),
+                enclosing_class: var_handle,
+            }),
+        ));
 
         InvokeClasses {
             method_type,
@@ -286,10 +370,39 @@ impl<'g> InvokeClasses<'g> {
             call_site,
             constant_call_site,
             mutable_call_site,
+            var_handle,
+            var_handle_access_mode,
         }
     }
 }
 
+impl<'g> RefClasses<'g> {
+    pub fn add_to_graph(class_graph: &ClassGraph<'g>, object: ClassId<'g>) -> RefClasses<'g> {
+        // Actually extends `java.lang.ref.Reference`
+        let weak_reference = class_graph.add_class(ClassData::new(
+            BinaryName::WEAKREFERENCE,
+            object,
+            ClassAccessFlags::SUPER | ClassAccessFlags::PUBLIC,
+            None,
+        ));
+
+        RefClasses { weak_reference }
+    }
+}
+
+impl<'g> IoClasses<'g> {
+    pub fn add_to_graph(class_graph: &ClassGraph<'g>, object: ClassId<'g>) -> IoClasses<'g> {
+        let input_stream = class_graph.add_class(ClassData::new(
+            BinaryName::INPUTSTREAM,
+            object,
+            ClassAccessFlags::SUPER | ClassAccessFlags::PUBLIC | ClassAccessFlags::ABSTRACT,
+            None,
+        ));
+
+        IoClasses { input_stream }
+    }
+}
+
 impl<'g> NioClasses<'g> {
     pub fn add_to_graph(class_graph: &ClassGraph<'g>, object: ClassId<'g>) -> NioClasses<'g> {
         let byte_order = class_graph.add_class(ClassData::new(
@@ -320,13 +433,23 @@ impl<'g> NioClasses<'g> {
 }
 
 impl<'g> UtilClasses<'g> {
-    pub fn add_to_graph(class_graph: &ClassGraph<'g>, object: ClassId<'g>) -> UtilClasses<'g> {
+    pub fn add_to_graph(
+        class_graph: &ClassGraph<'g>,
+        object: ClassId<'g>,
+        input_stream: ClassId<'g>,
+    ) -> UtilClasses<'g> {
         let arrays = class_graph.add_class(ClassData::new(
             BinaryName::ARRAYS,
             object,
             ClassAccessFlags::SUPER | ClassAccessFlags::PUBLIC,
             None,
         ));
+        let collections = class_graph.add_class(ClassData::new(
+            BinaryName::COLLECTIONS,
+            object,
+            ClassAccessFlags::SUPER | ClassAccessFlags::PUBLIC,
+            None,
+        ));
         let map = class_graph.add_class(ClassData::new(
             BinaryName::MAP,
             object,
@@ -340,12 +463,77 @@ impl<'g> UtilClasses<'g> {
             None,
         ));
 
+        let weak_hash_map = class_graph.add_class(ClassData::new(
+            BinaryName::WEAKHASHMAP,
+            object,
+            ClassAccessFlags::SUPER | ClassAccessFlags::PUBLIC,
+            None,
+        ));
+
         hash_map.interfaces.push(map);
+        weak_hash_map.interfaces.push(map);
+        let concurrent = ConcurrentClasses::add_to_graph(class_graph, object, map);
+        let zip = ZipClasses::add_to_graph(class_graph, input_stream);
 
         UtilClasses {
             arrays,
+            collections,
             map,
             hash_map,
+            weak_hash_map,
+            concurrent,
+            zip,
+        }
+    }
+}
+
+impl<'g> ConcurrentClasses<'g> {
+    pub fn add_to_graph(
+        class_graph: &ClassGraph<'g>,
+        object: ClassId<'g>,
+        map: ClassId<'g>,
+    ) -> ConcurrentClasses<'g> {
+        let concurrent_hash_map = class_graph.add_class(ClassData::new(
+            BinaryName::CONCURRENTHASHMAP,
+            object,
+            ClassAccessFlags::SUPER | ClassAccessFlags::PUBLIC,
+            None,
+        ));
+        let concurrent_linked_queue = class_graph.add_class(ClassData::new(
+            BinaryName::CONCURRENTLINKEDQUEUE,
+            object,
+            ClassAccessFlags::SUPER | ClassAccessFlags::PUBLIC,
+            None,
+        ));
+        let lock_support = class_graph.add_class(ClassData::new(
+            BinaryName::LOCKSUPPORT,
+            object,
+            ClassAccessFlags::SUPER | ClassAccessFlags::PUBLIC,
+            None,
+        ));
+
+        concurrent_hash_map.interfaces.push(map);
+
+        ConcurrentClasses {
+            concurrent_hash_map,
+            concurrent_linked_queue,
+            lock_support,
+        }
+    }
+}
+
+impl<'g> ZipClasses<'g> {
+    pub fn add_to_graph(class_graph: &ClassGraph<'g>, input_stream: ClassId<'g>) -> ZipClasses<'g> {
+        // Actually extends `java.io.FilterInputStream`, which extends `java.io.InputStream`
+        let inflater_input_stream = class_graph.add_class(ClassData::new(
+            BinaryName::INFLATERINPUTSTREAM,
+            input_stream,
+            ClassAccessFlags::SUPER | ClassAccessFlags::PUBLIC,
+            None,
+        ));
+
+        ZipClasses {
+            inflater_input_stream,
         }
     }
 }
@@ -8,6 +8,7 @@ use super::java_classes::JavaClasses;
 /// Members of classes inside `java.*`
 pub struct JavaMembers<'g> {
     pub lang: LangMembers<'g>,
+    pub io: IoMembers<'g>,
     pub nio: NioMembers<'g>,
     pub util: UtilMembers<'g>,
 }
@@ -17,6 +18,7 @@ pub struct LangMembers<'g> {
     pub object: ObjectMembers<'g>,
     pub char_sequence: CharSequenceMembers<'g>,
     pub class: ClassMembers<'g>,
+    pub class_loader: ClassLoaderMembers<'g>,
     pub string: StringMembers<'g>,
     pub number: NumberMembers<'g>,
     pub integer: IntegerMembers<'g>,
@@ -27,7 +29,9 @@ pub struct LangMembers<'g> {
     pub boolean: BooleanMembers<'g>,
     pub math: MathMembers<'g>,
     pub system: SystemMembers<'g>,
+    pub thread: ThreadMembers<'g>,
     pub invoke: InvokeMembers<'g>,
+    pub r#ref: RefMembers<'g>,
     pub throwable: ThrowableMembers<'g>,
     pub error: ErrorMembers<'g>,
     pub assertion_error: AssertionErrorMembers<'g>,
@@ -35,6 +39,7 @@ pub struct LangMembers<'g> {
     pub runtime_exception: RuntimeExceptionMembers<'g>,
     pub arithmetic_exception: ArithmeticExceptionMembers<'g>,
     pub illegal_argument_exception: IllegalArgumentExceptionMembers<'g>,
+    pub index_out_of_bounds_exception: IndexOutOfBoundsExceptionMembers<'g>,
 }
 
 /// Members of `java.lang.Object`
@@ -53,6 +58,12 @@ pub struct CharSequenceMembers<'g> {
 /// Members of `java.lang.Class`
 pub struct ClassMembers<'g> {
     pub is_assignable_from: MethodId<'g>,
+    pub get_class_loader: MethodId<'g>,
+}
+
+/// Members of `java.lang.ClassLoader`
+pub struct ClassLoaderMembers<'g> {
+    pub get_resource_as_stream: MethodId<'g>,
 }
 
 /// Members of `java.lang.String`
@@ -162,6 +173,12 @@ pub struct MathMembers<'g> {
 /// Members of `java.lang.System`
 pub struct SystemMembers<'g> {
     pub arraycopy: MethodId<'g>,
+    pub nano_time: MethodId<'g>,
+}
+
+/// Members of `java.lang.Thread`
+pub struct ThreadMembers<'g> {
+    pub current_thread: MethodId<'g>,
 }
 
 /// Members of classes inside `java.lang.invoke`
@@ -172,6 +189,19 @@ pub struct InvokeMembers<'g> {
     pub call_site: CallSiteMembers<'g>,
     pub constant_call_site: ConstantCallSiteMembers<'g>,
     pub mutable_call_site: MutableCallSiteMembers<'g>,
+    pub var_handle: VarHandleMembers<'g>,
+    pub var_handle_access_mode: VarHandleAccessModeMembers<'g>,
+}
+
+/// Members of classes inside `java.lang.ref`
+pub struct RefMembers<'g> {
+    pub weak_reference: WeakReferenceMembers<'g>,
+}
+
+/// Members of `java.lang.ref.WeakReference`
+pub struct WeakReferenceMembers<'g> {
+    pub init: MethodId<'g>,
+    pub get: MethodId<'g>,
 }
 
 /// Members of `java.lang.invoke.MethodType`
@@ -188,8 +218,10 @@ pub struct MethodTypeMembers<'g> {
 pub struct MethodHandleMembers<'g> {
     pub r#type: MethodId<'g>,
     pub as_type: MethodId<'g>,
+    pub as_collector: MethodId<'g>,
     pub bind_to: MethodId<'g>,
     pub change_return_type: MethodId<'g>,
+    pub invoke_with_arguments: MethodId<'g>,
 }
 
 /// Members of `java.lang.invoke.MethodHandles`
@@ -208,6 +240,9 @@ pub struct MethodHandlesMembers<'g> {
     pub empty: MethodId<'g>,
     pub identity: MethodId<'g>,
     pub constant: MethodId<'g>,
+    pub byte_buffer_view_var_handle: MethodId<'g>,
+    pub byte_array_view_var_handle: MethodId<'g>,
+    pub var_handle_exact_invoker: MethodId<'g>,
 }
 
 /// Members of `java.lang.invoke.CallSite`
@@ -229,6 +264,16 @@ pub struct MutableCallSiteMembers<'g> {
     pub init: MethodId<'g>,
 }
 
+/// Members of `java.lang.invoke.VarHandle`
+pub struct VarHandleMembers<'g> {
+    pub full_fence: MethodId<'g>,
+}
+
+/// Members of `java.lang.invoke.VarHandle.AccessMode`
+pub struct VarHandleAccessModeMembers<'g> {
+    pub value_from_method_name: MethodId<'g>,
+}
+
 /// Members of `java.lang.Throwable`
 pub struct ThrowableMembers<'g> {
     pub init: MethodId<'g>,
@@ -252,6 +297,9 @@ pub struct ExceptionMembers<'g> {
 /// Members of `java.lang.RuntimeException`
 pub struct RuntimeExceptionMembers<'g> {
     pub init: MethodId<'g>,
+
+    /// Constructor which can disable suppression and writable stack traces
+    pub init_with_flags: MethodId<'g>,
 }
 
 /// Members of `java.lang.ArithmeticException`
@@ -264,6 +312,22 @@ pub struct IllegalArgumentExceptionMembers<'g> {
     pub init: MethodId<'g>,
 }
 
+/// Members of `java.lang.IndexOutOfBoundsException`
+pub struct IndexOutOfBoundsExceptionMembers<'g> {
+    pub init: MethodId<'g>,
+}
+
+/// Members of classes inside `java.io.*`
+pub struct IoMembers<'g> {
+    pub input_stream: InputStreamMembers<'g>,
+}
+
+/// Members of `java.io.InputStream`
+pub struct InputStreamMembers<'g> {
+    pub read_all_bytes: MethodId<'g>,
+    pub close: MethodId<'g>,
+}
+
 /// Members of classes inside `java.nio.*`
 pub struct NioMembers<'g> {
     pub buffer: BufferMembers<'g>,
@@ -275,6 +339,8 @@ pub struct NioMembers<'g> {
 pub struct BufferMembers<'g> {
     pub position: MethodId<'g>,
     pub capacity: MethodId<'g>,
+    pub limit: MethodId<'g>,
+    pub set_limit: MethodId<'g>,
 }
 
 /// Members of `java.nio.ByteBuffer`
@@ -300,7 +366,6 @@ pub struct ByteBufferMembers<'g> {
     pub put_bytebuffer: MethodId<'g>,
     pub put_bytebuffer_relative: MethodId<'g>,
     pub put_bytearray_relative: MethodId<'g>,
-    pub put_byte_relative: MethodId<'g>,
     pub position: MethodId<'g>,
     pub order: MethodId<'g>,
     pub as_read_only_buffer: MethodId<'g>,
@@ -315,20 +380,31 @@ pub struct ByteOrderMembers<'g> {
 /// Members of classes inside `java.util.*`
 pub struct UtilMembers<'g> {
     pub arrays: ArraysMembers<'g>,
+    pub collections: CollectionsMembers<'g>,
     pub map: MapMembers<'g>,
     pub hash_map: HashMapMembers<'g>,
+    pub weak_hash_map: WeakHashMapMembers<'g>,
+    pub concurrent: ConcurrentMembers<'g>,
+    pub zip: ZipMembers<'g>,
 }
 
 /// Members of `java.util.Arrays`
 pub struct ArraysMembers<'g> {
     pub copy_of: MethodId<'g>,
     pub fill: MethodId<'g>,
+    pub fill_bytes: MethodId<'g>,
+}
+
+/// Members of `java.util.Collections`
+pub struct CollectionsMembers<'g> {
+    pub synchronized_map: MethodId<'g>,
 }
 
 /// Members of `java.util.Map`
 pub struct MapMembers<'g> {
     pub get: MethodId<'g>,
     pub put: MethodId<'g>,
+    pub put_if_absent: MethodId<'g>,
 }
 
 /// Members of `java.util.HashMap`
@@ -336,15 +412,64 @@ pub struct HashMapMembers<'g> {
     pub init: MethodId<'g>,
 }
 
+/// Members of `java.util.WeakHashMap`
+pub struct WeakHashMapMembers<'g> {
+    pub init: MethodId<'g>,
+}
+
+/// Members of classes inside `java.util.concurrent.*`
+pub struct ConcurrentMembers<'g> {
+    pub concurrent_hash_map: ConcurrentHashMapMembers<'g>,
+    pub concurrent_linked_queue: ConcurrentLinkedQueueMembers<'g>,
+    pub lock_support: LockSupportMembers<'g>,
+}
+
+/// Members of `java.util.concurrent.ConcurrentHashMap`
+pub struct ConcurrentHashMapMembers<'g> {
+    pub init: MethodId<'g>,
+}
+
+/// Members of `java.util.concurrent.ConcurrentLinkedQueue`
+pub struct ConcurrentLinkedQueueMembers<'g> {
+    pub init: MethodId<'g>,
+    pub add: MethodId<'g>,
+    pub contains: MethodId<'g>,
+    pub remove: MethodId<'g>,
+    pub poll: MethodId<'g>,
+}
+
+/// Members of classes inside `java.util.zip.*`
+pub struct ZipMembers<'g> {
+    pub inflater_input_stream: InflaterInputStreamMembers<'g>,
+}
+
+/// Members of `java.util.zip.InflaterInputStream`
+pub struct InflaterInputStreamMembers<'g> {
+    pub init: MethodId<'g>,
+}
+
+/// Members of `java.util.concurrent.locks.LockSupport`
+pub struct LockSupportMembers<'g> {
+    pub park: MethodId<'g>,
+    pub park_nanos: MethodId<'g>,
+    pub unpark: MethodId<'g>,
+}
+
 impl<'g> JavaMembers<'g> {
     pub fn add_to_graph(
         class_graph: &ClassGraph<'g>,
         classes: &JavaClasses<'g>,
     ) -> JavaMembers<'g> {
         let lang = LangMembers::add_to_graph(class_graph, classes);
+        let io = IoMembers::add_to_graph(class_graph, classes);
         let nio = NioMembers::add_to_graph(class_graph, classes);
         let util = UtilMembers::add_to_graph(class_graph, classes);
-        JavaMembers { lang, nio, util }
+        JavaMembers {
+            lang,
+            io,
+            nio,
+            util,
+        }
     }
 }
 
@@ -356,6 +481,7 @@ impl<'g> LangMembers<'g> {
         let object = ObjectMembers::add_to_graph(class_graph, classes);
         let char_sequence = CharSequenceMembers::add_to_graph(class_graph, classes);
         let class = ClassMembers::add_to_graph(class_graph, classes);
+        let class_loader = ClassLoaderMembers::add_to_graph(class_graph, classes);
         let string = StringMembers::add_to_graph(class_graph, classes);
         let number = NumberMembers::add_to_graph(class_graph, classes);
         let integer = IntegerMembers::add_to_graph(class_graph, classes);
@@ -366,7 +492,9 @@ impl<'g> LangMembers<'g> {
         let boolean = BooleanMembers::add_to_graph(class_graph, classes);
         let math = MathMembers::add_to_graph(class_graph, classes);
         let system = SystemMembers::add_to_graph(class_graph, classes);
+        let thread = ThreadMembers::add_to_graph(class_graph, classes);
         let invoke = InvokeMembers::add_to_graph(class_graph, classes);
+        let r#ref = RefMembers::add_to_graph(class_graph, classes);
         let throwable = ThrowableMembers::add_to_graph(class_graph, classes);
         let error = ErrorMembers::add_to_graph(class_graph, classes);
         let assertion_error = AssertionErrorMembers::add_to_graph(class_graph, classes);
@@ -375,10 +503,13 @@ impl<'g> LangMembers<'g> {
         let arithmetic_exception = ArithmeticExceptionMembers::add_to_graph(class_graph, classes);
         let illegal_argument_exception =
             IllegalArgumentExceptionMembers::add_to_graph(class_graph, classes);
+        let index_out_of_bounds_exception =
+            IndexOutOfBoundsExceptionMembers::add_to_graph(class_graph, classes);
         LangMembers {
             object,
             char_sequence,
             class,
+            class_loader,
             string,
             number,
             integer,
@@ -389,7 +520,9 @@ impl<'g> LangMembers<'g> {
             boolean,
             math,
             system,
+            thread,
             invoke,
+            r#ref,
             throwable,
             error,
             assertion_error,
@@ -397,6 +530,7 @@ impl<'g> LangMembers<'g> {
             runtime_exception,
             arithmetic_exception,
             illegal_argument_exception,
+            index_out_of_bounds_exception,
         }
     }
 }
@@ -486,7 +620,40 @@ impl<'g> ClassMembers<'g> {
                 return_type: Some(FieldType::boolean()),
             },
         });
-        ClassMembers { is_assignable_from }
+        let get_class_loader = class_graph.add_method(MethodData {
+            class,
+            name: UnqualifiedName::GETCLASSLOADER,
+            access_flags: MethodAccessFlags::PUBLIC,
+            descriptor: MethodDescriptor {
+                parameters: vec![],
+                return_type: Some(FieldType::object(classes.lang.class_loader)),
+            },
+        });
+        ClassMembers {
+            is_assignable_from,
+            get_class_loader,
+        }
+    }
+}
+
+impl<'g> ClassLoaderMembers<'g> {
+    pub fn add_to_graph(
+        class_graph: &ClassGraph<'g>,
+        classes: &JavaClasses<'g>,
+    ) -> ClassLoaderMembers<'g> {
+        let class = classes.lang.class_loader;
+        let get_resource_as_stream = class_graph.add_method(MethodData {
+            class,
+            name: UnqualifiedName::GETRESOURCEASSTREAM,
+            access_flags: MethodAccessFlags::PUBLIC,
+            descriptor: MethodDescriptor {
+                parameters: vec![FieldType::object(classes.lang.string)],
+                return_type: Some(FieldType::object(classes.io.input_stream)),
+            },
+        });
+        ClassLoaderMembers {
+            get_resource_as_stream,
+        }
     }
 }
 
@@ -1051,7 +1218,38 @@ impl<'g> SystemMembers<'g> {
                 return_type: None,
             },
         });
-        SystemMembers { arraycopy }
+        let nano_time = class_graph.add_method(MethodData {
+            class,
+            name: UnqualifiedName::NANOTIME,
+            access_flags: MethodAccessFlags::PUBLIC | MethodAccessFlags::STATIC,
+            descriptor: MethodDescriptor {
+                parameters: vec![],
+                return_type: Some(FieldType::long()),
+            },
+        });
+        SystemMembers {
+            arraycopy,
+            nano_time,
+        }
+    }
+}
+
+impl<'g> ThreadMembers<'g> {
+    pub fn add_to_graph(
+        class_graph: &ClassGraph<'g>,
+        classes: &JavaClasses<'g>,
+    ) -> ThreadMembers<'g> {
+        let class = classes.lang.thread;
+        let current_thread = class_graph.add_method(MethodData {
+            class,
+            name: UnqualifiedName::CURRENTTHREAD,
+            access_flags: MethodAccessFlags::PUBLIC | MethodAccessFlags::STATIC,
+            descriptor: MethodDescriptor {
+                parameters: vec![],
+                return_type: Some(FieldType::object(classes.lang.thread)),
+            },
+        });
+        ThreadMembers { current_thread }
     }
 }
 
@@ -1066,6 +1264,8 @@ impl<'g> InvokeMembers<'g> {
         let call_site = CallSiteMembers::add_to_graph(class_graph, classes);
         let constant_call_site = ConstantCallSiteMembers::add_to_graph(class_graph, classes);
         let mutable_call_site = MutableCallSiteMembers::add_to_graph(class_graph, classes);
+        let var_handle = VarHandleMembers::add_to_graph(class_graph, classes);
+        let var_handle_access_mode = VarHandleAccessModeMembers::add_to_graph(class_graph, classes);
         InvokeMembers {
             method_type,
             method_handle,
@@ -1073,10 +1273,47 @@ impl<'g> InvokeMembers<'g> {
             call_site,
             constant_call_site,
             mutable_call_site,
+            var_handle,
+            var_handle_access_mode,
         }
     }
 }
 
+impl<'g> RefMembers<'g> {
+    pub fn add_to_graph(class_graph: &ClassGraph<'g>, classes: &JavaClasses<'g>) -> RefMembers<'g> {
+        let weak_reference = WeakReferenceMembers::add_to_graph(class_graph, classes);
+        RefMembers { weak_reference }
+    }
+}
+
+impl<'g> WeakReferenceMembers<'g> {
+    pub fn add_to_graph(
+        class_graph: &ClassGraph<'g>,
+        classes: &JavaClasses<'g>,
+    ) -> WeakReferenceMembers<'g> {
+        let class = classes.lang.r#ref.weak_reference;
+        let init = class_graph.add_method(MethodData {
+            class,
+            name: UnqualifiedName::INIT,
+            access_flags: MethodAccessFlags::PUBLIC,
+            descriptor: MethodDescriptor {
+                parameters: vec![FieldType::object(classes.lang.object)],
+                return_type: None,
+            },
+        });
+        let get = class_graph.add_method(MethodData {
+            class,
+            name: UnqualifiedName::GET,
+            access_flags: MethodAccessFlags::PUBLIC,
+            descriptor: MethodDescriptor {
+                parameters: vec![],
+                return_type: Some(FieldType::object(classes.lang.object)),
+            },
+        });
+        WeakReferenceMembers { init, get }
+    }
+}
+
 impl<'g> MethodTypeMembers<'g> {
     pub fn add_to_graph(
         class_graph: &ClassGraph<'g>,
@@ -1175,6 +1412,15 @@ impl<'g> MethodHandleMembers<'g> {
                 return_type: Some(FieldType::object(classes.lang.invoke.method_handle)),
             },
         });
+        let as_collector = class_graph.add_method(MethodData {
+            class,
+            name: UnqualifiedName::ASCOLLECTOR,
+            access_flags: MethodAccessFlags::PUBLIC,
+            descriptor: MethodDescriptor {
+                parameters: vec![FieldType::object(classes.lang.class), FieldType::int()],
+                return_type: Some(FieldType::object(classes.lang.invoke.method_handle)),
+            },
+        });
         let bind_to = class_graph.add_method(MethodData {
             class,
             name: UnqualifiedName::BINDTO,
@@ -1193,11 +1439,22 @@ impl<'g> MethodHandleMembers<'g> {
                 return_type: Some(FieldType::object(classes.lang.invoke.method_type)),
             },
         });
+        let invoke_with_arguments = class_graph.add_method(MethodData {
+            class,
+            name: UnqualifiedName::INVOKEWITHARGUMENTS,
+            access_flags: MethodAccessFlags::PUBLIC,
+            descriptor: MethodDescriptor {
+                parameters: vec![FieldType::array(FieldType::object(classes.lang.object))],
+                return_type: Some(FieldType::object(classes.lang.object)),
+            },
+        });
         MethodHandleMembers {
             r#type,
             as_type,
+            as_collector,
             bind_to,
             change_return_type,
+            invoke_with_arguments,
         }
     }
 }
@@ -1360,6 +1617,42 @@ impl<'g> MethodHandlesMembers<'g> {
                 return_type: Some(FieldType::object(classes.lang.invoke.method_handle)),
             },
         });
+        let byte_buffer_view_var_handle = class_graph.add_method(MethodData {
+            class,
+            name: UnqualifiedName::BYTEBUFFERVIEWVARHANDLE,
+            access_flags: MethodAccessFlags::PUBLIC | MethodAccessFlags::STATIC,
+            descriptor: MethodDescriptor {
+                parameters: vec![
+                    FieldType::object(classes.lang.class),
+                    FieldType::object(classes.nio.byte_order),
+                ],
+                return_type: Some(FieldType::object(classes.lang.invoke.var_handle)),
+            },
+        });
+        let byte_array_view_var_handle = class_graph.add_method(MethodData {
+            class,
+            name: UnqualifiedName::BYTEARRAYVIEWVARHANDLE,
+            access_flags: MethodAccessFlags::PUBLIC | MethodAccessFlags::STATIC,
+            descriptor: MethodDescriptor {
+                parameters: vec![
+                    FieldType::object(classes.lang.class),
+                    FieldType::object(classes.nio.byte_order),
+                ],
+                return_type: Some(FieldType::object(classes.lang.invoke.var_handle)),
+            },
+        });
+        let var_handle_exact_invoker = class_graph.add_method(MethodData {
+            class,
+            name: UnqualifiedName::VARHANDLEEXACTINVOKER,
+            access_flags: MethodAccessFlags::PUBLIC | MethodAccessFlags::STATIC,
+            descriptor: MethodDescriptor {
+                parameters: vec![
+                    FieldType::object(classes.lang.invoke.var_handle_access_mode),
+                    FieldType::object(classes.lang.invoke.method_type),
+                ],
+                return_type: Some(FieldType::object(classes.lang.invoke.method_handle)),
+            },
+        });
 
         MethodHandlesMembers {
             drop_arguments,
@@ -1376,6 +1669,51 @@ impl<'g> MethodHandlesMembers<'g> {
             empty,
             identity,
             constant,
+            byte_buffer_view_var_handle,
+            byte_array_view_var_handle,
+            var_handle_exact_invoker,
+        }
+    }
+}
+
+impl<'g> VarHandleMembers<'g> {
+    pub fn add_to_graph(
+        class_graph: &ClassGraph<'g>,
+        classes: &JavaClasses<'g>,
+    ) -> VarHandleMembers<'g> {
+        let class = classes.lang.invoke.var_handle;
+        let full_fence = class_graph.add_method(MethodData {
+            class,
+            name: UnqualifiedName::FULLFENCE,
+            access_flags: MethodAccessFlags::PUBLIC | MethodAccessFlags::STATIC,
+            descriptor: MethodDescriptor {
+                parameters: vec![],
+                return_type: None,
+            },
+        });
+        VarHandleMembers { full_fence }
+    }
+}
+
+impl<'g> VarHandleAccessModeMembers<'g> {
+    pub fn add_to_graph(
+        class_graph: &ClassGraph<'g>,
+        classes: &JavaClasses<'g>,
+    ) -> VarHandleAccessModeMembers<'g> {
+        let class = classes.lang.invoke.var_handle_access_mode;
+        let value_from_method_name = class_graph.add_method(MethodData {
+            class,
+            name: UnqualifiedName::VALUEFROMMETHODNAME,
+            access_flags: MethodAccessFlags::PUBLIC | MethodAccessFlags::STATIC,
+            descriptor: MethodDescriptor {
+                parameters: vec![FieldType::object(classes.lang.string)],
+                return_type: Some(FieldType::object(
+                    classes.lang.invoke.var_handle_access_mode,
+                )),
+            },
+        });
+        VarHandleAccessModeMembers {
+            value_from_method_name,
         }
     }
 }
@@ -1572,7 +1910,24 @@ impl<'g> RuntimeExceptionMembers<'g> {
                 return_type: None,
             },
         });
-        RuntimeExceptionMembers { init }
+        let init_with_flags = class_graph.add_method(MethodData {
+            class,
+            name: UnqualifiedName::INIT,
+            access_flags: MethodAccessFlags::PROTECTED,
+            descriptor: MethodDescriptor {
+                parameters: vec![
+                    FieldType::object(classes.lang.string),
+                    FieldType::object(classes.lang.throwable),
+                    FieldType::boolean(),
+                    FieldType::boolean(),
+                ],
+                return_type: None,
+            },
+        });
+        RuntimeExceptionMembers {
+            init,
+            init_with_flags,
+        }
     }
 }
 
@@ -1614,6 +1969,63 @@ impl<'g> IllegalArgumentExceptionMembers<'g> {
     }
 }
 
+impl<'g> IndexOutOfBoundsExceptionMembers<'g> {
+    pub fn add_to_graph(
+        class_graph: &ClassGraph<'g>,
+        classes: &JavaClasses<'g>,
+    ) -> IndexOutOfBoundsExceptionMembers<'g> {
+        let class = classes.lang.index_out_of_bounds_exception;
+        let init = class_graph.add_method(MethodData {
+            class,
+            name: UnqualifiedName::INIT,
+            access_flags: MethodAccessFlags::PUBLIC,
+            descriptor: MethodDescriptor {
+                parameters: vec![FieldType::object(classes.lang.string)],
+                return_type: None,
+            },
+        });
+        IndexOutOfBoundsExceptionMembers { init }
+    }
+}
+
+impl<'g> IoMembers<'g> {
+    pub fn add_to_graph(class_graph: &ClassGraph<'g>, classes: &JavaClasses<'g>) -> IoMembers<'g> {
+        let input_stream = InputStreamMembers::add_to_graph(class_graph, classes);
+        IoMembers { input_stream }
+    }
+}
+
+impl<'g> InputStreamMembers<'g> {
+    pub fn add_to_graph(
+        class_graph: &ClassGraph<'g>,
+        classes: &JavaClasses<'g>,
+    ) -> InputStreamMembers<'g> {
+        let class = classes.io.input_stream;
+        let read_all_bytes = class_graph.add_method(MethodData {
+            class,
+            name: UnqualifiedName::READALLBYTES,
+            access_flags: MethodAccessFlags::PUBLIC,
+            descriptor: MethodDescriptor {
+                parameters: vec![],
+                return_type: Some(FieldType::array(FieldType::byte())),
+            },
+        });
+        let close = class_graph.add_method(MethodData {
+            class,
+            name: UnqualifiedName::CLOSE,
+            access_flags: MethodAccessFlags::PUBLIC,
+            descriptor: MethodDescriptor {
+                parameters: vec![],
+                return_type: None,
+            },
+        });
+        InputStreamMembers {
+            read_all_bytes,
+            close,
+        }
+    }
+}
+
 impl<'g> NioMembers<'g> {
     pub fn add_to_graph(class_graph: &ClassGraph<'g>, classes: &JavaClasses<'g>) -> NioMembers<'g> {
         let buffer = BufferMembers::add_to_graph(class_graph, classes);
@@ -1651,7 +2063,30 @@ impl<'g> BufferMembers<'g> {
                 return_type: Some(FieldType::int()),
             },
         });
-        BufferMembers { position, capacity }
+        let limit = class_graph.add_method(MethodData {
+            class,
+            name: UnqualifiedName::LIMIT,
+            access_flags: MethodAccessFlags::PUBLIC,
+            descriptor: MethodDescriptor {
+                parameters: vec![],
+                return_type: Some(FieldType::int()),
+            },
+        });
+        let set_limit = class_graph.add_method(MethodData {
+            class,
+            name: UnqualifiedName::LIMIT,
+            access_flags: MethodAccessFlags::PUBLIC,
+            descriptor: MethodDescriptor {
+                parameters: vec![FieldType::int()],
+                return_type: Some(FieldType::object(classes.nio.buffer)),
+            },
+        });
+        BufferMembers {
+            position,
+            capacity,
+            limit,
+            set_limit,
+        }
     }
 }
 
@@ -1780,7 +2215,6 @@ impl<'g> ByteBufferMembers<'g> {
         };
         let put_bytebuffer_relative = add_relative_put(FieldType::object(classes.nio.byte_buffer));
         let put_bytearray_relative = add_relative_put(FieldType::array(FieldType::byte()));
-        let put_byte_relative = add_relative_put(FieldType::byte());
 
         let position = class_graph.add_method(MethodData {
             class,
@@ -1832,7 +2266,6 @@ impl<'g> ByteBufferMembers<'g> {
             put_bytebuffer,
             put_bytebuffer_relative,
             put_bytearray_relative,
-            put_byte_relative,
             position,
             order,
             as_read_only_buffer,
@@ -1869,12 +2302,20 @@ impl<'g> UtilMembers<'g> {
         classes: &JavaClasses<'g>,
     ) -> UtilMembers<'g> {
         let arrays = ArraysMembers::add_to_graph(class_graph, classes);
+        let collections = CollectionsMembers::add_to_graph(class_graph, classes);
         let map = MapMembers::add_to_graph(class_graph, classes);
         let hash_map = HashMapMembers::add_to_graph(class_graph, classes);
+        let weak_hash_map = WeakHashMapMembers::add_to_graph(class_graph, classes);
+        let concurrent = ConcurrentMembers::add_to_graph(class_graph, classes);
+        let zip = ZipMembers::add_to_graph(class_graph, classes);
         UtilMembers {
             arrays,
+            collections,
             map,
             hash_map,
+            weak_hash_map,
+            concurrent,
+            zip,
         }
     }
 }
@@ -1911,7 +2352,44 @@ impl<'g> ArraysMembers<'g> {
                 return_type: None,
             },
         });
-        ArraysMembers { copy_of, fill }
+        let fill_bytes = class_graph.add_method(MethodData {
+            class,
+            name: UnqualifiedName::FILL,
+            access_flags: MethodAccessFlags::PUBLIC | MethodAccessFlags::STATIC,
+            descriptor: MethodDescriptor {
+                parameters: vec![
+                    FieldType::array(FieldType::byte()),
+                    FieldType::int(),
+                    FieldType::int(),
+                    FieldType::byte(),
+                ],
+                return_type: None,
+            },
+        });
+        ArraysMembers {
+            copy_of,
+            fill,
+            fill_bytes,
+        }
+    }
+}
+
+impl<'g> CollectionsMembers<'g> {
+    pub fn add_to_graph(
+        class_graph: &ClassGraph<'g>,
+        classes: &JavaClasses<'g>,
+    ) -> CollectionsMembers<'g> {
+        let class = classes.util.collections;
+        let synchronized_map = class_graph.add_method(MethodData {
+            class,
+            name: UnqualifiedName::SYNCHRONIZEDMAP,
+            access_flags: MethodAccessFlags::PUBLIC | MethodAccessFlags::STATIC,
+            descriptor: MethodDescriptor {
+                parameters: vec![FieldType::object(classes.util.map)],
+                return_type: Some(FieldType::object(classes.util.map)),
+            },
+        });
+        CollectionsMembers { synchronized_map }
     }
 }
 
@@ -1939,7 +2417,23 @@ impl<'g> MapMembers<'g> {
                 return_type: Some(FieldType::object(classes.lang.object)),
             },
         });
-        MapMembers { get, put }
+        let put_if_absent = class_graph.add_method(MethodData {
+            class,
+            name: UnqualifiedName::PUTIFABSENT,
+            access_flags: MethodAccessFlags::PUBLIC,
+            descriptor: MethodDescriptor {
+                parameters: vec![
+                    FieldType::object(classes.lang.object),
+                    FieldType::object(classes.lang.object),
+                ],
+                return_type: Some(FieldType::object(classes.lang.object)),
+            },
+        });
+        MapMembers {
+            get,
+            put,
+            put_if_absent,
+        }
     }
 }
 
@@ -1961,3 +2455,188 @@ impl<'g> HashMapMembers<'g> {
         HashMapMembers { init }
     }
 }
+
+impl<'g> WeakHashMapMembers<'g> {
+    pub fn add_to_graph(
+        class_graph: &ClassGraph<'g>,
+        classes: &JavaClasses<'g>,
+    ) -> WeakHashMapMembers<'g> {
+        let class = classes.util.weak_hash_map;
+        let init = class_graph.add_method(MethodData {
+            class,
+            name: UnqualifiedName::INIT,
+            access_flags: MethodAccessFlags::PUBLIC,
+            descriptor: MethodDescriptor {
+                parameters: vec![],
+                return_type: None,
+            },
+        });
+        WeakHashMapMembers { init }
+    }
+}
+
+impl<'g> ZipMembers<'g> {
+    pub fn add_to_graph(class_graph: &ClassGraph<'g>, classes: &JavaClasses<'g>) -> ZipMembers<'g> {
+        let inflater_input_stream = InflaterInputStreamMembers::add_to_graph(class_graph, classes);
+        ZipMembers {
+            inflater_input_stream,
+        }
+    }
+}
+
+impl<'g> InflaterInputStreamMembers<'g> {
+    pub fn add_to_graph(
+        class_graph: &ClassGraph<'g>,
+        classes: &JavaClasses<'g>,
+    ) -> InflaterInputStreamMembers<'g> {
+        let class = classes.util.zip.inflater_input_stream;
+        let init = class_graph.add_method(MethodData {
+            class,
+            name: UnqualifiedName::INIT,
+            access_flags: MethodAccessFlags::PUBLIC,
+            descriptor: MethodDescriptor {
+                parameters: vec![FieldType::object(classes.io.input_stream)],
+                return_type: None,
+            },
+        });
+        InflaterInputStreamMembers { init }
+    }
+}
+
+impl<'g> ConcurrentMembers<'g> {
+    pub fn add_to_graph(
+        class_graph: &ClassGraph<'g>,
+        classes: &JavaClasses<'g>,
+    ) -> ConcurrentMembers<'g> {
+        let concurrent_hash_map = ConcurrentHashMapMembers::add_to_graph(class_graph, classes);
+        let concurrent_linked_queue =
+            ConcurrentLinkedQueueMembers::add_to_graph(class_graph, classes);
+        let lock_support = LockSupportMembers::add_to_graph(class_graph, classes);
+        ConcurrentMembers {
+            concurrent_hash_map,
+            concurrent_linked_queue,
+            lock_support,
+        }
+    }
+}
+
+impl<'g> ConcurrentHashMapMembers<'g> {
+    pub fn add_to_graph(
+        class_graph: &ClassGraph<'g>,
+        classes: &JavaClasses<'g>,
+    ) -> ConcurrentHashMapMembers<'g> {
+        let class = classes.util.concurrent.concurrent_hash_map;
+        let init = class_graph.add_method(MethodData {
+            class,
+            name: UnqualifiedName::INIT,
+            access_flags: MethodAccessFlags::PUBLIC,
+            descriptor: MethodDescriptor {
+                parameters: vec![],
+                return_type: None,
+            },
+        });
+        ConcurrentHashMapMembers { init }
+    }
+}
+
+impl<'g> ConcurrentLinkedQueueMembers<'g> {
+    pub fn add_to_graph(
+        class_graph: &ClassGraph<'g>,
+        classes: &JavaClasses<'g>,
+    ) -> ConcurrentLinkedQueueMembers<'g> {
+        let class = classes.util.concurrent.concurrent_linked_queue;
+        let init = class_graph.add_method(MethodData {
+            class,
+            name: UnqualifiedName::INIT,
+            access_flags: MethodAccessFlags::PUBLIC,
+            descriptor: MethodDescriptor {
+                parameters: vec![],
+                return_type: None,
+            },
+        });
+        let add = class_graph.add_method(MethodData {
+            class,
+            name: UnqualifiedName::ADD,
+            access_flags: MethodAccessFlags::PUBLIC,
+            descriptor: MethodDescriptor {
+                parameters: vec![FieldType::object(classes.lang.object)],
+                return_type: Some(FieldType::boolean()),
+            },
+        });
+        let contains = class_graph.add_method(MethodData {
+            class,
+            name: UnqualifiedName::CONTAINS,
+            access_flags: MethodAccessFlags::PUBLIC,
+            descriptor: MethodDescriptor {
+                parameters: vec![FieldType::object(classes.lang.object)],
+                return_type: Some(FieldType::boolean()),
+            },
+        });
+        let remove = class_graph.add_method(MethodData {
+            class,
+            name: UnqualifiedName::REMOVE,
+            access_flags: MethodAccessFlags::PUBLIC,
+            descriptor: MethodDescriptor {
+                parameters: vec![FieldType::object(classes.lang.object)],
+                return_type: Some(FieldType::boolean()),
+            },
+        });
+        let poll = class_graph.add_method(MethodData {
+            class,
+            name: UnqualifiedName::POLL,
+            access_flags: MethodAccessFlags::PUBLIC,
+            descriptor: MethodDescriptor {
+                parameters: vec![],
+                return_type: Some(FieldType::object(classes.lang.object)),
+            },
+        });
+        ConcurrentLinkedQueueMembers {
+            init,
+            add,
+            contains,
+            remove,
+            poll,
+        }
+    }
+}
+
+impl<'g> LockSupportMembers<'g> {
+    pub fn add_to_graph(
+        class_graph: &ClassGraph<'g>,
+        classes: &JavaClasses<'g>,
+    ) -> LockSupportMembers<'g> {
+        let class = classes.util.concurrent.lock_support;
+        let park = class_graph.add_method(MethodData {
+            class,
+            name: UnqualifiedName::PARK,
+            access_flags: MethodAccessFlags::PUBLIC | MethodAccessFlags::STATIC,
+            descriptor: MethodDescriptor {
+                parameters: vec![],
+                return_type: None,
+            },
+        });
+        let park_nanos = class_graph.add_method(MethodData {
+            class,
+            name: UnqualifiedName::PARKNANOS,
+            access_flags: MethodAccessFlags::PUBLIC | MethodAccessFlags::STATIC,
+            descriptor: MethodDescriptor {
+                parameters: vec![FieldType::long()],
+                return_type: None,
+            },
+        });
+        let unpark = class_graph.add_method(MethodData {
+            class,
+            name: UnqualifiedName::UNPARK,
+            access_flags: MethodAccessFlags::PUBLIC | MethodAccessFlags::STATIC,
+            descriptor: MethodDescriptor {
+                parameters: vec![FieldType::object(classes.lang.thread)],
+                return_type: None,
+            },
+        });
+        LockSupportMembers {
+            park,
+            park_nanos,
+            unpark,
+        }
+    }
+}
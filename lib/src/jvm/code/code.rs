@@ -1,13 +1,17 @@
 use crate::jvm::class_file;
-use crate::jvm::class_file::{BytecodeIndex, ClassConstantIndex, ConstantsPool, Serialize};
-use crate::jvm::class_graph::BootstrapMethodId;
+use crate::jvm::class_file::{
+    BytecodeIndex, ClassConstantIndex, ConstantIndex, ConstantsPool, ConstantsWriter, Serialize,
+};
+use crate::jvm::class_graph::{BootstrapMethodId, ClassId};
 use crate::jvm::code::{
-    jump_encoding, SerializableBasicBlock, SynLabel, SynLabelGenerator, VerifierBasicBlock,
+    jump_encoding, BranchInstruction, SerializableBasicBlock, SynLabel, SynLabelGenerator,
+    VerifierBasicBlock,
 };
 use crate::jvm::verifier::Frame;
-use crate::jvm::Error;
+use crate::jvm::{Error, RefType};
 use crate::util::{Offset, Width};
 use std::collections::{HashMap, HashSet};
+use std::convert::Infallible;
 
 /// Semantic representation of a method body
 pub struct Code<'g> {
@@ -25,9 +29,56 @@ pub struct Code<'g> {
 
     /// Generator to produce the next label
     pub label_generator: SynLabelGenerator,
+
+    /// Exception handlers protecting blocks, innermost handler first (blocks which aren't keys are
+    /// not protected by any handler)
+    pub exception_handlers: HashMap<SynLabel, Vec<ExceptionHandler<'g>>>,
+}
+
+/// Exception handler protecting some blocks in [`Code`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ExceptionHandler<'g> {
+    /// Block to which control is transferred when an exception is caught
+    pub handler: SynLabel,
+
+    /// Type of exception caught (or `None` to catch everything)
+    pub catch_type: Option<ClassId<'g>>,
 }
 
 impl<'g> Code<'g> {
+    /// Upper bound on the length of the bytecode array the code will serialize into
+    ///
+    /// The exact length is only known once constants are resolved and oversized jumps are widened,
+    /// so this assumes that every `ldc` is wide and that every jump needs widening.
+    pub fn max_code_length(&self) -> usize {
+        let mut length = 0;
+        for block in self.blocks.values() {
+            for (_, _, insn) in block.instructions.iter() {
+                let insn = insn
+                    .map(
+                        |_| Ok(()),
+                        |_| Ok(ConstantIndex(u16::MAX)),
+                        |_| Ok(()),
+                        |_| Ok(()),
+                        |_| Ok::<(), Infallible>(()),
+                    )
+                    .unwrap();
+                length += insn.width();
+            }
+            length += block.branch_end.width();
+            length += match block.branch_end {
+                BranchInstruction::TableSwitch { .. } | BranchInstruction::LookupSwitch { .. } => 3,
+                BranchInstruction::Goto(_)
+                | BranchInstruction::If(..)
+                | BranchInstruction::IfICmp(..)
+                | BranchInstruction::IfACmp(..)
+                | BranchInstruction::IfNull(..) => 5,
+                _ => 0,
+            };
+        }
+        length
+    }
+
     pub fn serialize_code(
         mut self,
         constants_pool: &mut ConstantsPool<'g>,
@@ -78,6 +129,42 @@ impl<'g> Code<'g> {
             return Err(Error::MethodCodeOverflow(latest_offset));
         }
 
+        // Merge consecutive blocks protected by the same handlers into ranges
+        let mut protected_ranges: Vec<(Offset, Offset, &[ExceptionHandler<'g>])> = vec![];
+        for block_label in &self.block_order {
+            let start = label_offsets[block_label];
+            let end = Offset(start.0 + blocks[block_label].width());
+            let handlers: &[ExceptionHandler<'g>] = self
+                .exception_handlers
+                .get(block_label)
+                .map_or(&[], |handlers| handlers);
+            match protected_ranges.last_mut() {
+                Some((_, last_end, last_handlers)) if *last_handlers == handlers => *last_end = end,
+                _ => protected_ranges.push((start, end, handlers)),
+            }
+        }
+
+        // Exception handlers need stack map frames just like jump targets
+        let mut exception_table = vec![];
+        for (start, end, handlers) in protected_ranges {
+            jump_targets.extend(handlers.iter().map(|handler| handler.handler));
+            if start == end {
+                continue;
+            }
+            for handler in handlers {
+                let catch_type = match handler.catch_type {
+                    Some(class) => RefType::Object(class).constant_index(constants_pool)?,
+                    None => ConstantIndex::ZERO,
+                };
+                exception_table.push(class_file::ExceptionHandler {
+                    start_pc: BytecodeIndex(start.0 as u16),
+                    end_pc: BytecodeIndex(end.0 as u16),
+                    handler_pc: BytecodeIndex(label_offsets[&handler.handler].0 as u16),
+                    catch_type,
+                });
+            }
+        }
+
         // Loop through the blocks in placement order to accumulate code and frames
         let mut code_array: class_file::BytecodeArray = class_file::BytecodeArray(vec![]);
         let implicit_frame: Frame<ClassConstantIndex, BytecodeIndex> = blocks[&SynLabel::START]
@@ -170,7 +257,7 @@ impl<'g> Code<'g> {
             max_stack,
             max_locals,
             code_array,
-            exception_table: vec![],
+            exception_table,
             attributes,
         })
     }
@@ -1,7 +1,7 @@
 use crate::jvm::class_graph::{ClassGraph, ClassId, JavaLibrary, MethodId};
 use crate::jvm::code::{
-    BasicBlock, BranchInstruction, Code, LabelGenerator, SynLabel, SynLabelGenerator,
-    VerifierBasicBlock, VerifierInstruction,
+    BasicBlock, BranchInstruction, Code, ExceptionHandler, Instruction, LabelGenerator, SynLabel,
+    SynLabelGenerator, VerifierBasicBlock, VerifierInstruction,
 };
 use crate::jvm::verifier::*;
 use crate::jvm::{Error, FieldType, RefType, UnqualifiedName};
@@ -31,6 +31,13 @@ use std::collections::HashMap;
 /// enforce that labels cannot be placed unless they are reachable (either with a fall-through from
 /// above, or there has already been a jump to the label). This is also important for the sake of
 /// always being able to find the initial frame of the block.
+///
+/// ### Exception handlers
+///
+/// Exception handlers are scoped: everything between `begin_exception_handler` and the matching
+/// `end_exception_handler` is protected by the handler. The frame of the handler is fixed when
+/// the handler begins, so the local variables it sees must not change while it is active (the
+/// same "identical frames" simplification as above).
 pub struct CodeBuilder<'g> {
     /// Method code under construction
     code: Code<'g>,
@@ -42,6 +49,9 @@ pub struct CodeBuilder<'g> {
     /// Block currently under construction (label is not in `blocks` _or_ `unplaced_labels`)
     current_block: Option<CurrentBlock<'g>>,
 
+    /// Exception handlers protecting the current block (innermost handler last)
+    exception_handlers: Vec<ExceptionHandler<'g>>,
+
     /// Class graph
     pub class_graph: &'g ClassGraph<'g>,
 
@@ -91,12 +101,14 @@ impl<'g> CodeBuilder<'g> {
             blocks: HashMap::new(),
             block_order: vec![],
             label_generator,
+            exception_handlers: HashMap::new(),
         };
 
         CodeBuilder {
             code,
             unplaced_labels: HashMap::new(),
             current_block,
+            exception_handlers: vec![],
             class_graph,
             java,
             method,
@@ -215,7 +227,18 @@ impl<'g> CodeBuilder<'g> {
                 .latest_frame
                 .update_maximums(&mut self.code.max_locals, &mut self.code.max_stack);
 
+            let stores_local = matches!(
+                insn,
+                Instruction::IStore(_)
+                    | Instruction::LStore(_)
+                    | Instruction::FStore(_)
+                    | Instruction::DStore(_)
+                    | Instruction::AStore(_)
+            );
             current_block.instructions.push(insn);
+            if stores_local {
+                self.check_exception_handler_locals()?;
+            }
         }
         Ok(())
     }
@@ -255,6 +278,7 @@ impl<'g> CodeBuilder<'g> {
                 .close_block(insn.map_labels(|lbl| *lbl, |lbl| *lbl, |()| self.fresh_label()))?;
 
             // Update all the local state in the builder
+            self.record_exception_handlers(block_label);
             self.code.block_order.push(block_label);
             self.current_block = next_curr_block_opt;
             if self.code.blocks.insert(block_label, basic_block).is_some() {
@@ -300,6 +324,7 @@ impl<'g> CodeBuilder<'g> {
 
             // Update all the local state in the builder
             let _ = self.unplaced_labels.remove(&label);
+            self.record_exception_handlers(block_label);
             self.code.block_order.push(block_label);
             self.current_block = next_curr_block_opt;
             if self.code.blocks.insert(block_label, basic_block).is_some() {
@@ -376,11 +401,96 @@ impl<'g> CodeBuilder<'g> {
                         offset, local_type_assertion
                     ),
                     kind,
-                })
+                })?;
+            self.check_exception_handler_locals()
         } else {
             Ok(())
         }
     }
+
+    /// Start protecting code with an exception handler
+    ///
+    /// The handler block will start with the same locals as the current frame and just the caught
+    /// exception on the stack (typed as `Throwable` if `catch_type` is `None`). The current block
+    /// gets split, so that blocks always have a uniform set of handlers.
+    pub fn begin_exception_handler(
+        &mut self,
+        handler: SynLabel,
+        catch_type: Option<ClassId<'g>>,
+    ) -> Result<(), Error> {
+        if let Some(current_block) = self.current_block.as_ref() {
+            let exception_type = catch_type.unwrap_or(self.java.classes.lang.throwable);
+            let mut stack = OffsetVec::new();
+            stack.push(VerificationType::Object(RefType::Object(exception_type)));
+            let handler_frame = Frame {
+                locals: current_block.latest_frame.locals.clone(),
+                stack,
+            };
+            handler_frame.update_maximums(&mut self.code.max_locals, &mut self.code.max_stack);
+            self.assert_frame_for_label(handler, &handler_frame, None)?;
+
+            let protected_start = self.fresh_label();
+            self.place_label(protected_start)?;
+        }
+        self.exception_handlers.push(ExceptionHandler {
+            handler,
+            catch_type,
+        });
+        Ok(())
+    }
+
+    /// Stop protecting code with the innermost exception handler
+    ///
+    /// Note: this does not place the handler label.
+    pub fn end_exception_handler(&mut self) -> Result<(), Error> {
+        if self.current_block.is_some() {
+            let protected_end = self.fresh_label();
+            self.place_label(protected_end)?;
+        }
+        let _ = self.exception_handlers.pop();
+        Ok(())
+    }
+
+    /// Record which exception handlers protect a block that is being closed
+    fn record_exception_handlers(&mut self, block_label: SynLabel) {
+        if !self.exception_handlers.is_empty() {
+            let handlers = self.exception_handlers.iter().rev().copied().collect();
+            let _ = self.code.exception_handlers.insert(block_label, handlers);
+        }
+    }
+
+    /// Check that the locals of the current frame still match what the active exception handlers
+    /// expect (locals above those in the handler frame are not visible to the handler)
+    fn check_exception_handler_locals(&self) -> Result<(), Error> {
+        let current_frame = match self.current_block.as_ref() {
+            Some(current_block) => &current_block.latest_frame,
+            None => return Ok(()),
+        };
+        for handler in &self.exception_handlers {
+            let handler_frame = match self.lookup_frame(handler.handler) {
+                Some(handler_frame) => handler_frame,
+                None => continue,
+            };
+            let locals_match = handler_frame.locals.len() <= current_frame.locals.len()
+                && handler_frame
+                    .locals
+                    .iter()
+                    .zip(current_frame.locals.iter())
+                    .all(|(expected, found)| expected == found);
+            if !locals_match {
+                let found = Frame {
+                    locals: current_frame.locals.clone(),
+                    stack: handler_frame.stack.clone(),
+                };
+                return Err(Error::IncompatibleFrames(
+                    handler.handler,
+                    found.into_printable(),
+                    handler_frame.into_printable(),
+                ));
+            }
+        }
+        Ok(())
+    }
 }
 
 /// Just like `BasicBlock`, but not closed off yet
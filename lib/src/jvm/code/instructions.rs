@@ -1040,7 +1040,7 @@ impl Serialize for BranchInstruction<i16, i32, ()> {
                 lbl.serialize(writer)?;
             }
             BranchInstruction::GotoW(lbl_ext) => {
-                0xa8u8.serialize(writer)?;
+                0xc8u8.serialize(writer)?;
                 lbl_ext.serialize(writer)?;
             }
             BranchInstruction::TableSwitch {
@@ -30,6 +30,7 @@ mod code_builder_exts;
 mod instructions;
 pub mod jump_encoding;
 mod label;
+mod peephole;
 
 pub use basic_block::*;
 pub use code::*;
@@ -0,0 +1,695 @@
+//! Peephole optimizations over finished method code
+//!
+//! Code generators (especially the WASM translator) tend to produce bytecode with some obvious
+//! redundancies: values stored to a local only to be immediately loaded back, increments which
+//! could be `iinc`, jumps to jumps, etc. Since [`Code`] still has the structure of basic blocks
+//! (with stack map frames for each block), these are easier to clean up just before the code gets
+//! serialized than while it is being generated.
+//!
+//! All of the rewrites here preserve the frames at the start and end of every basic block, so
+//! there is never any need to recompute stack map frames. Specifically:
+//!
+//!   - `dup; pop`, `dup2; pop2`, and side-effect free pushes followed by a pop are removed
+//!   - `xload n; xstore n` is removed
+//!   - `xstore n; xload n` is removed when `n` is not used afterwards and it already held a value
+//!     of the same (primitive) type before the store
+//!   - `iload n; <const>; iadd; istore n` (and similar with `isub` or an extra `dup`) becomes `iinc`
+//!   - jumps to empty blocks which just jump elsewhere are redirected to their final target
+//!   - blocks which are no longer reachable are dropped
+//!   - `goto` to the block placed right afterwards turns into a fallthrough
+//!
+//! The rewrites rely on the same invariants [`CodeBuilder`](super::CodeBuilder) enforces (eg. the
+//! frame at the end of a block is compatible with the frames of the blocks it jumps to).
+
+use crate::jvm::code::{
+    BranchInstruction, Code, Instruction, SynLabel, VerifierBasicBlock, VerifierInstruction,
+};
+use crate::jvm::verifier::VerificationType;
+use crate::util::{Offset, OffsetResult, OffsetVec};
+use std::collections::{HashMap, HashSet};
+
+/// Upper bound on how many times all the rewrites get retried (each round can expose new
+/// opportunities for the others, but this converges quickly in practice)
+const MAX_ROUNDS: usize = 4;
+
+impl<'g> Code<'g> {
+    /// Apply peephole optimizations to the code
+    ///
+    /// This should be called once the code is done being generated (since it may drop or merge
+    /// blocks), but before it is serialized.
+    pub fn optimize_peephole(&mut self) {
+        for _ in 0..MAX_ROUNDS {
+            let mut changed = false;
+            for block in self.blocks.values_mut() {
+                changed |= simplify_block(block);
+            }
+            changed |= self.remove_dead_store_loads();
+            changed |= self.thread_jumps();
+            changed |= self.remove_unreachable_blocks();
+            if !changed {
+                break;
+            }
+        }
+        self.fall_through_to_next_blocks();
+    }
+
+    /// Remove `xstore n; xload n` pairs where `n` is dead afterwards
+    fn remove_dead_store_loads(&mut self) -> bool {
+        let (live_ins, live_outs) = self.live_locals();
+        let mut changed = false;
+
+        for (label, block) in self.blocks.iter_mut() {
+            let live_out = &live_outs[label];
+
+            // Locals which an exception handler for this block might read
+            let mut handler_live = LocalSet::new(self.max_locals.0);
+            for handler in self.exception_handlers.get(label).into_iter().flatten() {
+                handler_live.union_with(&live_ins[&handler.handler]);
+            }
+
+            // Walk backwards to find which loads are the last use of their local
+            let instructions: Vec<&VerifierInstruction<'g>> =
+                block.instructions.iter().map(|(_, _, insn)| insn).collect();
+            let mut live = live_out.clone();
+            let mut last_use = vec![false; instructions.len()];
+            for (i, insn) in instructions.iter().enumerate().rev() {
+                match local_access(insn) {
+                    Some((local, LocalAccess::Load(_))) => {
+                        last_use[i] = !live.contains(local) && !handler_live.contains(local);
+                        live.insert(local);
+                    }
+                    Some((local, LocalAccess::Store(_))) => live.remove(local),
+                    Some((local, LocalAccess::Increment)) => live.insert(local),
+                    None => (),
+                }
+            }
+
+            // Walk forwards tracking local types to find removable pairs
+            let mut local_kinds = LocalKinds::new(block);
+            let mut remove = vec![false; instructions.len()];
+            for i in 0..instructions.len() {
+                if let Some((local, LocalAccess::Store(kind))) = local_access(instructions[i]) {
+                    if let Some((local2, LocalAccess::Load(kind2))) =
+                        instructions.get(i + 1).and_then(|insn| local_access(insn))
+                    {
+                        if local == local2
+                            && kind == kind2
+                            && kind != LocalKind::Reference
+                            && last_use[i + 1]
+                            && local_kinds.get(local) == kind
+                        {
+                            remove[i] = true;
+                            remove[i + 1] = true;
+                        }
+                    }
+                    local_kinds.set(local, kind);
+                }
+            }
+
+            if remove.contains(&true) {
+                let instructions: OffsetVec<VerifierInstruction<'g>> = instructions
+                    .into_iter()
+                    .zip(remove)
+                    .filter(|(_, remove)| !remove)
+                    .map(|(insn, _)| insn.clone())
+                    .collect();
+                block.instructions = instructions;
+                changed = true;
+            }
+        }
+
+        changed
+    }
+
+    /// Compute the locals which are live at the start and end of every block
+    fn live_locals(&self) -> (HashMap<SynLabel, LocalSet>, HashMap<SynLabel, LocalSet>) {
+        let local_count = self.max_locals.0;
+
+        // Locals read before being written and locals written in each block
+        let mut uses_defs: HashMap<SynLabel, (LocalSet, LocalSet)> = HashMap::new();
+        for (label, block) in &self.blocks {
+            let mut uses = LocalSet::new(local_count);
+            let mut defs = LocalSet::new(local_count);
+            for (_, _, insn) in block.instructions.iter() {
+                match local_access(insn) {
+                    Some((local, LocalAccess::Load(_) | LocalAccess::Increment))
+                        if !defs.contains(local) =>
+                    {
+                        uses.insert(local)
+                    }
+                    Some((local, LocalAccess::Store(_))) => defs.insert(local),
+                    _ => (),
+                }
+            }
+            uses_defs.insert(*label, (uses, defs));
+        }
+
+        // Iterate to a fixed point (backwards through the blocks, since that converges faster)
+        let mut live_ins: HashMap<SynLabel, LocalSet> = HashMap::new();
+        let mut live_outs: HashMap<SynLabel, LocalSet> = HashMap::new();
+        for label in &self.block_order {
+            live_ins.insert(*label, LocalSet::new(local_count));
+            live_outs.insert(*label, LocalSet::new(local_count));
+        }
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for label in self.block_order.iter().rev() {
+                let mut live_out = LocalSet::new(local_count);
+                for successor in self.successors(*label) {
+                    live_out.union_with(&live_ins[&successor]);
+                }
+
+                let (uses, defs) = &uses_defs[label];
+                let mut live_in = live_out.clone();
+                live_in.difference_with(defs);
+                live_in.union_with(uses);
+
+                // Handlers may run before any of the stores in the block
+                for handler in self.exception_handlers.get(label).into_iter().flatten() {
+                    live_in.union_with(&live_ins[&handler.handler]);
+                }
+
+                changed |= live_ins.get_mut(label).unwrap().union_with(&live_in);
+                changed |= live_outs.get_mut(label).unwrap().union_with(&live_out);
+            }
+        }
+
+        (live_ins, live_outs)
+    }
+
+    /// Blocks to which control may be transferred after the block with the given label (excluding
+    /// exception handlers)
+    fn successors(&self, label: SynLabel) -> Vec<SynLabel> {
+        let branch_end = &self.blocks[&label].branch_end;
+        let mut successors = branch_end.jump_targets().targets().to_vec();
+        successors.extend(branch_end.fallthrough_target());
+        successors
+    }
+
+    /// Redirect jumps to empty blocks which just jump or fall through elsewhere
+    fn thread_jumps(&mut self) -> bool {
+        let forwards: HashMap<SynLabel, SynLabel> = self
+            .blocks
+            .iter()
+            .filter(|(_, block)| block.instructions.is_empty())
+            .filter_map(|(label, block)| match block.branch_end {
+                BranchInstruction::Goto(target) | BranchInstruction::FallThrough(target) => {
+                    Some((*label, target))
+                }
+                _ => None,
+            })
+            .collect();
+        if forwards.is_empty() {
+            return false;
+        }
+
+        let resolve = |label: &SynLabel| -> SynLabel {
+            let mut label = *label;
+            let mut steps = 0;
+            while let Some(target) = forwards.get(&label) {
+                // Guard against cycles of empty blocks (eg. `L: goto L`)
+                if steps > forwards.len() {
+                    break;
+                }
+                label = *target;
+                steps += 1;
+            }
+            label
+        };
+
+        let mut changed = false;
+        for block in self.blocks.values_mut() {
+            let branch_end = block.branch_end.map_labels(resolve, resolve, |lbl| *lbl);
+            if branch_end != block.branch_end {
+                block.branch_end = branch_end;
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    /// Drop blocks which can't be reached from the start of the method
+    fn remove_unreachable_blocks(&mut self) -> bool {
+        let mut reachable: HashSet<SynLabel> = HashSet::new();
+        let mut to_visit = vec![SynLabel::START];
+        while let Some(label) = to_visit.pop() {
+            if !reachable.insert(label) {
+                continue;
+            }
+            to_visit.extend(self.successors(label));
+            to_visit.extend(
+                self.exception_handlers
+                    .get(&label)
+                    .into_iter()
+                    .flatten()
+                    .map(|handler| handler.handler),
+            );
+        }
+
+        if reachable.len() == self.blocks.len() {
+            return false;
+        }
+        self.block_order.retain(|label| reachable.contains(label));
+        self.blocks.retain(|label, _| reachable.contains(label));
+        self.exception_handlers
+            .retain(|label, _| reachable.contains(label));
+        true
+    }
+
+    /// Replace `goto` with a fallthrough when the target is the next block anyways
+    fn fall_through_to_next_blocks(&mut self) {
+        for window in self.block_order.windows(2) {
+            let block = self.blocks.get_mut(&window[0]).unwrap();
+            if block.branch_end == BranchInstruction::Goto(window[1]) {
+                block.branch_end = BranchInstruction::FallThrough(window[1]);
+            }
+        }
+    }
+}
+
+/// Apply the rewrites that only look at a short window of instructions in one block
+fn simplify_block(block: &mut VerifierBasicBlock) -> bool {
+    let instructions: Vec<VerifierInstruction> = block
+        .instructions
+        .iter()
+        .map(|(_, _, insn)| insn.clone())
+        .collect();
+
+    let mut simplified = Vec::with_capacity(instructions.len());
+    let mut changed = false;
+    let mut i = 0;
+    while i < instructions.len() {
+        if let Some((consumed, replacement)) = simplify_prefix(&instructions[i..]) {
+            simplified.extend(replacement);
+            i += consumed;
+            changed = true;
+        } else {
+            simplified.push(instructions[i].clone());
+            i += 1;
+        }
+    }
+
+    if changed {
+        block.instructions = OffsetVec::from_iter(simplified);
+    }
+    changed
+}
+
+/// Try to simplify the instructions at the start of the slice, returning how many instructions
+/// were consumed and what to replace them with
+fn simplify_prefix<'g>(
+    instructions: &[VerifierInstruction<'g>],
+) -> Option<(usize, Vec<VerifierInstruction<'g>>)> {
+    use Instruction::*;
+
+    match instructions {
+        [Nop, ..] => Some((1, vec![])),
+        [Dup, Pop, ..] | [Dup2, Pop2, ..] => Some((2, vec![])),
+        [push, Pop, ..] if is_pure_push(push, false) => Some((2, vec![])),
+        [push, Pop2, ..] if is_pure_push(push, true) => Some((2, vec![])),
+        [load, store, ..] if is_load_store(load, store) => Some((2, vec![])),
+        _ => simplify_increment(instructions),
+    }
+}
+
+/// Is this a load from a local followed by a store of the same value back to that local?
+fn is_load_store(load: &VerifierInstruction, store: &VerifierInstruction) -> bool {
+    match (local_access(load), local_access(store)) {
+        (Some((n, LocalAccess::Load(k1))), Some((m, LocalAccess::Store(k2)))) => n == m && k1 == k2,
+        _ => false,
+    }
+}
+
+/// Try to turn a local variable update into `iinc`
+fn simplify_increment<'g>(
+    instructions: &[VerifierInstruction<'g>],
+) -> Option<(usize, Vec<VerifierInstruction<'g>>)> {
+    use Instruction::*;
+
+    let (local, delta, rest) = match instructions {
+        [ILoad(n), c, IAdd, rest @ ..] | [c, ILoad(n), IAdd, rest @ ..] => {
+            (*n, int_constant(c)?, rest)
+        }
+        [ILoad(n), c, ISub, rest @ ..] => (*n, int_constant(c)?.checked_neg()?, rest),
+        _ => return None,
+    };
+    let delta = i16::try_from(delta).ok()?;
+    match rest {
+        [IStore(m), ..] if *m == local => Some((4, vec![IInc(local, delta)])),
+        [Dup, IStore(m), ..] if *m == local => Some((5, vec![IInc(local, delta), ILoad(local)])),
+        _ => None,
+    }
+}
+
+/// If the instruction pushes an `int` constant, get that constant
+fn int_constant(insn: &VerifierInstruction) -> Option<i32> {
+    use Instruction::*;
+
+    match insn {
+        IConstM1 => Some(-1),
+        IConst0 => Some(0),
+        IConst1 => Some(1),
+        IConst2 => Some(2),
+        IConst3 => Some(3),
+        IConst4 => Some(4),
+        IConst5 => Some(5),
+        BiPush(b) => Some(*b as i32),
+        SiPush(s) => Some(*s as i32),
+        _ => None,
+    }
+}
+
+/// Does the instruction just push one value (of the specified width) onto the stack, without
+/// having any other effect?
+fn is_pure_push(insn: &VerifierInstruction, wide: bool) -> bool {
+    use Instruction::*;
+
+    if wide {
+        matches!(
+            insn,
+            LConst0 | LConst1 | DConst0 | DConst1 | LLoad(_) | DLoad(_)
+        )
+    } else {
+        int_constant(insn).is_some()
+            || matches!(
+                insn,
+                AConstNull | FConst0 | FConst1 | FConst2 | ILoad(_) | FLoad(_) | ALoad(_)
+            )
+    }
+}
+
+/// Kind of value in a local variable
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum LocalKind {
+    Int,
+    Long,
+    Float,
+    Double,
+    Reference,
+
+    /// Unusable or unknown (eg. the second half of a `long`)
+    Other,
+}
+
+impl LocalKind {
+    fn is_wide(self) -> bool {
+        matches!(self, LocalKind::Long | LocalKind::Double)
+    }
+}
+
+/// How an instruction accesses a local variable
+enum LocalAccess {
+    Load(LocalKind),
+    Store(LocalKind),
+    Increment,
+}
+
+fn local_access(insn: &VerifierInstruction) -> Option<(u16, LocalAccess)> {
+    use Instruction::*;
+
+    Some(match insn {
+        ILoad(n) => (*n, LocalAccess::Load(LocalKind::Int)),
+        LLoad(n) => (*n, LocalAccess::Load(LocalKind::Long)),
+        FLoad(n) => (*n, LocalAccess::Load(LocalKind::Float)),
+        DLoad(n) => (*n, LocalAccess::Load(LocalKind::Double)),
+        ALoad(n) => (*n, LocalAccess::Load(LocalKind::Reference)),
+        IStore(n) => (*n, LocalAccess::Store(LocalKind::Int)),
+        LStore(n) => (*n, LocalAccess::Store(LocalKind::Long)),
+        FStore(n) => (*n, LocalAccess::Store(LocalKind::Float)),
+        DStore(n) => (*n, LocalAccess::Store(LocalKind::Double)),
+        AStore(n) => (*n, LocalAccess::Store(LocalKind::Reference)),
+        IInc(n, _) => (*n, LocalAccess::Increment),
+        _ => return None,
+    })
+}
+
+/// Kinds of locals at some point in a block
+struct LocalKinds<'a, 'g> {
+    block: &'a VerifierBasicBlock<'g>,
+
+    /// Kinds which have changed since the start of the block
+    updated: HashMap<u16, LocalKind>,
+}
+
+impl<'a, 'g> LocalKinds<'a, 'g> {
+    fn new(block: &'a VerifierBasicBlock<'g>) -> Self {
+        LocalKinds {
+            block,
+            updated: HashMap::new(),
+        }
+    }
+
+    fn get(&self, local: u16) -> LocalKind {
+        if let Some(kind) = self.updated.get(&local) {
+            return *kind;
+        }
+        match self.block.frame.locals.get_offset(Offset(local as usize)) {
+            OffsetResult::Ok(_, VerificationType::Integer) => LocalKind::Int,
+            OffsetResult::Ok(_, VerificationType::Long) => LocalKind::Long,
+            OffsetResult::Ok(_, VerificationType::Float) => LocalKind::Float,
+            OffsetResult::Ok(_, VerificationType::Double) => LocalKind::Double,
+            OffsetResult::Ok(_, _) => LocalKind::Reference,
+            _ => LocalKind::Other,
+        }
+    }
+
+    fn set(&mut self, local: u16, kind: LocalKind) {
+        // Overwriting half of a wide value invalidates the other half
+        if local > 0 && self.get(local - 1).is_wide() {
+            self.updated.insert(local - 1, LocalKind::Other);
+        }
+        if kind.is_wide() {
+            self.updated.insert(local + 1, LocalKind::Other);
+        }
+        self.updated.insert(local, kind);
+    }
+}
+
+/// Set of local variable indices
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct LocalSet(Vec<u64>);
+
+impl LocalSet {
+    fn new(local_count: usize) -> LocalSet {
+        LocalSet(vec![0; (local_count + 63) / 64])
+    }
+
+    fn contains(&self, local: u16) -> bool {
+        let local = local as usize;
+        self.0
+            .get(local / 64)
+            .map_or(false, |word| word & (1 << (local % 64)) != 0)
+    }
+
+    fn insert(&mut self, local: u16) {
+        let local = local as usize;
+        if local / 64 >= self.0.len() {
+            self.0.resize(local / 64 + 1, 0);
+        }
+        self.0[local / 64] |= 1 << (local % 64);
+    }
+
+    fn remove(&mut self, local: u16) {
+        let local = local as usize;
+        if let Some(word) = self.0.get_mut(local / 64) {
+            *word &= !(1 << (local % 64));
+        }
+    }
+
+    /// Add all of the elements of the other set, returning whether anything was added
+    fn union_with(&mut self, other: &LocalSet) -> bool {
+        if other.0.len() > self.0.len() {
+            self.0.resize(other.0.len(), 0);
+        }
+        let mut changed = false;
+        for (word, other_word) in self.0.iter_mut().zip(&other.0) {
+            changed |= *other_word & !*word != 0;
+            *word |= other_word;
+        }
+        changed
+    }
+
+    fn difference_with(&mut self, other: &LocalSet) {
+        for (word, other_word) in self.0.iter_mut().zip(&other.0) {
+            *word &= !other_word;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::jvm::class_file::ConstantsPool;
+    use crate::jvm::class_graph::{ClassData, ClassGraph, ClassGraphArenas, MethodData};
+    use crate::jvm::code::{CodeBuilder, Instruction::*, OrdComparison};
+    use crate::jvm::{
+        BinaryName, ClassAccessFlags, Error, FieldType, MethodAccessFlags, MethodDescriptor, Name,
+        UnqualifiedName,
+    };
+
+    /// Build and optimize the code of a static `(II)I` method, then check it still serializes
+    fn optimize_int_method(
+        generate: impl for<'g> FnOnce(&mut CodeBuilder<'g>) -> Result<(), Error>,
+        check: impl for<'g> FnOnce(&Code<'g>),
+    ) {
+        let class_graph_arenas = ClassGraphArenas::new();
+        let class_graph = ClassGraph::new(&class_graph_arenas);
+        let java = class_graph.insert_java_library_types();
+
+        let class = class_graph.add_class(ClassData::new(
+            BinaryName::from_str("me/MyClass").unwrap(),
+            java.classes.lang.object,
+            ClassAccessFlags::PUBLIC,
+            None,
+        ));
+        let method = class_graph.add_method(MethodData {
+            class,
+            name: UnqualifiedName::from_str("myMethod").unwrap(),
+            access_flags: MethodAccessFlags::STATIC,
+            descriptor: MethodDescriptor {
+                parameters: vec![FieldType::int(), FieldType::int()],
+                return_type: Some(FieldType::int()),
+            },
+        });
+
+        let mut code_builder = CodeBuilder::new(&class_graph, &java, method);
+        generate(&mut code_builder).unwrap();
+        let mut code = code_builder.result().unwrap();
+        code.optimize_peephole();
+        check(&code);
+        code.serialize_code(&mut ConstantsPool::new(), &mut HashMap::new())
+            .unwrap();
+    }
+
+    fn block_instructions(code: &Code, label: SynLabel) -> String {
+        let instructions: Vec<_> = code.blocks[&label]
+            .instructions
+            .iter()
+            .map(|(_, _, insn)| insn)
+            .collect();
+        format!("{:?}", instructions)
+    }
+
+    #[test]
+    fn increment() {
+        optimize_int_method(
+            |code| {
+                code.push_instruction(ILoad(0))?;
+                code.push_instruction(BiPush(-7))?;
+                code.push_instruction(IAdd)?;
+                code.push_instruction(IStore(0))?;
+                code.push_instruction(ILoad(1))?;
+                code.push_instruction(IConst2)?;
+                code.push_instruction(ISub)?;
+                code.push_instruction(Dup)?;
+                code.push_instruction(IStore(1))?;
+                code.push_branch_instruction(BranchInstruction::IReturn)
+            },
+            |code| {
+                assert_eq!(
+                    block_instructions(code, SynLabel::START),
+                    "[IInc(0, -7), IInc(1, -2), ILoad(1)]",
+                );
+            },
+        );
+    }
+
+    #[test]
+    fn redundant_stack_and_local_operations() {
+        optimize_int_method(
+            |code| {
+                code.push_instruction(Nop)?;
+                code.push_instruction(ILoad(0))?;
+                code.push_instruction(Dup)?;
+                code.push_instruction(Pop)?;
+                code.push_instruction(IConst3)?;
+                code.push_instruction(Pop)?;
+                code.push_instruction(ILoad(1))?;
+                code.push_instruction(IStore(1))?;
+                code.push_instruction(IStore(1))?;
+                code.push_instruction(ILoad(1))?;
+                code.push_branch_instruction(BranchInstruction::IReturn)
+            },
+            |code| {
+                assert_eq!(block_instructions(code, SynLabel::START), "[ILoad(0)]");
+            },
+        );
+    }
+
+    #[test]
+    fn live_store_load_kept() {
+        optimize_int_method(
+            |code| {
+                code.push_instruction(ILoad(0))?;
+                code.push_instruction(IStore(1))?;
+                code.push_instruction(ILoad(1))?;
+                code.push_instruction(ILoad(1))?;
+                code.push_instruction(IAdd)?;
+                code.push_branch_instruction(BranchInstruction::IReturn)
+            },
+            |code| {
+                assert_eq!(
+                    block_instructions(code, SynLabel::START),
+                    "[ILoad(0), IStore(1), ILoad(1), ILoad(1), IAdd]",
+                );
+            },
+        );
+    }
+
+    #[test]
+    fn jump_threading() {
+        optimize_int_method(
+            |code| {
+                let middle = code.fresh_label();
+                let end = code.fresh_label();
+                let other = code.fresh_label();
+
+                code.push_instruction(ILoad(0))?;
+                code.push_branch_instruction(BranchInstruction::If(OrdComparison::EQ, other, ()))?;
+                code.push_branch_instruction(BranchInstruction::Goto(middle))?;
+                code.place_label(middle)?;
+                code.push_branch_instruction(BranchInstruction::Goto(end))?;
+                code.place_label(other)?;
+                code.push_instruction(IConst1)?;
+                code.push_branch_instruction(BranchInstruction::IReturn)?;
+                code.place_label(end)?;
+                code.push_instruction(IConst0)?;
+                code.push_branch_instruction(BranchInstruction::IReturn)
+            },
+            |code| {
+                // Only the `goto end` from the fallthrough of the `if` is left
+                assert_eq!(code.block_order.len(), 4);
+                let jumps: Vec<_> = code
+                    .block_order
+                    .iter()
+                    .filter(|label| code.blocks[label].instructions.is_empty())
+                    .map(|label| code.blocks[label].branch_end.clone())
+                    .collect();
+                assert_eq!(jumps.len(), 1);
+                assert!(matches!(jumps[0], BranchInstruction::Goto(_)));
+            },
+        );
+    }
+
+    #[test]
+    fn goto_next_block() {
+        optimize_int_method(
+            |code| {
+                let next = code.fresh_label();
+                code.push_branch_instruction(BranchInstruction::Goto(next))?;
+                code.place_label(next)?;
+                code.push_instruction(ILoad(1))?;
+                code.push_branch_instruction(BranchInstruction::IReturn)
+            },
+            |code| {
+                assert_eq!(code.block_order.len(), 2);
+                assert!(matches!(
+                    code.blocks[&SynLabel::START].branch_end,
+                    BranchInstruction::FallThrough(_)
+                ));
+            },
+        );
+    }
+}
@@ -4,7 +4,7 @@ use crate::jvm::class_file::{
     ConstantPoolOverflow, ConstantsPool, ConstantsWriter, InnerClass, InnerClasses, NestHost,
     NestMembers, Version,
 };
-use crate::jvm::class_graph::{ClassId, ConstantData, NestData};
+use crate::jvm::class_graph::{BootstrapMethodId, ClassId, ConstantData, NestData};
 use crate::jvm::model::{Field, Method};
 use crate::jvm::{Error, Name};
 use crate::util::RefId;
@@ -49,10 +49,21 @@ impl<'g> Class<'g> {
     ///   - [`InnerClasses`] based on all the classes that show up in the constant pool and which
     ///     are not nest hosts
     pub fn serialize(self, version: Version) -> Result<ClassFile, Error> {
-        // Construct a fresh constant pool
-        let mut constants_pool: ConstantsPool<'g> = ConstantsPool::new();
-        let mut bootstrap_methods = HashMap::new();
+        self.serialize_with(version, ConstantsPool::new(), HashMap::new(), vec![])
+    }
 
+    /// Serialize the class into a class file, along with methods which were already serialized
+    ///
+    /// The serialized methods come first, and `constants_pool` and `bootstrap_methods` must be
+    /// the ones which they were serialized with. This makes it possible to serialize methods as
+    /// they get added, and so know exactly how big a class is getting.
+    pub fn serialize_with(
+        self,
+        version: Version,
+        mut constants_pool: ConstantsPool<'g>,
+        mut bootstrap_methods: HashMap<BootstrapMethodId<'g>, u16>,
+        serialized_methods: Vec<class_file::Method>,
+    ) -> Result<ClassFile, Error> {
         let this_class = self.id.constant_index(&mut constants_pool)?;
         let super_class = self
             .id
@@ -74,11 +85,10 @@ impl<'g> Class<'g> {
             .into_iter()
             .map(|field| field.serialize_field(&mut constants_pool))
             .collect::<Result<Vec<class_file::Field>, Error>>()?;
-        let methods: Vec<class_file::Method> = self
-            .methods
-            .into_iter()
-            .map(|method| method.serialize_method(&mut constants_pool, &mut bootstrap_methods))
-            .collect::<Result<Vec<class_file::Method>, Error>>()?;
+        let mut methods: Vec<class_file::Method> = serialized_methods;
+        for method in self.methods {
+            methods.push(method.serialize_method(&mut constants_pool, &mut bootstrap_methods)?);
+        }
 
         // `BootstrapMethods` attribute
         let mut bootstrap_methods: Vec<_> = bootstrap_methods.into_iter().collect();
@@ -155,6 +155,8 @@ impl UnqualifiedName {
 
     // JDK names
     pub const ABS: Self = Self::name("abs");
+    pub const ACCESSMODE: Self = Self::name("AccessMode");
+    pub const ADD: Self = Self::name("add");
     pub const ADDEXACT: Self = Self::name("addExact");
     pub const ALLOCATE: Self = Self::name("allocate");
     pub const ALLOCATEDIRECT: Self = Self::name("allocateDirect");
@@ -164,21 +166,29 @@ impl UnqualifiedName {
     pub const ARRAYELEMENTGETTER: Self = Self::name("arrayElementGetter");
     pub const ARRAYELEMENTSETTER: Self = Self::name("arrayElementSetter");
     pub const ARRAYLENGTH: Self = Self::name("arrayLength");
+    pub const ASCOLLECTOR: Self = Self::name("asCollector");
     pub const ASREADONLYBUFFER: Self = Self::name("asReadOnlyBuffer");
     pub const ASTYPE: Self = Self::name("asType");
     pub const BIGENDIAN: Self = Self::name("BIG_ENDIAN");
     pub const BINDTO: Self = Self::name("bindTo");
     pub const BITCOUNT: Self = Self::name("bitCount");
+    pub const BYTEARRAYVIEWVARHANDLE: Self = Self::name("byteArrayViewVarHandle");
+    pub const BYTEBUFFERVIEWVARHANDLE: Self = Self::name("byteBufferViewVarHandle");
     pub const BYTEVALUE: Self = Self::name("byteValue");
     pub const CAPACITY: Self = Self::name("capacity");
     pub const CEIL: Self = Self::name("ceil");
     pub const CHANGERETURNTYPE: Self = Self::name("changeReturnType");
+    pub const CLOSE: Self = Self::name("close");
     pub const COLLECTARGUMENTS: Self = Self::name("collectArguments");
     pub const COMPARE: Self = Self::name("compare");
+    pub const COMPAREANDEXCHANGE: Self = Self::name("compareAndExchange");
+    pub const COMPAREANDSET: Self = Self::name("compareAndSet");
     pub const COMPAREUNSIGNED: Self = Self::name("compareUnsigned");
     pub const CONSTANT: Self = Self::name("constant");
+    pub const CONTAINS: Self = Self::name("contains");
     pub const COPYOF: Self = Self::name("copyOf");
     pub const COPYSIGN: Self = Self::name("copySign");
+    pub const CURRENTTHREAD: Self = Self::name("currentThread");
     pub const DIVIDEUNSIGNED: Self = Self::name("divideUnsigned");
     pub const DOUBLETORAWLONGBITS: Self = Self::name("doubleToRawLongBits");
     pub const DOUBLEVALUE: Self = Self::name("doubleValue");
@@ -194,15 +204,24 @@ impl UnqualifiedName {
     pub const FLOATTORAWINTBITS: Self = Self::name("floatToRawIntBits");
     pub const FLOATVALUE: Self = Self::name("floatValue");
     pub const FLOOR: Self = Self::name("floor");
+    pub const FULLFENCE: Self = Self::name("fullFence");
     pub const GET: Self = Self::name("get");
+    pub const GETANDADD: Self = Self::name("getAndAdd");
+    pub const GETANDBITWISEAND: Self = Self::name("getAndBitwiseAnd");
+    pub const GETANDBITWISEOR: Self = Self::name("getAndBitwiseOr");
+    pub const GETANDBITWISEXOR: Self = Self::name("getAndBitwiseXor");
+    pub const GETANDSET: Self = Self::name("getAndSet");
     pub const GETBYTES: Self = Self::name("getBytes");
     pub const GETCLASS: Self = Self::name("getClass");
+    pub const GETCLASSLOADER: Self = Self::name("getClassLoader");
     pub const GETDOUBLE: Self = Self::name("getDouble");
     pub const GETFLOAT: Self = Self::name("getFloat");
     pub const GETINT: Self = Self::name("getInt");
     pub const GETLONG: Self = Self::name("getLong");
+    pub const GETRESOURCEASSTREAM: Self = Self::name("getResourceAsStream");
     pub const GETSHORT: Self = Self::name("getShort");
     pub const GETTARGET: Self = Self::name("getTarget");
+    pub const GETVOLATILE: Self = Self::name("getVolatile");
     pub const GUARDWITHTEST: Self = Self::name("guardWithTest");
     pub const HASHCODE: Self = Self::name("hashCode");
     pub const IDENTITY: Self = Self::name("identity");
@@ -210,8 +229,10 @@ impl UnqualifiedName {
     pub const INTBITSTOFLOAT: Self = Self::name("intBitsToFloat");
     pub const INTVALUE: Self = Self::name("intValue");
     pub const INVOKEEXACT: Self = Self::name("invokeExact");
+    pub const INVOKEWITHARGUMENTS: Self = Self::name("invokeWithArguments");
     pub const ISASSIGNABLEFROM: Self = Self::name("isAssignableFrom");
     pub const LENGTH: Self = Self::name("length");
+    pub const LIMIT: Self = Self::name("limit");
     pub const LITTLEENDIAN: Self = Self::name("LITTLE_ENDIAN");
     pub const LONGBITSTODOUBLE: Self = Self::name("longBitsToDouble");
     pub const LONGVALUE: Self = Self::name("longValue");
@@ -222,6 +243,7 @@ impl UnqualifiedName {
     pub const MIN: Self = Self::name("min");
     pub const MINVALUE: Self = Self::name("MIN_VALUE");
     pub const NAN: Self = Self::name("NaN");
+    pub const NANOTIME: Self = Self::name("nanoTime");
     pub const NEGATIVEINFINITY: Self = Self::name("NEGATIVE_INFINITY");
     pub const NUMBEROFLEADINGZEROS: Self = Self::name("numberOfLeadingZeros");
     pub const NUMBEROFTRAILINGZEROS: Self = Self::name("numberOfTrailingZeros");
@@ -229,28 +251,40 @@ impl UnqualifiedName {
     pub const PARAMETERARRAY: Self = Self::name("parameterArray");
     pub const PARAMETERCOUNT: Self = Self::name("parameterCount");
     pub const PARAMETERTYPE: Self = Self::name("parameterType");
+    pub const PARK: Self = Self::name("park");
+    pub const PARKNANOS: Self = Self::name("parkNanos");
     pub const PERMUTEARGUMENTS: Self = Self::name("permuteArguments");
+    pub const POLL: Self = Self::name("poll");
     pub const POSITION: Self = Self::name("position");
     pub const POSITIVEINFINITY: Self = Self::name("POSITIVE_INFINITY");
     pub const PUT: Self = Self::name("put");
     pub const PUTDOUBLE: Self = Self::name("putDouble");
     pub const PUTFLOAT: Self = Self::name("putFloat");
+    pub const PUTIFABSENT: Self = Self::name("putIfAbsent");
     pub const PUTINT: Self = Self::name("putInt");
     pub const PUTLONG: Self = Self::name("putLong");
     pub const PUTSHORT: Self = Self::name("putShort");
+    pub const READALLBYTES: Self = Self::name("readAllBytes");
     pub const REMAINDERUNSIGNED: Self = Self::name("remainderUnsigned");
+    pub const REMOVE: Self = Self::name("remove");
     pub const RETURNTYPE: Self = Self::name("returnType");
     pub const RINT: Self = Self::name("rint");
     pub const ROTATELEFT: Self = Self::name("rotateLeft");
     pub const ROTATERIGHT: Self = Self::name("rotateRight");
+    pub const SET: Self = Self::name("set");
     pub const SETTARGET: Self = Self::name("setTarget");
+    pub const SETVOLATILE: Self = Self::name("setVolatile");
     pub const SHORTVALUE: Self = Self::name("shortValue");
     pub const SQRT: Self = Self::name("sqrt");
     pub const SYNCALL: Self = Self::name("syncAll");
+    pub const SYNCHRONIZEDMAP: Self = Self::name("synchronizedMap");
     pub const TOINTEXACT: Self = Self::name("toIntExact");
     pub const TYPE: Self = Self::name("type");
+    pub const UNPARK: Self = Self::name("unpark");
     pub const UPPERCASE_TYPE: Self = Self::name("TYPE");
+    pub const VALUEFROMMETHODNAME: Self = Self::name("valueFromMethodName");
     pub const VALUEOF: Self = Self::name("valueOf");
+    pub const VARHANDLEEXACTINVOKER: Self = Self::name("varHandleExactInvoker");
     pub const WRAP: Self = Self::name("wrap");
 
     // Special unqualified names - only these are allowed to have angle brackets in them
@@ -262,10 +296,16 @@ impl UnqualifiedName {
     pub const BOOTSTRAPEXTERNALMEMORY: Self = Self::name("bootstrapExternalMemory");
     pub const BOOTSTRAPEXTERNALTABLE: Self = Self::name("bootstrapExternalTable");
     pub const BOOTSTRAPTABLE: Self = Self::name("bootstrapTable");
+    pub const BOOTSTRAPVARHANDLE: Self = Self::name("bootstrapVarHandle");
     pub const BYTESTOPAGES: Self = Self::name("bytesToPages");
     pub const CALLINDIRECT: Self = Self::name("call_indirect");
+    pub const CALLINDIRECTISTARGET: Self = Self::name("callIndirectIsTarget");
+    pub const CALLINDIRECTMISS: Self = Self::name("callIndirectMiss");
     pub const COPYRESIZEDARRAY: Self = Self::name("copyResizedArray");
+    pub const COPYRESIZEDBYTEARRAY: Self = Self::name("copyResizedByteArray");
     pub const COPYRESIZEDBYTEBUFFER: Self = Self::name("copyResizedByteBuffer");
+    pub const EFFECTIVEADDRESS: Self = Self::name("effectiveAddress");
+    pub const EFFECTIVEADDRESS64: Self = Self::name("effectiveAddress64");
     pub const EXPORTS: Self = Self::name("exports");
     pub const EXTERNREFTABLEBOOTSTRAP: Self = Self::name("externrefTableBootstrap");
     pub const F32ABS: Self = Self::name("f32Abs");
@@ -277,6 +317,7 @@ impl UnqualifiedName {
     pub const F64CONVERTI64U: Self = Self::name("f64ConvertI64U");
     pub const F64TRUNC: Self = Self::name("f64Trunc");
     pub const FILLARRAYRANGE: Self = Self::name("fillArrayRange");
+    pub const FILLBYTEARRAYRANGE: Self = Self::name("fillByteArrayRange");
     pub const FILLBYTEBUFFERRANGE: Self = Self::name("fillByteBufferRange");
     pub const FUNCREFTABLEBOOTSTRAP: Self = Self::name("funcrefTableBootstrap");
     pub const I32DIVS: Self = Self::name("i32DivS");
@@ -340,7 +381,12 @@ impl BinaryName {
     pub const CALLSITE: Self = Self::name("java/lang/invoke/CallSite");
     pub const CHARSEQUENCE: Self = Self::name("java/lang/CharSequence");
     pub const CLASS: Self = Self::name("java/lang/Class");
+    pub const CLASSLOADER: Self = Self::name("java/lang/ClassLoader");
     pub const CLONEABLE: Self = Self::name("java/lang/Cloneable");
+    pub const COLLECTIONS: Self = Self::name("java/util/Collections");
+    pub const CONCURRENTHASHMAP: Self = Self::name("java/util/concurrent/ConcurrentHashMap");
+    pub const CONCURRENTLINKEDQUEUE: Self =
+        Self::name("java/util/concurrent/ConcurrentLinkedQueue");
     pub const CONSTANTCALLSITE: Self = Self::name("java/lang/invoke/ConstantCallSite");
     pub const DOUBLE: Self = Self::name("java/lang/Double");
     pub const ERROR: Self = Self::name("java/lang/Error");
@@ -348,7 +394,11 @@ impl BinaryName {
     pub const FLOAT: Self = Self::name("java/lang/Float");
     pub const HASHMAP: Self = Self::name("java/util/HashMap");
     pub const ILLEGALARGUMENTEXCEPTION: Self = Self::name("java/lang/IllegalArgumentException");
+    pub const INDEXOUTOFBOUNDSEXCEPTION: Self = Self::name("java/lang/IndexOutOfBoundsException");
+    pub const INFLATERINPUTSTREAM: Self = Self::name("java/util/zip/InflaterInputStream");
+    pub const INPUTSTREAM: Self = Self::name("java/io/InputStream");
     pub const INTEGER: Self = Self::name("java/lang/Integer");
+    pub const LOCKSUPPORT: Self = Self::name("java/util/concurrent/locks/LockSupport");
     pub const LONG: Self = Self::name("java/lang/Long");
     pub const MAP: Self = Self::name("java/util/Map");
     pub const MATH: Self = Self::name("java/lang/Math");
@@ -363,8 +413,13 @@ impl BinaryName {
     pub const SERIALIZABLE: Self = Self::name("java/io/Serializable");
     pub const STRING: Self = Self::name("java/lang/String");
     pub const SYSTEM: Self = Self::name("java/lang/System");
+    pub const THREAD: Self = Self::name("java/lang/Thread");
     pub const THROWABLE: Self = Self::name("java/lang/Throwable");
+    pub const VARHANDLE: Self = Self::name("java/lang/invoke/VarHandle");
+    pub const VARHANDLE_ACCESSMODE: Self = Self::name("java/lang/invoke/VarHandle$AccessMode");
     pub const VOID: Self = Self::name("java/lang/Void");
+    pub const WEAKHASHMAP: Self = Self::name("java/util/WeakHashMap");
+    pub const WEAKREFERENCE: Self = Self::name("java/lang/ref/WeakReference");
 }
 
 #[cfg(test)]
@@ -0,0 +1,101 @@
+use super::{RuntimeClasses, WasmRuntime};
+use crate::jvm::class_graph::{
+    ClassGraph, FieldData, FieldId, JavaClasses, JavaLibrary, MethodData, MethodId,
+};
+use crate::jvm::code::{CodeBuilder, CodeBuilderExts};
+use crate::jvm::model::{Class, Field, Method};
+use crate::jvm::{
+    Error, FieldAccessFlags, FieldType, MethodAccessFlags, MethodDescriptor, Name, RefType,
+    UnqualifiedName,
+};
+
+/// Members of `org.wasm2jar.WasmException`
+pub struct WasmExceptionMembers<'g> {
+    pub init: MethodId<'g>,
+
+    /// Tag of the exception
+    pub tag: FieldId<'g>,
+
+    /// Boxed values carried by the exception
+    pub payload: FieldId<'g>,
+}
+
+impl<'g> WasmExceptionMembers<'g> {
+    pub fn add_to_graph(
+        class_graph: &ClassGraph<'g>,
+        java_classes: &JavaClasses<'g>,
+        classes: &RuntimeClasses<'g>,
+    ) -> WasmExceptionMembers<'g> {
+        let class = classes.wasm_exception;
+        let init = class_graph.add_method(MethodData {
+            class,
+            name: UnqualifiedName::INIT,
+            access_flags: MethodAccessFlags::PUBLIC,
+            descriptor: MethodDescriptor {
+                parameters: vec![
+                    FieldType::object(classes.tag),
+                    FieldType::array(FieldType::object(java_classes.lang.object)),
+                ],
+                return_type: None,
+            },
+        });
+        let tag = class_graph.add_field(FieldData {
+            class,
+            name: UnqualifiedName::from_str_unsafe("tag"),
+            access_flags: FieldAccessFlags::PUBLIC | FieldAccessFlags::FINAL,
+            descriptor: FieldType::object(classes.tag),
+        });
+        let payload = class_graph.add_field(FieldData {
+            class,
+            name: UnqualifiedName::from_str_unsafe("payload"),
+            access_flags: FieldAccessFlags::PUBLIC | FieldAccessFlags::FINAL,
+            descriptor: FieldType::array(FieldType::object(java_classes.lang.object)),
+        });
+
+        WasmExceptionMembers { init, tag, payload }
+    }
+}
+
+pub fn make_wasm_exception_class<'g>(
+    class_graph: &'g ClassGraph<'g>,
+    java: &'g JavaLibrary<'g>,
+    runtime: &WasmRuntime<'g>,
+) -> Result<Class<'g>, Error> {
+    use crate::jvm::code::{BranchInstruction::*, Instruction::*, InvokeType};
+
+    let mut class = Class::new(runtime.classes.wasm_exception);
+    class.add_field(Field::new(runtime.members.wasm_exception.tag));
+    class.add_field(Field::new(runtime.members.wasm_exception.payload));
+
+    /* WASM exceptions are used for control flow, so skip filling in the stack trace
+     *
+     * public WasmException(Tag tag, Object[] payload) {
+     *   super(null, null, false, false);
+     *   this.tag = tag;
+     *   this.payload = payload;
+     * }
+     */
+    let mut code = CodeBuilder::new(class_graph, java, runtime.members.wasm_exception.init);
+    code.push_instruction(ALoad(0))?;
+    code.const_null(RefType::Object(java.classes.lang.string))?;
+    code.const_null(RefType::Object(java.classes.lang.throwable))?;
+    code.const_int(0)?;
+    code.const_int(0)?;
+    code.push_instruction(Invoke(
+        InvokeType::Special,
+        java.members.lang.runtime_exception.init_with_flags,
+    ))?;
+    code.push_instruction(ALoad(0))?;
+    code.push_instruction(ALoad(1))?;
+    code.push_instruction(PutField(runtime.members.wasm_exception.tag))?;
+    code.push_instruction(ALoad(0))?;
+    code.push_instruction(ALoad(2))?;
+    code.push_instruction(PutField(runtime.members.wasm_exception.payload))?;
+    code.push_branch_instruction(Return)?;
+
+    let mut constructor = Method::new(runtime.members.wasm_exception.init);
+    constructor.code_impl = Some(code.result()?);
+    class.add_method(constructor);
+
+    Ok(class)
+}
@@ -2,16 +2,33 @@ use super::{RuntimeClasses, WasmRuntime};
 use crate::jvm::class_graph::{
     ClassGraph, FieldData, FieldId, JavaClasses, JavaLibrary, MethodData, MethodId,
 };
-use crate::jvm::code::CodeBuilder;
+use crate::jvm::code::{CodeBuilder, CodeBuilderExts};
 use crate::jvm::model::{Class, Field, Method};
 use crate::jvm::{
-    Error, FieldAccessFlags, FieldType, MethodAccessFlags, MethodDescriptor, Name, UnqualifiedName,
+    Error, FieldAccessFlags, FieldType, MethodAccessFlags, MethodDescriptor, Name, RefType,
+    UnqualifiedName,
 };
+use crate::translate::MemoryBackend;
 
 /// Members of `org.wasm2jar.Memory`
 pub struct MemoryMembers<'g> {
     pub init: MethodId<'g>,
+
+    /// Bytes of the memory (a `ByteBuffer` or `byte[]`, depending on the memory backend)
     pub bytes: FieldId<'g>,
+
+    /// Queues of threads parked in `memory.atomic.wait*`, keyed by address
+    pub waiters: FieldId<'g>,
+
+    /// `memory.size` of a shared memory (only for `ByteBuffer` backends)
+    pub size: Option<MethodId<'g>>,
+
+    /// `memory.grow` of a shared memory (only for `ByteBuffer` backends)
+    ///
+    /// Shared memories are allocated up front with their maximum size, and growing only moves the
+    /// limit of the buffer. Since the buffer is never replaced, writes from other threads can't
+    /// get lost in the middle of a copy.
+    pub grow: Option<MethodId<'g>>,
 }
 
 impl<'g> MemoryMembers<'g> {
@@ -19,14 +36,16 @@ impl<'g> MemoryMembers<'g> {
         class_graph: &ClassGraph<'g>,
         java_classes: &JavaClasses<'g>,
         classes: &RuntimeClasses<'g>,
+        memory_backend: MemoryBackend,
     ) -> MemoryMembers<'g> {
         let class = classes.memory;
+        let bytes_type = memory_backend.bytes_type(java_classes);
         let init = class_graph.add_method(MethodData {
             class,
             name: UnqualifiedName::INIT,
             access_flags: MethodAccessFlags::PUBLIC,
             descriptor: MethodDescriptor {
-                parameters: vec![FieldType::object(java_classes.nio.byte_buffer)],
+                parameters: vec![bytes_type],
                 return_type: None,
             },
         });
@@ -34,10 +53,42 @@ impl<'g> MemoryMembers<'g> {
             class,
             name: UnqualifiedName::from_str_unsafe("bytes"),
             access_flags: FieldAccessFlags::PUBLIC,
-            descriptor: FieldType::object(java_classes.nio.byte_buffer),
+            descriptor: bytes_type,
         });
+        let waiters = class_graph.add_field(FieldData {
+            class,
+            name: UnqualifiedName::from_str_unsafe("waiters"),
+            access_flags: FieldAccessFlags::PUBLIC | FieldAccessFlags::FINAL,
+            descriptor: FieldType::object(java_classes.util.map),
+        });
+
+        let (size, grow) = match memory_backend {
+            MemoryBackend::HeapByteBuffer | MemoryBackend::DirectByteBuffer => {
+                let add_method = |name: &'static str, parameters: Vec<FieldType<_>>| {
+                    class_graph.add_method(MethodData {
+                        class,
+                        name: UnqualifiedName::from_str_unsafe(name),
+                        access_flags: MethodAccessFlags::PUBLIC | MethodAccessFlags::SYNCHRONIZED,
+                        descriptor: MethodDescriptor {
+                            parameters,
+                            return_type: Some(FieldType::int()),
+                        },
+                    })
+                };
+                let size = add_method("size", vec![]);
+                let grow = add_method("grow", vec![FieldType::int()]);
+                (Some(size), Some(grow))
+            }
+            MemoryBackend::ByteArray => (None, None),
+        };
 
-        MemoryMembers { init, bytes }
+        MemoryMembers {
+            init,
+            bytes,
+            waiters,
+            size,
+            grow,
+        }
     }
 }
 
@@ -46,10 +97,13 @@ pub fn make_memory_class<'g>(
     java: &'g JavaLibrary<'g>,
     runtime: &WasmRuntime<'g>,
 ) -> Result<Class<'g>, Error> {
-    use crate::jvm::code::{BranchInstruction::*, Instruction::*, InvokeType};
+    use crate::jvm::code::{
+        BranchInstruction::*, Instruction::*, InvokeType, OrdComparison, ShiftType,
+    };
 
     let mut class = Class::new(runtime.classes.memory);
     class.add_field(Field::new(runtime.members.memory.bytes));
+    class.add_field(Field::new(runtime.members.memory.waiters));
 
     // Constructor
     let mut code = CodeBuilder::new(class_graph, java, runtime.members.memory.init);
@@ -58,11 +112,87 @@ pub fn make_memory_class<'g>(
     code.push_instruction(ALoad(0))?;
     code.push_instruction(ALoad(1))?;
     code.push_instruction(PutField(runtime.members.memory.bytes))?;
+    code.push_instruction(ALoad(0))?;
+    code.push_instruction(New(RefType::Object(
+        java.classes.util.concurrent.concurrent_hash_map,
+    )))?;
+    code.push_instruction(Dup)?;
+    code.push_instruction(Invoke(
+        InvokeType::Special,
+        java.members.util.concurrent.concurrent_hash_map.init,
+    ))?;
+    code.push_instruction(PutField(runtime.members.memory.waiters))?;
     code.push_branch_instruction(Return)?;
 
     let mut constructor = Method::new(runtime.members.memory.init);
     constructor.code_impl = Some(code.result()?);
     class.add_method(constructor);
 
+    /* synchronized int size() {
+     *   return this.bytes.limit() >>> 16;
+     * }
+     */
+    if let Some(size) = runtime.members.memory.size {
+        let mut code = CodeBuilder::new(class_graph, java, size);
+        code.push_instruction(ALoad(0))?;
+        code.push_instruction(GetField(runtime.members.memory.bytes))?;
+        code.invoke(java.members.nio.buffer.limit)?;
+        code.const_int(16)?;
+        code.push_instruction(ISh(ShiftType::LogicalRight))?;
+        code.push_branch_instruction(IReturn)?;
+
+        let mut method = Method::new(size);
+        method.code_impl = Some(code.result()?);
+        class.add_method(method);
+    }
+
+    /* synchronized int grow(int pages) {
+     *   ByteBuffer bytes = this.bytes;
+     *   int oldPages = bytes.limit() >>> 16;
+     *   if (pages < 0 || pages > (bytes.capacity() >>> 16) - oldPages) return -1;
+     *   bytes.limit((oldPages + pages) << 16);
+     *   return oldPages;
+     * }
+     */
+    if let Some(grow) = runtime.members.memory.grow {
+        let mut code = CodeBuilder::new(class_graph, java, grow);
+        let failed = code.fresh_label();
+        code.push_instruction(ALoad(0))?;
+        code.push_instruction(GetField(runtime.members.memory.bytes))?;
+        code.push_instruction(AStore(2))?;
+        code.push_instruction(ALoad(2))?;
+        code.invoke(java.members.nio.buffer.limit)?;
+        code.const_int(16)?;
+        code.push_instruction(ISh(ShiftType::LogicalRight))?;
+        code.push_instruction(IStore(3))?;
+        code.push_instruction(ILoad(1))?;
+        code.push_branch_instruction(If(OrdComparison::LT, failed, ()))?;
+        code.push_instruction(ILoad(1))?;
+        code.push_instruction(ALoad(2))?;
+        code.invoke(java.members.nio.buffer.capacity)?;
+        code.const_int(16)?;
+        code.push_instruction(ISh(ShiftType::LogicalRight))?;
+        code.push_instruction(ILoad(3))?;
+        code.push_instruction(ISub)?;
+        code.push_branch_instruction(IfICmp(OrdComparison::GT, failed, ()))?;
+        code.push_instruction(ALoad(2))?;
+        code.push_instruction(ILoad(3))?;
+        code.push_instruction(ILoad(1))?;
+        code.push_instruction(IAdd)?;
+        code.const_int(16)?;
+        code.push_instruction(ISh(ShiftType::Left))?;
+        code.invoke(java.members.nio.buffer.set_limit)?;
+        code.push_instruction(Pop)?;
+        code.push_instruction(ILoad(3))?;
+        code.push_branch_instruction(IReturn)?;
+        code.place_label(failed)?;
+        code.push_instruction(IConstM1)?;
+        code.push_branch_instruction(IReturn)?;
+
+        let mut method = Method::new(grow);
+        method.code_impl = Some(code.result()?);
+        class.add_method(method);
+    }
+
     Ok(class)
 }
@@ -0,0 +1,984 @@
+use super::{RuntimeClasses, WasmRuntime};
+use crate::jvm::class_graph::{
+    AccessMode, ClassGraph, FieldData, FieldId, JavaClasses, JavaLibrary, MethodData, MethodId,
+};
+use crate::jvm::code::{
+    BranchInstruction, CodeBuilder, CodeBuilderExts, Instruction, OrdComparison, ShiftType,
+    SynLabel, VerifierInstruction,
+};
+use crate::jvm::model::{Class, Field, Method};
+use crate::jvm::{
+    BaseType, Error, FieldAccessFlags, FieldType, MethodAccessFlags, MethodDescriptor, Name,
+    RefType, UnqualifiedName,
+};
+use crate::util::Width;
+
+/// Each chunk of a 64-bit memory is exactly one WASM page
+const CHUNK_BITS: i32 = 16;
+const CHUNK_SIZE: i32 = 1 << CHUNK_BITS;
+
+/// Members of `org.wasm2jar.Memory64`
+///
+/// Unlike `org.wasm2jar.Memory`, the memory is split across an array of `ByteBuffer` chunks (one
+/// per page) so that it can grow past the 2 GiB limit of a single `ByteBuffer`. All accesses go
+/// through methods on the memory which take `long` addresses.
+pub struct Memory64Members<'g> {
+    /// Constructor taking the initial and maximum sizes (in pages)
+    pub init: MethodId<'g>,
+
+    /// Chunks of the memory, each of which is `CHUNK_SIZE` bytes
+    pub chunks: FieldId<'g>,
+
+    /// Maximum number of pages (never more than `Integer.MAX_VALUE`)
+    pub maximum: FieldId<'g>,
+
+    /// Get the chunk containing an address, throwing if there is none
+    pub chunk: MethodId<'g>,
+
+    /// Check that a range of addresses is in bounds
+    pub check_range: MethodId<'g>,
+
+    /// Read up to 8 bytes (which may straddle chunks) into a little-endian `ByteBuffer`
+    pub read_straddled: MethodId<'g>,
+
+    /// Write up to 8 bytes (which may straddle chunks) from a little-endian `ByteBuffer`
+    pub write_straddled: MethodId<'g>,
+
+    pub get_byte: MethodId<'g>,
+    pub put_byte: MethodId<'g>,
+    pub get_short: MethodId<'g>,
+    pub put_short: MethodId<'g>,
+    pub get_int: MethodId<'g>,
+    pub put_int: MethodId<'g>,
+    pub get_float: MethodId<'g>,
+    pub put_float: MethodId<'g>,
+    pub get_long: MethodId<'g>,
+    pub put_long: MethodId<'g>,
+    pub get_double: MethodId<'g>,
+    pub put_double: MethodId<'g>,
+
+    /// `memory.size`
+    pub size: MethodId<'g>,
+
+    /// `memory.grow`
+    pub grow: MethodId<'g>,
+
+    /// `memory.fill`
+    pub fill: MethodId<'g>,
+
+    /// `memory.copy` into this memory from another (or the same) memory
+    pub copy: MethodId<'g>,
+
+    /// Copy a slice of a byte array into memory (for `memory.init` and active data segments)
+    pub write: MethodId<'g>,
+}
+
+impl<'g> Memory64Members<'g> {
+    pub fn add_to_graph(
+        class_graph: &ClassGraph<'g>,
+        java_classes: &JavaClasses<'g>,
+        classes: &RuntimeClasses<'g>,
+    ) -> Memory64Members<'g> {
+        let class = classes.memory64;
+        let byte_buffer = FieldType::object(java_classes.nio.byte_buffer);
+        let add_method = |name: &'static str,
+                          parameters: Vec<FieldType<_>>,
+                          return_type: Option<FieldType<_>>| {
+            class_graph.add_method(MethodData {
+                class,
+                name: UnqualifiedName::from_str_unsafe(name),
+                access_flags: MethodAccessFlags::PUBLIC,
+                descriptor: MethodDescriptor {
+                    parameters,
+                    return_type,
+                },
+            })
+        };
+
+        let init = class_graph.add_method(MethodData {
+            class,
+            name: UnqualifiedName::INIT,
+            access_flags: MethodAccessFlags::PUBLIC,
+            descriptor: MethodDescriptor {
+                parameters: vec![FieldType::long(), FieldType::long()],
+                return_type: None,
+            },
+        });
+        let chunks = class_graph.add_field(FieldData {
+            class,
+            name: UnqualifiedName::from_str_unsafe("chunks"),
+            access_flags: FieldAccessFlags::PUBLIC,
+            descriptor: FieldType::array(byte_buffer),
+        });
+        let maximum = class_graph.add_field(FieldData {
+            class,
+            name: UnqualifiedName::from_str_unsafe("maximum"),
+            access_flags: FieldAccessFlags::PUBLIC | FieldAccessFlags::FINAL,
+            descriptor: FieldType::long(),
+        });
+
+        let chunk = add_method("chunk", vec![FieldType::long()], Some(byte_buffer));
+        let check_range = add_method(
+            "checkRange",
+            vec![FieldType::long(), FieldType::long()],
+            None,
+        );
+        let read_straddled = add_method(
+            "readStraddled",
+            vec![FieldType::long(), FieldType::int()],
+            Some(byte_buffer),
+        );
+        let write_straddled = add_method(
+            "writeStraddled",
+            vec![FieldType::long(), byte_buffer, FieldType::int()],
+            None,
+        );
+
+        let add_get = |name: &'static str, typ: FieldType<_>| {
+            add_method(name, vec![FieldType::long()], Some(typ))
+        };
+        let get_byte = add_get("getByte", FieldType::byte());
+        let get_short = add_get("getShort", FieldType::short());
+        let get_int = add_get("getInt", FieldType::int());
+        let get_float = add_get("getFloat", FieldType::float());
+        let get_long = add_get("getLong", FieldType::long());
+        let get_double = add_get("getDouble", FieldType::double());
+
+        let add_put = |name: &'static str, typ: FieldType<_>| {
+            add_method(name, vec![FieldType::long(), typ], None)
+        };
+        let put_byte = add_put("putByte", FieldType::byte());
+        let put_short = add_put("putShort", FieldType::short());
+        let put_int = add_put("putInt", FieldType::int());
+        let put_float = add_put("putFloat", FieldType::float());
+        let put_long = add_put("putLong", FieldType::long());
+        let put_double = add_put("putDouble", FieldType::double());
+
+        let size = add_method("size", vec![], Some(FieldType::long()));
+        let grow = add_method("grow", vec![FieldType::long()], Some(FieldType::long()));
+        let fill = add_method(
+            "fill",
+            vec![FieldType::long(), FieldType::int(), FieldType::long()],
+            None,
+        );
+        let copy = add_method(
+            "copy",
+            vec![
+                FieldType::long(),
+                FieldType::object(class),
+                FieldType::long(),
+                FieldType::long(),
+            ],
+            None,
+        );
+        let write = add_method(
+            "write",
+            vec![
+                FieldType::long(),
+                FieldType::array(FieldType::byte()),
+                FieldType::int(),
+                FieldType::int(),
+            ],
+            None,
+        );
+
+        Memory64Members {
+            init,
+            chunks,
+            maximum,
+            chunk,
+            check_range,
+            read_straddled,
+            write_straddled,
+            get_byte,
+            put_byte,
+            get_short,
+            put_short,
+            get_int,
+            put_int,
+            get_float,
+            put_float,
+            get_long,
+            put_long,
+            get_double,
+            put_double,
+            size,
+            grow,
+            fill,
+            copy,
+            write,
+        }
+    }
+
+    /// Method for loading a value of the given type from memory
+    pub fn get(&self, ty: BaseType) -> MethodId<'g> {
+        match ty {
+            BaseType::Byte => self.get_byte,
+            BaseType::Short => self.get_short,
+            BaseType::Int => self.get_int,
+            BaseType::Float => self.get_float,
+            BaseType::Long => self.get_long,
+            BaseType::Double => self.get_double,
+            t => panic!("Cannot get {:?}", t),
+        }
+    }
+
+    /// Method for storing a value of the given type into memory
+    pub fn put(&self, ty: BaseType) -> MethodId<'g> {
+        match ty {
+            BaseType::Byte => self.put_byte,
+            BaseType::Short => self.put_short,
+            BaseType::Int => self.put_int,
+            BaseType::Float => self.put_float,
+            BaseType::Long => self.put_long,
+            BaseType::Double => self.put_double,
+            t => panic!("Cannot put {:?}", t),
+        }
+    }
+}
+
+pub fn make_memory64_class<'g>(
+    class_graph: &'g ClassGraph<'g>,
+    java: &'g JavaLibrary<'g>,
+    runtime: &WasmRuntime<'g>,
+) -> Result<Class<'g>, Error> {
+    use crate::jvm::code::{BranchInstruction::*, Instruction::*, InvokeType};
+
+    let members = &runtime.members.memory64;
+    let byte_buffer = java.classes.nio.byte_buffer;
+    let chunks_type = RefType::array(FieldType::object(byte_buffer));
+
+    let mut class = Class::new(runtime.classes.memory64);
+    class.add_field(Field::new(members.chunks));
+    class.add_field(Field::new(members.maximum));
+
+    let mut add_method = |code: CodeBuilder<'g>, method_id: MethodId<'g>| -> Result<(), Error> {
+        let mut method = Method::new(method_id);
+        method.code_impl = Some(code.result()?);
+        class.add_method(method);
+        Ok(())
+    };
+
+    /* Memory64(long initial, long maximum) {
+     *   this.maximum = maximum;
+     *   this.chunks = new ByteBuffer[0];
+     *   this.grow(initial);
+     * }
+     */
+    let mut code = CodeBuilder::new(class_graph, java, members.init);
+    code.push_instruction(ALoad(0))?;
+    code.push_instruction(Invoke(InvokeType::Special, java.members.lang.object.init))?;
+    code.push_instruction(ALoad(0))?;
+    code.push_instruction(LLoad(3))?;
+    code.push_instruction(PutField(members.maximum))?;
+    code.push_instruction(ALoad(0))?;
+    code.push_instruction(IConst0)?;
+    code.new_ref_array(RefType::Object(byte_buffer))?;
+    code.push_instruction(PutField(members.chunks))?;
+    code.push_instruction(ALoad(0))?;
+    code.push_instruction(LLoad(1))?;
+    code.invoke(members.grow)?;
+    code.push_instruction(Pop2)?;
+    code.push_branch_instruction(Return)?;
+    add_method(code, members.init)?;
+
+    /* ByteBuffer chunk(long address) {
+     *   ByteBuffer[] chunks = this.chunks;
+     *   long index = address >>> CHUNK_BITS;
+     *   if (index >= chunks.length) throw new IndexOutOfBoundsException(..);
+     *   return chunks[(int) index];
+     * }
+     */
+    let mut code = CodeBuilder::new(class_graph, java, members.chunk);
+    let out_of_bounds = code.fresh_label();
+    code.push_instruction(ALoad(0))?;
+    code.push_instruction(GetField(members.chunks))?;
+    code.push_instruction(AStore(3))?;
+    code.push_instruction(LLoad(1))?;
+    code.const_int(CHUNK_BITS)?;
+    code.push_instruction(LSh(ShiftType::LogicalRight))?;
+    code.push_instruction(LStore(4))?;
+    code.push_instruction(LLoad(4))?;
+    code.push_instruction(ALoad(3))?;
+    code.push_instruction(ArrayLength)?;
+    code.push_instruction(I2L)?;
+    code.push_instruction(LCmp)?;
+    code.push_branch_instruction(If(OrdComparison::GE, out_of_bounds, ()))?;
+    code.push_instruction(ALoad(3))?;
+    code.push_instruction(LLoad(4))?;
+    code.push_instruction(L2I)?;
+    code.push_instruction(AALoad)?;
+    code.push_branch_instruction(AReturn)?;
+    code.place_label(out_of_bounds)?;
+    throw_out_of_bounds(&mut code)?;
+    add_method(code, members.chunk)?;
+
+    /* void checkRange(long address, long length) {
+     *   long size = (long) this.chunks.length << CHUNK_BITS;
+     *   if (Long.compareUnsigned(address, size) > 0) throw new IndexOutOfBoundsException(..);
+     *   if (Long.compareUnsigned(length, size - address) > 0) throw new IndexOutOfBoundsException(..);
+     * }
+     */
+    let mut code = CodeBuilder::new(class_graph, java, members.check_range);
+    let out_of_bounds = code.fresh_label();
+    code.push_instruction(ALoad(0))?;
+    code.push_instruction(GetField(members.chunks))?;
+    code.push_instruction(ArrayLength)?;
+    code.push_instruction(I2L)?;
+    code.const_int(CHUNK_BITS)?;
+    code.push_instruction(LSh(ShiftType::Left))?;
+    code.push_instruction(LStore(5))?;
+    code.push_instruction(LLoad(1))?;
+    code.push_instruction(LLoad(5))?;
+    code.invoke(java.members.lang.long.compare_unsigned)?;
+    code.push_branch_instruction(If(OrdComparison::GT, out_of_bounds, ()))?;
+    code.push_instruction(LLoad(3))?;
+    code.push_instruction(LLoad(5))?;
+    code.push_instruction(LLoad(1))?;
+    code.push_instruction(LSub)?;
+    code.invoke(java.members.lang.long.compare_unsigned)?;
+    code.push_branch_instruction(If(OrdComparison::GT, out_of_bounds, ()))?;
+    code.push_branch_instruction(Return)?;
+    code.place_label(out_of_bounds)?;
+    throw_out_of_bounds(&mut code)?;
+    add_method(code, members.check_range)?;
+
+    /* ByteBuffer readStraddled(long address, int width) {
+     *   ByteBuffer bytes = ByteBuffer.allocate(8).order(ByteOrder.LITTLE_ENDIAN);
+     *   for (int i = 0; i < width; i++) {
+     *     bytes.put(i, this.getByte(address + i));
+     *   }
+     *   return bytes;
+     * }
+     */
+    let mut code = CodeBuilder::new(class_graph, java, members.read_straddled);
+    let loop_start = code.fresh_label();
+    let loop_end = code.fresh_label();
+    allocate_little_endian(&mut code, 8)?;
+    code.push_instruction(AStore(4))?;
+    code.push_instruction(IConst0)?;
+    code.push_instruction(IStore(5))?;
+    code.place_label(loop_start)?;
+    code.push_instruction(ILoad(5))?;
+    code.push_instruction(ILoad(3))?;
+    code.push_branch_instruction(IfICmp(OrdComparison::GE, loop_end, ()))?;
+    code.push_instruction(ALoad(4))?;
+    code.push_instruction(ILoad(5))?;
+    code.push_instruction(ALoad(0))?;
+    code.push_instruction(LLoad(1))?;
+    code.push_instruction(ILoad(5))?;
+    code.push_instruction(I2L)?;
+    code.push_instruction(LAdd)?;
+    code.invoke(members.get_byte)?;
+    code.invoke(java.members.nio.byte_buffer.put_byte)?;
+    code.push_instruction(Pop)?;
+    code.push_instruction(IInc(5, 1))?;
+    code.push_branch_instruction(Goto(loop_start))?;
+    code.place_label(loop_end)?;
+    code.push_instruction(ALoad(4))?;
+    code.push_branch_instruction(AReturn)?;
+    add_method(code, members.read_straddled)?;
+
+    /* void writeStraddled(long address, ByteBuffer bytes, int width) {
+     *   this.chunk(address + width - 1); // bounds check before writing anything
+     *   for (int i = 0; i < width; i++) {
+     *     this.putByte(address + i, bytes.get(i));
+     *   }
+     * }
+     */
+    let mut code = CodeBuilder::new(class_graph, java, members.write_straddled);
+    let loop_start = code.fresh_label();
+    let loop_end = code.fresh_label();
+    code.push_instruction(ALoad(0))?;
+    code.push_instruction(LLoad(1))?;
+    code.push_instruction(ILoad(4))?;
+    code.push_instruction(I2L)?;
+    code.push_instruction(LAdd)?;
+    code.push_instruction(LConst1)?;
+    code.push_instruction(LSub)?;
+    code.invoke(members.chunk)?;
+    code.push_instruction(Pop)?;
+    code.push_instruction(IConst0)?;
+    code.push_instruction(IStore(5))?;
+    code.place_label(loop_start)?;
+    code.push_instruction(ILoad(5))?;
+    code.push_instruction(ILoad(4))?;
+    code.push_branch_instruction(IfICmp(OrdComparison::GE, loop_end, ()))?;
+    code.push_instruction(ALoad(0))?;
+    code.push_instruction(LLoad(1))?;
+    code.push_instruction(ILoad(5))?;
+    code.push_instruction(I2L)?;
+    code.push_instruction(LAdd)?;
+    code.push_instruction(ALoad(3))?;
+    code.push_instruction(ILoad(5))?;
+    code.invoke(java.members.nio.byte_buffer.get_byte)?;
+    code.invoke(members.put_byte)?;
+    code.push_instruction(IInc(5, 1))?;
+    code.push_branch_instruction(Goto(loop_start))?;
+    code.place_label(loop_end)?;
+    code.push_branch_instruction(Return)?;
+    add_method(code, members.write_straddled)?;
+
+    // Loads and stores (with the width of the access in bytes)
+    let accessors = [
+        (
+            BaseType::Byte,
+            1,
+            java.members.nio.byte_buffer.get_byte,
+            java.members.nio.byte_buffer.put_byte,
+        ),
+        (
+            BaseType::Short,
+            2,
+            java.members.nio.byte_buffer.get_short,
+            java.members.nio.byte_buffer.put_short,
+        ),
+        (
+            BaseType::Int,
+            4,
+            java.members.nio.byte_buffer.get_int,
+            java.members.nio.byte_buffer.put_int,
+        ),
+        (
+            BaseType::Float,
+            4,
+            java.members.nio.byte_buffer.get_float,
+            java.members.nio.byte_buffer.put_float,
+        ),
+        (
+            BaseType::Long,
+            8,
+            java.members.nio.byte_buffer.get_long,
+            java.members.nio.byte_buffer.put_long,
+        ),
+        (
+            BaseType::Double,
+            8,
+            java.members.nio.byte_buffer.get_double,
+            java.members.nio.byte_buffer.put_double,
+        ),
+    ];
+    for (ty, width, buffer_get, buffer_put) in accessors {
+        let field_type = FieldType::Base(ty);
+
+        /* T getT(long address) {
+         *   int offset = (int) address & (CHUNK_SIZE - 1);
+         *   if (offset > CHUNK_SIZE - width) return this.readStraddled(address, width).getT(0);
+         *   return this.chunk(address).getT(offset);
+         * }
+         */
+        let mut code = CodeBuilder::new(class_graph, java, members.get(ty));
+        let straddled = code.fresh_label();
+        chunk_offset(&mut code, 1, 3)?;
+        if width > 1 {
+            code.push_instruction(ILoad(3))?;
+            code.const_int(CHUNK_SIZE - width)?;
+            code.push_branch_instruction(IfICmp(OrdComparison::GT, straddled, ()))?;
+        }
+        code.push_instruction(ALoad(0))?;
+        code.push_instruction(LLoad(1))?;
+        code.invoke(members.chunk)?;
+        code.push_instruction(ILoad(3))?;
+        code.invoke(buffer_get)?;
+        code.return_(Some(field_type))?;
+        if width > 1 {
+            code.place_label(straddled)?;
+            code.push_instruction(ALoad(0))?;
+            code.push_instruction(LLoad(1))?;
+            code.const_int(width)?;
+            code.invoke(members.read_straddled)?;
+            code.push_instruction(IConst0)?;
+            code.invoke(buffer_get)?;
+            code.return_(Some(field_type))?;
+        }
+        add_method(code, members.get(ty))?;
+
+        /* void putT(long address, T value) {
+         *   int offset = (int) address & (CHUNK_SIZE - 1);
+         *   if (offset > CHUNK_SIZE - width) {
+         *     this.writeStraddled(
+         *       address,
+         *       ByteBuffer.allocate(8).order(ByteOrder.LITTLE_ENDIAN).putT(0, value),
+         *       width
+         *     );
+         *     return;
+         *   }
+         *   this.chunk(address).putT(offset, value);
+         * }
+         */
+        let mut code = CodeBuilder::new(class_graph, java, members.put(ty));
+        let straddled = code.fresh_label();
+        let offset_off = 3 + field_type.width() as u16;
+        chunk_offset(&mut code, 1, offset_off)?;
+        if width > 1 {
+            code.push_instruction(ILoad(offset_off))?;
+            code.const_int(CHUNK_SIZE - width)?;
+            code.push_branch_instruction(IfICmp(OrdComparison::GT, straddled, ()))?;
+        }
+        code.push_instruction(ALoad(0))?;
+        code.push_instruction(LLoad(1))?;
+        code.invoke(members.chunk)?;
+        code.push_instruction(ILoad(offset_off))?;
+        code.get_local(3, &field_type)?;
+        code.invoke(buffer_put)?;
+        code.push_instruction(Pop)?;
+        code.push_branch_instruction(Return)?;
+        if width > 1 {
+            code.place_label(straddled)?;
+            code.push_instruction(ALoad(0))?;
+            code.push_instruction(LLoad(1))?;
+            allocate_little_endian(&mut code, 8)?;
+            code.push_instruction(IConst0)?;
+            code.get_local(3, &field_type)?;
+            code.invoke(buffer_put)?;
+            code.const_int(width)?;
+            code.invoke(members.write_straddled)?;
+            code.push_branch_instruction(Return)?;
+        }
+        add_method(code, members.put(ty))?;
+    }
+
+    /* long size() {
+     *   return this.chunks.length;
+     * }
+     */
+    let mut code = CodeBuilder::new(class_graph, java, members.size);
+    code.push_instruction(ALoad(0))?;
+    code.push_instruction(GetField(members.chunks))?;
+    code.push_instruction(ArrayLength)?;
+    code.push_instruction(I2L)?;
+    code.push_branch_instruction(LReturn)?;
+    add_method(code, members.size)?;
+
+    /* long grow(long delta) {
+     *   ByteBuffer[] chunks = this.chunks;
+     *   long oldSize = chunks.length;
+     *   if (Long.compareUnsigned(delta, this.maximum - oldSize) > 0) return -1;
+     *   ByteBuffer[] grown = (ByteBuffer[]) Arrays.copyOf(chunks, (int) (oldSize + delta));
+     *   for (int i = chunks.length; i < grown.length; i++) {
+     *     grown[i] = ByteBuffer.allocate(CHUNK_SIZE).order(ByteOrder.LITTLE_ENDIAN);
+     *   }
+     *   this.chunks = grown;
+     *   return oldSize;
+     * }
+     */
+    let mut code = CodeBuilder::new(class_graph, java, members.grow);
+    let can_grow = code.fresh_label();
+    let loop_start = code.fresh_label();
+    let loop_end = code.fresh_label();
+    code.push_instruction(ALoad(0))?;
+    code.push_instruction(GetField(members.chunks))?;
+    code.push_instruction(AStore(3))?;
+    code.push_instruction(ALoad(3))?;
+    code.push_instruction(ArrayLength)?;
+    code.push_instruction(I2L)?;
+    code.push_instruction(LStore(4))?;
+    code.push_instruction(LLoad(1))?;
+    code.push_instruction(ALoad(0))?;
+    code.push_instruction(GetField(members.maximum))?;
+    code.push_instruction(LLoad(4))?;
+    code.push_instruction(LSub)?;
+    code.invoke(java.members.lang.long.compare_unsigned)?;
+    code.push_branch_instruction(If(OrdComparison::LE, can_grow, ()))?;
+    code.const_long(-1)?;
+    code.push_branch_instruction(LReturn)?;
+    code.place_label(can_grow)?;
+    code.push_instruction(ALoad(3))?;
+    code.push_instruction(LLoad(4))?;
+    code.push_instruction(LLoad(1))?;
+    code.push_instruction(LAdd)?;
+    code.push_instruction(L2I)?;
+    code.invoke(java.members.util.arrays.copy_of)?;
+    code.push_instruction(CheckCast(chunks_type))?;
+    code.push_instruction(AStore(6))?;
+    code.push_instruction(ALoad(3))?;
+    code.push_instruction(ArrayLength)?;
+    code.push_instruction(IStore(7))?;
+    code.place_label(loop_start)?;
+    code.push_instruction(ILoad(7))?;
+    code.push_instruction(ALoad(6))?;
+    code.push_instruction(ArrayLength)?;
+    code.push_branch_instruction(IfICmp(OrdComparison::GE, loop_end, ()))?;
+    code.push_instruction(ALoad(6))?;
+    code.push_instruction(ILoad(7))?;
+    allocate_little_endian(&mut code, CHUNK_SIZE)?;
+    code.push_instruction(AAStore)?;
+    code.push_instruction(IInc(7, 1))?;
+    code.push_branch_instruction(Goto(loop_start))?;
+    code.place_label(loop_end)?;
+    code.push_instruction(ALoad(0))?;
+    code.push_instruction(ALoad(6))?;
+    code.push_instruction(PutField(members.chunks))?;
+    code.push_instruction(LLoad(4))?;
+    code.push_branch_instruction(LReturn)?;
+    add_method(code, members.grow)?;
+
+    /* void fill(long address, int value, long length) {
+     *   this.checkRange(address, length);
+     *   while (length != 0) {
+     *     ByteBuffer chunk = this.chunk(address);
+     *     int offset = (int) address & (CHUNK_SIZE - 1);
+     *     int count = CHUNK_SIZE - offset;
+     *     if (length < count) count = (int) length;
+     *     for (int i = 0; i < count; i++) {
+     *       chunk.put(offset + i, (byte) value);
+     *     }
+     *     address += count;
+     *     length -= count;
+     *   }
+     * }
+     */
+    let mut code = CodeBuilder::new(class_graph, java, members.fill);
+    let loop_start = code.fresh_label();
+    let loop_end = code.fresh_label();
+    let count_chosen = code.fresh_label();
+    let inner_start = code.fresh_label();
+    let inner_end = code.fresh_label();
+    code.push_instruction(ALoad(0))?;
+    code.push_instruction(LLoad(1))?;
+    code.push_instruction(LLoad(4))?;
+    code.invoke(members.check_range)?;
+    code.place_label(loop_start)?;
+    code.push_instruction(LLoad(4))?;
+    code.push_instruction(LConst0)?;
+    code.push_instruction(LCmp)?;
+    code.push_branch_instruction(If(OrdComparison::EQ, loop_end, ()))?;
+    code.push_instruction(ALoad(0))?;
+    code.push_instruction(LLoad(1))?;
+    code.invoke(members.chunk)?;
+    code.push_instruction(AStore(6))?;
+    chunk_offset(&mut code, 1, 7)?;
+    code.const_int(CHUNK_SIZE)?;
+    code.push_instruction(ILoad(7))?;
+    code.push_instruction(ISub)?;
+    code.push_instruction(IStore(8))?;
+    clamp_count(&mut code, 4, 8, count_chosen)?;
+    code.push_instruction(IConst0)?;
+    code.push_instruction(IStore(9))?;
+    code.place_label(inner_start)?;
+    code.push_instruction(ILoad(9))?;
+    code.push_instruction(ILoad(8))?;
+    code.push_branch_instruction(IfICmp(OrdComparison::GE, inner_end, ()))?;
+    code.push_instruction(ALoad(6))?;
+    code.push_instruction(ILoad(7))?;
+    code.push_instruction(ILoad(9))?;
+    code.push_instruction(IAdd)?;
+    code.push_instruction(ILoad(3))?;
+    code.push_instruction(I2B)?;
+    code.invoke(java.members.nio.byte_buffer.put_byte)?;
+    code.push_instruction(Pop)?;
+    code.push_instruction(IInc(9, 1))?;
+    code.push_branch_instruction(Goto(inner_start))?;
+    code.place_label(inner_end)?;
+    advance_long(&mut code, 1, 8, LAdd)?;
+    advance_long(&mut code, 4, 8, LSub)?;
+    code.kill_top_local(9, Some(FieldType::int()))?;
+    code.kill_top_local(8, Some(FieldType::int()))?;
+    code.kill_top_local(7, Some(FieldType::int()))?;
+    code.kill_top_local(6, None)?;
+    code.push_branch_instruction(Goto(loop_start))?;
+    code.place_label(loop_end)?;
+    code.push_branch_instruction(Return)?;
+    add_method(code, members.fill)?;
+
+    /* void copy(long dst, Memory64 srcMemory, long src, long length) {
+     *   this.checkRange(dst, length);
+     *   srcMemory.checkRange(src, length);
+     *   if (dst <= src) {
+     *     while (length != 0) {
+     *       int dstOffset = (int) dst & (CHUNK_SIZE - 1);
+     *       int srcOffset = (int) src & (CHUNK_SIZE - 1);
+     *       int count = min(CHUNK_SIZE - dstOffset, CHUNK_SIZE - srcOffset, length);
+     *       this.chunk(dst).put(dstOffset, srcMemory.chunk(src), srcOffset, count);
+     *       dst += count; src += count; length -= count;
+     *     }
+     *   } else {
+     *     // Copy backwards, in case the ranges overlap
+     *     dst += length; src += length;
+     *     while (length != 0) {
+     *       int dstEnd = ((int) (dst - 1) & (CHUNK_SIZE - 1)) + 1;
+     *       int srcEnd = ((int) (src - 1) & (CHUNK_SIZE - 1)) + 1;
+     *       int count = min(dstEnd, srcEnd, length);
+     *       dst -= count; src -= count; length -= count;
+     *       this.chunk(dst).put((int) dst & (CHUNK_SIZE - 1), srcMemory.chunk(src), (int) src & (CHUNK_SIZE - 1), count);
+     *     }
+     *   }
+     * }
+     */
+    let mut code = CodeBuilder::new(class_graph, java, members.copy);
+    let backward = code.fresh_label();
+    let forward_start = code.fresh_label();
+    let backward_start = code.fresh_label();
+    let copy_end = code.fresh_label();
+    code.push_instruction(ALoad(0))?;
+    code.push_instruction(LLoad(1))?;
+    code.push_instruction(LLoad(6))?;
+    code.invoke(members.check_range)?;
+    code.push_instruction(ALoad(3))?;
+    code.push_instruction(LLoad(4))?;
+    code.push_instruction(LLoad(6))?;
+    code.invoke(members.check_range)?;
+    code.push_instruction(LLoad(1))?;
+    code.push_instruction(LLoad(4))?;
+    code.push_instruction(LCmp)?;
+    code.push_branch_instruction(If(OrdComparison::GT, backward, ()))?;
+
+    // Forward copy
+    code.place_label(forward_start)?;
+    code.push_instruction(LLoad(6))?;
+    code.push_instruction(LConst0)?;
+    code.push_instruction(LCmp)?;
+    code.push_branch_instruction(If(OrdComparison::EQ, copy_end, ()))?;
+    chunk_offset(&mut code, 1, 8)?;
+    chunk_offset(&mut code, 4, 9)?;
+    code.const_int(CHUNK_SIZE)?;
+    code.push_instruction(ILoad(8))?;
+    code.push_instruction(ISub)?;
+    code.push_instruction(IStore(10))?;
+    let src_room_chosen = code.fresh_label();
+    code.const_int(CHUNK_SIZE)?;
+    code.push_instruction(ILoad(9))?;
+    code.push_instruction(ISub)?;
+    code.push_instruction(ILoad(10))?;
+    code.push_branch_instruction(IfICmp(OrdComparison::GE, src_room_chosen, ()))?;
+    code.const_int(CHUNK_SIZE)?;
+    code.push_instruction(ILoad(9))?;
+    code.push_instruction(ISub)?;
+    code.push_instruction(IStore(10))?;
+    code.place_label(src_room_chosen)?;
+    let count_chosen = code.fresh_label();
+    clamp_count(&mut code, 6, 10, count_chosen)?;
+    copy_chunk_slice(&mut code, &runtime.members.memory64, java, 8, 9)?;
+    advance_long(&mut code, 1, 10, LAdd)?;
+    advance_long(&mut code, 4, 10, LAdd)?;
+    advance_long(&mut code, 6, 10, LSub)?;
+    code.kill_top_local(10, Some(FieldType::int()))?;
+    code.kill_top_local(9, Some(FieldType::int()))?;
+    code.kill_top_local(8, Some(FieldType::int()))?;
+    code.push_branch_instruction(Goto(forward_start))?;
+
+    // Backward copy
+    code.place_label(backward)?;
+    code.push_instruction(LLoad(1))?;
+    code.push_instruction(LLoad(6))?;
+    code.push_instruction(LAdd)?;
+    code.push_instruction(LStore(1))?;
+    code.push_instruction(LLoad(4))?;
+    code.push_instruction(LLoad(6))?;
+    code.push_instruction(LAdd)?;
+    code.push_instruction(LStore(4))?;
+    code.place_label(backward_start)?;
+    code.push_instruction(LLoad(6))?;
+    code.push_instruction(LConst0)?;
+    code.push_instruction(LCmp)?;
+    code.push_branch_instruction(If(OrdComparison::EQ, copy_end, ()))?;
+    for (address_off, end_off) in [(1, 8), (4, 9)] {
+        code.push_instruction(LLoad(address_off))?;
+        code.push_instruction(LConst1)?;
+        code.push_instruction(LSub)?;
+        code.push_instruction(L2I)?;
+        code.const_int(CHUNK_SIZE - 1)?;
+        code.push_instruction(IAnd)?;
+        code.push_instruction(IConst1)?;
+        code.push_instruction(IAdd)?;
+        code.push_instruction(IStore(end_off))?;
+    }
+    code.push_instruction(ILoad(8))?;
+    code.push_instruction(IStore(10))?;
+    let src_end_chosen = code.fresh_label();
+    code.push_instruction(ILoad(9))?;
+    code.push_instruction(ILoad(10))?;
+    code.push_branch_instruction(IfICmp(OrdComparison::GE, src_end_chosen, ()))?;
+    code.push_instruction(ILoad(9))?;
+    code.push_instruction(IStore(10))?;
+    code.place_label(src_end_chosen)?;
+    let count_chosen = code.fresh_label();
+    clamp_count(&mut code, 6, 10, count_chosen)?;
+    advance_long(&mut code, 1, 10, LSub)?;
+    advance_long(&mut code, 4, 10, LSub)?;
+    advance_long(&mut code, 6, 10, LSub)?;
+    chunk_offset(&mut code, 1, 8)?;
+    chunk_offset(&mut code, 4, 9)?;
+    copy_chunk_slice(&mut code, &runtime.members.memory64, java, 8, 9)?;
+    code.kill_top_local(10, Some(FieldType::int()))?;
+    code.kill_top_local(9, Some(FieldType::int()))?;
+    code.kill_top_local(8, Some(FieldType::int()))?;
+    code.push_branch_instruction(Goto(backward_start))?;
+
+    code.place_label(copy_end)?;
+    code.push_branch_instruction(Return)?;
+    add_method(code, members.copy)?;
+
+    /* void write(long address, byte[] data, int offset, int length) {
+     *   if ((offset & 0xFFFFFFFFL) + (length & 0xFFFFFFFFL) > data.length) {
+     *     throw new IndexOutOfBoundsException(..);
+     *   }
+     *   this.checkRange(address, length & 0xFFFFFFFFL);
+     *   while (length != 0) {
+     *     int chunkOffset = (int) address & (CHUNK_SIZE - 1);
+     *     int count = CHUNK_SIZE - chunkOffset;
+     *     if (length < count) count = length;
+     *     this.chunk(address).put(chunkOffset, data, offset, count);
+     *     address += count; offset += count; length -= count;
+     *   }
+     * }
+     */
+    let mut code = CodeBuilder::new(class_graph, java, members.write);
+    let out_of_bounds = code.fresh_label();
+    let loop_start = code.fresh_label();
+    let loop_end = code.fresh_label();
+    let count_chosen = code.fresh_label();
+    code.push_instruction(ILoad(4))?;
+    code.push_instruction(I2L)?;
+    code.const_long(0xFFFF_FFFF)?;
+    code.push_instruction(LAnd)?;
+    code.push_instruction(ILoad(5))?;
+    code.push_instruction(I2L)?;
+    code.const_long(0xFFFF_FFFF)?;
+    code.push_instruction(LAnd)?;
+    code.push_instruction(LAdd)?;
+    code.push_instruction(ALoad(3))?;
+    code.push_instruction(ArrayLength)?;
+    code.push_instruction(I2L)?;
+    code.push_instruction(LCmp)?;
+    code.push_branch_instruction(If(OrdComparison::GT, out_of_bounds, ()))?;
+    code.push_instruction(ALoad(0))?;
+    code.push_instruction(LLoad(1))?;
+    code.push_instruction(ILoad(5))?;
+    code.push_instruction(I2L)?;
+    code.invoke(members.check_range)?;
+    code.place_label(loop_start)?;
+    code.push_instruction(ILoad(5))?;
+    code.push_branch_instruction(If(OrdComparison::EQ, loop_end, ()))?;
+    chunk_offset(&mut code, 1, 6)?;
+    code.const_int(CHUNK_SIZE)?;
+    code.push_instruction(ILoad(6))?;
+    code.push_instruction(ISub)?;
+    code.push_instruction(IStore(7))?;
+    code.push_instruction(ILoad(5))?;
+    code.push_instruction(ILoad(7))?;
+    code.push_branch_instruction(IfICmp(OrdComparison::GE, count_chosen, ()))?;
+    code.push_instruction(ILoad(5))?;
+    code.push_instruction(IStore(7))?;
+    code.place_label(count_chosen)?;
+    code.push_instruction(ALoad(0))?;
+    code.push_instruction(LLoad(1))?;
+    code.invoke(members.chunk)?;
+    code.push_instruction(ILoad(6))?;
+    code.push_instruction(ALoad(3))?;
+    code.push_instruction(ILoad(4))?;
+    code.push_instruction(ILoad(7))?;
+    code.invoke(java.members.nio.byte_buffer.put_bytearray)?;
+    code.push_instruction(Pop)?;
+    advance_long(&mut code, 1, 7, LAdd)?;
+    code.push_instruction(ILoad(4))?;
+    code.push_instruction(ILoad(7))?;
+    code.push_instruction(IAdd)?;
+    code.push_instruction(IStore(4))?;
+    code.push_instruction(ILoad(5))?;
+    code.push_instruction(ILoad(7))?;
+    code.push_instruction(ISub)?;
+    code.push_instruction(IStore(5))?;
+    code.kill_top_local(7, Some(FieldType::int()))?;
+    code.kill_top_local(6, Some(FieldType::int()))?;
+    code.push_branch_instruction(Goto(loop_start))?;
+    code.place_label(loop_end)?;
+    code.push_branch_instruction(Return)?;
+    code.place_label(out_of_bounds)?;
+    throw_out_of_bounds(&mut code)?;
+    add_method(code, members.write)?;
+
+    Ok(class)
+}
+
+/// Throw an `IndexOutOfBoundsException` for an out of bounds memory access
+fn throw_out_of_bounds<'g>(code: &mut CodeBuilder<'g>) -> Result<(), Error> {
+    let java = code.java;
+    code.new(java.classes.lang.index_out_of_bounds_exception)?;
+    code.push_instruction(Instruction::Dup)?;
+    code.const_string("out of bounds memory access")?;
+    code.invoke(java.members.lang.index_out_of_bounds_exception.init)?;
+    code.push_branch_instruction(BranchInstruction::AThrow)?;
+    Ok(())
+}
+
+/// Push a fresh little-endian heap `ByteBuffer` of the given capacity onto the stack
+fn allocate_little_endian<'g>(code: &mut CodeBuilder<'g>, capacity: i32) -> Result<(), Error> {
+    let java = code.java;
+    code.const_int(capacity)?;
+    code.invoke(java.members.nio.byte_buffer.allocate)?;
+    code.access_field(java.members.nio.byte_order.little_endian, AccessMode::Read)?;
+    code.invoke(java.members.nio.byte_buffer.order)?;
+    Ok(())
+}
+
+/// Store the offset of the `long` address in one local within its chunk into an `int` local
+fn chunk_offset<'g>(
+    code: &mut CodeBuilder<'g>,
+    address_off: u16,
+    offset_off: u16,
+) -> Result<(), Error> {
+    code.push_instruction(Instruction::LLoad(address_off))?;
+    code.push_instruction(Instruction::L2I)?;
+    code.const_int(CHUNK_SIZE - 1)?;
+    code.push_instruction(Instruction::IAnd)?;
+    code.push_instruction(Instruction::IStore(offset_off))?;
+    Ok(())
+}
+
+/// Lower the `int` count local to the `long` length local if the length is smaller
+fn clamp_count<'g>(
+    code: &mut CodeBuilder<'g>,
+    length_off: u16,
+    count_off: u16,
+    count_chosen: SynLabel,
+) -> Result<(), Error> {
+    code.push_instruction(Instruction::LLoad(length_off))?;
+    code.push_instruction(Instruction::ILoad(count_off))?;
+    code.push_instruction(Instruction::I2L)?;
+    code.push_instruction(Instruction::LCmp)?;
+    code.push_branch_instruction(BranchInstruction::If(OrdComparison::GE, count_chosen, ()))?;
+    code.push_instruction(Instruction::LLoad(length_off))?;
+    code.push_instruction(Instruction::L2I)?;
+    code.push_instruction(Instruction::IStore(count_off))?;
+    code.place_label(count_chosen)?;
+    Ok(())
+}
+
+/// Add (or subtract) the `int` count local to a `long` local
+fn advance_long<'g>(
+    code: &mut CodeBuilder<'g>,
+    long_off: u16,
+    count_off: u16,
+    operation: VerifierInstruction<'g>,
+) -> Result<(), Error> {
+    code.push_instruction(Instruction::LLoad(long_off))?;
+    code.push_instruction(Instruction::ILoad(count_off))?;
+    code.push_instruction(Instruction::I2L)?;
+    code.push_instruction(operation)?;
+    code.push_instruction(Instruction::LStore(long_off))?;
+    Ok(())
+}
+
+/// Copy `count` bytes from the chunk containing `src` in `srcMemory` to the chunk containing
+/// `dst` in `this` (using the locals layout of `Memory64.copy`)
+fn copy_chunk_slice<'g>(
+    code: &mut CodeBuilder<'g>,
+    members: &Memory64Members<'g>,
+    java: &'g JavaLibrary<'g>,
+    dst_offset_off: u16,
+    src_offset_off: u16,
+) -> Result<(), Error> {
+    code.push_instruction(Instruction::ALoad(0))?;
+    code.push_instruction(Instruction::LLoad(1))?;
+    code.invoke(members.chunk)?;
+    code.push_instruction(Instruction::ILoad(dst_offset_off))?;
+    code.push_instruction(Instruction::ALoad(3))?;
+    code.push_instruction(Instruction::LLoad(4))?;
+    code.invoke(members.chunk)?;
+    code.push_instruction(Instruction::ILoad(src_offset_off))?;
+    code.push_instruction(Instruction::ILoad(10))?;
+    code.invoke(java.members.nio.byte_buffer.put_bytebuffer)?;
+    code.push_instruction(Instruction::Pop)?;
+    Ok(())
+}
@@ -1,13 +1,23 @@
 //! Runtime types used in the `wasm2jar` interface
+mod exception;
 mod function;
 mod global;
 mod memory;
+mod memory64;
 mod table;
+mod tag;
+mod tail_call;
+mod tuple;
 
+pub use exception::*;
 pub use function::*;
 pub use global::*;
 pub use memory::*;
+pub use memory64::*;
 pub use table::*;
+pub use tag::*;
+pub use tail_call::*;
+pub use tuple::*;
 
 // TODO: support generating these in a custom package (eg. `org.wasm2jar`)
 // TODO: consider a more complex class hierarchy (immutable or not, resizable or not, specialized
@@ -15,21 +25,32 @@ pub use table::*;
 
 use crate::jvm::class_graph::{ClassData, ClassGraph, ClassId, JavaClasses};
 use crate::jvm::{BinaryName, ClassAccessFlags, Name};
+use crate::translate::MemoryBackend;
 
 pub struct WasmRuntime<'g> {
     pub classes: RuntimeClasses<'g>,
     pub members: RuntimeMembers<'g>,
+
+    /// Subclasses of `org.wasm2jar.Tuple` used for multiple return values
+    pub tuples: TupleClasses<'g>,
 }
 
 impl<'g> WasmRuntime<'g> {
     // TODO: customize package
     pub fn add_to_graph(
-        class_graph: &ClassGraph<'g>,
-        java_classes: &JavaClasses<'g>,
+        class_graph: &'g ClassGraph<'g>,
+        java_classes: &'g JavaClasses<'g>,
+        memory_backend: MemoryBackend,
     ) -> WasmRuntime<'g> {
         let classes = RuntimeClasses::add_to_graph(class_graph, java_classes);
-        let members = RuntimeMembers::add_to_graph(class_graph, java_classes, &classes);
-        WasmRuntime { classes, members }
+        let members =
+            RuntimeMembers::add_to_graph(class_graph, java_classes, &classes, memory_backend);
+        let tuples = TupleClasses::new(class_graph, java_classes, &classes);
+        WasmRuntime {
+            classes,
+            members,
+            tuples,
+        }
     }
 }
 
@@ -40,6 +61,11 @@ pub struct RuntimeClasses<'g> {
     pub function_table: ClassId<'g>,
     pub reference_table: ClassId<'g>,
     pub memory: ClassId<'g>,
+    pub memory64: ClassId<'g>,
+    pub tail_call: ClassId<'g>,
+    pub tag: ClassId<'g>,
+    pub wasm_exception: ClassId<'g>,
+    pub tuple: ClassId<'g>,
 }
 
 /// Members of classes inside `org.wasm2jar.*`
@@ -49,6 +75,11 @@ pub struct RuntimeMembers<'g> {
     pub function_table: FunctionTableMembers<'g>,
     pub reference_table: ReferenceTableMembers<'g>,
     pub memory: MemoryMembers<'g>,
+    pub memory64: Memory64Members<'g>,
+    pub tail_call: TailCallMembers<'g>,
+    pub tag: TagMembers<'g>,
+    pub wasm_exception: WasmExceptionMembers<'g>,
+    pub tuple: TupleMembers<'g>,
 }
 
 impl<'g> RuntimeClasses<'g> {
@@ -86,6 +117,36 @@ impl<'g> RuntimeClasses<'g> {
             ClassAccessFlags::SUPER | ClassAccessFlags::PUBLIC,
             None,
         ));
+        let memory64 = class_graph.add_class(ClassData::new(
+            BinaryName::from_str_unsafe("org/wasm2jar/Memory64"),
+            java_classes.lang.object,
+            ClassAccessFlags::SUPER | ClassAccessFlags::PUBLIC,
+            None,
+        ));
+        let tail_call = class_graph.add_class(ClassData::new(
+            BinaryName::from_str_unsafe("org/wasm2jar/TailCall"),
+            java_classes.lang.object,
+            ClassAccessFlags::SUPER | ClassAccessFlags::PUBLIC,
+            None,
+        ));
+        let tag = class_graph.add_class(ClassData::new(
+            BinaryName::from_str_unsafe("org/wasm2jar/Tag"),
+            java_classes.lang.object,
+            ClassAccessFlags::SUPER | ClassAccessFlags::PUBLIC,
+            None,
+        ));
+        let wasm_exception = class_graph.add_class(ClassData::new(
+            BinaryName::from_str_unsafe("org/wasm2jar/WasmException"),
+            java_classes.lang.runtime_exception,
+            ClassAccessFlags::SUPER | ClassAccessFlags::PUBLIC,
+            None,
+        ));
+        let tuple = class_graph.add_class(ClassData::new(
+            BinaryName::from_str_unsafe("org/wasm2jar/Tuple"),
+            java_classes.lang.object,
+            ClassAccessFlags::SUPER | ClassAccessFlags::PUBLIC | ClassAccessFlags::ABSTRACT,
+            None,
+        ));
 
         RuntimeClasses {
             function,
@@ -93,6 +154,11 @@ impl<'g> RuntimeClasses<'g> {
             function_table,
             reference_table,
             memory,
+            memory64,
+            tail_call,
+            tag,
+            wasm_exception,
+            tuple,
         }
     }
 }
@@ -102,13 +168,20 @@ impl<'g> RuntimeMembers<'g> {
         class_graph: &ClassGraph<'g>,
         java_classes: &JavaClasses<'g>,
         classes: &RuntimeClasses<'g>,
+        memory_backend: MemoryBackend,
     ) -> RuntimeMembers<'g> {
         let function = FunctionMembers::add_to_graph(class_graph, java_classes, classes);
         let global = GlobalMembers::add_to_graph(class_graph, java_classes, classes);
         let function_table = FunctionTableMembers::add_to_graph(class_graph, java_classes, classes);
         let reference_table =
             ReferenceTableMembers::add_to_graph(class_graph, java_classes, classes);
-        let memory = MemoryMembers::add_to_graph(class_graph, java_classes, classes);
+        let memory =
+            MemoryMembers::add_to_graph(class_graph, java_classes, classes, memory_backend);
+        let memory64 = Memory64Members::add_to_graph(class_graph, java_classes, classes);
+        let tail_call = TailCallMembers::add_to_graph(class_graph, java_classes, classes);
+        let tag = TagMembers::add_to_graph(class_graph, java_classes, classes);
+        let wasm_exception = WasmExceptionMembers::add_to_graph(class_graph, java_classes, classes);
+        let tuple = TupleMembers::add_to_graph(class_graph, java_classes, classes);
 
         RuntimeMembers {
             function,
@@ -116,6 +189,11 @@ impl<'g> RuntimeMembers<'g> {
             function_table,
             reference_table,
             memory,
+            memory64,
+            tail_call,
+            tag,
+            wasm_exception,
+            tuple,
         }
     }
 }
@@ -0,0 +1,70 @@
+use super::{RuntimeClasses, WasmRuntime};
+use crate::jvm::class_graph::{
+    ClassGraph, FieldData, FieldId, JavaClasses, JavaLibrary, MethodData, MethodId,
+};
+use crate::jvm::code::CodeBuilder;
+use crate::jvm::model::{Class, Field, Method};
+use crate::jvm::{
+    Error, FieldAccessFlags, FieldType, MethodAccessFlags, MethodDescriptor, Name, UnqualifiedName,
+};
+
+/// Members of `org.wasm2jar.Tag`
+pub struct TagMembers<'g> {
+    pub init: MethodId<'g>,
+
+    /// Method type whose parameters are the types of the exception payload
+    pub r#type: FieldId<'g>,
+}
+
+impl<'g> TagMembers<'g> {
+    pub fn add_to_graph(
+        class_graph: &ClassGraph<'g>,
+        java_classes: &JavaClasses<'g>,
+        classes: &RuntimeClasses<'g>,
+    ) -> TagMembers<'g> {
+        let class = classes.tag;
+        let init = class_graph.add_method(MethodData {
+            class,
+            name: UnqualifiedName::INIT,
+            access_flags: MethodAccessFlags::PUBLIC,
+            descriptor: MethodDescriptor {
+                parameters: vec![FieldType::object(java_classes.lang.invoke.method_type)],
+                return_type: None,
+            },
+        });
+        let r#type = class_graph.add_field(FieldData {
+            class,
+            name: UnqualifiedName::from_str_unsafe("type"),
+            access_flags: FieldAccessFlags::PUBLIC | FieldAccessFlags::FINAL,
+            descriptor: FieldType::object(java_classes.lang.invoke.method_type),
+        });
+
+        TagMembers { init, r#type }
+    }
+}
+
+pub fn make_tag_class<'g>(
+    class_graph: &'g ClassGraph<'g>,
+    java: &'g JavaLibrary<'g>,
+    runtime: &WasmRuntime<'g>,
+) -> Result<Class<'g>, Error> {
+    use crate::jvm::code::{BranchInstruction::*, Instruction::*, InvokeType};
+
+    let mut class = Class::new(runtime.classes.tag);
+    class.add_field(Field::new(runtime.members.tag.r#type));
+
+    // Constructor
+    let mut code = CodeBuilder::new(class_graph, java, runtime.members.tag.init);
+    code.push_instruction(ALoad(0))?;
+    code.push_instruction(Invoke(InvokeType::Special, java.members.lang.object.init))?;
+    code.push_instruction(ALoad(0))?;
+    code.push_instruction(ALoad(1))?;
+    code.push_instruction(PutField(runtime.members.tag.r#type))?;
+    code.push_branch_instruction(Return)?;
+
+    let mut constructor = Method::new(runtime.members.tag.init);
+    constructor.code_impl = Some(code.result()?);
+    class.add_method(constructor);
+
+    Ok(class)
+}
@@ -0,0 +1,329 @@
+use super::{RuntimeClasses, WasmRuntime};
+use crate::jvm::class_graph::{
+    ClassGraph, ConstantData, FieldData, FieldId, JavaClasses, JavaLibrary, MethodData, MethodId,
+};
+use crate::jvm::code::{CodeBuilder, CodeBuilderExts, EqComparison, OrdComparison};
+use crate::jvm::model::{Class, Field, Method};
+use crate::jvm::{
+    Error, FieldAccessFlags, FieldType, MethodAccessFlags, MethodDescriptor, Name, RefType,
+    UnqualifiedName,
+};
+
+/// Members of `org.wasm2jar.TailCall`
+pub struct TailCallMembers<'g> {
+    pub init: MethodId<'g>,
+
+    /// Method handle of type `()Ljava/lang/Object;` which performs the tail call
+    pub thunk: FieldId<'g>,
+
+    /// Evaluate thunks until the result is no longer a `TailCall`
+    pub trampoline: MethodId<'g>,
+
+    /// Weak map from function handles made by `trampolined` to the tail call method handle and
+    /// module they were made from
+    pub tail_call_handles: FieldId<'g>,
+
+    /// Initializes `tail_call_handles`
+    pub clinit: MethodId<'g>,
+
+    /// Make a function handle of the given type out of a tail call method handle (whose last
+    /// argument is the module) and a module, remembering what it was made from
+    pub trampolined: MethodId<'g>,
+
+    /// Make a thunk calling a function handle, going through its tail call handle if it has one
+    pub indirect_thunk: MethodId<'g>,
+}
+
+impl<'g> TailCallMembers<'g> {
+    pub fn add_to_graph(
+        class_graph: &ClassGraph<'g>,
+        java: &JavaClasses<'g>,
+        runtime: &RuntimeClasses<'g>,
+    ) -> TailCallMembers<'g> {
+        let class = runtime.tail_call;
+        let init = class_graph.add_method(MethodData {
+            class,
+            name: UnqualifiedName::INIT,
+            access_flags: MethodAccessFlags::PUBLIC,
+            descriptor: MethodDescriptor {
+                parameters: vec![FieldType::object(java.lang.invoke.method_handle)],
+                return_type: None,
+            },
+        });
+        let thunk = class_graph.add_field(FieldData {
+            class,
+            name: UnqualifiedName::from_str_unsafe("thunk"),
+            access_flags: FieldAccessFlags::PUBLIC | FieldAccessFlags::FINAL,
+            descriptor: FieldType::object(java.lang.invoke.method_handle),
+        });
+        let trampoline = class_graph.add_method(MethodData {
+            class,
+            name: UnqualifiedName::from_str_unsafe("trampoline"),
+            access_flags: MethodAccessFlags::PUBLIC | MethodAccessFlags::STATIC,
+            descriptor: MethodDescriptor {
+                parameters: vec![FieldType::object(java.lang.object)],
+                return_type: Some(FieldType::object(java.lang.object)),
+            },
+        });
+
+        let tail_call_handles = class_graph.add_field(FieldData {
+            class,
+            name: UnqualifiedName::from_str_unsafe("tailCallHandles"),
+            access_flags: FieldAccessFlags::PRIVATE
+                | FieldAccessFlags::STATIC
+                | FieldAccessFlags::FINAL,
+            descriptor: FieldType::object(java.util.map),
+        });
+        let clinit = class_graph.add_method(MethodData {
+            class,
+            name: UnqualifiedName::CLINIT,
+            access_flags: MethodAccessFlags::STATIC,
+            descriptor: MethodDescriptor {
+                parameters: vec![],
+                return_type: None,
+            },
+        });
+        let trampolined = class_graph.add_method(MethodData {
+            class,
+            name: UnqualifiedName::from_str_unsafe("trampolined"),
+            access_flags: MethodAccessFlags::PUBLIC | MethodAccessFlags::STATIC,
+            descriptor: MethodDescriptor {
+                parameters: vec![
+                    FieldType::object(java.lang.invoke.method_handle),
+                    FieldType::object(java.lang.object),
+                    FieldType::object(java.lang.invoke.method_type),
+                ],
+                return_type: Some(FieldType::object(java.lang.invoke.method_handle)),
+            },
+        });
+        let indirect_thunk = class_graph.add_method(MethodData {
+            class,
+            name: UnqualifiedName::from_str_unsafe("indirectThunk"),
+            access_flags: MethodAccessFlags::PUBLIC | MethodAccessFlags::STATIC,
+            descriptor: MethodDescriptor {
+                parameters: vec![
+                    FieldType::object(java.lang.invoke.method_handle),
+                    FieldType::object(java.lang.invoke.method_type),
+                    FieldType::array(FieldType::object(java.lang.object)),
+                ],
+                return_type: Some(FieldType::object(java.lang.invoke.method_handle)),
+            },
+        });
+
+        TailCallMembers {
+            init,
+            thunk,
+            trampoline,
+            tail_call_handles,
+            clinit,
+            trampolined,
+            indirect_thunk,
+        }
+    }
+}
+
+pub fn make_tail_call_class<'g>(
+    class_graph: &'g ClassGraph<'g>,
+    java: &'g JavaLibrary<'g>,
+    runtime: &WasmRuntime<'g>,
+) -> Result<Class<'g>, Error> {
+    use crate::jvm::code::{BranchInstruction::*, Instruction::*, InvokeType};
+
+    let mut class = Class::new(runtime.classes.tail_call);
+    class.add_field(Field::new(runtime.members.tail_call.thunk));
+    class.add_field(Field::new(runtime.members.tail_call.tail_call_handles));
+
+    // Static initializer
+    //
+    // The module is only weakly referenced from the values, since its tables may well reference
+    // the keys. It stays alive as long as the key does, since the key has the module bound.
+    let mut code = CodeBuilder::new(class_graph, java, runtime.members.tail_call.clinit);
+    code.push_instruction(New(RefType::Object(java.classes.util.weak_hash_map)))?;
+    code.push_instruction(Dup)?;
+    code.push_instruction(Invoke(
+        InvokeType::Special,
+        java.members.util.weak_hash_map.init,
+    ))?;
+    code.invoke(java.members.util.collections.synchronized_map)?;
+    code.push_instruction(PutStatic(runtime.members.tail_call.tail_call_handles))?;
+    code.push_branch_instruction(Return)?;
+
+    let mut clinit = Method::new(runtime.members.tail_call.clinit);
+    clinit.code_impl = Some(code.result()?);
+    class.add_method(clinit);
+
+    // Constructor
+    let mut code = CodeBuilder::new(class_graph, java, runtime.members.tail_call.init);
+    code.push_instruction(ALoad(0))?;
+    code.push_instruction(Invoke(InvokeType::Special, java.members.lang.object.init))?;
+    code.push_instruction(ALoad(0))?;
+    code.push_instruction(ALoad(1))?;
+    code.push_instruction(PutField(runtime.members.tail_call.thunk))?;
+    code.push_branch_instruction(Return)?;
+
+    let mut constructor = Method::new(runtime.members.tail_call.init);
+    constructor.code_impl = Some(code.result()?);
+    class.add_method(constructor);
+
+    /* static Object trampoline(Object result) {
+     *   while (result instanceof TailCall) {
+     *     result = ((TailCall) result).thunk.invokeExact();
+     *   }
+     *   return result;
+     * }
+     */
+    let mut code = CodeBuilder::new(class_graph, java, runtime.members.tail_call.trampoline);
+    let loop_start = code.fresh_label();
+    let loop_end = code.fresh_label();
+    code.place_label(loop_start)?;
+    code.push_instruction(ALoad(0))?;
+    code.push_instruction(InstanceOf(RefType::Object(runtime.classes.tail_call)))?;
+    code.push_branch_instruction(If(OrdComparison::EQ, loop_end, ()))?;
+    code.push_instruction(ALoad(0))?;
+    code.push_instruction(CheckCast(RefType::Object(runtime.classes.tail_call)))?;
+    code.push_instruction(GetField(runtime.members.tail_call.thunk))?;
+    code.invoke_invoke_exact(MethodDescriptor {
+        parameters: vec![],
+        return_type: Some(FieldType::object(java.classes.lang.object)),
+    })?;
+    code.push_instruction(AStore(0))?;
+    code.push_branch_instruction(Goto(loop_start))?;
+    code.place_label(loop_end)?;
+    code.push_instruction(ALoad(0))?;
+    code.push_branch_instruction(AReturn)?;
+
+    let mut trampoline = Method::new(runtime.members.tail_call.trampoline);
+    trampoline.code_impl = Some(code.result()?);
+    class.add_method(trampoline);
+
+    /* static MethodHandle trampolined(MethodHandle tailCall, Object module, MethodType type) {
+     *   MethodHandle handle = MethodHandles.filterReturnValue(
+     *     MethodHandles.insertArguments(tailCall, type.parameterCount(), new Object[] { module }),
+     *     TailCall::trampoline
+     *   ).asType(type);
+     *   tailCallHandles.put(handle, new Object[] { tailCall, new WeakReference(module) });
+     *   return handle;
+     * }
+     */
+    let method_handles = &java.members.lang.invoke.method_handles;
+    let method_handle = &java.members.lang.invoke.method_handle;
+    let mut code = CodeBuilder::new(class_graph, java, runtime.members.tail_call.trampolined);
+    code.push_instruction(ALoad(0))?;
+    code.push_instruction(ALoad(2))?;
+    code.invoke(java.members.lang.invoke.method_type.parameter_count)?;
+    code.push_instruction(IConst1)?;
+    code.new_ref_array(RefType::Object(java.classes.lang.object))?;
+    code.push_instruction(Dup)?;
+    code.push_instruction(IConst0)?;
+    code.push_instruction(ALoad(1))?;
+    code.push_instruction(AAStore)?;
+    code.invoke(method_handles.insert_arguments)?;
+    code.const_methodhandle(runtime.members.tail_call.trampoline)?;
+    code.invoke(method_handles.filter_return_value)?;
+    code.push_instruction(ALoad(2))?;
+    code.invoke(method_handle.as_type)?;
+    code.push_instruction(AStore(3))?;
+    code.push_instruction(GetStatic(runtime.members.tail_call.tail_call_handles))?;
+    code.push_instruction(ALoad(3))?;
+    code.push_instruction(IConst2)?;
+    code.new_ref_array(RefType::Object(java.classes.lang.object))?;
+    code.push_instruction(Dup)?;
+    code.push_instruction(IConst0)?;
+    code.push_instruction(ALoad(0))?;
+    code.push_instruction(AAStore)?;
+    code.push_instruction(Dup)?;
+    code.push_instruction(IConst1)?;
+    code.push_instruction(New(RefType::Object(java.classes.lang.r#ref.weak_reference)))?;
+    code.push_instruction(Dup)?;
+    code.push_instruction(ALoad(1))?;
+    code.push_instruction(Invoke(
+        InvokeType::Special,
+        java.members.lang.r#ref.weak_reference.init,
+    ))?;
+    code.push_instruction(AAStore)?;
+    code.invoke(java.members.util.map.put)?;
+    code.push_instruction(Pop)?;
+    code.push_instruction(ALoad(3))?;
+    code.push_branch_instruction(AReturn)?;
+
+    let mut trampolined = Method::new(runtime.members.tail_call.trampolined);
+    trampolined.code_impl = Some(code.result()?);
+    class.add_method(trampolined);
+
+    /* static MethodHandle indirectThunk(MethodHandle handle, MethodType type, Object[] arguments) {
+     *   Object[] tailCall = (Object[]) tailCallHandles.get(handle);
+     *   if (tailCall != null && handle.type().equals(type)) {
+     *     Object[] tailCallArguments = Arrays.copyOf(arguments, arguments.length + 1);
+     *     tailCallArguments[arguments.length] = ((WeakReference) tailCall[1]).get();
+     *     return MethodHandles.insertArguments((MethodHandle) tailCall[0], 0, tailCallArguments);
+     *   }
+     *   return MethodHandles.insertArguments(MethodHandles.exactInvoker(type), 1, arguments)
+     *     .bindTo(handle)
+     *     .asType(MethodType.methodType(Object.class));
+     * }
+     *
+     * Checking the type of the handle in the fallback case is deferred to when the thunk gets
+     * evaluated, where it will fail the same way a regular `call_indirect` would.
+     */
+    let object_array = RefType::array(FieldType::object(java.classes.lang.object));
+    let mut code = CodeBuilder::new(class_graph, java, runtime.members.tail_call.indirect_thunk);
+    let call_handle = code.fresh_label();
+    code.push_instruction(GetStatic(runtime.members.tail_call.tail_call_handles))?;
+    code.push_instruction(ALoad(0))?;
+    code.invoke(java.members.util.map.get)?;
+    code.push_instruction(CheckCast(object_array))?;
+    code.push_instruction(AStore(3))?;
+    code.push_instruction(ALoad(3))?;
+    code.push_branch_instruction(IfNull(EqComparison::EQ, call_handle, ()))?;
+    code.push_instruction(ALoad(0))?;
+    code.invoke(method_handle.r#type)?;
+    code.push_instruction(ALoad(1))?;
+    code.invoke(java.members.lang.object.equals)?;
+    code.push_branch_instruction(If(OrdComparison::EQ, call_handle, ()))?;
+    code.push_instruction(ALoad(3))?;
+    code.push_instruction(IConst0)?;
+    code.push_instruction(AALoad)?;
+    code.push_instruction(CheckCast(RefType::Object(
+        java.classes.lang.invoke.method_handle,
+    )))?;
+    code.push_instruction(IConst0)?;
+    code.push_instruction(ALoad(2))?;
+    code.push_instruction(ALoad(2))?;
+    code.push_instruction(ArrayLength)?;
+    code.push_instruction(IConst1)?;
+    code.push_instruction(IAdd)?;
+    code.invoke(java.members.util.arrays.copy_of)?;
+    code.push_instruction(Dup)?;
+    code.push_instruction(ALoad(2))?;
+    code.push_instruction(ArrayLength)?;
+    code.push_instruction(ALoad(3))?;
+    code.push_instruction(IConst1)?;
+    code.push_instruction(AALoad)?;
+    code.push_instruction(CheckCast(RefType::Object(
+        java.classes.lang.r#ref.weak_reference,
+    )))?;
+    code.invoke(java.members.lang.r#ref.weak_reference.get)?;
+    code.push_instruction(AAStore)?;
+    code.invoke(method_handles.insert_arguments)?;
+    code.push_branch_instruction(AReturn)?;
+    code.place_label(call_handle)?;
+    code.push_instruction(ALoad(1))?;
+    code.invoke(method_handles.exact_invoker)?;
+    code.push_instruction(IConst1)?;
+    code.push_instruction(ALoad(2))?;
+    code.invoke(method_handles.insert_arguments)?;
+    code.push_instruction(ALoad(0))?;
+    code.invoke(method_handle.bind_to)?;
+    code.push_instruction(Ldc(ConstantData::MethodType(MethodDescriptor {
+        parameters: vec![],
+        return_type: Some(FieldType::object(java.classes.lang.object)),
+    })))?;
+    code.invoke(method_handle.as_type)?;
+    code.push_branch_instruction(AReturn)?;
+
+    let mut indirect_thunk = Method::new(runtime.members.tail_call.indirect_thunk);
+    indirect_thunk.code_impl = Some(code.result()?);
+    class.add_method(indirect_thunk);
+
+    Ok(class)
+}
@@ -0,0 +1,319 @@
+use super::{RuntimeClasses, WasmRuntime};
+use crate::jvm::class_graph::{
+    AccessMode, ClassData, ClassGraph, ClassId, FieldData, FieldId, JavaClasses, JavaLibrary,
+    MethodData, MethodId,
+};
+use crate::jvm::code::{CodeBuilder, CodeBuilderExts};
+use crate::jvm::model::{Class, Field, Method};
+use crate::jvm::{
+    BinaryName, ClassAccessFlags, Error, FieldAccessFlags, FieldType, MethodAccessFlags,
+    MethodDescriptor, Name, RefType, UnqualifiedName,
+};
+use crate::util::Width;
+use crate::wasm::StackType;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+/// Members of `org.wasm2jar.Tuple`
+pub struct TupleMembers<'g> {
+    pub init: MethodId<'g>,
+
+    /// Copy the values of the tuple into a (boxed) `Object[]`
+    pub to_array: MethodId<'g>,
+}
+
+impl<'g> TupleMembers<'g> {
+    pub fn add_to_graph(
+        class_graph: &ClassGraph<'g>,
+        java: &JavaClasses<'g>,
+        runtime: &RuntimeClasses<'g>,
+    ) -> TupleMembers<'g> {
+        let class = runtime.tuple;
+        let init = class_graph.add_method(MethodData {
+            class,
+            name: UnqualifiedName::INIT,
+            access_flags: MethodAccessFlags::PROTECTED,
+            descriptor: MethodDescriptor {
+                parameters: vec![],
+                return_type: None,
+            },
+        });
+        let to_array = class_graph.add_method(MethodData {
+            class,
+            name: UnqualifiedName::from_str_unsafe("toArray"),
+            access_flags: MethodAccessFlags::PUBLIC | MethodAccessFlags::ABSTRACT,
+            descriptor: MethodDescriptor {
+                parameters: vec![],
+                return_type: Some(FieldType::array(FieldType::object(java.lang.object))),
+            },
+        });
+
+        TupleMembers { init, to_array }
+    }
+}
+
+pub fn make_tuple_class<'g>(
+    class_graph: &'g ClassGraph<'g>,
+    java: &'g JavaLibrary<'g>,
+    runtime: &WasmRuntime<'g>,
+) -> Result<Class<'g>, Error> {
+    use crate::jvm::code::{BranchInstruction::*, Instruction::*, InvokeType};
+
+    let mut class = Class::new(runtime.classes.tuple);
+
+    // Constructor
+    let mut code = CodeBuilder::new(class_graph, java, runtime.members.tuple.init);
+    code.push_instruction(ALoad(0))?;
+    code.push_instruction(Invoke(InvokeType::Special, java.members.lang.object.init))?;
+    code.push_branch_instruction(Return)?;
+
+    let mut constructor = Method::new(runtime.members.tuple.init);
+    constructor.code_impl = Some(code.result()?);
+    class.add_method(constructor);
+
+    // `toArray` is abstract
+    class.add_method(Method::new(runtime.members.tuple.to_array));
+
+    Ok(class)
+}
+
+/// Subclass of `org.wasm2jar.Tuple` for values of a fixed list of types
+///
+/// These are used to return multiple values out of a function without boxing each value. The
+/// class for types `[i32, i64]` is analogous to
+///
+/// ```java
+/// public final class TupleIJ extends Tuple {
+///   public final int _0;
+///   public final long _1;
+///
+///   public TupleIJ(int _0, long _1) { this._0 = _0; this._1 = _1; }
+///   public static TupleIJ of(int _0, long _1) { return new TupleIJ(_0, _1); }
+///   public Object[] toArray() { return new Object[] { _0, _1 }; }
+/// }
+/// ```
+///
+/// Since the name only depends on the types, modules returning tuples to each other (eg. through
+/// imported functions or shared tables) agree on the classes used.
+#[derive(Clone)]
+pub struct TupleClass<'g> {
+    pub class: ClassId<'g>,
+
+    /// Types of the values in the tuple
+    pub types: Vec<StackType>,
+
+    /// One field for each value
+    pub fields: Vec<FieldId<'g>>,
+
+    /// Constructor taking one argument for each value
+    pub init: MethodId<'g>,
+
+    /// Static method which takes the values and constructs the tuple
+    pub of: MethodId<'g>,
+
+    /// Implementation of `Tuple.toArray`
+    pub to_array: MethodId<'g>,
+}
+
+/// Tuple classes used so far, keyed by the types of their values
+///
+/// Classes are added to the class graph the first time they are requested, and only those
+/// classes need to be generated (see [`TupleClasses::make_classes`]).
+pub struct TupleClasses<'g> {
+    class_graph: &'g ClassGraph<'g>,
+    java: &'g JavaClasses<'g>,
+    tuple: ClassId<'g>,
+    classes: RefCell<HashMap<Vec<StackType>, TupleClass<'g>>>,
+}
+
+impl<'g> TupleClasses<'g> {
+    pub fn new(
+        class_graph: &'g ClassGraph<'g>,
+        java: &'g JavaClasses<'g>,
+        runtime: &RuntimeClasses<'g>,
+    ) -> TupleClasses<'g> {
+        TupleClasses {
+            class_graph,
+            java,
+            tuple: runtime.tuple,
+            classes: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Get (or add to the class graph) the tuple class for values of the given types
+    pub fn get(&self, types: &[StackType]) -> TupleClass<'g> {
+        if let Some(tuple_class) = self.classes.borrow().get(types) {
+            return tuple_class.clone();
+        }
+        let tuple_class = self.add_to_graph(types);
+        self.classes
+            .borrow_mut()
+            .insert(types.to_vec(), tuple_class.clone());
+        tuple_class
+    }
+
+    fn add_to_graph(&self, types: &[StackType]) -> TupleClass<'g> {
+        let mut name = String::from("org/wasm2jar/Tuple");
+        name.extend(types.iter().map(|ty| match ty {
+            StackType::I32 => 'I',
+            StackType::I64 => 'J',
+            StackType::F32 => 'F',
+            StackType::F64 => 'D',
+            StackType::V128 => 'V',
+            StackType::FuncRef => 'H',
+            StackType::ExternRef => 'E',
+        }));
+        let class = self.class_graph.add_class(ClassData::new(
+            BinaryName::from_string(name).expect("tuple class name"),
+            self.tuple,
+            ClassAccessFlags::SUPER | ClassAccessFlags::PUBLIC | ClassAccessFlags::FINAL,
+            None,
+        ));
+
+        let field_types: Vec<FieldType<ClassId<'g>>> =
+            types.iter().map(|ty| ty.field_type(self.java)).collect();
+        let fields = field_types
+            .iter()
+            .enumerate()
+            .map(|(i, field_type)| {
+                self.class_graph.add_field(FieldData {
+                    class,
+                    name: UnqualifiedName::from_string(format!("_{}", i)).expect("field name"),
+                    access_flags: FieldAccessFlags::PUBLIC | FieldAccessFlags::FINAL,
+                    descriptor: *field_type,
+                })
+            })
+            .collect();
+        let init = self.class_graph.add_method(MethodData {
+            class,
+            name: UnqualifiedName::INIT,
+            access_flags: MethodAccessFlags::PUBLIC,
+            descriptor: MethodDescriptor {
+                parameters: field_types.clone(),
+                return_type: None,
+            },
+        });
+        let of = self.class_graph.add_method(MethodData {
+            class,
+            name: UnqualifiedName::from_str_unsafe("of"),
+            access_flags: MethodAccessFlags::PUBLIC | MethodAccessFlags::STATIC,
+            descriptor: MethodDescriptor {
+                parameters: field_types,
+                return_type: Some(FieldType::object(class)),
+            },
+        });
+        let to_array = self.class_graph.add_method(MethodData {
+            class,
+            name: UnqualifiedName::from_str_unsafe("toArray"),
+            access_flags: MethodAccessFlags::PUBLIC,
+            descriptor: MethodDescriptor {
+                parameters: vec![],
+                return_type: Some(FieldType::array(FieldType::object(self.java.lang.object))),
+            },
+        });
+
+        TupleClass {
+            class,
+            types: types.to_vec(),
+            fields,
+            init,
+            of,
+            to_array,
+        }
+    }
+
+    /// Generate all of the tuple classes which have been requested
+    pub fn make_classes(
+        &self,
+        class_graph: &'g ClassGraph<'g>,
+        java: &'g JavaLibrary<'g>,
+        runtime: &WasmRuntime<'g>,
+    ) -> Result<Vec<Class<'g>>, Error> {
+        let mut tuple_classes: Vec<TupleClass<'g>> =
+            self.classes.borrow().values().cloned().collect();
+        tuple_classes.sort_by(|t1, t2| t1.class.name.as_str().cmp(t2.class.name.as_str()));
+        tuple_classes
+            .iter()
+            .map(|tuple_class| make_tuple_subclass(class_graph, java, runtime, tuple_class))
+            .collect()
+    }
+}
+
+fn make_tuple_subclass<'g>(
+    class_graph: &'g ClassGraph<'g>,
+    java: &'g JavaLibrary<'g>,
+    runtime: &WasmRuntime<'g>,
+    tuple_class: &TupleClass<'g>,
+) -> Result<Class<'g>, Error> {
+    let mut class = Class::new(tuple_class.class);
+    for field in &tuple_class.fields {
+        class.add_field(Field::new(*field));
+    }
+
+    // Constructor
+    let mut code = CodeBuilder::new(class_graph, java, tuple_class.init);
+    let this_type = FieldType::object(tuple_class.class);
+    code.get_local(0, &this_type)?;
+    code.invoke(runtime.members.tuple.init)?;
+    let mut offset = 1;
+    for field in &tuple_class.fields {
+        code.get_local(0, &this_type)?;
+        code.get_local(offset, &field.descriptor)?;
+        code.access_field(*field, AccessMode::Write)?;
+        offset += field.descriptor.width() as u16;
+    }
+    code.return_(None)?;
+
+    let mut constructor = Method::new(tuple_class.init);
+    constructor.code_impl = Some(code.result()?);
+    class.add_method(constructor);
+
+    // Static factory
+    let mut code = CodeBuilder::new(class_graph, java, tuple_class.of);
+    code.new(tuple_class.class)?;
+    code.dup()?;
+    let mut offset = 0;
+    for field in &tuple_class.fields {
+        code.get_local(offset, &field.descriptor)?;
+        offset += field.descriptor.width() as u16;
+    }
+    code.invoke(tuple_class.init)?;
+    code.return_(Some(this_type))?;
+
+    let mut of = Method::new(tuple_class.of);
+    of.code_impl = Some(code.result()?);
+    class.add_method(of);
+
+    // Boxing into an array
+    let mut code = CodeBuilder::new(class_graph, java, tuple_class.to_array);
+    code.const_int(tuple_class.fields.len() as i32)?;
+    code.new_ref_array(RefType::Object(java.classes.lang.object))?;
+    for (i, (field, ty)) in tuple_class
+        .fields
+        .iter()
+        .zip(&tuple_class.types)
+        .enumerate()
+    {
+        code.dup()?;
+        code.const_int(i as i32)?;
+        code.get_local(0, &this_type)?;
+        code.access_field(*field, AccessMode::Read)?;
+        match ty {
+            StackType::I32 => code.invoke(java.members.lang.integer.value_of)?,
+            StackType::I64 => code.invoke(java.members.lang.long.value_of)?,
+            StackType::F32 => code.invoke(java.members.lang.float.value_of)?,
+            StackType::F64 => code.invoke(java.members.lang.double.value_of)?,
+            StackType::V128 | StackType::FuncRef | StackType::ExternRef => (),
+        }
+        code.push_instruction(crate::jvm::code::Instruction::AAStore)?;
+    }
+    code.return_(Some(FieldType::array(FieldType::object(
+        java.classes.lang.object,
+    ))))?;
+
+    let mut to_array = Method::new(tuple_class.to_array);
+    to_array.code_impl = Some(code.result()?);
+    class.add_method(to_array);
+
+    Ok(class)
+}
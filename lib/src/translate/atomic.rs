@@ -0,0 +1,954 @@
+use super::Error;
+use crate::jvm::class_graph::{BootstrapMethodId, ClassId};
+use crate::jvm::code::{
+    BranchInstruction, CodeBuilder, CodeBuilderExts, EqComparison, Instruction, OrdComparison,
+    ShiftType,
+};
+use crate::jvm::{BaseType, FieldType, MethodDescriptor, Name, UnqualifiedName};
+use crate::util::Width;
+use wasmparser::{MemArg, Operator};
+
+/* Atomic accesses to memory go through `VarHandle` views of the memory's bytes (see
+ * `MethodHandles.byteBufferViewVarHandle` and `MethodHandles.byteArrayViewVarHandle`). Those views
+ * only support atomic accesses to `int` and `long`, and they check both bounds and alignment
+ * (which conveniently line up with WASM traps).
+ * Each access is an `invokedynamic` whose name is the `VarHandle.AccessMode` method name and whose
+ * type is the exact access mode type, so the call site ends up linked to a pre-bound invoker.
+ *
+ * 8 and 16 bit accesses are emulated by operating on the aligned `int` that contains them (using a
+ * compare-and-set loop for updates).
+ *
+ * `memory.atomic.wait*` and `memory.atomic.notify` use a map (stored on the shared memory object)
+ * from addresses to queues of waiting threads. Waiting threads add themselves to the queue then
+ * park until they are either removed from the queue by a notifier or they time out.
+ */
+
+/// Width of an atomic memory access
+#[derive(Copy, Clone, Hash, PartialEq, Eq, Debug)]
+pub enum AtomicWidth {
+    I8,
+    I16,
+    I32,
+    I64,
+}
+
+impl AtomicWidth {
+    /// Mask for the bits in an `int` covered by an access narrower than 32 bits
+    pub const fn narrow_mask(self) -> Option<i32> {
+        match self {
+            AtomicWidth::I8 => Some(0xff),
+            AtomicWidth::I16 => Some(0xffff),
+            AtomicWidth::I32 | AtomicWidth::I64 => None,
+        }
+    }
+
+    /// Type of the `VarHandle` view used for accesses of this width
+    pub const fn view_type(self) -> BaseType {
+        match self {
+            AtomicWidth::I64 => BaseType::Long,
+            _ => BaseType::Int,
+        }
+    }
+
+    const fn bits(self) -> u8 {
+        match self {
+            AtomicWidth::I8 => 8,
+            AtomicWidth::I16 => 16,
+            AtomicWidth::I32 => 32,
+            AtomicWidth::I64 => 64,
+        }
+    }
+}
+
+/// Read-modify-write operation
+#[derive(Copy, Clone, Hash, PartialEq, Eq, Debug)]
+pub enum RmwOp {
+    Add,
+    Sub,
+    And,
+    Or,
+    Xor,
+    Xchg,
+}
+
+impl RmwOp {
+    /// `VarHandle` access mode used for full-width accesses
+    ///
+    /// Note that `Sub` has no access mode and is done by adding the negated operand.
+    pub const fn access_mode(self) -> UnqualifiedName {
+        match self {
+            RmwOp::Add | RmwOp::Sub => UnqualifiedName::GETANDADD,
+            RmwOp::And => UnqualifiedName::GETANDBITWISEAND,
+            RmwOp::Or => UnqualifiedName::GETANDBITWISEOR,
+            RmwOp::Xor => UnqualifiedName::GETANDBITWISEXOR,
+            RmwOp::Xchg => UnqualifiedName::GETANDSET,
+        }
+    }
+}
+
+/// Kind of atomic memory instruction
+#[derive(Copy, Clone, Hash, PartialEq, Eq, Debug)]
+pub enum AtomicAccess {
+    Load,
+    Store,
+    Rmw(RmwOp),
+    Cmpxchg,
+    Wait,
+    Notify,
+}
+
+/// Atomic memory instruction (other than `atomic.fence`)
+#[derive(Copy, Clone, Hash, PartialEq, Eq, Debug)]
+pub struct AtomicInstruction {
+    pub access: AtomicAccess,
+
+    /// Width of the memory being accessed
+    pub width: AtomicWidth,
+
+    /// Type of the values being loaded or stored (`int` or `long`)
+    pub value_type: BaseType,
+}
+
+impl AtomicInstruction {
+    /// Classify an atomic memory operator
+    pub fn from_operator(operator: &Operator) -> Option<(MemArg, AtomicInstruction)> {
+        use AtomicAccess::*;
+        use AtomicWidth::*;
+        use RmwOp::*;
+
+        let (memarg, access, width, value_type) = match *operator {
+            Operator::MemoryAtomicNotify { memarg } => (memarg, Notify, I32, BaseType::Int),
+            Operator::MemoryAtomicWait32 { memarg } => (memarg, Wait, I32, BaseType::Int),
+            Operator::MemoryAtomicWait64 { memarg } => (memarg, Wait, I64, BaseType::Long),
+
+            Operator::I32AtomicLoad { memarg } => (memarg, Load, I32, BaseType::Int),
+            Operator::I64AtomicLoad { memarg } => (memarg, Load, I64, BaseType::Long),
+            Operator::I32AtomicLoad8U { memarg } => (memarg, Load, I8, BaseType::Int),
+            Operator::I32AtomicLoad16U { memarg } => (memarg, Load, I16, BaseType::Int),
+            Operator::I64AtomicLoad8U { memarg } => (memarg, Load, I8, BaseType::Long),
+            Operator::I64AtomicLoad16U { memarg } => (memarg, Load, I16, BaseType::Long),
+            Operator::I64AtomicLoad32U { memarg } => (memarg, Load, I32, BaseType::Long),
+
+            Operator::I32AtomicStore { memarg } => (memarg, Store, I32, BaseType::Int),
+            Operator::I64AtomicStore { memarg } => (memarg, Store, I64, BaseType::Long),
+            Operator::I32AtomicStore8 { memarg } => (memarg, Store, I8, BaseType::Int),
+            Operator::I32AtomicStore16 { memarg } => (memarg, Store, I16, BaseType::Int),
+            Operator::I64AtomicStore8 { memarg } => (memarg, Store, I8, BaseType::Long),
+            Operator::I64AtomicStore16 { memarg } => (memarg, Store, I16, BaseType::Long),
+            Operator::I64AtomicStore32 { memarg } => (memarg, Store, I32, BaseType::Long),
+
+            Operator::I32AtomicRmwAdd { memarg } => (memarg, Rmw(Add), I32, BaseType::Int),
+            Operator::I64AtomicRmwAdd { memarg } => (memarg, Rmw(Add), I64, BaseType::Long),
+            Operator::I32AtomicRmw8AddU { memarg } => (memarg, Rmw(Add), I8, BaseType::Int),
+            Operator::I32AtomicRmw16AddU { memarg } => (memarg, Rmw(Add), I16, BaseType::Int),
+            Operator::I64AtomicRmw8AddU { memarg } => (memarg, Rmw(Add), I8, BaseType::Long),
+            Operator::I64AtomicRmw16AddU { memarg } => (memarg, Rmw(Add), I16, BaseType::Long),
+            Operator::I64AtomicRmw32AddU { memarg } => (memarg, Rmw(Add), I32, BaseType::Long),
+
+            Operator::I32AtomicRmwSub { memarg } => (memarg, Rmw(Sub), I32, BaseType::Int),
+            Operator::I64AtomicRmwSub { memarg } => (memarg, Rmw(Sub), I64, BaseType::Long),
+            Operator::I32AtomicRmw8SubU { memarg } => (memarg, Rmw(Sub), I8, BaseType::Int),
+            Operator::I32AtomicRmw16SubU { memarg } => (memarg, Rmw(Sub), I16, BaseType::Int),
+            Operator::I64AtomicRmw8SubU { memarg } => (memarg, Rmw(Sub), I8, BaseType::Long),
+            Operator::I64AtomicRmw16SubU { memarg } => (memarg, Rmw(Sub), I16, BaseType::Long),
+            Operator::I64AtomicRmw32SubU { memarg } => (memarg, Rmw(Sub), I32, BaseType::Long),
+
+            Operator::I32AtomicRmwAnd { memarg } => (memarg, Rmw(And), I32, BaseType::Int),
+            Operator::I64AtomicRmwAnd { memarg } => (memarg, Rmw(And), I64, BaseType::Long),
+            Operator::I32AtomicRmw8AndU { memarg } => (memarg, Rmw(And), I8, BaseType::Int),
+            Operator::I32AtomicRmw16AndU { memarg } => (memarg, Rmw(And), I16, BaseType::Int),
+            Operator::I64AtomicRmw8AndU { memarg } => (memarg, Rmw(And), I8, BaseType::Long),
+            Operator::I64AtomicRmw16AndU { memarg } => (memarg, Rmw(And), I16, BaseType::Long),
+            Operator::I64AtomicRmw32AndU { memarg } => (memarg, Rmw(And), I32, BaseType::Long),
+
+            Operator::I32AtomicRmwOr { memarg } => (memarg, Rmw(Or), I32, BaseType::Int),
+            Operator::I64AtomicRmwOr { memarg } => (memarg, Rmw(Or), I64, BaseType::Long),
+            Operator::I32AtomicRmw8OrU { memarg } => (memarg, Rmw(Or), I8, BaseType::Int),
+            Operator::I32AtomicRmw16OrU { memarg } => (memarg, Rmw(Or), I16, BaseType::Int),
+            Operator::I64AtomicRmw8OrU { memarg } => (memarg, Rmw(Or), I8, BaseType::Long),
+            Operator::I64AtomicRmw16OrU { memarg } => (memarg, Rmw(Or), I16, BaseType::Long),
+            Operator::I64AtomicRmw32OrU { memarg } => (memarg, Rmw(Or), I32, BaseType::Long),
+
+            Operator::I32AtomicRmwXor { memarg } => (memarg, Rmw(Xor), I32, BaseType::Int),
+            Operator::I64AtomicRmwXor { memarg } => (memarg, Rmw(Xor), I64, BaseType::Long),
+            Operator::I32AtomicRmw8XorU { memarg } => (memarg, Rmw(Xor), I8, BaseType::Int),
+            Operator::I32AtomicRmw16XorU { memarg } => (memarg, Rmw(Xor), I16, BaseType::Int),
+            Operator::I64AtomicRmw8XorU { memarg } => (memarg, Rmw(Xor), I8, BaseType::Long),
+            Operator::I64AtomicRmw16XorU { memarg } => (memarg, Rmw(Xor), I16, BaseType::Long),
+            Operator::I64AtomicRmw32XorU { memarg } => (memarg, Rmw(Xor), I32, BaseType::Long),
+
+            Operator::I32AtomicRmwXchg { memarg } => (memarg, Rmw(Xchg), I32, BaseType::Int),
+            Operator::I64AtomicRmwXchg { memarg } => (memarg, Rmw(Xchg), I64, BaseType::Long),
+            Operator::I32AtomicRmw8XchgU { memarg } => (memarg, Rmw(Xchg), I8, BaseType::Int),
+            Operator::I32AtomicRmw16XchgU { memarg } => (memarg, Rmw(Xchg), I16, BaseType::Int),
+            Operator::I64AtomicRmw8XchgU { memarg } => (memarg, Rmw(Xchg), I8, BaseType::Long),
+            Operator::I64AtomicRmw16XchgU { memarg } => (memarg, Rmw(Xchg), I16, BaseType::Long),
+            Operator::I64AtomicRmw32XchgU { memarg } => (memarg, Rmw(Xchg), I32, BaseType::Long),
+
+            Operator::I32AtomicRmwCmpxchg { memarg } => (memarg, Cmpxchg, I32, BaseType::Int),
+            Operator::I64AtomicRmwCmpxchg { memarg } => (memarg, Cmpxchg, I64, BaseType::Long),
+            Operator::I32AtomicRmw8CmpxchgU { memarg } => (memarg, Cmpxchg, I8, BaseType::Int),
+            Operator::I32AtomicRmw16CmpxchgU { memarg } => (memarg, Cmpxchg, I16, BaseType::Int),
+            Operator::I64AtomicRmw8CmpxchgU { memarg } => (memarg, Cmpxchg, I8, BaseType::Long),
+            Operator::I64AtomicRmw16CmpxchgU { memarg } => (memarg, Cmpxchg, I16, BaseType::Long),
+            Operator::I64AtomicRmw32CmpxchgU { memarg } => (memarg, Cmpxchg, I32, BaseType::Long),
+
+            _ => return None,
+        };
+        let instruction = AtomicInstruction {
+            access,
+            width,
+            value_type,
+        };
+        Some((memarg, instruction))
+    }
+
+    /// Types of the operands after the address (in the order they are pushed on the stack)
+    pub fn operand_types(&self) -> Vec<BaseType> {
+        match self.access {
+            AtomicAccess::Load => vec![],
+            AtomicAccess::Store | AtomicAccess::Rmw(_) => vec![self.value_type],
+            AtomicAccess::Cmpxchg => vec![self.value_type, self.value_type],
+            AtomicAccess::Wait => vec![self.value_type, BaseType::Long],
+            AtomicAccess::Notify => vec![BaseType::Int],
+        }
+    }
+}
+
+/// Atomic operations that are generated as utility methods
+#[derive(Copy, Clone, Hash, PartialEq, Eq, Debug)]
+pub enum AtomicOperation {
+    /// Load an 8 or 16 bit value (zero-extended)
+    NarrowLoad(AtomicWidth),
+
+    /// Read-modify-write of an 8 or 16 bit value, returning the old value (zero-extended)
+    NarrowRmw(AtomicWidth, RmwOp),
+
+    /// Compare-exchange of an 8 or 16 bit value, returning the old value (zero-extended)
+    NarrowCmpxchg(AtomicWidth),
+
+    /// `memory.atomic.wait32`
+    Wait32,
+
+    /// `memory.atomic.wait64`
+    Wait64,
+
+    /// `memory.atomic.notify`
+    Notify,
+}
+
+impl AtomicOperation {
+    /// Get the method name
+    pub fn name(&self) -> UnqualifiedName {
+        let name = match self {
+            AtomicOperation::NarrowLoad(width) => format!("atomicLoad{}", width.bits()),
+            AtomicOperation::NarrowRmw(width, op) => format!("atomicRmw{}{:?}", width.bits(), op),
+            AtomicOperation::NarrowCmpxchg(width) => format!("atomicCmpxchg{}", width.bits()),
+            AtomicOperation::Wait32 => String::from("atomicWait32"),
+            AtomicOperation::Wait64 => String::from("atomicWait64"),
+            AtomicOperation::Notify => String::from("atomicNotify"),
+        };
+        UnqualifiedName::from_string(name).expect("atomic utility names are valid")
+    }
+
+    /// Type of the `VarHandle` view used in the operation
+    pub const fn view_type(&self) -> BaseType {
+        match self {
+            AtomicOperation::Wait64 => BaseType::Long,
+            _ => BaseType::Int,
+        }
+    }
+
+    /// Get the method descriptor
+    pub fn descriptor<'g>(
+        &self,
+        bytes_type: FieldType<ClassId<'g>>,
+        map: ClassId<'g>,
+    ) -> MethodDescriptor<ClassId<'g>> {
+        let map = FieldType::object(map);
+        let parameters = match self {
+            AtomicOperation::NarrowLoad(_) => vec![bytes_type, FieldType::int()],
+            AtomicOperation::NarrowRmw(_, _) => {
+                vec![bytes_type, FieldType::int(), FieldType::int()]
+            }
+            AtomicOperation::NarrowCmpxchg(_) => vec![
+                bytes_type,
+                FieldType::int(), // address
+                FieldType::int(), // expected
+                FieldType::int(), // replacement
+            ],
+            AtomicOperation::Wait32 => vec![
+                bytes_type,
+                FieldType::int(),  // address
+                FieldType::int(),  // expected
+                FieldType::long(), // timeout (in nanoseconds)
+                map,               // waiters
+            ],
+            AtomicOperation::Wait64 => vec![
+                bytes_type,
+                FieldType::int(),  // address
+                FieldType::long(), // expected
+                FieldType::long(), // timeout (in nanoseconds)
+                map,               // waiters
+            ],
+            AtomicOperation::Notify => vec![
+                bytes_type,
+                FieldType::int(), // address
+                FieldType::int(), // count
+                map,              // waiters
+            ],
+        };
+        MethodDescriptor {
+            parameters,
+            return_type: Some(FieldType::int()),
+        }
+    }
+
+    /// Generate the body of the utility method
+    ///
+    /// The bootstrap method should be the `VarHandle` bootstrap for `view_type`.
+    pub fn generate<'g>(
+        &self,
+        code: &mut CodeBuilder<'g>,
+        var_handle: BootstrapMethodId<'g>,
+        bytes_type: FieldType<ClassId<'g>>,
+    ) -> Result<(), Error> {
+        match *self {
+            AtomicOperation::NarrowLoad(width) => {
+                Self::generate_narrow_load(code, var_handle, bytes_type, width)
+            }
+            AtomicOperation::NarrowRmw(width, op) => {
+                Self::generate_narrow_rmw(code, var_handle, bytes_type, width, op)
+            }
+            AtomicOperation::NarrowCmpxchg(width) => {
+                Self::generate_narrow_cmpxchg(code, var_handle, bytes_type, width)
+            }
+            AtomicOperation::Wait32 => {
+                Self::generate_wait(code, var_handle, bytes_type, BaseType::Int)
+            }
+            AtomicOperation::Wait64 => {
+                Self::generate_wait(code, var_handle, bytes_type, BaseType::Long)
+            }
+            AtomicOperation::Notify => Self::generate_notify(code, var_handle, bytes_type),
+        }
+    }
+
+    /// Throw if a 16-bit access isn't aligned, then compute the index of the containing `int`, the
+    /// shift to get to the narrow value, and the mask of the narrow value (in that order) into the
+    /// locals starting at `word_local`
+    fn generate_narrow_prologue<'g>(
+        code: &mut CodeBuilder<'g>,
+        width: AtomicWidth,
+        address_argument: u16,
+        word_local: u16,
+    ) -> Result<(), Error> {
+        let mask = width.narrow_mask().expect("narrow width");
+        let shift_local = word_local + 1;
+        let mask_local = word_local + 2;
+
+        if width == AtomicWidth::I16 {
+            let aligned = code.fresh_label();
+            code.push_instruction(Instruction::ILoad(address_argument))?;
+            code.push_instruction(Instruction::IConst1)?;
+            code.push_instruction(Instruction::IAnd)?;
+            code.push_branch_instruction(BranchInstruction::If(OrdComparison::EQ, aligned, ()))?;
+            code.new(code.java.classes.lang.illegal_argument_exception)?;
+            code.push_instruction(Instruction::Dup)?;
+            code.const_string("unaligned atomic")?;
+            code.invoke(code.java.members.lang.illegal_argument_exception.init)?;
+            code.push_branch_instruction(BranchInstruction::AThrow)?;
+            code.place_label(aligned)?;
+        }
+
+        // int word = address & ~3;
+        code.push_instruction(Instruction::ILoad(address_argument))?;
+        code.const_int(!3)?;
+        code.push_instruction(Instruction::IAnd)?;
+        code.push_instruction(Instruction::IStore(word_local))?;
+
+        // int shift = (address & 3) << 3;
+        code.push_instruction(Instruction::ILoad(address_argument))?;
+        code.push_instruction(Instruction::IConst3)?;
+        code.push_instruction(Instruction::IAnd)?;
+        code.push_instruction(Instruction::IConst3)?;
+        code.push_instruction(Instruction::ISh(ShiftType::Left))?;
+        code.push_instruction(Instruction::IStore(shift_local))?;
+
+        // int mask = MASK << shift;
+        code.const_int(mask)?;
+        code.push_instruction(Instruction::ILoad(shift_local))?;
+        code.push_instruction(Instruction::ISh(ShiftType::Left))?;
+        code.push_instruction(Instruction::IStore(mask_local))?;
+
+        Ok(())
+    }
+
+    /// Push `(word >>> shift) & MASK` onto the stack, assuming `word` is on top of the stack
+    fn generate_extract_narrow<'g>(
+        code: &mut CodeBuilder<'g>,
+        width: AtomicWidth,
+        shift_local: u16,
+    ) -> Result<(), Error> {
+        code.push_instruction(Instruction::ILoad(shift_local))?;
+        code.push_instruction(Instruction::ISh(ShiftType::LogicalRight))?;
+        code.const_int(width.narrow_mask().expect("narrow width"))?;
+        code.push_instruction(Instruction::IAnd)?;
+        Ok(())
+    }
+
+    /// Push `(old & ~mask) | (value << shift)` onto the stack, assuming `value` is on top of the
+    /// stack (and already masked)
+    fn generate_insert_narrow<'g>(
+        code: &mut CodeBuilder<'g>,
+        old_local: u16,
+        shift_local: u16,
+        mask_local: u16,
+    ) -> Result<(), Error> {
+        code.push_instruction(Instruction::ILoad(shift_local))?;
+        code.push_instruction(Instruction::ISh(ShiftType::Left))?;
+        code.push_instruction(Instruction::ILoad(old_local))?;
+        code.push_instruction(Instruction::ILoad(mask_local))?;
+        code.push_instruction(Instruction::IConstM1)?;
+        code.push_instruction(Instruction::IXor)?;
+        code.push_instruction(Instruction::IAnd)?;
+        code.push_instruction(Instruction::IOr)?;
+        Ok(())
+    }
+
+    /// Analagous to
+    ///
+    /// ```java
+    /// static int atomicLoad16(ByteBuffer buffer, int address) {
+    ///   if ((address & 1) != 0) throw new IllegalArgumentException("unaligned atomic");
+    ///   int word = address & ~3;
+    ///   int shift = (address & 3) << 3;
+    ///   int mask = 0xffff << shift;
+    ///   return (INT_VIEW.getVolatile(buffer, word) >>> shift) & 0xffff;
+    /// }
+    /// ```
+    fn generate_narrow_load<'g>(
+        code: &mut CodeBuilder<'g>,
+        var_handle: BootstrapMethodId<'g>,
+        bytes_type: FieldType<ClassId<'g>>,
+        width: AtomicWidth,
+    ) -> Result<(), Error> {
+        let buffer_argument = 0;
+        let address_argument = 1;
+        let word_local = 2;
+        let shift_local = 3;
+
+        Self::generate_narrow_prologue(code, width, address_argument, word_local)?;
+        code.push_instruction(Instruction::ALoad(buffer_argument))?;
+        code.push_instruction(Instruction::ILoad(word_local))?;
+        invoke_var_handle(
+            code,
+            var_handle,
+            bytes_type,
+            BaseType::Int,
+            UnqualifiedName::GETVOLATILE,
+            0,
+            Some(FieldType::int()),
+        )?;
+        Self::generate_extract_narrow(code, width, shift_local)?;
+        code.push_branch_instruction(BranchInstruction::IReturn)?;
+
+        Ok(())
+    }
+
+    /// Analagous to
+    ///
+    /// ```java
+    /// static int atomicRmw8Add(ByteBuffer buffer, int address, int operand) {
+    ///   int word = address & ~3;
+    ///   int shift = (address & 3) << 3;
+    ///   int mask = 0xff << shift;
+    ///   int old, oldNarrow;
+    ///   do {
+    ///     old = INT_VIEW.getVolatile(buffer, word);
+    ///     oldNarrow = (old >>> shift) & 0xff;
+    ///     int replacement = (oldNarrow + operand) & 0xff;
+    ///   } while (!INT_VIEW.compareAndSet(buffer, word, old, (old & ~mask) | (replacement << shift)));
+    ///   return oldNarrow;
+    /// }
+    /// ```
+    fn generate_narrow_rmw<'g>(
+        code: &mut CodeBuilder<'g>,
+        var_handle: BootstrapMethodId<'g>,
+        bytes_type: FieldType<ClassId<'g>>,
+        width: AtomicWidth,
+        op: RmwOp,
+    ) -> Result<(), Error> {
+        let buffer_argument = 0;
+        let address_argument = 1;
+        let operand_argument = 2;
+        let word_local = 3;
+        let shift_local = 4;
+        let mask_local = 5;
+        let old_local = 6;
+        let old_narrow_local = 7;
+
+        let retry = code.fresh_label();
+
+        Self::generate_narrow_prologue(code, width, address_argument, word_local)?;
+        code.push_instruction(Instruction::IConst0)?;
+        code.push_instruction(Instruction::IStore(old_local))?;
+        code.push_instruction(Instruction::IConst0)?;
+        code.push_instruction(Instruction::IStore(old_narrow_local))?;
+
+        // Read the current value
+        code.place_label(retry)?;
+        code.push_instruction(Instruction::ALoad(buffer_argument))?;
+        code.push_instruction(Instruction::ILoad(word_local))?;
+        invoke_var_handle(
+            code,
+            var_handle,
+            bytes_type,
+            BaseType::Int,
+            UnqualifiedName::GETVOLATILE,
+            0,
+            Some(FieldType::int()),
+        )?;
+        code.push_instruction(Instruction::Dup)?;
+        code.push_instruction(Instruction::IStore(old_local))?;
+        Self::generate_extract_narrow(code, width, shift_local)?;
+        code.push_instruction(Instruction::IStore(old_narrow_local))?;
+
+        // Try to swap in the updated value
+        code.push_instruction(Instruction::ALoad(buffer_argument))?;
+        code.push_instruction(Instruction::ILoad(word_local))?;
+        code.push_instruction(Instruction::ILoad(old_local))?;
+        if op != RmwOp::Xchg {
+            code.push_instruction(Instruction::ILoad(old_narrow_local))?;
+        }
+        code.push_instruction(Instruction::ILoad(operand_argument))?;
+        match op {
+            RmwOp::Add => code.push_instruction(Instruction::IAdd)?,
+            RmwOp::Sub => code.push_instruction(Instruction::ISub)?,
+            RmwOp::And => code.push_instruction(Instruction::IAnd)?,
+            RmwOp::Or => code.push_instruction(Instruction::IOr)?,
+            RmwOp::Xor => code.push_instruction(Instruction::IXor)?,
+            RmwOp::Xchg => (),
+        }
+        code.const_int(width.narrow_mask().expect("narrow width"))?;
+        code.push_instruction(Instruction::IAnd)?;
+        Self::generate_insert_narrow(code, old_local, shift_local, mask_local)?;
+        invoke_var_handle(
+            code,
+            var_handle,
+            bytes_type,
+            BaseType::Int,
+            UnqualifiedName::COMPAREANDSET,
+            2,
+            Some(FieldType::boolean()),
+        )?;
+        code.push_branch_instruction(BranchInstruction::If(OrdComparison::EQ, retry, ()))?;
+
+        code.push_instruction(Instruction::ILoad(old_narrow_local))?;
+        code.push_branch_instruction(BranchInstruction::IReturn)?;
+
+        Ok(())
+    }
+
+    /// Analagous to
+    ///
+    /// ```java
+    /// static int atomicCmpxchg8(ByteBuffer buffer, int address, int expected, int replacement) {
+    ///   expected &= 0xff;
+    ///   replacement &= 0xff;
+    ///   int word = address & ~3;
+    ///   int shift = (address & 3) << 3;
+    ///   int mask = 0xff << shift;
+    ///   int old, oldNarrow;
+    ///   do {
+    ///     old = INT_VIEW.getVolatile(buffer, word);
+    ///     oldNarrow = (old >>> shift) & 0xff;
+    ///     if (oldNarrow != expected) break;
+    ///   } while (!INT_VIEW.compareAndSet(buffer, word, old, (old & ~mask) | (replacement << shift)));
+    ///   return oldNarrow;
+    /// }
+    /// ```
+    fn generate_narrow_cmpxchg<'g>(
+        code: &mut CodeBuilder<'g>,
+        var_handle: BootstrapMethodId<'g>,
+        bytes_type: FieldType<ClassId<'g>>,
+        width: AtomicWidth,
+    ) -> Result<(), Error> {
+        let buffer_argument = 0;
+        let address_argument = 1;
+        let expected_argument = 2;
+        let replacement_argument = 3;
+        let word_local = 4;
+        let shift_local = 5;
+        let mask_local = 6;
+        let old_local = 7;
+        let old_narrow_local = 8;
+
+        let retry = code.fresh_label();
+        let done = code.fresh_label();
+        let mask = width.narrow_mask().expect("narrow width");
+
+        // Wrap the operands to the access width
+        code.push_instruction(Instruction::ILoad(expected_argument))?;
+        code.const_int(mask)?;
+        code.push_instruction(Instruction::IAnd)?;
+        code.push_instruction(Instruction::IStore(expected_argument))?;
+        code.push_instruction(Instruction::ILoad(replacement_argument))?;
+        code.const_int(mask)?;
+        code.push_instruction(Instruction::IAnd)?;
+        code.push_instruction(Instruction::IStore(replacement_argument))?;
+
+        Self::generate_narrow_prologue(code, width, address_argument, word_local)?;
+        code.push_instruction(Instruction::IConst0)?;
+        code.push_instruction(Instruction::IStore(old_local))?;
+        code.push_instruction(Instruction::IConst0)?;
+        code.push_instruction(Instruction::IStore(old_narrow_local))?;
+
+        // Read the current value and check it is what we expect
+        code.place_label(retry)?;
+        code.push_instruction(Instruction::ALoad(buffer_argument))?;
+        code.push_instruction(Instruction::ILoad(word_local))?;
+        invoke_var_handle(
+            code,
+            var_handle,
+            bytes_type,
+            BaseType::Int,
+            UnqualifiedName::GETVOLATILE,
+            0,
+            Some(FieldType::int()),
+        )?;
+        code.push_instruction(Instruction::Dup)?;
+        code.push_instruction(Instruction::IStore(old_local))?;
+        Self::generate_extract_narrow(code, width, shift_local)?;
+        code.push_instruction(Instruction::Dup)?;
+        code.push_instruction(Instruction::IStore(old_narrow_local))?;
+        code.push_instruction(Instruction::ILoad(expected_argument))?;
+        code.push_branch_instruction(BranchInstruction::IfICmp(OrdComparison::NE, done, ()))?;
+
+        // Try to swap in the replacement
+        code.push_instruction(Instruction::ALoad(buffer_argument))?;
+        code.push_instruction(Instruction::ILoad(word_local))?;
+        code.push_instruction(Instruction::ILoad(old_local))?;
+        code.push_instruction(Instruction::ILoad(replacement_argument))?;
+        Self::generate_insert_narrow(code, old_local, shift_local, mask_local)?;
+        invoke_var_handle(
+            code,
+            var_handle,
+            bytes_type,
+            BaseType::Int,
+            UnqualifiedName::COMPAREANDSET,
+            2,
+            Some(FieldType::boolean()),
+        )?;
+        code.push_branch_instruction(BranchInstruction::If(OrdComparison::EQ, retry, ()))?;
+
+        code.place_label(done)?;
+        code.push_instruction(Instruction::ILoad(old_narrow_local))?;
+        code.push_branch_instruction(BranchInstruction::IReturn)?;
+
+        Ok(())
+    }
+
+    /// Analagous to
+    ///
+    /// ```java
+    /// static int atomicWait32(
+    ///   ByteBuffer buffer,
+    ///   int address,
+    ///   int expected,
+    ///   long timeout,
+    ///   Map waiters
+    /// ) {
+    ///   INT_VIEW.getVolatile(buffer, address); // bounds and alignment check
+    ///
+    ///   ConcurrentLinkedQueue queue = new ConcurrentLinkedQueue();
+    ///   Object existing = waiters.putIfAbsent(Integer.valueOf(address), queue);
+    ///   if (existing != null) queue = (ConcurrentLinkedQueue) existing;
+    ///   Thread thread = Thread.currentThread();
+    ///   queue.add(thread);
+    ///
+    ///   if (INT_VIEW.getVolatile(buffer, address) != expected) {
+    ///     return queue.remove(thread) ? 1 : 0;
+    ///   }
+    ///
+    ///   long deadline = System.nanoTime() + timeout;
+    ///   long remaining = 0;
+    ///   do {
+    ///     if (timeout < 0) {
+    ///       LockSupport.park();
+    ///     } else {
+    ///       remaining = deadline - System.nanoTime();
+    ///       if (remaining <= 0) return queue.remove(thread) ? 2 : 0;
+    ///       LockSupport.parkNanos(remaining);
+    ///     }
+    ///   } while (queue.contains(thread));
+    ///   return 0;
+    /// }
+    /// ```
+    fn generate_wait<'g>(
+        code: &mut CodeBuilder<'g>,
+        var_handle: BootstrapMethodId<'g>,
+        bytes_type: FieldType<ClassId<'g>>,
+        view: BaseType,
+    ) -> Result<(), Error> {
+        let value_type = FieldType::Base(view);
+        let buffer_argument = 0;
+        let address_argument = 1;
+        let expected_argument = 2;
+        let timeout_argument = expected_argument + value_type.width() as u16;
+        let waiters_argument = timeout_argument + 2;
+        let queue_local = waiters_argument + 1;
+        let thread_local = queue_local + 1;
+        let deadline_local = thread_local + 1;
+        let remaining_local = deadline_local + 2;
+
+        let queue_ready = code.fresh_label();
+        let fresh_queue = code.fresh_label();
+        let equal = code.fresh_label();
+        let retry = code.fresh_label();
+        let timed = code.fresh_label();
+        let not_timed_out = code.fresh_label();
+        let check_woken = code.fresh_label();
+        let woken = code.fresh_label();
+
+        let queue_class = code.java.classes.util.concurrent.concurrent_linked_queue;
+        let queue_members = &code.java.members.util.concurrent.concurrent_linked_queue;
+
+        // Check bounds and alignment
+        code.push_instruction(Instruction::ALoad(buffer_argument))?;
+        code.push_instruction(Instruction::ILoad(address_argument))?;
+        invoke_var_handle(
+            code,
+            var_handle,
+            bytes_type,
+            view,
+            UnqualifiedName::GETVOLATILE,
+            0,
+            Some(value_type),
+        )?;
+        code.pop()?;
+
+        // Find or create the queue of waiters
+        code.new(queue_class)?;
+        code.push_instruction(Instruction::Dup)?;
+        code.invoke(queue_members.init)?;
+        code.push_instruction(Instruction::AStore(queue_local))?;
+        code.push_instruction(Instruction::ALoad(waiters_argument))?;
+        code.push_instruction(Instruction::ILoad(address_argument))?;
+        code.invoke(code.java.members.lang.integer.value_of)?;
+        code.push_instruction(Instruction::ALoad(queue_local))?;
+        code.invoke(code.java.members.util.map.put_if_absent)?;
+        code.push_instruction(Instruction::Dup)?;
+        code.push_branch_instruction(BranchInstruction::IfNull(EqComparison::EQ, fresh_queue, ()))?;
+        code.checkcast(queue_class)?;
+        code.push_instruction(Instruction::AStore(queue_local))?;
+        code.push_branch_instruction(BranchInstruction::Goto(queue_ready))?;
+        code.place_label(fresh_queue)?;
+        code.push_instruction(Instruction::Pop)?;
+        code.place_label(queue_ready)?;
+
+        // Enqueue the current thread
+        code.invoke(code.java.members.lang.thread.current_thread)?;
+        code.push_instruction(Instruction::AStore(thread_local))?;
+        code.push_instruction(Instruction::ALoad(queue_local))?;
+        code.push_instruction(Instruction::ALoad(thread_local))?;
+        code.invoke(queue_members.add)?;
+        code.push_instruction(Instruction::Pop)?;
+
+        // Don't wait if the value is not what was expected
+        code.push_instruction(Instruction::ALoad(buffer_argument))?;
+        code.push_instruction(Instruction::ILoad(address_argument))?;
+        invoke_var_handle(
+            code,
+            var_handle,
+            bytes_type,
+            view,
+            UnqualifiedName::GETVOLATILE,
+            0,
+            Some(value_type),
+        )?;
+        if view == BaseType::Long {
+            code.push_instruction(Instruction::LLoad(expected_argument))?;
+            code.push_instruction(Instruction::LCmp)?;
+            code.push_branch_instruction(BranchInstruction::If(OrdComparison::EQ, equal, ()))?;
+        } else {
+            code.push_instruction(Instruction::ILoad(expected_argument))?;
+            code.push_branch_instruction(BranchInstruction::IfICmp(OrdComparison::EQ, equal, ()))?;
+        }
+        code.push_instruction(Instruction::ALoad(queue_local))?;
+        code.push_instruction(Instruction::ALoad(thread_local))?;
+        code.invoke(queue_members.remove)?;
+        code.push_branch_instruction(BranchInstruction::IReturn)?;
+
+        // Park until we get removed from the queue or we time out
+        code.place_label(equal)?;
+        code.invoke(code.java.members.lang.system.nano_time)?;
+        code.push_instruction(Instruction::LLoad(timeout_argument))?;
+        code.push_instruction(Instruction::LAdd)?;
+        code.push_instruction(Instruction::LStore(deadline_local))?;
+        code.push_instruction(Instruction::LConst0)?;
+        code.push_instruction(Instruction::LStore(remaining_local))?;
+
+        code.place_label(retry)?;
+        code.push_instruction(Instruction::LLoad(timeout_argument))?;
+        code.push_instruction(Instruction::LConst0)?;
+        code.push_instruction(Instruction::LCmp)?;
+        code.push_branch_instruction(BranchInstruction::If(OrdComparison::GE, timed, ()))?;
+        code.invoke(code.java.members.util.concurrent.lock_support.park)?;
+        code.push_branch_instruction(BranchInstruction::Goto(check_woken))?;
+
+        code.place_label(timed)?;
+        code.push_instruction(Instruction::LLoad(deadline_local))?;
+        code.invoke(code.java.members.lang.system.nano_time)?;
+        code.push_instruction(Instruction::LSub)?;
+        code.push_instruction(Instruction::Dup2)?;
+        code.push_instruction(Instruction::LStore(remaining_local))?;
+        code.push_instruction(Instruction::LConst0)?;
+        code.push_instruction(Instruction::LCmp)?;
+        code.push_branch_instruction(BranchInstruction::If(OrdComparison::GT, not_timed_out, ()))?;
+        code.push_instruction(Instruction::ALoad(queue_local))?;
+        code.push_instruction(Instruction::ALoad(thread_local))?;
+        code.invoke(queue_members.remove)?;
+        code.push_branch_instruction(BranchInstruction::If(OrdComparison::EQ, woken, ()))?;
+        code.push_instruction(Instruction::IConst2)?;
+        code.push_branch_instruction(BranchInstruction::IReturn)?;
+
+        code.place_label(not_timed_out)?;
+        code.push_instruction(Instruction::LLoad(remaining_local))?;
+        code.invoke(code.java.members.util.concurrent.lock_support.park_nanos)?;
+
+        code.place_label(check_woken)?;
+        code.push_instruction(Instruction::ALoad(queue_local))?;
+        code.push_instruction(Instruction::ALoad(thread_local))?;
+        code.invoke(queue_members.contains)?;
+        code.push_branch_instruction(BranchInstruction::If(OrdComparison::NE, retry, ()))?;
+
+        code.place_label(woken)?;
+        code.push_instruction(Instruction::IConst0)?;
+        code.push_branch_instruction(BranchInstruction::IReturn)?;
+
+        Ok(())
+    }
+
+    /// Analagous to
+    ///
+    /// ```java
+    /// static int atomicNotify(ByteBuffer buffer, int address, int count, Map waiters) {
+    ///   INT_VIEW.getVolatile(buffer, address); // bounds and alignment check
+    ///
+    ///   Object queue = waiters.get(Integer.valueOf(address));
+    ///   if (queue == null) return 0;
+    ///
+    ///   int woken = 0;
+    ///   while (Integer.compareUnsigned(woken, count) < 0) {
+    ///     Object thread = ((ConcurrentLinkedQueue) queue).poll();
+    ///     if (thread == null) break;
+    ///     LockSupport.unpark((Thread) thread);
+    ///     woken++;
+    ///   }
+    ///   return woken;
+    /// }
+    /// ```
+    fn generate_notify<'g>(
+        code: &mut CodeBuilder<'g>,
+        var_handle: BootstrapMethodId<'g>,
+        bytes_type: FieldType<ClassId<'g>>,
+    ) -> Result<(), Error> {
+        let buffer_argument = 0;
+        let address_argument = 1;
+        let count_argument = 2;
+        let waiters_argument = 3;
+        let queue_local = 4;
+        let woken_local = 5;
+
+        let has_queue = code.fresh_label();
+        let retry = code.fresh_label();
+        let queue_empty = code.fresh_label();
+        let done = code.fresh_label();
+
+        let queue_class = code.java.classes.util.concurrent.concurrent_linked_queue;
+
+        // Check bounds and alignment
+        code.push_instruction(Instruction::ALoad(buffer_argument))?;
+        code.push_instruction(Instruction::ILoad(address_argument))?;
+        invoke_var_handle(
+            code,
+            var_handle,
+            bytes_type,
+            BaseType::Int,
+            UnqualifiedName::GETVOLATILE,
+            0,
+            Some(FieldType::int()),
+        )?;
+        code.push_instruction(Instruction::Pop)?;
+
+        // Find the queue of waiters
+        code.push_instruction(Instruction::ALoad(waiters_argument))?;
+        code.push_instruction(Instruction::ILoad(address_argument))?;
+        code.invoke(code.java.members.lang.integer.value_of)?;
+        code.invoke(code.java.members.util.map.get)?;
+        code.push_instruction(Instruction::Dup)?;
+        code.push_branch_instruction(BranchInstruction::IfNull(EqComparison::NE, has_queue, ()))?;
+        code.push_instruction(Instruction::Pop)?;
+        code.push_instruction(Instruction::IConst0)?;
+        code.push_branch_instruction(BranchInstruction::IReturn)?;
+
+        code.place_label(has_queue)?;
+        code.checkcast(queue_class)?;
+        code.push_instruction(Instruction::AStore(queue_local))?;
+        code.push_instruction(Instruction::IConst0)?;
+        code.push_instruction(Instruction::IStore(woken_local))?;
+
+        // Wake up threads until we've hit the count or the queue is empty
+        code.place_label(retry)?;
+        code.push_instruction(Instruction::ILoad(woken_local))?;
+        code.push_instruction(Instruction::ILoad(count_argument))?;
+        code.invoke(code.java.members.lang.integer.compare_unsigned)?;
+        code.push_branch_instruction(BranchInstruction::If(OrdComparison::GE, done, ()))?;
+        code.push_instruction(Instruction::ALoad(queue_local))?;
+        code.invoke(
+            code.java
+                .members
+                .util
+                .concurrent
+                .concurrent_linked_queue
+                .poll,
+        )?;
+        code.push_instruction(Instruction::Dup)?;
+        code.push_branch_instruction(BranchInstruction::IfNull(EqComparison::EQ, queue_empty, ()))?;
+        code.checkcast(code.java.classes.lang.thread)?;
+        code.invoke(code.java.members.util.concurrent.lock_support.unpark)?;
+        code.push_instruction(Instruction::IInc(woken_local, 1))?;
+        code.push_branch_instruction(BranchInstruction::Goto(retry))?;
+
+        code.place_label(queue_empty)?;
+        code.push_instruction(Instruction::Pop)?;
+        code.place_label(done)?;
+        code.push_instruction(Instruction::ILoad(woken_local))?;
+        code.push_branch_instruction(BranchInstruction::IReturn)?;
+
+        Ok(())
+    }
+}
+
+/// Access memory through a `VarHandle` view of its bytes
+///
+/// Assumes the top of the stack has the bytes (of type `bytes_type`), the index, then `operands`
+/// values of the view type. The bootstrap method should be the `VarHandle` bootstrap for `view`.
+pub fn invoke_var_handle<'g>(
+    code: &mut CodeBuilder<'g>,
+    var_handle: BootstrapMethodId<'g>,
+    bytes_type: FieldType<ClassId<'g>>,
+    view: BaseType,
+    access_mode: UnqualifiedName,
+    operands: usize,
+    return_type: Option<FieldType<ClassId<'g>>>,
+) -> Result<(), Error> {
+    let mut parameters = vec![bytes_type, FieldType::int()];
+    parameters.extend(std::iter::repeat(FieldType::Base(view)).take(operands));
+    let descriptor = MethodDescriptor {
+        parameters,
+        return_type,
+    };
+    code.invoke_dynamic(var_handle, access_mode, descriptor)?;
+    Ok(())
+}
@@ -1,15 +1,18 @@
-use crate::jvm::class_graph::{AccessMode, ClassGraph, FieldId, JavaLibrary, MethodId};
+use crate::jvm::class_graph::{AccessMode, ClassGraph, ClassId, FieldId, JavaLibrary, MethodId};
 use crate::jvm::code::{
     BranchInstruction, CodeBuilder, CodeBuilderExts, EqComparison, Instruction,
 };
 use crate::jvm::model::Method;
-use crate::jvm::{BaseType, Error, FieldType};
+use crate::jvm::{BaseType, Error, FieldType, MethodDescriptor};
 use wasmparser::DataKind;
 
 /// Translated data segment
 ///
 /// Every data segment is turned into a static private method on the main WASM module. That method
 /// takes as argument the WASM module and returns the byte array associated with the constant data.
+/// The bytes are either in string constants or in a resource file (see `DataStorage`), and they
+/// get read by an initializer method in one of the parts (so that the string constants don't all
+/// end up in the constant pool of the main class).
 pub struct Data<'a, 'g> {
     /// Kind of data segment (active vs. passive)
     pub kind: Option<DataKind<'a>>,
@@ -22,16 +25,49 @@ pub struct Data<'a, 'g> {
 
     /// Field in WASM class containing the data, if initialized
     pub field: FieldId<'g>,
+
+    /// Resource file from which the data bytes get read (instead of string constants)
+    pub resource: Option<DataResource>,
+}
+
+/// Resource file holding the bytes of a data segment
+pub struct DataResource {
+    /// Path of the resource, relative to the root of the class path
+    pub name: String,
+
+    /// Whether the bytes are compressed (in the zlib format)
+    pub compressed: bool,
 }
 
 impl<'a, 'g> Data<'a, 'g> {
+    /// Estimated bytes of bytecode per string constant in an initializer method
+    pub const INIT_CHUNK_CODE_LENGTH: usize = 16;
+
+    /// Number of string constants holding the bytes
+    pub fn string_constants(&self) -> usize {
+        match (&self.resource, self.bytes) {
+            (None, Some(bytes)) => (bytes.len() + u16::MAX as usize - 1) / u16::MAX as usize,
+            _ => 0,
+        }
+    }
+
+    /// Descriptor of the initializer method, which takes the module and returns the bytes
+    pub fn init_method_descriptor(wasm_module_class: ClassId<'g>) -> MethodDescriptor<ClassId<'g>> {
+        MethodDescriptor {
+            parameters: vec![FieldType::object(wasm_module_class)],
+            return_type: Some(FieldType::array(FieldType::byte())),
+        }
+    }
+
     /// Generate the static method associated with the data segment
+    ///
+    /// The bytes are cached in `field`. The first time around, they get read by `init_method`.
     pub fn generate_method(
         &self,
         class_graph: &'g ClassGraph<'g>,
         java: &'g JavaLibrary<'g>,
+        init_method: MethodId<'g>,
     ) -> Result<Method<'g>, Error> {
-        let bytes = self.bytes.unwrap();
         let mut code = CodeBuilder::new(class_graph, java, self.method);
         let generate = code.fresh_label();
         let this_off = 0;
@@ -44,22 +80,10 @@ impl<'a, 'g> Data<'a, 'g> {
         code.return_(Some(FieldType::array(FieldType::byte())))?;
         code.place_label(generate)?;
         code.pop()?;
+        code.push_instruction(Instruction::ALoad(this_off))?;
+        code.invoke(init_method)?;
 
-        // Prepare the byte array to return
-        code.const_int(bytes.len() as i32)?;
-        code.push_instruction(Instruction::NewArray(BaseType::Byte))?;
-        code.invoke(java.members.nio.byte_buffer.wrap)?;
-
-        // Copy in all of the data into the buffer
-        for chunk in bytes.chunks(u16::MAX as usize) {
-            code.const_string(chunk.iter().map(|&c| c as char).collect::<String>())?;
-            code.const_string("ISO-8859-1")?;
-            code.invoke(java.members.lang.string.get_bytes)?;
-            code.invoke(java.members.nio.byte_buffer.put_bytearray_relative)?;
-        }
-
-        // Return the buffer
-        code.invoke(code.java.members.nio.byte_buffer.array)?;
+        // Cache and return the bytes
         code.dup()?;
         code.push_instruction(Instruction::ALoad(this_off))?;
         code.push_instruction(Instruction::Swap)?;
@@ -74,6 +98,64 @@ impl<'a, 'g> Data<'a, 'g> {
         })
     }
 
+    /// Generate the body of the initializer method, which reads the bytes
+    pub fn generate_init_method(&self, code: &mut CodeBuilder<'g>) -> Result<(), Error> {
+        let java = code.java;
+        let this_off = 0;
+
+        if let Some(resource) = &self.resource {
+            // Open the resource using the class loader of the module
+            code.push_instruction(Instruction::ALoad(this_off))?;
+            code.invoke(java.members.lang.object.get_class)?;
+            code.invoke(java.members.lang.class.get_class_loader)?;
+            code.const_string(resource.name.clone())?;
+            code.invoke(java.members.lang.class_loader.get_resource_as_stream)?;
+            let resource_found = code.fresh_label();
+            code.dup()?;
+            code.push_branch_instruction(BranchInstruction::IfNull(
+                EqComparison::NE,
+                resource_found,
+                (),
+            ))?;
+            code.new(java.classes.lang.illegal_argument_exception)?;
+            code.dup()?;
+            code.const_string(format!("Could not find resource {}", resource.name))?;
+            code.invoke(java.members.lang.illegal_argument_exception.init)?;
+            code.push_branch_instruction(BranchInstruction::AThrow)?;
+            code.place_label(resource_found)?;
+            if resource.compressed {
+                code.new(java.classes.util.zip.inflater_input_stream)?;
+                code.push_instruction(Instruction::DupX1)?;
+                code.push_instruction(Instruction::Swap)?;
+                code.invoke(java.members.util.zip.inflater_input_stream.init)?;
+            }
+
+            // Read all of the bytes, then close the stream
+            code.dup()?;
+            code.invoke(java.members.io.input_stream.read_all_bytes)?;
+            code.push_instruction(Instruction::Swap)?;
+            code.invoke(java.members.io.input_stream.close)?;
+        } else {
+            // Prepare the byte array to return
+            let bytes = self.bytes.unwrap();
+            code.const_int(bytes.len() as i32)?;
+            code.push_instruction(Instruction::NewArray(BaseType::Byte))?;
+            code.invoke(java.members.nio.byte_buffer.wrap)?;
+
+            // Copy in all of the data into the buffer
+            for chunk in bytes.chunks(u16::MAX as usize) {
+                code.const_string(chunk.iter().map(|&c| c as char).collect::<String>())?;
+                code.const_string("ISO-8859-1")?;
+                code.invoke(java.members.lang.string.get_bytes)?;
+                code.invoke(java.members.nio.byte_buffer.put_bytearray_relative)?;
+            }
+            code.invoke(code.java.members.nio.byte_buffer.array)?;
+        }
+        code.return_(Some(FieldType::array(FieldType::byte())))?;
+
+        Ok(())
+    }
+
     /// Generate code corresponding to dropping the data
     pub fn drop_data(&self, code: &mut CodeBuilder<'g>, this_off: u16) -> Result<(), Error> {
         code.push_instruction(Instruction::ALoad(this_off))?;
@@ -1,9 +1,9 @@
-use crate::jvm::class_graph::{AccessMode, ClassGraph, FieldId, JavaLibrary, MethodId};
+use crate::jvm::class_graph::{AccessMode, ClassGraph, ClassId, FieldId, JavaLibrary, MethodId};
 use crate::jvm::code::{
     BranchInstruction, CodeBuilder, CodeBuilderExts, EqComparison, Instruction,
 };
 use crate::jvm::model::Method;
-use crate::jvm::{FieldType, RefType};
+use crate::jvm::{FieldType, MethodDescriptor};
 use crate::runtime::WasmRuntime;
 use crate::translate::{Error, Function, Global};
 use crate::wasm::TableType;
@@ -29,18 +29,42 @@ pub struct Element<'a, 'g> {
 }
 
 impl<'a, 'g> Element<'a, 'g> {
-    /// Generate the static method associated with the data segment
+    /// Number of items filled in by each of the initializer methods of an element
+    ///
+    /// Initializers are split up this way since pushing an item can take a couple dozen bytes of
+    /// bytecode and large tables would otherwise overflow the JVM's code length limit.
+    pub const INIT_CHUNK_ITEMS: usize = 1024;
+
+    /// Estimated bytes of bytecode per item in an initializer method
+    pub const INIT_ITEM_CODE_LENGTH: usize = 24;
+
+    /// Descriptor of the initializer methods, which take the array to fill in and the module
+    pub fn init_method_descriptor(
+        &self,
+        java: &'g JavaLibrary<'g>,
+        wasm_module_class: ClassId<'g>,
+    ) -> MethodDescriptor<ClassId<'g>> {
+        MethodDescriptor {
+            parameters: vec![
+                FieldType::array(self.element_type.field_type(&java.classes)),
+                FieldType::object(wasm_module_class),
+            ],
+            return_type: None,
+        }
+    }
+
+    /// Generate the static method which gets the element array
+    ///
+    /// The array is cached in `field`. The first time around, it gets filled in by calling
+    /// `init_methods`, each of which fills in the next `INIT_CHUNK_ITEMS` items.
     pub fn generate_method(
         &self,
         class_graph: &'g ClassGraph<'g>,
         java: &'g JavaLibrary<'g>,
-        runtime: &WasmRuntime<'g>,
-        functions: &[Function<'a, 'g>],
-        globals: &[Global<'a, 'g>],
+        init_methods: &[MethodId<'g>],
     ) -> Result<Method<'g>, Error> {
         let mut code = CodeBuilder::new(class_graph, java, self.method);
         let this_off = 0;
-        let offset_var = 1;
         let generate = code.fresh_label();
 
         // Check if the cached data field is non-null (and return it if so)
@@ -60,42 +84,11 @@ impl<'a, 'g> Element<'a, 'g> {
             self.element_type.ref_type(&java.classes),
         ))?;
 
-        // Index variable
-        code.const_int(0)?;
-        code.push_instruction(Instruction::IStore(offset_var))?;
-
-        // Copy in all of the items into the element array
-        for item in &self.items {
-            code.push_instruction(Instruction::Dup)?;
-            code.push_instruction(Instruction::ILoad(offset_var))?;
-            match item {
-                ElementItem::Func(func_idx) => {
-                    let function = &functions[*func_idx as usize];
-                    code.const_methodhandle(function.method)?;
-                    code.const_int(function.func_type.inputs.len() as i32)?;
-                    code.const_int(1)?;
-                    code.new_ref_array(RefType::Object(code.java.classes.lang.object))?;
-                    code.dup()?;
-                    code.const_int(0)?;
-                    code.push_instruction(Instruction::ALoad(this_off))?;
-                    code.push_instruction(Instruction::AAStore)?;
-                    code.invoke(
-                        code.java
-                            .members
-                            .lang
-                            .invoke
-                            .method_handles
-                            .insert_arguments,
-                    )?;
-                }
-                ElementItem::Expr(elem_expr) => {
-                    super::translate_const_expr(
-                        functions, globals, runtime, this_off, &mut code, elem_expr,
-                    )?;
-                }
-            }
-            code.push_instruction(Instruction::AAStore)?;
-            code.push_instruction(Instruction::IInc(offset_var, 1))?;
+        // Fill in the items, one chunk at a time
+        for init_method in init_methods {
+            code.dup()?;
+            code.push_instruction(Instruction::ALoad(this_off))?;
+            code.invoke(*init_method)?;
         }
 
         // Return the array
@@ -115,6 +108,42 @@ impl<'a, 'g> Element<'a, 'g> {
         })
     }
 
+    /// Generate the body of an initializer method, filling in the items from `chunk_start`
+    pub fn generate_init_method(
+        &self,
+        code: &mut CodeBuilder<'g>,
+        runtime: &WasmRuntime<'g>,
+        functions: &[Function<'a, 'g>],
+        globals: &[Global<'a, 'g>],
+        chunk_start: usize,
+    ) -> Result<(), Error> {
+        let array_off = 0;
+        let this_off = 1;
+
+        // Copy in the items of the chunk into the element array
+        let chunk = self.items[chunk_start..]
+            .iter()
+            .take(Self::INIT_CHUNK_ITEMS);
+        for (item_idx, item) in chunk.enumerate() {
+            code.push_instruction(Instruction::ALoad(array_off))?;
+            code.const_int((chunk_start + item_idx) as i32)?;
+            match item {
+                ElementItem::Func(func_idx) => {
+                    functions[*func_idx as usize].load_reference(runtime, code, this_off)?;
+                }
+                ElementItem::Expr(elem_expr) => {
+                    super::translate_const_expr(
+                        functions, globals, runtime, this_off, code, elem_expr,
+                    )?;
+                }
+            }
+            code.push_instruction(Instruction::AAStore)?;
+        }
+        code.push_branch_instruction(BranchInstruction::Return)?;
+
+        Ok(())
+    }
+
     /// Generate code corresponding to dropping the element
     pub fn drop_element(&self, code: &mut CodeBuilder<'g>, this_off: u16) -> Result<(), Error> {
         code.push_instruction(Instruction::ALoad(this_off))?;
@@ -8,6 +8,7 @@ pub enum Error {
     UnsupportedType(wasm::BadType),
     MalformedName(String),
     LocalsOverflow,
+    Unsupported(&'static str),
 }
 
 impl From<jvm::class_file::ConstantPoolOverflow> for Error {
@@ -1,19 +1,24 @@
 use super::{
-    BootstrapUtilities, Data, Element, Error, Function, Global, Memory, Settings, Table,
-    UtilityClass, UtilityMethod,
+    box_return_value, const_v128, extract_lane, invoke_var_handle, lane_half, lane_keep_mask,
+    lane_to_bits, read_locals, unbox_return_value, AtomicAccess, AtomicInstruction,
+    AtomicOperation, AtomicWidth, BootstrapUtilities, Data, Element, Error, FrameArray, Function,
+    Global, LaneShape, LocalsAllocation, Memory, MemoryAccess, MemoryBackend, MemorySource,
+    OffsetOperator, RmwOp, Settings, SimdOperation, SplitFunction, SsaBlockCall, SsaFunction,
+    SsaOperation, SsaTerminator, SsaValue, Table, Tag, UtilityClass, UtilityMethod,
 };
-use crate::jvm::class_graph::ClassId;
+use crate::jvm::class_graph::{AccessMode, ClassId, ConstantData, JavaClasses, MethodId};
 use crate::jvm::code::{
-    BranchCond, BranchInstruction, CodeBuilder, CodeBuilderExts, EqComparison, Instruction,
+    BranchCond, BranchInstruction, Code, CodeBuilder, CodeBuilderExts, EqComparison, Instruction,
     OrdComparison, SynLabel,
 };
 use crate::jvm::{BaseType, FieldType, MethodDescriptor, RefType, UnqualifiedName};
 use crate::runtime::WasmRuntime;
 use crate::util::{OffsetVec, Width};
 use crate::wasm::{
-    ref_type_from_general, ControlFrame, FunctionType, StackType, WasmModuleResourcesExt,
+    ref_type_from_general, v128_ref_type, ControlFrame, FunctionType, StackType,
+    WasmModuleResourcesExt,
 };
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::convert::TryFrom;
 use std::iter::FromIterator;
 use std::ops::Not;
@@ -21,11 +26,70 @@ use wasmparser::{
     BlockType, BrTable, FuncValidator, FunctionBody, MemArg, Operator, ValType, ValidatorResources,
 };
 
+/// Module-wide state which the translation of function bodies refers to
+#[derive(Copy, Clone)]
+pub struct ModuleContext<'a, 'b, 'g> {
+    /// Translation settings
+    pub settings: &'b Settings,
+
+    /// Main module class
+    pub class: ClassId<'g>,
+
+    pub runtime: &'b WasmRuntime<'g>,
+
+    /// Functions
+    pub functions: &'b [Function<'a, 'g>],
+
+    /// Tables
+    pub tables: &'b [Table<'a, 'g>],
+
+    /// Memories
+    pub memories: &'b [Memory<'a, 'g>],
+
+    /// Globals
+    pub globals: &'b [Global<'a, 'g>],
+
+    /// Datas
+    pub datas: &'b [Data<'a, 'g>],
+
+    /// Elements
+    pub elements: &'b [Element<'a, 'g>],
+
+    /// Tags
+    pub tags: &'b [Tag<'a, 'g>],
+}
+
+/// WASM function to translate, along with what was worked out about it ahead of translation
+#[derive(Copy, Clone)]
+pub struct FunctionInput<'a, 'b, 'g> {
+    /// WASM type of the function
+    pub function_typ: &'b FunctionType,
+
+    /// Whether to translate the function into its tail call variant
+    pub trampolined: bool,
+
+    /// Body of the function
+    pub body: FunctionBody<'a>,
+
+    /// Operators of the body (possibly optimized, see `optimize_operators`)
+    pub operators: &'b [OffsetOperator<'a>],
+
+    /// Types of the locals added for functions inlined into the body (see `inline_calls`)
+    pub inlined_locals: &'b [ValType],
+
+    /// How the function is split up into methods (see the `split` module)
+    pub split: &'b SplitFunction<'g>,
+}
+
 /// Context for translating a WASM function into a JVM one
-pub struct FunctionTranslator<'a, 'b, 'g> {
+pub struct FunctionTranslator<'a, 'b, 'r, 'g> {
     /// WASM type of the function being translated
     function_typ: &'b FunctionType,
 
+    /// Whether the function is being translated into its tail call variant (meaning return
+    /// values get boxed and `return_call`s return thunks)
+    trampolined: bool,
+
     /// Translation settings
     settings: &'b Settings,
 
@@ -61,11 +125,32 @@ pub struct FunctionTranslator<'a, 'b, 'g> {
     /// Elements
     wasm_elements: &'b [Element<'a, 'g>],
 
+    /// Tags
+    wasm_tags: &'b [Tag<'a, 'g>],
+
     /// Local variables
     jvm_locals: LocalsLayout<'g>,
 
+    /// Locals for the caught exception and the saved operand stack of `try` blocks, indexed by
+    /// how many `try` blocks they are nested in
+    ///
+    /// These are allocated up front (right after the WASM locals) so that the locals are the same
+    /// everywhere in the body, which is what lets exception handlers and labels share frames.
+    jvm_try_locals: Vec<(u16, u16)>,
+
+    /// Locals caching the bytes of each memory (see [`Memory::load_bytes`]), for memories
+    /// accessed by loads and stores in the function
+    ///
+    /// These get refreshed after anything which might replace the bytes of a resizable memory:
+    /// calls (which could grow the memory), `memory.grow`, and entering an exception handler.
+    jvm_memory_bytes: Vec<Option<u16>>,
+
+    /// Label of a block which just rethrows the exception on the stack, for `delegate` to the
+    /// caller (placed at the very end of the method, outside of all exception handlers)
+    jvm_delegate_to_caller: Option<SynLabel>,
+
     /// Validator for the WASM function
-    pub wasm_validator: &'b mut FuncValidator<ValidatorResources>,
+    pub wasm_validator: &'b mut FuncValidator<&'r ValidatorResources>,
 
     /// Previous height of the WASM stack
     wasm_prev_operand_stack_height: u32,
@@ -73,46 +158,88 @@ pub struct FunctionTranslator<'a, 'b, 'g> {
     /// WASM function being translated
     wasm_function: FunctionBody<'a>,
 
+    /// Operators of the function being translated (possibly optimized, see `optimize_operators`)
+    wasm_operators: &'b [OffsetOperator<'a>],
+
+    /// Types of the locals added for functions inlined into the function (see `inline_calls`)
+    wasm_inlined_locals: &'b [ValType],
+
     /// Stack of WASM structured control flow frames
     wasm_frames: Vec<ControlFrame<SynLabel>>,
 
     /// Count of WASM control frames which are unreachable
     wasm_unreachable_frame_count: usize,
+
+    /// How the function is split up into methods (see the `split` module)
+    wasm_split: &'b SplitFunction<'g>,
+
+    /// Offset of the last operator of the last region translated into a split method (operators
+    /// up to there have already been visited)
+    wasm_skip_until: Option<usize>,
+
+    /// Index of the region being translated, if this is translating a split method
+    wasm_split_region: Option<usize>,
+
+    /// Height of the WASM operand stack under the region being translated (the values under that
+    /// aren't on the JVM stack), if this is translating a split method
+    wasm_split_base_stack_height: u32,
+
+    /// Split methods which have been translated (by the index of their region), along with their
+    /// code
+    pub split_methods: Vec<(usize, Code<'g>)>,
 }
 
-impl<'a, 'b, 'g> FunctionTranslator<'a, 'b, 'g> {
+impl<'a, 'b, 'r, 'g> FunctionTranslator<'a, 'b, 'r, 'g> {
     pub fn new(
-        function_typ: &'b FunctionType,
-        settings: &'b Settings,
+        module: ModuleContext<'a, 'b, 'g>,
+        function: FunctionInput<'a, 'b, 'g>,
         utilities: &'b mut UtilityClass<'g>,
         bootstrap_utilities: &'b mut BootstrapUtilities<'g>,
         jvm_code: &'b mut CodeBuilder<'g>,
-        class: ClassId<'g>,
-        runtime: &'b WasmRuntime<'g>,
-        wasm_functions: &'b [Function<'a, 'g>],
-        wasm_tables: &'b [Table<'a, 'g>],
-        wasm_memories: &'b [Memory<'a, 'g>],
-        wasm_globals: &'b [Global<'a, 'g>],
-        wasm_datas: &'b [Data<'a, 'g>],
-        wasm_elements: &'b [Element<'a, 'g>],
-        wasm_function: FunctionBody<'a>,
-        wasm_validator: &'b mut FuncValidator<ValidatorResources>,
+        wasm_validator: &'b mut FuncValidator<&'r ValidatorResources>,
     ) -> Result<Self, Error> {
-        let jvm_locals = LocalsLayout::new(
+        let ModuleContext {
+            settings,
+            class,
+            runtime,
+            functions: wasm_functions,
+            tables: wasm_tables,
+            memories: wasm_memories,
+            globals: wasm_globals,
+            datas: wasm_datas,
+            elements: wasm_elements,
+            tags: wasm_tags,
+        } = module;
+        let FunctionInput {
+            function_typ,
+            trampolined,
+            body: wasm_function,
+            operators: wasm_operators,
+            inlined_locals: wasm_inlined_locals,
+            split: wasm_split,
+        } = function;
+        let mut jvm_locals = LocalsLayout::new(
             function_typ
-                .inputs
-                .iter()
-                .map(|wasm_ty| wasm_ty.field_type(&jvm_code.java.classes)),
+                .method_descriptor(&jvm_code.java.classes, &runtime.tuples)
+                .parameters
+                .into_iter(),
             RefType::Object(class),
         );
+        if !function_typ.packed_inputs().is_empty() {
+            jvm_locals.jvm_packed_inputs_idx = Some(function_typ.unpacked_inputs());
+        }
 
         Ok(FunctionTranslator {
             function_typ,
+            trampolined,
             settings,
             utilities,
             bootstrap_utilities,
             jvm_code,
             jvm_locals,
+            jvm_try_locals: vec![],
+            jvm_memory_bytes: vec![],
+            jvm_delegate_to_caller: None,
             class,
             runtime,
             wasm_functions,
@@ -121,11 +248,19 @@ impl<'a, 'b, 'g> FunctionTranslator<'a, 'b, 'g> {
             wasm_globals,
             wasm_datas,
             wasm_elements,
+            wasm_tags,
             wasm_validator,
             wasm_prev_operand_stack_height: 0,
             wasm_function,
+            wasm_operators,
+            wasm_inlined_locals,
             wasm_frames: vec![],
             wasm_unreachable_frame_count: 0,
+            wasm_split,
+            wasm_skip_until: None,
+            wasm_split_region: None,
+            wasm_split_base_stack_height: 0,
+            split_methods: vec![],
         })
     }
 
@@ -136,31 +271,687 @@ impl<'a, 'b, 'g> FunctionTranslator<'a, 'b, 'g> {
         Ok(())
     }
 
+    /// Translate a function from its SSA form (the operators are then only used by split methods)
+    ///
+    /// Every SSA value gets its own JVM local, and all of those are zero-initialized up front so
+    /// that every block starts with the same frame: all the locals set and an empty stack. This
+    /// means jumps between blocks are just stores of the arguments into the locals of the block
+    /// parameters, followed by a `goto`.
+    pub fn translate_ssa(&mut self, function: &SsaFunction) -> Result<(), Error> {
+        // Parameters of the entry block are the method arguments
+        let mut value_locals: HashMap<SsaValue, (u16, FieldType<ClassId<'g>>)> = HashMap::new();
+        for (local_idx, value) in function.blocks[0].params.iter().enumerate() {
+            value_locals.insert(*value, self.jvm_locals.lookup_local(local_idx as u32)?);
+        }
+        for value in function.defined_values() {
+            if let std::collections::hash_map::Entry::Vacant(entry) = value_locals.entry(value) {
+                let field_type = function
+                    .value_type(value)
+                    .field_type(&self.jvm_code.java.classes);
+                let off = self.jvm_locals.push_local(field_type)?;
+                self.jvm_code.zero_local(off, field_type)?;
+                entry.insert((off, field_type));
+            }
+        }
+        self.visit_memory_locals(0, usize::MAX)?;
+        let block_frame = self
+            .jvm_code
+            .current_frame()
+            .expect("no current frame")
+            .clone();
+        let block_labels: Vec<SynLabel> = function
+            .blocks
+            .iter()
+            .map(|_| self.jvm_code.fresh_label())
+            .collect();
+
+        for (block_idx, block) in function.blocks.iter().enumerate() {
+            if block_idx > 0 {
+                self.jvm_code
+                    .place_label_with_frame(block_labels[block_idx], &block_frame)?;
+            }
+
+            for instruction in &block.instructions {
+                for operand in &instruction.operands {
+                    let (off, field_type) = value_locals[operand];
+                    self.jvm_code.get_local(off, &field_type)?;
+                }
+                match &instruction.operation {
+                    SsaOperation::Zero(ty) => match ty {
+                        StackType::I32 => self.jvm_code.const_int(0)?,
+                        StackType::I64 => self.jvm_code.const_long(0)?,
+                        StackType::F32 => self.jvm_code.const_float(0.0)?,
+                        StackType::F64 => self.jvm_code.const_double(0.0)?,
+                        _ => return Err(Error::Unsupported("zero of a non-numeric type")),
+                    },
+                    SsaOperation::Operator(operator) => {
+                        self.translate_operator(operator.clone(), &mut None)?
+                    }
+                }
+                for result in instruction.results.iter().rev() {
+                    let (off, field_type) = value_locals[result];
+                    self.jvm_code.set_local(off, &field_type)?;
+                }
+            }
+
+            // Jumps to the next block can fall through
+            let next_block = block_idx + 1;
+            match &block.terminator {
+                SsaTerminator::Jump(call) => {
+                    self.translate_ssa_jump(function, call, &value_locals)?;
+                    if call.block.0 as usize != next_block {
+                        let label = block_labels[call.block.0 as usize];
+                        self.jvm_code
+                            .push_branch_instruction(BranchInstruction::Goto(label))?;
+                    }
+                }
+                SsaTerminator::Branch {
+                    condition,
+                    if_true,
+                    if_false,
+                } => {
+                    let (off, field_type) = value_locals[condition];
+                    self.jvm_code.get_local(off, &field_type)?;
+
+                    // Branch straight to a block without arguments, but go through an extra
+                    // block when both jumps have arguments
+                    let (cond, direct, other) = if if_true.arguments.is_empty() {
+                        (OrdComparison::NE, if_true, if_false)
+                    } else {
+                        (OrdComparison::EQ, if_false, if_true)
+                    };
+                    let direct_label = if direct.arguments.is_empty() {
+                        block_labels[direct.block.0 as usize]
+                    } else {
+                        self.jvm_code.fresh_label()
+                    };
+                    self.jvm_code.push_branch_instruction(
+                        BranchCond::If(cond).into_instruction(direct_label, ()),
+                    )?;
+                    self.translate_ssa_jump(function, other, &value_locals)?;
+                    if other.block.0 as usize != next_block || !direct.arguments.is_empty() {
+                        let label = block_labels[other.block.0 as usize];
+                        self.jvm_code
+                            .push_branch_instruction(BranchInstruction::Goto(label))?;
+                    }
+                    if !direct.arguments.is_empty() {
+                        self.jvm_code.place_label(direct_label)?;
+                        self.translate_ssa_jump(function, direct, &value_locals)?;
+                        let label = block_labels[direct.block.0 as usize];
+                        self.jvm_code
+                            .push_branch_instruction(BranchInstruction::Goto(label))?;
+                    }
+                }
+                SsaTerminator::Switch {
+                    index,
+                    targets,
+                    default,
+                } => {
+                    let (off, field_type) = value_locals[index];
+                    self.jvm_code.get_local(off, &field_type)?;
+
+                    // Jumps with arguments go through extra blocks (one per distinct jump)
+                    let mut pending_jumps: Vec<(SynLabel, &SsaBlockCall)> = vec![];
+                    let mut switch_targets = vec![];
+                    for call in targets.iter().chain(std::iter::once(default)) {
+                        let label = if call.arguments.is_empty() {
+                            block_labels[call.block.0 as usize]
+                        } else if let Some((label, _)) =
+                            pending_jumps.iter().find(|(_, pending)| *pending == call)
+                        {
+                            *label
+                        } else {
+                            let label = self.jvm_code.fresh_label();
+                            pending_jumps.push((label, call));
+                            label
+                        };
+                        switch_targets.push(label);
+                    }
+
+                    let default = switch_targets.pop().expect("no default target found");
+                    if switch_targets.is_empty() {
+                        self.jvm_code.push_instruction(Instruction::Pop)?;
+                        self.jvm_code
+                            .push_branch_instruction(BranchInstruction::Goto(default))?;
+                    } else {
+                        self.jvm_code
+                            .push_branch_instruction(BranchInstruction::TableSwitch {
+                                padding: 0,
+                                default,
+                                low: 0,
+                                targets: switch_targets,
+                            })?;
+                    }
+
+                    for (label, call) in pending_jumps {
+                        self.jvm_code.place_label(label)?;
+                        self.translate_ssa_jump(function, call, &value_locals)?;
+                        let label = block_labels[call.block.0 as usize];
+                        self.jvm_code
+                            .push_branch_instruction(BranchInstruction::Goto(label))?;
+                    }
+                }
+                SsaTerminator::Return(values) => {
+                    for value in values {
+                        let (off, field_type) = value_locals[value];
+                        self.jvm_code.get_local(off, &field_type)?;
+                    }
+                    self.visit_return()?;
+                }
+                SsaTerminator::Unreachable => {
+                    self.utilities
+                        .invoke_utility(UtilityMethod::Unreachable, self.jvm_code)?;
+                    self.jvm_code
+                        .push_branch_instruction(BranchInstruction::AThrow)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Store the arguments of a jump into the locals of the parameters of the block jumped to
+    /// (but don't jump yet)
+    ///
+    /// The arguments all get loaded before any parameter is stored, since arguments can also be
+    /// parameters of the same block (eg. in a loop swapping two locals).
+    fn translate_ssa_jump(
+        &mut self,
+        function: &SsaFunction,
+        call: &SsaBlockCall,
+        value_locals: &HashMap<SsaValue, (u16, FieldType<ClassId<'g>>)>,
+    ) -> Result<(), Error> {
+        let params = &function.blocks[call.block.0 as usize].params;
+        let copies: Vec<(SsaValue, SsaValue)> = params
+            .iter()
+            .zip(&call.arguments)
+            .filter(|(param, argument)| param != argument)
+            .map(|(param, argument)| (*param, *argument))
+            .collect();
+        for (_, argument) in &copies {
+            let (off, field_type) = value_locals[argument];
+            self.jvm_code.get_local(off, &field_type)?;
+        }
+        for (param, _) in copies.iter().rev() {
+            let (off, field_type) = value_locals[param];
+            self.jvm_code.set_local(off, &field_type)?;
+        }
+        Ok(())
+    }
+
     /// Visit all locals
     ///
     /// This also handles zero-initializing the locals (as is required by WASM)
     fn visit_locals(&mut self) -> Result<(), Error> {
-        let mut reader = self.wasm_function.get_binary_reader();
-        self.wasm_validator.read_locals(&mut reader)?;
+        read_locals(
+            &self.wasm_function,
+            self.wasm_inlined_locals,
+            self.wasm_validator,
+        )?;
+
+        // Inputs which were packed into an array get unpacked into their own locals
+        let function_typ = self.function_typ;
+        let packed_inputs = function_typ.packed_inputs();
+        if !packed_inputs.is_empty() {
+            let mut input_locals = vec![];
+            for input in packed_inputs {
+                let field_type = input.field_type(&self.jvm_code.java.classes);
+                let off = self.jvm_locals.push_local(field_type)?;
+                self.jvm_code.zero_local(off, field_type)?;
+                input_locals.push((off, field_type));
+            }
+            let (off, field_type) = self.jvm_locals.lookup_packed_inputs()?;
+            self.jvm_code.get_local(off, &field_type)?;
+            self.unpack_stack_from_array(packed_inputs)?;
+            for (off, field_type) in input_locals.iter().rev() {
+                self.jvm_code.set_local(*off, field_type)?;
+            }
+        }
+
+        self.allocate_wasm_locals()?;
+        self.visit_memory_locals(0, usize::MAX)?;
+        self.visit_try_locals(0, usize::MAX)
+    }
 
-        let first_local_idx = self.function_typ.inputs.len() as u32;
-        for local_idx in first_local_idx..self.wasm_validator.len_locals() {
+    /// Allocate the JVM locals of the WASM locals (other than the parameters)
+    ///
+    /// WASM locals which are live at different times share JVM locals (see [`LocalsAllocation`]).
+    /// The JVM locals are all zero initialized, as are the spilled locals. Locals accessed in
+    /// split methods go in the frame instead (see `visit_split_frame`).
+    fn allocate_wasm_locals(&mut self) -> Result<(), Error> {
+        // Split methods take the frame instead of the parameters
+        let parameter_count = match self.wasm_split_region {
+            None => self.function_typ.inputs.len(),
+            Some(_) => 0,
+        };
+        let mut local_types = self.function_typ.inputs.clone();
+        for local_idx in local_types.len() as u32..self.wasm_validator.len_locals() {
             let local_type = self.wasm_validator.get_local_type(local_idx).unwrap();
+            local_types.push(StackType::from_general(local_type)?);
+        }
+        let framed_locals: BTreeSet<u32> = self
+            .wasm_split
+            .framed_locals
+            .iter()
+            .enumerate()
+            .filter(|(local_idx, framed)| framed.is_some() && *local_idx >= parameter_count)
+            .map(|(local_idx, _)| local_idx as u32)
+            .collect();
+        let own_operators;
+        let operators = if self.wasm_split.regions.is_empty() {
+            self.wasm_operators
+        } else {
+            own_operators = self.own_operators();
+            &own_operators
+        };
+        let allocation = LocalsAllocation::new(
+            operators,
+            &local_types,
+            parameter_count,
+            &framed_locals,
+            MAX_WASM_LOCAL_SLOTS,
+        );
+
+        let mut wasm_locals: Vec<Option<WasmLocal>> = vec![None; local_types.len()];
+        let mut shared_slots = vec![];
+        for (shared_idx, shared) in allocation.shared.iter().enumerate() {
+            let field_type = shared.local_type.field_type(&self.jvm_code.java.classes);
+            let idx = if shared_idx < parameter_count {
+                match self.jvm_locals.lookup_wasm_local(shared_idx as u32) {
+                    WasmLocal::Jvm(idx) => idx,
+                    _ => unreachable!("parameters are always in JVM locals"),
+                }
+            } else {
+                let idx = self.jvm_locals.jvm_locals.len();
+                let off = self.jvm_locals.push_local(field_type)?;
+                if let StackType::V128 = shared.local_type {
+                    const_v128(self.jvm_code, 0)?;
+                    self.jvm_code.set_local(off, &field_type)?;
+                } else {
+                    self.jvm_code.zero_local(off, field_type)?;
+                }
+                idx
+            };
+            for local_idx in &shared.locals {
+                wasm_locals[*local_idx as usize] = Some(WasmLocal::Jvm(idx));
+            }
+            if let FieldType::Base(_) = field_type {
+                let (off, _) = self.jvm_locals.jvm_locals.get_index(idx).unwrap();
+                shared_slots.push((off.0 as u16, field_type, shared.live_until));
+            }
+        }
+        shared_slots.sort_by_key(|(_, _, live_until)| *live_until);
+
+        if !allocation.spilled.is_empty() {
+            let object_class = self.jvm_code.java.classes.lang.object;
+            let spilled_type = FieldType::array(FieldType::object(object_class));
+            let idx = self.jvm_locals.jvm_locals.len();
+            let off = self.jvm_locals.push_local(spilled_type)?;
+            self.jvm_locals.jvm_spilled_idx = Some(idx);
+            self.jvm_code.const_int(allocation.spilled.len() as i32)?;
+            self.jvm_code.new_ref_array(RefType::Object(object_class))?;
+            for (spilled_idx, local_idx) in allocation.spilled.iter().enumerate() {
+                wasm_locals[*local_idx as usize] = Some(WasmLocal::Spilled(spilled_idx));
+
+                // References are already `null`, but everything else needs a boxed zero
+                let local_type = local_types[*local_idx as usize];
+                if let StackType::FuncRef | StackType::ExternRef = local_type {
+                    continue;
+                }
+                self.jvm_code.dup()?;
+                self.jvm_code.const_int(spilled_idx as i32)?;
+                match local_type {
+                    StackType::I32 => self.jvm_code.const_int(0)?,
+                    StackType::I64 => self.jvm_code.const_long(0)?,
+                    StackType::F32 => self.jvm_code.const_float(0.0)?,
+                    StackType::F64 => self.jvm_code.const_double(0.0)?,
+                    _ => const_v128(self.jvm_code, 0)?,
+                }
+                box_return_value(self.jvm_code, &[local_type])?;
+                self.jvm_code.push_instruction(Instruction::AAStore)?;
+            }
+            self.jvm_code.set_local(off, &spilled_type)?;
+        }
+
+        if !self.wasm_split.regions.is_empty() {
+            self.visit_split_frame(&local_types, &mut wasm_locals)?;
+        }
+        self.jvm_locals.jvm_wasm_locals = Some(wasm_locals);
+        self.jvm_locals.jvm_shared_slots = shared_slots;
+        Ok(())
+    }
+
+    /// Operators translated into this method, leaving out the regions split out of it
+    fn own_operators(&self) -> Vec<OffsetOperator<'a>> {
+        let regions = &self.wasm_split.regions;
+        let (start, end) = match self.wasm_split_region {
+            Some(region_idx) => (regions[region_idx].start, regions[region_idx].end),
+            None => (0, usize::MAX),
+        };
+        let mut children = self
+            .wasm_split
+            .children(self.wasm_split_region)
+            .into_iter()
+            .map(|region_idx| (regions[region_idx].start, regions[region_idx].end))
+            .peekable();
+        let mut operators = vec![];
+        for (operator, offset) in self.wasm_operators {
+            while matches!(children.peek(), Some((_, child_end)) if child_end < offset) {
+                children.next();
+            }
+            let in_child =
+                matches!(children.peek(), Some((child_start, _)) if child_start <= offset);
+            if start <= *offset && *offset <= end && !in_child {
+                operators.push((operator.clone(), *offset));
+            }
+        }
+        operators
+    }
+
+    /// Set up the frame shared by the function and its split methods (see the `split` module)
+    ///
+    /// The function allocates the frame arrays, and moves the parameters which are accessed in
+    /// split methods into them. Split methods get the arrays as arguments.
+    fn visit_split_frame(
+        &mut self,
+        local_types: &[StackType],
+        wasm_locals: &mut [Option<WasmLocal>],
+    ) -> Result<(), Error> {
+        let split = self.wasm_split;
+        if self.wasm_split_region.is_none() {
+            let frame_idx = self.jvm_locals.jvm_locals.len();
+            for (array, length) in FrameArray::ALL.into_iter().zip(split.frame_lengths) {
+                let field_type = array.field_type(&self.jvm_code.java.classes);
+                let off = self.jvm_locals.push_local(field_type)?;
+                self.jvm_code.const_int(length as i32)?;
+                match array {
+                    FrameArray::Int => self
+                        .jvm_code
+                        .push_instruction(Instruction::NewArray(BaseType::Int))?,
+                    FrameArray::Long => self
+                        .jvm_code
+                        .push_instruction(Instruction::NewArray(BaseType::Long))?,
+                    FrameArray::Float => self
+                        .jvm_code
+                        .push_instruction(Instruction::NewArray(BaseType::Float))?,
+                    FrameArray::Double => self
+                        .jvm_code
+                        .push_instruction(Instruction::NewArray(BaseType::Double))?,
+                    FrameArray::Object => {
+                        let object_class = self.jvm_code.java.classes.lang.object;
+                        self.jvm_code.new_ref_array(RefType::Object(object_class))?
+                    }
+                }
+                self.jvm_code.set_local(off, &field_type)?;
+            }
+            self.jvm_locals.jvm_frame_idx = Some(frame_idx);
+        }
+
+        for (local_idx, framed) in split.framed_locals.iter().enumerate() {
+            let (array, idx) = match framed {
+                Some(framed) => *framed,
+                None => continue,
+            };
+            let local_type = local_types[local_idx];
+            if self.wasm_split_region.is_none() {
+                if local_idx < self.function_typ.inputs.len() {
+                    self.get_wasm_local(local_idx as u32)?;
+                    self.set_framed_local(array, idx, local_type)?;
+                } else if local_type == StackType::V128 {
+                    const_v128(self.jvm_code, 0)?;
+                    self.set_framed_local(array, idx, local_type)?;
+                }
+            }
+            wasm_locals[local_idx] = Some(WasmLocal::Framed(array, idx));
+        }
+        Ok(())
+    }
 
-            // WASM locals are zero initialized
-            let local_type = StackType::from_general(local_type)?;
-            let field_type = local_type.field_type(&self.jvm_code.java.classes);
-            let idx = self.jvm_locals.push_local(field_type)?;
-            self.jvm_code.zero_local(idx, field_type)?;
+    /// Push the value of a WASM local onto the stack
+    fn get_wasm_local(&mut self, local_idx: u32) -> Result<(), Error> {
+        match self.jvm_locals.lookup_wasm_local(local_idx) {
+            WasmLocal::Jvm(_) => {
+                let (off, field_type) = self.jvm_locals.lookup_local(local_idx)?;
+                self.jvm_code.get_local(off, &field_type)?;
+            }
+            WasmLocal::Spilled(spilled_idx) => {
+                let local_type = self.wasm_local_type(local_idx)?;
+                let (off, field_type) = self.jvm_locals.lookup_spilled()?;
+                self.jvm_code.get_local(off, &field_type)?;
+                self.jvm_code.const_int(spilled_idx as i32)?;
+                self.jvm_code.push_instruction(Instruction::AALoad)?;
+                unbox_return_value(self.jvm_code, &self.runtime.tuples, &[local_type])?;
+            }
+            WasmLocal::Framed(array, idx) => {
+                let local_type = self.wasm_local_type(local_idx)?;
+                self.get_frame_array(array)?;
+                self.jvm_code.const_int(idx as i32)?;
+                match array {
+                    FrameArray::Int => self.jvm_code.push_instruction(Instruction::IALoad)?,
+                    FrameArray::Long => self.jvm_code.push_instruction(Instruction::LALoad)?,
+                    FrameArray::Float => self.jvm_code.push_instruction(Instruction::FALoad)?,
+                    FrameArray::Double => self.jvm_code.push_instruction(Instruction::DALoad)?,
+                    FrameArray::Object => {
+                        self.jvm_code.push_instruction(Instruction::AALoad)?;
+                        unbox_return_value(self.jvm_code, &self.runtime.tuples, &[local_type])?;
+                    }
+                }
+            }
         }
+        Ok(())
+    }
 
+    /// Pop the value on top of the stack into a WASM local
+    fn set_wasm_local(&mut self, local_idx: u32) -> Result<(), Error> {
+        match self.jvm_locals.lookup_wasm_local(local_idx) {
+            WasmLocal::Jvm(_) => {
+                let (off, field_type) = self.jvm_locals.lookup_local(local_idx)?;
+                self.jvm_code.set_local(off, &field_type)?;
+            }
+            WasmLocal::Spilled(spilled_idx) => {
+                let local_type = self.wasm_local_type(local_idx)?;
+                let (off, field_type) = self.jvm_locals.lookup_spilled()?;
+                box_return_value(self.jvm_code, &[local_type])?;
+                self.jvm_code.get_local(off, &field_type)?;
+                self.jvm_code.push_instruction(Instruction::Swap)?;
+                self.jvm_code.const_int(spilled_idx as i32)?;
+                self.jvm_code.push_instruction(Instruction::Swap)?;
+                self.jvm_code.push_instruction(Instruction::AAStore)?;
+            }
+            WasmLocal::Framed(array, idx) => {
+                let local_type = self.wasm_local_type(local_idx)?;
+                self.set_framed_local(array, idx, local_type)?;
+            }
+        }
         Ok(())
     }
 
+    /// Pop the value on top of the stack into an element of the frame
+    fn set_framed_local(
+        &mut self,
+        array: FrameArray,
+        idx: u32,
+        local_type: StackType,
+    ) -> Result<(), Error> {
+        // Move the array and index under the value
+        self.get_frame_array(array)?;
+        self.jvm_code.const_int(idx as i32)?;
+        if local_type.width() == 2 {
+            self.jvm_code.push_instruction(Instruction::Dup2X2)?;
+        } else {
+            self.jvm_code.push_instruction(Instruction::Dup2X1)?;
+        }
+        self.jvm_code.push_instruction(Instruction::Pop2)?;
+        match array {
+            FrameArray::Int => self.jvm_code.push_instruction(Instruction::IAStore)?,
+            FrameArray::Long => self.jvm_code.push_instruction(Instruction::LAStore)?,
+            FrameArray::Float => self.jvm_code.push_instruction(Instruction::FAStore)?,
+            FrameArray::Double => self.jvm_code.push_instruction(Instruction::DAStore)?,
+            FrameArray::Object => self.jvm_code.push_instruction(Instruction::AAStore)?,
+        }
+        Ok(())
+    }
+
+    /// Push one of the arrays of the frame shared with split methods
+    fn get_frame_array(&mut self, array: FrameArray) -> Result<(), Error> {
+        let (off, field_type) = self.jvm_locals.lookup_frame_array(array)?;
+        self.jvm_code.get_local(off, &field_type)?;
+        Ok(())
+    }
+
+    /// Push a temporary local (see [`LocalsLayout::push_temp_local`])
+    fn push_temp_local(&mut self, field_type: FieldType<ClassId<'g>>) -> Result<u16, Error> {
+        self.jvm_locals.push_temp_local(field_type)
+    }
+
+    /// Pop the last temporary local, killing it unless it reused the slot of a WASM local
+    fn pop_temp_local(&mut self) -> Result<(), Error> {
+        let (offset, field_type, shared) = self.jvm_locals.pop_temp_local()?;
+        if !shared {
+            self.jvm_code.kill_top_local(offset, Some(field_type))?;
+        }
+        Ok(())
+    }
+
+    /// Allocate (and fill in) the locals caching the bytes of memories accessed by loads and
+    /// stores in the operators whose offsets are in `start..=end`
+    ///
+    /// Shared memories are never cached since other threads can grow them at any point, and
+    /// 64-bit memories aren't a single set of bytes.
+    fn visit_memory_locals(&mut self, start: usize, end: usize) -> Result<(), Error> {
+        let mut accessed = vec![false; self.wasm_memories.len()];
+        for (operator, offset) in self.wasm_operators {
+            if *offset < start || *offset > end {
+                continue;
+            }
+            if let Some(memarg) = load_store_memarg(operator) {
+                accessed[memarg.memory as usize] = true;
+            }
+        }
+
+        for (memory_idx, accessed) in accessed.into_iter().enumerate() {
+            let memory = &self.wasm_memories[memory_idx];
+            if !accessed || memory.is_memory64() || memory.memory_type.shared {
+                self.jvm_memory_bytes.push(None);
+                continue;
+            }
+            let bytes_type = memory.backend.bytes_type(&self.jvm_code.java.classes);
+            let off = self.jvm_locals.push_local(bytes_type)?;
+            self.jvm_memory_bytes.push(Some(off));
+            self.refresh_memory_local(memory_idx as u32)?;
+        }
+
+        Ok(())
+    }
+
+    /// Reload the bytes of a memory into its caching local (if it has one)
+    fn refresh_memory_local(&mut self, memory_idx: u32) -> Result<(), Error> {
+        let off = match self.jvm_memory_bytes.get(memory_idx as usize) {
+            Some(Some(off)) => *off,
+            _ => return Ok(()),
+        };
+        let memory = &self.wasm_memories[memory_idx as usize];
+        let (this_off, this_type) = self.jvm_locals.lookup_this()?;
+        self.jvm_code.get_local(this_off, &this_type)?;
+        memory.load_bytes(self.runtime, self.jvm_code)?;
+        self.jvm_code
+            .set_local(off, &memory.backend.bytes_type(&self.jvm_code.java.classes))?;
+        Ok(())
+    }
+
+    /// Reload the cached bytes of every resizable memory, since they might have been replaced
+    fn refresh_memory_locals(&mut self) -> Result<(), Error> {
+        for memory_idx in 0..self.jvm_memory_bytes.len() {
+            if self.wasm_memories[memory_idx].is_resizable() {
+                self.refresh_memory_local(memory_idx as u32)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Where a load or store to the memory should get the memory from
+    fn memory_source(&self, memory_idx: u32) -> Result<MemorySource, Error> {
+        match self.jvm_memory_bytes.get(memory_idx as usize) {
+            Some(Some(off)) => Ok(MemorySource::CachedBytes(*off)),
+            _ => Ok(MemorySource::Module(self.jvm_locals.lookup_this()?.0)),
+        }
+    }
+
+    /// Allocate (and zero-initialize) the locals used by `try` blocks in the operators whose
+    /// offsets are in `start..=end`
+    fn visit_try_locals(&mut self, start: usize, end: usize) -> Result<(), Error> {
+        let exception_type = FieldType::object(self.runtime.classes.wasm_exception);
+        let saved_stack_type =
+            FieldType::array(FieldType::object(self.jvm_code.java.classes.lang.object));
+        for _ in 0..self.max_try_depth(start, end)? {
+            let exception_local = self.jvm_locals.push_local(exception_type)?;
+            self.jvm_code.zero_local(exception_local, exception_type)?;
+            let saved_stack_local = self.jvm_locals.push_local(saved_stack_type)?;
+            self.jvm_code
+                .zero_local(saved_stack_local, saved_stack_type)?;
+            self.jvm_try_locals
+                .push((exception_local, saved_stack_local));
+        }
+
+        Ok(())
+    }
+
+    /// Compute the maximum number of `try` blocks in which any operator whose offset is in
+    /// `start..=end` is nested (only counting `try` blocks also in that range)
+    fn max_try_depth(&self, start: usize, end: usize) -> Result<usize, Error> {
+        let mut frame_is_try: Vec<bool> = vec![];
+        let mut try_depth = 0;
+        let mut max_try_depth = 0;
+        for (operator, offset) in self.wasm_operators {
+            let offset = *offset;
+            if offset < start || offset > end {
+                continue;
+            }
+            match operator {
+                Operator::Block { .. } | Operator::Loop { .. } | Operator::If { .. } => {
+                    frame_is_try.push(false)
+                }
+                Operator::Try { .. } => {
+                    frame_is_try.push(true);
+                    try_depth += 1;
+                    max_try_depth = max_try_depth.max(try_depth);
+                }
+                Operator::End | Operator::Delegate { .. } => {
+                    if let Some(true) = frame_is_try.pop() {
+                        try_depth -= 1;
+                    }
+                }
+                _ => (),
+            }
+        }
+        Ok(max_try_depth)
+    }
+
     /// Visit all operators
     fn visit_operators(&mut self) -> Result<(), Error> {
-        let op_reader = self.wasm_function.get_operators_reader()?;
-        let mut op_iter = op_reader.into_iter_with_offsets();
+        let last_offset = self.visit_operator_range(0, usize::MAX)?;
+
+        // If control flow falls through to the end, insert an implicit return
+        if self.jvm_code.current_frame().is_some() {
+            self.visit_return()?;
+        }
+
+        // Exceptions delegated to the caller get rethrown outside of all exception handlers
+        if let Some(delegate_to_caller) = self.jvm_delegate_to_caller {
+            self.jvm_code.place_label(delegate_to_caller)?;
+            self.jvm_code
+                .push_branch_instruction(BranchInstruction::AThrow)?;
+        }
+
+        self.wasm_validator.finish(last_offset + 1)?;
+        Ok(())
+    }
+
+    /// Visit the operators whose offsets are in `start..=end`, returning the offset of the last
+    /// operator read
+    fn visit_operator_range(&mut self, start: usize, end: usize) -> Result<usize, Error> {
+        let operators: &'b [OffsetOperator<'a>] = self.wasm_operators;
+        let mut op_iter = operators.iter().cloned();
         let mut last_offset = 0;
 
         /* When we call `visit_operator`, we need to pass in an operator which we know will get
@@ -171,32 +962,45 @@ impl<'a, 'b, 'g> FunctionTranslator<'a, 'b, 'g> {
         loop {
             let this_operator = if let Some(operator) = next_operator.take() {
                 operator
-            } else if let Some(op_offset) = op_iter.next() {
-                let operator_offset = op_offset?;
+            } else if let Some(operator_offset) = op_iter.next() {
                 last_offset = operator_offset.1;
                 operator_offset
             } else {
                 break;
             };
-            next_operator = match op_iter.next() {
-                None => None,
-                Some(Ok(next_op)) => {
-                    last_offset = next_op.1;
-                    Some(next_op)
+
+            // Skip operators before the range or already visited in a split method
+            let this_offset = this_operator.1;
+            if this_offset < start
+                || self
+                    .wasm_skip_until
+                    .map_or(false, |skip| this_offset <= skip)
+            {
+                continue;
+            } else if this_offset > end {
+                break;
+            }
+
+            next_operator = op_iter.next();
+            if let Some((_, next_offset)) = &next_operator {
+                last_offset = *next_offset;
+            }
+
+            // Operators past the range or in a split region can't be fused into this one
+            let lookahead = match &next_operator {
+                Some((_, next_offset))
+                    if *next_offset > end || self.split_region_at(*next_offset).is_some() =>
+                {
+                    &mut None
                 }
-                Some(Err(err)) => return Err(Error::WasmParser(err)),
+                _ => &mut next_operator,
             };
 
-            self.visit_operator(this_operator, &mut next_operator)?;
-        }
-
-        // If control flow falls through to the end, insert an implicit return
-        if self.jvm_code.current_frame().is_some() {
-            self.visit_return()?;
+            self.jvm_locals.wasm_offset = this_offset;
+            self.visit_operator(this_operator, lookahead)?;
         }
 
-        self.wasm_validator.finish(last_offset + 1)?;
-        Ok(())
+        Ok(last_offset)
     }
 
     /// Visit and interpret an operator
@@ -210,28 +1014,39 @@ impl<'a, 'b, 'g> FunctionTranslator<'a, 'b, 'g> {
         operator_offset: (Operator, usize),
         next_operator_offset: &mut Option<(Operator, usize)>,
     ) -> Result<(), Error> {
-        use crate::jvm::code::CompareMode::*;
-        use crate::jvm::code::Instruction::*;
-        use crate::jvm::code::ShiftType::*;
-
         let (operator, offset) = operator_offset;
-        let next_op = next_operator_offset;
+
+        // Regions split out of the method get translated (and validated) in one go
+        if self.jvm_code.current_frame().is_some() {
+            if let Some(region_idx) = self.split_region_at(offset) {
+                return self.visit_split_region(region_idx);
+            }
+        }
+
         self.wasm_validator.op(offset, &operator)?;
 
         // Detect if the current frame is unreachable and handle things differently
         if self.jvm_code.current_frame().is_none() {
             match operator {
                 // Increment the unreachable frame count and skip the operator
-                Operator::Block { .. } | Operator::Loop { .. } | Operator::If { .. } => {
+                Operator::Block { .. }
+                | Operator::Loop { .. }
+                | Operator::If { .. }
+                | Operator::Try { .. } => {
                     self.wasm_unreachable_frame_count += 1;
                     return Ok(());
                 }
 
                 // Process the operator as normal (don't do an early return)
-                Operator::End | Operator::Else if 0 == self.wasm_unreachable_frame_count => (),
+                Operator::End
+                | Operator::Else
+                | Operator::Catch { .. }
+                | Operator::CatchAll
+                | Operator::Delegate { .. }
+                    if 0 == self.wasm_unreachable_frame_count => {}
 
                 // Decrement the unreachable frame count and skip the operator
-                Operator::End => {
+                Operator::End | Operator::Delegate { .. } => {
                     self.wasm_unreachable_frame_count -= 1;
                     return Ok(());
                 }
@@ -241,6 +1056,24 @@ impl<'a, 'b, 'g> FunctionTranslator<'a, 'b, 'g> {
             }
         }
 
+        self.translate_operator(operator, next_operator_offset)?;
+        self.wasm_prev_operand_stack_height = self.wasm_validator.operand_stack_height();
+        Ok(())
+    }
+
+    /// Translate an operator which has already been validated and is reachable
+    ///
+    /// Outside of control instructions, this only depends on the operands being on the stack (so
+    /// it is also used to lower the instructions of the SSA form, see `translate_ssa`).
+    fn translate_operator(
+        &mut self,
+        operator: Operator,
+        next_op: &mut Option<(Operator, usize)>,
+    ) -> Result<(), Error> {
+        use crate::jvm::code::CompareMode::*;
+        use crate::jvm::code::Instruction::*;
+        use crate::jvm::code::ShiftType::*;
+
         match operator {
             // Control Instructions
             Operator::Unreachable => {
@@ -256,6 +1089,12 @@ impl<'a, 'b, 'g> FunctionTranslator<'a, 'b, 'g> {
                 self.visit_if(blockty, BranchCond::If(OrdComparison::NE))?
             }
             Operator::Else => self.visit_else()?,
+            Operator::Try { blockty } => self.visit_try(blockty)?,
+            Operator::Catch { tag_index } => self.visit_catch(Some(tag_index))?,
+            Operator::CatchAll => self.visit_catch(None)?,
+            Operator::Throw { tag_index } => self.visit_throw(tag_index)?,
+            Operator::Rethrow { relative_depth } => self.visit_rethrow(relative_depth)?,
+            Operator::Delegate { relative_depth } => self.visit_delegate(relative_depth)?,
             Operator::End => self.visit_end()?,
             Operator::Br { relative_depth } => self.visit_branch(relative_depth)?,
             Operator::BrIf { relative_depth } => {
@@ -269,6 +1108,11 @@ impl<'a, 'b, 'g> FunctionTranslator<'a, 'b, 'g> {
                 table_index,
                 table_byte: _,
             } => self.visit_call_indirect(BlockType::FuncType(type_index), table_index)?,
+            Operator::ReturnCall { function_index } => self.visit_return_call(function_index)?,
+            Operator::ReturnCallIndirect {
+                type_index,
+                table_index,
+            } => self.visit_return_call_indirect(BlockType::FuncType(type_index), table_index)?,
 
             // Parametric Instructions
             Operator::Drop => self.jvm_code.pop()?,
@@ -278,18 +1122,11 @@ impl<'a, 'b, 'g> FunctionTranslator<'a, 'b, 'g> {
             }
 
             // Variable Instructions
-            Operator::LocalGet { local_index } => {
-                let (off, field_type) = self.jvm_locals.lookup_local(local_index)?;
-                self.jvm_code.get_local(off, &field_type)?;
-            }
-            Operator::LocalSet { local_index } => {
-                let (off, field_type) = self.jvm_locals.lookup_local(local_index)?;
-                self.jvm_code.set_local(off, &field_type)?;
-            }
+            Operator::LocalGet { local_index } => self.get_wasm_local(local_index)?,
+            Operator::LocalSet { local_index } => self.set_wasm_local(local_index)?,
             Operator::LocalTee { local_index } => {
-                let (off, field_type) = self.jvm_locals.lookup_local(local_index)?;
                 self.jvm_code.dup()?;
-                self.jvm_code.set_local(off, &field_type)?;
+                self.set_wasm_local(local_index)?;
             }
             Operator::GlobalGet { global_index } => self.visit_global_get(global_index)?,
             Operator::GlobalSet { global_index } => self.visit_global_set(global_index)?,
@@ -858,35 +1695,151 @@ impl<'a, 'b, 'g> FunctionTranslator<'a, 'b, 'g> {
                 self.visit_cond(BranchCond::IfNull(EqComparison::EQ), next_op)?
             }
             Operator::RefFunc { function_index } => {
-                let function = &self.wasm_functions[function_index as usize];
                 let this_off = self.jvm_locals.lookup_this()?.0;
+                self.wasm_functions[function_index as usize].load_reference(
+                    self.runtime,
+                    self.jvm_code,
+                    this_off,
+                )?;
+            }
 
-                self.jvm_code.const_methodhandle(function.method)?;
-                self.jvm_code
-                    .const_int(function.func_type.inputs.len() as i32)?;
-                self.jvm_code.const_int(1)?;
-                self.jvm_code
-                    .new_ref_array(RefType::Object(self.jvm_code.java.classes.lang.object))?;
-                self.jvm_code.dup()?;
-                self.jvm_code.const_int(0)?;
-                self.jvm_code
-                    .push_instruction(Instruction::ALoad(this_off))?;
-                self.jvm_code.push_instruction(Instruction::AAStore)?;
-                self.jvm_code.invoke(
-                    self.jvm_code
-                        .java
-                        .members
-                        .lang
-                        .invoke
-                        .method_handles
-                        .insert_arguments,
+            // Vector Instructions
+            Operator::V128Load { memarg } => self.visit_v128_load(memarg)?,
+            Operator::V128Load8x8S { memarg } => {
+                self.visit_v128_load_extend(memarg, LaneShape::I16x8, true)?
+            }
+            Operator::V128Load8x8U { memarg } => {
+                self.visit_v128_load_extend(memarg, LaneShape::I16x8, false)?
+            }
+            Operator::V128Load16x4S { memarg } => {
+                self.visit_v128_load_extend(memarg, LaneShape::I32x4, true)?
+            }
+            Operator::V128Load16x4U { memarg } => {
+                self.visit_v128_load_extend(memarg, LaneShape::I32x4, false)?
+            }
+            Operator::V128Load32x2S { memarg } => {
+                self.visit_v128_load_extend(memarg, LaneShape::I64x2, true)?
+            }
+            Operator::V128Load32x2U { memarg } => {
+                self.visit_v128_load_extend(memarg, LaneShape::I64x2, false)?
+            }
+            Operator::V128Load8Splat { memarg } => {
+                self.visit_v128_load_splat(memarg, LaneShape::I8x16)?
+            }
+            Operator::V128Load16Splat { memarg } => {
+                self.visit_v128_load_splat(memarg, LaneShape::I16x8)?
+            }
+            Operator::V128Load32Splat { memarg } => {
+                self.visit_v128_load_splat(memarg, LaneShape::I32x4)?
+            }
+            Operator::V128Load64Splat { memarg } => {
+                self.visit_v128_load_splat(memarg, LaneShape::I64x2)?
+            }
+            Operator::V128Load32Zero { memarg } => {
+                self.visit_memory_load(memarg, BaseType::Int)?;
+                self.jvm_code.push_instruction(I2L)?;
+                self.jvm_code.const_long(0xFFFFFFFF)?;
+                self.jvm_code.push_instruction(LAnd)?;
+                self.jvm_code.push_instruction(LConst0)?;
+                self.utilities.invoke_utility(
+                    UtilityMethod::Simd(SimdOperation::FromHalves),
+                    self.jvm_code,
+                )?;
+            }
+            Operator::V128Load64Zero { memarg } => {
+                self.visit_memory_load(memarg, BaseType::Long)?;
+                self.jvm_code.push_instruction(LConst0)?;
+                self.utilities.invoke_utility(
+                    UtilityMethod::Simd(SimdOperation::FromHalves),
+                    self.jvm_code,
                 )?;
             }
+            Operator::V128Store { memarg } => self.visit_v128_store(memarg)?,
+            Operator::V128Load8Lane { memarg, lane } => {
+                self.visit_v128_load_lane(memarg, LaneShape::I8x16, lane)?
+            }
+            Operator::V128Load16Lane { memarg, lane } => {
+                self.visit_v128_load_lane(memarg, LaneShape::I16x8, lane)?
+            }
+            Operator::V128Load32Lane { memarg, lane } => {
+                self.visit_v128_load_lane(memarg, LaneShape::I32x4, lane)?
+            }
+            Operator::V128Load64Lane { memarg, lane } => {
+                self.visit_v128_load_lane(memarg, LaneShape::I64x2, lane)?
+            }
+            Operator::V128Store8Lane { memarg, lane } => {
+                self.visit_v128_store_lane(memarg, LaneShape::I8x16, lane)?
+            }
+            Operator::V128Store16Lane { memarg, lane } => {
+                self.visit_v128_store_lane(memarg, LaneShape::I16x8, lane)?
+            }
+            Operator::V128Store32Lane { memarg, lane } => {
+                self.visit_v128_store_lane(memarg, LaneShape::I32x4, lane)?
+            }
+            Operator::V128Store64Lane { memarg, lane } => {
+                self.visit_v128_store_lane(memarg, LaneShape::I64x2, lane)?
+            }
+            Operator::V128Const { value } => const_v128(self.jvm_code, value.i128() as u128)?,
+            Operator::I8x16ExtractLaneS { lane } => {
+                extract_lane(self.jvm_code, LaneShape::I8x16, lane, true)?
+            }
+            Operator::I8x16ExtractLaneU { lane } => {
+                extract_lane(self.jvm_code, LaneShape::I8x16, lane, false)?
+            }
+            Operator::I16x8ExtractLaneS { lane } => {
+                extract_lane(self.jvm_code, LaneShape::I16x8, lane, true)?
+            }
+            Operator::I16x8ExtractLaneU { lane } => {
+                extract_lane(self.jvm_code, LaneShape::I16x8, lane, false)?
+            }
+            Operator::I32x4ExtractLane { lane } => {
+                extract_lane(self.jvm_code, LaneShape::I32x4, lane, true)?
+            }
+            Operator::I64x2ExtractLane { lane } => {
+                extract_lane(self.jvm_code, LaneShape::I64x2, lane, true)?
+            }
+            Operator::F32x4ExtractLane { lane } => {
+                extract_lane(self.jvm_code, LaneShape::F32x4, lane, true)?
+            }
+            Operator::F64x2ExtractLane { lane } => {
+                extract_lane(self.jvm_code, LaneShape::F64x2, lane, true)?
+            }
+            Operator::I8x16ReplaceLane { lane } => {
+                self.visit_v128_replace_lane(LaneShape::I8x16, lane)?
+            }
+            Operator::I16x8ReplaceLane { lane } => {
+                self.visit_v128_replace_lane(LaneShape::I16x8, lane)?
+            }
+            Operator::I32x4ReplaceLane { lane } => {
+                self.visit_v128_replace_lane(LaneShape::I32x4, lane)?
+            }
+            Operator::I64x2ReplaceLane { lane } => {
+                self.visit_v128_replace_lane(LaneShape::I64x2, lane)?
+            }
+            Operator::F32x4ReplaceLane { lane } => {
+                self.visit_v128_replace_lane(LaneShape::F32x4, lane)?
+            }
+            Operator::F64x2ReplaceLane { lane } => {
+                self.visit_v128_replace_lane(LaneShape::F64x2, lane)?
+            }
+
+            // Atomic instructions
+            Operator::AtomicFence => self
+                .jvm_code
+                .invoke(self.jvm_code.java.members.lang.invoke.var_handle.full_fence)?,
 
-            _ => todo!(),
+            other => {
+                if let Some((memarg, atomic)) = AtomicInstruction::from_operator(&other) {
+                    self.visit_atomic(memarg, atomic)?
+                } else if let Some(simd) = SimdOperation::from_operator(&other) {
+                    self.utilities
+                        .invoke_utility(UtilityMethod::Simd(simd), self.jvm_code)?
+                } else {
+                    todo!()
+                }
+            }
         }
 
-        self.wasm_prev_operand_stack_height = self.wasm_validator.operand_stack_height();
         Ok(())
     }
 
@@ -1012,6 +1965,506 @@ impl<'a, 'b, 'g> FunctionTranslator<'a, 'b, 'g> {
         Ok(())
     }
 
+    /// Find the region split out of this method which starts at an offset, if any
+    fn split_region_at(&self, offset: usize) -> Option<usize> {
+        let regions = &self.wasm_split.regions;
+        let first = regions.partition_point(|region| region.start < offset);
+        regions[first..]
+            .iter()
+            .take_while(|region| region.start == offset)
+            .position(|region| region.parent == self.wasm_split_region)
+            .map(|idx| first + idx)
+    }
+
+    /// Visit a region which gets translated into its own split method
+    ///
+    /// The region is translated into the split method right away (using the same validator), and
+    /// its operators then get skipped here. In its place, the block inputs (if any) are passed
+    /// through the frame, the split method gets called, and then control dispatches on how it left
+    /// the region. See the `split` module.
+    fn visit_split_region(&mut self, region_idx: usize) -> Result<(), Error> {
+        let split: &'b SplitFunction<'g> = self.wasm_split;
+        let region = &split.regions[region_idx];
+        let blockty = region.blockty.unwrap_or(BlockType::Empty);
+        let ty = self.wasm_validator.resources().block_type(blockty)?;
+        let base_stack_height = self.wasm_validator.operand_stack_height() - ty.inputs.len() as u32;
+
+        // Values carried by branches out of the region (to the function, then to each frame)
+        let mut exits = vec![self.function_typ.outputs.clone()];
+        exits.extend(
+            self.wasm_frames
+                .iter()
+                .map(|frame| frame.branch_values().to_vec()),
+        );
+
+        // Translate the region into the split method
+        let mut split_code =
+            CodeBuilder::new(self.jvm_code.class_graph, self.jvm_code.java, region.method);
+        let (used_exits, nested_split_methods) = {
+            let module = ModuleContext {
+                settings: self.settings,
+                class: self.class,
+                runtime: self.runtime,
+                functions: self.wasm_functions,
+                tables: self.wasm_tables,
+                memories: self.wasm_memories,
+                globals: self.wasm_globals,
+                datas: self.wasm_datas,
+                elements: self.wasm_elements,
+                tags: self.wasm_tags,
+            };
+            let function = FunctionInput {
+                function_typ: self.function_typ,
+                trampolined: self.trampolined,
+                body: self.wasm_function,
+                operators: self.wasm_operators,
+                inlined_locals: self.wasm_inlined_locals,
+                split,
+            };
+            let mut split_translator = FunctionTranslator::new(
+                module,
+                function,
+                self.utilities,
+                self.bootstrap_utilities,
+                &mut split_code,
+                self.wasm_validator,
+            )?;
+            split_translator.jvm_locals =
+                LocalsLayout::new_split(RefType::Object(self.class), &self.jvm_code.java.classes);
+            split_translator.wasm_split_region = Some(region_idx);
+            split_translator.wasm_split_base_stack_height = base_stack_height;
+            let used_exits = split_translator.translate_split_region(&exits)?;
+            (used_exits, split_translator.split_methods)
+        };
+        self.split_methods.push((region_idx, split_code.result()?));
+        self.split_methods.extend(nested_split_methods);
+        self.wasm_skip_until = Some(region.end);
+
+        // Pass the block inputs through the frame, then call the split method
+        if !ty.inputs.is_empty() {
+            self.pack_block_values(&ty.inputs)?;
+            self.get_frame_array(FrameArray::Object)?;
+            self.jvm_code.push_instruction(Instruction::Swap)?;
+            self.jvm_code.const_int(0)?;
+            self.jvm_code.push_instruction(Instruction::Swap)?;
+            self.jvm_code.push_instruction(Instruction::AAStore)?;
+        }
+        for array in FrameArray::ALL {
+            self.get_frame_array(array)?;
+        }
+        let (this_off, this_type) = self.jvm_locals.lookup_this()?;
+        self.jvm_code.get_local(this_off, &this_type)?;
+        self.jvm_code.invoke(region.method)?;
+        self.refresh_memory_locals()?;
+
+        // Dispatch on how control left the region
+        let fallthrough = self.jvm_code.fresh_label();
+        let mut targets = vec![fallthrough];
+        let mut exit_blocks = vec![];
+        for (exit_idx, used) in used_exits.into_iter().enumerate() {
+            if used {
+                let exit_block = self.jvm_code.fresh_label();
+                targets.push(exit_block);
+                exit_blocks.push((exit_block, exit_idx));
+            } else {
+                targets.push(fallthrough);
+            }
+        }
+        if exit_blocks.is_empty() {
+            self.jvm_code.pop()?;
+        } else {
+            self.jvm_code
+                .push_branch_instruction(BranchInstruction::TableSwitch {
+                    padding: 0,
+                    default: fallthrough,
+                    low: 0,
+                    targets,
+                })?;
+        }
+        for (exit_block, exit_idx) in exit_blocks {
+            self.jvm_code.place_label(exit_block)?;
+            self.get_split_values(&exits[exit_idx])?;
+            self.wasm_prev_operand_stack_height = base_stack_height + exits[exit_idx].len() as u32;
+            self.visit_branch((self.wasm_frames.len() - exit_idx) as u32)?;
+        }
+        self.jvm_code.place_label(fallthrough)?;
+        self.get_split_values(&ty.outputs)?;
+        self.wasm_prev_operand_stack_height = self.wasm_validator.operand_stack_height();
+
+        Ok(())
+    }
+
+    /// Translate the region of a split method, returning which exits out of the region (see
+    /// `visit_split_region`) get used
+    fn translate_split_region(&mut self, exits: &[Vec<StackType>]) -> Result<Vec<bool>, Error> {
+        let region_idx = self
+            .wasm_split_region
+            .expect("not translating a split method");
+        let region = &self.wasm_split.regions[region_idx];
+        let blockty = region.blockty.unwrap_or(BlockType::Empty);
+        let ty = self.wasm_validator.resources().block_type(blockty)?;
+
+        self.allocate_wasm_locals()?;
+        self.visit_memory_locals(region.start, region.end)?;
+        self.visit_try_locals(region.start, region.end)?;
+
+        // Branches out of the region go to frames whose end exits the method
+        for exit in exits {
+            let end_block = self.jvm_code.fresh_label();
+            self.wasm_frames.push(ControlFrame::Block {
+                end_block,
+                return_values: exit.clone(),
+                base_stack_height: self.wasm_split_base_stack_height,
+            });
+        }
+
+        // Get the block inputs and visit the region
+        self.get_split_values(&ty.inputs)?;
+        self.wasm_prev_operand_stack_height = self.wasm_validator.operand_stack_height();
+        self.visit_operator_range(region.start, region.end)?;
+
+        // Exit the method
+        if self.jvm_code.current_frame().is_some() {
+            self.exit_split_method(0, &ty.outputs)?;
+        }
+        let mut used_exits = vec![];
+        for (exit_idx, exit) in exits.iter().enumerate() {
+            let exit_label = self.wasm_frames[exit_idx].end_label();
+            let used = self.jvm_code.lookup_frame(exit_label).is_some();
+            if used {
+                self.jvm_code.place_label(exit_label)?;
+                self.exit_split_method(exit_idx as i32 + 1, exit)?;
+            }
+            used_exits.push(used);
+        }
+
+        Ok(used_exits)
+    }
+
+    /// Exit a split method, passing the values on the stack through the frame
+    fn exit_split_method(&mut self, exit_code: i32, values: &[StackType]) -> Result<(), Error> {
+        if !values.is_empty() {
+            self.pack_block_values(values)?;
+            self.get_frame_array(FrameArray::Object)?;
+            self.jvm_code.push_instruction(Instruction::Swap)?;
+            self.jvm_code.const_int(0)?;
+            self.jvm_code.push_instruction(Instruction::Swap)?;
+            self.jvm_code.push_instruction(Instruction::AAStore)?;
+        }
+        self.jvm_code.const_int(exit_code)?;
+        self.jvm_code.return_(Some(FieldType::int()))?;
+
+        Ok(())
+    }
+
+    /// Push values passed in or out of a split method through the frame
+    fn get_split_values(&mut self, values: &[StackType]) -> Result<(), Error> {
+        if !values.is_empty() {
+            self.get_frame_array(FrameArray::Object)?;
+            self.jvm_code.const_int(0)?;
+            self.jvm_code.push_instruction(Instruction::AALoad)?;
+            self.unpack_block_values(values)?;
+        }
+        Ok(())
+    }
+
+    /// Type of a WASM local
+    fn wasm_local_type(&self, local_idx: u32) -> Result<StackType, Error> {
+        let local_type = self
+            .wasm_validator
+            .get_local_type(local_idx)
+            .expect("missing local");
+        Ok(StackType::from_general(local_type)?)
+    }
+
+    /// Visit a `try` block
+    ///
+    /// The body of the block is protected by an exception handler catching `WasmException`. Since
+    /// the JVM clears the operand stack when an exception is caught, any values on the WASM operand
+    /// stack under the block get saved into an array first.
+    fn visit_try(&mut self, ty: BlockType) -> Result<(), Error> {
+        let ty = self.wasm_validator.resources().block_type(ty)?;
+        let handler = self.jvm_code.fresh_label();
+        let end_block = self.jvm_code.fresh_label();
+
+        #[cfg(debug_assertions)]
+        self.assert_top_stack(&ty.inputs);
+
+        let base_stack_height = self.wasm_validator.operand_stack_height() - ty.inputs.len() as u32;
+        let saved_stack_height = base_stack_height - self.wasm_split_base_stack_height;
+        let try_depth = self
+            .wasm_frames
+            .iter()
+            .filter(|frame| matches!(frame, ControlFrame::Try { .. } | ControlFrame::Catch { .. }))
+            .count();
+        let (exception_local, saved_stack_local) = self.jvm_try_locals[try_depth];
+
+        // Types of the values under the block (from the bottom of the stack up)
+        let mut saved_stack = vec![];
+        for depth in (ty.inputs.len()..ty.inputs.len() + saved_stack_height as usize).rev() {
+            let operand_type = self
+                .wasm_validator
+                .get_operand_type(depth)
+                .flatten()
+                .expect("Unknown operand type under `try`");
+            saved_stack.push(StackType::from_general(operand_type)?);
+        }
+
+        if !saved_stack.is_empty() {
+            // Stash block inputs (so we can get to the stack under them)
+            for input in ty.inputs.iter().rev() {
+                let field_type = input.field_type(&self.jvm_code.java.classes);
+                let local_idx = self.push_temp_local(field_type)?;
+                self.jvm_code.set_local(local_idx, &field_type)?;
+            }
+
+            // Save a copy of the stack under the block
+            let saved_stack_type =
+                FieldType::array(FieldType::object(self.jvm_code.java.classes.lang.object));
+            self.pack_stack_into_array(&saved_stack)?;
+            self.jvm_code.dup()?;
+            self.jvm_code
+                .set_local(saved_stack_local, &saved_stack_type)?;
+            self.unpack_stack_from_array(&saved_stack)?;
+
+            // Unstash block inputs
+            for _ in 0..ty.inputs.len() {
+                let (local_idx, field_type, _) = *self.jvm_locals.jvm_temps.last().unwrap();
+                self.jvm_code.get_local(local_idx, &field_type)?;
+                self.pop_temp_local()?;
+            }
+        }
+
+        self.wasm_frames.push(ControlFrame::Try {
+            handler,
+            end_block,
+            return_values: ty.outputs,
+            base_stack_height,
+            exception_local,
+            saved_stack_local,
+            saved_stack,
+        });
+        self.jvm_code
+            .begin_exception_handler(handler, Some(self.runtime.classes.wasm_exception))?;
+
+        Ok(())
+    }
+
+    /// Visit a `catch` or `catch_all` clause
+    ///
+    /// The first clause starts at the exception handler of the `try` body. Clauses are then
+    /// tried in order: a `catch` jumps to the next clause if the tag of the exception doesn't
+    /// match while a `catch_all` always matches.
+    fn visit_catch(&mut self, tag_index: Option<u32>) -> Result<(), Error> {
+        let exception_type = FieldType::object(self.runtime.classes.wasm_exception);
+        let (
+            end_block,
+            return_values,
+            base_stack_height,
+            exception_local,
+            saved_stack_local,
+            saved_stack,
+        ) = match self.wasm_frames.pop() {
+            Some(ControlFrame::Try {
+                handler,
+                end_block,
+                return_values,
+                base_stack_height,
+                exception_local,
+                saved_stack_local,
+                saved_stack,
+            }) => {
+                self.jvm_code.end_exception_handler()?;
+                self.jvm_code
+                    .push_branch_instruction(BranchInstruction::Goto(end_block))?;
+                self.jvm_code.place_label(handler)?;
+                self.jvm_code.set_local(exception_local, &exception_type)?;
+                self.refresh_memory_locals()?;
+                (
+                    end_block,
+                    return_values,
+                    base_stack_height,
+                    exception_local,
+                    saved_stack_local,
+                    saved_stack,
+                )
+            }
+            Some(ControlFrame::Catch {
+                next_catch: Some(next_catch),
+                end_block,
+                return_values,
+                base_stack_height,
+                exception_local,
+                saved_stack_local,
+                saved_stack,
+            }) => {
+                self.jvm_code
+                    .push_branch_instruction(BranchInstruction::Goto(end_block))?;
+                self.jvm_code.place_label(next_catch)?;
+                (
+                    end_block,
+                    return_values,
+                    base_stack_height,
+                    exception_local,
+                    saved_stack_local,
+                    saved_stack,
+                )
+            }
+            _ => panic!("expected `try` or `catch` control frame before `catch`"),
+        };
+
+        // Check the tag of the exception
+        let next_catch = if let Some(tag_index) = tag_index {
+            let next_catch = self.jvm_code.fresh_label();
+            let (this_off, this_ty) = self.jvm_locals.lookup_this()?;
+            self.jvm_code.get_local(exception_local, &exception_type)?;
+            self.jvm_code
+                .access_field(self.runtime.members.wasm_exception.tag, AccessMode::Read)?;
+            self.jvm_code.get_local(this_off, &this_ty)?;
+            self.wasm_tags[tag_index as usize].load_tag(self.jvm_code)?;
+            self.jvm_code
+                .push_branch_instruction(BranchInstruction::IfACmp(
+                    EqComparison::NE,
+                    next_catch,
+                    (),
+                ))?;
+            Some(next_catch)
+        } else {
+            None
+        };
+
+        // Restore the stack under the block
+        if !saved_stack.is_empty() {
+            let saved_stack_type =
+                FieldType::array(FieldType::object(self.jvm_code.java.classes.lang.object));
+            self.jvm_code
+                .get_local(saved_stack_local, &saved_stack_type)?;
+            self.unpack_stack_from_array(&saved_stack)?;
+        }
+
+        // Push the values carried by the exception
+        if let Some(tag_index) = tag_index {
+            let tags = self.wasm_tags;
+            let payload = &tags[tag_index as usize].tag_type.inputs;
+            if !payload.is_empty() {
+                self.jvm_code.get_local(exception_local, &exception_type)?;
+                self.jvm_code.access_field(
+                    self.runtime.members.wasm_exception.payload,
+                    AccessMode::Read,
+                )?;
+                self.unpack_stack_from_array(payload)?;
+            }
+        }
+
+        self.wasm_frames.push(ControlFrame::Catch {
+            next_catch,
+            end_block,
+            return_values,
+            base_stack_height,
+            exception_local,
+            saved_stack_local,
+            saved_stack,
+        });
+
+        Ok(())
+    }
+
+    /// Visit a `throw`
+    ///
+    /// Analagous to
+    ///
+    /// ```java
+    /// throw new WasmException(module.tagN, new Object[] { arg0, arg1, .. });
+    /// ```
+    fn visit_throw(&mut self, tag_index: u32) -> Result<(), Error> {
+        let tags = self.wasm_tags;
+        let tag = &tags[tag_index as usize];
+        let (this_off, this_ty) = self.jvm_locals.lookup_this()?;
+
+        self.pack_stack_into_array(&tag.tag_type.inputs)?;
+        self.jvm_code.new(self.runtime.classes.wasm_exception)?;
+        self.jvm_code.push_instruction(Instruction::DupX1)?;
+        self.jvm_code.push_instruction(Instruction::Swap)?;
+        self.jvm_code.get_local(this_off, &this_ty)?;
+        tag.load_tag(self.jvm_code)?;
+        self.jvm_code.push_instruction(Instruction::Swap)?;
+        self.jvm_code
+            .invoke(self.runtime.members.wasm_exception.init)?;
+        self.jvm_code
+            .push_branch_instruction(BranchInstruction::AThrow)?;
+
+        Ok(())
+    }
+
+    /// Visit a `rethrow` of the exception caught by an enclosing `catch` clause
+    fn visit_rethrow(&mut self, relative_depth: u32) -> Result<(), Error> {
+        let exception_local = match self.wasm_frames.iter().nth_back(relative_depth as usize) {
+            Some(ControlFrame::Catch {
+                exception_local, ..
+            }) => *exception_local,
+            _ => panic!("expected `catch` control frame for `rethrow`"),
+        };
+        let exception_type = FieldType::object(self.runtime.classes.wasm_exception);
+        self.jvm_code.get_local(exception_local, &exception_type)?;
+        self.jvm_code
+            .push_branch_instruction(BranchInstruction::AThrow)?;
+
+        Ok(())
+    }
+
+    /// Visit the `delegate` ending a `try` block
+    ///
+    /// Exceptions caught by the body are passed on to whichever handler is in effect inside the
+    /// block targeted by the `delegate` (or rethrown to the caller if the target is the function
+    /// body). The rest is just like ending a regular block.
+    fn visit_delegate(&mut self, relative_depth: u32) -> Result<(), Error> {
+        let block_frame = match self.wasm_frames.pop() {
+            Some(ControlFrame::Try {
+                handler,
+                end_block,
+                return_values,
+                base_stack_height,
+                ..
+            }) => {
+                self.jvm_code.end_exception_handler()?;
+                self.jvm_code
+                    .push_branch_instruction(BranchInstruction::Goto(end_block))?;
+                self.jvm_code.place_label(handler)?;
+                ControlFrame::Block {
+                    end_block,
+                    return_values,
+                    base_stack_height,
+                }
+            }
+            _ => panic!("expected `try` control frame before `delegate`"),
+        };
+
+        // Find the innermost `try` body enclosing the target
+        let target_handler = self
+            .wasm_frames
+            .iter()
+            .rev()
+            .skip(relative_depth as usize)
+            .find_map(|frame| match frame {
+                ControlFrame::Try { handler, .. } => Some(*handler),
+                _ => None,
+            });
+        let target_handler = match (target_handler, self.jvm_delegate_to_caller) {
+            (Some(handler), _) => handler,
+            (None, Some(delegate_to_caller)) => delegate_to_caller,
+            (None, None) => {
+                let delegate_to_caller = self.jvm_code.fresh_label();
+                self.jvm_delegate_to_caller = Some(delegate_to_caller);
+                delegate_to_caller
+            }
+        };
+        self.jvm_code
+            .push_branch_instruction(BranchInstruction::Goto(target_handler))?;
+
+        self.wasm_frames.push(block_frame);
+        self.visit_end()
+    }
+
     /// Visit the end of a block
     ///
     /// Note: unlike `br`/`br_if`, reaching the end of a block naturally means that the stack
@@ -1030,9 +2483,36 @@ impl<'a, 'b, 'g> FunctionTranslator<'a, 'b, 'g> {
             return Ok(());
         };
 
-        // In the case of a single-arm `if`, we must place the else label
-        if let ControlFrame::If { else_block, .. } = &control_frame {
-            self.jvm_code.place_label(*else_block)?;
+        match &control_frame {
+            // In the case of a single-arm `if`, we must place the else label
+            ControlFrame::If { else_block, .. } => self.jvm_code.place_label(*else_block)?,
+
+            // Exceptions not caught by any clause get rethrown
+            ControlFrame::Try {
+                handler, end_block, ..
+            } => {
+                self.jvm_code.end_exception_handler()?;
+                self.jvm_code
+                    .push_branch_instruction(BranchInstruction::Goto(*end_block))?;
+                self.jvm_code.place_label(*handler)?;
+                self.jvm_code
+                    .push_branch_instruction(BranchInstruction::AThrow)?;
+            }
+            ControlFrame::Catch {
+                next_catch: Some(next_catch),
+                end_block,
+                exception_local,
+                ..
+            } => {
+                let exception_type = FieldType::object(self.runtime.classes.wasm_exception);
+                self.jvm_code
+                    .push_branch_instruction(BranchInstruction::Goto(*end_block))?;
+                self.jvm_code.place_label(*next_catch)?;
+                self.jvm_code.get_local(*exception_local, &exception_type)?;
+                self.jvm_code
+                    .push_branch_instruction(BranchInstruction::AThrow)?;
+            }
+            _ => (),
         }
 
         use crate::jvm::Error::PlacingLabelBeforeReference;
@@ -1113,7 +2593,7 @@ impl<'a, 'b, 'g> FunctionTranslator<'a, 'b, 'g> {
             // Stash branch values (so we can unwind the stack under them)
             for branch_value in branch_values.iter().rev() {
                 let field_type = branch_value.field_type(&self.jvm_code.java.classes);
-                let local_idx = self.jvm_locals.push_local(field_type)?;
+                let local_idx = self.push_temp_local(field_type)?;
                 self.jvm_code.set_local(local_idx, &field_type)?;
             }
 
@@ -1125,9 +2605,9 @@ impl<'a, 'b, 'g> FunctionTranslator<'a, 'b, 'g> {
 
             // Unstash branch values
             for _ in 0..branch_values.len() {
-                let (local_idx, field_type) = self.jvm_locals.pop_local()?;
+                let (local_idx, field_type, _) = *self.jvm_locals.jvm_temps.last().unwrap();
                 self.jvm_code.get_local(local_idx, &field_type)?;
-                self.jvm_code.kill_top_local(local_idx, Some(field_type))?;
+                self.pop_temp_local()?;
             }
         }
 
@@ -1285,24 +2765,125 @@ impl<'a, 'b, 'g> FunctionTranslator<'a, 'b, 'g> {
 
     /// Visit a return
     fn visit_return(&mut self) -> Result<(), Error> {
+        // In a split method, this is a branch to the frame standing in for the function
+        if self.wasm_split_region.is_some() {
+            return self.visit_branch(self.wasm_frames.len() as u32 - 1);
+        }
+
         if self.function_typ.outputs.len() > 1 {
-            // TODO: this clone is spurious, but to satiate the borrow checker which doesn't
-            // know that `pack_stack_into_array` doesn't touch `self.function_typ.outputs`
-            // Consider pulling `pack_stack_into_array` out of this class to avoid this.
-            self.pack_stack_into_array(&self.function_typ.outputs.clone())?
+            self.pack_stack_into_tuple(&self.function_typ.outputs)?
+        }
+
+        if self.trampolined {
+            box_return_value(self.jvm_code, &self.function_typ.outputs)?;
+            self.jvm_code.return_(Some(FieldType::object(
+                self.jvm_code.java.classes.lang.object,
+            )))?;
+        } else {
+            self.jvm_code.return_(
+                self.function_typ
+                    .method_descriptor(&self.jvm_code.java.classes, &self.runtime.tuples)
+                    .return_type,
+            )?;
         }
+        Ok(())
+    }
+
+    /// Visit a `return_call`
+    ///
+    /// When trampolining, this returns a thunk which calls the tail call variant of the function.
+    /// Otherwise, it is just a call followed by a return.
+    fn visit_return_call(&mut self, function_index: u32) -> Result<(), Error> {
+        let function = &self.wasm_functions[function_index as usize];
+        let tailcall_method = match function.tailcall_method {
+            Some(tailcall_method) if self.trampolined => tailcall_method,
+            _ => {
+                self.visit_call(function_index)?;
+                return self.visit_return();
+            }
+        };
 
-        self.jvm_code.return_(
-            self.function_typ
-                .method_descriptor(&self.jvm_code.java.classes)
-                .return_type,
+        // Pack the arguments along with the module (which is just one more reference)
+        self.pack_inputs(&function.func_type)?;
+        let mut arguments = parameter_stack_types(&function.func_type);
+        arguments.push(StackType::ExternRef);
+        let (off, field_type) = self.jvm_locals.lookup_this()?;
+        self.jvm_code.get_local(off, &field_type)?;
+        self.pack_stack_into_array(&arguments)?;
+
+        // `MethodHandles.insertArguments(tailcallMethod, 0, arguments)`
+        self.jvm_code.const_methodhandle(tailcall_method)?;
+        self.jvm_code.push_instruction(Instruction::Swap)?;
+        self.jvm_code.const_int(0)?;
+        self.jvm_code.push_instruction(Instruction::Swap)?;
+        self.jvm_code.invoke(
+            self.jvm_code
+                .java
+                .members
+                .lang
+                .invoke
+                .method_handles
+                .insert_arguments,
         )?;
+
+        self.return_thunk()
+    }
+
+    /// Visit a `return_call_indirect`
+    ///
+    /// When trampolining, this returns a thunk which calls the function handle from the table (or
+    /// rather the tail call variant of the function, if it has one). Otherwise, it is just a
+    /// `call_indirect` followed by a return.
+    fn visit_return_call_indirect(&mut self, typ: BlockType, table_idx: u32) -> Result<(), Error> {
+        if !self.trampolined {
+            self.visit_call_indirect(typ, table_idx)?;
+            return self.visit_return();
+        }
+        let func_typ = self.wasm_validator.resources().block_type(typ)?;
+        let handle_type = FieldType::object(self.jvm_code.java.classes.lang.invoke.method_handle);
+
+        // Stash the function handle from the table
+        self.visit_table_get(table_idx)?;
+        let handle_offset = self.jvm_locals.push_local(handle_type)?;
+        self.jvm_code.set_local(handle_offset, &handle_type)?;
+
+        // Pack the arguments (the handle already has the module bound)
+        self.pack_inputs(&func_typ)?;
+        self.pack_stack_into_array(&parameter_stack_types(&func_typ))?;
+
+        // `TailCall.indirectThunk(handle, type, arguments)`
+        self.jvm_code.get_local(handle_offset, &handle_type)?;
+        self.jvm_code.push_instruction(Instruction::Swap)?;
+        self.jvm_code
+            .push_instruction(Instruction::Ldc(ConstantData::MethodType(
+                func_typ.method_descriptor(&self.jvm_code.java.classes, &self.runtime.tuples),
+            )))?;
+        self.jvm_code.push_instruction(Instruction::Swap)?;
+        self.jvm_code
+            .invoke(self.runtime.members.tail_call.indirect_thunk)?;
+
+        let _ = self.jvm_locals.pop_local()?;
+        self.jvm_code.kill_top_local(handle_offset, None)?;
+
+        self.return_thunk()
+    }
+
+    /// Wrap the thunk on the top of the stack into a `TailCall` and return it
+    fn return_thunk(&mut self) -> Result<(), Error> {
+        // `return new TailCall(thunk);`
+        self.jvm_code.new(self.runtime.classes.tail_call)?;
+        self.jvm_code.push_instruction(Instruction::DupX1)?;
+        self.jvm_code.push_instruction(Instruction::Swap)?;
+        self.jvm_code.invoke(self.runtime.members.tail_call.init)?;
+        self.jvm_code
+            .return_(Some(FieldType::object(self.runtime.classes.tail_call)))?;
         Ok(())
     }
 
     /// Visit a call
     fn visit_call(&mut self, function_index: u32) -> Result<(), Error> {
         let function = &self.wasm_functions[function_index as usize];
+        self.pack_inputs(&function.func_type)?;
 
         // Load the module reference onto the stack (it is always the last argument)
         let (off, field_type) = self.jvm_locals.lookup_this()?;
@@ -1310,8 +2891,9 @@ impl<'a, 'b, 'g> FunctionTranslator<'a, 'b, 'g> {
 
         // Call the corresponding method and unpack the outputs if need be
         self.jvm_code.invoke(function.method)?;
+        self.refresh_memory_locals()?;
         if function.func_type.outputs.len() > 1 {
-            self.unpack_stack_from_array(&function.func_type.outputs)?;
+            self.unpack_stack_from_tuple(&function.func_type.outputs)?;
         }
 
         Ok(())
@@ -1322,8 +2904,18 @@ impl<'a, 'b, 'g> FunctionTranslator<'a, 'b, 'g> {
         let func_typ = self.wasm_validator.resources().block_type(typ)?;
         let table = &self.wasm_tables[table_idx as usize];
 
+        // The table index is on top of the inputs, so it gets stashed while packing them
+        if !func_typ.packed_inputs().is_empty() {
+            let index_offset = self.push_temp_local(FieldType::int())?;
+            self.jvm_code.set_local(index_offset, &FieldType::int())?;
+            self.pack_inputs(&func_typ)?;
+            self.jvm_code.get_local(index_offset, &FieldType::int())?;
+            self.pop_temp_local()?;
+        }
+
         // Compute the method descriptor we'll actually be calling
-        let mut desc = func_typ.method_descriptor(&self.jvm_code.java.classes);
+        let mut desc =
+            func_typ.method_descriptor(&self.jvm_code.java.classes, &self.runtime.tuples);
         desc.parameters.push(FieldType::int());
         desc.parameters.push(FieldType::object(self.class));
 
@@ -1341,23 +2933,36 @@ impl<'a, 'b, 'g> FunctionTranslator<'a, 'b, 'g> {
             .push_instruction(Instruction::ALoad(this_off))?;
         self.jvm_code
             .invoke_dynamic(bootstrap_method, UnqualifiedName::CALLINDIRECT, desc)?;
+        self.refresh_memory_locals()?;
         if func_typ.outputs.len() > 1 {
-            self.unpack_stack_from_array(&func_typ.outputs)?;
+            self.unpack_stack_from_tuple(&func_typ.outputs)?;
         }
 
         Ok(())
     }
 
+    /// Pack the inputs of a call which don't fit in the JVM's parameter limit into an array
+    ///
+    /// The inputs are on the top of the stack. See [`FunctionType::packed_inputs`].
+    fn pack_inputs(&mut self, func_typ: &FunctionType) -> Result<(), Error> {
+        let packed_inputs = func_typ.packed_inputs();
+        if !packed_inputs.is_empty() {
+            self.pack_stack_into_array(packed_inputs)?;
+        }
+        Ok(())
+    }
+
     /// Pack the top stack elements into an array
     ///
-    /// This is used when returning out of functions that return multiple values.
+    /// This is used for inputs past the JVM parameter limit, arguments of trampolined tail calls,
+    /// and exception payloads.
     /// TODO: this could be made into a powerful `invokedynamic` packer (since the `MethodType` is
     /// enough to figure out what to do).
     fn pack_stack_into_array(&mut self, expected: &[StackType]) -> Result<(), Error> {
         let object = FieldType::object(self.jvm_code.java.classes.lang.object);
 
         // Initialize the variable containing the array for packing values
-        let arr_offset = self.jvm_locals.push_local(FieldType::array(object))?;
+        let arr_offset = self.push_temp_local(FieldType::array(object))?;
         self.jvm_code.const_int(expected.len() as i32)?;
         self.jvm_code
             .push_instruction(Instruction::ANewArray(RefType::Object(
@@ -1367,12 +2972,12 @@ impl<'a, 'b, 'g> FunctionTranslator<'a, 'b, 'g> {
             .set_local(arr_offset, &FieldType::array(object))?;
 
         // Initialize the variable containing the index
-        let idx_offset = self.jvm_locals.push_local(FieldType::int())?;
+        let idx_offset = self.push_temp_local(FieldType::int())?;
         self.jvm_code.const_int(expected.len() as i32 - 1)?;
         self.jvm_code.set_local(idx_offset, &FieldType::int())?;
 
         // Initialize the a temporary variable for stashing boxed values
-        let tmp_offset = self.jvm_locals.push_local(object)?;
+        let tmp_offset = self.push_temp_local(object)?;
         self.jvm_code.zero_local(tmp_offset, object)?;
 
         for stack_value in expected.iter().rev() {
@@ -1390,7 +2995,7 @@ impl<'a, 'b, 'g> FunctionTranslator<'a, 'b, 'g> {
                 StackType::F64 => self
                     .jvm_code
                     .invoke(self.jvm_code.java.members.lang.double.value_of)?,
-                StackType::FuncRef | StackType::ExternRef => (), // already reference types
+                StackType::V128 | StackType::FuncRef | StackType::ExternRef => (), // already references
             }
             self.jvm_code
                 .push_instruction(Instruction::AStore(tmp_offset))?;
@@ -1414,30 +3019,24 @@ impl<'a, 'b, 'g> FunctionTranslator<'a, 'b, 'g> {
             .push_instruction(Instruction::ALoad(arr_offset))?;
 
         // Kill the locals
-        let _ = self.jvm_locals.pop_local()?;
-        let _ = self.jvm_locals.pop_local()?;
-        let _ = self.jvm_locals.pop_local()?;
-        self.jvm_code.kill_top_local(tmp_offset, None)?;
-        self.jvm_code
-            .kill_top_local(idx_offset, Some(FieldType::int()))?;
-        self.jvm_code.kill_top_local(arr_offset, None)?;
+        self.pop_temp_local()?;
+        self.pop_temp_local()?;
+        self.pop_temp_local()?;
 
         Ok(())
     }
 
-    /// Unpack the top stack elements from an array
-    ///
-    /// This is used when calling functions that return multiple values.
+    /// Unpack the top stack elements from an array (see `pack_stack_into_array`)
     fn unpack_stack_from_array(&mut self, expected: &[StackType]) -> Result<(), Error> {
         let object = FieldType::object(self.jvm_code.java.classes.lang.object);
 
         // Initialize the variable containing the array for packing values
-        let arr_offset = self.jvm_locals.push_local(FieldType::array(object))?;
+        let arr_offset = self.push_temp_local(FieldType::array(object))?;
         self.jvm_code
             .push_instruction(Instruction::AStore(arr_offset))?;
 
         // Initialize the variable containing the index
-        let idx_offset = self.jvm_locals.push_local(FieldType::int())?;
+        let idx_offset = self.push_temp_local(FieldType::int())?;
         self.jvm_code.push_instruction(Instruction::IConst0)?;
         self.jvm_code
             .push_instruction(Instruction::IStore(idx_offset))?;
@@ -1486,6 +3085,10 @@ impl<'a, 'b, 'g> FunctionTranslator<'a, 'b, 'g> {
                     self.jvm_code
                         .push_instruction(Instruction::CheckCast(handle_cls))?;
                 }
+                StackType::V128 => {
+                    self.jvm_code
+                        .push_instruction(Instruction::CheckCast(v128_ref_type()))?;
+                }
                 StackType::ExternRef => (), // already supposed to be `java/lang/Object`
             }
 
@@ -1495,12 +3098,57 @@ impl<'a, 'b, 'g> FunctionTranslator<'a, 'b, 'g> {
         }
 
         // Kill the locals
+        self.pop_temp_local()?;
+        self.pop_temp_local()?;
+
+        Ok(())
+    }
+
+    /// Pack the top stack elements into a tuple (see [`TupleClasses`])
+    ///
+    /// This is used when returning out of functions that return multiple values.
+    fn pack_stack_into_tuple(&mut self, expected: &[StackType]) -> Result<(), Error> {
+        let tuple_class = self.runtime.tuples.get(expected);
+        self.jvm_code.invoke(tuple_class.of)?;
+        Ok(())
+    }
+
+    /// Unpack the fields of the tuple on the top of the stack (see [`TupleClasses`])
+    ///
+    /// This is used when calling functions that return multiple values.
+    fn unpack_stack_from_tuple(&mut self, expected: &[StackType]) -> Result<(), Error> {
+        let tuple_class = self.runtime.tuples.get(expected);
+        let tuple_type = FieldType::object(tuple_class.class);
+
+        let tuple_offset = self.jvm_locals.push_local(tuple_type)?;
+        self.jvm_code.set_local(tuple_offset, &tuple_type)?;
+        for field in &tuple_class.fields {
+            self.jvm_code.get_local(tuple_offset, &tuple_type)?;
+            self.jvm_code.access_field(*field, AccessMode::Read)?;
+        }
         let _ = self.jvm_locals.pop_local()?;
-        let _ = self.jvm_locals.pop_local()?;
-        self.jvm_code
-            .kill_top_local(idx_offset, Some(FieldType::int()))?;
-        self.jvm_code.kill_top_local(arr_offset, None)?;
+        self.jvm_code.kill_top_local(tuple_offset, None)?;
+
+        Ok(())
+    }
 
+    /// Pack the values on the top of the stack into one `Object` (for passing block inputs and
+    /// results in and out of split methods)
+    ///
+    /// This is `null` for no values, the boxed value for one value, and a tuple otherwise.
+    fn pack_block_values(&mut self, expected: &[StackType]) -> Result<(), Error> {
+        if expected.len() > 1 {
+            self.pack_stack_into_tuple(expected)?;
+        }
+        box_return_value(self.jvm_code, expected)
+    }
+
+    /// Unpack the `Object` on the top of the stack into values (see `pack_block_values`)
+    fn unpack_block_values(&mut self, expected: &[StackType]) -> Result<(), Error> {
+        unbox_return_value(self.jvm_code, &self.runtime.tuples, expected)?;
+        if expected.len() > 1 {
+            self.unpack_stack_from_tuple(expected)?;
+        }
         Ok(())
     }
 
@@ -1545,7 +3193,7 @@ impl<'a, 'b, 'g> FunctionTranslator<'a, 'b, 'g> {
         let global_field_type = global.global_type.field_type(&self.jvm_code.java.classes);
 
         // Stash the value being set in a local
-        let temp_index = self.jvm_locals.push_local(global_field_type)?;
+        let temp_index = self.push_temp_local(global_field_type)?;
         self.jvm_code.set_local(temp_index, &global_field_type)?;
 
         // Write to the field
@@ -1555,10 +3203,8 @@ impl<'a, 'b, 'g> FunctionTranslator<'a, 'b, 'g> {
         self.jvm_code.get_local(temp_index, &global_field_type)?;
         global.write(self.runtime, self.jvm_code)?;
 
-        // Clear the local
-        self.jvm_code
-            .kill_top_local(temp_index, Some(global_field_type))?;
-        self.jvm_locals.pop_local()?;
+        // Clear the local
+        self.pop_temp_local()?;
 
         Ok(())
     }
@@ -1601,9 +3247,9 @@ impl<'a, 'b, 'g> FunctionTranslator<'a, 'b, 'g> {
         let this_off = self.jvm_locals.lookup_this()?.0;
 
         // Put the length, source index, and destination index in variables
-        let len_off = self.jvm_locals.push_local(FieldType::int())?;
-        let src_off = self.jvm_locals.push_local(FieldType::int())?;
-        let dst_off = self.jvm_locals.push_local(FieldType::int())?;
+        let len_off = self.push_temp_local(FieldType::int())?;
+        let src_off = self.push_temp_local(FieldType::int())?;
+        let dst_off = self.push_temp_local(FieldType::int())?;
         self.jvm_code
             .push_instruction(Instruction::IStore(len_off))?;
         self.jvm_code
@@ -1622,15 +3268,9 @@ impl<'a, 'b, 'g> FunctionTranslator<'a, 'b, 'g> {
         )?;
 
         // Clear the locals
-        self.jvm_locals.pop_local()?;
-        self.jvm_locals.pop_local()?;
-        self.jvm_locals.pop_local()?;
-        self.jvm_code
-            .kill_top_local(dst_off, Some(FieldType::int()))?;
-        self.jvm_code
-            .kill_top_local(src_off, Some(FieldType::int()))?;
-        self.jvm_code
-            .kill_top_local(len_off, Some(FieldType::int()))?;
+        self.pop_temp_local()?;
+        self.pop_temp_local()?;
+        self.pop_temp_local()?;
 
         Ok(())
     }
@@ -1642,17 +3282,17 @@ impl<'a, 'b, 'g> FunctionTranslator<'a, 'b, 'g> {
         let this_off = self.jvm_locals.lookup_this()?.0;
 
         // Number of entries to copy
-        let len_idx = self.jvm_locals.push_local(FieldType::int())?;
+        let len_idx = self.push_temp_local(FieldType::int())?;
         self.jvm_code
             .push_instruction(Instruction::IStore(len_idx))?;
 
         // Copy from this offset
-        let src_off_idx = self.jvm_locals.push_local(FieldType::int())?;
+        let src_off_idx = self.push_temp_local(FieldType::int())?;
         self.jvm_code
             .push_instruction(Instruction::IStore(src_off_idx))?;
 
         // Copy to this offset
-        let dst_off_idx = self.jvm_locals.push_local(FieldType::int())?;
+        let dst_off_idx = self.push_temp_local(FieldType::int())?;
         self.jvm_code
             .push_instruction(Instruction::IStore(dst_off_idx))?;
 
@@ -1673,15 +3313,9 @@ impl<'a, 'b, 'g> FunctionTranslator<'a, 'b, 'g> {
             .invoke(self.jvm_code.java.members.lang.system.arraycopy)?;
 
         // Clean up temporary locals
-        self.jvm_locals.pop_local()?;
-        self.jvm_locals.pop_local()?;
-        self.jvm_locals.pop_local()?;
-        self.jvm_code
-            .kill_top_local(dst_off_idx, Some(FieldType::int()))?;
-        self.jvm_code
-            .kill_top_local(src_off_idx, Some(FieldType::int()))?;
-        self.jvm_code
-            .kill_top_local(len_idx, Some(FieldType::int()))?;
+        self.pop_temp_local()?;
+        self.pop_temp_local()?;
+        self.pop_temp_local()?;
 
         Ok(())
     }
@@ -1763,39 +3397,340 @@ impl<'a, 'b, 'g> FunctionTranslator<'a, 'b, 'g> {
 
     fn visit_memory_load(&mut self, memarg: MemArg, ty: BaseType) -> Result<(), Error> {
         let memory = &self.wasm_memories[memarg.memory as usize];
-        let this_off = self.jvm_locals.lookup_this()?.0;
-        memory.load(self.runtime, self.jvm_code, this_off, memarg, ty)?;
+        let access = MemoryAccess {
+            source: self.memory_source(memarg.memory)?,
+            memarg,
+        };
+        memory.load(self.runtime, self.jvm_code, self.utilities, access, ty)?;
 
         Ok(())
     }
 
     fn visit_memory_store(&mut self, memarg: MemArg, ty: BaseType) -> Result<(), Error> {
         let memory = &self.wasm_memories[memarg.memory as usize];
-        let this_off = self.jvm_locals.lookup_this()?.0;
+        let access = MemoryAccess {
+            source: self.memory_source(memarg.memory)?,
+            memarg,
+        };
 
         // TODO: this is unused if the type has width 1
         let temp_off = self.jvm_locals.push_local(FieldType::Base(ty))?;
-        memory.store(self.runtime, self.jvm_code, this_off, temp_off, memarg, ty)?;
+        memory.store(
+            self.runtime,
+            self.jvm_code,
+            self.utilities,
+            access,
+            temp_off,
+            ty,
+        )?;
+        self.jvm_locals.pop_local()?;
+
+        Ok(())
+    }
+
+    fn visit_v128_load(&mut self, memarg: MemArg) -> Result<(), Error> {
+        let memory = &self.wasm_memories[memarg.memory as usize];
+        let access = MemoryAccess {
+            source: self.memory_source(memarg.memory)?,
+            memarg,
+        };
+        memory.load_v128(self.runtime, self.jvm_code, self.utilities, access)?;
+        self.utilities.invoke_utility(
+            UtilityMethod::Simd(SimdOperation::FromHalves),
+            self.jvm_code,
+        )?;
+
+        Ok(())
+    }
+
+    /// Load 64 bits and extend each of the narrow lanes to fill the 128 bits
+    fn visit_v128_load_extend(
+        &mut self,
+        memarg: MemArg,
+        shape: LaneShape,
+        signed: bool,
+    ) -> Result<(), Error> {
+        self.visit_memory_load(memarg, BaseType::Long)?;
+        self.jvm_code.push_instruction(Instruction::LConst0)?;
+        self.utilities.invoke_utility(
+            UtilityMethod::Simd(SimdOperation::FromHalves),
+            self.jvm_code,
+        )?;
+        let extend = SimdOperation::Extend {
+            shape,
+            high: false,
+            signed,
+        };
+        self.utilities
+            .invoke_utility(UtilityMethod::Simd(extend), self.jvm_code)?;
+
+        Ok(())
+    }
+
+    fn visit_v128_load_splat(&mut self, memarg: MemArg, shape: LaneShape) -> Result<(), Error> {
+        self.visit_memory_load(memarg, Self::lane_base_type(shape))?;
+        self.utilities.invoke_utility(
+            UtilityMethod::Simd(SimdOperation::Splat(shape)),
+            self.jvm_code,
+        )?;
+
+        Ok(())
+    }
+
+    fn visit_v128_load_lane(
+        &mut self,
+        memarg: MemArg,
+        shape: LaneShape,
+        lane: u8,
+    ) -> Result<(), Error> {
+        let v128 = StackType::V128.field_type(&self.jvm_code.java.classes);
+        let lane_type = shape.lane_type();
+
+        // Stash the vector while loading the lane
+        let temp_off = self.jvm_locals.push_local(v128)?;
+        self.jvm_code.set_local(temp_off, &v128)?;
+        self.visit_memory_load(memarg, Self::lane_base_type(shape))?;
+        self.jvm_code.get_local(temp_off, &v128)?;
+        self.jvm_code.kill_top_local(temp_off, None)?;
+        self.jvm_locals.pop_local()?;
+
+        // Re-order the stack
+        if lane_type.width() == 1 {
+            self.jvm_code.push_instruction(Instruction::Swap)?;
+        } else {
+            self.jvm_code.push_instruction(Instruction::DupX2)?;
+            self.jvm_code.push_instruction(Instruction::Pop)?;
+        }
+
+        self.visit_v128_replace_lane(shape, lane)
+    }
+
+    fn visit_v128_store(&mut self, memarg: MemArg) -> Result<(), Error> {
+        let memory = &self.wasm_memories[memarg.memory as usize];
+        let access = MemoryAccess {
+            source: self.memory_source(memarg.memory)?,
+            memarg,
+        };
+
+        let v128 = StackType::V128.field_type(&self.jvm_code.java.classes);
+        let temp_off = self.jvm_locals.push_local(v128)?;
+        memory.store_v128(
+            self.runtime,
+            self.jvm_code,
+            self.utilities,
+            access,
+            temp_off,
+        )?;
         self.jvm_locals.pop_local()?;
 
         Ok(())
     }
 
+    fn visit_v128_store_lane(
+        &mut self,
+        memarg: MemArg,
+        shape: LaneShape,
+        lane: u8,
+    ) -> Result<(), Error> {
+        extract_lane(self.jvm_code, shape, lane, true)?;
+        self.visit_memory_store(memarg, Self::lane_base_type(shape))
+    }
+
+    /// Replace a lane in a vector
+    ///
+    /// Assumes the top of the stack is the new lane value followed by the vector.
+    fn visit_v128_replace_lane(&mut self, shape: LaneShape, lane: u8) -> Result<(), Error> {
+        lane_to_bits(self.jvm_code, shape, lane)?;
+        self.jvm_code.const_int(lane_half(shape, lane) as i32)?;
+        self.jvm_code.const_long(lane_keep_mask(shape, lane))?;
+        self.utilities.invoke_utility(
+            UtilityMethod::Simd(SimdOperation::ReplaceBits),
+            self.jvm_code,
+        )?;
+
+        Ok(())
+    }
+
+    /// Type used to load or store a single lane from memory
+    fn lane_base_type(shape: LaneShape) -> BaseType {
+        match shape {
+            LaneShape::I8x16 => BaseType::Byte,
+            LaneShape::I16x8 => BaseType::Short,
+            LaneShape::I32x4 => BaseType::Int,
+            LaneShape::I64x2 => BaseType::Long,
+            LaneShape::F32x4 => BaseType::Float,
+            LaneShape::F64x2 => BaseType::Double,
+        }
+    }
+
+    /// Visit an atomic memory instruction
+    ///
+    /// Full-width accesses go directly through a `VarHandle` view of the memory, everything else
+    /// is delegated to utility methods.
+    fn visit_atomic(&mut self, memarg: MemArg, atomic: AtomicInstruction) -> Result<(), Error> {
+        let memory = &self.wasm_memories[memarg.memory as usize];
+        let this_off = self.jvm_locals.lookup_this()?.0;
+
+        if memory.is_memory64() {
+            return Err(Error::Unsupported("atomic accesses to 64-bit memories"));
+        }
+
+        // Unshared memories never have any waiters
+        if !memory.memory_type.shared {
+            match atomic.access {
+                AtomicAccess::Wait => {
+                    for _ in 0..3 {
+                        self.jvm_code.pop()?;
+                    }
+                    let java = self.jvm_code.java;
+                    self.jvm_code
+                        .new(java.classes.lang.illegal_argument_exception)?;
+                    self.jvm_code.push_instruction(Instruction::Dup)?;
+                    self.jvm_code.const_string("expected shared memory")?;
+                    self.jvm_code
+                        .invoke(java.members.lang.illegal_argument_exception.init)?;
+                    self.jvm_code
+                        .push_branch_instruction(BranchInstruction::AThrow)?;
+                    return Ok(());
+                }
+                AtomicAccess::Notify => {
+                    // Still check the address is valid
+                    self.jvm_code.pop()?;
+                    let load = AtomicInstruction {
+                        access: AtomicAccess::Load,
+                        width: AtomicWidth::I32,
+                        value_type: BaseType::Int,
+                    };
+                    self.visit_atomic(memarg, load)?;
+                    self.jvm_code.push_instruction(Instruction::Pop)?;
+                    self.jvm_code.push_instruction(Instruction::IConst0)?;
+                    return Ok(());
+                }
+                _ => (),
+            }
+        }
+
+        // Stash the operands
+        let operand_types = atomic.operand_types();
+        let mut operand_offs = vec![];
+        for ty in operand_types.iter().rev() {
+            let field_type = FieldType::Base(*ty);
+            let off = self.push_temp_local(field_type)?;
+            self.jvm_code.set_local(off, &field_type)?;
+            operand_offs.push(off);
+        }
+        operand_offs.reverse();
+
+        // Adjust the offset
+        memory.add_offset(self.jvm_code, self.utilities, memarg)?;
+
+        // Load the memory and re-order the stack
+        self.jvm_code
+            .push_instruction(Instruction::ALoad(this_off))?;
+        memory.load_bytes(self.runtime, self.jvm_code)?;
+        self.jvm_code.push_instruction(Instruction::Swap)?;
+
+        // Reload the operands, narrowing values that don't fill a `long`
+        let narrow_long = atomic.value_type == BaseType::Long && atomic.width != AtomicWidth::I64;
+        let is_narrow = atomic.width.narrow_mask().is_some();
+        for (i, (ty, off)) in operand_types.iter().zip(&operand_offs).enumerate() {
+            self.jvm_code.get_local(*off, &FieldType::Base(*ty))?;
+            let is_value = match atomic.access {
+                AtomicAccess::Wait => i == 0,
+                AtomicAccess::Notify => false,
+                _ => true,
+            };
+            if is_value && narrow_long {
+                self.jvm_code.push_instruction(Instruction::L2I)?;
+            }
+            if atomic.access == AtomicAccess::Rmw(RmwOp::Sub) && !is_narrow {
+                let negate = match atomic.width.view_type() {
+                    BaseType::Long => Instruction::LNeg,
+                    _ => Instruction::INeg,
+                };
+                self.jvm_code.push_instruction(negate)?;
+            }
+        }
+        for _ in &operand_offs {
+            self.pop_temp_local()?;
+        }
+
+        let narrow_operation = match atomic.access {
+            AtomicAccess::Wait if atomic.width == AtomicWidth::I64 => Some(AtomicOperation::Wait64),
+            AtomicAccess::Wait => Some(AtomicOperation::Wait32),
+            AtomicAccess::Notify => Some(AtomicOperation::Notify),
+            _ if !is_narrow => None,
+            AtomicAccess::Load => Some(AtomicOperation::NarrowLoad(atomic.width)),
+            AtomicAccess::Store => Some(AtomicOperation::NarrowRmw(atomic.width, RmwOp::Xchg)),
+            AtomicAccess::Rmw(op) => Some(AtomicOperation::NarrowRmw(atomic.width, op)),
+            AtomicAccess::Cmpxchg => Some(AtomicOperation::NarrowCmpxchg(atomic.width)),
+        };
+
+        if let Some(operation) = narrow_operation {
+            if let AtomicAccess::Wait | AtomicAccess::Notify = atomic.access {
+                self.jvm_code
+                    .push_instruction(Instruction::ALoad(this_off))?;
+                memory.load_waiters(self.runtime, self.jvm_code)?;
+            }
+            self.utilities
+                .invoke_utility(UtilityMethod::Atomic(operation), self.jvm_code)?;
+            if atomic.access == AtomicAccess::Store {
+                self.jvm_code.push_instruction(Instruction::Pop)?;
+            }
+        } else {
+            let view = atomic.width.view_type();
+            let view_type = Some(FieldType::Base(view));
+            let (access_mode, operands, return_type) = match atomic.access {
+                AtomicAccess::Load => (UnqualifiedName::GETVOLATILE, 0, view_type),
+                AtomicAccess::Store => (UnqualifiedName::SETVOLATILE, 1, None),
+                AtomicAccess::Rmw(op) => (op.access_mode(), 1, view_type),
+                AtomicAccess::Cmpxchg => (UnqualifiedName::COMPAREANDEXCHANGE, 2, view_type),
+                AtomicAccess::Wait | AtomicAccess::Notify => unreachable!(),
+            };
+            let bootstrap = self.utilities.get_var_handle_bootstrap(
+                view,
+                self.jvm_code.java,
+                self.jvm_code.class_graph,
+            )?;
+            invoke_var_handle(
+                self.jvm_code,
+                bootstrap,
+                memory.backend.bytes_type(&self.jvm_code.java.classes),
+                view,
+                access_mode,
+                operands,
+                return_type,
+            )?;
+        }
+
+        // Zero-extend results that don't fill a `long`
+        if narrow_long && atomic.access != AtomicAccess::Store {
+            if is_narrow {
+                self.jvm_code.push_instruction(Instruction::I2L)?;
+            } else {
+                self.utilities
+                    .invoke_utility(UtilityMethod::I64ExtendI32U, self.jvm_code)?;
+            }
+        }
+
+        Ok(())
+    }
+
     fn visit_memory_init(&mut self, mem: u32, segment: u32) -> Result<(), Error> {
         let memory = &self.wasm_memories[mem as usize];
         let data = &self.wasm_datas[segment as usize];
         let this_off = self.jvm_locals.lookup_this()?.0;
+        let address_type = memory.address_type();
 
         // Put the length, source index, and destination index in variables
-        let len_off = self.jvm_locals.push_local(FieldType::int())?;
-        let src_off = self.jvm_locals.push_local(FieldType::int())?;
-        let dst_off = self.jvm_locals.push_local(FieldType::int())?;
+        let len_off = self.push_temp_local(FieldType::int())?;
+        let src_off = self.push_temp_local(FieldType::int())?;
+        let dst_off = self.jvm_locals.push_local(address_type)?;
         self.jvm_code
             .push_instruction(Instruction::IStore(len_off))?;
         self.jvm_code
             .push_instruction(Instruction::IStore(src_off))?;
-        self.jvm_code
-            .push_instruction(Instruction::IStore(dst_off))?;
+        self.jvm_code.set_local(dst_off, &address_type)?;
 
         memory.init(
             self.runtime,
@@ -1838,48 +3773,105 @@ impl<'a, 'b, 'g> FunctionTranslator<'a, 'b, 'g> {
         let dst_memory = &self.wasm_memories[dst_memory as usize];
         let this_off = self.jvm_locals.lookup_this()?.0;
 
+        match (src_memory.is_memory64(), dst_memory.is_memory64()) {
+            (false, false) => (),
+            (true, true) => {
+                // Put the length, source index, and destination index in variables
+                let len_off = self.push_temp_local(FieldType::long())?;
+                let src_off = self.push_temp_local(FieldType::long())?;
+                let dst_off = self.push_temp_local(FieldType::long())?;
+                self.jvm_code
+                    .push_instruction(Instruction::LStore(len_off))?;
+                self.jvm_code
+                    .push_instruction(Instruction::LStore(src_off))?;
+                self.jvm_code
+                    .push_instruction(Instruction::LStore(dst_off))?;
+
+                // `dstMemory.copy(dst, srcMemory, src, len)`
+                self.jvm_code
+                    .push_instruction(Instruction::ALoad(this_off))?;
+                self.jvm_code
+                    .access_field(dst_memory.field.unwrap(), AccessMode::Read)?;
+                self.jvm_code
+                    .push_instruction(Instruction::LLoad(dst_off))?;
+                self.jvm_code
+                    .push_instruction(Instruction::ALoad(this_off))?;
+                self.jvm_code
+                    .access_field(src_memory.field.unwrap(), AccessMode::Read)?;
+                self.jvm_code
+                    .push_instruction(Instruction::LLoad(src_off))?;
+                self.jvm_code
+                    .push_instruction(Instruction::LLoad(len_off))?;
+                self.jvm_code.invoke(self.runtime.members.memory64.copy)?;
+
+                // Clean up temporary locals
+                for _ in [dst_off, src_off, len_off] {
+                    self.pop_temp_local()?;
+                }
+
+                return Ok(());
+            }
+            _ => {
+                return Err(Error::Unsupported(
+                    "memory.copy between 32-bit and 64-bit memories",
+                ))
+            }
+        }
+
         // Number of entries to copy
-        let len_idx = self.jvm_locals.push_local(FieldType::int())?;
+        let len_idx = self.push_temp_local(FieldType::int())?;
         self.jvm_code
             .push_instruction(Instruction::IStore(len_idx))?;
 
         // Copy from this offset
-        let src_off_idx = self.jvm_locals.push_local(FieldType::int())?;
+        let src_off_idx = self.push_temp_local(FieldType::int())?;
         self.jvm_code
             .push_instruction(Instruction::IStore(src_off_idx))?;
 
         // Copy to this offset
-        let dst_off_idx = self.jvm_locals.push_local(FieldType::int())?;
+        let dst_off_idx = self.push_temp_local(FieldType::int())?;
         self.jvm_code
             .push_instruction(Instruction::IStore(dst_off_idx))?;
 
-        // System.arraycopy()
-        self.jvm_code
-            .push_instruction(Instruction::ALoad(this_off))?;
-        src_memory.load_bytebuffer(self.runtime, self.jvm_code)?;
-        self.jvm_code
-            .push_instruction(Instruction::ILoad(dst_off_idx))?;
-        self.jvm_code
-            .push_instruction(Instruction::ALoad(this_off))?;
-        dst_memory.load_bytebuffer(self.runtime, self.jvm_code)?;
-        self.jvm_code
-            .push_instruction(Instruction::ILoad(src_off_idx))?;
-        self.jvm_code
-            .push_instruction(Instruction::ILoad(len_idx))?;
-        self.jvm_code
-            .invoke(self.jvm_code.java.members.nio.byte_buffer.put_bytebuffer)?;
-        self.jvm_code.push_instruction(Instruction::Pop)?;
+        if let MemoryBackend::ByteArray = dst_memory.backend {
+            // System.arraycopy(srcBytes, srcOffset, dstBytes, dstOffset, length)
+            self.jvm_code
+                .push_instruction(Instruction::ALoad(this_off))?;
+            src_memory.load_bytes(self.runtime, self.jvm_code)?;
+            self.jvm_code
+                .push_instruction(Instruction::ILoad(src_off_idx))?;
+            self.jvm_code
+                .push_instruction(Instruction::ALoad(this_off))?;
+            dst_memory.load_bytes(self.runtime, self.jvm_code)?;
+            self.jvm_code
+                .push_instruction(Instruction::ILoad(dst_off_idx))?;
+            self.jvm_code
+                .push_instruction(Instruction::ILoad(len_idx))?;
+            self.jvm_code
+                .invoke(self.jvm_code.java.members.lang.system.arraycopy)?;
+        } else {
+            // dstBuffer.put(dstOffset, srcBuffer, srcOffset, length)
+            self.jvm_code
+                .push_instruction(Instruction::ALoad(this_off))?;
+            dst_memory.load_bytes(self.runtime, self.jvm_code)?;
+            self.jvm_code
+                .push_instruction(Instruction::ILoad(dst_off_idx))?;
+            self.jvm_code
+                .push_instruction(Instruction::ALoad(this_off))?;
+            src_memory.load_bytes(self.runtime, self.jvm_code)?;
+            self.jvm_code
+                .push_instruction(Instruction::ILoad(src_off_idx))?;
+            self.jvm_code
+                .push_instruction(Instruction::ILoad(len_idx))?;
+            self.jvm_code
+                .invoke(self.jvm_code.java.members.nio.byte_buffer.put_bytebuffer)?;
+            self.jvm_code.push_instruction(Instruction::Pop)?;
+        }
 
         // Clean up temporary locals
-        let _ = self.jvm_locals.pop_local()?;
-        let _ = self.jvm_locals.pop_local()?;
-        let _ = self.jvm_locals.pop_local()?;
-        self.jvm_code
-            .kill_top_local(dst_off_idx, Some(FieldType::int()))?;
-        self.jvm_code
-            .kill_top_local(src_off_idx, Some(FieldType::int()))?;
-        self.jvm_code
-            .kill_top_local(len_idx, Some(FieldType::int()))?;
+        self.pop_temp_local()?;
+        self.pop_temp_local()?;
+        self.pop_temp_local()?;
 
         Ok(())
     }
@@ -1890,7 +3882,16 @@ impl<'a, 'b, 'g> FunctionTranslator<'a, 'b, 'g> {
             parameters: vec![FieldType::int()],
             return_type: Some(FieldType::int()),
         };
+        let memory = &self.wasm_memories[memory_idx as usize];
+        if memory.is_memory64() {
+            let method = self.runtime.members.memory64.grow;
+            return self.visit_memory_method_operator(memory_idx, method);
+        } else if memory.memory_type.shared {
+            let method = self.runtime.members.memory.grow.unwrap();
+            return self.visit_memory_method_operator(memory_idx, method);
+        }
         self.visit_memory_operator(memory_idx, UnqualifiedName::MEMORYGROW, desc)?;
+        self.refresh_memory_local(memory_idx)?;
 
         Ok(())
     }
@@ -1901,6 +3902,10 @@ impl<'a, 'b, 'g> FunctionTranslator<'a, 'b, 'g> {
             parameters: vec![FieldType::int(), FieldType::int(), FieldType::int()],
             return_type: None,
         };
+        if self.wasm_memories[memory_idx as usize].is_memory64() {
+            let method = self.runtime.members.memory64.fill;
+            return self.visit_memory_method_operator(memory_idx, method);
+        }
         self.visit_memory_operator(memory_idx, UnqualifiedName::MEMORYFILL, desc)?;
 
         Ok(())
@@ -1912,6 +3917,14 @@ impl<'a, 'b, 'g> FunctionTranslator<'a, 'b, 'g> {
             parameters: vec![],
             return_type: Some(FieldType::int()),
         };
+        let memory = &self.wasm_memories[memory_idx as usize];
+        if memory.is_memory64() {
+            let method = self.runtime.members.memory64.size;
+            return self.visit_memory_method_operator(memory_idx, method);
+        } else if memory.memory_type.shared {
+            let method = self.runtime.members.memory.size.unwrap();
+            return self.visit_memory_method_operator(memory_idx, method);
+        }
         self.visit_memory_operator(memory_idx, UnqualifiedName::MEMORYSIZE, desc)?;
 
         Ok(())
@@ -1947,6 +3960,45 @@ impl<'a, 'b, 'g> FunctionTranslator<'a, 'b, 'g> {
 
         Ok(())
     }
+
+    /// Visit a memory operator by calling the corresponding method on the memory object
+    ///
+    /// This is an `org.wasm2jar.Memory64` for 64-bit memories, or an `org.wasm2jar.Memory` for
+    /// shared memories (whose size and growth are synchronized).
+    fn visit_memory_method_operator(
+        &mut self,
+        memory_idx: u32,
+        method: MethodId<'g>,
+    ) -> Result<(), Error> {
+        let memory = &self.wasm_memories[memory_idx as usize];
+        let this_off = self.jvm_locals.lookup_this()?.0;
+
+        // Stash the operands
+        let parameters = &method.descriptor.parameters;
+        let mut operand_offs = vec![];
+        for parameter in parameters.iter().rev() {
+            let off = self.push_temp_local(*parameter)?;
+            self.jvm_code.set_local(off, parameter)?;
+            operand_offs.push(off);
+        }
+        operand_offs.reverse();
+
+        // Load the memory and then the operands
+        self.jvm_code
+            .push_instruction(Instruction::ALoad(this_off))?;
+        self.jvm_code
+            .access_field(memory.field.unwrap(), AccessMode::Read)?;
+        for (parameter, off) in parameters.iter().zip(&operand_offs) {
+            self.jvm_code.get_local(*off, parameter)?;
+        }
+        for _ in &operand_offs {
+            self.pop_temp_local()?;
+        }
+
+        self.jvm_code.invoke(method)?;
+
+        Ok(())
+    }
 }
 
 // #[derive(Debug)]
@@ -1961,8 +4013,56 @@ struct LocalsLayout<'g> {
 
     /// Index into `jvm_locals` for getting the "this" argument
     jvm_module_idx: usize,
+
+    /// Index into `jvm_locals` of the `Object[]` argument carrying the inputs which didn't fit in
+    /// the JVM's parameter limit (those inputs then go in locals right after the module)
+    jvm_packed_inputs_idx: Option<usize>,
+
+    /// Index into `jvm_locals` of the first of the arrays of the frame shared with split methods
+    /// (the others follow in the order of `FrameArray::ALL`)
+    jvm_frame_idx: Option<usize>,
+
+    /// Where each WASM local is stored, once they've been allocated (see [`LocalsAllocation`])
+    ///
+    /// Locals which are never accessed have nowhere to be stored.
+    jvm_wasm_locals: Option<Vec<Option<WasmLocal>>>,
+
+    /// Index into `jvm_locals` of the `Object[]` holding the spilled WASM locals
+    jvm_spilled_idx: Option<usize>,
+
+    /// Offsets and types of the JVM locals shared by primitive WASM locals, along with the offset
+    /// of the last operator at which they are live
+    ///
+    /// Past that, temporary locals of the same type can reuse the slot. Only primitive types
+    /// qualify, since storing a reference of a more specific type would change the frame.
+    jvm_shared_slots: Vec<(u16, FieldType<ClassId<'g>>, usize)>,
+
+    /// Offset of the WASM operator being translated
+    wasm_offset: usize,
+
+    /// Temporary locals (see `push_temp_local`), along with whether they reuse a shared slot
+    jvm_temps: Vec<(u16, FieldType<ClassId<'g>>, bool)>,
 }
 
+/// Where the value of a WASM local is stored
+#[derive(Copy, Clone, Debug)]
+enum WasmLocal {
+    /// JVM local at this index into `jvm_locals`
+    Jvm(usize),
+
+    /// Element at this index in the array of spilled locals
+    Spilled(usize),
+
+    /// Element at this index in an array of the frame shared with split methods
+    Framed(FrameArray, u32),
+}
+
+/// Slots which WASM locals (other than parameters) can take up before they start getting spilled
+///
+/// This leaves room under the JVM's limit of 65535 slots for the locals the translator allocates
+/// after the WASM locals (for `try` blocks, cached memories, temporaries, etc.).
+const MAX_WASM_LOCAL_SLOTS: usize = u16::MAX as usize - 1024;
+
 impl<'g> LocalsLayout<'g> {
     fn new(
         method_arguments: impl Iterator<Item = FieldType<ClassId<'g>>>,
@@ -1974,9 +4074,26 @@ impl<'g> LocalsLayout<'g> {
         LocalsLayout {
             jvm_locals,
             jvm_module_idx,
+            jvm_packed_inputs_idx: None,
+            jvm_frame_idx: None,
+            jvm_wasm_locals: None,
+            jvm_spilled_idx: None,
+            jvm_shared_slots: vec![],
+            wasm_offset: 0,
+            jvm_temps: vec![],
         }
     }
 
+    /// Layout for a split method, whose arguments are the frame arrays and the module
+    fn new_split(module_typ: RefType<ClassId<'g>>, java: &JavaClasses<'g>) -> Self {
+        let frame_types = FrameArray::ALL
+            .into_iter()
+            .map(|array| array.field_type(java));
+        let mut layout = LocalsLayout::new(frame_types, module_typ);
+        layout.jvm_frame_idx = Some(0);
+        layout
+    }
+
     /// Lookup the JVM local and index associated with the "this" argument
     fn lookup_this(&self) -> Result<(u16, FieldType<ClassId<'g>>), Error> {
         let (off, field_type) = self
@@ -1986,18 +4103,68 @@ impl<'g> LocalsLayout<'g> {
         Ok((off.0 as u16, *field_type))
     }
 
+    /// Lookup the JVM local and type associated with the packed inputs argument
+    fn lookup_packed_inputs(&self) -> Result<(u16, FieldType<ClassId<'g>>), Error> {
+        let idx = self
+            .jvm_packed_inputs_idx
+            .expect("function has no packed inputs");
+        let (off, field_type) = self
+            .jvm_locals
+            .get_index(idx)
+            .expect("missing packed local");
+        Ok((off.0 as u16, *field_type))
+    }
+
     /// Lookup the JVM local and type associated with a WASM local index
     ///
-    /// Adjusts for the fact that JVM locals sometimes take two slots, and that there is an extra
-    /// local argument corresponding to the parameter that is used to pass around the module.
-    fn lookup_local(&self, mut local_idx: u32) -> Result<(u16, FieldType<ClassId<'g>>), Error> {
-        if local_idx as usize >= self.jvm_module_idx {
-            local_idx += 1;
+    /// Adjusts for the fact that JVM locals sometimes take two slots, and that there are extra
+    /// local arguments corresponding to the parameter that is used to pass around the module (and
+    /// to the packed inputs argument, if there is one). Fails if the WASM local didn't get a JVM
+    /// local of its own (because it got spilled or is part of the frame shared with split methods).
+    fn lookup_local(&self, local_idx: u32) -> Result<(u16, FieldType<ClassId<'g>>), Error> {
+        let idx = match self.lookup_wasm_local(local_idx) {
+            WasmLocal::Jvm(idx) => idx,
+            WasmLocal::Spilled(_) | WasmLocal::Framed(..) => return Err(Error::LocalsOverflow),
+        };
+        let (off, field_type) = self.jvm_locals.get_index(idx).expect("missing local");
+        Ok((off.0 as u16, *field_type))
+    }
+
+    /// Lookup where a WASM local is stored
+    fn lookup_wasm_local(&self, local_idx: u32) -> WasmLocal {
+        if let Some(wasm_locals) = &self.jvm_wasm_locals {
+            return wasm_locals[local_idx as usize].expect("local is never accessed");
         }
+        let unpacked_locals = self.jvm_packed_inputs_idx.unwrap_or(self.jvm_module_idx);
+        if local_idx as usize >= unpacked_locals {
+            WasmLocal::Jvm(local_idx as usize + self.jvm_module_idx + 1 - unpacked_locals)
+        } else {
+            WasmLocal::Jvm(local_idx as usize)
+        }
+    }
+
+    /// Lookup the JVM local and type of the array of spilled WASM locals
+    fn lookup_spilled(&self) -> Result<(u16, FieldType<ClassId<'g>>), Error> {
+        let idx = self
+            .jvm_spilled_idx
+            .expect("function has no spilled locals");
         let (off, field_type) = self
             .jvm_locals
-            .get_index(local_idx as usize)
-            .expect("missing local");
+            .get_index(idx)
+            .expect("missing spilled locals");
+        Ok((off.0 as u16, *field_type))
+    }
+
+    /// Lookup the JVM local and type of one of the arrays of the frame shared with split methods
+    fn lookup_frame_array(
+        &self,
+        array: FrameArray,
+    ) -> Result<(u16, FieldType<ClassId<'g>>), Error> {
+        let idx = self.jvm_frame_idx.expect("function has no split methods");
+        let (off, field_type) = self
+            .jvm_locals
+            .get_index(idx + array as usize)
+            .expect("missing frame array");
         Ok((off.0 as u16, *field_type))
     }
 
@@ -2016,4 +4183,80 @@ impl<'g> LocalsLayout<'g> {
             .map(|(offset, _, field_type)| (offset.0 as u16, field_type))
             .ok_or(Error::LocalsOverflow)
     }
+
+    /// Push a temporary local, reusing a slot of WASM locals which are no longer live if possible
+    fn push_temp_local(&mut self, field_type: FieldType<ClassId<'g>>) -> Result<u16, Error> {
+        let wasm_offset = self.wasm_offset;
+        let reusable = self
+            .jvm_shared_slots
+            .iter()
+            .take_while(|(_, _, live_until)| *live_until < wasm_offset)
+            .find(|(off, slot_type, _)| {
+                *slot_type == field_type && !self.jvm_temps.iter().any(|(temp, _, _)| temp == off)
+            })
+            .map(|(off, _, _)| *off);
+        let (offset, shared) = match reusable {
+            Some(offset) => (offset, true),
+            None => (self.push_local(field_type)?, false),
+        };
+        self.jvm_temps.push((offset, field_type, shared));
+        Ok(offset)
+    }
+
+    /// Pop the last temporary local pushed with `push_temp_local`
+    ///
+    /// The last part of the output is whether a slot was reused (in which case the local must not
+    /// be killed, since it is still part of the frame).
+    fn pop_temp_local(&mut self) -> Result<(u16, FieldType<ClassId<'g>>, bool), Error> {
+        let (offset, field_type, shared) = self.jvm_temps.pop().ok_or(Error::LocalsOverflow)?;
+        if !shared {
+            self.pop_local()?;
+        }
+        Ok((offset, field_type, shared))
+    }
+}
+
+/// Memory argument of a plain load or store (the accesses which can use the cached bytes of the
+/// memory)
+fn load_store_memarg(operator: &Operator) -> Option<MemArg> {
+    match operator {
+        Operator::I32Load { memarg }
+        | Operator::I64Load { memarg }
+        | Operator::F32Load { memarg }
+        | Operator::F64Load { memarg }
+        | Operator::I32Load8S { memarg }
+        | Operator::I32Load8U { memarg }
+        | Operator::I32Load16S { memarg }
+        | Operator::I32Load16U { memarg }
+        | Operator::I64Load8S { memarg }
+        | Operator::I64Load8U { memarg }
+        | Operator::I64Load16S { memarg }
+        | Operator::I64Load16U { memarg }
+        | Operator::I64Load32S { memarg }
+        | Operator::I64Load32U { memarg }
+        | Operator::V128Load { memarg }
+        | Operator::I32Store { memarg }
+        | Operator::I64Store { memarg }
+        | Operator::F32Store { memarg }
+        | Operator::F64Store { memarg }
+        | Operator::I32Store8 { memarg }
+        | Operator::I32Store16 { memarg }
+        | Operator::I64Store8 { memarg }
+        | Operator::I64Store16 { memarg }
+        | Operator::I64Store32 { memarg }
+        | Operator::V128Store { memarg } => Some(*memarg),
+        _ => None,
+    }
+}
+
+/// Types of the parameters of the method for a function type, as stack types
+///
+/// This is the unpacked inputs followed by the `Object[]` of packed inputs (if there is one),
+/// which is already a reference so it gets passed around like an `externref`.
+fn parameter_stack_types(func_typ: &FunctionType) -> Vec<StackType> {
+    let mut parameters = func_typ.inputs[..func_typ.unpacked_inputs()].to_vec();
+    if !func_typ.packed_inputs().is_empty() {
+        parameters.push(StackType::ExternRef);
+    }
+    parameters
 }
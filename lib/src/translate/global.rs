@@ -3,7 +3,7 @@ use crate::jvm::class_graph::{AccessMode, FieldId};
 use crate::jvm::code::{CodeBuilder, CodeBuilderExts, Instruction};
 use crate::jvm::Error;
 use crate::runtime::WasmRuntime;
-use crate::wasm::StackType;
+use crate::wasm::{v128_ref_type, StackType};
 use wasmparser::ConstExpr;
 
 /// Translated global variable
@@ -66,7 +66,7 @@ impl<'a, 'g> Global<'a, 'g> {
                     StackType::I64 => code.invoke(code.java.members.lang.long.value_of)?,
                     StackType::F32 => code.invoke(code.java.members.lang.float.value_of)?,
                     StackType::F64 => code.invoke(code.java.members.lang.double.value_of)?,
-                    StackType::FuncRef | StackType::ExternRef => (),
+                    StackType::V128 | StackType::FuncRef | StackType::ExternRef => (),
                 }
                 code.push_instruction(Instruction::Swap)?;
                 code.access_field(self.field.unwrap(), AccessMode::Read)?;
@@ -103,6 +103,9 @@ impl<'a, 'g> Global<'a, 'g> {
                         code.checkcast(code.java.classes.lang.double)?;
                         code.invoke(code.java.members.lang.number.double_value)?;
                     }
+                    StackType::V128 => {
+                        code.push_instruction(Instruction::CheckCast(v128_ref_type()))?;
+                    }
                     StackType::FuncRef => {
                         code.checkcast(code.java.classes.lang.invoke.method_handle)?;
                     }
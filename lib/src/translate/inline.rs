@@ -0,0 +1,350 @@
+//! Inlining of small leaf functions
+//!
+//! Compilers targeting WASM leave behind lots of tiny functions (getters, wrappers, etc.) and
+//! calls to them would otherwise only get inlined by the JIT, which won't see through calls from
+//! one part class to another. Instead, calls to small enough functions which don't call anything
+//! themselves are replaced by the body of the callee before the caller gets translated:
+//!
+//!   * the parameters and locals of the callee become new locals of the caller, so the translator
+//!     allocates them like any other local (and they get to share JVM locals, see the `locals`
+//!     module)
+//!   * the arguments are popped off the stack into the parameter locals, and the locals of the
+//!     callee which might be read before being written get zeroed
+//!   * the body is wrapped in a `block` whose result is the result of the callee, with `return`
+//!     becoming a branch out of that `block`
+//!
+//! Inlined operators all get the offset of the `call` they replace.
+
+use super::{written_before_read, Error, OffsetOperator, PartReferences};
+use crate::wasm::FunctionType;
+use std::collections::HashMap;
+use std::mem;
+use wasmparser::{BlockType, FuncValidator, FunctionBody, Operator, ValType, WasmModuleResources};
+
+/// Maximum number of locals in a function (including parameters) accepted by the validator
+const MAX_WASM_FUNCTION_LOCALS: usize = 50_000;
+
+/// Body of a function which can be inlined into its callers
+pub struct InlineBody<'a, 'g> {
+    /// Types of the parameters
+    pub inputs: Vec<ValType>,
+
+    /// Type of the result, if there is one
+    pub output: Option<ValType>,
+
+    /// Types of the declared locals (after the parameters)
+    pub locals: Vec<ValType>,
+
+    /// Operators of the body, including the final `end`
+    pub operators: Vec<OffsetOperator<'a>>,
+
+    /// Declared locals which might be read before they are written
+    pub zeroed_locals: Vec<u32>,
+
+    /// Entities referenced by the body, which callers also end up referencing
+    pub references: PartReferences<'g>,
+}
+
+impl<'a, 'g> InlineBody<'a, 'g> {
+    /// Read the body of a function, if it is small enough and simple enough to be inlined
+    ///
+    /// Only functions with at most `max_operators` operators (and as many locals) and at most one
+    /// result are inlined. They also can't contain calls (so inlining never needs to be repeated
+    /// and recursion is not a concern) or `try` blocks.
+    pub fn from_function_body(
+        func_type: &FunctionType,
+        function_body: &FunctionBody<'a>,
+        max_operators: usize,
+    ) -> Result<Option<InlineBody<'a, 'g>>, Error> {
+        if func_type.outputs.len() > 1 {
+            return Ok(None);
+        }
+
+        let mut locals = vec![];
+        for local in function_body.get_locals_reader()? {
+            let (count, local_type) = local?;
+            if locals.len() + count as usize > max_operators {
+                return Ok(None);
+            }
+            locals.extend(std::iter::repeat(local_type).take(count as usize));
+        }
+
+        // The last operator is the `end` of the function, which doesn't count
+        let mut operators = vec![];
+        for operator in function_body
+            .get_operators_reader()?
+            .into_iter_with_offsets()
+        {
+            let (operator, offset) = operator?;
+            match operator {
+                Operator::Call { .. }
+                | Operator::CallIndirect { .. }
+                | Operator::ReturnCall { .. }
+                | Operator::ReturnCallIndirect { .. }
+                | Operator::Try { .. }
+                | Operator::Catch { .. }
+                | Operator::CatchAll
+                | Operator::Delegate { .. }
+                | Operator::Rethrow { .. } => return Ok(None),
+                _ => (),
+            }
+            if operators.len() > max_operators {
+                return Ok(None);
+            }
+            operators.push((operator, offset));
+        }
+
+        let inputs: Vec<ValType> = func_type.inputs.iter().map(|ty| ty.to_general()).collect();
+        let written_first =
+            written_before_read(&operators, inputs.len() + locals.len(), inputs.len());
+        let zeroed_locals = (inputs.len()..written_first.len())
+            .filter(|local_idx| !written_first[*local_idx])
+            .map(|local_idx| local_idx as u32)
+            .collect();
+
+        Ok(Some(InlineBody {
+            inputs,
+            output: func_type.outputs.first().map(|ty| ty.to_general()),
+            locals,
+            operators,
+            zeroed_locals,
+            references: PartReferences::from_function_body(function_body)?,
+        }))
+    }
+
+    /// Number of locals (including parameters) which the body adds to its callers
+    fn local_count(&self) -> usize {
+        self.inputs.len() + self.locals.len()
+    }
+
+    /// Type of a local of the body
+    fn local_type(&self, local_idx: u32) -> ValType {
+        let local_idx = local_idx as usize;
+        if local_idx < self.inputs.len() {
+            self.inputs[local_idx]
+        } else {
+            self.locals[local_idx - self.inputs.len()]
+        }
+    }
+}
+
+/// Replace calls to functions which can be inlined with the bodies of those functions
+///
+/// The caller already has `local_count` locals (including parameters). The types of the locals
+/// added for the inlined bodies are returned, and must be declared to the validator after the
+/// caller's own locals (see [`read_locals`]).
+pub fn inline_calls<'a>(
+    operators: &mut Vec<OffsetOperator<'a>>,
+    local_count: u32,
+    inline_bodies: &HashMap<u32, InlineBody<'a, '_>>,
+) -> Vec<ValType> {
+    let mut inlined_locals: Vec<ValType> = vec![];
+    for (operator, offset) in mem::take(operators) {
+        let body = match operator {
+            Operator::Call { function_index } => inline_bodies.get(&function_index),
+            _ => None,
+        };
+        let base_local = local_count + inlined_locals.len() as u32;
+        let body = match body {
+            Some(body) if base_local as usize + body.local_count() <= MAX_WASM_FUNCTION_LOCALS => {
+                body
+            }
+            _ => {
+                operators.push((operator, offset));
+                continue;
+            }
+        };
+        inlined_locals.extend(&body.inputs);
+        inlined_locals.extend(&body.locals);
+
+        // Arguments are on the stack with the last one on top
+        for local_idx in (0..body.inputs.len() as u32).rev() {
+            let local_index = base_local + local_idx;
+            operators.push((Operator::LocalSet { local_index }, offset));
+        }
+        for local_idx in &body.zeroed_locals {
+            for zero in zero_value(body.local_type(*local_idx)) {
+                operators.push((zero, offset));
+            }
+            let local_index = base_local + local_idx;
+            operators.push((Operator::LocalSet { local_index }, offset));
+        }
+
+        // The `block` takes the place of the function frame (so the final `end` closes it)
+        let blockty = match body.output {
+            Some(output) => BlockType::Type(output),
+            None => BlockType::Empty,
+        };
+        operators.push((Operator::Block { blockty }, offset));
+        let mut depth = 0;
+        for (operator, _) in &body.operators {
+            let operator = match operator {
+                Operator::LocalGet { local_index } => Operator::LocalGet {
+                    local_index: base_local + local_index,
+                },
+                Operator::LocalSet { local_index } => Operator::LocalSet {
+                    local_index: base_local + local_index,
+                },
+                Operator::LocalTee { local_index } => Operator::LocalTee {
+                    local_index: base_local + local_index,
+                },
+                Operator::Return => Operator::Br {
+                    relative_depth: depth,
+                },
+                Operator::Block { .. } | Operator::Loop { .. } | Operator::If { .. } => {
+                    depth += 1;
+                    operator.clone()
+                }
+                Operator::End if depth > 0 => {
+                    depth -= 1;
+                    Operator::End
+                }
+                operator => operator.clone(),
+            };
+            operators.push((operator, offset));
+        }
+    }
+    inlined_locals
+}
+
+/// Operators pushing the zero value of a type
+///
+/// There is no way to construct float or vector constants, so those are built from integers.
+fn zero_value<'a>(ty: ValType) -> Vec<Operator<'a>> {
+    match ty {
+        ValType::I32 => vec![Operator::I32Const { value: 0 }],
+        ValType::I64 => vec![Operator::I64Const { value: 0 }],
+        ValType::F32 => vec![Operator::I32Const { value: 0 }, Operator::F32ReinterpretI32],
+        ValType::F64 => vec![Operator::I64Const { value: 0 }, Operator::F64ReinterpretI64],
+        ValType::V128 => vec![Operator::I32Const { value: 0 }, Operator::I32x4Splat],
+        ValType::FuncRef | ValType::ExternRef => vec![Operator::RefNull { ty }],
+    }
+}
+
+/// Declare the locals of a function body to a validator, followed by the locals added for the
+/// functions inlined into it
+pub fn read_locals<T: WasmModuleResources>(
+    function_body: &FunctionBody,
+    inlined_locals: &[ValType],
+    validator: &mut FuncValidator<T>,
+) -> Result<(), Error> {
+    let mut reader = function_body.get_binary_reader();
+    validator.read_locals(&mut reader)?;
+    let offset = reader.original_position();
+    for local_type in inlined_locals {
+        validator.define_locals(offset, 1, *local_type)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::translate::read_operators;
+    use crate::translate::test_util::{
+        assemble, first_function_operators, function_bodies, render_operators,
+    };
+    use crate::wasm::StackType;
+
+    #[test]
+    fn inline_leaf_function() {
+        let wasm = assemble(
+            "(module
+              (func $caller (param i32) (result i32) (local i64)
+                local.get 0 local.get 1 call $callee
+                local.get 0 local.get 1 call $callee
+                i32.add call $caller)
+              (func $callee (param i32 i64) (result i32) (local f32 i32 f64)
+                local.get 0
+                if
+                  block local.get 3 return end
+                end
+                f32.const 0 local.set 2 local.get 2 drop
+                local.get 4 drop
+                local.get 1 i32.wrap_i64))",
+        );
+        let function_bodies = function_bodies(&wasm);
+        let func_type = FunctionType {
+            inputs: vec![StackType::I32, StackType::I64],
+            outputs: vec![StackType::I32],
+        };
+        let body = InlineBody::from_function_body(&func_type, &function_bodies[1], 100)
+            .unwrap()
+            .unwrap();
+        assert_eq!(body.zeroed_locals, vec![3, 4]);
+
+        let mut operators = read_operators(&function_bodies[0]).unwrap();
+        let inlined_locals = inline_calls(&mut operators, 2, &HashMap::from([(1, body)]));
+        assert_eq!(
+            inlined_locals,
+            [
+                ValType::I32,
+                ValType::I64,
+                ValType::F32,
+                ValType::I32,
+                ValType::F64
+            ]
+            .repeat(2),
+        );
+
+        // Locals of the callee are moved past those of the caller (and the previous inlined call)
+        let inlined = |base: u32| {
+            format!(
+                "local.set {arg1} local.set {arg0}
+                i32.const 0 local.set {i32}
+                i64.const 0 f64.reinterpret_i64 local.set {f64}
+                block (result i32)
+                  local.get {arg0}
+                  if
+                    block local.get {i32} br 2 end
+                  end
+                  f32.const 0 local.set {f32} local.get {f32} drop
+                  local.get {f64} drop
+                  local.get {arg1} i32.wrap_i64
+                end",
+                arg0 = base,
+                arg1 = base + 1,
+                f32 = base + 2,
+                i32 = base + 3,
+                f64 = base + 4,
+            )
+        };
+        let expected_wasm = assemble(&format!(
+            "(module (func local.get 0 local.get 1 {} local.get 0 local.get 1 {} i32.add call 0))",
+            inlined(2),
+            inlined(7),
+        ));
+        let expected_operators = first_function_operators(&expected_wasm);
+        assert_eq!(
+            render_operators(&operators),
+            render_operators(&expected_operators)
+        );
+    }
+
+    #[test]
+    fn inline_only_small_leaf_functions() {
+        let wasm = assemble(
+            "(module
+              (func)
+              (func call 0)
+              (func try nop catch_all end)
+              (func nop nop nop nop))",
+        );
+        let func_type = FunctionType {
+            inputs: vec![],
+            outputs: vec![],
+        };
+        let inlinable = |function_body: &FunctionBody, max_operators: usize| {
+            InlineBody::from_function_body(&func_type, function_body, max_operators)
+                .unwrap()
+                .is_some()
+        };
+        let function_bodies = function_bodies(&wasm);
+        assert!(inlinable(&function_bodies[0], 0));
+        assert!(!inlinable(&function_bodies[1], 100));
+        assert!(!inlinable(&function_bodies[2], 100));
+        assert!(inlinable(&function_bodies[3], 4));
+        assert!(!inlinable(&function_bodies[3], 3));
+    }
+}
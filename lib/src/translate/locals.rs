@@ -0,0 +1,571 @@
+//! Sharing of JVM local slots between WASM locals
+//!
+//! Giving every WASM local its own JVM local makes frames needlessly large (and big functions
+//! can run out of locals altogether). Instead, each WASM local gets a live range over the offsets
+//! of the operators, and locals of the same type whose ranges don't overlap share a slot.
+//!
+//! Since every slot gets zero-initialized on entry (so that frames are the same everywhere), a
+//! local can only move into a slot previously used by another local if it never reads the
+//! implicit zero value, meaning it is always written before being read. Locals which don't pass
+//! that check (and the parameters) are always the first local in their slot.
+//!
+//! Live ranges are conservative:
+//!
+//!   * they start at the first access (or the start of the function for parameters) and end at
+//!     the last access
+//!   * a range overlapping a `loop` without containing it is extended to cover the whole loop,
+//!     since the value might be carried over to the next iteration
+//!
+//! Locals accessed in split methods live in the frame shared with those methods instead (see the
+//! `split` module), so they don't get a JVM local at all.
+
+use super::OffsetOperator;
+use crate::util::Width;
+use crate::wasm::StackType;
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, BinaryHeap, HashMap};
+use wasmparser::Operator;
+
+/// JVM local shared by WASM locals of the same type
+#[derive(Debug)]
+pub struct SharedLocal {
+    /// Type of the WASM locals
+    pub local_type: StackType,
+
+    /// WASM locals using the slot
+    pub locals: Vec<u32>,
+
+    /// Offset of the last operator at which any of the WASM locals are live
+    pub live_until: usize,
+}
+
+/// Assignment of the WASM locals of a function to JVM locals
+#[derive(Debug)]
+pub struct LocalsAllocation {
+    /// Shared JVM locals, starting with one for each parameter (in order)
+    pub shared: Vec<SharedLocal>,
+
+    /// WASM locals which didn't fit in the slot budget, and get boxed into an array instead
+    pub spilled: Vec<u32>,
+}
+
+impl LocalsAllocation {
+    /// Allocate JVM locals for the WASM locals of a function
+    ///
+    /// The parameters are always allocated (they are the arguments of the method) while the
+    /// other locals are spilled once they would need more than `max_slots` slots. Locals which
+    /// are never accessed (or which are in `framed_locals`) don't get a JVM local at all.
+    pub fn new(
+        operators: &[OffsetOperator],
+        local_types: &[StackType],
+        parameter_count: usize,
+        framed_locals: &BTreeSet<u32>,
+        max_slots: usize,
+    ) -> LocalsAllocation {
+        let mut ranges: Vec<Option<(usize, usize)>> = vec![None; local_types.len()];
+        for range in ranges.iter_mut().take(parameter_count) {
+            *range = Some((0, 0));
+        }
+        let mut loops: Vec<(usize, usize)> = vec![];
+        let mut open_loops: Vec<Option<usize>> = vec![];
+        for (operator, offset) in operators {
+            let offset = *offset;
+            match operator {
+                Operator::LocalGet { local_index }
+                | Operator::LocalSet { local_index }
+                | Operator::LocalTee { local_index }
+                    if !framed_locals.contains(local_index) =>
+                {
+                    let range = &mut ranges[*local_index as usize];
+                    *range = match *range {
+                        Some((start, end)) => Some((start.min(offset), end.max(offset))),
+                        None => Some((offset, offset)),
+                    };
+                }
+                Operator::Loop { .. } => open_loops.push(Some(offset)),
+                Operator::Block { .. } | Operator::If { .. } | Operator::Try { .. } => {
+                    open_loops.push(None)
+                }
+                Operator::End | Operator::Delegate { .. } => {
+                    if let Some(Some(start)) = open_loops.pop() {
+                        loops.push((start, offset));
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        // Extend ranges over the loops they partially overlap
+        for (start, end) in ranges.iter_mut().flatten() {
+            let mut changed = true;
+            while changed {
+                changed = false;
+                for (ext_start, ext_end) in &loops {
+                    let overlaps = *ext_start <= *end && *start <= *ext_end;
+                    let contains = *start <= *ext_start && *ext_end <= *end;
+                    if overlaps && !contains {
+                        *start = (*start).min(*ext_start);
+                        *end = (*end).max(*ext_end);
+                        changed = true;
+                    }
+                }
+            }
+        }
+
+        let written_first = written_before_read(operators, local_types.len(), parameter_count);
+
+        // Parameters come first, then locals get greedily assigned to the free slot of their type
+        // which has been free the longest
+        let mut shared: Vec<SharedLocal> = vec![];
+        let mut spilled = vec![];
+        let mut free: HashMap<StackType, BinaryHeap<Reverse<(usize, usize)>>> = HashMap::new();
+        let mut slots_used = 0;
+        let mut locals: Vec<(usize, usize, u32)> = ranges
+            .iter()
+            .enumerate()
+            .skip(parameter_count)
+            .filter_map(|(local_idx, range)| {
+                range.map(|(start, end)| (start, end, local_idx as u32))
+            })
+            .collect();
+        locals.sort_unstable();
+        let parameters = ranges
+            .iter()
+            .take(parameter_count)
+            .enumerate()
+            .map(|(local_idx, range)| (0, range.map_or(0, |(_, end)| end), local_idx as u32));
+        for (is_parameter, (start, end, local_idx)) in parameters
+            .map(|local| (true, local))
+            .chain(locals.into_iter().map(|local| (false, local)))
+        {
+            let local_type = local_types[local_idx as usize];
+            let free_slots = free.entry(local_type).or_default();
+
+            let reusable = match free_slots.peek() {
+                Some(Reverse((live_until, _))) => {
+                    !is_parameter && written_first[local_idx as usize] && *live_until < start
+                }
+                None => false,
+            };
+            let shared_idx = if reusable {
+                let Reverse((_, shared_idx)) = free_slots.pop().unwrap();
+                shared[shared_idx].locals.push(local_idx);
+                shared[shared_idx].live_until = end;
+                shared_idx
+            } else if is_parameter || slots_used + local_type.width() <= max_slots {
+                if !is_parameter {
+                    slots_used += local_type.width();
+                }
+                shared.push(SharedLocal {
+                    local_type,
+                    locals: vec![local_idx],
+                    live_until: end,
+                });
+                shared.len() - 1
+            } else {
+                spilled.push(local_idx);
+                continue;
+            };
+            free_slots.push(Reverse((end, shared_idx)));
+        }
+
+        LocalsAllocation { shared, spilled }
+    }
+}
+
+/// Control frame which is open while looking for reads of unassigned locals
+struct AssignedFrame {
+    /// Which operator opened the frame
+    kind: FrameKind,
+
+    /// Locals definitely assigned when entering the frame
+    entry: Vec<bool>,
+
+    /// Locals definitely assigned at every branch to the end of the frame so far
+    branches: Option<Vec<bool>>,
+
+    /// Locals definitely assigned at the end of the `then` arm of an `if`
+    then_end: Option<Vec<bool>>,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum FrameKind {
+    Block,
+
+    /// Branches to a loop go to its start, so they don't affect what is assigned after it
+    Loop,
+
+    /// Without an `else`, the end of an `if` can also be reached by skipping the `then` arm
+    If,
+
+    /// Clauses of a `try` can be entered from anywhere in its body
+    Try,
+}
+
+/// Find which locals are always written before they are read (so they never see the implicit
+/// zero value of locals)
+///
+/// This is a forward analysis of the locals which are definitely assigned at each operator. Since
+/// a local can't become unassigned, a loop is entered with the locals assigned before it and the
+/// clauses of a `try` with the locals assigned before the `try`.
+pub fn written_before_read(
+    operators: &[OffsetOperator],
+    local_count: usize,
+    parameter_count: usize,
+) -> Vec<bool> {
+    fn intersect(into: &mut [bool], other: &[bool]) {
+        for (assigned, other_assigned) in into.iter_mut().zip(other) {
+            *assigned &= *other_assigned;
+        }
+    }
+
+    let mut written_first = vec![true; local_count];
+    let mut assigned: Vec<bool> = (0..local_count).map(|idx| idx < parameter_count).collect();
+    let mut frames: Vec<AssignedFrame> = vec![AssignedFrame {
+        kind: FrameKind::Block,
+        entry: assigned.clone(),
+        branches: None,
+        then_end: None,
+    }];
+
+    // Branches past the outermost frame leave the operators (eg. those of a split method)
+    let branch_to = |frames: &mut Vec<AssignedFrame>, assigned: &[bool], relative_depth: u32| {
+        let frame_idx = match frames.len().checked_sub(relative_depth as usize + 1) {
+            Some(frame_idx) => frame_idx,
+            None => return,
+        };
+        let frame = &mut frames[frame_idx];
+        if frame.kind != FrameKind::Loop {
+            match &mut frame.branches {
+                Some(branches) => intersect(branches, assigned),
+                None => frame.branches = Some(assigned.to_vec()),
+            }
+        }
+    };
+
+    for (operator, _) in operators {
+        match operator {
+            Operator::LocalGet { local_index } if !assigned[*local_index as usize] => {
+                written_first[*local_index as usize] = false;
+            }
+            Operator::LocalSet { local_index } | Operator::LocalTee { local_index } => {
+                assigned[*local_index as usize] = true;
+            }
+            Operator::Block { .. }
+            | Operator::Loop { .. }
+            | Operator::If { .. }
+            | Operator::Try { .. } => {
+                let kind = match operator {
+                    Operator::Loop { .. } => FrameKind::Loop,
+                    Operator::If { .. } => FrameKind::If,
+                    Operator::Try { .. } => FrameKind::Try,
+                    _ => FrameKind::Block,
+                };
+                frames.push(AssignedFrame {
+                    kind,
+                    entry: assigned.clone(),
+                    branches: None,
+                    then_end: None,
+                })
+            }
+            Operator::Else => {
+                let frame = frames.last_mut().expect("`else` outside of `if`");
+                frame.then_end = Some(std::mem::replace(&mut assigned, frame.entry.clone()));
+            }
+            Operator::Catch { .. } | Operator::CatchAll => {
+                let frame = frames.last().expect("`catch` outside of `try`");
+                assigned = frame.entry.clone();
+            }
+            Operator::End | Operator::Delegate { .. } => {
+                if frames.len() == 1 {
+                    continue;
+                }
+                let frame = frames.pop().unwrap();
+                if frame.kind == FrameKind::Try {
+                    assigned = frame.entry;
+                    continue;
+                }
+                if let Some(branches) = &frame.branches {
+                    intersect(&mut assigned, branches);
+                }
+                match (&frame.then_end, frame.kind) {
+                    (Some(then_end), _) => intersect(&mut assigned, then_end),
+                    (None, FrameKind::If) => intersect(&mut assigned, &frame.entry),
+                    (None, _) => (),
+                }
+            }
+            Operator::Br { relative_depth } => {
+                branch_to(&mut frames, &assigned, *relative_depth);
+                assigned = vec![true; local_count];
+            }
+            Operator::BrIf { relative_depth } => {
+                branch_to(&mut frames, &assigned, *relative_depth);
+            }
+            Operator::BrTable { targets } => {
+                for target in targets
+                    .targets()
+                    .chain(std::iter::once(Ok(targets.default())))
+                {
+                    let relative_depth = target.expect("invalid `br_table` target");
+                    branch_to(&mut frames, &assigned, relative_depth);
+                }
+                assigned = vec![true; local_count];
+            }
+            Operator::Return
+            | Operator::Unreachable
+            | Operator::Throw { .. }
+            | Operator::Rethrow { .. }
+            | Operator::ReturnCall { .. }
+            | Operator::ReturnCallIndirect { .. } => {
+                assigned = vec![true; local_count];
+            }
+            _ => (),
+        }
+    }
+
+    written_first
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::translate::test_util::{assemble, first_function_operators};
+
+    /// Module with just one function, given everything after `func` in its definition
+    fn function(definition: &str) -> Vec<u8> {
+        assemble(&format!("(module (func {}))", definition))
+    }
+
+    /// Check which of the `local_count` locals are written before being read
+    fn check_written_first(definition: &str, parameter_count: usize, expected: &[bool]) {
+        let wasm = function(definition);
+        assert_eq!(
+            written_before_read(
+                &first_function_operators(&wasm),
+                expected.len(),
+                parameter_count
+            ),
+            expected,
+            "{}",
+            definition,
+        );
+    }
+
+    #[test]
+    fn read_before_write() {
+        check_written_first(
+            "(param i32) (local i32 i32 i32)
+            local.get 0 local.set 1 local.get 1 local.get 2 i32.add drop",
+            1,
+            &[true, true, false, true],
+        );
+    }
+
+    #[test]
+    fn written_on_some_paths() {
+        for (definition, written_first) in [
+            (
+                "local.get 0 if i32.const 1 local.set 1 end local.get 1 drop",
+                false,
+            ),
+            (
+                "local.get 0 if i32.const 1 local.set 1 else nop end local.get 1 drop",
+                false,
+            ),
+            (
+                "local.get 0 if i32.const 1 local.set 1 else i32.const 2 local.set 1 end
+                local.get 1 drop",
+                true,
+            ),
+            (
+                "local.get 0 if i32.const 1 local.set 1 else unreachable end local.get 1 drop",
+                true,
+            ),
+            (
+                "block local.get 0 br_if 0 i32.const 1 local.set 1 end local.get 1 drop",
+                false,
+            ),
+            (
+                "block i32.const 1 local.set 1 local.get 0 br_if 0 end local.get 1 drop",
+                true,
+            ),
+            (
+                "block block local.get 0 br_table 0 1 end i32.const 1 local.set 1 end
+                local.get 1 drop",
+                false,
+            ),
+            ("block br 0 local.get 1 drop end", true),
+            (
+                "try i32.const 1 local.set 1 catch_all i32.const 2 local.set 1 end
+                local.get 1 drop",
+                false,
+            ),
+        ] {
+            check_written_first(
+                &format!("(param i32) (local i32) {}", definition),
+                1,
+                &[true, written_first],
+            );
+        }
+    }
+
+    #[test]
+    fn loop_carried_read() {
+        check_written_first(
+            "(param i32) (local i32)
+            loop local.get 1 drop i32.const 1 local.set 1 local.get 0 br_if 0 end",
+            1,
+            &[true, false],
+        );
+        check_written_first(
+            "(param i32) (local i32)
+            loop i32.const 1 local.set 1 local.get 1 drop local.get 0 br_if 0 end",
+            1,
+            &[true, true],
+        );
+    }
+
+    /// Allocate the locals of a function, then summarize the allocation as the locals in each JVM
+    /// local and the spilled locals
+    fn allocate(
+        definition: &str,
+        local_types: &[StackType],
+        parameter_count: usize,
+        framed_locals: &[u32],
+        max_slots: usize,
+    ) -> (Vec<Vec<u32>>, Vec<u32>) {
+        let wasm = function(definition);
+        let allocation = LocalsAllocation::new(
+            &first_function_operators(&wasm),
+            local_types,
+            parameter_count,
+            &framed_locals.iter().copied().collect(),
+            max_slots,
+        );
+        let shared = allocation
+            .shared
+            .into_iter()
+            .map(|shared| shared.locals)
+            .collect();
+        (shared, allocation.spilled)
+    }
+
+    #[test]
+    fn share_disjoint_locals() {
+        // The parameter is never read, so its JVM local is free from the start
+        let definition = "(param i32) (local i32 i32 i64)
+            i32.const 1 local.set 1 local.get 1 drop
+            i32.const 2 local.set 2 local.get 2 drop
+            i64.const 3 local.set 3 local.get 3 drop";
+        assert_eq!(
+            allocate(
+                definition,
+                &[
+                    StackType::I32,
+                    StackType::I32,
+                    StackType::I32,
+                    StackType::I64
+                ],
+                1,
+                &[],
+                100
+            ),
+            (vec![vec![0, 1, 2], vec![3]], vec![]),
+        );
+
+        // The parameter and the local read before being written each need a fresh JVM local
+        let definition = "(param i32) (local i32 i32)
+            local.get 0 drop
+            local.get 1 drop
+            i32.const 2 local.set 2 local.get 2 drop";
+        assert_eq!(
+            allocate(definition, &[StackType::I32; 3], 1, &[], 100),
+            (vec![vec![0, 2], vec![1]], vec![]),
+        );
+    }
+
+    #[test]
+    fn extend_over_loops() {
+        // Local 0 is read on the next iteration, after local 1 has been written
+        let body = "i32.const 1 local.set 1
+            LOOP
+              local.get 1 drop
+              i32.const 2 local.set 2 local.get 2 drop
+              local.get 0 br_if 0
+            end";
+        let definition = |body: &str| format!("(param i32) (local i32 i32) {}", body);
+        assert_eq!(
+            allocate(
+                &definition(&body.replace("LOOP", "loop")),
+                &[StackType::I32; 3],
+                1,
+                &[],
+                100
+            ),
+            (vec![vec![0], vec![1], vec![2]], vec![]),
+        );
+        assert_eq!(
+            allocate(
+                &definition(&body.replace("LOOP", "block")),
+                &[StackType::I32; 3],
+                1,
+                &[],
+                100
+            ),
+            (vec![vec![0], vec![1, 2]], vec![]),
+        );
+    }
+
+    #[test]
+    fn skip_framed_locals() {
+        // Local 2 takes over the slot of local 1, unless local 1 lives in the frame
+        let definition = "(param i32) (local i32 i32)
+            local.get 1 drop
+            i32.const 2 local.set 2 local.get 2 drop
+            local.get 0 drop";
+        assert_eq!(
+            allocate(definition, &[StackType::I32; 3], 1, &[], 100),
+            (vec![vec![0], vec![1, 2]], vec![]),
+        );
+        assert_eq!(
+            allocate(definition, &[StackType::I32; 3], 1, &[1], 100),
+            (vec![vec![0], vec![2]], vec![]),
+        );
+
+        // Framed locals don't count towards the budget either
+        assert_eq!(
+            allocate(definition, &[StackType::I32; 3], 1, &[1], 1),
+            (vec![vec![0], vec![2]], vec![]),
+        );
+    }
+
+    #[test]
+    fn spill_past_max_slots() {
+        let definition = "(param i64) (local i32 i64 i32 f32)
+            local.get 1 local.get 2 local.get 3 local.get 4
+            drop drop drop drop
+            i32.const 1 local.set 3 local.get 3 drop";
+        let local_types = [
+            StackType::I64,
+            StackType::I32,
+            StackType::I64,
+            StackType::I32,
+            StackType::F32,
+        ];
+
+        // Parameters don't count towards the budget, and local 3 still fits after local 2 didn't
+        assert_eq!(
+            allocate(definition, &local_types, 1, &[], 5),
+            (vec![vec![0], vec![1], vec![2], vec![3], vec![4]], vec![]),
+        );
+        assert_eq!(
+            allocate(definition, &local_types, 1, &[], 2),
+            (vec![vec![0], vec![1], vec![3]], vec![2, 4]),
+        );
+        assert_eq!(
+            allocate(definition, &local_types, 1, &[], 0),
+            (vec![vec![0]], vec![1, 2, 3, 4]),
+        );
+    }
+}
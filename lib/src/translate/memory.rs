@@ -1,7 +1,10 @@
-use super::{Data, ExportName, ImportName};
-use crate::jvm::class_graph::{AccessMode, FieldId};
+use super::{
+    invoke_var_handle, Data, Error, ExportName, ImportName, MemoryBackend, UtilityClass,
+    UtilityMethod,
+};
+use crate::jvm::class_graph::{AccessMode, ClassId, FieldId, JavaClasses};
 use crate::jvm::code::{CodeBuilder, CodeBuilderExts, Instruction};
-use crate::jvm::{BaseType, Error, FieldType};
+use crate::jvm::{BaseType, FieldType, UnqualifiedName};
 use crate::runtime::WasmRuntime;
 use crate::util::Width;
 use wasmparser::{MemArg, MemoryType};
@@ -25,6 +28,12 @@ pub struct Memory<'a, 'g> {
 
     /// If the memory is exported, this holds the export information
     pub export: Vec<ExportName<'a>>,
+
+    /// What holds the bytes of the memory (unless it is a 64-bit memory)
+    pub backend: MemoryBackend,
+
+    /// Whether adding static offsets to addresses should trap on overflow
+    pub trap_address_overflow: bool,
 }
 
 /// Representation of a memory in a module
@@ -35,6 +44,43 @@ pub enum MemoryRepr {
 
     /// Memory uses an internal field
     Internal,
+
+    /// Memory uses a `org.wasm2jar.Memory64` field
+    ///
+    /// This is always the case for memories with 64-bit addresses, since those can exceed the
+    /// size of a single `ByteBuffer`.
+    Memory64,
+}
+
+/// Where a memory access gets the memory from
+#[derive(Copy, Clone, Debug)]
+pub enum MemorySource {
+    /// Read the memory off of the module object in this local
+    Module(u16),
+
+    /// The bytes of the memory (see [`Memory::load_bytes`]) are already cached in this local
+    ///
+    /// This is never the case for 64-bit memories.
+    CachedBytes(u16),
+}
+
+/// Where a memory access gets the memory from, along with its static offset and alignment
+#[derive(Copy, Clone, Debug)]
+pub struct MemoryAccess {
+    pub source: MemorySource,
+    pub memarg: MemArg,
+}
+
+impl MemoryBackend {
+    /// Type of the bytes of a memory
+    pub fn bytes_type<'g>(&self, java: &JavaClasses<'g>) -> FieldType<ClassId<'g>> {
+        match self {
+            MemoryBackend::HeapByteBuffer | MemoryBackend::DirectByteBuffer => {
+                FieldType::object(java.nio.byte_buffer)
+            }
+            MemoryBackend::ByteArray => FieldType::array(FieldType::byte()),
+        }
+    }
 }
 
 impl<'a, 'g> Memory<'a, 'g> {
@@ -45,17 +91,196 @@ impl<'a, 'g> Memory<'a, 'g> {
             .is_none()
     }
 
-    /// Load the memory bytebuffer onto the stack
+    pub fn is_memory64(&self) -> bool {
+        matches!(self.repr, MemoryRepr::Memory64)
+    }
+
+    /// Type of addresses into the memory
+    pub fn address_type<C>(&self) -> FieldType<C> {
+        if self.is_memory64() {
+            FieldType::long()
+        } else {
+            FieldType::int()
+        }
+    }
+
+    /// Load the bytes of the memory (a `ByteBuffer` or `byte[]`, depending on the backend) onto
+    /// the stack
     ///
     /// Assumes the stack starts with having the main WASM module object on it
-    pub fn load_bytebuffer(
+    pub fn load_bytes(
+        &self,
+        runtime: &WasmRuntime<'g>,
+        code: &mut CodeBuilder<'g>,
+    ) -> Result<(), Error> {
+        code.access_field(self.field.unwrap(), AccessMode::Read)?;
+        match self.repr {
+            MemoryRepr::External => {
+                code.access_field(runtime.members.memory.bytes, AccessMode::Read)?;
+            }
+            MemoryRepr::Internal => (),
+            MemoryRepr::Memory64 => panic!("64-bit memories are not a single set of bytes"),
+        }
+        Ok(())
+    }
+
+    /// Load the bytes of the memory onto the stack, from wherever the source says they are
+    fn load_bytes_from(
+        &self,
+        runtime: &WasmRuntime<'g>,
+        code: &mut CodeBuilder<'g>,
+        source: MemorySource,
+    ) -> Result<(), Error> {
+        match source {
+            MemorySource::Module(this_off) => {
+                code.push_instruction(Instruction::ALoad(this_off))?;
+                self.load_bytes(runtime, code)?;
+            }
+            MemorySource::CachedBytes(bytes_off) => {
+                code.get_local(bytes_off, &self.backend.bytes_type(&code.java.classes))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Load the `org.wasm2jar.Memory64` object underneath the address on top of the stack
+    fn load_memory64_under_address(
+        &self,
+        code: &mut CodeBuilder<'g>,
+        source: MemorySource,
+    ) -> Result<(), Error> {
+        let this_off = match source {
+            MemorySource::Module(this_off) => this_off,
+            MemorySource::CachedBytes(_) => panic!("64-bit memories are not cached"),
+        };
+        code.push_instruction(Instruction::ALoad(this_off))?;
+        code.access_field(self.field.unwrap(), AccessMode::Read)?;
+        code.push_instruction(Instruction::DupX2)?;
+        code.push_instruction(Instruction::Pop)?;
+        Ok(())
+    }
+
+    /// Add the static offset of a memory access to the address on top of the stack
+    ///
+    /// The sum is computed as an unsigned integer, so (unless `trap_address_overflow` is off) an
+    /// overflow throws an `IndexOutOfBoundsException` instead of wrapping around.
+    pub fn add_offset(
+        &self,
+        code: &mut CodeBuilder<'g>,
+        utilities: &mut UtilityClass<'g>,
+        memarg: MemArg,
+    ) -> Result<(), Error> {
+        if memarg.offset == 0 {
+            return Ok(());
+        }
+        if self.is_memory64() {
+            code.const_long(memarg.offset as i64)?;
+            if self.trap_address_overflow {
+                utilities.invoke_utility(UtilityMethod::EffectiveAddress64, code)?;
+            } else {
+                code.push_instruction(Instruction::LAdd)?;
+            }
+        } else {
+            // Offsets into 32-bit memories fit in 32 bits, though not necessarily in an `int`
+            code.const_int(memarg.offset as u32 as i32)?;
+            if self.trap_address_overflow {
+                utilities.invoke_utility(UtilityMethod::EffectiveAddress, code)?;
+            } else {
+                code.push_instruction(Instruction::IAdd)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Load the map of threads waiting on addresses in the memory onto the stack
+    ///
+    /// Assumes the stack starts with having the main WASM module object on it. Only memories with
+    /// an external representation track waiters.
+    pub fn load_waiters(
         &self,
         runtime: &WasmRuntime<'g>,
         code: &mut CodeBuilder<'g>,
     ) -> Result<(), Error> {
+        assert!(
+            matches!(self.repr, MemoryRepr::External),
+            "only external memories track waiters"
+        );
         code.access_field(self.field.unwrap(), AccessMode::Read)?;
-        if let MemoryRepr::External = self.repr {
-            code.access_field(runtime.members.memory.bytes, AccessMode::Read)?;
+        code.access_field(runtime.members.memory.waiters, AccessMode::Read)?;
+        Ok(())
+    }
+
+    /// Read a value out of the bytes of the memory
+    ///
+    /// Assumes the top of the stack is the address, followed by the bytes of the memory
+    fn get(
+        &self,
+        code: &mut CodeBuilder<'g>,
+        utilities: &mut UtilityClass<'g>,
+        ty: BaseType,
+    ) -> Result<(), Error> {
+        match self.backend {
+            MemoryBackend::HeapByteBuffer | MemoryBackend::DirectByteBuffer => {
+                let get_func = match ty {
+                    BaseType::Byte => code.java.members.nio.byte_buffer.get_byte,
+                    BaseType::Short => code.java.members.nio.byte_buffer.get_short,
+                    BaseType::Int => code.java.members.nio.byte_buffer.get_int,
+                    BaseType::Float => code.java.members.nio.byte_buffer.get_float,
+                    BaseType::Long => code.java.members.nio.byte_buffer.get_long,
+                    BaseType::Double => code.java.members.nio.byte_buffer.get_double,
+                    t => panic!("Cannot get {:?}", t),
+                };
+                code.invoke(get_func)?;
+            }
+            MemoryBackend::ByteArray if ty == BaseType::Byte => {
+                code.push_instruction(Instruction::BALoad)?;
+            }
+            MemoryBackend::ByteArray => {
+                let bootstrap =
+                    utilities.get_var_handle_bootstrap(ty, code.java, code.class_graph)?;
+                let bytes_type = self.backend.bytes_type(&code.java.classes);
+                let return_type = Some(FieldType::Base(ty));
+                let access_mode = UnqualifiedName::GET;
+                invoke_var_handle(code, bootstrap, bytes_type, ty, access_mode, 0, return_type)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write a value into the bytes of the memory
+    ///
+    /// Assumes the top of the stack is the value, followed by the address, followed by the bytes
+    /// of the memory
+    fn put(
+        &self,
+        code: &mut CodeBuilder<'g>,
+        utilities: &mut UtilityClass<'g>,
+        ty: BaseType,
+    ) -> Result<(), Error> {
+        match self.backend {
+            MemoryBackend::HeapByteBuffer | MemoryBackend::DirectByteBuffer => {
+                let put_func = match ty {
+                    BaseType::Byte => code.java.members.nio.byte_buffer.put_byte,
+                    BaseType::Short => code.java.members.nio.byte_buffer.put_short,
+                    BaseType::Int => code.java.members.nio.byte_buffer.put_int,
+                    BaseType::Float => code.java.members.nio.byte_buffer.put_float,
+                    BaseType::Long => code.java.members.nio.byte_buffer.put_long,
+                    BaseType::Double => code.java.members.nio.byte_buffer.put_double,
+                    t => panic!("Cannot store {:?}", t),
+                };
+                code.invoke(put_func)?;
+                code.push_instruction(Instruction::Pop)?;
+            }
+            MemoryBackend::ByteArray if ty == BaseType::Byte => {
+                code.push_instruction(Instruction::BAStore)?;
+            }
+            MemoryBackend::ByteArray => {
+                let bootstrap =
+                    utilities.get_var_handle_bootstrap(ty, code.java, code.class_graph)?;
+                let bytes_type = self.backend.bytes_type(&code.java.classes);
+                let access_mode = UnqualifiedName::SET;
+                invoke_var_handle(code, bootstrap, bytes_type, ty, access_mode, 1, None)?;
+            }
         }
         Ok(())
     }
@@ -67,32 +292,25 @@ impl<'a, 'g> Memory<'a, 'g> {
         &self,
         runtime: &WasmRuntime<'g>,
         code: &mut CodeBuilder<'g>,
-        this_off: u16,
-        memarg: MemArg,
+        utilities: &mut UtilityClass<'g>,
+        access: MemoryAccess,
         ty: BaseType,
     ) -> Result<(), Error> {
-        // Adjust the offset
-        if memarg.offset != 0 {
-            code.const_int(memarg.offset as i32)?; // TODO: overflow
-            code.push_instruction(Instruction::IAdd)?;
+        let MemoryAccess { source, memarg } = access;
+        self.add_offset(code, utilities, memarg)?;
+
+        if self.is_memory64() {
+            self.load_memory64_under_address(code, source)?;
+            code.invoke(runtime.members.memory64.get(ty))?;
+            return Ok(());
         }
 
         // Load the memory
-        code.push_instruction(Instruction::ALoad(this_off))?;
-        self.load_bytebuffer(runtime, code)?;
+        self.load_bytes_from(runtime, code, source)?;
 
-        // Re-order the stack and call the get function
+        // Re-order the stack and get the value
         code.push_instruction(Instruction::Swap)?;
-        let get_func = match ty {
-            BaseType::Byte => code.java.members.nio.byte_buffer.get_byte,
-            BaseType::Short => code.java.members.nio.byte_buffer.get_short,
-            BaseType::Int => code.java.members.nio.byte_buffer.get_int,
-            BaseType::Float => code.java.members.nio.byte_buffer.get_float,
-            BaseType::Long => code.java.members.nio.byte_buffer.get_long,
-            BaseType::Double => code.java.members.nio.byte_buffer.get_double,
-            t => panic!("Cannot get {:?}", t),
-        };
-        code.invoke(get_func)?;
+        self.get(code, utilities, ty)?;
 
         Ok(())
     }
@@ -104,15 +322,29 @@ impl<'a, 'g> Memory<'a, 'g> {
         &self,
         runtime: &WasmRuntime<'g>,
         code: &mut CodeBuilder<'g>,
-        this_off: u16,
+        utilities: &mut UtilityClass<'g>,
+        access: MemoryAccess,
         temp_off: u16,
-        memarg: MemArg,
         ty: BaseType,
     ) -> Result<(), Error> {
+        let MemoryAccess { source, memarg } = access;
+        if self.is_memory64() {
+            // Stash the value being stored
+            code.set_local(temp_off, &FieldType::Base(ty))?;
+            self.add_offset(code, utilities, memarg)?;
+
+            // Load the memory and re-order the stack
+            self.load_memory64_under_address(code, source)?;
+            code.get_local(temp_off, &FieldType::Base(ty))?;
+            code.kill_top_local(temp_off, None)?;
+
+            code.invoke(runtime.members.memory64.put(ty))?;
+            return Ok(());
+        }
+
         if ty.width() == 1 && memarg.offset == 0 {
             // Load the memory
-            code.push_instruction(Instruction::ALoad(this_off))?;
-            self.load_bytebuffer(runtime, code)?;
+            self.load_bytes_from(runtime, code, source)?;
 
             // Re-order the stack
             code.push_instruction(Instruction::DupX2)?;
@@ -121,15 +353,10 @@ impl<'a, 'g> Memory<'a, 'g> {
             // Stash the value being stored
             code.set_local(temp_off, &FieldType::Base(ty))?;
 
-            // Adjust the offset
-            if memarg.offset != 0 {
-                code.const_int(memarg.offset as i32)?; // TODO: overflow
-                code.push_instruction(Instruction::IAdd)?;
-            }
+            self.add_offset(code, utilities, memarg)?;
 
             // Load the memory
-            code.push_instruction(Instruction::ALoad(this_off))?;
-            self.load_bytebuffer(runtime, code)?;
+            self.load_bytes_from(runtime, code, source)?;
 
             // Re-order the stack
             code.push_instruction(Instruction::Swap)?;
@@ -137,27 +364,130 @@ impl<'a, 'g> Memory<'a, 'g> {
             code.kill_top_local(temp_off, None)?;
         }
 
-        // Call the store function
-        let put_func = match ty {
-            BaseType::Byte => code.java.members.nio.byte_buffer.put_byte,
-            BaseType::Short => code.java.members.nio.byte_buffer.put_short,
-            BaseType::Int => code.java.members.nio.byte_buffer.put_int,
-            BaseType::Float => code.java.members.nio.byte_buffer.put_float,
-            BaseType::Long => code.java.members.nio.byte_buffer.put_long,
-            BaseType::Double => code.java.members.nio.byte_buffer.put_double,
-            t => panic!("Cannot store {:?}", t),
-        };
-        code.invoke(put_func)?;
-        code.push_instruction(Instruction::Pop)?;
+        self.put(code, utilities, ty)?;
+
+        Ok(())
+    }
+
+    /// Load a `v128` from memory onto the stack, as its low and high `long` halves
+    ///
+    /// Assumes the top of the stack is the offset into the memory
+    pub fn load_v128(
+        &self,
+        runtime: &WasmRuntime<'g>,
+        code: &mut CodeBuilder<'g>,
+        utilities: &mut UtilityClass<'g>,
+        access: MemoryAccess,
+    ) -> Result<(), Error> {
+        let MemoryAccess { source, memarg } = access;
+        self.add_offset(code, utilities, memarg)?;
+
+        if self.is_memory64() {
+            let get_long = runtime.members.memory64.get_long;
+            code.push_instruction(Instruction::Dup2)?;
+
+            // Low half
+            self.load_memory64_under_address(code, source)?;
+            code.invoke(get_long)?;
+
+            // High half
+            code.push_instruction(Instruction::Dup2X2)?;
+            code.push_instruction(Instruction::Pop2)?;
+            code.const_long(8)?;
+            code.push_instruction(Instruction::LAdd)?;
+            self.load_memory64_under_address(code, source)?;
+            code.invoke(get_long)?;
+
+            return Ok(());
+        }
+
+        code.push_instruction(Instruction::Dup)?;
+
+        // Low half
+        self.load_bytes_from(runtime, code, source)?;
+        code.push_instruction(Instruction::Swap)?;
+        self.get(code, utilities, BaseType::Long)?;
+
+        // High half
+        code.push_instruction(Instruction::Dup2X1)?;
+        code.push_instruction(Instruction::Pop2)?;
+        code.const_int(8)?;
+        code.push_instruction(Instruction::IAdd)?;
+        self.load_bytes_from(runtime, code, source)?;
+        code.push_instruction(Instruction::Swap)?;
+        self.get(code, utilities, BaseType::Long)?;
+
+        Ok(())
+    }
+
+    /// Store a `v128` from the stack to memory
+    ///
+    /// Assumes the top of the stack is the `v128` followed by the offset into the memory. The
+    /// temporary local must have room for the `v128`.
+    pub fn store_v128(
+        &self,
+        runtime: &WasmRuntime<'g>,
+        code: &mut CodeBuilder<'g>,
+        utilities: &mut UtilityClass<'g>,
+        access: MemoryAccess,
+        temp_off: u16,
+    ) -> Result<(), Error> {
+        let MemoryAccess { source, memarg } = access;
+        // Stash the value being stored
+        code.push_instruction(Instruction::AStore(temp_off))?;
+        self.add_offset(code, utilities, memarg)?;
+
+        if self.is_memory64() {
+            for half in 0..2 {
+                if half == 0 {
+                    code.push_instruction(Instruction::Dup2)?;
+                } else {
+                    code.const_long(8)?;
+                    code.push_instruction(Instruction::LAdd)?;
+                }
+
+                // Load the memory and re-order the stack
+                self.load_memory64_under_address(code, source)?;
+
+                // Store the half
+                code.push_instruction(Instruction::ALoad(temp_off))?;
+                code.const_int(half)?;
+                code.push_instruction(Instruction::LALoad)?;
+                code.invoke(runtime.members.memory64.put_long)?;
+            }
+            code.kill_top_local(temp_off, None)?;
+
+            return Ok(());
+        }
+
+        for half in 0..2 {
+            if half == 0 {
+                code.push_instruction(Instruction::Dup)?;
+            } else {
+                code.const_int(8)?;
+                code.push_instruction(Instruction::IAdd)?;
+            }
+
+            // Load the memory and re-order the stack
+            self.load_bytes_from(runtime, code, source)?;
+            code.push_instruction(Instruction::Swap)?;
+
+            // Store the half
+            code.push_instruction(Instruction::ALoad(temp_off))?;
+            code.const_int(half)?;
+            code.push_instruction(Instruction::LALoad)?;
+            self.put(code, utilities, BaseType::Long)?;
+        }
+        code.kill_top_local(temp_off, None)?;
 
         Ok(())
     }
 
     /// Initialize memory from a data segment
     ///
-    /// Assumes the top of the stack is the number of bytes to intiialize, followed by an offset
-    /// into the data segment from which to start copying, followed by an offset in memory where to
-    /// start writing.
+    /// Assumes the number of bytes to intiialize, the offset into the data segment from which to
+    /// start copying, and the offset in memory where to start writing are all in locals (the last
+    /// one having the memory's `address_type`).
     pub fn init(
         &self,
         runtime: &WasmRuntime<'g>,
@@ -168,9 +498,37 @@ impl<'a, 'g> Memory<'a, 'g> {
         dst_off: u16,
         data: &Data<'a, 'g>,
     ) -> Result<(), Error> {
+        if self.is_memory64() {
+            // `memory.write(dst, wasm_elem(), src, len)`
+            code.push_instruction(Instruction::ALoad(this_off))?;
+            code.access_field(self.field.unwrap(), AccessMode::Read)?;
+            code.push_instruction(Instruction::LLoad(dst_off))?;
+            code.push_instruction(Instruction::ALoad(this_off))?;
+            code.invoke(data.method)?;
+            code.push_instruction(Instruction::ILoad(src_off))?;
+            code.push_instruction(Instruction::ILoad(len_off))?;
+            code.invoke(runtime.members.memory64.write)?;
+
+            return Ok(());
+        }
+
+        if let MemoryBackend::ByteArray = self.backend {
+            // `System.arraycopy(wasm_elem(), src, memory_bytes, dst, len)`
+            code.push_instruction(Instruction::ALoad(this_off))?;
+            code.invoke(data.method)?;
+            code.push_instruction(Instruction::ILoad(src_off))?;
+            code.push_instruction(Instruction::ALoad(this_off))?;
+            self.load_bytes(runtime, code)?;
+            code.push_instruction(Instruction::ILoad(dst_off))?;
+            code.push_instruction(Instruction::ILoad(len_off))?;
+            code.invoke(code.java.members.lang.system.arraycopy)?;
+
+            return Ok(());
+        }
+
         // Load the memory
         code.push_instruction(Instruction::ALoad(this_off))?;
-        self.load_bytebuffer(runtime, code)?;
+        self.load_bytes(runtime, code)?;
 
         // `memory_bytebuffer.put(dst, wasm_elem(), src, len)`
         code.push_instruction(Instruction::ILoad(dst_off))?;
@@ -1,28 +1,52 @@
+mod atomic;
 mod data;
 mod element;
 mod errors;
 mod function;
 mod global;
+mod inline;
+mod locals;
 mod memory;
 mod module;
+mod optimize;
+mod part;
+mod reachability;
 mod renamer;
 mod settings;
+mod simd;
+mod split;
+mod ssa;
 mod table;
+mod tag;
+mod tail_call;
+#[cfg(test)]
+mod test_util;
 mod utility;
 
+pub use atomic::*;
 pub use data::*;
 pub use element::*;
 pub use errors::*;
 pub use function::*;
 pub use global::*;
+pub use inline::*;
+pub use locals::*;
 pub use memory::*;
 pub use module::*;
+pub use optimize::*;
+pub use part::*;
+pub use reachability::*;
 pub use renamer::*;
 pub use settings::*;
+pub use simd::*;
+pub use split::*;
+pub use ssa::*;
 pub use table::*;
+pub use tag::*;
+pub use tail_call::*;
 pub use utility::*;
 
-use crate::jvm::class_graph::{FieldId, MethodId};
+use crate::jvm::class_graph::{ConstantData, FieldId, MethodId};
 use crate::jvm::code::{CodeBuilder, CodeBuilderExts, Instruction};
 use crate::jvm::RefType;
 use crate::runtime::WasmRuntime;
@@ -34,19 +58,29 @@ pub struct Function<'a, 'g> {
     /// Function type
     pub func_type: FunctionType,
 
+    /// Index of the function type in the type section
+    pub type_idx: u32,
+
     /// Method in the class containing the implementation
     ///
     /// Note: the method will have an "adapted" signature, meaning there is always one final
     /// argument that is the module itself. In addition, it should always be a static method.
     pub method: MethodId<'g>,
 
-    /// If the function contains a `return_call` or `return_call_indirect`, this is the method that
-    /// should be used when the function is itself used in a tail call.
+    /// When tail calls are trampolined, this is the method that should be used when the function
+    /// is itself used in a tail call.
     ///
     /// It has the same signature as `method` except that the return value will either be the
-    /// (boxed) return value of `method` or a thunk to evaluate.
+    /// (boxed) return value of `method` or a thunk to evaluate. If the function contains a
+    /// `return_call` or `return_call_indirect`, this is where its body gets translated (and
+    /// `method` just trampolines it). Otherwise, this is an adapter around `method` that only
+    /// gets generated if the function is the target of some `return_call`.
     pub tailcall_method: Option<MethodId<'g>>,
 
+    /// Whether the body of the function is translated into `tailcall_method` (because it
+    /// contains tail calls)
+    pub trampolined: bool,
+
     /// If the function is imported, this contains the name under which it is imported along with
     /// the field (on the main WASM object) holding the method handle.
     pub import: Option<(ImportName<'a>, FieldId<'g>)>,
@@ -59,6 +93,53 @@ pub struct Function<'a, 'g> {
     pub export: Vec<(ExportName<'a>, bool)>,
 }
 
+impl<'a, 'g> Function<'a, 'g> {
+    /// Push a reference to the function onto the stack
+    ///
+    /// This is a handle to `method` with the module bound as the last argument. For trampolined
+    /// functions, the handle is made out of `tailcall_method` instead (using
+    /// `TailCall.trampolined`), so that a `return_call_indirect` can call that directly instead
+    /// of nesting trampolines.
+    pub fn load_reference(
+        &self,
+        runtime: &WasmRuntime<'g>,
+        code: &mut CodeBuilder<'g>,
+        wasm_module_variable_off: u16,
+    ) -> Result<(), Error> {
+        // `TailCall.trampolined(tailCallHandle, module, type)`
+        if let Some(tailcall_method) = self.tailcall_method.filter(|_| self.trampolined) {
+            let func_type = self
+                .func_type
+                .method_descriptor(&code.java.classes, &runtime.tuples);
+            code.const_methodhandle(tailcall_method)?;
+            code.push_instruction(Instruction::ALoad(wasm_module_variable_off))?;
+            code.push_instruction(Instruction::Ldc(ConstantData::MethodType(func_type)))?;
+            code.invoke(runtime.members.tail_call.trampolined)?;
+            return Ok(());
+        }
+
+        // `MethodHandles.insertArguments(method, n, new Object[1] { module })`
+        code.const_methodhandle(self.method)?;
+        code.const_int(self.func_type.parameter_count() as i32)?;
+        code.const_int(1)?;
+        code.new_ref_array(RefType::Object(code.java.classes.lang.object))?;
+        code.dup()?;
+        code.const_int(0)?;
+        code.push_instruction(Instruction::ALoad(wasm_module_variable_off))?;
+        code.push_instruction(Instruction::AAStore)?;
+        code.invoke(
+            code.java
+                .members
+                .lang
+                .invoke
+                .method_handles
+                .insert_arguments,
+        )?;
+
+        Ok(())
+    }
+}
+
 #[derive(Debug)]
 pub struct ImportName<'a> {
     /// Name of the module from which the entity is imported
@@ -89,27 +170,16 @@ fn translate_const_expr<'a, 'g>(
             Operator::I64Const { value } => code.const_long(value)?,
             Operator::F32Const { value } => code.const_float(f32::from_bits(value.bits()))?,
             Operator::F64Const { value } => code.const_double(f64::from_bits(value.bits()))?,
+            Operator::V128Const { value } => const_v128(code, value.i128() as u128)?,
             Operator::RefNull { ty } => {
                 let ref_type = ref_type_from_general(ty, &code.java.classes)?;
                 code.const_null(ref_type)?;
             }
             Operator::RefFunc { function_index } => {
-                let function = &functions[function_index as usize];
-                code.const_methodhandle(function.method)?;
-                code.const_int(function.func_type.inputs.len() as i32)?;
-                code.const_int(1)?;
-                code.new_ref_array(RefType::Object(code.java.classes.lang.object))?;
-                code.dup()?;
-                code.const_int(0)?;
-                code.push_instruction(Instruction::ALoad(wasm_module_variable_off))?;
-                code.push_instruction(Instruction::AAStore)?;
-                code.invoke(
-                    code.java
-                        .members
-                        .lang
-                        .invoke
-                        .method_handles
-                        .insert_arguments,
+                functions[function_index as usize].load_reference(
+                    runtime,
+                    code,
+                    wasm_module_variable_off,
                 )?;
             }
             Operator::End => (),
@@ -117,10 +187,13 @@ fn translate_const_expr<'a, 'g>(
                 code.push_instruction(Instruction::ALoad(wasm_module_variable_off))?;
                 globals[global_index as usize].read(runtime, code)?;
             }
-            other => todo!(
-                "figure out which other expressions and valid, then rule out the rest {:?}",
-                other
-            ),
+            Operator::I32Add => code.push_instruction(Instruction::IAdd)?,
+            Operator::I32Sub => code.push_instruction(Instruction::ISub)?,
+            Operator::I32Mul => code.push_instruction(Instruction::IMul)?,
+            Operator::I64Add => code.push_instruction(Instruction::LAdd)?,
+            Operator::I64Sub => code.push_instruction(Instruction::LSub)?,
+            Operator::I64Mul => code.push_instruction(Instruction::LMul)?,
+            _ => return Err(Error::Unsupported("operator in constant expression")),
         }
     }
 
@@ -1,34 +1,45 @@
 use super::{
-    BootstrapUtilities, Data, Element, Error, ExportName, Function, FunctionTranslator, Global,
-    GlobalRepr, ImportName, Memory, MemoryRepr, Settings, Table, TableRepr, UtilityClass,
+    element_item_references, find_split_regions, generate_forwarding_method,
+    generate_tailcall_adapter, generate_trampoline, inline_calls, optimize_operators,
+    read_operators, validate_operators, Data, DataResource, DataStorage, Element, Error,
+    ExportName, Function, FunctionInput, FunctionTranslator, Global, GlobalRepr, ImportName,
+    InlineBody, Memory, MemoryBackend, MemoryRepr, ModuleContext, Part, PartReferences, PartUsage,
+    Reachability, Reachable, Settings, SplitAnalysis, SplitFunction, SplitRegion, SplitSpan,
+    SsaFunction, Table, TableRepr, Tag, TailCallStrategy, UtilityClass, UtilityMethod,
+    MAX_CODE_LENGTH,
 };
 use crate::jvm;
 use crate::jvm::class_file;
 use crate::jvm::class_graph::{
     AccessMode, ClassData, ClassGraph, ClassId, ConstantData, FieldData, JavaLibrary, MethodData,
-    NestedClassData,
+    MethodId, NestedClassData,
 };
 use crate::jvm::code::{
-    BranchInstruction, CodeBuilder, CodeBuilderExts, EqComparison, Instruction, OrdComparison,
+    BranchInstruction, Code, CodeBuilder, CodeBuilderExts, EqComparison, Instruction, OrdComparison,
 };
 use crate::jvm::model::{Class, Field, Method};
 use crate::jvm::{
-    BinaryName, ClassAccessFlags, FieldAccessFlags, FieldType, InnerClassAccessFlags,
+    BaseType, BinaryName, ClassAccessFlags, FieldAccessFlags, FieldType, InnerClassAccessFlags,
     MethodAccessFlags, MethodDescriptor, Name, RefType, UnqualifiedName,
 };
 use crate::runtime::{
-    make_function_class, make_function_table_class, make_global_class, make_memory_class,
-    make_reference_table_class, WasmRuntime,
+    make_function_class, make_function_table_class, make_global_class, make_memory64_class,
+    make_memory_class, make_reference_table_class, make_tag_class, make_tail_call_class,
+    make_tuple_class, make_wasm_exception_class, WasmRuntime,
 };
-use crate::util::Width;
+use crate::util::{zlib_compress, Offset, Width};
 use crate::wasm::{FunctionType, StackType, TableType};
+use std::cmp::Reverse;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
 use std::iter;
 use wasmparser::types::Types;
 use wasmparser::{
     ConstExpr, DataKind, DataSectionReader, ElementKind, ElementSectionReader, ExportSectionReader,
-    ExternalKind, FuncValidatorAllocations, FunctionBody, FunctionSectionReader,
-    GlobalSectionReader, Import, ImportSectionReader, MemorySectionReader, Parser, Payload,
-    TableSectionReader, Type, TypeRef, TypeSectionReader, Validator,
+    ExternalKind, FuncToValidate, FuncValidatorAllocations, FunctionBody, FunctionSectionReader,
+    GlobalSectionReader, Import, ImportSectionReader, MemorySectionReader, MemoryType, Operator,
+    Parser, Payload, TableSectionReader, TagSectionReader, Type, TypeRef, TypeSectionReader,
+    Validator, ValidatorResources,
 };
 
 /// Main entry point for translating a WASM module
@@ -37,12 +48,13 @@ pub struct ModuleTranslator<'a, 'g> {
     validator: Validator,
     func_validator_allocations: Option<FuncValidatorAllocations>,
     class: Class<'g>,
-    previous_parts: Vec<Class<'g>>,
+    previous_parts: Vec<Part<'g>>,
     fields_generated: bool,
+    functions_declared: bool,
     class_graph: &'g ClassGraph<'g>,
     java: &'g JavaLibrary<'g>,
     runtime: WasmRuntime<'g>,
-    current_part: CurrentPart<'g>,
+    current_part: Part<'g>,
 
     /// Populated when visiting the start section with the index of the "start" function. Note that
     /// modules are not required to have a start function (so this may remain empty).
@@ -57,6 +69,31 @@ pub struct ModuleTranslator<'a, 'g> {
     /// Populated when we visit functions
     functions: Vec<Function<'a, 'g>>,
 
+    /// Type indices of functions from the function section
+    ///
+    /// These only get added to `functions` once all function bodies have been seen, since the
+    /// part in which a function is declared depends on its body (see `declare_functions`).
+    function_declarations: Vec<u32>,
+
+    /// Exported functions, which get applied to `functions` in `declare_functions`
+    function_exports: Vec<(u32, ExportName<'a>)>,
+
+    /// Number of entries in the code section
+    function_bodies_count: usize,
+
+    /// Function bodies seen so far (translated once they've all been seen)
+    function_bodies: Vec<(FunctionBody<'a>, FuncToValidate<ValidatorResources>)>,
+
+    /// Bodies of the functions which get inlined into their callers, keyed by function index
+    inline_bodies: HashMap<u32, InlineBody<'a, 'g>>,
+
+    /// Functions and globals which get translated (computed in `declare_functions`)
+    reachability: Reachability,
+
+    /// Functions with the same type and body as an earlier function, mapped to the index of that
+    /// function (whose methods they share instead of getting their own)
+    duplicate_functions: HashMap<u32, u32>,
+
     /// Populated when we visit tables
     tables: Vec<Table<'a, 'g>>,
 
@@ -72,18 +109,30 @@ pub struct ModuleTranslator<'a, 'g> {
     /// Populated when we visit datas
     datas: Vec<Data<'a, 'g>>,
 
+    /// Resource files to output alongside the classes, by path
+    resources: Vec<(String, Vec<u8>)>,
+
+    /// Populated when we visit tags
+    tags: Vec<Tag<'a, 'g>>,
+
     /// Every time we see a new function, this gets incremented
     current_func_idx: u32,
-}
 
-struct CurrentPart<'g> {
-    class: Class<'g>,
-    bootstrap: BootstrapUtilities<'g>,
+    /// Functions which are the target of a trampolined `return_call`
+    tailcall_targets: HashSet<u32>,
+
+    /// Room reserved for functions in the part they are declared in, until they get translated
+    function_reservations: HashMap<u32, PartUsage>,
 }
-impl<'g> CurrentPart<'g> {
-    fn result(self) -> Result<Class<'g>, Error> {
-        Ok(self.class)
-    }
+
+/// Output of translating a module
+pub struct Translation {
+    /// Classes, along with their names
+    pub classes: Vec<(BinaryName, class_file::ClassFile)>,
+
+    /// Resource files which the classes read, along with their paths (relative to the root of the
+    /// class path)
+    pub resources: Vec<(String, Vec<u8>)>,
 }
 
 impl<'a, 'g> ModuleTranslator<'a, 'g> {
@@ -102,7 +151,8 @@ impl<'a, 'g> ModuleTranslator<'a, 'g> {
         ));
         let current_part = Self::new_part(&settings, class_id, class_graph, java, 0)?;
         let utilities = UtilityClass::new(&settings, class_id, class_graph, java)?;
-        let runtime = WasmRuntime::add_to_graph(class_graph, &java.classes);
+        let runtime =
+            WasmRuntime::add_to_graph(class_graph, &java.classes, settings.memory_backend);
 
         Ok(ModuleTranslator {
             settings,
@@ -111,6 +161,7 @@ impl<'a, 'g> ModuleTranslator<'a, 'g> {
             class: Class::new(class_id),
             previous_parts: vec![],
             fields_generated: false,
+            functions_declared: false,
             class_graph,
             java,
             runtime,
@@ -119,12 +170,23 @@ impl<'a, 'g> ModuleTranslator<'a, 'g> {
             start_function: None,
             types: vec![],
             functions: vec![],
+            function_declarations: vec![],
+            function_exports: vec![],
+            function_bodies_count: 0,
+            function_bodies: vec![],
+            inline_bodies: HashMap::new(),
+            reachability: Reachability::everything(0, 0),
+            duplicate_functions: HashMap::new(),
             tables: vec![],
             memories: vec![],
             globals: vec![],
             elements: vec![],
             datas: vec![],
+            resources: vec![],
+            tags: vec![],
             current_func_idx: 0,
+            tailcall_targets: HashSet::new(),
+            function_reservations: HashMap::new(),
         })
     }
 
@@ -148,7 +210,7 @@ impl<'a, 'g> ModuleTranslator<'a, 'g> {
         class_graph: &'g ClassGraph<'g>,
         java: &'g JavaLibrary<'g>,
         part_idx: usize,
-    ) -> Result<CurrentPart<'g>, Error> {
+    ) -> Result<Part<'g>, Error> {
         let name = settings
             .part_short_class_name
             .concat(&UnqualifiedName::number(part_idx));
@@ -166,10 +228,23 @@ impl<'a, 'g> ModuleTranslator<'a, 'g> {
             }),
         ));
 
-        Ok(CurrentPart {
-            class: Class::new(part_id),
-            bootstrap: BootstrapUtilities::new(),
-        })
+        Ok(Part::new(Class::new(part_id)))
+    }
+
+    /// Find the part in which a method was declared
+    fn find_part<'p>(
+        current_part: &'p mut Part<'g>,
+        previous_parts: &'p mut [Part<'g>],
+        method: MethodId<'g>,
+    ) -> &'p mut Part<'g> {
+        if current_part.class.id == method.class {
+            current_part
+        } else {
+            previous_parts
+                .iter_mut()
+                .find(|part| part.class.id == method.class)
+                .expect("Method is not declared in a part")
+        }
     }
 
     /// Process one payload, return types on the final `End` payload
@@ -182,9 +257,11 @@ impl<'a, 'g> ModuleTranslator<'a, 'g> {
         {
             self.generate_table_fields()?;
             self.generate_memory_fields()?;
-            self.generate_global_fields()?;
             self.fields_generated = true
         }
+        if !self.functions_declared && matches!(&payload, Payload::End(_)) {
+            self.declare_functions()?;
+        }
 
         match payload {
             Payload::Version {
@@ -197,7 +274,7 @@ impl<'a, 'g> ModuleTranslator<'a, 'g> {
             Payload::InstanceSection(section) => self.validator.instance_section(&section)?,
             Payload::TableSection(section) => self.visit_tables(section)?,
             Payload::MemorySection(section) => self.visit_memories(section)?,
-            Payload::TagSection(section) => self.validator.tag_section(&section)?,
+            Payload::TagSection(section) => self.visit_tags(section)?,
             Payload::GlobalSection(section) => self.visit_globals(section)?,
             Payload::ExportSection(section) => self.visit_exports(section)?,
             Payload::FunctionSection(section) => self.visit_function_declarations(section)?,
@@ -213,9 +290,16 @@ impl<'a, 'g> ModuleTranslator<'a, 'g> {
             Payload::DataSection(section) => self.visit_datas(section)?,
             Payload::CustomSection { .. } => (),
             Payload::CodeSectionStart { count, range, .. } => {
-                self.validator.code_section_start(count, &range)?
+                self.validator.code_section_start(count, &range)?;
+                self.function_bodies_count = count as usize;
+            }
+            Payload::CodeSectionEntry(function_body) => {
+                let func_to_validate = self.validator.code_section_entry(&function_body)?;
+                self.function_bodies.push((function_body, func_to_validate));
+                if self.function_bodies.len() == self.function_bodies_count {
+                    self.visit_function_bodies()?;
+                }
             }
-            Payload::CodeSectionEntry(function_body) => self.visit_function_body(function_body)?,
             Payload::ModuleSection { range, .. } => self.validator.module_section(&range)?,
             Payload::UnknownSection { id, range, .. } => {
                 self.validator.unknown_section(id, &range)?
@@ -248,48 +332,733 @@ impl<'a, 'g> ModuleTranslator<'a, 'g> {
         Ok(None)
     }
 
+    /// Visit all the function bodies, once the last one has been seen
+    fn visit_function_bodies(&mut self) -> Result<(), Error> {
+        self.declare_functions()?;
+        for (function_body, func_to_validate) in std::mem::take(&mut self.function_bodies) {
+            self.visit_function_body(function_body, func_to_validate)?;
+        }
+        Ok(())
+    }
+
+    /// Find the functions and globals which can be reached from the roots of the module
+    fn find_reachable(&mut self) -> Result<(), Error> {
+        let imported_functions = self.functions.len();
+        let function_count = imported_functions + self.function_declarations.len();
+        if !self.settings.dead_code_elimination {
+            self.reachability = Reachability::everything(function_count, self.globals.len());
+            return Ok(());
+        }
+
+        // Imports, exports, the start function, and element segments are the roots
+        let mut roots = vec![];
+        roots.extend((0..imported_functions as u32).map(Reachable::Function));
+        for (global_idx, global) in self.globals.iter().enumerate() {
+            if global.import.is_some() || !global.export.is_empty() {
+                roots.push(Reachable::Global(global_idx as u32));
+            }
+        }
+        roots.extend(
+            self.function_exports
+                .iter()
+                .map(|(func_idx, _)| Reachable::Function(*func_idx)),
+        );
+        roots.extend(
+            self.start_function
+                .map(|func_idx| Reachable::Function(func_idx as u32)),
+        );
+        for element in &self.elements {
+            for item in &element.items {
+                roots.extend(element_item_references(item)?);
+            }
+        }
+
+        let mut function_bodies: Vec<Option<&FunctionBody<'a>>> = vec![None; function_count];
+        for (declaration_idx, (function_body, _)) in self.function_bodies.iter().enumerate() {
+            function_bodies[imported_functions + declaration_idx] = Some(function_body);
+        }
+        let global_initializers: Vec<Option<&ConstExpr<'a>>> = self
+            .globals
+            .iter()
+            .map(|global| global.initial.as_ref())
+            .collect();
+        self.reachability = Reachability::new(roots, &function_bodies, &global_initializers)?;
+        log::debug!(
+            "Reachable functions: {}/{}",
+            (0..function_count)
+                .filter(|func_idx| self.reachability.function(*func_idx))
+                .count(),
+            function_count
+        );
+        Ok(())
+    }
+
+    /// Find the reachable functions whose type and body are identical to those of an earlier
+    /// reachable function
+    fn find_duplicate_functions(&mut self) -> Result<(), Error> {
+        if !self.settings.deduplicate_functions {
+            return Ok(());
+        }
+        let imported_functions = self.functions.len();
+        let mut first_functions: HashMap<(&FunctionType, &'a [u8]), u32> = HashMap::new();
+        for (declaration_idx, (function_body, _)) in self.function_bodies.iter().enumerate() {
+            let func_idx = (imported_functions + declaration_idx) as u32;
+            if !self.reachability.function(func_idx as usize) {
+                continue;
+            }
+            let func_type = &self.types[self.function_declarations[declaration_idx] as usize];
+            let mut reader = function_body.get_binary_reader();
+            let body = reader.read_bytes(reader.bytes_remaining())?;
+            match first_functions.entry((func_type, body)) {
+                Entry::Occupied(first) => {
+                    self.duplicate_functions.insert(func_idx, *first.get());
+                }
+                Entry::Vacant(first) => {
+                    first.insert(func_idx);
+                }
+            }
+        }
+        log::debug!("Deduplicating {} functions", self.duplicate_functions.len());
+        Ok(())
+    }
+
+    /// Find the functions which are small enough to be inlined into their callers
+    ///
+    /// This runs before any function gets declared, since the part in which a function is
+    /// declared depends on what gets inlined into it.
+    fn find_inline_bodies(&mut self) -> Result<(), Error> {
+        let max_operators = self.settings.inline_max_operators;
+        if max_operators == 0 {
+            return Ok(());
+        }
+        let imported_functions = self.functions.len();
+        for (declaration_idx, (function_body, _)) in self.function_bodies.iter().enumerate() {
+            if !self
+                .reachability
+                .function(imported_functions + declaration_idx)
+            {
+                continue;
+            }
+            let func_type_idx = self.function_declarations[declaration_idx];
+            let func_type = &self.types[func_type_idx as usize];
+            if let Some(inline_body) =
+                InlineBody::from_function_body(func_type, function_body, max_operators)?
+            {
+                let func_idx = (imported_functions + declaration_idx) as u32;
+                self.inline_bodies.insert(func_idx, inline_body);
+            }
+        }
+        log::debug!("Inlining calls to {} functions", self.inline_bodies.len());
+        Ok(())
+    }
+
+    /// Declare the methods for functions from the function section
+    ///
+    /// Functions are packed into the current part until it is full, at which point a new part is
+    /// started. Whether a function fits is estimated from its body, which is why this waits until
+    /// all function bodies have been seen. This is also when the functions and globals which
+    /// can't be reached get found, so that they don't take up any room.
+    fn declare_functions(&mut self) -> Result<(), Error> {
+        self.find_reachable()?;
+        self.find_duplicate_functions()?;
+        self.generate_global_fields()?;
+        self.find_inline_bodies()?;
+
+        let declared_methods = match self.settings.tail_call_strategy {
+            TailCallStrategy::Trampoline => 2,
+            TailCallStrategy::PlainCall => 1,
+        };
+        let function_declarations = std::mem::take(&mut self.function_declarations);
+        for (declaration_idx, func_type_idx) in function_declarations.into_iter().enumerate() {
+            let func_idx = self.functions.len();
+
+            // Duplicate functions share the methods of the function they duplicate
+            if let Some(first_idx) = self.duplicate_functions.get(&(func_idx as u32)) {
+                let first = &self.functions[*first_idx as usize];
+                self.functions.push(Function {
+                    func_type: first.func_type.clone(),
+                    type_idx: func_type_idx,
+                    method: first.method,
+                    tailcall_method: first.tailcall_method,
+                    trampolined: first.trampolined,
+                    import: None,
+                    export: vec![],
+                });
+                continue;
+            }
+
+            // Pick the part in which to declare the function (unreachable functions take no room)
+            if self.reachability.function(func_idx) {
+                let (mut references, body_length) = match self.function_bodies.get(declaration_idx)
+                {
+                    Some((function_body, _)) => (
+                        PartReferences::from_function_body(function_body)?,
+                        function_body.range().end - function_body.range().start,
+                    ),
+                    None => (PartReferences::default(), 0),
+                };
+                references.types.insert(func_type_idx);
+                let mut inlined_references = PartReferences::default();
+                for callee_idx in &references.functions {
+                    if let Some(inline_body) = self.inline_bodies.get(callee_idx) {
+                        inlined_references.extend(&inline_body.references);
+                    }
+                }
+                references.extend(&inlined_references);
+                let reservation = self.current_part.estimate(
+                    declared_methods,
+                    &references,
+                    body_length * PartUsage::CODE_EXPANSION,
+                );
+                self.make_room_in_part(reservation)?;
+                self.current_part.reserve(reservation, &references);
+                self.function_reservations
+                    .insert(func_idx as u32, reservation);
+            }
+
+            // Build up a method descriptor, which includes a trailing "WASM module" argument
+            let func_type = self.types[func_type_idx as usize].clone();
+            let mut descriptor =
+                func_type.method_descriptor(&self.java.classes, &self.runtime.tuples);
+            descriptor.parameters.push(FieldType::object(self.class.id));
+
+            // Tail call variant has the same arguments, but returns a boxed value or a thunk
+            let tailcall_method = match self.settings.tail_call_strategy {
+                TailCallStrategy::Trampoline => {
+                    let mut descriptor = descriptor.clone();
+                    descriptor.return_type = Some(FieldType::object(self.java.classes.lang.object));
+                    Some(self.class_graph.add_method(MethodData {
+                        class: self.current_part.class.id,
+                        name: self.settings.wasm_tailcall_function_name(func_idx),
+                        access_flags: MethodAccessFlags::STATIC,
+                        descriptor,
+                    }))
+                }
+                TailCallStrategy::PlainCall => None,
+            };
+
+            // Functions with tail calls get translated into their tail call variant
+            let trampolined = match (tailcall_method, self.function_bodies.get(declaration_idx)) {
+                (Some(_), Some((function_body, _))) if self.reachability.function(func_idx) => {
+                    Self::visit_tail_calls(
+                        function_body,
+                        &self.duplicate_functions,
+                        &mut self.tailcall_targets,
+                    )?
+                }
+                _ => false,
+            };
+
+            let method = self.class_graph.add_method(MethodData {
+                class: self.current_part.class.id,
+                name: self.settings.wasm_function_name(func_idx),
+                access_flags: MethodAccessFlags::STATIC,
+                descriptor,
+            });
+
+            self.functions.push(Function {
+                func_type,
+                type_idx: func_type_idx,
+                method,
+                tailcall_method,
+                trampolined,
+                import: None,
+                export: vec![],
+            });
+        }
+
+        for (func_idx, export_name) in std::mem::take(&mut self.function_exports) {
+            let function: &mut Function = self
+                .functions
+                .get_mut(func_idx as usize)
+                .expect("Exporting function that doesn't exist");
+            function
+                .export
+                .push((export_name, self.settings.methods_for_function_exports));
+        }
+
+        self.functions_declared = true;
+        Ok(())
+    }
+
+    /// Make sure the current part has room for `usage`, starting a new part if it doesn't
+    fn make_room_in_part(&mut self, usage: PartUsage) -> Result<(), Error> {
+        if self.current_part.has_room(&self.settings, usage) {
+            return Ok(());
+        }
+
+        let part_idx = self.previous_parts.len() + 1;
+        let new_part = Self::new_part(
+            &self.settings,
+            self.class.id,
+            self.class_graph,
+            self.java,
+            part_idx,
+        )?;
+        let full_part = std::mem::replace(&mut self.current_part, new_part);
+        let full_usage = full_part.planned_usage();
+        log::debug!(
+            "Starting part {} (previous part has ~{} methods, ~{} constants, ~{} bytes)",
+            part_idx,
+            full_usage.methods,
+            full_usage.constants,
+            full_usage.class_size,
+        );
+        self.previous_parts.push(full_part);
+        Ok(())
+    }
+
+    /// Move a function out of the part it was declared in, and into the current part
+    ///
+    /// This is for when the part a function was declared in filled up faster than estimated. The
+    /// methods declared for the function may already be referenced, so they stay behind and
+    /// forward to the function's new methods.
+    fn relocate_function(&mut self, func_idx: u32, usage: PartUsage) -> Result<(), Error> {
+        self.make_room_in_part(usage)?;
+        let part_class = self.current_part.class.id;
+        let function = &mut self.functions[func_idx as usize];
+        log::debug!(
+            "Moving function {} to part {} since part {} is full",
+            func_idx,
+            part_class.name.as_str(),
+            function.method.class.name.as_str(),
+        );
+
+        let class_graph = self.class_graph;
+        let relocate = |method: MethodId<'g>| {
+            class_graph.add_method(MethodData {
+                class: part_class,
+                name: method.name.clone(),
+                access_flags: method.access_flags,
+                descriptor: method.descriptor.clone(),
+            })
+        };
+        let mut forwarded_methods = vec![(function.method, relocate(function.method))];
+        function.method = forwarded_methods[0].1;
+        if let Some(tailcall_method) = function.tailcall_method {
+            let relocated = relocate(tailcall_method);
+            if function.trampolined || self.tailcall_targets.contains(&func_idx) {
+                forwarded_methods.push((tailcall_method, relocated));
+            }
+            function.tailcall_method = Some(relocated);
+        }
+
+        for (method, target) in forwarded_methods {
+            let mut code = CodeBuilder::new(self.class_graph, self.java, method);
+            generate_forwarding_method(&mut code, target)?;
+            let part = Self::find_part(&mut self.current_part, &mut self.previous_parts, method);
+            part.add_method(
+                Method {
+                    id: method,
+                    code_impl: Some(code.result()?),
+                    exceptions: vec![],
+                    generic_signature: None,
+                },
+                &self.settings,
+            )?;
+        }
+        Ok(())
+    }
+
     /// Visit a function body
-    fn visit_function_body(&mut self, function_body: FunctionBody) -> Result<(), Error> {
-        let func_to_validate = self.validator.code_section_entry(&function_body)?;
-        let func_validator_allocs = self.func_validator_allocations.take().unwrap_or_default();
-        let mut validator = func_to_validate.into_validator(func_validator_allocs);
+    ///
+    /// If the translated method doesn't fit in the JVM's limit on code length, the function gets
+    /// translated again with regions of it split out into their own methods (using smaller and
+    /// smaller regions until everything fits).
+    fn visit_function_body(
+        &mut self,
+        function_body: FunctionBody,
+        func_to_validate: FuncToValidate<ValidatorResources>,
+    ) -> Result<(), Error> {
+        let mut func_validator_allocs = self.func_validator_allocations.take().unwrap_or_default();
+
+        // Since the function may need to be validated more than once, this is just for resources
+        let resources = func_to_validate.into_validator(Default::default());
 
         // Look up the previously declared method and start implementing it
-        let function = &self.functions[self.current_func_idx as usize];
+        let func_idx = self.current_func_idx;
+        let function = &self.functions[func_idx as usize];
+        self.current_func_idx += 1;
+
+        // Functions which can't be reached or which duplicate another function just get validated
+        if !self.reachability.function(func_idx as usize)
+            || self.duplicate_functions.contains_key(&func_idx)
+        {
+            log::trace!("Skipping unreachable or duplicate function {}", func_idx);
+            let mut validator = FuncToValidate::new(
+                func_idx,
+                function.type_idx,
+                resources.resources(),
+                &self.settings.wasm_features,
+            )
+            .into_validator(func_validator_allocs);
+            let operators = read_operators(&function_body)?;
+            validate_operators(&function_body, &operators, &mut validator)?;
+            self.func_validator_allocations = Some(validator.into_allocations());
+            return Ok(());
+        }
+
+        // If the part the function was declared in filled up faster than estimated, move it
+        let reservation = self
+            .function_reservations
+            .remove(&func_idx)
+            .unwrap_or_default();
+        let declared_part = Self::find_part(
+            &mut self.current_part,
+            &mut self.previous_parts,
+            function.method,
+        );
+        declared_part.release(reservation);
+        if !declared_part.has_room(&self.settings, reservation) {
+            self.relocate_function(func_idx, reservation)?;
+        }
+        let function = &self.functions[func_idx as usize];
+
+        let trampolined = function.trampolined;
+        let translated_method = if trampolined {
+            function.tailcall_method.unwrap()
+        } else {
+            function.method
+        };
         log::trace!(
             "Translating function {} (as {:?})",
-            self.current_func_idx,
-            function.method
+            func_idx,
+            translated_method
         );
-        self.current_func_idx += 1;
-        let mut code_builder = CodeBuilder::new(self.class_graph, self.java, function.method);
 
-        let mut function_translator = FunctionTranslator::new(
-            &function.func_type,
-            &self.settings,
-            &mut self.utilities,
-            &mut self.current_part.bootstrap,
-            &mut code_builder,
-            self.class.id,
-            &self.runtime,
-            &self.functions,
-            &self.tables,
-            &self.memories,
-            &self.globals,
-            &self.datas,
-            &self.elements,
-            function_body,
-            &mut validator,
-        )?;
-        function_translator.translate()?;
-        self.func_validator_allocations = Some(validator.into_allocations());
+        let part = Self::find_part(
+            &mut self.current_part,
+            &mut self.previous_parts,
+            function.method,
+        );
 
-        self.current_part.class.add_method(Method {
-            id: function.method,
-            code_impl: Some(code_builder.result()?),
-            exceptions: vec![],
-            generic_signature: None,
-        });
+        // Read the operators, inlining calls and optimizing them if need be
+        let mut operators = read_operators(&function_body)?;
+        let mut inlined_locals = vec![];
+        let mut inlined_locals_start = u32::MAX;
+        if self.settings.optimization_level > 0 || !self.inline_bodies.is_empty() {
+            // Only the transformed operators get validated during translation
+            let mut validator = FuncToValidate::new(
+                func_idx,
+                function.type_idx,
+                resources.resources(),
+                &self.settings.wasm_features,
+            )
+            .into_validator(func_validator_allocs);
+            validate_operators(&function_body, &operators, &mut validator)?;
+            inlined_locals_start = validator.len_locals();
+            func_validator_allocs = validator.into_allocations();
+            inlined_locals =
+                inline_calls(&mut operators, inlined_locals_start, &self.inline_bodies);
+            optimize_operators(&mut operators, self.settings.optimization_level);
+        }
+
+        // Build the SSA form, if the function can be translated through it
+        let mut ssa_function = None;
+        if self.settings.ssa_translation
+            && !trampolined
+            && function.func_type.packed_inputs().is_empty()
+        {
+            let mut validator = FuncToValidate::new(
+                func_idx,
+                function.type_idx,
+                resources.resources(),
+                &self.settings.wasm_features,
+            )
+            .into_validator(func_validator_allocs);
+            ssa_function = SsaFunction::build(
+                &function.func_type,
+                &function_body,
+                &operators,
+                &inlined_locals,
+                &mut validator,
+            )?;
+            func_validator_allocs = validator.into_allocations();
+        }
+
+        let mut split_spans: Vec<SplitSpan> = vec![];
+        let mut split_function = SplitFunction::default();
+        let mut split_analysis: Option<SplitAnalysis> = None;
+        let mut split_method_ids: Vec<MethodId<'g>> = vec![];
+        loop {
+            let mut validator = FuncToValidate::new(
+                func_idx,
+                function.type_idx,
+                resources.resources(),
+                &self.settings.wasm_features,
+            )
+            .into_validator(func_validator_allocs);
+            let mut code_builder = CodeBuilder::new(self.class_graph, self.java, translated_method);
+            let module = ModuleContext {
+                settings: &self.settings,
+                class: self.class.id,
+                runtime: &self.runtime,
+                functions: &self.functions,
+                tables: &self.tables,
+                memories: &self.memories,
+                globals: &self.globals,
+                datas: &self.datas,
+                elements: &self.elements,
+                tags: &self.tags,
+            };
+            let function_input = FunctionInput {
+                function_typ: &function.func_type,
+                trampolined,
+                body: function_body,
+                operators: &operators,
+                inlined_locals: &inlined_locals,
+                split: &split_function,
+            };
+            let mut function_translator = FunctionTranslator::new(
+                module,
+                function_input,
+                &mut self.utilities,
+                &mut part.bootstrap,
+                &mut code_builder,
+                &mut validator,
+            )?;
+            match &ssa_function {
+                Some(ssa) if split_spans.is_empty() => {
+                    // Every SSA value takes up a JVM local, so there may not be enough of them
+                    match function_translator.translate_ssa(ssa) {
+                        Err(Error::LocalsOverflow) => {
+                            log::debug!(
+                                "Function {} has too many SSA values, translating it directly",
+                                func_idx
+                            );
+                            func_validator_allocs = validator.into_allocations();
+                            ssa_function = None;
+                            continue;
+                        }
+                        result => result?,
+                    }
+                }
+                _ => function_translator.translate()?,
+            }
+            let split_methods = std::mem::take(&mut function_translator.split_methods);
+            let code = code_builder.result()?;
+            func_validator_allocs = validator.into_allocations();
+
+            // Methods which are still too big (`None` being the function itself)
+            let mut oversized: Vec<(Option<usize>, usize)> = split_methods
+                .iter()
+                .map(|(region_idx, split_code)| (Some(*region_idx), split_code.max_code_length()))
+                .chain(iter::once((None, code.max_code_length())))
+                .filter(|(_, code_length)| *code_length > MAX_CODE_LENGTH)
+                .collect();
+            if oversized.is_empty() {
+                part.add_method(
+                    Method {
+                        id: translated_method,
+                        code_impl: Some(code),
+                        exceptions: vec![],
+                        generic_signature: None,
+                    },
+                    &self.settings,
+                )?;
+
+                // Regions in unreachable code never get translated, but still need a body
+                let mut split_codes: Vec<Option<Code<'g>>> =
+                    split_function.regions.iter().map(|_| None).collect();
+                for (region_idx, split_code) in split_methods {
+                    split_codes[region_idx] = Some(split_code);
+                }
+                for (region, split_code) in split_function.regions.iter().zip(split_codes) {
+                    let split_code = match split_code {
+                        Some(split_code) => split_code,
+                        None => {
+                            let mut code =
+                                CodeBuilder::new(self.class_graph, self.java, region.method);
+                            self.utilities
+                                .invoke_utility(UtilityMethod::Unreachable, &mut code)?;
+                            code.push_branch_instruction(BranchInstruction::AThrow)?;
+                            code.result()?
+                        }
+                    };
+                    part.add_method(
+                        Method {
+                            id: region.method,
+                            code_impl: Some(split_code),
+                            exceptions: vec![],
+                            generic_signature: None,
+                        },
+                        &self.settings,
+                    )?;
+                }
+                break;
+            }
+
+            // The SSA lowering can come out bigger than the direct translation, so try that first
+            if ssa_function.is_some() && split_spans.is_empty() {
+                log::debug!(
+                    "Function {} is too big through SSA ({} bytes), translating it directly",
+                    func_idx,
+                    code.max_code_length()
+                );
+                ssa_function = None;
+                continue;
+            }
+
+            // Split more regions out of every method which is still too big
+            let analysis = match &mut split_analysis {
+                Some(analysis) => analysis,
+                None => {
+                    let mut validator = FuncToValidate::new(
+                        func_idx,
+                        function.type_idx,
+                        resources.resources(),
+                        &self.settings.wasm_features,
+                    )
+                    .into_validator(func_validator_allocs);
+                    let analysis = SplitAnalysis::new(
+                        &function_body,
+                        &mut operators,
+                        &inlined_locals,
+                        &mut validator,
+                    )?;
+                    func_validator_allocs = validator.into_allocations();
+                    split_analysis.insert(analysis)
+                }
+            };
+            oversized.sort_unstable();
+            let mut new_spans = vec![];
+            for (method_region, code_length) in oversized {
+                let method = match method_region {
+                    Some(region_idx) => {
+                        (split_spans[region_idx].first, split_spans[region_idx].last)
+                    }
+                    None => (0, operators.len() - 1),
+                };
+                let children: Vec<SplitSpan> = split_function
+                    .children(method_region)
+                    .into_iter()
+                    .map(|region_idx| split_spans[region_idx])
+                    .collect();
+                let spans = find_split_regions(
+                    &operators,
+                    &analysis.statement_starts,
+                    method,
+                    &children,
+                    code_length,
+                );
+                if spans.is_empty() {
+                    return Err(Error::BytecodeGen(jvm::Error::MethodCodeOverflow(Offset(
+                        code_length,
+                    ))));
+                }
+                log::debug!(
+                    "Function {} has a method which is too big ({} bytes), splitting out {} more regions",
+                    func_idx,
+                    code_length,
+                    spans.len(),
+                );
+                new_spans.extend(spans);
+            }
+            split_spans.extend(new_spans);
+            split_spans.sort_unstable_by_key(|span| (span.first, Reverse(span.last)));
+
+            let class_graph = self.class_graph;
+            let settings = &self.settings;
+            let part_class = part.class.id;
+            let descriptor = SplitRegion::method_descriptor(&self.java.classes, self.class.id);
+            split_function = SplitFunction::new(
+                &operators,
+                &split_spans,
+                &analysis.local_types,
+                inlined_locals_start,
+                |region_idx| {
+                    while split_method_ids.len() <= region_idx {
+                        split_method_ids.push(class_graph.add_method(MethodData {
+                            class: part_class,
+                            name: settings.wasm_split_function_name(
+                                func_idx as usize,
+                                split_method_ids.len(),
+                            ),
+                            access_flags: MethodAccessFlags::STATIC,
+                            descriptor: descriptor.clone(),
+                        }));
+                    }
+                    split_method_ids[region_idx]
+                },
+            );
+        }
+        self.func_validator_allocations = Some(func_validator_allocs);
+
+        // The regular method just evaluates the tail call variant
+        if trampolined {
+            let mut code = CodeBuilder::new(self.class_graph, self.java, function.method);
+            generate_trampoline(&mut code, &self.runtime, function)?;
+            part.add_method(
+                Method {
+                    id: function.method,
+                    code_impl: Some(code.result()?),
+                    exceptions: vec![],
+                    generic_signature: None,
+                },
+                &self.settings,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Check if a function body contains any tail calls, recording the targets of direct ones
+    ///
+    /// Targets which are duplicates get recorded as the function they duplicate, since that is
+    /// the function whose tail call variant needs to exist.
+    fn visit_tail_calls(
+        function_body: &FunctionBody,
+        duplicate_functions: &HashMap<u32, u32>,
+        tailcall_targets: &mut HashSet<u32>,
+    ) -> Result<bool, Error> {
+        let mut has_tail_calls = false;
+        for operator in function_body.get_operators_reader()? {
+            match operator? {
+                Operator::ReturnCall { function_index } => {
+                    let function_index = duplicate_functions
+                        .get(&function_index)
+                        .copied()
+                        .unwrap_or(function_index);
+                    tailcall_targets.insert(function_index);
+                    has_tail_calls = true;
+                }
+                Operator::ReturnCallIndirect { .. } => has_tail_calls = true,
+                _ => (),
+            }
+        }
+        Ok(has_tail_calls)
+    }
+
+    /// Generate the tail call variants of functions which are the target of a `return_call` but
+    /// which weren't trampolined themselves
+    fn generate_tailcall_adapters(&mut self) -> Result<(), Error> {
+        for (func_idx, function) in self.functions.iter().enumerate() {
+            let func_idx = func_idx as u32;
+            let tailcall_method = match function.tailcall_method {
+                Some(tailcall_method) if function.import.is_none() => tailcall_method,
+                _ => continue,
+            };
+            if !self.tailcall_targets.contains(&func_idx) || function.trampolined {
+                continue;
+            }
+
+            let mut code = CodeBuilder::new(self.class_graph, self.java, tailcall_method);
+            generate_tailcall_adapter(&mut code, function)?;
+            let part = Self::find_part(
+                &mut self.current_part,
+                &mut self.previous_parts,
+                tailcall_method,
+            );
+            part.add_method(
+                Method {
+                    id: tailcall_method,
+                    code_impl: Some(code.result()?),
+                    exceptions: vec![],
+                    generic_signature: None,
+                },
+                &self.settings,
+            )?;
+        }
 
         Ok(())
     }
@@ -315,6 +1084,10 @@ impl<'a, 'g> ModuleTranslator<'a, 'g> {
     /// Generate the fields associated with globals
     fn generate_global_fields(&mut self) -> Result<(), Error> {
         for (global_idx, global) in self.globals.iter_mut().enumerate() {
+            if !self.reachability.global(global_idx) {
+                continue;
+            }
+
             let access_flags = match global.repr {
                 GlobalRepr::BoxedExternal => FieldAccessFlags::FINAL,
                 GlobalRepr::UnboxedInternal if global.mutable => FieldAccessFlags::PRIVATE,
@@ -364,34 +1137,16 @@ impl<'a, 'g> ModuleTranslator<'a, 'g> {
     }
 
     /// Visit function section
+    ///
+    /// The methods for these functions only get declared once the function bodies are known, in
+    /// `declare_functions`.
     fn visit_function_declarations(
         &mut self,
         functions: FunctionSectionReader<'a>,
     ) -> Result<(), Error> {
         self.validator.function_section(&functions)?;
-        for (func_idx, func_type_idx) in functions.into_iter().enumerate() {
-            // Offset by imported functions
-            let func_idx = func_idx + self.current_func_idx as usize;
-
-            // Build up a method descriptor, which includes a trailing "WASM module" argument
-            let func_type = self.types[func_type_idx? as usize].clone();
-            let mut descriptor = func_type.method_descriptor(&self.java.classes);
-            descriptor.parameters.push(FieldType::object(self.class.id));
-
-            let method = self.class_graph.add_method(MethodData {
-                class: self.current_part.class.id, // TODO: choose the right part here
-                name: self.settings.wasm_function_name(func_idx),
-                access_flags: MethodAccessFlags::STATIC,
-                descriptor,
-            });
-
-            self.functions.push(Function {
-                func_type,
-                method,
-                tailcall_method: None,
-                import: None,
-                export: vec![],
-            });
+        for func_type_idx in functions {
+            self.function_declarations.push(func_type_idx?);
         }
         Ok(())
     }
@@ -415,11 +1170,24 @@ impl<'a, 'g> ModuleTranslator<'a, 'g> {
                 descriptor: FieldType::array(FieldType::byte()),
             });
 
+            let resource = match self.settings.data_storage {
+                DataStorage::StringConstants => None,
+                DataStorage::Resources => Some(DataResource {
+                    name: self.settings.wasm_data_resource_name(data_idx),
+                    compressed: false,
+                }),
+                DataStorage::CompressedResources => Some(DataResource {
+                    name: self.settings.wasm_data_resource_name(data_idx),
+                    compressed: true,
+                }),
+            };
+
             self.datas.push(Data {
                 kind: None,
                 bytes: None,
                 method,
                 field,
+                resource,
             });
         }
 
@@ -474,7 +1242,8 @@ impl<'a, 'g> ModuleTranslator<'a, 'g> {
 
                 // This is the expected descriptor of the imported function
                 let func_type = self.types[func_type_idx as usize].clone();
-                let imported_descriptor = func_type.method_descriptor(&java.classes);
+                let imported_descriptor =
+                    func_type.method_descriptor(&java.classes, &self.runtime.tuples);
 
                 // Build up a method descriptor, which includes a trailing "WASM module" argument
                 let mut descriptor = imported_descriptor.clone();
@@ -494,6 +1263,9 @@ impl<'a, 'g> ModuleTranslator<'a, 'g> {
                 });
 
                 // Trampoline method, whose sole responsibility is to invoke the method handle
+                let mut references = PartReferences::default();
+                references.types.insert(func_type_idx);
+                self.make_room_in_part(self.current_part.estimate(1, &references, 0))?;
                 let method_id = self.class_graph.add_method(MethodData {
                     class: self.current_part.class.id,
                     name: self.settings.wasm_function_name(func_idx as usize),
@@ -502,8 +1274,10 @@ impl<'a, 'g> ModuleTranslator<'a, 'g> {
                 });
                 self.functions.push(Function {
                     func_type,
+                    type_idx: func_type_idx,
                     method: method_id,
                     tailcall_method: None,
+                    trampolined: false,
                     import: Some((import_name, import_field)),
                     export: vec![],
                 });
@@ -524,30 +1298,70 @@ impl<'a, 'g> ModuleTranslator<'a, 'g> {
                 code.invoke_invoke_exact(imported_descriptor)?;
                 code.return_(return_type)?;
 
-                self.current_part.class.add_method(Method {
-                    id: method_id,
-                    code_impl: Some(code.result()?),
-                    exceptions: vec![],
-                    generic_signature: None,
-                });
+                self.current_part.add_method(
+                    Method {
+                        id: method_id,
+                        code_impl: Some(code.result()?),
+                        exceptions: vec![],
+                        generic_signature: None,
+                    },
+                    &self.settings,
+                )?;
             }
 
             TypeRef::Memory(memory_type) => {
+                let repr = Self::memory_repr(&memory_type)?;
                 self.memories.push(Memory {
                     field: None,
-                    repr: MemoryRepr::External,
+                    repr: match repr {
+                        MemoryRepr::Internal => MemoryRepr::External,
+                        repr => repr,
+                    },
                     memory_type,
                     import: Some(import_name),
                     export: vec![],
+                    backend: self.settings.memory_backend,
+                    trap_address_overflow: self.settings.trap_address_overflow,
                 });
             }
 
-            TypeRef::Tag(_) => todo!("import tag"),
+            TypeRef::Tag(tag_type) => {
+                let tag_idx = self.tags.len();
+                self.add_tag(tag_idx, tag_type.func_type_idx, Some(import_name));
+            }
         }
 
         Ok(())
     }
 
+    /// Visit the tags section
+    fn visit_tags(&mut self, tags: TagSectionReader<'a>) -> Result<(), Error> {
+        self.validator.tag_section(&tags)?;
+        for tag in tags {
+            let tag_type = tag?;
+            let tag_idx = self.tags.len();
+            self.add_tag(tag_idx, tag_type.func_type_idx, None);
+        }
+        Ok(())
+    }
+
+    /// Add a tag along with the field that will store it
+    fn add_tag(&mut self, tag_idx: usize, func_type_idx: u32, import: Option<ImportName<'a>>) {
+        let field = self.class_graph.add_field(FieldData {
+            class: self.class.id,
+            access_flags: FieldAccessFlags::FINAL,
+            name: self.settings.wasm_tag_name(tag_idx),
+            descriptor: FieldType::object(self.runtime.classes.tag),
+        });
+        self.class.add_field(Field::new(field));
+        self.tags.push(Tag {
+            field,
+            tag_type: self.types[func_type_idx as usize].clone(),
+            import,
+            export: vec![],
+        });
+    }
+
     /// Visit the tables section
     fn visit_tables(&mut self, tables: TableSectionReader<'a>) -> Result<(), Error> {
         self.validator.table_section(&tables)?;
@@ -616,12 +1430,19 @@ impl<'a, 'g> ModuleTranslator<'a, 'g> {
         self.validator.memory_section(&memories)?;
         for memory in memories {
             let memory_type = memory?;
+            if memory_type.shared && self.settings.memory_backend == MemoryBackend::ByteArray {
+                return Err(Error::Unsupported(
+                    "shared memories with the byte array memory backend",
+                ));
+            }
             let memory = Memory {
                 field: None,
-                repr: MemoryRepr::Internal,
+                repr: Self::memory_repr(&memory_type)?,
                 memory_type,
                 import: None,
                 export: vec![],
+                backend: self.settings.memory_backend,
+                trap_address_overflow: self.settings.trap_address_overflow,
             };
             self.memories.push(memory);
         }
@@ -629,17 +1450,33 @@ impl<'a, 'g> ModuleTranslator<'a, 'g> {
         Ok(())
     }
 
+    /// Pick the representation of a memory (before accounting for imports and exports)
+    fn memory_repr(memory_type: &MemoryType) -> Result<MemoryRepr, Error> {
+        if memory_type.memory64 {
+            if memory_type.shared {
+                return Err(Error::Unsupported("shared 64-bit memories"));
+            }
+            Ok(MemoryRepr::Memory64)
+        } else if memory_type.shared {
+            // Shared memories need somewhere to keep track of threads waiting on them
+            Ok(MemoryRepr::External)
+        } else {
+            Ok(MemoryRepr::Internal)
+        }
+    }
+
     /// Generate the fields associated with memories
     fn generate_memory_fields(&mut self) -> Result<(), Error> {
         for (memory_idx, memory) in &mut self.memories.iter_mut().enumerate() {
             let access_flags = match memory.repr {
-                MemoryRepr::External => FieldAccessFlags::FINAL,
+                MemoryRepr::External | MemoryRepr::Memory64 => FieldAccessFlags::FINAL,
                 MemoryRepr::Internal => FieldAccessFlags::PUBLIC, // empty(), // FieldAccessFlags::PRIVATE,
             };
 
             let descriptor = match memory.repr {
                 MemoryRepr::External => FieldType::object(self.runtime.classes.memory),
-                MemoryRepr::Internal => FieldType::object(self.java.classes.nio.byte_buffer),
+                MemoryRepr::Memory64 => FieldType::object(self.runtime.classes.memory64),
+                MemoryRepr::Internal => memory.backend.bytes_type(&self.java.classes),
             };
 
             // TODO: this only works for Java 11+. For other Java versions, private fields from
@@ -674,13 +1511,7 @@ impl<'a, 'g> ModuleTranslator<'a, 'g> {
             let export_name = ExportName { name: export.name };
             match export.kind {
                 ExternalKind::Func => {
-                    let function: &mut Function = self
-                        .functions
-                        .get_mut(export.index as usize)
-                        .expect("Exporting function that doesn't exist");
-                    function
-                        .export
-                        .push((export_name, self.settings.methods_for_function_exports));
+                    self.function_exports.push((export.index, export_name));
                 }
 
                 ExternalKind::Table => {
@@ -697,7 +1528,9 @@ impl<'a, 'g> ModuleTranslator<'a, 'g> {
                         .memories
                         .get_mut(export.index as usize)
                         .expect("Exporting memory that doesn't exist");
-                    memory.repr = MemoryRepr::External;
+                    if let MemoryRepr::Internal = memory.repr {
+                        memory.repr = MemoryRepr::External;
+                    }
                     memory.export.push(export_name);
                 }
 
@@ -710,7 +1543,13 @@ impl<'a, 'g> ModuleTranslator<'a, 'g> {
                     global.export.push(export_name);
                 }
 
-                _ => unimplemented!(),
+                ExternalKind::Tag => {
+                    let tag: &mut Tag = self
+                        .tags
+                        .get_mut(export.index as usize)
+                        .expect("Exporting tag that doesn't exist");
+                    tag.export.push(export_name);
+                }
             }
         }
         Ok(())
@@ -725,7 +1564,9 @@ impl<'a, 'g> ModuleTranslator<'a, 'g> {
                     continue;
                 }
 
-                let export_descriptor = function.func_type.method_descriptor(&self.java.classes);
+                let export_descriptor = function
+                    .func_type
+                    .method_descriptor(&self.java.classes, &self.runtime.tuples);
 
                 // Implementation function
                 let mut underlying_descriptor = export_descriptor.clone();
@@ -768,19 +1609,118 @@ impl<'a, 'g> ModuleTranslator<'a, 'g> {
     }
 
     /// Generate functions for summoning data and elements
+    ///
+    /// The getters go in the main class, but the methods which read the data bytes or fill in the
+    /// element items go in parts, since they can use up a lot of constants.
     fn generate_constant_segments(&mut self) -> Result<(), Error> {
-        for data in &self.datas {
-            self.class
-                .add_method(data.generate_method(self.class_graph, self.java)?);
+        for data_idx in 0..self.datas.len() {
+            let data = &self.datas[data_idx];
+            let mut string_bytes = 0;
+            if let Some(resource) = &data.resource {
+                let bytes = data.bytes.unwrap();
+                let contents = if resource.compressed {
+                    zlib_compress(bytes)
+                } else {
+                    bytes.to_vec()
+                };
+                self.resources.push((resource.name.clone(), contents));
+            } else if let Some(bytes) = data.bytes {
+                // Bytes from 0x80 up take two bytes in the modified UTF-8 of string constants
+                string_bytes = 2 * bytes.len();
+            }
+
+            let string_constants = data.string_constants();
+            let mut usage = PartUsage::estimate(
+                1,
+                2 * string_constants,
+                string_constants * Data::INIT_CHUNK_CODE_LENGTH,
+            );
+            usage.class_size += string_bytes;
+            self.make_room_in_part(usage)?;
+            let init_method = self.class_graph.add_method(MethodData {
+                class: self.current_part.class.id,
+                name: self.settings.wasm_data_init_name(data_idx),
+                access_flags: MethodAccessFlags::STATIC,
+                descriptor: Data::init_method_descriptor(self.class.id),
+            });
+            let data = &self.datas[data_idx];
+            let mut code = CodeBuilder::new(self.class_graph, self.java, init_method);
+            data.generate_init_method(&mut code)?;
+            self.current_part.add_method(
+                Method {
+                    id: init_method,
+                    code_impl: Some(code.result()?),
+                    exceptions: vec![],
+                    generic_signature: None,
+                },
+                &self.settings,
+            )?;
+
+            self.class.add_method(data.generate_method(
+                self.class_graph,
+                self.java,
+                init_method,
+            )?);
             self.class.add_field(Field::new(data.field));
         }
-        for element in &self.elements {
+
+        for element_idx in 0..self.elements.len() {
+            let items_len = self.elements[element_idx].items.len();
+            let mut init_methods = vec![];
+            for chunk_start in (0..items_len).step_by(Element::INIT_CHUNK_ITEMS) {
+                let element = &self.elements[element_idx];
+                let chunk = &element.items[chunk_start..];
+                let chunk = &chunk[..chunk.len().min(Element::INIT_CHUNK_ITEMS)];
+                let mut references = PartReferences::default();
+                for item in chunk {
+                    for reachable in element_item_references(item)? {
+                        match reachable {
+                            Reachable::Function(func_idx) => references.functions.insert(func_idx),
+                            Reachable::Global(global_idx) => references.globals.insert(global_idx),
+                        };
+                    }
+                }
+                let usage = self.current_part.estimate(
+                    1,
+                    &references,
+                    chunk.len() * Element::INIT_ITEM_CODE_LENGTH,
+                );
+                let descriptor = element.init_method_descriptor(self.java, self.class.id);
+
+                self.make_room_in_part(usage)?;
+                let init_method = self.class_graph.add_method(MethodData {
+                    class: self.current_part.class.id,
+                    name: self
+                        .settings
+                        .wasm_element_init_name(element_idx, init_methods.len()),
+                    access_flags: MethodAccessFlags::STATIC,
+                    descriptor,
+                });
+                let mut code = CodeBuilder::new(self.class_graph, self.java, init_method);
+                self.elements[element_idx].generate_init_method(
+                    &mut code,
+                    &self.runtime,
+                    &self.functions,
+                    &self.globals,
+                    chunk_start,
+                )?;
+                self.current_part.add_method(
+                    Method {
+                        id: init_method,
+                        code_impl: Some(code.result()?),
+                        exceptions: vec![],
+                        generic_signature: None,
+                    },
+                    &self.settings,
+                )?;
+                init_methods.push(init_method);
+            }
+
+            let element = &self.elements[element_idx];
             self.class.add_method(element.generate_method(
                 self.class_graph,
                 self.java,
-                &self.runtime,
-                &self.functions,
-                &self.globals,
+                &init_methods,
             )?);
             self.class.add_field(Field::new(element.field));
         }
@@ -933,7 +1873,9 @@ impl<'a, 'g> ModuleTranslator<'a, 'g> {
                 let right_type = jvm_code.fresh_label();
                 jvm_code.dup()?;
                 jvm_code.invoke(self.java.members.lang.invoke.method_handle.r#type)?;
-                let expected_descriptor = function.func_type.method_descriptor(&self.java.classes);
+                let expected_descriptor = function
+                    .func_type
+                    .method_descriptor(&self.java.classes, &self.runtime.tuples);
                 match expected_descriptor.return_type {
                     Some(ret_class) => jvm_code.const_class(ret_class)?,
                     None => jvm_code
@@ -988,14 +1930,17 @@ impl<'a, 'g> ModuleTranslator<'a, 'g> {
                 jvm_code.access_field(self.runtime.members.global.value, AccessMode::Read)?;
                 jvm_code.invoke(self.java.members.lang.object.get_class)?;
                 let expected_class = match global.global_type {
-                    StackType::I32 => self.java.classes.lang.integer,
-                    StackType::I64 => self.java.classes.lang.long,
-                    StackType::F32 => self.java.classes.lang.float,
-                    StackType::F64 => self.java.classes.lang.double,
-                    StackType::FuncRef => self.java.classes.lang.invoke.method_handle,
-                    StackType::ExternRef => self.java.classes.lang.object,
+                    StackType::I32 => FieldType::object(self.java.classes.lang.integer),
+                    StackType::I64 => FieldType::object(self.java.classes.lang.long),
+                    StackType::F32 => FieldType::object(self.java.classes.lang.float),
+                    StackType::F64 => FieldType::object(self.java.classes.lang.double),
+                    StackType::V128 => StackType::V128.field_type(&self.java.classes),
+                    StackType::FuncRef => {
+                        FieldType::object(self.java.classes.lang.invoke.method_handle)
+                    }
+                    StackType::ExternRef => FieldType::object(self.java.classes.lang.object),
                 };
-                jvm_code.const_class(FieldType::object(expected_class))?;
+                jvm_code.const_class(expected_class)?;
                 jvm_code.invoke(self.java.members.lang.class.is_assignable_from)?;
                 jvm_code.push_branch_instruction(BranchInstruction::If(
                     OrdComparison::NE,
@@ -1049,7 +1994,11 @@ impl<'a, 'g> ModuleTranslator<'a, 'g> {
 
                 // Get the imported memory
                 Self::lookup_import(&mut jvm_code, import_loc)?;
-                jvm_code.checkcast(self.runtime.classes.memory)?;
+                if memory.is_memory64() {
+                    jvm_code.checkcast(self.runtime.classes.memory64)?;
+                } else {
+                    jvm_code.checkcast(self.runtime.classes.memory)?;
+                }
 
                 // Assign it to the right field
                 jvm_code.access_field(memory.field.unwrap(), AccessMode::Write)?;
@@ -1076,8 +2025,54 @@ impl<'a, 'g> ModuleTranslator<'a, 'g> {
                 break;
             }
         }
+        for tag in &self.tags {
+            if let Some(import_loc) = &tag.import {
+                jvm_code.push_instruction(Instruction::Dup2)?;
+
+                // Get the imported tag
+                Self::lookup_import(&mut jvm_code, import_loc)?;
+                jvm_code.checkcast(self.runtime.classes.tag)?;
+
+                // Check that it has the right signature
+                let right_type = jvm_code.fresh_label();
+                jvm_code.dup()?;
+                jvm_code.access_field(self.runtime.members.tag.r#type, AccessMode::Read)?;
+                jvm_code.push_instruction(Instruction::Ldc(ConstantData::MethodType(
+                    tag.payload_descriptor(&self.java.classes, &self.runtime),
+                )))?;
+                jvm_code.invoke(self.java.members.lang.object.equals)?;
+                jvm_code.push_branch_instruction(BranchInstruction::If(
+                    OrdComparison::NE,
+                    right_type,
+                    (),
+                ))?;
+                jvm_code.new(self.java.classes.lang.illegal_argument_exception)?;
+                jvm_code.push_instruction(Instruction::Dup)?;
+                jvm_code.const_string(format!(
+                    "Invalid import type for tag import {}.{} (expected {:?})",
+                    import_loc.module, import_loc.name, tag.tag_type,
+                ))?;
+                jvm_code.invoke(self.java.members.lang.illegal_argument_exception.init)?;
+                jvm_code.push_branch_instruction(BranchInstruction::AThrow)?;
+                jvm_code.place_label(right_type)?;
+
+                // Assign it to the right field
+                jvm_code.access_field(tag.field, AccessMode::Write)?;
+            } else {
+                break;
+            }
+        }
         jvm_code.push_instruction(Instruction::Pop2)?;
 
+        // Fresh tags
+        for tag in &self.tags {
+            if tag.import.is_none() {
+                jvm_code.push_instruction(Instruction::ALoad(0))?;
+                tag.new_tag(&self.runtime, &mut jvm_code)?;
+                jvm_code.access_field(tag.field, AccessMode::Write)?;
+            }
+        }
+
         // Initial table arrays
         for table in &self.tables {
             if table.import.is_none() {
@@ -1110,19 +2105,60 @@ impl<'a, 'g> ModuleTranslator<'a, 'g> {
         // Initialize memory
         for memory in &self.memories {
             if memory.import.is_none() {
-                if memory.memory_type.memory64 {
-                    todo!("64-bit memory")
+                if memory.is_memory64() {
+                    let maximum = memory
+                        .memory_type
+                        .maximum
+                        .unwrap_or(u64::MAX)
+                        .min(i32::MAX as u64);
+                    jvm_code.push_instruction(Instruction::ALoad(0))?;
+                    jvm_code.new(self.runtime.classes.memory64)?;
+                    jvm_code.push_instruction(Instruction::Dup)?;
+                    jvm_code.const_long(memory.memory_type.initial as i64)?;
+                    jvm_code.const_long(maximum as i64)?;
+                    jvm_code.invoke(self.runtime.members.memory64.init)?;
+                    jvm_code.access_field(memory.field.unwrap(), AccessMode::Write)?;
+                    continue;
                 }
 
                 let initial: u64 = memory.memory_type.initial * 65536;
                 jvm_code.push_instruction(Instruction::ALoad(0))?;
-                jvm_code.const_int(initial as i32)?; // TODO: error if too big
-                jvm_code.invoke(jvm_code.java.members.nio.byte_buffer.allocate)?; // TODO: add option for allocate direct
-                jvm_code.access_field(
-                    jvm_code.java.members.nio.byte_order.little_endian,
-                    AccessMode::Read,
-                )?;
-                jvm_code.invoke(jvm_code.java.members.nio.byte_buffer.order)?;
+                if memory.memory_type.shared {
+                    // Shared memories get their maximum size up front (see `Memory.grow`)
+                    let maximum = memory
+                        .memory_type
+                        .maximum
+                        .unwrap_or(u64::MAX)
+                        .min(i32::MAX as u64 >> 16);
+                    jvm_code.const_int((maximum << 16) as i32)?;
+                } else {
+                    jvm_code.const_int(initial as i32)?; // TODO: error if too big
+                }
+                match memory.backend {
+                    MemoryBackend::HeapByteBuffer | MemoryBackend::DirectByteBuffer => {
+                        let byte_buffer = &jvm_code.java.members.nio.byte_buffer;
+                        let allocate = if memory.backend == MemoryBackend::DirectByteBuffer {
+                            byte_buffer.allocate_direct
+                        } else {
+                            byte_buffer.allocate
+                        };
+                        jvm_code.invoke(allocate)?;
+                        jvm_code.access_field(
+                            jvm_code.java.members.nio.byte_order.little_endian,
+                            AccessMode::Read,
+                        )?;
+                        jvm_code.invoke(jvm_code.java.members.nio.byte_buffer.order)?;
+                        if memory.memory_type.shared {
+                            jvm_code.push_instruction(Instruction::Dup)?;
+                            jvm_code.const_int(initial as i32)?;
+                            jvm_code.invoke(jvm_code.java.members.nio.buffer.set_limit)?;
+                            jvm_code.push_instruction(Instruction::Pop)?;
+                        }
+                    }
+                    MemoryBackend::ByteArray => {
+                        jvm_code.push_instruction(Instruction::NewArray(BaseType::Byte))?;
+                    }
+                }
 
                 if let MemoryRepr::External = memory.repr {
                     jvm_code.new(self.runtime.classes.memory)?;
@@ -1135,9 +2171,9 @@ impl<'a, 'g> ModuleTranslator<'a, 'g> {
             }
         }
 
-        // Initialize globals
+        // Initialize globals (except for unreachable ones, which have no field)
         for global in &self.globals {
-            if let Some(init_expr) = &global.initial {
+            if let (Some(init_expr), Some(_)) = (&global.initial, global.field) {
                 jvm_code.push_instruction(Instruction::ALoad(0))?;
 
                 match global.repr {
@@ -1159,7 +2195,7 @@ impl<'a, 'g> ModuleTranslator<'a, 'g> {
                             StackType::F64 => {
                                 jvm_code.invoke(self.java.members.lang.double.value_of)?
                             }
-                            StackType::FuncRef | StackType::ExternRef => (),
+                            StackType::V128 | StackType::FuncRef | StackType::ExternRef => (),
                         }
                         jvm_code.const_int(global.mutable as i32)?;
                         jvm_code.invoke(self.runtime.members.global.init)?;
@@ -1219,23 +2255,47 @@ impl<'a, 'g> ModuleTranslator<'a, 'g> {
             {
                 let memory = &self.memories[memory_index as usize];
 
-                // Load onto the stack the memory bytebuffer
-                jvm_code.push_instruction(Instruction::ALoad(0))?;
-                memory.load_bytebuffer(&self.runtime, &mut jvm_code)?;
-
-                // Set the starting offset for the buffer
-                jvm_code.push_instruction(Instruction::Dup)?;
-                self.translate_const_expr(&mut jvm_code, &offset_expr)?;
-                jvm_code.invoke(jvm_code.java.members.nio.buffer.position)?;
-                jvm_code.push_instruction(Instruction::Pop)?;
-
-                // Get the data as a bytebuffer and put that
-                jvm_code.push_instruction(Instruction::ALoad(0))?;
-                jvm_code.invoke(data.method)?;
-                jvm_code.invoke(jvm_code.java.members.nio.byte_buffer.put_bytearray_relative)?;
-
-                // Kill the local variable, drop the bytebuffer
-                jvm_code.push_instruction(Instruction::Pop)?;
+                if memory.is_memory64() {
+                    // `memory.write(offset, data(), 0, data().length)`
+                    jvm_code.push_instruction(Instruction::ALoad(0))?;
+                    jvm_code.access_field(memory.field.unwrap(), AccessMode::Read)?;
+                    self.translate_const_expr(&mut jvm_code, &offset_expr)?;
+                    jvm_code.push_instruction(Instruction::ALoad(0))?;
+                    jvm_code.invoke(data.method)?;
+                    jvm_code.push_instruction(Instruction::IConst0)?;
+                    jvm_code.push_instruction(Instruction::ALoad(0))?;
+                    jvm_code.invoke(data.method)?;
+                    jvm_code.push_instruction(Instruction::ArrayLength)?;
+                    jvm_code.invoke(self.runtime.members.memory64.write)?;
+                } else if let MemoryBackend::ByteArray = memory.backend {
+                    // `System.arraycopy(data(), 0, memory_bytes, offset, data().length)`
+                    jvm_code.push_instruction(Instruction::ALoad(0))?;
+                    jvm_code.invoke(data.method)?;
+                    jvm_code.push_instruction(Instruction::IConst0)?;
+                    jvm_code.push_instruction(Instruction::ALoad(0))?;
+                    memory.load_bytes(&self.runtime, &mut jvm_code)?;
+                    self.translate_const_expr(&mut jvm_code, &offset_expr)?;
+                    jvm_code.push_instruction(Instruction::ALoad(0))?;
+                    jvm_code.invoke(data.method)?;
+                    jvm_code.push_instruction(Instruction::ArrayLength)?;
+                    jvm_code.invoke(jvm_code.java.members.lang.system.arraycopy)?;
+                } else {
+                    // `memory_bytebuffer.put(offset, data(), 0, data().length)`
+                    //
+                    // The put is absolute, since a shared memory could already be in use by
+                    // other threads.
+                    jvm_code.push_instruction(Instruction::ALoad(0))?;
+                    memory.load_bytes(&self.runtime, &mut jvm_code)?;
+                    self.translate_const_expr(&mut jvm_code, &offset_expr)?;
+                    jvm_code.push_instruction(Instruction::ALoad(0))?;
+                    jvm_code.invoke(data.method)?;
+                    jvm_code.push_instruction(Instruction::IConst0)?;
+                    jvm_code.push_instruction(Instruction::ALoad(0))?;
+                    jvm_code.invoke(data.method)?;
+                    jvm_code.push_instruction(Instruction::ArrayLength)?;
+                    jvm_code.invoke(jvm_code.java.members.nio.byte_buffer.put_bytearray)?;
+                    jvm_code.push_instruction(Instruction::Pop)?;
+                }
 
                 data.drop_data(&mut jvm_code, 0)?;
             }
@@ -1274,32 +2334,10 @@ impl<'a, 'g> ModuleTranslator<'a, 'g> {
                 jvm_code.push_instruction(Instruction::Dup)?;
                 jvm_code.const_string(name.to_string())?;
 
-                // Implementation function
-                let method = function.method;
-                let method_handle = ConstantData::MethodHandle(method);
-
                 // `new org.wasm2jar.Function(handle);`
                 jvm_code.new(self.runtime.classes.function)?;
                 jvm_code.push_instruction(Instruction::Dup)?;
-
-                // `MethodHandles.insertArguments(hdl, n - 1, new Object[1] { this })`
-                jvm_code.push_instruction(Instruction::Ldc(method_handle))?;
-                jvm_code.const_int((method.descriptor.parameters.len() - 1) as i32)?;
-                jvm_code.const_int(1)?;
-                jvm_code.new_ref_array(RefType::Object(jvm_code.java.classes.lang.object))?;
-                jvm_code.dup()?;
-                jvm_code.const_int(0)?;
-                jvm_code.push_instruction(Instruction::ALoad(0))?;
-                jvm_code.push_instruction(Instruction::AAStore)?;
-                jvm_code.invoke(
-                    jvm_code
-                        .java
-                        .members
-                        .lang
-                        .invoke
-                        .method_handles
-                        .insert_arguments,
-                )?;
+                function.load_reference(&self.runtime, &mut jvm_code, 0)?;
                 jvm_code.invoke(self.runtime.members.function.init)?;
 
                 // Put the value in the map
@@ -1359,6 +2397,22 @@ impl<'a, 'g> ModuleTranslator<'a, 'g> {
             }
         }
 
+        // Add tag exports to the exports map
+        for tag in &self.tags {
+            for ExportName { name } in &tag.export {
+                jvm_code.push_instruction(Instruction::Dup)?;
+                jvm_code.const_string(name.to_string())?;
+
+                // Get tag
+                jvm_code.push_instruction(Instruction::ALoad(0))?;
+                tag.load_tag(&mut jvm_code)?;
+
+                // Put the value in the map
+                jvm_code.invoke(jvm_code.java.members.util.map.put)?;
+                jvm_code.pop()?;
+            }
+        }
+
         jvm_code.push_instruction(Instruction::PutField(exports_field))?;
 
         // Main function, if there is one
@@ -1399,12 +2453,14 @@ impl<'a, 'g> ModuleTranslator<'a, 'g> {
         )
     }
 
-    /// Emit the final classes
+    /// Emit the final classes and resources
     ///
-    /// The first element in the output vector is the output class. The rest of the elements are
-    /// the "part" inner classes.
-    pub fn result(mut self) -> Result<Vec<(BinaryName, class_file::ClassFile)>, Error> {
+    /// The first class in the output is the output class. The rest of the classes are the "part"
+    /// inner classes. Resources are only output if data segments are stored in them (see
+    /// `Settings::data_storage`).
+    pub fn result(mut self) -> Result<Translation, Error> {
         self.generate_exports()?;
+        self.generate_tailcall_adapters()?;
         self.generate_constant_segments()?;
         self.generate_constructor()?;
 
@@ -1415,25 +2471,51 @@ impl<'a, 'g> ModuleTranslator<'a, 'g> {
             make_function_table_class(self.class_graph, self.java, &self.runtime)?,
             make_reference_table_class(self.class_graph, self.java, &self.runtime)?,
             make_memory_class(self.class_graph, self.java, &self.runtime)?,
+            make_memory64_class(self.class_graph, self.java, &self.runtime)?,
+            make_tail_call_class(self.class_graph, self.java, &self.runtime)?,
+            make_tag_class(self.class_graph, self.java, &self.runtime)?,
+            make_wasm_exception_class(self.class_graph, self.java, &self.runtime)?,
+            make_tuple_class(self.class_graph, self.java, &self.runtime)?,
         ];
-
-        // Assemble all the parts
-        let mut parts = self.previous_parts;
-        parts.push(self.current_part.result()?);
-
-        // Final results
-        let results: Vec<(BinaryName, class_file::ClassFile)> = iter::once(self.class)
+        let tuple_classes =
+            self.runtime
+                .tuples
+                .make_classes(self.class_graph, self.java, &self.runtime)?;
+
+        // Final results (parts have been serialized as they went)
+        let peephole_optimization = self.settings.peephole_optimization;
+        let serialize = |mut builder: Class<'g>| {
+            if peephole_optimization {
+                for method in &mut builder.methods {
+                    if let Some(code) = &mut method.code_impl {
+                        code.optimize_peephole();
+                    }
+                }
+            }
+            let name = builder.id.name.clone();
+            builder
+                .serialize(class_file::Version::JAVA11)
+                .map(|cls| (name, cls))
+        };
+        let mut classes: Vec<(BinaryName, class_file::ClassFile)> = iter::once(self.class)
             .chain(self.utilities.into_builder().into_iter())
-            .chain(parts.into_iter())
-            .chain(runtime_classes.into_iter())
-            .map(|builder| {
-                let name = builder.id.name.clone();
-                builder
-                    .serialize(class_file::Version::JAVA11)
-                    .map(|cls| (name, cls))
-            })
+            .map(serialize)
             .collect::<Result<Vec<_>, jvm::Error>>()?;
+        for part in self
+            .previous_parts
+            .into_iter()
+            .chain(iter::once(self.current_part))
+        {
+            let name = part.class.id.name.clone();
+            classes.push((name, part.result(class_file::Version::JAVA11)?));
+        }
+        for class in runtime_classes.into_iter().chain(tuple_classes) {
+            classes.push(serialize(class)?);
+        }
 
-        Ok(results)
+        Ok(Translation {
+            classes,
+            resources: self.resources,
+        })
     }
 }
@@ -0,0 +1,1094 @@
+//! WASM-level optimization of function bodies
+//!
+//! Before a function body gets translated, its operators are run through a few rewriting passes
+//! (how many depends on `Settings::optimization_level`):
+//!
+//!   * constant folding, including branches and `select`s on constant conditions
+//!   * removal of dead code after `unreachable`, `br`, `return`, etc. and in `if` arms which can
+//!     never be taken
+//!   * simplification of redundant pairs of `local.get`/`local.set`/`local.tee`/`drop`
+//!   * removal of redundant bounds checks: static offsets are folded into constant addresses (so
+//!     there is no overflow to check), and a bounds check which already passed earlier in the
+//!     same straight-line code is dropped
+//!
+//! Every pass maps a valid sequence of operators onto a valid sequence of operators with the same
+//! behaviour. The translator (which drives a validator over the operators it translates) only
+//! ever sees the optimized operators, so the original operators get validated on their own first
+//! (otherwise invalid dead code could just disappear).
+//!
+//! Operators replacing a run of original operators get the offset of the first operator in the
+//! run. The `block`, `loop`, and `end` operators delimiting split regions keep their offsets (see
+//! the `split` module), and regions which end up in dead code just never get visited.
+
+use super::Error;
+use std::mem;
+use wasmparser::{BlockType, FuncValidator, FunctionBody, Operator, WasmModuleResources};
+
+/// Operator in a function body, along with its offset
+pub type OffsetOperator<'a> = (Operator<'a>, usize);
+
+/// Maximum number of rounds of passes (passes get re-run as long as they keep changing things)
+const MAX_OPTIMIZATION_ROUNDS: usize = 4;
+
+/// Read all the operators of a function body
+pub fn read_operators<'a>(
+    function_body: &FunctionBody<'a>,
+) -> Result<Vec<OffsetOperator<'a>>, Error> {
+    let mut operators = vec![];
+    for operator in function_body
+        .get_operators_reader()?
+        .into_iter_with_offsets()
+    {
+        operators.push(operator?);
+    }
+    Ok(operators)
+}
+
+/// Validate a function body (whose operators have already been read)
+pub fn validate_operators<T: WasmModuleResources>(
+    function_body: &FunctionBody,
+    operators: &[OffsetOperator],
+    validator: &mut FuncValidator<T>,
+) -> Result<(), Error> {
+    validator.read_locals(&mut function_body.get_binary_reader())?;
+    let mut last_offset = 0;
+    for (operator, offset) in operators {
+        validator.op(*offset, operator)?;
+        last_offset = *offset;
+    }
+    validator.finish(last_offset + 1)?;
+    Ok(())
+}
+
+/// Optimize the (already validated) operators of a function body
+pub fn optimize_operators(operators: &mut Vec<OffsetOperator>, optimization_level: u8) {
+    if optimization_level == 0 {
+        return;
+    }
+
+    for _ in 0..MAX_OPTIMIZATION_ROUNDS {
+        let mut changed = false;
+        changed |= fold_constants(operators);
+        changed |= simplify_locals(operators);
+        changed |= remove_dead_code(operators);
+        if optimization_level >= 2 {
+            changed |= remove_redundant_bounds_checks(operators);
+        }
+        if !changed {
+            break;
+        }
+    }
+}
+
+/// Fold operators on constants into constants
+///
+/// This also folds identities (eg. adding `0`), dropped constants, and branches or `select`s on a
+/// constant condition. Constant `if`s are left to `remove_dead_code`.
+fn fold_constants(operators: &mut Vec<OffsetOperator>) -> bool {
+    let mut changed = false;
+    for (operator, offset) in mem::take(operators) {
+        let len = operators.len();
+
+        // Binary operators with constant operands
+        if len >= 2 {
+            if let Some(folded) =
+                fold_binary(&operators[len - 2].0, &operators[len - 1].0, &operator)
+            {
+                let first_offset = operators[len - 2].1;
+                operators.truncate(len - 2);
+                operators.push((folded, first_offset));
+                changed = true;
+                continue;
+            }
+        }
+
+        // Operators with a constant operand on the top of the stack
+        let (top, top_offset) = match operators.last() {
+            Some((top, top_offset)) => (top, *top_offset),
+            None => {
+                operators.push((operator, offset));
+                continue;
+            }
+        };
+        if let Some(folded) = fold_unary(top, &operator) {
+            operators.pop();
+            operators.push((folded, top_offset));
+            changed = true;
+        } else if is_identity(top, &operator) || (is_constant(top) && is_drop(&operator)) {
+            operators.pop();
+            changed = true;
+        } else if let Operator::I32Const { value } = *top {
+            match operator {
+                Operator::BrIf { relative_depth } => {
+                    operators.pop();
+                    if value != 0 {
+                        operators.push((Operator::Br { relative_depth }, top_offset));
+                    }
+                    changed = true;
+                }
+                Operator::BrTable { targets } => {
+                    let index = value as u32;
+                    let relative_depth = if index < targets.len() {
+                        match targets.targets().nth(index as usize) {
+                            Some(Ok(relative_depth)) => relative_depth,
+                            _ => targets.default(),
+                        }
+                    } else {
+                        targets.default()
+                    };
+                    operators.pop();
+                    operators.push((Operator::Br { relative_depth }, top_offset));
+                    changed = true;
+                }
+
+                // Only the second operand needs to be dropped
+                Operator::Select | Operator::TypedSelect { .. } if value != 0 => {
+                    operators.pop();
+                    operators.push((Operator::Drop, top_offset));
+                    changed = true;
+                }
+                operator => operators.push((operator, offset)),
+            }
+        } else {
+            operators.push((operator, offset));
+        }
+    }
+    changed
+}
+
+/// Fold a binary operator on two constants
+fn fold_binary<'a>(lhs: &Operator, rhs: &Operator, operator: &Operator) -> Option<Operator<'a>> {
+    match (lhs, rhs) {
+        (Operator::I32Const { value: a }, Operator::I32Const { value: b }) => {
+            fold_i32_binary(*a, *b, operator)
+        }
+        (Operator::I64Const { value: a }, Operator::I64Const { value: b }) => {
+            fold_i64_binary(*a, *b, operator)
+        }
+        _ => None,
+    }
+}
+
+/// Fold a binary operator on two `i32` constants
+///
+/// Divisions and remainders which would trap are left alone.
+fn fold_i32_binary<'a>(a: i32, b: i32, operator: &Operator) -> Option<Operator<'a>> {
+    let (ua, ub) = (a as u32, b as u32);
+    let value = match operator {
+        Operator::I32Add => a.wrapping_add(b),
+        Operator::I32Sub => a.wrapping_sub(b),
+        Operator::I32Mul => a.wrapping_mul(b),
+        Operator::I32DivS if b != 0 && (a != i32::MIN || b != -1) => a / b,
+        Operator::I32DivU if b != 0 => (ua / ub) as i32,
+        Operator::I32RemS if b != 0 => a.wrapping_rem(b),
+        Operator::I32RemU if b != 0 => (ua % ub) as i32,
+        Operator::I32And => a & b,
+        Operator::I32Or => a | b,
+        Operator::I32Xor => a ^ b,
+        Operator::I32Shl => a.wrapping_shl(ub),
+        Operator::I32ShrS => a.wrapping_shr(ub),
+        Operator::I32ShrU => ua.wrapping_shr(ub) as i32,
+        Operator::I32Rotl => ua.rotate_left(ub & 31) as i32,
+        Operator::I32Rotr => ua.rotate_right(ub & 31) as i32,
+        Operator::I32Eq => (a == b) as i32,
+        Operator::I32Ne => (a != b) as i32,
+        Operator::I32LtS => (a < b) as i32,
+        Operator::I32LtU => (ua < ub) as i32,
+        Operator::I32GtS => (a > b) as i32,
+        Operator::I32GtU => (ua > ub) as i32,
+        Operator::I32LeS => (a <= b) as i32,
+        Operator::I32LeU => (ua <= ub) as i32,
+        Operator::I32GeS => (a >= b) as i32,
+        Operator::I32GeU => (ua >= ub) as i32,
+        _ => return None,
+    };
+    Some(Operator::I32Const { value })
+}
+
+/// Fold a binary operator on two `i64` constants
+///
+/// Divisions and remainders which would trap are left alone.
+fn fold_i64_binary<'a>(a: i64, b: i64, operator: &Operator) -> Option<Operator<'a>> {
+    let (ua, ub) = (a as u64, b as u64);
+    let value = match operator {
+        Operator::I64Add => a.wrapping_add(b),
+        Operator::I64Sub => a.wrapping_sub(b),
+        Operator::I64Mul => a.wrapping_mul(b),
+        Operator::I64DivS if b != 0 && (a != i64::MIN || b != -1) => a / b,
+        Operator::I64DivU if b != 0 => (ua / ub) as i64,
+        Operator::I64RemS if b != 0 => a.wrapping_rem(b),
+        Operator::I64RemU if b != 0 => (ua % ub) as i64,
+        Operator::I64And => a & b,
+        Operator::I64Or => a | b,
+        Operator::I64Xor => a ^ b,
+        Operator::I64Shl => a.wrapping_shl(ub as u32),
+        Operator::I64ShrS => a.wrapping_shr(ub as u32),
+        Operator::I64ShrU => ua.wrapping_shr(ub as u32) as i64,
+        Operator::I64Rotl => ua.rotate_left((ub & 63) as u32) as i64,
+        Operator::I64Rotr => ua.rotate_right((ub & 63) as u32) as i64,
+        Operator::I64Eq => {
+            return Some(Operator::I32Const {
+                value: (a == b) as i32,
+            })
+        }
+        Operator::I64Ne => {
+            return Some(Operator::I32Const {
+                value: (a != b) as i32,
+            })
+        }
+        Operator::I64LtS => {
+            return Some(Operator::I32Const {
+                value: (a < b) as i32,
+            })
+        }
+        Operator::I64LtU => {
+            return Some(Operator::I32Const {
+                value: (ua < ub) as i32,
+            })
+        }
+        Operator::I64GtS => {
+            return Some(Operator::I32Const {
+                value: (a > b) as i32,
+            })
+        }
+        Operator::I64GtU => {
+            return Some(Operator::I32Const {
+                value: (ua > ub) as i32,
+            })
+        }
+        Operator::I64LeS => {
+            return Some(Operator::I32Const {
+                value: (a <= b) as i32,
+            })
+        }
+        Operator::I64LeU => {
+            return Some(Operator::I32Const {
+                value: (ua <= ub) as i32,
+            })
+        }
+        Operator::I64GeS => {
+            return Some(Operator::I32Const {
+                value: (a >= b) as i32,
+            })
+        }
+        Operator::I64GeU => {
+            return Some(Operator::I32Const {
+                value: (ua >= ub) as i32,
+            })
+        }
+        _ => return None,
+    };
+    Some(Operator::I64Const { value })
+}
+
+/// Fold a unary operator on a constant
+fn fold_unary<'a>(operand: &Operator, operator: &Operator) -> Option<Operator<'a>> {
+    let folded = match (operand, operator) {
+        (Operator::I32Const { value }, operator) => {
+            let value = *value;
+            match operator {
+                Operator::I32Eqz => Operator::I32Const {
+                    value: (value == 0) as i32,
+                },
+                Operator::I32Clz => Operator::I32Const {
+                    value: value.leading_zeros() as i32,
+                },
+                Operator::I32Ctz => Operator::I32Const {
+                    value: value.trailing_zeros() as i32,
+                },
+                Operator::I32Popcnt => Operator::I32Const {
+                    value: value.count_ones() as i32,
+                },
+                Operator::I32Extend8S => Operator::I32Const {
+                    value: value as i8 as i32,
+                },
+                Operator::I32Extend16S => Operator::I32Const {
+                    value: value as i16 as i32,
+                },
+                Operator::I64ExtendI32S => Operator::I64Const {
+                    value: value as i64,
+                },
+                Operator::I64ExtendI32U => Operator::I64Const {
+                    value: value as u32 as i64,
+                },
+                _ => return None,
+            }
+        }
+        (Operator::I64Const { value }, operator) => {
+            let value = *value;
+            match operator {
+                Operator::I64Eqz => Operator::I32Const {
+                    value: (value == 0) as i32,
+                },
+                Operator::I64Clz => Operator::I64Const {
+                    value: value.leading_zeros() as i64,
+                },
+                Operator::I64Ctz => Operator::I64Const {
+                    value: value.trailing_zeros() as i64,
+                },
+                Operator::I64Popcnt => Operator::I64Const {
+                    value: value.count_ones() as i64,
+                },
+                Operator::I64Extend8S => Operator::I64Const {
+                    value: value as i8 as i64,
+                },
+                Operator::I64Extend16S => Operator::I64Const {
+                    value: value as i16 as i64,
+                },
+                Operator::I64Extend32S => Operator::I64Const {
+                    value: value as i32 as i64,
+                },
+                Operator::I32WrapI64 => Operator::I32Const {
+                    value: value as i32,
+                },
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+    Some(folded)
+}
+
+/// Check if a binary operator with a constant right operand just returns its left operand
+fn is_identity(rhs: &Operator, operator: &Operator) -> bool {
+    match *rhs {
+        Operator::I32Const { value } => match operator {
+            Operator::I32Add | Operator::I32Sub | Operator::I32Or | Operator::I32Xor => value == 0,
+            Operator::I32Shl
+            | Operator::I32ShrS
+            | Operator::I32ShrU
+            | Operator::I32Rotl
+            | Operator::I32Rotr => value & 31 == 0,
+            Operator::I32Mul | Operator::I32DivS | Operator::I32DivU => value == 1,
+            Operator::I32And => value == -1,
+            _ => false,
+        },
+        Operator::I64Const { value } => match operator {
+            Operator::I64Add | Operator::I64Sub | Operator::I64Or | Operator::I64Xor => value == 0,
+            Operator::I64Shl
+            | Operator::I64ShrS
+            | Operator::I64ShrU
+            | Operator::I64Rotl
+            | Operator::I64Rotr => value & 63 == 0,
+            Operator::I64Mul | Operator::I64DivS | Operator::I64DivU => value == 1,
+            Operator::I64And => value == -1,
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Check if an operator just pushes a constant
+fn is_constant(operator: &Operator) -> bool {
+    matches!(
+        operator,
+        Operator::I32Const { .. }
+            | Operator::I64Const { .. }
+            | Operator::F32Const { .. }
+            | Operator::F64Const { .. }
+            | Operator::V128Const { .. }
+            | Operator::RefNull { .. }
+            | Operator::RefFunc { .. }
+    )
+}
+
+fn is_drop(operator: &Operator) -> bool {
+    matches!(operator, Operator::Drop)
+}
+
+/// Simplify redundant pairs of local operators
+///
+///   * `local.set x; local.get x` becomes `local.tee x`
+///   * `local.tee x; drop` becomes `local.set x`
+///   * `local.get x; local.set x` and `local.get x; drop` (or `global.get`) get removed
+///   * `local.get x; local.tee x` becomes `local.get x`
+fn simplify_locals(operators: &mut Vec<OffsetOperator>) -> bool {
+    let mut changed = false;
+    for (operator, offset) in mem::take(operators) {
+        let top = operators.last().map(|(top, top_offset)| (top, *top_offset));
+        match (top, &operator) {
+            (
+                Some((Operator::LocalSet { local_index: x }, top_offset)),
+                Operator::LocalGet { local_index: y },
+            ) if x == y => {
+                let local_index = *x;
+                operators.pop();
+                operators.push((Operator::LocalTee { local_index }, top_offset));
+            }
+            (Some((Operator::LocalTee { local_index }, top_offset)), Operator::Drop) => {
+                let local_index = *local_index;
+                operators.pop();
+                operators.push((Operator::LocalSet { local_index }, top_offset));
+            }
+            (
+                Some((Operator::LocalGet { local_index: x }, _)),
+                Operator::LocalSet { local_index: y },
+            ) if x == y => {
+                operators.pop();
+            }
+            (Some((Operator::LocalGet { .. } | Operator::GlobalGet { .. }, _)), Operator::Drop) => {
+                operators.pop();
+            }
+            (
+                Some((Operator::LocalGet { local_index: x }, _)),
+                Operator::LocalTee { local_index: y },
+            ) if x == y => {}
+            _ => {
+                operators.push((operator, offset));
+                continue;
+            }
+        }
+        changed = true;
+    }
+    changed
+}
+
+/// Frame opened while removing dead code
+enum DeadCodeFrame {
+    /// Frame which is kept as is
+    Kept,
+
+    /// `if` on a constant condition, which has been turned into a `block` (the boolean is whether
+    /// the condition is true, meaning the `then` arm is kept and the `else` arm is dead)
+    ConstantIf(bool),
+}
+
+/// Remove operators which can never be reached
+///
+/// Everything following an unconditional control transfer is dead until the end of the enclosing
+/// frame (or the start of its `else`/`catch` clauses). An `if` on a constant condition becomes a
+/// `block` containing just the arm which can be taken.
+fn remove_dead_code(operators: &mut Vec<OffsetOperator>) -> bool {
+    let mut changed = false;
+    let mut frames: Vec<DeadCodeFrame> = vec![];
+
+    // When in dead code, the number of frames opened in the dead code
+    let mut dead_depth: Option<usize> = None;
+
+    for (operator, offset) in mem::take(operators) {
+        if let Some(depth) = dead_depth.as_mut() {
+            match operator {
+                Operator::Block { .. }
+                | Operator::Loop { .. }
+                | Operator::If { .. }
+                | Operator::Try { .. } => *depth += 1,
+                Operator::End | Operator::Delegate { .. } if *depth > 0 => *depth -= 1,
+                Operator::End
+                | Operator::Else
+                | Operator::Catch { .. }
+                | Operator::CatchAll
+                | Operator::Delegate { .. } => dead_depth = None,
+                _ => (),
+            }
+            if dead_depth.is_some() {
+                changed = true;
+                continue;
+            }
+        }
+
+        match operator {
+            Operator::Block { .. } | Operator::Loop { .. } | Operator::Try { .. } => {
+                frames.push(DeadCodeFrame::Kept);
+            }
+            Operator::If { blockty } => {
+                if let Some((Operator::I32Const { value }, const_offset)) = operators.last() {
+                    let (value, const_offset) = (*value, *const_offset);
+                    operators.pop();
+                    operators.push((Operator::Block { blockty }, const_offset));
+                    frames.push(DeadCodeFrame::ConstantIf(value != 0));
+                    if value == 0 {
+                        dead_depth = Some(0);
+                    }
+                    changed = true;
+                    continue;
+                }
+                frames.push(DeadCodeFrame::Kept);
+            }
+            Operator::Else => {
+                if let Some(DeadCodeFrame::ConstantIf(taken)) = frames.last() {
+                    if *taken {
+                        dead_depth = Some(0);
+                    }
+                    changed = true;
+                    continue;
+                }
+            }
+            Operator::End | Operator::Delegate { .. } => {
+                frames.pop();
+            }
+            Operator::Unreachable
+            | Operator::Br { .. }
+            | Operator::BrTable { .. }
+            | Operator::Return
+            | Operator::ReturnCall { .. }
+            | Operator::ReturnCallIndirect { .. }
+            | Operator::Throw { .. }
+            | Operator::Rethrow { .. } => dead_depth = Some(0),
+            _ => (),
+        }
+        operators.push((operator, offset));
+    }
+    changed
+}
+
+/// Operand compared against in a bounds check
+#[derive(PartialEq, Eq)]
+enum CheckOperand {
+    Local(u32),
+    I32(i32),
+    I64(i64),
+}
+
+impl CheckOperand {
+    fn from_operator(operator: &Operator) -> Option<CheckOperand> {
+        match *operator {
+            Operator::LocalGet { local_index } => Some(CheckOperand::Local(local_index)),
+            Operator::I32Const { value } => Some(CheckOperand::I32(value)),
+            Operator::I64Const { value } => Some(CheckOperand::I64(value)),
+            _ => None,
+        }
+    }
+}
+
+/// What a bounds check does when it fails
+#[derive(PartialEq, Eq)]
+enum CheckExit {
+    /// `br_if` to a label carrying no values
+    BranchIf(u32),
+
+    /// `if unreachable end`
+    Trap,
+}
+
+/// Bounds check which leaves the stack untouched: `local.get a`, `b` (a local or a constant), an
+/// integer comparison, and then either `br_if` or `if unreachable end`
+///
+/// Once such a check is passed, running it again before either `a` or `b` is changed has no
+/// effect.
+#[derive(PartialEq, Eq)]
+struct BoundsCheck {
+    lhs: u32,
+    rhs: CheckOperand,
+    comparison: u8,
+    exit: CheckExit,
+}
+
+impl BoundsCheck {
+    /// Number of operators in the check
+    fn len(&self) -> usize {
+        match self.exit {
+            CheckExit::BranchIf(_) => 4,
+            CheckExit::Trap => 6,
+        }
+    }
+
+    /// Recognize the check which starts with the given operators (the ones making up the
+    /// condition and the operator right after it)
+    fn from_condition(
+        lhs: &Operator,
+        rhs: &Operator,
+        comparison: &Operator,
+        exit: CheckExit,
+    ) -> Option<BoundsCheck> {
+        let lhs = match *lhs {
+            Operator::LocalGet { local_index } => local_index,
+            _ => return None,
+        };
+        Some(BoundsCheck {
+            lhs,
+            rhs: CheckOperand::from_operator(rhs)?,
+            comparison: integer_comparison(comparison)?,
+            exit,
+        })
+    }
+
+    fn uses_local(&self, local_index: u32) -> bool {
+        self.lhs == local_index || self.rhs == CheckOperand::Local(local_index)
+    }
+}
+
+/// Identify integer comparison operators
+fn integer_comparison(operator: &Operator) -> Option<u8> {
+    let comparison = match operator {
+        Operator::I32Eq => 0,
+        Operator::I32Ne => 1,
+        Operator::I32LtS => 2,
+        Operator::I32LtU => 3,
+        Operator::I32GtS => 4,
+        Operator::I32GtU => 5,
+        Operator::I32LeS => 6,
+        Operator::I32LeU => 7,
+        Operator::I32GeS => 8,
+        Operator::I32GeU => 9,
+        Operator::I64Eq => 10,
+        Operator::I64Ne => 11,
+        Operator::I64LtS => 12,
+        Operator::I64LtU => 13,
+        Operator::I64GtS => 14,
+        Operator::I64GtU => 15,
+        Operator::I64LeS => 16,
+        Operator::I64LeU => 17,
+        Operator::I64GeS => 18,
+        Operator::I64GeU => 19,
+        _ => return None,
+    };
+    Some(comparison)
+}
+
+/// Number of values carried by a branch to a frame (if known without looking up types)
+fn branch_arity(blockty: BlockType, is_loop: bool) -> Option<usize> {
+    match blockty {
+        BlockType::Empty => Some(0),
+        BlockType::Type(_) => Some(if is_loop { 0 } else { 1 }),
+        BlockType::FuncType(_) => None,
+    }
+}
+
+/// Remove redundant bounds checks
+///
+/// Static offsets of loads and stores to constant addresses get folded into the address, which
+/// saves checking the effective address for overflow. Bounds checks (see `BoundsCheck`) which
+/// have already been passed in the same straight-line code are removed.
+fn remove_redundant_bounds_checks(operators: &mut Vec<OffsetOperator>) -> bool {
+    let mut changed = false;
+
+    // Branch arity of each open frame
+    let mut frames: Vec<Option<usize>> = vec![];
+
+    // Checks which have been passed since the start of the straight-line code
+    let mut passed: Vec<BoundsCheck> = vec![];
+
+    for (mut operator, offset) in mem::take(operators) {
+        changed |= fold_constant_address(operators, &mut operator);
+        let len = operators.len();
+
+        // Recognize a bounds check ending with this operator
+        let check = match operator {
+            Operator::BrIf { relative_depth } if len >= 3 => {
+                let arity = frames
+                    .len()
+                    .checked_sub(relative_depth as usize + 1)
+                    .and_then(|idx| frames[idx]);
+                if arity == Some(0) {
+                    BoundsCheck::from_condition(
+                        &operators[len - 3].0,
+                        &operators[len - 2].0,
+                        &operators[len - 1].0,
+                        CheckExit::BranchIf(relative_depth),
+                    )
+                } else {
+                    None
+                }
+            }
+            Operator::End if len >= 5 => match (&operators[len - 2].0, &operators[len - 1].0) {
+                (
+                    Operator::If {
+                        blockty: BlockType::Empty,
+                    },
+                    Operator::Unreachable,
+                ) => BoundsCheck::from_condition(
+                    &operators[len - 5].0,
+                    &operators[len - 4].0,
+                    &operators[len - 3].0,
+                    CheckExit::Trap,
+                ),
+                _ => None,
+            },
+            _ => None,
+        };
+        if let Some(check) = check {
+            if let CheckExit::Trap = check.exit {
+                frames.pop();
+            }
+            if passed.contains(&check) {
+                operators.truncate(len + 1 - check.len());
+                changed = true;
+            } else {
+                passed.push(check);
+                operators.push((operator, offset));
+            }
+            continue;
+        }
+
+        match operator {
+            Operator::Block { blockty } | Operator::If { blockty } | Operator::Try { blockty } => {
+                frames.push(branch_arity(blockty, false));
+            }
+            Operator::Loop { blockty } => frames.push(branch_arity(blockty, true)),
+            Operator::End | Operator::Delegate { .. } => {
+                frames.pop();
+            }
+            Operator::LocalSet { local_index } | Operator::LocalTee { local_index } => {
+                passed.retain(|check| !check.uses_local(local_index));
+            }
+            _ => (),
+        }
+
+        // Checks passed before an `if` still hold in its `then` arm, but the other frame
+        // boundaries are where control flow can merge in from elsewhere
+        match operator {
+            Operator::Block { .. }
+            | Operator::Loop { .. }
+            | Operator::Try { .. }
+            | Operator::Else
+            | Operator::Catch { .. }
+            | Operator::CatchAll
+            | Operator::End
+            | Operator::Delegate { .. } => passed.clear(),
+            _ => (),
+        }
+
+        operators.push((operator, offset));
+    }
+    changed
+}
+
+/// Fold the static offset of a load or store into a constant address
+///
+/// This only happens when the folded address is known to not overflow (so the access behaves the
+/// same). For stores, the address is under the value being stored, so this only catches values
+/// which are pushed by a single operator.
+fn fold_constant_address(operators: &mut [OffsetOperator], operator: &mut Operator) -> bool {
+    let (memarg, is_store) = match operator {
+        Operator::I32Load { memarg }
+        | Operator::I64Load { memarg }
+        | Operator::F32Load { memarg }
+        | Operator::F64Load { memarg }
+        | Operator::I32Load8S { memarg }
+        | Operator::I32Load8U { memarg }
+        | Operator::I32Load16S { memarg }
+        | Operator::I32Load16U { memarg }
+        | Operator::I64Load8S { memarg }
+        | Operator::I64Load8U { memarg }
+        | Operator::I64Load16S { memarg }
+        | Operator::I64Load16U { memarg }
+        | Operator::I64Load32S { memarg }
+        | Operator::I64Load32U { memarg }
+        | Operator::V128Load { memarg } => (memarg, false),
+        Operator::I32Store { memarg }
+        | Operator::I64Store { memarg }
+        | Operator::F32Store { memarg }
+        | Operator::F64Store { memarg }
+        | Operator::I32Store8 { memarg }
+        | Operator::I32Store16 { memarg }
+        | Operator::I64Store8 { memarg }
+        | Operator::I64Store16 { memarg }
+        | Operator::I64Store32 { memarg }
+        | Operator::V128Store { memarg } => (memarg, true),
+        _ => return false,
+    };
+    if memarg.offset == 0 {
+        return false;
+    }
+
+    let address_idx = match (is_store, operators.len()) {
+        (false, len) if len >= 1 => len - 1,
+        (true, len) if len >= 2 => {
+            let value = &operators[len - 1].0;
+            if !is_constant(value)
+                && !matches!(
+                    value,
+                    Operator::LocalGet { .. } | Operator::GlobalGet { .. }
+                )
+            {
+                return false;
+            }
+            len - 2
+        }
+        _ => return false,
+    };
+    match &mut operators[address_idx].0 {
+        // Addresses into 32-bit memories past `i32::MAX` are out of bounds anyway
+        Operator::I32Const { value } => {
+            let address = *value as u32 as u64 + memarg.offset;
+            if address > i32::MAX as u64 {
+                return false;
+            }
+            *value = address as i32;
+        }
+        Operator::I64Const { value } => match (*value as u64).checked_add(memarg.offset) {
+            Some(address) => *value = address as i64,
+            None => return false,
+        },
+        _ => return false,
+    }
+    memarg.offset = 0;
+    true
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::translate::test_util::{assemble, first_function_operators, render_operators};
+
+    /// Run a pass over a function (with a memory and a tag `$e` in scope) and check its result
+    /// is the second function
+    fn check_pass(
+        pass: fn(&mut Vec<OffsetOperator>) -> bool,
+        function: &str,
+        expected_function: &str,
+    ) {
+        let module = |function: &str| format!("(module (memory 1) (tag $e) {})", function);
+        let wasm = assemble(&module(function));
+        let mut operators = first_function_operators(&wasm);
+        let changed = pass(&mut operators);
+
+        let expected_wasm = assemble(&module(expected_function));
+        let expected_operators = first_function_operators(&expected_wasm);
+        assert_eq!(
+            render_operators(&operators),
+            render_operators(&expected_operators)
+        );
+        assert_eq!(changed, function != expected_function);
+    }
+
+    #[test]
+    fn fold_arithmetic() {
+        check_pass(
+            fold_constants,
+            "(func (result i32) i32.const 7 i32.const 2 i32.div_s i32.const 3 i32.mul)",
+            "(func (result i32) i32.const 9)",
+        );
+        check_pass(
+            fold_constants,
+            "(func (result i64) i64.const -1 i64.const 3 i64.rem_u)",
+            "(func (result i64) i64.const 0)",
+        );
+        check_pass(
+            fold_constants,
+            "(func (result i32) i32.const -2147483648 i32.const -1 i32.rem_s)",
+            "(func (result i32) i32.const 0)",
+        );
+    }
+
+    #[test]
+    fn fold_leaves_trapping_division() {
+        for function in [
+            "(func (result i32) i32.const 1 i32.const 0 i32.div_s)",
+            "(func (result i32) i32.const 1 i32.const 0 i32.div_u)",
+            "(func (result i32) i32.const 1 i32.const 0 i32.rem_s)",
+            "(func (result i32) i32.const 1 i32.const 0 i32.rem_u)",
+            "(func (result i32) i32.const -2147483648 i32.const -1 i32.div_s)",
+            "(func (result i64) i64.const 1 i64.const 0 i64.div_s)",
+            "(func (result i64) i64.const 1 i64.const 0 i64.div_u)",
+            "(func (result i64) i64.const 1 i64.const 0 i64.rem_s)",
+            "(func (result i64) i64.const 1 i64.const 0 i64.rem_u)",
+            "(func (result i64) i64.const -9223372036854775808 i64.const -1 i64.div_s)",
+        ] {
+            check_pass(fold_constants, function, function);
+        }
+    }
+
+    #[test]
+    fn fold_branches() {
+        check_pass(
+            fold_constants,
+            "(func block block block i32.const 1 br_table 0 1 2 end end end)",
+            "(func block block block br 1 end end end)",
+        );
+        check_pass(
+            fold_constants,
+            "(func block block block i32.const 3 br_table 0 1 2 end end end)",
+            "(func block block block br 2 end end end)",
+        );
+        check_pass(
+            fold_constants,
+            "(func block block block i32.const -1 br_table 1 0 2 end end end)",
+            "(func block block block br 2 end end end)",
+        );
+        check_pass(
+            fold_constants,
+            "(func block i32.const 0 br_if 0 i32.const 5 br_if 0 end)",
+            "(func block br 0 end)",
+        );
+        check_pass(
+            fold_constants,
+            "(func (param i32 i32) (result i32) local.get 0 local.get 1 i32.const 1 select)",
+            "(func (param i32 i32) (result i32) local.get 0 local.get 1 drop)",
+        );
+    }
+
+    #[test]
+    fn constant_if() {
+        check_pass(
+            remove_dead_code,
+            "(func (result i32) i32.const 1 if (result i32) i32.const 2 else i32.const 3 end)",
+            "(func (result i32) block (result i32) i32.const 2 end)",
+        );
+        check_pass(
+            remove_dead_code,
+            "(func (result i32) i32.const 0 if (result i32) i32.const 2 else i32.const 3 end)",
+            "(func (result i32) block (result i32) i32.const 3 end)",
+        );
+        check_pass(
+            remove_dead_code,
+            "(func i32.const 0 if block nop end loop nop end end nop)",
+            "(func block end nop)",
+        );
+    }
+
+    #[test]
+    fn dead_code_until_frame_end() {
+        check_pass(
+            remove_dead_code,
+            "(func (param i32) block br 0 local.get 0 drop block nop end end return nop)",
+            "(func (param i32) block br 0 end return)",
+        );
+        check_pass(
+            remove_dead_code,
+            "(func (param i32) local.get 0 if unreachable nop else nop end)",
+            "(func (param i32) local.get 0 if unreachable else nop end)",
+        );
+    }
+
+    #[test]
+    fn dead_code_until_catch() {
+        check_pass(
+            remove_dead_code,
+            "(func try throw $e nop catch $e rethrow 0 nop catch_all nop end)",
+            "(func try throw $e catch $e rethrow 0 catch_all nop end)",
+        );
+        check_pass(
+            remove_dead_code,
+            "(func try try throw $e nop delegate 0 nop catch_all end)",
+            "(func try try throw $e delegate 0 nop catch_all end)",
+        );
+        check_pass(
+            remove_dead_code,
+            "(func try throw $e try nop delegate 0 nop catch_all end)",
+            "(func try throw $e catch_all end)",
+        );
+    }
+
+    #[test]
+    fn simplify_local_accesses() {
+        check_pass(
+            simplify_locals,
+            "(func (param i32) (result i32) i32.const 1 local.set 0 local.get 0)",
+            "(func (param i32) (result i32) i32.const 1 local.tee 0)",
+        );
+        check_pass(
+            simplify_locals,
+            "(func (param i32) i32.const 1 local.tee 0 drop)",
+            "(func (param i32) i32.const 1 local.set 0)",
+        );
+        check_pass(
+            simplify_locals,
+            "(func (param i32) local.get 0 local.set 0 local.get 0 drop)",
+            "(func (param i32))",
+        );
+        check_pass(
+            simplify_locals,
+            "(func (param i32) (result i32) local.get 0 local.tee 0)",
+            "(func (param i32) (result i32) local.get 0)",
+        );
+        check_pass(
+            simplify_locals,
+            "(func (param i32 i32) local.get 0 local.set 1 local.get 1 local.tee 0 drop)",
+            "(func (param i32 i32) local.get 0 local.tee 1 local.set 0)",
+        );
+    }
+
+    /// Bounds check on the first parameter, which branches out of the innermost block
+    const CHECK: &str = "local.get 0 i32.const 10 i32.ge_u br_if 0";
+
+    /// Bounds check on the first parameter, which traps
+    const TRAP_CHECK: &str = "local.get 0 local.get 1 i32.ge_u if unreachable end";
+
+    /// Function taking two `i32`s, with a body made of the given parts
+    fn check_function(body: &[&str]) -> String {
+        format!("(func (param i32 i32) {})", body.join(" "))
+    }
+
+    #[test]
+    fn repeated_bounds_check() {
+        check_pass(
+            remove_redundant_bounds_checks,
+            &check_function(&["block", CHECK, "nop", CHECK, "end"]),
+            &check_function(&["block", CHECK, "nop", "end"]),
+        );
+        check_pass(
+            remove_redundant_bounds_checks,
+            &check_function(&[
+                TRAP_CHECK,
+                "i32.const 1 local.set 1",
+                TRAP_CHECK,
+                TRAP_CHECK,
+            ]),
+            &check_function(&[TRAP_CHECK, "i32.const 1 local.set 1", TRAP_CHECK]),
+        );
+        check_pass(
+            remove_redundant_bounds_checks,
+            &check_function(&[
+                "block",
+                CHECK,
+                "local.get 1 if",
+                TRAP_CHECK,
+                CHECK,
+                "end end",
+            ]),
+            &check_function(&["block", CHECK, "local.get 1 if", TRAP_CHECK, "end end"]),
+        );
+    }
+
+    #[test]
+    fn bounds_check_invalidated_by_local_set() {
+        for function in [
+            check_function(&["block", CHECK, "i32.const 3 local.set 0", CHECK, "end"]),
+            check_function(&["block", CHECK, "local.get 1 local.tee 0 drop", CHECK, "end"]),
+            check_function(&[TRAP_CHECK, "i32.const 3 local.set 1", TRAP_CHECK]),
+        ] {
+            check_pass(remove_redundant_bounds_checks, &function, &function);
+        }
+    }
+
+    #[test]
+    fn bounds_check_not_carried_across_frames() {
+        for function in [
+            check_function(&["block", CHECK, "block", CHECK, "end end"]),
+            check_function(&["block", CHECK, "loop", TRAP_CHECK, CHECK, "end end"]),
+            check_function(&["block", CHECK, "block end", CHECK, "end"]),
+            check_function(&[TRAP_CHECK, "local.get 1 if nop else", TRAP_CHECK, "end"]),
+            check_function(&[
+                TRAP_CHECK,
+                "try",
+                TRAP_CHECK,
+                "catch_all",
+                TRAP_CHECK,
+                "end",
+            ]),
+        ] {
+            check_pass(remove_redundant_bounds_checks, &function, &function);
+        }
+    }
+
+    #[test]
+    fn bounds_check_to_label_with_values() {
+        let function = "(func (param i32) (result i32) block (result i32) i32.const 0
+            local.get 0 i32.const 10 i32.ge_u br_if 0
+            local.get 0 i32.const 10 i32.ge_u br_if 0 end)";
+        check_pass(remove_redundant_bounds_checks, function, function);
+    }
+
+    #[test]
+    fn fold_constant_addresses() {
+        check_pass(
+            remove_redundant_bounds_checks,
+            "(func (result i32) i32.const 16 i32.load offset=4)",
+            "(func (result i32) i32.const 20 i32.load)",
+        );
+        check_pass(
+            remove_redundant_bounds_checks,
+            "(func i32.const 16 i32.const 1 i32.store8 offset=4)",
+            "(func i32.const 20 i32.const 1 i32.store8)",
+        );
+        for function in [
+            "(func (result i32) i32.const 2147483647 i32.load offset=1)",
+            "(func (result i32) i32.const 1 i32.const 1 i32.add i32.load offset=1)",
+            "(func i32.const 16 i32.const 1 i32.const 1 i32.add i32.store offset=4)",
+        ] {
+            check_pass(remove_redundant_bounds_checks, function, function);
+        }
+    }
+}
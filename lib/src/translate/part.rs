@@ -0,0 +1,343 @@
+//! Packing of functions into part classes
+//!
+//! Functions are translated into static methods on nested "part" classes. A single class can only
+//! hold so much: the JVM caps both the number of methods and the number of constant pool entries
+//! at 65535, and very large class files are slow to load. Once the current part is full, a new
+//! one (`Part1`, `Part2`, ...) is started.
+//!
+//! Methods get serialized as soon as they are added to a part, so the constants, methods, and
+//! bytes a part uses up are always known exactly. However, since functions can call functions
+//! whose bodies come later in the code section, the part of every function has to be picked
+//! before any function body is translated. Room for untranslated functions is therefore reserved
+//! based on an estimate made from their WASM body: each distinct function, global, type, and
+//! numeric constant referenced costs a few constant pool entries, and the bytecode is assumed to
+//! be a fixed multiple of the size of the WASM body.
+//!
+//! When an estimate turns out to be too low and a part fills up before all of the functions
+//! declared in it are translated, the remaining functions get translated into a new part instead,
+//! leaving behind methods which just forward to them (see `ModuleTranslator::relocate_function`).
+//! Element and data segment initializers are only generated once all functions are translated,
+//! so they go wherever there is room left.
+
+use super::{BootstrapUtilities, Error, Settings, MAX_CODE_LENGTH};
+use crate::jvm::class_file::{self, ClassFile, ConstantsPool, Version};
+use crate::jvm::class_graph::{BootstrapMethodId, ConstantData, MethodId};
+use crate::jvm::code::{CodeBuilder, CodeBuilderExts};
+use crate::jvm::model::{Class, Method};
+use crate::util::Width;
+use std::collections::{HashMap, HashSet};
+use std::ops::{Add, AddAssign, SubAssign};
+use wasmparser::{FunctionBody, Operator};
+
+/// Entities referenced by functions which end up in the constant pool of their part
+#[derive(Default)]
+pub struct PartReferences<'g> {
+    /// Functions called or referenced (with `ref.func`)
+    pub functions: HashSet<u32>,
+
+    /// Globals read or written
+    pub globals: HashSet<u32>,
+
+    /// Function types of declared methods and of `call_indirect`s
+    pub types: HashSet<u32>,
+
+    /// Numeric constants which don't fit in a `sipush`
+    pub constants: HashSet<ConstantData<'g>>,
+}
+
+impl<'g> PartReferences<'g> {
+    /// Estimated constant pool entries for a function reference (`Methodref`, `NameAndType`, the
+    /// name, and a share of the `Class` of the part declaring the function)
+    const FUNCTION_CONSTANTS: usize = 5;
+
+    /// Estimated constant pool entries for a global reference (`Fieldref`, `NameAndType`, name)
+    const GLOBAL_CONSTANTS: usize = 3;
+
+    /// Estimated constant pool entries for a type (method descriptors and `InvokeDynamic`s)
+    const TYPE_CONSTANTS: usize = 4;
+
+    /// Collect the references of a function body
+    pub fn from_function_body(function_body: &FunctionBody) -> Result<Self, Error> {
+        let mut references = PartReferences::default();
+        for operator in function_body.get_operators_reader()? {
+            match operator? {
+                Operator::Call { function_index }
+                | Operator::ReturnCall { function_index }
+                | Operator::RefFunc { function_index } => {
+                    references.functions.insert(function_index);
+                }
+                Operator::CallIndirect { type_index, .. }
+                | Operator::ReturnCallIndirect { type_index, .. } => {
+                    references.types.insert(type_index);
+                }
+                Operator::GlobalGet { global_index } | Operator::GlobalSet { global_index } => {
+                    references.globals.insert(global_index);
+                }
+                Operator::I32Const { value } if i16::try_from(value).is_err() => {
+                    references.constants.insert(ConstantData::Integer(value));
+                }
+                Operator::I64Const { value } if !(0..=1).contains(&value) => {
+                    references.constants.insert(ConstantData::Long(value));
+                }
+                Operator::F32Const { value } => {
+                    references
+                        .constants
+                        .insert(ConstantData::float(f32::from_bits(value.bits())));
+                }
+                Operator::F64Const { value } => {
+                    references
+                        .constants
+                        .insert(ConstantData::double(f64::from_bits(value.bits())));
+                }
+                _ => (),
+            }
+        }
+        Ok(references)
+    }
+
+    /// Estimated constant pool entries needed for the references not already in `existing`
+    fn new_constants(&self, existing: &PartReferences<'g>) -> usize {
+        let functions = self.functions.difference(&existing.functions).count();
+        let globals = self.globals.difference(&existing.globals).count();
+        let types = self.types.difference(&existing.types).count();
+        let constants: usize = self
+            .constants
+            .difference(&existing.constants)
+            .map(|constant| match constant {
+                ConstantData::Long(_) | ConstantData::Double(_) => 2,
+                _ => 1,
+            })
+            .sum();
+
+        functions * Self::FUNCTION_CONSTANTS
+            + globals * Self::GLOBAL_CONSTANTS
+            + types * Self::TYPE_CONSTANTS
+            + constants
+    }
+
+    pub fn extend(&mut self, other: &PartReferences<'g>) {
+        self.functions.extend(&other.functions);
+        self.globals.extend(&other.globals);
+        self.types.extend(&other.types);
+        self.constants.extend(other.constants.iter().cloned());
+    }
+}
+
+/// Resources used up in a part class
+#[derive(Default, Clone, Copy, Debug)]
+pub struct PartUsage {
+    /// Number of methods
+    pub methods: usize,
+
+    /// Number of constant pool entries
+    pub constants: usize,
+
+    /// Size of the class file in bytes
+    pub class_size: usize,
+}
+
+impl PartUsage {
+    /// Estimated bytes of bytecode per byte of WASM function body
+    pub const CODE_EXPANSION: usize = 4;
+
+    /// Estimated bytes of class file per method, excluding its bytecode
+    const METHOD_SIZE: usize = 64;
+
+    /// Estimated bytes of class file per constant pool entry
+    const CONSTANT_SIZE: usize = 16;
+
+    /// Estimate the resources used up by methods
+    ///
+    /// There are `declared_methods` methods (more if the code is big enough that it'll need to be
+    /// split), with `constants` new constants and `code_length` bytes of bytecode in total.
+    pub fn estimate(declared_methods: usize, constants: usize, code_length: usize) -> Self {
+        let methods = declared_methods + code_length / (MAX_CODE_LENGTH / 2);
+        let constants = 2 * declared_methods + constants;
+        PartUsage {
+            methods,
+            constants,
+            class_size: methods * Self::METHOD_SIZE + code_length + constants * Self::CONSTANT_SIZE,
+        }
+    }
+
+    /// Whether this is over one of the part limits in `settings`
+    fn exceeds(&self, settings: &Settings) -> bool {
+        self.methods > settings.part_max_methods
+            || self.constants > settings.part_max_constants
+            || self.class_size > settings.part_max_class_size
+    }
+}
+
+impl Add for PartUsage {
+    type Output = PartUsage;
+
+    fn add(self, other: PartUsage) -> PartUsage {
+        PartUsage {
+            methods: self.methods + other.methods,
+            constants: self.constants + other.constants,
+            class_size: self.class_size + other.class_size,
+        }
+    }
+}
+
+impl AddAssign for PartUsage {
+    fn add_assign(&mut self, other: PartUsage) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for PartUsage {
+    fn sub_assign(&mut self, other: PartUsage) {
+        self.methods -= other.methods;
+        self.constants -= other.constants;
+        self.class_size -= other.class_size;
+    }
+}
+
+/// Part class, along with the methods serialized into it so far
+pub struct Part<'g> {
+    /// Part class (methods get serialized instead of being added to it)
+    pub class: Class<'g>,
+
+    pub bootstrap: BootstrapUtilities<'g>,
+
+    /// Constants pool shared by all the methods serialized so far
+    constants_pool: ConstantsPool<'g>,
+
+    /// Bootstrap methods used by the methods serialized so far
+    bootstrap_methods: HashMap<BootstrapMethodId<'g>, u16>,
+
+    /// Methods serialized so far
+    methods: Vec<class_file::Method>,
+
+    /// Size in bytes of the methods serialized so far
+    methods_size: usize,
+
+    /// Estimated resources of what is declared in the part but not yet added
+    reserved: PartUsage,
+
+    /// Everything referenced from functions reserved in the part
+    references: PartReferences<'g>,
+}
+
+impl<'g> Part<'g> {
+    /// Constant pool entries added when the class gets serialized (the class itself, its super
+    /// class, bootstrap method arguments, and the `NestHost` and `InnerClasses` attributes)
+    const CLASS_CONSTANTS: usize = 1024;
+
+    pub fn new(class: Class<'g>) -> Self {
+        Part {
+            class,
+            bootstrap: BootstrapUtilities::new(),
+            constants_pool: ConstantsPool::new(),
+            bootstrap_methods: HashMap::new(),
+            methods: vec![],
+            methods_size: 0,
+            reserved: PartUsage::default(),
+            references: PartReferences::default(),
+        }
+    }
+
+    /// Resources used up by the methods added so far
+    pub fn usage(&self) -> PartUsage {
+        PartUsage {
+            methods: self.methods.len(),
+            constants: self.constants_pool.len() + Self::CLASS_CONSTANTS,
+            class_size: self.methods_size + self.constants_pool.serialized_len(),
+        }
+    }
+
+    /// Resources used up by the methods added so far, along with the room reserved
+    pub fn planned_usage(&self) -> PartUsage {
+        self.usage() + self.reserved
+    }
+
+    /// Whether nothing has been added to or reserved in the part
+    pub fn is_empty(&self) -> bool {
+        self.methods.is_empty() && self.reserved.methods == 0
+    }
+
+    /// Estimate the resources used up by adding a function to the part
+    ///
+    /// The function will be declared as `declared_methods` methods, uses `references`, and is
+    /// expected to have `code_length` bytes of bytecode.
+    pub fn estimate(
+        &self,
+        declared_methods: usize,
+        references: &PartReferences<'g>,
+        code_length: usize,
+    ) -> PartUsage {
+        PartUsage::estimate(
+            declared_methods,
+            references.new_constants(&self.references),
+            code_length,
+        )
+    }
+
+    /// Check if there is room for `usage` in the part
+    ///
+    /// There is always room in an empty part. Otherwise, the methods added so far, the room
+    /// reserved, and `usage` must all fit in the limits from `settings`.
+    pub fn has_room(&self, settings: &Settings, usage: PartUsage) -> bool {
+        self.is_empty() || !(self.planned_usage() + usage).exceeds(settings)
+    }
+
+    /// Reserve room in the part for a function which will be translated later
+    pub fn reserve(&mut self, usage: PartUsage, references: &PartReferences<'g>) {
+        self.reserved += usage;
+        self.references.extend(references);
+    }
+
+    /// Release room reserved with `reserve`
+    pub fn release(&mut self, usage: PartUsage) {
+        self.reserved -= usage;
+    }
+
+    /// Serialize a method into the part
+    pub fn add_method(&mut self, mut method: Method<'g>, settings: &Settings) -> Result<(), Error> {
+        assert_eq!(
+            method.id.class, self.class.id,
+            "Method doesn't belong to this part"
+        );
+        if settings.peephole_optimization {
+            if let Some(code) = &mut method.code_impl {
+                code.optimize_peephole();
+            }
+        }
+        let method =
+            method.serialize_method(&mut self.constants_pool, &mut self.bootstrap_methods)?;
+        self.methods_size += 8 + method
+            .attributes
+            .iter()
+            .map(|attribute| 6 + attribute.info.len())
+            .sum::<usize>();
+        self.methods.push(method);
+        Ok(())
+    }
+
+    /// Serialize the part class
+    pub fn result(self, version: Version) -> Result<ClassFile, Error> {
+        let class_file = self.class.serialize_with(
+            version,
+            self.constants_pool,
+            self.bootstrap_methods,
+            self.methods,
+        )?;
+        Ok(class_file)
+    }
+}
+
+/// Generate the body of a method which just calls `target`, a method with the same descriptor
+pub fn generate_forwarding_method<'g>(
+    code: &mut CodeBuilder<'g>,
+    target: MethodId<'g>,
+) -> Result<(), Error> {
+    let mut offset = 0;
+    for parameter in &target.descriptor.parameters {
+        code.get_local(offset, parameter)?;
+        offset += parameter.width() as u16;
+    }
+    code.invoke(target)?;
+    code.return_(target.descriptor.return_type)?;
+    Ok(())
+}
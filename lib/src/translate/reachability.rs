@@ -0,0 +1,258 @@
+//! Reachability of functions and globals
+//!
+//! Libraries compiled to WASM often only export a handful of entry points, and most of what they
+//! contain can't be reached from those. Functions and globals which can't be reached don't get a
+//! method or a field (which also means the utility methods only they use are never generated).
+//!
+//! The roots are the imports, the exports, the start function, and the functions referenced by
+//! element segments. From there:
+//!
+//!   * a function reaches the functions it calls (or references with `ref.func`) and the globals
+//!     it reads or writes
+//!   * a global reaches what its initializer references
+//!
+//! Unreachable functions are still validated, so invalid modules are rejected either way.
+
+use super::Error;
+use wasmparser::{ConstExpr, ElementItem, FunctionBody, Operator};
+
+/// Functions and globals which can be reached from the roots of a module
+pub struct Reachability {
+    /// Reachable functions, by function index
+    functions: Vec<bool>,
+
+    /// Reachable globals, by global index
+    globals: Vec<bool>,
+}
+
+/// Function or global which has been found to be reachable
+#[derive(Copy, Clone)]
+pub enum Reachable {
+    Function(u32),
+    Global(u32),
+}
+
+impl Reachability {
+    /// Treat every function and global as reachable
+    pub fn everything(function_count: usize, global_count: usize) -> Reachability {
+        Reachability {
+            functions: vec![true; function_count],
+            globals: vec![true; global_count],
+        }
+    }
+
+    /// Find what can be reached from some roots
+    ///
+    /// Functions without a body (imports) and globals without an initializer (imports) don't
+    /// reach anything.
+    pub fn new<'a>(
+        roots: impl IntoIterator<Item = Reachable>,
+        function_bodies: &[Option<&FunctionBody<'a>>],
+        global_initializers: &[Option<&ConstExpr<'a>>],
+    ) -> Result<Reachability, Error> {
+        let mut reachability = Reachability {
+            functions: vec![false; function_bodies.len()],
+            globals: vec![false; global_initializers.len()],
+        };
+
+        let mut worklist: Vec<Reachable> = roots.into_iter().collect();
+        while let Some(reachable) = worklist.pop() {
+            match reachable {
+                Reachable::Function(func_idx) => {
+                    let seen = &mut reachability.functions[func_idx as usize];
+                    if std::mem::replace(seen, true) {
+                        continue;
+                    }
+                    if let Some(function_body) = function_bodies[func_idx as usize] {
+                        for operator in function_body.get_operators_reader()? {
+                            worklist.extend(references(&operator?));
+                        }
+                    }
+                }
+                Reachable::Global(global_idx) => {
+                    let seen = &mut reachability.globals[global_idx as usize];
+                    if std::mem::replace(seen, true) {
+                        continue;
+                    }
+                    if let Some(init_expr) = global_initializers[global_idx as usize] {
+                        worklist.extend(const_expr_references(init_expr)?);
+                    }
+                }
+            }
+        }
+
+        Ok(reachability)
+    }
+
+    /// Is the function reachable?
+    pub fn function(&self, func_idx: usize) -> bool {
+        self.functions[func_idx]
+    }
+
+    /// Is the global reachable?
+    pub fn global(&self, global_idx: usize) -> bool {
+        self.globals[global_idx]
+    }
+}
+
+/// Functions and globals referenced by a constant expression
+fn const_expr_references(const_expr: &ConstExpr) -> Result<Vec<Reachable>, Error> {
+    let mut reachable = vec![];
+    for operator in const_expr.get_operators_reader() {
+        reachable.extend(references(&operator?));
+    }
+    Ok(reachable)
+}
+
+/// Functions and globals referenced by an item of an element segment
+pub fn element_item_references(item: &ElementItem) -> Result<Vec<Reachable>, Error> {
+    match item {
+        ElementItem::Func(func_idx) => Ok(vec![Reachable::Function(*func_idx)]),
+        ElementItem::Expr(expr) => const_expr_references(expr),
+    }
+}
+
+/// Function or global referenced by an operator
+fn references(operator: &Operator) -> Option<Reachable> {
+    match operator {
+        Operator::Call { function_index }
+        | Operator::ReturnCall { function_index }
+        | Operator::RefFunc { function_index } => Some(Reachable::Function(*function_index)),
+        Operator::GlobalGet { global_index } | Operator::GlobalSet { global_index } => {
+            Some(Reachable::Global(*global_index))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::translate::test_util::assemble;
+    use wasmparser::{Parser, Payload, TypeRef};
+
+    /// Find which functions and globals of a module are reachable from the given roots and the
+    /// items of its element segments
+    fn reachable(wat: &str, roots: &[Reachable]) -> (Vec<bool>, Vec<bool>) {
+        let wasm = assemble(wat);
+        let mut function_bodies = vec![];
+        let mut global_initializers = vec![];
+        let mut roots = roots.to_vec();
+        for payload in Parser::new(0).parse_all(&wasm) {
+            match payload.unwrap() {
+                Payload::ImportSection(section) => {
+                    for import in section {
+                        match import.unwrap().ty {
+                            TypeRef::Func(_) => function_bodies.push(None),
+                            TypeRef::Global(_) => global_initializers.push(None),
+                            _ => (),
+                        }
+                    }
+                }
+                Payload::FunctionSection(section) => {
+                    function_bodies.extend((0..section.get_count()).map(|_| None))
+                }
+                Payload::GlobalSection(section) => {
+                    for global in section {
+                        global_initializers.push(Some(global.unwrap().init_expr));
+                    }
+                }
+                Payload::ElementSection(section) => {
+                    for element in section {
+                        for item in element.unwrap().items.get_items_reader().unwrap() {
+                            roots.extend(element_item_references(&item.unwrap()).unwrap());
+                        }
+                    }
+                }
+                Payload::CodeSectionEntry(function_body) => {
+                    let func_idx = function_bodies.iter().position(Option::is_none).unwrap();
+                    function_bodies[func_idx] = Some(function_body);
+                }
+                _ => (),
+            }
+        }
+
+        let function_bodies: Vec<Option<&FunctionBody>> =
+            function_bodies.iter().map(Option::as_ref).collect();
+        let global_initializers: Vec<Option<&ConstExpr>> =
+            global_initializers.iter().map(Option::as_ref).collect();
+        let reachability =
+            Reachability::new(roots, &function_bodies, &global_initializers).unwrap();
+        (
+            (0..function_bodies.len())
+                .map(|func_idx| reachability.function(func_idx))
+                .collect(),
+            (0..global_initializers.len())
+                .map(|global_idx| reachability.global(global_idx))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn calls_and_globals() {
+        let wat = "(module
+            (import \"env\" \"f\" (func $imported))
+            (import \"env\" \"g\" (global $imported i32))
+            (global $read (mut i32) (global.get $imported))
+            (global $written (mut i32) (i32.const 0))
+            (global $unused i32 (i32.const 0))
+            (func $root call $callee global.get $read drop)
+            (func $callee i32.const 1 global.set $written return_call $imported)
+            (func $unused call $root))";
+        assert_eq!(
+            reachable(wat, &[Reachable::Function(1)]),
+            (vec![true, true, true, false], vec![true, true, true, false],),
+        );
+    }
+
+    #[test]
+    fn ref_func_in_reachable_bodies() {
+        let wat = "(module
+            (func $root ref.func $referenced drop)
+            (func $referenced)
+            (func $unreachable ref.func $only_from_unreachable drop)
+            (func $only_from_unreachable))";
+        assert_eq!(
+            reachable(wat, &[Reachable::Function(0)]),
+            (vec![true, true, false, false], vec![]),
+        );
+    }
+
+    #[test]
+    fn element_segment_items() {
+        let wat = "(module
+            (table 4 funcref)
+            (global $func_ref funcref (ref.func $from_global))
+            (func $from_index)
+            (func $from_expr)
+            (func $from_global)
+            (func $unreferenced)
+            (elem (i32.const 0) func $from_index)
+            (elem (i32.const 1) funcref (ref.func $from_expr) (ref.null func)
+              (global.get $func_ref)))";
+        assert_eq!(
+            reachable(wat, &[]),
+            (vec![true, true, true, false], vec![true]),
+        );
+    }
+
+    #[test]
+    fn global_initializers() {
+        let wat = "(module
+            (import \"env\" \"g\" (global $imported funcref))
+            (global $exported funcref (ref.func $from_exported))
+            (global $unused funcref (ref.func $from_unused))
+            (global $indirect funcref (global.get $imported))
+            (func $from_exported)
+            (func $from_unused))";
+        assert_eq!(
+            reachable(wat, &[Reachable::Global(1)]),
+            (vec![true, false], vec![false, true, false, false]),
+        );
+        assert_eq!(
+            reachable(wat, &[Reachable::Global(3)]),
+            (vec![false, false], vec![true, false, false, true]),
+        );
+    }
+}
@@ -13,6 +13,15 @@ pub struct Settings {
     /// Function name prefix (eg. `func`)
     pub wasm_function_name_prefix: UnqualifiedName,
 
+    /// Suffix for the tail call variant of a function (eg. `$tail`)
+    pub wasm_tailcall_function_name_suffix: UnqualifiedName,
+
+    /// Suffix for the methods into which oversized functions get split (eg. `$split`)
+    pub wasm_split_function_name_suffix: UnqualifiedName,
+
+    /// Suffix for the methods in parts which initialize data and element segments (eg. `$init`)
+    pub wasm_init_name_suffix: UnqualifiedName,
+
     /// Name for fields associated with imports
     pub wasm_import_name_prefix: UnqualifiedName,
 
@@ -31,6 +40,9 @@ pub struct Settings {
     /// Data name prefix (eg. `element`)
     pub wasm_element_name_prefix: UnqualifiedName,
 
+    /// Tag name prefix (eg. `tag`)
+    pub wasm_tag_name_prefix: UnqualifiedName,
+
     /// Utilities class strategy
     pub utilities_strategy: UtilitiesStrategy,
 
@@ -43,6 +55,22 @@ pub struct Settings {
     /// would fit in a class constant pool.
     pub part_short_class_name: UnqualifiedName,
 
+    /// Maximum number of methods in a part before a new part is started
+    ///
+    /// The JVM caps the number of methods in a class at 65535.
+    pub part_max_methods: usize,
+
+    /// Number of constant pool entries in a part above which a new part is started
+    ///
+    /// The JVM caps the constant pool at 65535 entries. The part of a function is picked before
+    /// any function is translated, using an estimate of what it needs. Functions which then don't
+    /// fit get moved to a new part, leaving behind a method forwarding to them, so this should
+    /// leave some headroom for those.
+    pub part_max_constants: usize,
+
+    /// Class file size (in bytes) of a part above which a new part is started
+    pub part_max_class_size: usize,
+
     /// Field name for arrays of `funcref` tables
     ///
     /// This has type `[[Ljava/lang/invoke/MethodHandle;` with values in the outer array being
@@ -78,6 +106,14 @@ pub struct Settings {
     /// sign of NaN, contrary to the behaviour of Java's `Math.abs`.
     pub bitwise_floating_abs: bool,
 
+    /// Trap when adding the static offset of a memory access to its address overflows
+    ///
+    /// WASM computes effective addresses as unsigned with infinite precision, so an overflowing
+    /// sum is always out of bounds. Turning this off saves a check on every access with a
+    /// non-zero offset, but then an overflowing sum silently wraps around (possibly to a valid
+    /// address), so this should only be done for trusted code.
+    pub trap_address_overflow: bool,
+
     /// Renaming strategy for exports
     ///
     /// TODO: remove `AssertUnwindSafe` after we weed out panics that make catching necessary
@@ -86,6 +122,61 @@ pub struct Settings {
     /// Should extra public (non-static) methods be generated for exported functions? If so, the
     /// renamer is how those method names will be determined.
     pub methods_for_function_exports: bool,
+
+    /// How should `return_call` and `return_call_indirect` be handled
+    pub tail_call_strategy: TailCallStrategy,
+
+    /// What holds the bytes of (32-bit) memories
+    ///
+    /// This also determines the type of the `bytes` field of `org.wasm2jar.Memory`, so modules
+    /// sharing memories must be translated with the same backend.
+    pub memory_backend: MemoryBackend,
+
+    /// How much to optimize function bodies before translating them (see the `optimize` module)
+    ///
+    ///   * `0` translates function bodies as they are
+    ///   * `1` folds constants, removes dead code, and simplifies redundant local accesses
+    ///   * `2` (or more) also removes redundant bounds checks
+    pub optimization_level: u8,
+
+    /// Translate function bodies through their SSA form (see the `ssa` module)
+    ///
+    /// Functions using anything which the SSA form doesn't support yet, or which are too big for
+    /// one JVM method, still get translated directly.
+    pub ssa_translation: bool,
+
+    /// Clean up the bytecode of all generated methods with a peephole pass (see
+    /// [`crate::jvm::code::Code::optimize_peephole`])
+    pub peephole_optimization: bool,
+
+    /// How many different targets a `call_indirect` links to directly before giving up on
+    /// caching them
+    ///
+    /// Each cached target is guarded by a check that the table slot still holds it, which is
+    /// cheaper than the signature check done when going through the table. `0` disables the
+    /// inline caches entirely.
+    pub call_indirect_cache_size: u8,
+
+    /// Maximum number of operators in a function for calls to it to be inlined
+    ///
+    /// Only functions which don't call any other function (and which have at most one result)
+    /// get inlined, see the `inline` module. `0` disables inlining.
+    pub inline_max_operators: usize,
+
+    /// Skip functions and globals which can't be reached from the imports, exports, start
+    /// function, or element segments
+    ///
+    /// Unreachable functions are still validated, but they don't get translated into methods
+    /// (see the `reachability` module).
+    pub dead_code_elimination: bool,
+
+    /// Translate functions with the same type and byte-identical bodies into just one method
+    ///
+    /// The other functions get validated, then share the methods of the first such function.
+    pub deduplicate_functions: bool,
+
+    /// Where the bytes of data segments get stored
+    pub data_storage: DataStorage,
 }
 
 /// Strategy for handling utility functions
@@ -110,15 +201,15 @@ impl Settings {
         reference_types: true,
         multi_value: true,
         bulk_memory: true,
-        simd: false,
+        simd: true,
         relaxed_simd: false,
-        threads: false,
-        tail_call: false,
+        threads: true,
+        tail_call: true,
         deterministic_only: true,
-        multi_memory: false,
-        exceptions: false,
-        memory64: false,
-        extended_const: false,
+        multi_memory: true,
+        exceptions: true,
+        memory64: true,
+        extended_const: true,
         component_model: false,
     };
 
@@ -146,22 +237,40 @@ impl Settings {
             output_full_class_name: make_name(output_full_class_name)?,
             start_function_name: make_name("initialize")?,
             wasm_function_name_prefix: make_name("func")?,
+            wasm_tailcall_function_name_suffix: make_name("$tail")?,
+            wasm_split_function_name_suffix: make_name("$split")?,
+            wasm_init_name_suffix: make_name("$init")?,
             wasm_import_name_prefix: make_name("imprt")?,
             wasm_global_name_prefix: make_name("global")?,
             wasm_table_name_prefix: make_name("table")?,
             wasm_memory_name_prefix: make_name("memory")?,
             wasm_data_name_prefix: make_name("data")?,
             wasm_element_name_prefix: make_name("element")?,
+            wasm_tag_name_prefix: make_name("tag")?,
             utilities_strategy,
             part_short_class_name: make_name("Part")?,
+            part_max_methods: 16_384,
+            part_max_constants: 48_000,
+            part_max_class_size: 8 * 1024 * 1024,
             funcref_array_table_field_name: make_name("funcref_tables")?,
             externref_array_table_field_name: make_name("externref_tables")?,
             wasm_features,
             export_strategy: ExportStrategy::Members,
             trap_integer_division_overflow: true,
             bitwise_floating_abs: true,
+            trap_address_overflow: true,
             renamer: AssertUnwindSafe(Box::new(JavaRenamer::new())),
             methods_for_function_exports: true,
+            tail_call_strategy: TailCallStrategy::Trampoline,
+            memory_backend: MemoryBackend::HeapByteBuffer,
+            optimization_level: 2,
+            ssa_translation: false,
+            peephole_optimization: true,
+            call_indirect_cache_size: 4,
+            inline_max_operators: 0,
+            dead_code_elimination: true,
+            deduplicate_functions: true,
+            data_storage: DataStorage::StringConstants,
         })
     }
 
@@ -170,6 +279,19 @@ impl Settings {
             .concat(&UnqualifiedName::number(func_idx))
     }
 
+    /// Name given to the method used when a function is the target of a trampolined tail call
+    pub fn wasm_tailcall_function_name(&self, func_idx: usize) -> UnqualifiedName {
+        self.wasm_function_name(func_idx)
+            .concat(&self.wasm_tailcall_function_name_suffix)
+    }
+
+    /// Name given to a method containing a region of an oversized function
+    pub fn wasm_split_function_name(&self, func_idx: usize, split_idx: usize) -> UnqualifiedName {
+        self.wasm_function_name(func_idx)
+            .concat(&self.wasm_split_function_name_suffix)
+            .concat(&UnqualifiedName::number(split_idx))
+    }
+
     /// Name given to the `MethodHandle` fields that store imported functions
     pub fn wasm_import_name(&self, import_idx: usize) -> UnqualifiedName {
         self.wasm_import_name_prefix
@@ -203,6 +325,21 @@ impl Settings {
             .concat(&UnqualifiedName::number(data_idx))
     }
 
+    /// Name given to the method which reads the bytes of a data segment
+    pub fn wasm_data_init_name(&self, data_idx: usize) -> UnqualifiedName {
+        self.wasm_data_name(data_idx)
+            .concat(&self.wasm_init_name_suffix)
+    }
+
+    /// Path (relative to the root of the class path) of the resource holding a data segment
+    pub fn wasm_data_resource_name(&self, data_idx: usize) -> String {
+        format!(
+            "{}.{}",
+            self.output_full_class_name.as_str(),
+            self.wasm_data_name(data_idx).as_str()
+        )
+    }
+
     pub fn wasm_element_getter_name(&self, element_idx: usize) -> UnqualifiedName {
         UnqualifiedName::GET
             .concat(&UnqualifiedName::UNDERSCORE)
@@ -214,6 +351,18 @@ impl Settings {
         self.wasm_element_name_prefix
             .concat(&UnqualifiedName::number(element_idx))
     }
+
+    /// Name given to a method filling in a chunk of the items of an element segment
+    pub fn wasm_element_init_name(&self, element_idx: usize, chunk_idx: usize) -> UnqualifiedName {
+        self.wasm_element_name(element_idx)
+            .concat(&self.wasm_init_name_suffix)
+            .concat(&UnqualifiedName::number(chunk_idx))
+    }
+
+    pub fn wasm_tag_name(&self, tag_idx: usize) -> UnqualifiedName {
+        self.wasm_tag_name_prefix
+            .concat(&UnqualifiedName::number(tag_idx))
+    }
 }
 
 pub enum ExportStrategy {
@@ -223,3 +372,51 @@ pub enum ExportStrategy {
     /// Exports packed into `Map<String, ?>`
     Exports,
 }
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TailCallStrategy {
+    /// Tail calls return a thunk which gets evaluated by a trampoline in the nearest caller that
+    /// isn't itself a tail call, so that tail calls don't grow the JVM stack
+    Trampoline,
+
+    /// Tail calls are translated as regular calls followed by a return (cheaper, but deeply
+    /// tail-recursive code can overflow the stack)
+    PlainCall,
+}
+
+/// Representation of the bytes of a 32-bit memory
+///
+/// 64-bit memories always use `org.wasm2jar.Memory64`, which manages its own `ByteBuffer`s.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MemoryBackend {
+    /// Little-endian `ByteBuffer` allocated with `ByteBuffer.allocate`
+    HeapByteBuffer,
+
+    /// Little-endian `ByteBuffer` allocated with `ByteBuffer.allocateDirect`
+    ///
+    /// The memory lives outside the Java heap, which makes it cheap to hand over to native code.
+    DirectByteBuffer,
+
+    /// `byte[]`, accessed through little-endian `MethodHandles.byteArrayViewVarHandle` views
+    ///
+    /// This gives the JIT the most room to optimize memory accesses.
+    ByteArray,
+}
+
+/// Storage for the bytes of data segments
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum DataStorage {
+    /// String constants in the module class, turned back into bytes with `String.getBytes`
+    ///
+    /// Each constant holds at most 65535 bytes, and they all take up room in the constant pool.
+    StringConstants,
+
+    /// Resource files next to the module class, read with `ClassLoader.getResourceAsStream`
+    ///
+    /// The resources must be on the class path along with the classes (eg. in the same JAR), or
+    /// loading the data throws an `IllegalArgumentException` naming the missing resource.
+    Resources,
+
+    /// Same as `Resources`, but the files are deflate-compressed (in the zlib format)
+    CompressedResources,
+}
@@ -0,0 +1,1608 @@
+use super::Error;
+use crate::jvm::class_graph::ClassId;
+use crate::jvm::code::{
+    BranchCond, BranchInstruction, CodeBuilder, CodeBuilderExts, CompareMode, Instruction,
+    OrdComparison, ShiftType, SynLabel, VerifierInstruction,
+};
+use crate::jvm::{BaseType, FieldType, MethodDescriptor, Name, UnqualifiedName};
+use wasmparser::Operator;
+
+/* WASM `v128` values are represented as `long[]` of length 2: the first entry contains the low 64
+ * bits and the second entry the high 64 bits (so lane `0` of an `i8x16` is the lowest byte of the
+ * first `long`). Arrays are never mutated after they are built, so they can be freely shared.
+ *
+ * Outside of a handful of cheap operations (constants, extracting lanes, etc.), SIMD operations are
+ * generated on demand as utility methods that operate lane by lane.
+ */
+
+/// Interpretation of the 128 bits of a `v128` as lanes
+#[derive(Copy, Clone, Hash, PartialEq, Eq, Debug)]
+pub enum LaneShape {
+    I8x16,
+    I16x8,
+    I32x4,
+    I64x2,
+    F32x4,
+    F64x2,
+}
+
+impl LaneShape {
+    /// Number of lanes
+    pub const fn lane_count(self) -> u8 {
+        match self {
+            LaneShape::I8x16 => 16,
+            LaneShape::I16x8 => 8,
+            LaneShape::I32x4 | LaneShape::F32x4 => 4,
+            LaneShape::I64x2 | LaneShape::F64x2 => 2,
+        }
+    }
+
+    /// Number of bits in each lane
+    pub const fn lane_bits(self) -> u8 {
+        128 / self.lane_count()
+    }
+
+    /// Number of lanes packed into each `long` half
+    const fn lanes_per_half(self) -> u8 {
+        self.lane_count() / 2
+    }
+
+    /// JVM type used for a single lane (`i8` and `i16` lanes are widened to `int`)
+    pub const fn lane_type<'g>(self) -> FieldType<ClassId<'g>> {
+        match self {
+            LaneShape::I8x16 | LaneShape::I16x8 | LaneShape::I32x4 => FieldType::int(),
+            LaneShape::I64x2 => FieldType::long(),
+            LaneShape::F32x4 => FieldType::float(),
+            LaneShape::F64x2 => FieldType::double(),
+        }
+    }
+
+    /// Integer shape with lanes of the same width
+    const fn bits_shape(self) -> LaneShape {
+        match self {
+            LaneShape::F32x4 => LaneShape::I32x4,
+            LaneShape::F64x2 => LaneShape::I64x2,
+            other => other,
+        }
+    }
+
+    /// Shape with lanes that are half as wide (and twice as many)
+    const fn narrower(self) -> LaneShape {
+        match self {
+            LaneShape::I16x8 => LaneShape::I8x16,
+            LaneShape::I32x4 => LaneShape::I16x8,
+            LaneShape::I64x2 => LaneShape::I32x4,
+            _ => panic!("shape has no narrower integer shape"),
+        }
+    }
+
+    /// Shape with lanes that are twice as wide (and half as many)
+    const fn wider(self) -> LaneShape {
+        match self {
+            LaneShape::I8x16 => LaneShape::I16x8,
+            LaneShape::I16x8 => LaneShape::I32x4,
+            LaneShape::I32x4 => LaneShape::I64x2,
+            _ => panic!("shape has no wider integer shape"),
+        }
+    }
+
+    /// Prefix used in WASM instruction names
+    const fn prefix(self) -> &'static str {
+        match self {
+            LaneShape::I8x16 => "i8x16",
+            LaneShape::I16x8 => "i16x8",
+            LaneShape::I32x4 => "i32x4",
+            LaneShape::I64x2 => "i64x2",
+            LaneShape::F32x4 => "f32x4",
+            LaneShape::F64x2 => "f64x2",
+        }
+    }
+}
+
+/// Bitwise operations over the full 128 bits
+#[derive(Copy, Clone, Hash, PartialEq, Eq, Debug)]
+pub enum BitwiseOp {
+    Not,
+    And,
+    AndNot,
+    Or,
+    Xor,
+    Bitselect,
+}
+
+/// Lanewise operations with one input
+#[derive(Copy, Clone, Hash, PartialEq, Eq, Debug)]
+pub enum UnaryOp {
+    Abs,
+    Neg,
+    Popcnt,
+    Sqrt,
+    Ceil,
+    Floor,
+    Trunc,
+    Nearest,
+}
+
+/// Lanewise operations with two inputs
+#[derive(Copy, Clone, Hash, PartialEq, Eq, Debug)]
+pub enum BinaryOp {
+    Add,
+    AddSatS,
+    AddSatU,
+    Sub,
+    SubSatS,
+    SubSatU,
+    Mul,
+    Div,
+    MinS,
+    MinU,
+    MaxS,
+    MaxU,
+    AvgrU,
+    Q15MulrSatS,
+    Min,
+    Max,
+    PMin,
+    PMax,
+}
+
+/// Lanewise comparisons (producing lanes of all ones or all zeros)
+///
+/// Floating point shapes use the signed variants.
+#[derive(Copy, Clone, Hash, PartialEq, Eq, Debug)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    LtS,
+    LtU,
+    GtS,
+    GtU,
+    LeS,
+    LeU,
+    GeS,
+    GeU,
+}
+
+/// Lanewise shifts by a scalar amount
+#[derive(Copy, Clone, Hash, PartialEq, Eq, Debug)]
+pub enum ShiftOp {
+    Shl,
+    ShrS,
+    ShrU,
+}
+
+/// Conversions between integer and floating point lanes
+#[derive(Copy, Clone, Hash, PartialEq, Eq, Debug)]
+pub enum ConvertOp {
+    I32x4TruncSatF32x4S,
+    I32x4TruncSatF32x4U,
+    F32x4ConvertI32x4S,
+    F32x4ConvertI32x4U,
+    I32x4TruncSatF64x2SZero,
+    I32x4TruncSatF64x2UZero,
+    F64x2ConvertLowI32x4S,
+    F64x2ConvertLowI32x4U,
+    F32x4DemoteF64x2Zero,
+    F64x2PromoteLowF32x4,
+}
+
+/// SIMD operations that are generated as utility methods
+#[derive(Copy, Clone, Hash, PartialEq, Eq, Debug)]
+pub enum SimdOperation {
+    /// Build a `v128` from its low and high `long` halves
+    FromHalves,
+
+    /// Replace the bits outside of a mask in one of the `long` halves of a `v128`
+    ReplaceBits,
+
+    /// Build a `v128` with all lanes set to the same value
+    Splat(LaneShape),
+
+    /// Select bytes from two `v128` using constant indices
+    Shuffle([u8; 16]),
+
+    /// Select bytes from a `v128` using indices in another `v128`
+    Swizzle,
+
+    /// Bitwise operation on all 128 bits
+    Bitwise(BitwiseOp),
+
+    /// Check if any bit is set
+    AnyTrue,
+
+    /// Check if all lanes are non-zero
+    AllTrue(LaneShape),
+
+    /// Collect the sign bits of all lanes into an `int`
+    Bitmask(LaneShape),
+
+    /// Lanewise operation with one input
+    Unary(LaneShape, UnaryOp),
+
+    /// Lanewise operation with two inputs
+    Binary(LaneShape, BinaryOp),
+
+    /// Lanewise comparison
+    Compare(LaneShape, CompareOp),
+
+    /// Lanewise shift by an `int` amount
+    Shift(LaneShape, ShiftOp),
+
+    /// Saturating narrowing of two `v128` into lanes of the specified shape
+    Narrow { shape: LaneShape, signed: bool },
+
+    /// Extend the low or high half of the narrower lanes into lanes of the specified shape
+    Extend {
+        shape: LaneShape,
+        high: bool,
+        signed: bool,
+    },
+
+    /// Extended multiplication of the low or high half of narrower lanes
+    ExtMul {
+        shape: LaneShape,
+        high: bool,
+        signed: bool,
+    },
+
+    /// Extended addition of adjacent pairs of narrower lanes
+    ExtAddPairwise { shape: LaneShape, signed: bool },
+
+    /// Dot product of signed `i16x8` lanes into `i32x4` lanes
+    Dot,
+
+    /// Conversion between integer and floating point lanes
+    Convert(ConvertOp),
+}
+
+impl SimdOperation {
+    /// Map a WASM operator into a SIMD utility (if it is implemented as one)
+    pub fn from_operator(operator: &Operator) -> Option<SimdOperation> {
+        use LaneShape::*;
+        use SimdOperation::*;
+
+        Some(match operator {
+            Operator::I8x16Shuffle { lanes } => Shuffle(*lanes),
+            Operator::I8x16Swizzle => Swizzle,
+            Operator::I8x16Splat => Splat(I8x16),
+            Operator::I16x8Splat => Splat(I16x8),
+            Operator::I32x4Splat => Splat(I32x4),
+            Operator::I64x2Splat => Splat(I64x2),
+            Operator::F32x4Splat => Splat(F32x4),
+            Operator::F64x2Splat => Splat(F64x2),
+
+            Operator::I8x16Eq => Compare(I8x16, CompareOp::Eq),
+            Operator::I8x16Ne => Compare(I8x16, CompareOp::Ne),
+            Operator::I8x16LtS => Compare(I8x16, CompareOp::LtS),
+            Operator::I8x16LtU => Compare(I8x16, CompareOp::LtU),
+            Operator::I8x16GtS => Compare(I8x16, CompareOp::GtS),
+            Operator::I8x16GtU => Compare(I8x16, CompareOp::GtU),
+            Operator::I8x16LeS => Compare(I8x16, CompareOp::LeS),
+            Operator::I8x16LeU => Compare(I8x16, CompareOp::LeU),
+            Operator::I8x16GeS => Compare(I8x16, CompareOp::GeS),
+            Operator::I8x16GeU => Compare(I8x16, CompareOp::GeU),
+            Operator::I16x8Eq => Compare(I16x8, CompareOp::Eq),
+            Operator::I16x8Ne => Compare(I16x8, CompareOp::Ne),
+            Operator::I16x8LtS => Compare(I16x8, CompareOp::LtS),
+            Operator::I16x8LtU => Compare(I16x8, CompareOp::LtU),
+            Operator::I16x8GtS => Compare(I16x8, CompareOp::GtS),
+            Operator::I16x8GtU => Compare(I16x8, CompareOp::GtU),
+            Operator::I16x8LeS => Compare(I16x8, CompareOp::LeS),
+            Operator::I16x8LeU => Compare(I16x8, CompareOp::LeU),
+            Operator::I16x8GeS => Compare(I16x8, CompareOp::GeS),
+            Operator::I16x8GeU => Compare(I16x8, CompareOp::GeU),
+            Operator::I32x4Eq => Compare(I32x4, CompareOp::Eq),
+            Operator::I32x4Ne => Compare(I32x4, CompareOp::Ne),
+            Operator::I32x4LtS => Compare(I32x4, CompareOp::LtS),
+            Operator::I32x4LtU => Compare(I32x4, CompareOp::LtU),
+            Operator::I32x4GtS => Compare(I32x4, CompareOp::GtS),
+            Operator::I32x4GtU => Compare(I32x4, CompareOp::GtU),
+            Operator::I32x4LeS => Compare(I32x4, CompareOp::LeS),
+            Operator::I32x4LeU => Compare(I32x4, CompareOp::LeU),
+            Operator::I32x4GeS => Compare(I32x4, CompareOp::GeS),
+            Operator::I32x4GeU => Compare(I32x4, CompareOp::GeU),
+            Operator::I64x2Eq => Compare(I64x2, CompareOp::Eq),
+            Operator::I64x2Ne => Compare(I64x2, CompareOp::Ne),
+            Operator::I64x2LtS => Compare(I64x2, CompareOp::LtS),
+            Operator::I64x2GtS => Compare(I64x2, CompareOp::GtS),
+            Operator::I64x2LeS => Compare(I64x2, CompareOp::LeS),
+            Operator::I64x2GeS => Compare(I64x2, CompareOp::GeS),
+            Operator::F32x4Eq => Compare(F32x4, CompareOp::Eq),
+            Operator::F32x4Ne => Compare(F32x4, CompareOp::Ne),
+            Operator::F32x4Lt => Compare(F32x4, CompareOp::LtS),
+            Operator::F32x4Gt => Compare(F32x4, CompareOp::GtS),
+            Operator::F32x4Le => Compare(F32x4, CompareOp::LeS),
+            Operator::F32x4Ge => Compare(F32x4, CompareOp::GeS),
+            Operator::F64x2Eq => Compare(F64x2, CompareOp::Eq),
+            Operator::F64x2Ne => Compare(F64x2, CompareOp::Ne),
+            Operator::F64x2Lt => Compare(F64x2, CompareOp::LtS),
+            Operator::F64x2Gt => Compare(F64x2, CompareOp::GtS),
+            Operator::F64x2Le => Compare(F64x2, CompareOp::LeS),
+            Operator::F64x2Ge => Compare(F64x2, CompareOp::GeS),
+
+            Operator::V128Not => Bitwise(BitwiseOp::Not),
+            Operator::V128And => Bitwise(BitwiseOp::And),
+            Operator::V128AndNot => Bitwise(BitwiseOp::AndNot),
+            Operator::V128Or => Bitwise(BitwiseOp::Or),
+            Operator::V128Xor => Bitwise(BitwiseOp::Xor),
+            Operator::V128Bitselect => Bitwise(BitwiseOp::Bitselect),
+            Operator::V128AnyTrue => AnyTrue,
+
+            Operator::I8x16Abs => Unary(I8x16, UnaryOp::Abs),
+            Operator::I8x16Neg => Unary(I8x16, UnaryOp::Neg),
+            Operator::I8x16Popcnt => Unary(I8x16, UnaryOp::Popcnt),
+            Operator::I8x16AllTrue => AllTrue(I8x16),
+            Operator::I8x16Bitmask => Bitmask(I8x16),
+            Operator::I8x16NarrowI16x8S => Narrow {
+                shape: I8x16,
+                signed: true,
+            },
+            Operator::I8x16NarrowI16x8U => Narrow {
+                shape: I8x16,
+                signed: false,
+            },
+            Operator::I8x16Shl => Shift(I8x16, ShiftOp::Shl),
+            Operator::I8x16ShrS => Shift(I8x16, ShiftOp::ShrS),
+            Operator::I8x16ShrU => Shift(I8x16, ShiftOp::ShrU),
+            Operator::I8x16Add => Binary(I8x16, BinaryOp::Add),
+            Operator::I8x16AddSatS => Binary(I8x16, BinaryOp::AddSatS),
+            Operator::I8x16AddSatU => Binary(I8x16, BinaryOp::AddSatU),
+            Operator::I8x16Sub => Binary(I8x16, BinaryOp::Sub),
+            Operator::I8x16SubSatS => Binary(I8x16, BinaryOp::SubSatS),
+            Operator::I8x16SubSatU => Binary(I8x16, BinaryOp::SubSatU),
+            Operator::I8x16MinS => Binary(I8x16, BinaryOp::MinS),
+            Operator::I8x16MinU => Binary(I8x16, BinaryOp::MinU),
+            Operator::I8x16MaxS => Binary(I8x16, BinaryOp::MaxS),
+            Operator::I8x16MaxU => Binary(I8x16, BinaryOp::MaxU),
+            Operator::I8x16AvgrU => Binary(I8x16, BinaryOp::AvgrU),
+
+            Operator::I16x8ExtAddPairwiseI8x16S => ExtAddPairwise {
+                shape: I16x8,
+                signed: true,
+            },
+            Operator::I16x8ExtAddPairwiseI8x16U => ExtAddPairwise {
+                shape: I16x8,
+                signed: false,
+            },
+            Operator::I16x8Abs => Unary(I16x8, UnaryOp::Abs),
+            Operator::I16x8Neg => Unary(I16x8, UnaryOp::Neg),
+            Operator::I16x8Q15MulrSatS => Binary(I16x8, BinaryOp::Q15MulrSatS),
+            Operator::I16x8AllTrue => AllTrue(I16x8),
+            Operator::I16x8Bitmask => Bitmask(I16x8),
+            Operator::I16x8NarrowI32x4S => Narrow {
+                shape: I16x8,
+                signed: true,
+            },
+            Operator::I16x8NarrowI32x4U => Narrow {
+                shape: I16x8,
+                signed: false,
+            },
+            Operator::I16x8ExtendLowI8x16S => Extend {
+                shape: I16x8,
+                high: false,
+                signed: true,
+            },
+            Operator::I16x8ExtendHighI8x16S => Extend {
+                shape: I16x8,
+                high: true,
+                signed: true,
+            },
+            Operator::I16x8ExtendLowI8x16U => Extend {
+                shape: I16x8,
+                high: false,
+                signed: false,
+            },
+            Operator::I16x8ExtendHighI8x16U => Extend {
+                shape: I16x8,
+                high: true,
+                signed: false,
+            },
+            Operator::I16x8Shl => Shift(I16x8, ShiftOp::Shl),
+            Operator::I16x8ShrS => Shift(I16x8, ShiftOp::ShrS),
+            Operator::I16x8ShrU => Shift(I16x8, ShiftOp::ShrU),
+            Operator::I16x8Add => Binary(I16x8, BinaryOp::Add),
+            Operator::I16x8AddSatS => Binary(I16x8, BinaryOp::AddSatS),
+            Operator::I16x8AddSatU => Binary(I16x8, BinaryOp::AddSatU),
+            Operator::I16x8Sub => Binary(I16x8, BinaryOp::Sub),
+            Operator::I16x8SubSatS => Binary(I16x8, BinaryOp::SubSatS),
+            Operator::I16x8SubSatU => Binary(I16x8, BinaryOp::SubSatU),
+            Operator::I16x8Mul => Binary(I16x8, BinaryOp::Mul),
+            Operator::I16x8MinS => Binary(I16x8, BinaryOp::MinS),
+            Operator::I16x8MinU => Binary(I16x8, BinaryOp::MinU),
+            Operator::I16x8MaxS => Binary(I16x8, BinaryOp::MaxS),
+            Operator::I16x8MaxU => Binary(I16x8, BinaryOp::MaxU),
+            Operator::I16x8AvgrU => Binary(I16x8, BinaryOp::AvgrU),
+            Operator::I16x8ExtMulLowI8x16S => ExtMul {
+                shape: I16x8,
+                high: false,
+                signed: true,
+            },
+            Operator::I16x8ExtMulHighI8x16S => ExtMul {
+                shape: I16x8,
+                high: true,
+                signed: true,
+            },
+            Operator::I16x8ExtMulLowI8x16U => ExtMul {
+                shape: I16x8,
+                high: false,
+                signed: false,
+            },
+            Operator::I16x8ExtMulHighI8x16U => ExtMul {
+                shape: I16x8,
+                high: true,
+                signed: false,
+            },
+
+            Operator::I32x4ExtAddPairwiseI16x8S => ExtAddPairwise {
+                shape: I32x4,
+                signed: true,
+            },
+            Operator::I32x4ExtAddPairwiseI16x8U => ExtAddPairwise {
+                shape: I32x4,
+                signed: false,
+            },
+            Operator::I32x4Abs => Unary(I32x4, UnaryOp::Abs),
+            Operator::I32x4Neg => Unary(I32x4, UnaryOp::Neg),
+            Operator::I32x4AllTrue => AllTrue(I32x4),
+            Operator::I32x4Bitmask => Bitmask(I32x4),
+            Operator::I32x4ExtendLowI16x8S => Extend {
+                shape: I32x4,
+                high: false,
+                signed: true,
+            },
+            Operator::I32x4ExtendHighI16x8S => Extend {
+                shape: I32x4,
+                high: true,
+                signed: true,
+            },
+            Operator::I32x4ExtendLowI16x8U => Extend {
+                shape: I32x4,
+                high: false,
+                signed: false,
+            },
+            Operator::I32x4ExtendHighI16x8U => Extend {
+                shape: I32x4,
+                high: true,
+                signed: false,
+            },
+            Operator::I32x4Shl => Shift(I32x4, ShiftOp::Shl),
+            Operator::I32x4ShrS => Shift(I32x4, ShiftOp::ShrS),
+            Operator::I32x4ShrU => Shift(I32x4, ShiftOp::ShrU),
+            Operator::I32x4Add => Binary(I32x4, BinaryOp::Add),
+            Operator::I32x4Sub => Binary(I32x4, BinaryOp::Sub),
+            Operator::I32x4Mul => Binary(I32x4, BinaryOp::Mul),
+            Operator::I32x4MinS => Binary(I32x4, BinaryOp::MinS),
+            Operator::I32x4MinU => Binary(I32x4, BinaryOp::MinU),
+            Operator::I32x4MaxS => Binary(I32x4, BinaryOp::MaxS),
+            Operator::I32x4MaxU => Binary(I32x4, BinaryOp::MaxU),
+            Operator::I32x4DotI16x8S => Dot,
+            Operator::I32x4ExtMulLowI16x8S => ExtMul {
+                shape: I32x4,
+                high: false,
+                signed: true,
+            },
+            Operator::I32x4ExtMulHighI16x8S => ExtMul {
+                shape: I32x4,
+                high: true,
+                signed: true,
+            },
+            Operator::I32x4ExtMulLowI16x8U => ExtMul {
+                shape: I32x4,
+                high: false,
+                signed: false,
+            },
+            Operator::I32x4ExtMulHighI16x8U => ExtMul {
+                shape: I32x4,
+                high: true,
+                signed: false,
+            },
+
+            Operator::I64x2Abs => Unary(I64x2, UnaryOp::Abs),
+            Operator::I64x2Neg => Unary(I64x2, UnaryOp::Neg),
+            Operator::I64x2AllTrue => AllTrue(I64x2),
+            Operator::I64x2Bitmask => Bitmask(I64x2),
+            Operator::I64x2ExtendLowI32x4S => Extend {
+                shape: I64x2,
+                high: false,
+                signed: true,
+            },
+            Operator::I64x2ExtendHighI32x4S => Extend {
+                shape: I64x2,
+                high: true,
+                signed: true,
+            },
+            Operator::I64x2ExtendLowI32x4U => Extend {
+                shape: I64x2,
+                high: false,
+                signed: false,
+            },
+            Operator::I64x2ExtendHighI32x4U => Extend {
+                shape: I64x2,
+                high: true,
+                signed: false,
+            },
+            Operator::I64x2Shl => Shift(I64x2, ShiftOp::Shl),
+            Operator::I64x2ShrS => Shift(I64x2, ShiftOp::ShrS),
+            Operator::I64x2ShrU => Shift(I64x2, ShiftOp::ShrU),
+            Operator::I64x2Add => Binary(I64x2, BinaryOp::Add),
+            Operator::I64x2Sub => Binary(I64x2, BinaryOp::Sub),
+            Operator::I64x2Mul => Binary(I64x2, BinaryOp::Mul),
+            Operator::I64x2ExtMulLowI32x4S => ExtMul {
+                shape: I64x2,
+                high: false,
+                signed: true,
+            },
+            Operator::I64x2ExtMulHighI32x4S => ExtMul {
+                shape: I64x2,
+                high: true,
+                signed: true,
+            },
+            Operator::I64x2ExtMulLowI32x4U => ExtMul {
+                shape: I64x2,
+                high: false,
+                signed: false,
+            },
+            Operator::I64x2ExtMulHighI32x4U => ExtMul {
+                shape: I64x2,
+                high: true,
+                signed: false,
+            },
+
+            Operator::F32x4Ceil => Unary(F32x4, UnaryOp::Ceil),
+            Operator::F32x4Floor => Unary(F32x4, UnaryOp::Floor),
+            Operator::F32x4Trunc => Unary(F32x4, UnaryOp::Trunc),
+            Operator::F32x4Nearest => Unary(F32x4, UnaryOp::Nearest),
+            Operator::F32x4Abs => Unary(F32x4, UnaryOp::Abs),
+            Operator::F32x4Neg => Unary(F32x4, UnaryOp::Neg),
+            Operator::F32x4Sqrt => Unary(F32x4, UnaryOp::Sqrt),
+            Operator::F32x4Add => Binary(F32x4, BinaryOp::Add),
+            Operator::F32x4Sub => Binary(F32x4, BinaryOp::Sub),
+            Operator::F32x4Mul => Binary(F32x4, BinaryOp::Mul),
+            Operator::F32x4Div => Binary(F32x4, BinaryOp::Div),
+            Operator::F32x4Min => Binary(F32x4, BinaryOp::Min),
+            Operator::F32x4Max => Binary(F32x4, BinaryOp::Max),
+            Operator::F32x4PMin => Binary(F32x4, BinaryOp::PMin),
+            Operator::F32x4PMax => Binary(F32x4, BinaryOp::PMax),
+            Operator::F64x2Ceil => Unary(F64x2, UnaryOp::Ceil),
+            Operator::F64x2Floor => Unary(F64x2, UnaryOp::Floor),
+            Operator::F64x2Trunc => Unary(F64x2, UnaryOp::Trunc),
+            Operator::F64x2Nearest => Unary(F64x2, UnaryOp::Nearest),
+            Operator::F64x2Abs => Unary(F64x2, UnaryOp::Abs),
+            Operator::F64x2Neg => Unary(F64x2, UnaryOp::Neg),
+            Operator::F64x2Sqrt => Unary(F64x2, UnaryOp::Sqrt),
+            Operator::F64x2Add => Binary(F64x2, BinaryOp::Add),
+            Operator::F64x2Sub => Binary(F64x2, BinaryOp::Sub),
+            Operator::F64x2Mul => Binary(F64x2, BinaryOp::Mul),
+            Operator::F64x2Div => Binary(F64x2, BinaryOp::Div),
+            Operator::F64x2Min => Binary(F64x2, BinaryOp::Min),
+            Operator::F64x2Max => Binary(F64x2, BinaryOp::Max),
+            Operator::F64x2PMin => Binary(F64x2, BinaryOp::PMin),
+            Operator::F64x2PMax => Binary(F64x2, BinaryOp::PMax),
+
+            Operator::I32x4TruncSatF32x4S => Convert(ConvertOp::I32x4TruncSatF32x4S),
+            Operator::I32x4TruncSatF32x4U => Convert(ConvertOp::I32x4TruncSatF32x4U),
+            Operator::F32x4ConvertI32x4S => Convert(ConvertOp::F32x4ConvertI32x4S),
+            Operator::F32x4ConvertI32x4U => Convert(ConvertOp::F32x4ConvertI32x4U),
+            Operator::I32x4TruncSatF64x2SZero => Convert(ConvertOp::I32x4TruncSatF64x2SZero),
+            Operator::I32x4TruncSatF64x2UZero => Convert(ConvertOp::I32x4TruncSatF64x2UZero),
+            Operator::F64x2ConvertLowI32x4S => Convert(ConvertOp::F64x2ConvertLowI32x4S),
+            Operator::F64x2ConvertLowI32x4U => Convert(ConvertOp::F64x2ConvertLowI32x4U),
+            Operator::F32x4DemoteF64x2Zero => Convert(ConvertOp::F32x4DemoteF64x2Zero),
+            Operator::F64x2PromoteLowF32x4 => Convert(ConvertOp::F64x2PromoteLowF32x4),
+
+            _ => return None,
+        })
+    }
+
+    /// Get the method name
+    pub fn name(&self) -> UnqualifiedName {
+        let name = match self {
+            SimdOperation::FromHalves => String::from("v128FromHalves"),
+            SimdOperation::ReplaceBits => String::from("v128ReplaceBits"),
+            SimdOperation::Splat(shape) => format!("{}Splat", shape.prefix()),
+            SimdOperation::Shuffle(lanes) => {
+                let mut name = String::from("i8x16Shuffle_");
+                for lane in lanes {
+                    name.push_str(&format!("{:02x}", lane));
+                }
+                name
+            }
+            SimdOperation::Swizzle => String::from("i8x16Swizzle"),
+            SimdOperation::Bitwise(op) => format!("v128{:?}", op),
+            SimdOperation::AnyTrue => String::from("v128AnyTrue"),
+            SimdOperation::AllTrue(shape) => format!("{}AllTrue", shape.prefix()),
+            SimdOperation::Bitmask(shape) => format!("{}Bitmask", shape.prefix()),
+            SimdOperation::Unary(shape, op) => format!("{}{:?}", shape.prefix(), op),
+            SimdOperation::Binary(shape, op) => format!("{}{:?}", shape.prefix(), op),
+            SimdOperation::Compare(shape, op) => format!("{}{:?}", shape.prefix(), op),
+            SimdOperation::Shift(shape, op) => format!("{}{:?}", shape.prefix(), op),
+            SimdOperation::Narrow { shape, signed } => format!(
+                "{}Narrow{}{}",
+                shape.prefix(),
+                Self::shape_suffix(shape.wider()),
+                Self::sign_suffix(*signed)
+            ),
+            SimdOperation::Extend {
+                shape,
+                high,
+                signed,
+            } => format!(
+                "{}Extend{}{}{}",
+                shape.prefix(),
+                if *high { "High" } else { "Low" },
+                Self::shape_suffix(shape.narrower()),
+                Self::sign_suffix(*signed)
+            ),
+            SimdOperation::ExtMul {
+                shape,
+                high,
+                signed,
+            } => format!(
+                "{}ExtMul{}{}{}",
+                shape.prefix(),
+                if *high { "High" } else { "Low" },
+                Self::shape_suffix(shape.narrower()),
+                Self::sign_suffix(*signed)
+            ),
+            SimdOperation::ExtAddPairwise { shape, signed } => format!(
+                "{}ExtAddPairwise{}{}",
+                shape.prefix(),
+                Self::shape_suffix(shape.narrower()),
+                Self::sign_suffix(*signed)
+            ),
+            SimdOperation::Dot => String::from("i32x4DotI16x8S"),
+            SimdOperation::Convert(op) => {
+                let mut name = format!("{:?}", op);
+                name[..1].make_ascii_lowercase();
+                name
+            }
+        };
+        UnqualifiedName::from_string(name).expect("SIMD utility names are valid")
+    }
+
+    fn shape_suffix(shape: LaneShape) -> String {
+        let mut prefix = String::from(shape.prefix());
+        prefix[..1].make_ascii_uppercase();
+        prefix
+    }
+
+    const fn sign_suffix(signed: bool) -> &'static str {
+        if signed {
+            "S"
+        } else {
+            "U"
+        }
+    }
+
+    /// Get the method descriptor
+    pub fn descriptor<'g>(&self) -> MethodDescriptor<ClassId<'g>> {
+        let v128 = FieldType::array(FieldType::long());
+        let (parameters, return_type) = match self {
+            SimdOperation::FromHalves => (vec![FieldType::long(), FieldType::long()], v128),
+            SimdOperation::ReplaceBits => (
+                vec![
+                    v128,
+                    FieldType::long(), // bits to insert
+                    FieldType::int(),  // index of the half being updated
+                    FieldType::long(), // mask of bits to keep
+                ],
+                v128,
+            ),
+            SimdOperation::Splat(shape) => (vec![shape.lane_type()], v128),
+            SimdOperation::Bitwise(BitwiseOp::Not)
+            | SimdOperation::Unary(_, _)
+            | SimdOperation::Extend { .. }
+            | SimdOperation::ExtAddPairwise { .. }
+            | SimdOperation::Convert(_) => (vec![v128], v128),
+            SimdOperation::Bitwise(BitwiseOp::Bitselect) => (vec![v128, v128, v128], v128),
+            SimdOperation::Shuffle(_)
+            | SimdOperation::Swizzle
+            | SimdOperation::Bitwise(_)
+            | SimdOperation::Binary(_, _)
+            | SimdOperation::Compare(_, _)
+            | SimdOperation::Narrow { .. }
+            | SimdOperation::ExtMul { .. }
+            | SimdOperation::Dot => (vec![v128, v128], v128),
+            SimdOperation::AnyTrue | SimdOperation::AllTrue(_) | SimdOperation::Bitmask(_) => {
+                (vec![v128], FieldType::int())
+            }
+            SimdOperation::Shift(_, _) => (vec![v128, FieldType::int()], v128),
+        };
+        MethodDescriptor {
+            parameters,
+            return_type: Some(return_type),
+        }
+    }
+
+    /// Generate the body of the utility method
+    pub fn generate<'g>(&self, code: &mut CodeBuilder<'g>) -> Result<(), Error> {
+        match *self {
+            SimdOperation::FromHalves => {
+                build_v128(code, LaneShape::I64x2, |code, lane| {
+                    code.push_instruction(Instruction::LLoad(lane as u16 * 2))?;
+                    Ok(())
+                })?;
+            }
+            SimdOperation::ReplaceBits => Self::generate_replace_bits(code)?,
+            SimdOperation::Splat(shape) => {
+                build_v128(code, shape, |code, _| {
+                    code.get_local(0, &shape.lane_type())?;
+                    Ok(())
+                })?;
+            }
+            SimdOperation::Shuffle(lanes) => {
+                build_v128(code, LaneShape::I8x16, |code, lane| {
+                    let idx = lanes[lane as usize];
+                    if idx < 16 {
+                        push_lane(code, 0, LaneShape::I8x16, idx, true)
+                    } else {
+                        push_lane(code, 1, LaneShape::I8x16, idx - 16, true)
+                    }
+                })?;
+            }
+            SimdOperation::Swizzle => Self::generate_swizzle(code)?,
+            SimdOperation::Bitwise(op) => {
+                build_v128(code, LaneShape::I64x2, |code, lane| {
+                    Self::generate_bitwise(code, op, lane)
+                })?;
+            }
+            SimdOperation::AnyTrue => {
+                for half in 0..2 {
+                    code.push_instruction(Instruction::ALoad(0))?;
+                    code.const_int(half)?;
+                    code.push_instruction(Instruction::LALoad)?;
+                }
+                code.push_instruction(Instruction::LOr)?;
+                code.push_instruction(Instruction::LConst0)?;
+                code.push_instruction(Instruction::LCmp)?;
+                code.condition(&BranchCond::If(OrdComparison::NE))?;
+                code.push_branch_instruction(BranchInstruction::IReturn)?;
+                return Ok(());
+            }
+            SimdOperation::AllTrue(shape) => {
+                let is_false = code.fresh_label();
+                for lane in 0..shape.lane_count() {
+                    push_lane(code, 0, shape, lane, true)?;
+                    if shape == LaneShape::I64x2 {
+                        code.push_instruction(Instruction::LConst0)?;
+                        code.push_instruction(Instruction::LCmp)?;
+                    }
+                    code.push_branch_instruction(BranchInstruction::If(
+                        OrdComparison::EQ,
+                        is_false,
+                        (),
+                    ))?;
+                }
+                code.push_instruction(Instruction::IConst1)?;
+                code.push_branch_instruction(BranchInstruction::IReturn)?;
+
+                code.place_label(is_false)?;
+                code.push_instruction(Instruction::IConst0)?;
+                code.push_branch_instruction(BranchInstruction::IReturn)?;
+                return Ok(());
+            }
+            SimdOperation::Bitmask(shape) => {
+                code.push_instruction(Instruction::IConst0)?;
+                for lane in 0..shape.lane_count() {
+                    push_lane(code, 0, shape, lane, true)?;
+                    if shape == LaneShape::I64x2 {
+                        code.const_int(63)?;
+                        code.push_instruction(Instruction::LSh(ShiftType::LogicalRight))?;
+                        code.push_instruction(Instruction::L2I)?;
+                    } else {
+                        code.const_int(31)?;
+                        code.push_instruction(Instruction::ISh(ShiftType::LogicalRight))?;
+                    }
+                    if lane != 0 {
+                        code.const_int(lane as i32)?;
+                        code.push_instruction(Instruction::ISh(ShiftType::Left))?;
+                    }
+                    code.push_instruction(Instruction::IOr)?;
+                }
+                code.push_branch_instruction(BranchInstruction::IReturn)?;
+                return Ok(());
+            }
+            SimdOperation::Unary(shape, op) => Self::generate_unary(code, shape, op)?,
+            SimdOperation::Binary(shape, op) => Self::generate_binary(code, shape, op)?,
+            SimdOperation::Compare(shape, op) => Self::generate_compare(code, shape, op)?,
+            SimdOperation::Shift(shape, op) => {
+                build_v128(code, shape, |code, lane| {
+                    let (signed, shift_type) = match op {
+                        ShiftOp::Shl => (true, ShiftType::Left),
+                        ShiftOp::ShrS => (true, ShiftType::ArithmeticRight),
+                        ShiftOp::ShrU => (false, ShiftType::LogicalRight),
+                    };
+                    push_lane(code, 0, shape, lane, signed)?;
+                    code.push_instruction(Instruction::ILoad(1))?;
+                    code.const_int(shape.lane_bits() as i32 - 1)?;
+                    code.push_instruction(Instruction::IAnd)?;
+                    if shape == LaneShape::I64x2 {
+                        code.push_instruction(Instruction::LSh(shift_type))?;
+                    } else {
+                        code.push_instruction(Instruction::ISh(shift_type))?;
+                    }
+                    Ok(())
+                })?;
+            }
+            SimdOperation::Narrow { shape, signed } => {
+                let input_shape = shape.wider();
+                let (min, max) = lane_range(shape, signed);
+                build_v128(code, shape, |code, lane| {
+                    if lane < input_shape.lane_count() {
+                        push_lane(code, 0, input_shape, lane, true)?;
+                    } else {
+                        let lane = lane - input_shape.lane_count();
+                        push_lane(code, 1, input_shape, lane, true)?;
+                    }
+                    clamp(code, false, min, max)
+                })?;
+            }
+            SimdOperation::Extend {
+                shape,
+                high,
+                signed,
+            } => {
+                let input_shape = shape.narrower();
+                let offset = if high { shape.lane_count() } else { 0 };
+                build_v128(code, shape, |code, lane| {
+                    push_lane(code, 0, input_shape, lane + offset, signed)?;
+                    widen_lane(code, shape, signed)
+                })?;
+            }
+            SimdOperation::ExtMul {
+                shape,
+                high,
+                signed,
+            } => {
+                let input_shape = shape.narrower();
+                let offset = if high { shape.lane_count() } else { 0 };
+                build_v128(code, shape, |code, lane| {
+                    push_lane(code, 0, input_shape, lane + offset, signed)?;
+                    widen_lane(code, shape, signed)?;
+                    push_lane(code, 1, input_shape, lane + offset, signed)?;
+                    widen_lane(code, shape, signed)?;
+                    if shape == LaneShape::I64x2 {
+                        code.push_instruction(Instruction::LMul)?;
+                    } else {
+                        code.push_instruction(Instruction::IMul)?;
+                    }
+                    Ok(())
+                })?;
+            }
+            SimdOperation::ExtAddPairwise { shape, signed } => {
+                let input_shape = shape.narrower();
+                build_v128(code, shape, |code, lane| {
+                    push_lane(code, 0, input_shape, lane * 2, signed)?;
+                    push_lane(code, 0, input_shape, lane * 2 + 1, signed)?;
+                    code.push_instruction(Instruction::IAdd)?;
+                    Ok(())
+                })?;
+            }
+            SimdOperation::Dot => {
+                build_v128(code, LaneShape::I32x4, |code, lane| {
+                    for input_lane in [lane * 2, lane * 2 + 1] {
+                        push_lane(code, 0, LaneShape::I16x8, input_lane, true)?;
+                        push_lane(code, 1, LaneShape::I16x8, input_lane, true)?;
+                        code.push_instruction(Instruction::IMul)?;
+                    }
+                    code.push_instruction(Instruction::IAdd)?;
+                    Ok(())
+                })?;
+            }
+            SimdOperation::Convert(op) => Self::generate_convert(code, op)?,
+        }
+        code.push_branch_instruction(BranchInstruction::AReturn)?;
+
+        Ok(())
+    }
+
+    fn generate_replace_bits<'g>(code: &mut CodeBuilder<'g>) -> Result<(), Error> {
+        // Copy the input array
+        code.const_int(2)?;
+        code.push_instruction(Instruction::NewArray(BaseType::Long))?;
+        code.push_instruction(Instruction::AStore(6))?;
+        for half in 0..2 {
+            code.push_instruction(Instruction::ALoad(6))?;
+            code.const_int(half)?;
+            code.push_instruction(Instruction::ALoad(0))?;
+            code.const_int(half)?;
+            code.push_instruction(Instruction::LALoad)?;
+            code.push_instruction(Instruction::LAStore)?;
+        }
+
+        // Update the one half that changes
+        code.push_instruction(Instruction::ALoad(6))?;
+        code.push_instruction(Instruction::ILoad(3))?;
+        code.push_instruction(Instruction::ALoad(0))?;
+        code.push_instruction(Instruction::ILoad(3))?;
+        code.push_instruction(Instruction::LALoad)?;
+        code.push_instruction(Instruction::LLoad(4))?;
+        code.push_instruction(Instruction::LAnd)?;
+        code.push_instruction(Instruction::LLoad(1))?;
+        code.push_instruction(Instruction::LOr)?;
+        code.push_instruction(Instruction::LAStore)?;
+
+        code.push_instruction(Instruction::ALoad(6))?;
+        Ok(())
+    }
+
+    fn generate_swizzle<'g>(code: &mut CodeBuilder<'g>) -> Result<(), Error> {
+        build_v128(code, LaneShape::I8x16, |code, lane| {
+            let in_range = code.fresh_label();
+            let end = code.fresh_label();
+
+            // Index of the byte to select (stashed in a temporary variable)
+            push_lane(code, 1, LaneShape::I8x16, lane, false)?;
+            code.push_instruction(Instruction::IStore(2))?;
+            code.push_instruction(Instruction::ILoad(2))?;
+            code.const_int(16)?;
+            code.push_branch_instruction(BranchInstruction::IfICmp(
+                OrdComparison::LT,
+                in_range,
+                (),
+            ))?;
+
+            // Out of range indices select 0
+            code.push_instruction(Instruction::IConst0)?;
+            code.push_branch_instruction(BranchInstruction::Goto(end))?;
+
+            // `(int) (input[index >> 3] >>> ((index & 7) * 8))`
+            code.place_label(in_range)?;
+            code.push_instruction(Instruction::ALoad(0))?;
+            code.push_instruction(Instruction::ILoad(2))?;
+            code.const_int(3)?;
+            code.push_instruction(Instruction::ISh(ShiftType::ArithmeticRight))?;
+            code.push_instruction(Instruction::LALoad)?;
+            code.push_instruction(Instruction::ILoad(2))?;
+            code.const_int(7)?;
+            code.push_instruction(Instruction::IAnd)?;
+            code.const_int(3)?;
+            code.push_instruction(Instruction::ISh(ShiftType::Left))?;
+            code.push_instruction(Instruction::LSh(ShiftType::LogicalRight))?;
+            code.push_instruction(Instruction::L2I)?;
+
+            code.place_label(end)?;
+            Ok(())
+        })
+    }
+
+    fn generate_bitwise<'g>(
+        code: &mut CodeBuilder<'g>,
+        op: BitwiseOp,
+        half: u8,
+    ) -> Result<(), Error> {
+        let push_half = |code: &mut CodeBuilder<'g>, local: u16| -> Result<(), Error> {
+            push_lane(code, local, LaneShape::I64x2, half, true)
+        };
+        let not = |code: &mut CodeBuilder<'g>| -> Result<(), Error> {
+            code.const_long(-1)?;
+            code.push_instruction(Instruction::LXor)?;
+            Ok(())
+        };
+
+        match op {
+            BitwiseOp::Not => {
+                push_half(code, 0)?;
+                not(code)?;
+            }
+            BitwiseOp::And | BitwiseOp::Or | BitwiseOp::Xor => {
+                push_half(code, 0)?;
+                push_half(code, 1)?;
+                code.push_instruction(match op {
+                    BitwiseOp::And => Instruction::LAnd,
+                    BitwiseOp::Or => Instruction::LOr,
+                    _ => Instruction::LXor,
+                })?;
+            }
+            BitwiseOp::AndNot => {
+                push_half(code, 0)?;
+                push_half(code, 1)?;
+                not(code)?;
+                code.push_instruction(Instruction::LAnd)?;
+            }
+            BitwiseOp::Bitselect => {
+                push_half(code, 0)?;
+                push_half(code, 2)?;
+                code.push_instruction(Instruction::LAnd)?;
+                push_half(code, 1)?;
+                push_half(code, 2)?;
+                not(code)?;
+                code.push_instruction(Instruction::LAnd)?;
+                code.push_instruction(Instruction::LOr)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn generate_unary<'g>(
+        code: &mut CodeBuilder<'g>,
+        shape: LaneShape,
+        op: UnaryOp,
+    ) -> Result<(), Error> {
+        let is_long = shape == LaneShape::I64x2;
+        match (shape, op) {
+            // Floating point absolute value and negation only touch the sign bit
+            (LaneShape::F32x4 | LaneShape::F64x2, UnaryOp::Abs | UnaryOp::Neg) => {
+                let bits_shape = shape.bits_shape();
+                let sign_bit = 1i64 << (bits_shape.lane_bits() - 1);
+                build_v128(code, bits_shape, |code, lane| {
+                    push_lane(code, 0, bits_shape, lane, true)?;
+                    match (bits_shape, op) {
+                        (LaneShape::I32x4, UnaryOp::Abs) => {
+                            code.const_int(!(sign_bit as i32))?;
+                            code.push_instruction(Instruction::IAnd)?;
+                        }
+                        (LaneShape::I32x4, _) => {
+                            code.const_int(sign_bit as i32)?;
+                            code.push_instruction(Instruction::IXor)?;
+                        }
+                        (_, UnaryOp::Abs) => {
+                            code.const_long(!sign_bit)?;
+                            code.push_instruction(Instruction::LAnd)?;
+                        }
+                        (_, _) => {
+                            code.const_long(sign_bit)?;
+                            code.push_instruction(Instruction::LXor)?;
+                        }
+                    }
+                    Ok(())
+                })?;
+            }
+
+            (_, UnaryOp::Abs) => {
+                build_v128(code, shape, |code, lane| {
+                    let end = code.fresh_label();
+                    push_lane(code, 0, shape, lane, true)?;
+                    if is_long {
+                        code.push_instruction(Instruction::Dup2)?;
+                        code.push_instruction(Instruction::LConst0)?;
+                        code.push_instruction(Instruction::LCmp)?;
+                    } else {
+                        code.push_instruction(Instruction::Dup)?;
+                    }
+                    code.push_branch_instruction(BranchInstruction::If(
+                        OrdComparison::GE,
+                        end,
+                        (),
+                    ))?;
+                    code.push_instruction(if is_long {
+                        Instruction::LNeg
+                    } else {
+                        Instruction::INeg
+                    })?;
+                    code.place_label(end)?;
+                    Ok(())
+                })?;
+            }
+            (_, UnaryOp::Neg) => {
+                build_v128(code, shape, |code, lane| {
+                    push_lane(code, 0, shape, lane, true)?;
+                    code.push_instruction(if is_long {
+                        Instruction::LNeg
+                    } else {
+                        Instruction::INeg
+                    })?;
+                    Ok(())
+                })?;
+            }
+            (_, UnaryOp::Popcnt) => {
+                build_v128(code, shape, |code, lane| {
+                    push_lane(code, 0, shape, lane, false)?;
+                    code.invoke(code.java.members.lang.integer.bit_count)?;
+                    Ok(())
+                })?;
+            }
+
+            // Remaining operations are all floating point rounding operations
+            (_, _) => {
+                let is_float = shape == LaneShape::F32x4;
+                build_v128(code, shape, |code, lane| {
+                    push_lane(code, 0, shape, lane, true)?;
+                    if is_float {
+                        code.push_instruction(Instruction::F2D)?;
+                    }
+                    match op {
+                        UnaryOp::Sqrt => code.invoke(code.java.members.lang.math.sqrt)?,
+                        UnaryOp::Ceil => code.invoke(code.java.members.lang.math.ceil)?,
+                        UnaryOp::Floor => code.invoke(code.java.members.lang.math.floor)?,
+                        UnaryOp::Nearest => code.invoke(code.java.members.lang.math.rint)?,
+                        _ => {
+                            let negative = code.fresh_label();
+                            let end = code.fresh_label();
+                            code.push_instruction(Instruction::Dup2)?;
+                            code.push_instruction(Instruction::DConst0)?;
+                            code.push_instruction(Instruction::DCmp(CompareMode::G))?;
+                            code.push_branch_instruction(BranchInstruction::If(
+                                OrdComparison::LT,
+                                negative,
+                                (),
+                            ))?;
+                            code.invoke(code.java.members.lang.math.floor)?;
+                            code.push_branch_instruction(BranchInstruction::Goto(end))?;
+                            code.place_label(negative)?;
+                            code.invoke(code.java.members.lang.math.ceil)?;
+                            code.place_label(end)?;
+                        }
+                    }
+                    if is_float {
+                        code.push_instruction(Instruction::D2F)?;
+                    }
+                    Ok(())
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    fn generate_binary<'g>(
+        code: &mut CodeBuilder<'g>,
+        shape: LaneShape,
+        op: BinaryOp,
+    ) -> Result<(), Error> {
+        let signed = !matches!(
+            op,
+            BinaryOp::AddSatU
+                | BinaryOp::SubSatU
+                | BinaryOp::MinU
+                | BinaryOp::MaxU
+                | BinaryOp::AvgrU
+        );
+
+        build_v128(code, shape, |code, lane| {
+            match op {
+                BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div => {
+                    push_lane(code, 0, shape, lane, signed)?;
+                    push_lane(code, 1, shape, lane, signed)?;
+                    code.push_instruction(arithmetic_instruction(shape, op))?;
+                }
+                BinaryOp::AddSatS | BinaryOp::AddSatU | BinaryOp::SubSatS | BinaryOp::SubSatU => {
+                    push_lane(code, 0, shape, lane, signed)?;
+                    push_lane(code, 1, shape, lane, signed)?;
+                    if matches!(op, BinaryOp::AddSatS | BinaryOp::AddSatU) {
+                        code.push_instruction(Instruction::IAdd)?;
+                    } else {
+                        code.push_instruction(Instruction::ISub)?;
+                    }
+                    let (min, max) = lane_range(shape, signed);
+                    clamp(code, false, min, max)?;
+                }
+                BinaryOp::MinS | BinaryOp::MinU | BinaryOp::Min => {
+                    if shape == LaneShape::F32x4 {
+                        push_lane(code, 0, shape, lane, signed)?;
+                        push_lane(code, 1, shape, lane, signed)?;
+                        code.invoke(code.java.members.lang.float.min)?;
+                    } else if shape == LaneShape::F64x2 {
+                        push_lane(code, 0, shape, lane, signed)?;
+                        push_lane(code, 1, shape, lane, signed)?;
+                        code.invoke(code.java.members.lang.double.min)?;
+                    } else {
+                        select_lane(code, shape, lane, signed, OrdComparison::LE, 0, 1)?;
+                    }
+                }
+                BinaryOp::MaxS | BinaryOp::MaxU | BinaryOp::Max => {
+                    if shape == LaneShape::F32x4 {
+                        push_lane(code, 0, shape, lane, signed)?;
+                        push_lane(code, 1, shape, lane, signed)?;
+                        code.invoke(code.java.members.lang.float.max)?;
+                    } else if shape == LaneShape::F64x2 {
+                        push_lane(code, 0, shape, lane, signed)?;
+                        push_lane(code, 1, shape, lane, signed)?;
+                        code.invoke(code.java.members.lang.double.max)?;
+                    } else {
+                        select_lane(code, shape, lane, signed, OrdComparison::GE, 0, 1)?;
+                    }
+                }
+                BinaryOp::PMin => {
+                    // `b < a ? b : a`
+                    select_lane(code, shape, lane, signed, OrdComparison::LT, 1, 0)?;
+                }
+                BinaryOp::PMax => {
+                    // `a < b ? b : a`
+                    select_lane(code, shape, lane, signed, OrdComparison::GT, 1, 0)?;
+                }
+                BinaryOp::AvgrU => {
+                    push_lane(code, 0, shape, lane, signed)?;
+                    push_lane(code, 1, shape, lane, signed)?;
+                    code.push_instruction(Instruction::IAdd)?;
+                    code.push_instruction(Instruction::IConst1)?;
+                    code.push_instruction(Instruction::IAdd)?;
+                    code.push_instruction(Instruction::IConst1)?;
+                    code.push_instruction(Instruction::ISh(ShiftType::LogicalRight))?;
+                }
+                BinaryOp::Q15MulrSatS => {
+                    push_lane(code, 0, shape, lane, signed)?;
+                    push_lane(code, 1, shape, lane, signed)?;
+                    code.push_instruction(Instruction::IMul)?;
+                    code.const_int(0x4000)?;
+                    code.push_instruction(Instruction::IAdd)?;
+                    code.const_int(15)?;
+                    code.push_instruction(Instruction::ISh(ShiftType::ArithmeticRight))?;
+                    let (min, max) = lane_range(shape, signed);
+                    clamp(code, false, min, max)?;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    fn generate_compare<'g>(
+        code: &mut CodeBuilder<'g>,
+        shape: LaneShape,
+        op: CompareOp,
+    ) -> Result<(), Error> {
+        let (ord, signed) = match op {
+            CompareOp::Eq => (OrdComparison::EQ, true),
+            CompareOp::Ne => (OrdComparison::NE, true),
+            CompareOp::LtS => (OrdComparison::LT, true),
+            CompareOp::LtU => (OrdComparison::LT, false),
+            CompareOp::GtS => (OrdComparison::GT, true),
+            CompareOp::GtU => (OrdComparison::GT, false),
+            CompareOp::LeS => (OrdComparison::LE, true),
+            CompareOp::LeU => (OrdComparison::LE, false),
+            CompareOp::GeS => (OrdComparison::GE, true),
+            CompareOp::GeU => (OrdComparison::GE, false),
+        };
+        let output_shape = shape.bits_shape();
+
+        build_v128(code, output_shape, |code, lane| {
+            let is_true = code.fresh_label();
+            let end = code.fresh_label();
+
+            push_lane(code, 0, shape, lane, signed)?;
+            push_lane(code, 1, shape, lane, signed)?;
+            compare_lanes(code, shape, signed, ord, is_true)?;
+            if output_shape == LaneShape::I64x2 {
+                code.const_long(0)?;
+                code.push_branch_instruction(BranchInstruction::Goto(end))?;
+                code.place_label(is_true)?;
+                code.const_long(-1)?;
+            } else {
+                code.const_int(0)?;
+                code.push_branch_instruction(BranchInstruction::Goto(end))?;
+                code.place_label(is_true)?;
+                code.const_int(-1)?;
+            }
+            code.place_label(end)?;
+
+            Ok(())
+        })
+    }
+
+    fn generate_convert<'g>(code: &mut CodeBuilder<'g>, op: ConvertOp) -> Result<(), Error> {
+        let (input_shape, output_shape) = match op {
+            ConvertOp::I32x4TruncSatF32x4S | ConvertOp::I32x4TruncSatF32x4U => {
+                (LaneShape::F32x4, LaneShape::I32x4)
+            }
+            ConvertOp::F32x4ConvertI32x4S | ConvertOp::F32x4ConvertI32x4U => {
+                (LaneShape::I32x4, LaneShape::F32x4)
+            }
+            ConvertOp::I32x4TruncSatF64x2SZero | ConvertOp::I32x4TruncSatF64x2UZero => {
+                (LaneShape::F64x2, LaneShape::I32x4)
+            }
+            ConvertOp::F64x2ConvertLowI32x4S | ConvertOp::F64x2ConvertLowI32x4U => {
+                (LaneShape::I32x4, LaneShape::F64x2)
+            }
+            ConvertOp::F32x4DemoteF64x2Zero => (LaneShape::F64x2, LaneShape::F32x4),
+            ConvertOp::F64x2PromoteLowF32x4 => (LaneShape::F32x4, LaneShape::F64x2),
+        };
+
+        build_v128(code, output_shape, |code, lane| {
+            // Lanes past the end of the input are zero
+            if lane >= input_shape.lane_count() {
+                if output_shape == LaneShape::F32x4 {
+                    code.push_instruction(Instruction::FConst0)?;
+                } else {
+                    code.const_int(0)?;
+                }
+                return Ok(());
+            }
+
+            push_lane(code, 0, input_shape, lane, true)?;
+            match op {
+                ConvertOp::I32x4TruncSatF32x4S => code.push_instruction(Instruction::F2I)?,
+                ConvertOp::I32x4TruncSatF64x2SZero => code.push_instruction(Instruction::D2I)?,
+                ConvertOp::I32x4TruncSatF32x4U | ConvertOp::I32x4TruncSatF64x2UZero => {
+                    if input_shape == LaneShape::F32x4 {
+                        code.push_instruction(Instruction::F2L)?;
+                    } else {
+                        code.push_instruction(Instruction::D2L)?;
+                    }
+                    clamp(code, true, 0, 0xFFFF_FFFF)?;
+                    code.push_instruction(Instruction::L2I)?;
+                }
+                ConvertOp::F32x4ConvertI32x4S => code.push_instruction(Instruction::I2F)?,
+                ConvertOp::F64x2ConvertLowI32x4S => code.push_instruction(Instruction::I2D)?,
+                ConvertOp::F32x4ConvertI32x4U | ConvertOp::F64x2ConvertLowI32x4U => {
+                    widen_lane(code, LaneShape::I64x2, false)?;
+                    if output_shape == LaneShape::F32x4 {
+                        code.push_instruction(Instruction::L2F)?;
+                    } else {
+                        code.push_instruction(Instruction::L2D)?;
+                    }
+                }
+                ConvertOp::F32x4DemoteF64x2Zero => code.push_instruction(Instruction::D2F)?,
+                ConvertOp::F64x2PromoteLowF32x4 => code.push_instruction(Instruction::F2D)?,
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Push a constant `v128` onto the stack
+pub fn const_v128<'g>(code: &mut CodeBuilder<'g>, value: u128) -> Result<(), Error> {
+    code.const_int(2)?;
+    code.push_instruction(Instruction::NewArray(BaseType::Long))?;
+    for (half, bits) in [value as i64, (value >> 64) as i64].into_iter().enumerate() {
+        code.push_instruction(Instruction::Dup)?;
+        code.const_int(half as i32)?;
+        code.const_long(bits)?;
+        code.push_instruction(Instruction::LAStore)?;
+    }
+    Ok(())
+}
+
+/// Extract a lane from the `v128` on the top of the stack
+///
+/// Lanes narrower than an `int` are sign or zero extended based on `signed`.
+pub fn extract_lane<'g>(
+    code: &mut CodeBuilder<'g>,
+    shape: LaneShape,
+    lane: u8,
+    signed: bool,
+) -> Result<(), Error> {
+    let half = lane / shape.lanes_per_half();
+    let shift = (lane % shape.lanes_per_half()) * shape.lane_bits();
+
+    code.const_int(half as i32)?;
+    code.push_instruction(Instruction::LALoad)?;
+    if shift != 0 {
+        code.const_int(shift as i32)?;
+        code.push_instruction(Instruction::LSh(ShiftType::LogicalRight))?;
+    }
+
+    match shape {
+        LaneShape::I8x16 | LaneShape::I16x8 => {
+            code.push_instruction(Instruction::L2I)?;
+            match (shape, signed) {
+                (LaneShape::I8x16, true) => code.push_instruction(Instruction::I2B)?,
+                (_, true) => code.push_instruction(Instruction::I2S)?,
+                (_, false) => {
+                    code.const_int((1 << shape.lane_bits()) - 1)?;
+                    code.push_instruction(Instruction::IAnd)?;
+                }
+            }
+        }
+        LaneShape::I32x4 => code.push_instruction(Instruction::L2I)?,
+        LaneShape::I64x2 => (),
+        LaneShape::F32x4 => {
+            code.push_instruction(Instruction::L2I)?;
+            code.invoke(code.java.members.lang.float.int_bits_to_float)?;
+        }
+        LaneShape::F64x2 => code.invoke(code.java.members.lang.double.long_bits_to_double)?,
+    }
+
+    Ok(())
+}
+
+/// Convert the lane value on the top of the stack into a `long` with the lane bits in their
+/// position within a `v128` half (all other bits are 0)
+///
+/// This is the inverse of `extract_lane`.
+pub fn lane_to_bits<'g>(
+    code: &mut CodeBuilder<'g>,
+    shape: LaneShape,
+    lane: u8,
+) -> Result<(), Error> {
+    let shift = (lane % shape.lanes_per_half()) * shape.lane_bits();
+
+    match shape {
+        LaneShape::I8x16 | LaneShape::I16x8 | LaneShape::I32x4 | LaneShape::F32x4 => {
+            if shape == LaneShape::F32x4 {
+                code.invoke(code.java.members.lang.float.float_to_raw_int_bits)?;
+            }
+            code.push_instruction(Instruction::I2L)?;
+            if shift + shape.lane_bits() < 64 {
+                code.const_long((1 << shape.lane_bits()) - 1)?;
+                code.push_instruction(Instruction::LAnd)?;
+            }
+        }
+        LaneShape::I64x2 => (),
+        LaneShape::F64x2 => code.invoke(code.java.members.lang.double.double_to_raw_long_bits)?,
+    }
+    if shift != 0 {
+        code.const_int(shift as i32)?;
+        code.push_instruction(Instruction::LSh(ShiftType::Left))?;
+    }
+
+    Ok(())
+}
+
+/// Mask of the bits of a `v128` half that are _not_ in the specified lane
+pub fn lane_keep_mask(shape: LaneShape, lane: u8) -> i64 {
+    let shift = (lane % shape.lanes_per_half()) * shape.lane_bits();
+    let lane_mask = if shape.lane_bits() == 64 {
+        -1i64
+    } else {
+        ((1i64 << shape.lane_bits()) - 1) << shift
+    };
+    !lane_mask
+}
+
+/// Index of the `v128` half containing a lane
+pub fn lane_half(shape: LaneShape, lane: u8) -> u8 {
+    lane / shape.lanes_per_half()
+}
+
+/// Push onto the stack a lane from the `v128` in the specified local
+fn push_lane<'g>(
+    code: &mut CodeBuilder<'g>,
+    local: u16,
+    shape: LaneShape,
+    lane: u8,
+    signed: bool,
+) -> Result<(), Error> {
+    code.push_instruction(Instruction::ALoad(local))?;
+    extract_lane(code, shape, lane, signed)
+}
+
+/// Build a fresh `v128` and leave it on the stack
+///
+/// The callback is used to push each lane value (of type `shape.lane_type()`) onto the stack.
+fn build_v128<'g>(
+    code: &mut CodeBuilder<'g>,
+    shape: LaneShape,
+    mut push_lane_value: impl FnMut(&mut CodeBuilder<'g>, u8) -> Result<(), Error>,
+) -> Result<(), Error> {
+    code.const_int(2)?;
+    code.push_instruction(Instruction::NewArray(BaseType::Long))?;
+    for half in 0..2 {
+        code.push_instruction(Instruction::Dup)?;
+        code.const_int(half)?;
+        for idx in 0..shape.lanes_per_half() {
+            let lane = half as u8 * shape.lanes_per_half() + idx;
+            push_lane_value(code, lane)?;
+            lane_to_bits(code, shape, lane)?;
+            if idx != 0 {
+                code.push_instruction(Instruction::LOr)?;
+            }
+        }
+        code.push_instruction(Instruction::LAStore)?;
+    }
+    Ok(())
+}
+
+/// Range of values representable in a lane of an integer shape
+fn lane_range(shape: LaneShape, signed: bool) -> (i64, i64) {
+    let bits = shape.lane_bits();
+    if signed {
+        (-(1i64 << (bits - 1)), (1i64 << (bits - 1)) - 1)
+    } else {
+        (0, (1i64 << bits) - 1)
+    }
+}
+
+/// Convert the integer lane on top of the stack into a lane of the specified wider shape
+///
+/// This is only needed when widening into `long` lanes.
+fn widen_lane<'g>(code: &mut CodeBuilder<'g>, shape: LaneShape, signed: bool) -> Result<(), Error> {
+    if shape == LaneShape::I64x2 {
+        code.push_instruction(Instruction::I2L)?;
+        if !signed {
+            code.const_long(0xFFFF_FFFF)?;
+            code.push_instruction(Instruction::LAnd)?;
+        }
+    }
+    Ok(())
+}
+
+/// Clamp the `int` (or `long`) on the top of the stack into a range
+fn clamp<'g>(code: &mut CodeBuilder<'g>, is_long: bool, min: i64, max: i64) -> Result<(), Error> {
+    for (bound, ord) in [(max, OrdComparison::LE), (min, OrdComparison::GE)] {
+        let in_bound = code.fresh_label();
+        if is_long {
+            code.push_instruction(Instruction::Dup2)?;
+            code.const_long(bound)?;
+            code.push_instruction(Instruction::LCmp)?;
+            code.push_branch_instruction(BranchInstruction::If(ord, in_bound, ()))?;
+            code.push_instruction(Instruction::Pop2)?;
+            code.const_long(bound)?;
+        } else {
+            code.push_instruction(Instruction::Dup)?;
+            code.const_int(bound as i32)?;
+            code.push_branch_instruction(BranchInstruction::IfICmp(ord, in_bound, ()))?;
+            code.push_instruction(Instruction::Pop)?;
+            code.const_int(bound as i32)?;
+        }
+        code.place_label(in_bound)?;
+    }
+    Ok(())
+}
+
+/// Compare the two lanes on the top of the stack, and jump to the label if the comparison holds
+fn compare_lanes<'g>(
+    code: &mut CodeBuilder<'g>,
+    shape: LaneShape,
+    signed: bool,
+    ord: OrdComparison,
+    target: SynLabel,
+) -> Result<(), Error> {
+    // NaN must make all comparisons (except `!=`) false
+    let nan_mode = match ord {
+        OrdComparison::LT | OrdComparison::LE => CompareMode::G,
+        _ => CompareMode::L,
+    };
+
+    let cond = match shape {
+        LaneShape::I8x16 | LaneShape::I16x8 => BranchCond::IfICmp(ord),
+        LaneShape::I32x4 if signed => BranchCond::IfICmp(ord),
+        LaneShape::I32x4 => {
+            code.invoke(code.java.members.lang.integer.compare_unsigned)?;
+            BranchCond::If(ord)
+        }
+        LaneShape::I64x2 if signed => {
+            code.push_instruction(Instruction::LCmp)?;
+            BranchCond::If(ord)
+        }
+        LaneShape::I64x2 => {
+            code.invoke(code.java.members.lang.long.compare_unsigned)?;
+            BranchCond::If(ord)
+        }
+        LaneShape::F32x4 => {
+            code.push_instruction(Instruction::FCmp(nan_mode))?;
+            BranchCond::If(ord)
+        }
+        LaneShape::F64x2 => {
+            code.push_instruction(Instruction::DCmp(nan_mode))?;
+            BranchCond::If(ord)
+        }
+    };
+    code.push_branch_instruction(cond.into_instruction(target, ()))?;
+
+    Ok(())
+}
+
+/// Push onto the stack the lane from local `first` if `first <ord> second`, else the lane from
+/// local `second`
+fn select_lane<'g>(
+    code: &mut CodeBuilder<'g>,
+    shape: LaneShape,
+    lane: u8,
+    signed: bool,
+    ord: OrdComparison,
+    first: u16,
+    second: u16,
+) -> Result<(), Error> {
+    let take_first = code.fresh_label();
+    let end = code.fresh_label();
+
+    push_lane(code, first, shape, lane, signed)?;
+    push_lane(code, second, shape, lane, signed)?;
+    compare_lanes(code, shape, signed, ord, take_first)?;
+    push_lane(code, second, shape, lane, signed)?;
+    code.push_branch_instruction(BranchInstruction::Goto(end))?;
+    code.place_label(take_first)?;
+    push_lane(code, first, shape, lane, signed)?;
+    code.place_label(end)?;
+
+    Ok(())
+}
+
+/// Instruction for a simple arithmetic operation on lanes of the specified shape
+fn arithmetic_instruction<'g>(shape: LaneShape, op: BinaryOp) -> VerifierInstruction<'g> {
+    match (shape.lane_type(), op) {
+        (FieldType::Base(BaseType::Long), BinaryOp::Add) => Instruction::LAdd,
+        (FieldType::Base(BaseType::Long), BinaryOp::Sub) => Instruction::LSub,
+        (FieldType::Base(BaseType::Long), _) => Instruction::LMul,
+        (FieldType::Base(BaseType::Float), BinaryOp::Add) => Instruction::FAdd,
+        (FieldType::Base(BaseType::Float), BinaryOp::Sub) => Instruction::FSub,
+        (FieldType::Base(BaseType::Float), BinaryOp::Mul) => Instruction::FMul,
+        (FieldType::Base(BaseType::Float), _) => Instruction::FDiv,
+        (FieldType::Base(BaseType::Double), BinaryOp::Add) => Instruction::DAdd,
+        (FieldType::Base(BaseType::Double), BinaryOp::Sub) => Instruction::DSub,
+        (FieldType::Base(BaseType::Double), BinaryOp::Mul) => Instruction::DMul,
+        (FieldType::Base(BaseType::Double), _) => Instruction::DDiv,
+        (_, BinaryOp::Add) => Instruction::IAdd,
+        (_, BinaryOp::Sub) => Instruction::ISub,
+        (_, _) => Instruction::IMul,
+    }
+}
@@ -0,0 +1,717 @@
+//! Splitting of functions which are too big for one JVM method
+//!
+//! The JVM caps the bytecode of a method at 65535 bytes. When the translation of a function goes
+//! over that, the function is translated again, but with some regions of its body each translated
+//! into their own static method (a "split" method) which the function calls instead. A region is
+//! either a run of consecutive statements (operators between which the operand stack of the
+//! enclosing frame is empty) or a single `block` or `loop`. Regions can themselves contain
+//! regions, when a split method is still too big.
+//!
+//! Split methods have the signature
+//! `int split(int[] ints, long[] longs, float[] floats, double[] doubles, Object[] objects, MyWasmModule module)`.
+//! Those arrays are the "frame" of the function: it allocates them once on entry, and every WASM
+//! local accessed in a region lives in them (in the function and in all of its split methods), so
+//! calling a split method doesn't need to copy any locals. Stack values going in and out of a
+//! region (the inputs and outputs of a `block`, or the values being branched with) are packed into
+//! `objects[0]`.
+//!
+//! The returned `int` is how control left the region: `0` when falling through its end, `1` for
+//! a return out of the function, and `i + 2` for a branch to the `i`-th enclosing control frame
+//! (counting from the outermost frame). The caller then dispatches on that value.
+
+use super::{read_locals, Error, OffsetOperator};
+use crate::jvm::class_graph::{ClassId, JavaClasses, MethodId};
+use crate::jvm::{BaseType, FieldType, MethodDescriptor};
+use crate::wasm::StackType;
+use std::cmp::Reverse;
+use std::collections::BTreeSet;
+use wasmparser::{BlockType, FuncValidator, FunctionBody, Operator, ValType, WasmModuleResources};
+
+/// Maximum length of the bytecode array of a JVM method
+pub const MAX_CODE_LENGTH: usize = u16::MAX as usize;
+
+/// Estimated size (in bytes of WASM) of the code standing in for a region, which calls the split
+/// method and dispatches on how control left the region
+const SPLIT_CALL_SIZE: usize = 16;
+
+/// Array of the frame of a split function
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FrameArray {
+    Int,
+    Long,
+    Float,
+    Double,
+    Object,
+}
+
+impl FrameArray {
+    /// All frame arrays, in the order they are passed to split methods
+    pub const ALL: [FrameArray; 5] = [
+        FrameArray::Int,
+        FrameArray::Long,
+        FrameArray::Float,
+        FrameArray::Double,
+        FrameArray::Object,
+    ];
+
+    /// Array in which a local of some type is stored
+    pub fn of(local_type: StackType) -> FrameArray {
+        match local_type {
+            StackType::I32 => FrameArray::Int,
+            StackType::I64 => FrameArray::Long,
+            StackType::F32 => FrameArray::Float,
+            StackType::F64 => FrameArray::Double,
+            StackType::V128 | StackType::FuncRef | StackType::ExternRef => FrameArray::Object,
+        }
+    }
+
+    /// Type of the array
+    pub fn field_type<'g>(self, java: &JavaClasses<'g>) -> FieldType<ClassId<'g>> {
+        FieldType::array(match self {
+            FrameArray::Int => FieldType::int(),
+            FrameArray::Long => FieldType::long(),
+            FrameArray::Float => FieldType::float(),
+            FrameArray::Double => FieldType::double(),
+            FrameArray::Object => FieldType::object(java.lang.object),
+        })
+    }
+}
+
+/// Span of operators (by index) which gets translated into its own split method
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SplitSpan {
+    /// Index of the first operator
+    pub first: usize,
+
+    /// Index of the last operator
+    pub last: usize,
+
+    /// If the span is a single `block` or `loop`, its type (its inputs and outputs then go in and
+    /// out of the split method)
+    ///
+    /// Other spans are runs of statements, which leave the operand stack as they found it.
+    pub blockty: Option<BlockType>,
+}
+
+/// Region of a function which gets translated into its own split method
+pub struct SplitRegion<'g> {
+    /// Offset of the first operator in the region
+    pub start: usize,
+
+    /// Offset of the last operator in the region
+    pub end: usize,
+
+    /// Type of the region, if it is a single `block` or `loop` (see `SplitSpan`)
+    pub blockty: Option<BlockType>,
+
+    /// Index of the region containing this one, if any
+    pub parent: Option<usize>,
+
+    /// Method into which the region gets translated
+    pub method: MethodId<'g>,
+}
+
+impl<'g> SplitRegion<'g> {
+    /// Descriptor of split methods, given the class of the WASM module
+    pub fn method_descriptor(
+        java: &JavaClasses<'g>,
+        module_class: ClassId<'g>,
+    ) -> MethodDescriptor<ClassId<'g>> {
+        let mut parameters: Vec<FieldType<ClassId<'g>>> = FrameArray::ALL
+            .iter()
+            .map(|array| array.field_type(java))
+            .collect();
+        parameters.push(FieldType::object(module_class));
+        MethodDescriptor {
+            parameters,
+            return_type: Some(FieldType::Base(BaseType::Int)),
+        }
+    }
+}
+
+/// How a function is split up into methods
+#[derive(Default)]
+pub struct SplitFunction<'g> {
+    /// Regions translated into split methods, ordered by their start (enclosing regions first)
+    pub regions: Vec<SplitRegion<'g>>,
+
+    /// For each WASM local, where it is stored in the frame (if it is accessed in some region)
+    ///
+    /// Locals of inlined functions are never in the frame, since each inlined body ends up
+    /// entirely inside one method.
+    pub framed_locals: Vec<Option<(FrameArray, u32)>>,
+
+    /// Length of each of the frame arrays (see `FrameArray::ALL`)
+    pub frame_lengths: [u32; 5],
+}
+
+impl<'g> SplitFunction<'g> {
+    /// Lay out the regions of a function and the frame they share
+    ///
+    /// The spans must be sorted by their first operator (enclosing spans first) and must not
+    /// partially overlap. Locals from `inlined_locals_start` onwards are the locals of inlined
+    /// functions.
+    pub fn new(
+        operators: &[OffsetOperator],
+        spans: &[SplitSpan],
+        local_types: &[StackType],
+        inlined_locals_start: u32,
+        mut method_for_region: impl FnMut(usize) -> MethodId<'g>,
+    ) -> SplitFunction<'g> {
+        let mut regions: Vec<SplitRegion<'g>> = vec![];
+        let mut enclosing: Vec<(usize, usize)> = vec![];
+        for (region_idx, span) in spans.iter().enumerate() {
+            while matches!(enclosing.last(), Some((_, last)) if *last < span.first) {
+                enclosing.pop();
+            }
+            regions.push(SplitRegion {
+                start: operators[span.first].1,
+                end: operators[span.last].1,
+                blockty: span.blockty,
+                parent: enclosing.last().map(|(parent, _)| *parent),
+                method: method_for_region(region_idx),
+            });
+            enclosing.push((region_idx, span.last));
+        }
+
+        // Locals accessed anywhere in a region go in the frame (the `Object` array starts with the
+        // slot for stack values)
+        let mut accessed = BTreeSet::new();
+        for span in spans {
+            for (operator, _) in &operators[span.first..=span.last] {
+                if let Operator::LocalGet { local_index }
+                | Operator::LocalSet { local_index }
+                | Operator::LocalTee { local_index } = operator
+                {
+                    if *local_index < inlined_locals_start {
+                        accessed.insert(*local_index);
+                    }
+                }
+            }
+        }
+        let mut framed_locals = vec![None; local_types.len()];
+        let mut frame_lengths = [0, 0, 0, 0, 1];
+        for local_idx in accessed {
+            let array = FrameArray::of(local_types[local_idx as usize]);
+            let length = &mut frame_lengths[array as usize];
+            framed_locals[local_idx as usize] = Some((array, *length));
+            *length += 1;
+        }
+
+        SplitFunction {
+            regions,
+            framed_locals,
+            frame_lengths,
+        }
+    }
+
+    /// Regions directly inside of a region (or the function itself, for `None`)
+    pub fn children(&self, parent: Option<usize>) -> Vec<usize> {
+        self.regions
+            .iter()
+            .enumerate()
+            .filter(|(_, region)| region.parent == parent)
+            .map(|(region_idx, _)| region_idx)
+            .collect()
+    }
+}
+
+/// What splitting needs to know about a function body, worked out by validating it
+pub struct SplitAnalysis {
+    /// For each operator, whether it starts a statement: the operand stack of the innermost
+    /// control frame is empty before it, and it is reachable
+    pub statement_starts: Vec<bool>,
+
+    /// Types of the WASM locals (including parameters and the locals of inlined functions)
+    pub local_types: Vec<StackType>,
+}
+
+impl SplitAnalysis {
+    /// Analyze the operators of a function body
+    ///
+    /// Chains of `local.tee` (such as those `simplify_locals` makes out of straight line code)
+    /// have no statements to split at, so each `local.tee` which would end a statement if it were
+    /// a `local.set` gets turned back into a `local.set` followed by a `local.get`. That `local.get`
+    /// takes the offset right after the `local.tee`, which is never the offset of an operator since
+    /// `local.tee` is at least two bytes long. Locals of inlined functions are left alone, since
+    /// they must not be accessed outside of the inlined body.
+    pub fn new<T: WasmModuleResources>(
+        function_body: &FunctionBody,
+        operators: &mut Vec<OffsetOperator>,
+        inlined_locals: &[ValType],
+        validator: &mut FuncValidator<T>,
+    ) -> Result<SplitAnalysis, Error> {
+        read_locals(function_body, inlined_locals, validator)?;
+        let mut local_types = vec![];
+        for local_idx in 0..validator.len_locals() {
+            let local_type = validator.get_local_type(local_idx).expect("missing local");
+            local_types.push(StackType::from_general(local_type)?);
+        }
+
+        let inlined_locals_start = (local_types.len() - inlined_locals.len()) as u32;
+
+        // Frames opened in unreachable code are unreachable too, although the validator doesn't
+        // mark them as such
+        let mut unreachable_depth = 0;
+        let mut statement_starts = vec![];
+        let original_operators = std::mem::take(operators);
+        for (idx, (operator, offset)) in original_operators.iter().enumerate() {
+            let frame = validator
+                .get_control_frame(0)
+                .expect("missing control frame");
+            let height = validator.operand_stack_height() as usize;
+            let reachable = !frame.unreachable && unreachable_depth == 0;
+            statement_starts.push(reachable && frame.height == height);
+
+            let next_offset = original_operators
+                .get(idx + 1)
+                .map_or(usize::MAX, |(_, offset)| *offset);
+            match operator {
+                Operator::LocalTee { local_index }
+                    if reachable
+                        && frame.height + 1 == height
+                        && *offset + 1 < next_offset
+                        && *local_index < inlined_locals_start =>
+                {
+                    let set = Operator::LocalSet {
+                        local_index: *local_index,
+                    };
+                    let get = Operator::LocalGet {
+                        local_index: *local_index,
+                    };
+                    validator.op(*offset, &set)?;
+                    validator.op(*offset + 1, &get)?;
+                    operators.push((set, *offset));
+                    operators.push((get, *offset + 1));
+                    statement_starts.push(true);
+                    continue;
+                }
+                Operator::Block { .. }
+                | Operator::Loop { .. }
+                | Operator::If { .. }
+                | Operator::Try { .. }
+                    if !reachable =>
+                {
+                    unreachable_depth += 1
+                }
+                Operator::End | Operator::Delegate { .. } if unreachable_depth > 0 => {
+                    unreachable_depth -= 1
+                }
+                _ => (),
+            }
+            validator.op(*offset, operator)?;
+            operators.push((operator.clone(), *offset));
+        }
+
+        Ok(SplitAnalysis {
+            statement_starts,
+            local_types,
+        })
+    }
+}
+
+/// Group of consecutive operators in a control frame, delimited by statement starts
+struct Item {
+    /// Index of the first operator
+    first: usize,
+
+    /// Index of the last operator
+    last: usize,
+
+    /// Estimated size (in bytes of WASM, once the regions chosen inside it are split out)
+    size: usize,
+
+    /// Whether a region can start at the item
+    starts_statement: bool,
+
+    /// Whether a region can end after the item
+    ends_statement: bool,
+
+    /// Whether the item refers to a frame outside of itself (through `rethrow` or `delegate`) or
+    /// contains a tail call, so it can't be part of a region
+    pinned: bool,
+
+    /// `block`s and `loop`s nested in the item which could be split out on their own, along with
+    /// their sizes
+    frames: Vec<(SplitSpan, usize)>,
+}
+
+/// Control frame which is open while looking for regions
+struct Level {
+    /// Index of the operator opening the frame
+    first: usize,
+
+    /// Type of the frame, if it is a `block` or `loop`
+    blockty: Option<BlockType>,
+
+    /// Whether the frame could be split out on its own
+    splittable: bool,
+
+    /// Size of the operators of the frame which aren't in any item (`end`, `else`, etc.)
+    size: usize,
+
+    /// Items of the frame so far
+    items: Vec<Item>,
+}
+
+impl Level {
+    /// Item being extended, which gets started if there isn't one already
+    fn current_item(&mut self, first: usize, starts_statement: bool) -> &mut Item {
+        if self.items.last().map_or(true, |item| item.ends_statement) {
+            self.items.push(Item {
+                first,
+                last: first,
+                size: 0,
+                starts_statement,
+                ends_statement: false,
+                pinned: false,
+                frames: vec![],
+            });
+        }
+        self.items.last_mut().unwrap()
+    }
+
+    /// End the current item (if there is one) right before an operator
+    fn end_item(&mut self, next: usize, ends_statement: bool) {
+        if let Some(item) = self.items.last_mut() {
+            if !item.ends_statement {
+                item.last = next - 1;
+                item.ends_statement = ends_statement;
+            }
+        }
+    }
+
+    /// Add an operator which is a barrier between items (eg. `else`)
+    fn push_barrier(&mut self, idx: usize, size: usize) {
+        self.end_item(idx, false);
+        self.items.push(Item {
+            first: idx,
+            last: idx,
+            size,
+            starts_statement: false,
+            ends_statement: true,
+            pinned: true,
+            frames: vec![],
+        });
+    }
+}
+
+/// Find new regions to split out of a method which is still too big
+///
+/// The method covers the operators in `method` (the whole function or a region), and `children`
+/// are the spans of the regions already split out of it (sorted). Its translation was
+/// `code_length` bytes long, and assuming that the code grows linearly with the size of the WASM,
+/// the aim is for the method and every new region to be about half of the limit.
+///
+/// Frames are visited from the inside out. Once a frame is too big, its statements get grouped
+/// into runs which are just under the budget, and each run becomes a region. Statements which
+/// are too big for that on their own (eg. a huge expression) can still have `block`s and `loop`s
+/// inside of them split out. Frames which were inlined (whose operators all have the offset of
+/// the inlined `call`) are never cut into, since their locals must stay in one method.
+///
+/// Regions never contain tail calls (which need to return from the function's own method) or
+/// `rethrow`/`delegate` to a frame outside of them, and regions smaller than an eighth of the
+/// budget aren't worth the cost of calling a split method.
+pub fn find_split_regions(
+    operators: &[OffsetOperator],
+    statement_starts: &[bool],
+    method: (usize, usize),
+    children: &[SplitSpan],
+    code_length: usize,
+) -> Vec<SplitSpan> {
+    let offset_after = |idx: usize| {
+        operators
+            .get(idx + 1)
+            .map_or(operators[idx].1 + 1, |(_, offset)| *offset)
+    };
+    let raw_size = |first: usize, last: usize| offset_after(last) - operators[first].1;
+
+    // Size of the method, counting its children as calls
+    let children_size: usize = children
+        .iter()
+        .map(|child| raw_size(child.first, child.last))
+        .sum();
+    let method_size =
+        raw_size(method.0, method.1) - children_size + children.len() * SPLIT_CALL_SIZE;
+
+    let mut budget = method_size * (MAX_CODE_LENGTH / 2) / code_length.max(1);
+    while budget > 0 {
+        let spans = find_regions_with_budget(
+            operators,
+            statement_starts,
+            method,
+            children,
+            budget,
+            &offset_after,
+        );
+        if !spans.is_empty() {
+            return spans;
+        }
+        budget /= 2;
+    }
+    vec![]
+}
+
+/// Find regions of at most `budget` bytes of WASM (see `find_split_regions`)
+fn find_regions_with_budget(
+    operators: &[OffsetOperator],
+    statement_starts: &[bool],
+    method: (usize, usize),
+    children: &[SplitSpan],
+    budget: usize,
+    offset_after: &impl Fn(usize) -> usize,
+) -> Vec<SplitSpan> {
+    // Statements can only be split at operators which weren't inlined
+    let is_cut = |idx: usize| {
+        idx < operators.len()
+            && statement_starts[idx]
+            && (idx == 0 || operators[idx - 1].1 < operators[idx].1)
+    };
+    let operator_size = |idx: usize| offset_after(idx) - operators[idx].1;
+
+    let mut spans = vec![];
+    let mut children = children.iter().peekable();
+    let mut levels = vec![Level {
+        first: method.0,
+        blockty: None,
+        splittable: false,
+        size: 0,
+        items: vec![],
+    }];
+
+    let mut idx = method.0;
+    while idx <= method.1 {
+        let cut = is_cut(idx);
+        let depth = levels.len();
+        let level = levels.last_mut().unwrap();
+        if cut {
+            level.end_item(idx, true);
+        }
+
+        // Regions already split out are just calls
+        if let Some(child) = children.next_if(|child| child.first == idx) {
+            let item = level.current_item(idx, cut);
+            item.size += SPLIT_CALL_SIZE;
+            item.last = child.last;
+            idx = child.last + 1;
+            continue;
+        }
+
+        match &operators[idx].0 {
+            Operator::Block { blockty } | Operator::Loop { blockty } => {
+                level.current_item(idx, cut);
+                levels.push(Level {
+                    first: idx,
+                    blockty: Some(*blockty),
+                    splittable: true,
+                    size: operator_size(idx),
+                    items: vec![],
+                });
+            }
+            Operator::If { .. } | Operator::Try { .. } => {
+                level.current_item(idx, cut);
+                levels.push(Level {
+                    first: idx,
+                    blockty: None,
+                    splittable: false,
+                    size: operator_size(idx),
+                    items: vec![],
+                });
+            }
+            Operator::Else | Operator::Catch { .. } | Operator::CatchAll => {
+                level.push_barrier(idx, operator_size(idx));
+            }
+            Operator::End | Operator::Delegate { .. } if depth > 1 => {
+                let mut level = levels.pop().unwrap();
+                level.end_item(idx, cut);
+                level.size += operator_size(idx);
+                let inlined = operators[level.first].1 == operators[idx].1;
+                let (size, nested_frames) =
+                    cut_frame(&mut level.items, level.size, budget, method, &mut spans);
+
+                let parent = levels.last_mut().unwrap();
+                let item = parent.items.last_mut().unwrap();
+                item.size += size;
+                item.last = idx;
+                match level.blockty {
+                    Some(blockty)
+                        if level.splittable
+                            && !inlined
+                            && size <= budget
+                            && (level.first, idx) != method =>
+                    {
+                        let span = SplitSpan {
+                            first: level.first,
+                            last: idx,
+                            blockty: Some(blockty),
+                        };
+                        item.frames.push((span, size));
+                    }
+                    _ => item.frames.extend(nested_frames),
+                }
+
+                // Frames between the `delegate` and its target can't be split out
+                if let Operator::Delegate { relative_depth } = operators[idx].0 {
+                    let target = levels.len().saturating_sub(relative_depth as usize + 1);
+                    pin_levels(&mut levels[target..], idx, true);
+                }
+            }
+            Operator::Rethrow { relative_depth } => {
+                let target = depth.saturating_sub(*relative_depth as usize + 1);
+                level.current_item(idx, cut).size += operator_size(idx);
+                pin_levels(&mut levels[target..], idx, false);
+            }
+            Operator::ReturnCall { .. } | Operator::ReturnCallIndirect { .. } => {
+                level.current_item(idx, cut).size += operator_size(idx);
+                pin_levels(&mut levels, idx, true);
+            }
+            _ => level.current_item(idx, cut).size += operator_size(idx),
+        }
+        if let Some(item) = levels.last_mut().unwrap().items.last_mut() {
+            if !item.ends_statement {
+                item.last = idx;
+            }
+        }
+        idx += 1;
+    }
+
+    // What is left of the method itself
+    let mut root = levels.pop().unwrap();
+    root.end_item(method.1 + 1, is_cut(method.1 + 1));
+    cut_frame(&mut root.items, root.size, budget, method, &mut spans);
+
+    spans.sort_by_key(|span| (span.first, Reverse(span.last)));
+    spans
+}
+
+/// Mark the current items of frames as pinned (see `Item::pinned`), and the frames as not
+/// splittable (all of them, or all but the first)
+fn pin_levels(levels: &mut [Level], idx: usize, including_first: bool) {
+    for (level_idx, level) in levels.iter_mut().enumerate() {
+        level.current_item(idx, false).pinned = true;
+        if including_first || level_idx > 0 {
+            level.splittable = false;
+        }
+    }
+}
+
+/// Once all the items of a frame are known, split out runs of them if the frame is too big
+///
+/// Returns the size of the frame once the runs are split out, along with the frames nested in it
+/// which could still be split out on their own (if no runs were).
+fn cut_frame(
+    items: &mut [Item],
+    own_size: usize,
+    budget: usize,
+    method: (usize, usize),
+    spans: &mut Vec<SplitSpan>,
+) -> (usize, Vec<(SplitSpan, usize)>) {
+    let total = own_size + items.iter().map(|item| item.size).sum::<usize>();
+    if total <= budget {
+        let nested_frames = items
+            .iter_mut()
+            .flat_map(|item| std::mem::take(&mut item.frames))
+            .collect();
+        return (total, nested_frames);
+    }
+
+    let min_size = budget / 8;
+    let mut size = own_size;
+    let mut run: Option<Run> = None;
+    for item_idx in 0..items.len() {
+        let item = &items[item_idx];
+        if item.pinned || item.size > budget {
+            if let Some(run) = run.take() {
+                size += run.finish(items, min_size, method, spans);
+            }
+            size += split_nested_frames(&items[item_idx], min_size, spans);
+            continue;
+        }
+        if let Some(current) = &run {
+            if current.size + item.size > budget {
+                size += run.take().unwrap().finish(items, min_size, method, spans);
+            }
+        }
+        match &mut run {
+            Some(run) => run.push(item_idx, item),
+            None if item.starts_statement => {
+                let mut new_run = Run {
+                    first: item_idx,
+                    last_ending: None,
+                    size: 0,
+                    items: 0,
+                };
+                new_run.push(item_idx, item);
+                run = Some(new_run);
+            }
+            None => size += item.size,
+        }
+    }
+    if let Some(run) = run {
+        size += run.finish(items, min_size, method, spans);
+    }
+    (size, vec![])
+}
+
+/// Consecutive items which might get split out together
+struct Run {
+    /// Index of the first item
+    first: usize,
+
+    /// Index of the last item after which the run can end, with the size of the run up to there
+    last_ending: Option<(usize, usize)>,
+
+    /// Size of all the items
+    size: usize,
+
+    /// Number of items
+    items: usize,
+}
+
+impl Run {
+    fn push(&mut self, item_idx: usize, item: &Item) {
+        self.size += item.size;
+        self.items += 1;
+        if item.ends_statement {
+            self.last_ending = Some((item_idx, self.size));
+        }
+    }
+
+    /// Split out the run if it is big enough, returning the size of what is left of it
+    fn finish(
+        self,
+        items: &[Item],
+        min_size: usize,
+        method: (usize, usize),
+        spans: &mut Vec<SplitSpan>,
+    ) -> usize {
+        let (last, size) = match self.last_ending {
+            Some(last_ending) => last_ending,
+            None => return self.size,
+        };
+        let first = items[self.first].first;
+        let last_op = items[last].last;
+        if size < min_size || (first, last_op) == method {
+            return self.size;
+        }
+        spans.push(SplitSpan {
+            first,
+            last: last_op,
+            blockty: None,
+        });
+        self.size - size + SPLIT_CALL_SIZE
+    }
+}
+
+/// Split out the frames nested in an item which can't be part of a run, returning the size of
+/// what is left of the item
+fn split_nested_frames(item: &Item, min_size: usize, spans: &mut Vec<SplitSpan>) -> usize {
+    let mut size = item.size;
+    for (span, frame_size) in &item.frames {
+        if *frame_size >= min_size {
+            spans.push(*span);
+            size = size - frame_size + SPLIT_CALL_SIZE;
+        }
+    }
+    size
+}
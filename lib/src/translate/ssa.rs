@@ -0,0 +1,1073 @@
+//! SSA form of function bodies
+//!
+//! Function bodies normally get translated straight from their operators into JVM bytecode, with
+//! at most one operator of lookahead. The SSA form is an intermediate representation for when more
+//! of the function needs to be seen at once:
+//!
+//!   * values are defined exactly once (by an instruction or as a block parameter), and WASM
+//!     locals and the operand stack disappear entirely
+//!   * instead of phi nodes, blocks have parameters, which the jumps to the block supply
+//!   * instructions are WASM operators taking their operands from values instead of the stack
+//!   * every block ends with a terminator (jump, conditional branch, switch, return, or trap)
+//!
+//! The SSA form is built from the (possibly optimized) operators of a function, using the
+//! validator to find the types of values. Construction is the simple non-minimal kind: blocks which
+//! can be entered from more than one place get a parameter for every WASM local and for every
+//! stack value entering the block, then parameters which always receive the same value get
+//! replaced by that value until there are none left (for reducible control flow, which is all that
+//! WASM can express, this leaves minimal SSA).
+//!
+//! Only part of WASM is supported for now: values must be numeric (no `v128` or references) and
+//! the body can't use exceptions, tail calls, tables, atomics, or SIMD. `SsaFunction::build`
+//! returns `None` on anything else, and the function then gets translated directly. Lowering back
+//! into JVM bytecode is done by `FunctionTranslator::translate_ssa`.
+
+use super::{read_locals, Error, OffsetOperator};
+use crate::wasm::{FunctionType, StackType, WasmModuleResourcesExt};
+use std::collections::HashMap;
+use wasmparser::{BlockType, FuncValidator, FunctionBody, Operator, ValType, ValidatorResources};
+
+/// Value in the SSA form (an index into `SsaFunction::values`)
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SsaValue(pub u32);
+
+/// Basic block in the SSA form (an index into `SsaFunction::blocks`)
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SsaBlockId(pub u32);
+
+/// Function in SSA form
+#[derive(Debug)]
+pub struct SsaFunction<'a> {
+    /// Types of all values
+    ///
+    /// Values which were defined while building the function but then optimized away stay in
+    /// here, even though nothing defines or uses them anymore.
+    pub values: Vec<StackType>,
+
+    /// Basic blocks, in the order they should be laid out
+    ///
+    /// The first block is the entry block: its parameters are the function inputs and nothing
+    /// jumps to it. Every other block comes after at least one of the blocks jumping to it.
+    pub blocks: Vec<SsaBlock<'a>>,
+}
+
+/// Basic block
+#[derive(Debug)]
+pub struct SsaBlock<'a> {
+    /// Parameters of the block (one for every argument in the jumps to this block)
+    pub params: Vec<SsaValue>,
+
+    /// Straight-line instructions of the block
+    pub instructions: Vec<SsaInstruction<'a>>,
+
+    /// How control leaves the block
+    pub terminator: SsaTerminator,
+}
+
+/// Instruction in a basic block
+#[derive(Debug)]
+pub struct SsaInstruction<'a> {
+    /// What the instruction does
+    pub operation: SsaOperation<'a>,
+
+    /// Offset of the operator from which the instruction originated
+    pub offset: usize,
+
+    /// Values consumed (in the order they would have been pushed on the WASM stack)
+    pub operands: Vec<SsaValue>,
+
+    /// Values defined (in the order they would have been pushed on the WASM stack)
+    pub results: Vec<SsaValue>,
+}
+
+/// Operation performed by an instruction
+#[derive(Debug)]
+pub enum SsaOperation<'a> {
+    /// Zero value of a type (the initial value of WASM locals)
+    Zero(StackType),
+
+    /// WASM operator, which is never a control, local, or `drop` operator and is only ever a
+    /// `select` in its typed form
+    Operator(Operator<'a>),
+}
+
+/// Jump to a block, along with the arguments for the block parameters
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SsaBlockCall {
+    pub block: SsaBlockId,
+    pub arguments: Vec<SsaValue>,
+}
+
+/// How control leaves a basic block
+#[derive(Debug)]
+pub enum SsaTerminator {
+    /// Unconditional jump
+    Jump(SsaBlockCall),
+
+    /// Jump to the first block if the `i32` condition is non-zero, otherwise to the second
+    Branch {
+        condition: SsaValue,
+        if_true: SsaBlockCall,
+        if_false: SsaBlockCall,
+    },
+
+    /// Jump to the block at the `i32` index in the targets (or to the default block if the index
+    /// is out of bounds)
+    Switch {
+        index: SsaValue,
+        targets: Vec<SsaBlockCall>,
+        default: SsaBlockCall,
+    },
+
+    /// Return from the function
+    Return(Vec<SsaValue>),
+
+    /// Trap
+    Unreachable,
+}
+
+impl SsaTerminator {
+    /// Jumps to other blocks
+    pub fn block_calls(&self) -> Vec<&SsaBlockCall> {
+        match self {
+            SsaTerminator::Jump(call) => vec![call],
+            SsaTerminator::Branch {
+                if_true, if_false, ..
+            } => vec![if_true, if_false],
+            SsaTerminator::Switch {
+                targets, default, ..
+            } => targets.iter().chain(std::iter::once(default)).collect(),
+            SsaTerminator::Return(_) | SsaTerminator::Unreachable => vec![],
+        }
+    }
+
+    /// Jumps to other blocks (mutably)
+    pub fn block_calls_mut(&mut self) -> Vec<&mut SsaBlockCall> {
+        match self {
+            SsaTerminator::Jump(call) => vec![call],
+            SsaTerminator::Branch {
+                if_true, if_false, ..
+            } => vec![if_true, if_false],
+            SsaTerminator::Switch {
+                targets, default, ..
+            } => targets.iter_mut().chain(std::iter::once(default)).collect(),
+            SsaTerminator::Return(_) | SsaTerminator::Unreachable => vec![],
+        }
+    }
+
+    /// Values used by the terminator (including the arguments of the jumps)
+    pub fn uses_mut(&mut self) -> Vec<&mut SsaValue> {
+        let mut uses = vec![];
+        match self {
+            SsaTerminator::Jump(call) => uses.extend(call.arguments.iter_mut()),
+            SsaTerminator::Branch {
+                condition,
+                if_true,
+                if_false,
+            } => {
+                uses.push(condition);
+                uses.extend(if_true.arguments.iter_mut());
+                uses.extend(if_false.arguments.iter_mut());
+            }
+            SsaTerminator::Switch {
+                index,
+                targets,
+                default,
+            } => {
+                uses.push(index);
+                for call in targets.iter_mut().chain(std::iter::once(default)) {
+                    uses.extend(call.arguments.iter_mut());
+                }
+            }
+            SsaTerminator::Return(values) => uses.extend(values.iter_mut()),
+            SsaTerminator::Unreachable => (),
+        }
+        uses
+    }
+}
+
+impl<'a> SsaFunction<'a> {
+    /// Build the SSA form of a function from its operators, or `None` if the function uses
+    /// something which isn't supported
+    ///
+    /// The operators are validated along the way, so the validator should be fresh.
+    pub fn build(
+        function_typ: &FunctionType,
+        function_body: &FunctionBody<'a>,
+        operators: &[OffsetOperator<'a>],
+        inlined_locals: &[ValType],
+        validator: &mut FuncValidator<&ValidatorResources>,
+    ) -> Result<Option<SsaFunction<'a>>, Error> {
+        if !operators.iter().all(|(operator, _)| is_supported(operator)) {
+            return Ok(None);
+        }
+
+        read_locals(function_body, inlined_locals, validator)?;
+        let mut local_types = vec![];
+        for local_idx in 0..validator.len_locals() {
+            match StackType::from_general(validator.get_local_type(local_idx).unwrap())? {
+                ty @ (StackType::I32 | StackType::I64 | StackType::F32 | StackType::F64) => {
+                    local_types.push(ty)
+                }
+                _ => return Ok(None),
+            }
+        }
+
+        let mut builder = SsaBuilder::new(function_typ, local_types);
+        let mut last_offset = 0;
+        for (operator, offset) in operators {
+            if !builder.visit_operator(operator, *offset, validator)? {
+                return Ok(None);
+            }
+            last_offset = *offset;
+        }
+        validator.finish(last_offset + 1)?;
+
+        Ok(Some(builder.finish()))
+    }
+
+    /// Values which are defined in the function, in the order they get defined
+    pub fn defined_values(&self) -> impl Iterator<Item = SsaValue> + '_ {
+        self.blocks.iter().flat_map(|block| {
+            block.params.iter().copied().chain(
+                block
+                    .instructions
+                    .iter()
+                    .flat_map(|instruction| instruction.results.iter().copied()),
+            )
+        })
+    }
+
+    /// Type of a value
+    pub fn value_type(&self, value: SsaValue) -> StackType {
+        self.values[value.0 as usize]
+    }
+}
+
+/// Block while the SSA form is being built
+struct PartialBlock<'a> {
+    params: Vec<SsaValue>,
+    instructions: Vec<SsaInstruction<'a>>,
+
+    /// Only `None` until the block is terminated
+    terminator: Option<SsaTerminator>,
+
+    /// Whether there has been a jump to this block yet
+    jumped_to: bool,
+}
+
+/// WASM control frame while the SSA form is being built
+struct SsaFrame {
+    /// Block to jump to for a branch to the frame, or `None` if that is a return
+    branch_target: Option<SsaBlockId>,
+
+    /// Number of values carried by a branch to the frame
+    branch_arity: usize,
+
+    /// Block following the `end` of the frame, or `None` for the frame of the function itself
+    end: Option<SsaBlockId>,
+
+    /// Number of values left by the frame
+    result_arity: usize,
+
+    /// Height of the operand stack under the inputs of the frame
+    base_height: usize,
+
+    /// For `if` frames (until the `else`), the block for the `else` arm along with the locals and
+    /// inputs at the `if`
+    else_arm: Option<(SsaBlockId, Vec<SsaValue>, Vec<SsaValue>)>,
+}
+
+/// Context for building the SSA form of a function
+struct SsaBuilder<'a> {
+    values: Vec<StackType>,
+    blocks: Vec<PartialBlock<'a>>,
+
+    /// Blocks in the order they were entered (which is the order they get laid out in)
+    layout: Vec<SsaBlockId>,
+
+    /// Block being built, or `None` if the current code is unreachable
+    current: Option<SsaBlockId>,
+
+    /// Number of frames opened since the current code became unreachable
+    unreachable_depth: usize,
+
+    /// Current value of every WASM local
+    locals: Vec<SsaValue>,
+    local_types: Vec<StackType>,
+
+    /// Operand stack
+    stack: Vec<SsaValue>,
+
+    /// Control frames (the outermost one being the function itself)
+    frames: Vec<SsaFrame>,
+
+    /// Function outputs
+    outputs: Vec<StackType>,
+}
+
+impl<'a> SsaBuilder<'a> {
+    fn new(function_typ: &FunctionType, local_types: Vec<StackType>) -> Self {
+        let mut builder = SsaBuilder {
+            values: vec![],
+            blocks: vec![],
+            layout: vec![],
+            current: None,
+            unreachable_depth: 0,
+            locals: vec![],
+            local_types,
+            stack: vec![],
+            frames: vec![SsaFrame {
+                branch_target: None,
+                branch_arity: function_typ.outputs.len(),
+                end: None,
+                result_arity: function_typ.outputs.len(),
+                base_height: 0,
+                else_arm: None,
+            }],
+            outputs: function_typ.outputs.clone(),
+        };
+
+        // The entry block has the inputs as parameters, and zero-initializes the other locals
+        let entry = builder.new_block(&function_typ.inputs);
+        builder.enter(entry);
+        builder.locals = builder.blocks[0].params.clone();
+        for local_idx in function_typ.inputs.len()..builder.local_types.len() {
+            let ty = builder.local_types[local_idx];
+            let value = builder.new_value(ty);
+            builder.push_instruction(SsaOperation::Zero(ty), 0, vec![], vec![value]);
+            builder.locals.push(value);
+        }
+
+        builder
+    }
+
+    fn new_value(&mut self, ty: StackType) -> SsaValue {
+        let value = SsaValue(self.values.len() as u32);
+        self.values.push(ty);
+        value
+    }
+
+    /// Make a new block with parameters of the given types
+    fn new_block(&mut self, param_types: &[StackType]) -> SsaBlockId {
+        let params = param_types.iter().map(|ty| self.new_value(*ty)).collect();
+        let block = SsaBlockId(self.blocks.len() as u32);
+        self.blocks.push(PartialBlock {
+            params,
+            instructions: vec![],
+            terminator: None,
+            jumped_to: false,
+        });
+        block
+    }
+
+    /// Make a new block which can be entered from more than one place, so it has parameters for
+    /// all the locals followed by parameters for the stack values entering it
+    fn new_join_block(&mut self, stack_types: &[StackType]) -> SsaBlockId {
+        let mut param_types = self.local_types.clone();
+        param_types.extend_from_slice(stack_types);
+        self.new_block(&param_types)
+    }
+
+    /// Start building a block
+    fn enter(&mut self, block: SsaBlockId) {
+        self.current = Some(block);
+        self.layout.push(block);
+    }
+
+    /// Start building a block made by `new_join_block`
+    fn enter_join(&mut self, block: SsaBlockId, base_height: usize) {
+        self.enter(block);
+        let params = &self.blocks[block.0 as usize].params;
+        let (locals, stack) = params.split_at(self.local_types.len());
+        self.locals = locals.to_vec();
+        self.stack.truncate(base_height);
+        self.stack.extend_from_slice(stack);
+    }
+
+    /// Jump to a block made by `new_join_block`, with the locals and the top stack values
+    fn join_call(&mut self, block: SsaBlockId, stack_values: usize) -> SsaBlockCall {
+        let mut arguments = self.locals.clone();
+        arguments.extend_from_slice(&self.stack[self.stack.len() - stack_values..]);
+        self.blocks[block.0 as usize].jumped_to = true;
+        SsaBlockCall { block, arguments }
+    }
+
+    /// Jump to a block without parameters
+    fn plain_call(&mut self, block: SsaBlockId) -> SsaBlockCall {
+        self.blocks[block.0 as usize].jumped_to = true;
+        SsaBlockCall {
+            block,
+            arguments: vec![],
+        }
+    }
+
+    /// Jump for a branch to an enclosing frame
+    ///
+    /// Branches to the function frame are returns, so they go through a new block which returns.
+    fn branch_call(&mut self, relative_depth: u32) -> SsaBlockCall {
+        let frame = &self.frames[self.frames.len() - 1 - relative_depth as usize];
+        let arity = frame.branch_arity;
+        match frame.branch_target {
+            Some(target) => self.join_call(target, arity),
+            None => {
+                let returned = self.stack[self.stack.len() - arity..].to_vec();
+                let return_block = self.new_block(&[]);
+                self.layout.push(return_block);
+                self.blocks[return_block.0 as usize].terminator =
+                    Some(SsaTerminator::Return(returned));
+                self.plain_call(return_block)
+            }
+        }
+    }
+
+    /// Terminate the current block, making the code that follows unreachable
+    fn terminate(&mut self, terminator: SsaTerminator) {
+        let block = self.current.take().expect("no current block");
+        self.blocks[block.0 as usize].terminator = Some(terminator);
+    }
+
+    fn push_instruction(
+        &mut self,
+        operation: SsaOperation<'a>,
+        offset: usize,
+        operands: Vec<SsaValue>,
+        results: Vec<SsaValue>,
+    ) {
+        let block = self.current.expect("no current block");
+        self.blocks[block.0 as usize]
+            .instructions
+            .push(SsaInstruction {
+                operation,
+                offset,
+                operands,
+                results,
+            });
+    }
+
+    fn pop(&mut self) -> SsaValue {
+        self.stack.pop().expect("empty operand stack")
+    }
+
+    /// Visit an operator, returning `false` if it isn't supported
+    fn visit_operator(
+        &mut self,
+        operator: &Operator<'a>,
+        offset: usize,
+        validator: &mut FuncValidator<&ValidatorResources>,
+    ) -> Result<bool, Error> {
+        let height_before = validator.operand_stack_height() as usize;
+        validator.op(offset, operator)?;
+
+        // In unreachable code, only the nesting of frames matters
+        if self.current.is_none() {
+            match operator {
+                Operator::Block { .. } | Operator::Loop { .. } | Operator::If { .. } => {
+                    self.unreachable_depth += 1;
+                    return Ok(true);
+                }
+                Operator::End | Operator::Else if self.unreachable_depth == 0 => (),
+                Operator::End => {
+                    self.unreachable_depth -= 1;
+                    return Ok(true);
+                }
+                _ => return Ok(true),
+            }
+        }
+
+        match operator {
+            Operator::Unreachable => self.terminate(SsaTerminator::Unreachable),
+            Operator::Nop => (),
+            Operator::Block { blockty } => {
+                let ty = validator.resources().block_type(*blockty)?;
+                let end = self.new_join_block(&ty.outputs);
+                self.frames.push(SsaFrame {
+                    branch_target: Some(end),
+                    branch_arity: ty.outputs.len(),
+                    end: Some(end),
+                    result_arity: ty.outputs.len(),
+                    base_height: self.stack.len() - ty.inputs.len(),
+                    else_arm: None,
+                });
+            }
+            Operator::Loop { blockty } => {
+                let ty = validator.resources().block_type(*blockty)?;
+                let base_height = self.stack.len() - ty.inputs.len();
+                let header = self.new_join_block(&ty.inputs);
+                let call = self.join_call(header, ty.inputs.len());
+                self.terminate(SsaTerminator::Jump(call));
+                self.enter_join(header, base_height);
+                let end = self.new_join_block(&ty.outputs);
+                self.frames.push(SsaFrame {
+                    branch_target: Some(header),
+                    branch_arity: ty.inputs.len(),
+                    end: Some(end),
+                    result_arity: ty.outputs.len(),
+                    base_height,
+                    else_arm: None,
+                });
+            }
+            Operator::If { blockty } => {
+                let ty = validator.resources().block_type(*blockty)?;
+                let condition = self.pop();
+                let base_height = self.stack.len() - ty.inputs.len();
+                let then_arm = self.new_block(&[]);
+                let else_arm = self.new_block(&[]);
+                let end = self.new_join_block(&ty.outputs);
+                let if_true = self.plain_call(then_arm);
+                let if_false = self.plain_call(else_arm);
+                self.terminate(SsaTerminator::Branch {
+                    condition,
+                    if_true,
+                    if_false,
+                });
+                self.frames.push(SsaFrame {
+                    branch_target: Some(end),
+                    branch_arity: ty.outputs.len(),
+                    end: Some(end),
+                    result_arity: ty.outputs.len(),
+                    base_height,
+                    else_arm: Some((
+                        else_arm,
+                        self.locals.clone(),
+                        self.stack[base_height..].to_vec(),
+                    )),
+                });
+                self.enter(then_arm);
+            }
+            Operator::Else => {
+                let frame = self.frames.last_mut().expect("no frame for else");
+                let (else_arm, locals, inputs) = frame.else_arm.take().expect("else without if");
+                let (end, result_arity, base_height) =
+                    (frame.end.unwrap(), frame.result_arity, frame.base_height);
+                if self.current.is_some() {
+                    let call = self.join_call(end, result_arity);
+                    self.terminate(SsaTerminator::Jump(call));
+                }
+                self.enter(else_arm);
+                self.locals = locals;
+                self.stack.truncate(base_height);
+                self.stack.extend(inputs);
+            }
+            Operator::End => {
+                let frame = self.frames.pop().expect("no frame for end");
+                match frame.end {
+                    None => {
+                        if self.current.is_some() {
+                            let returned = &self.stack[self.stack.len() - frame.result_arity..];
+                            self.terminate(SsaTerminator::Return(returned.to_vec()));
+                        }
+                    }
+                    Some(end) => {
+                        if self.current.is_some() {
+                            let call = self.join_call(end, frame.result_arity);
+                            self.terminate(SsaTerminator::Jump(call));
+                        }
+
+                        // An `if` without `else` has an implicit `else` passing its inputs on
+                        if let Some((else_arm, locals, inputs)) = frame.else_arm {
+                            self.enter(else_arm);
+                            self.locals = locals;
+                            self.stack.truncate(frame.base_height);
+                            self.stack.extend(inputs);
+                            let call = self.join_call(end, frame.result_arity);
+                            self.terminate(SsaTerminator::Jump(call));
+                        }
+
+                        if self.blocks[end.0 as usize].jumped_to {
+                            self.enter_join(end, frame.base_height);
+                        }
+                    }
+                }
+            }
+            Operator::Br { relative_depth } => {
+                let call = self.branch_call(*relative_depth);
+                self.terminate(SsaTerminator::Jump(call));
+            }
+            Operator::BrIf { relative_depth } => {
+                let condition = self.pop();
+                let if_true = self.branch_call(*relative_depth);
+                let fall_through = self.new_block(&[]);
+                let if_false = self.plain_call(fall_through);
+                self.terminate(SsaTerminator::Branch {
+                    condition,
+                    if_true,
+                    if_false,
+                });
+                self.enter(fall_through);
+            }
+            Operator::BrTable { targets } => {
+                let index = self.pop();
+
+                // Branches to the same frame share the same jump (and so the same return block)
+                let mut calls: HashMap<u32, SsaBlockCall> = HashMap::new();
+                let mut table = vec![];
+                for target in targets
+                    .targets()
+                    .chain(std::iter::once(Ok(targets.default())))
+                {
+                    let relative_depth = target?;
+                    let call = match calls.get(&relative_depth) {
+                        Some(call) => call.clone(),
+                        None => {
+                            let call = self.branch_call(relative_depth);
+                            calls.insert(relative_depth, call.clone());
+                            call
+                        }
+                    };
+                    table.push(call);
+                }
+                let default = table.pop().expect("no default target");
+                self.terminate(SsaTerminator::Switch {
+                    index,
+                    targets: table,
+                    default,
+                });
+            }
+            Operator::Return => {
+                let returned = self.stack[self.stack.len() - self.outputs.len()..].to_vec();
+                self.terminate(SsaTerminator::Return(returned));
+            }
+
+            Operator::Drop => {
+                self.pop();
+            }
+            Operator::LocalGet { local_index } => {
+                self.stack.push(self.locals[*local_index as usize])
+            }
+            Operator::LocalSet { local_index } => self.locals[*local_index as usize] = self.pop(),
+            Operator::LocalTee { local_index } => {
+                self.locals[*local_index as usize] = *self.stack.last().expect("empty stack")
+            }
+
+            // `select` always gets an explicit type, so that its lowering needs no type inference
+            Operator::Select | Operator::TypedSelect { .. } => {
+                let ty = match validator.get_operand_type(0) {
+                    Some(Some(ty)) => ty,
+                    _ => return Ok(false),
+                };
+                if !self.visit_simple_operator(
+                    Operator::TypedSelect { ty },
+                    offset,
+                    3,
+                    1,
+                    validator,
+                )? {
+                    return Ok(false);
+                }
+            }
+
+            other => {
+                let result_count = match other {
+                    Operator::Call { function_index } => validator
+                        .resources()
+                        .function_idx_type(*function_index)?
+                        .outputs
+                        .len(),
+                    Operator::CallIndirect { type_index, .. } => validator
+                        .resources()
+                        .block_type(BlockType::FuncType(*type_index))?
+                        .outputs
+                        .len(),
+                    _ if produces_value(other) => 1,
+                    _ => 0,
+                };
+                let height_after = validator.operand_stack_height() as usize;
+                let operand_count = height_before + result_count - height_after;
+                if !self.visit_simple_operator(
+                    other.clone(),
+                    offset,
+                    operand_count,
+                    result_count,
+                    validator,
+                )? {
+                    return Ok(false);
+                }
+            }
+        }
+
+        debug_assert!(
+            self.current.is_none() || self.stack.len() == validator.operand_stack_height() as usize
+        );
+        Ok(true)
+    }
+
+    /// Visit an operator which just turns values on top of the stack into other values, returning
+    /// `false` if any of those values don't have supported types
+    fn visit_simple_operator(
+        &mut self,
+        operator: Operator<'a>,
+        offset: usize,
+        operand_count: usize,
+        result_count: usize,
+        validator: &FuncValidator<&ValidatorResources>,
+    ) -> Result<bool, Error> {
+        let operands = self.stack.split_off(self.stack.len() - operand_count);
+        let mut results = vec![];
+        for depth in (0..result_count).rev() {
+            let ty = match validator.get_operand_type(depth) {
+                Some(Some(ty)) => StackType::from_general(ty)?,
+                _ => return Ok(false),
+            };
+            match ty {
+                StackType::I32 | StackType::I64 | StackType::F32 | StackType::F64 => {
+                    results.push(self.new_value(ty))
+                }
+                _ => return Ok(false),
+            }
+        }
+        self.stack.extend_from_slice(&results);
+        self.push_instruction(SsaOperation::Operator(operator), offset, operands, results);
+        Ok(true)
+    }
+
+    /// Finish building the function
+    fn finish(mut self) -> SsaFunction<'a> {
+        let replacements = self.remove_trivial_params();
+
+        // Replace all uses of removed parameters
+        let resolve = |value: &mut SsaValue| *value = resolve_value(&replacements, *value);
+        for block in &mut self.blocks {
+            for instruction in &mut block.instructions {
+                instruction.operands.iter_mut().for_each(resolve);
+            }
+            if let Some(terminator) = block.terminator.as_mut() {
+                terminator.uses_mut().into_iter().for_each(resolve);
+            }
+        }
+
+        self.remove_unused_zeros();
+
+        // Lay out the blocks in the order they were entered
+        let mut renumbering: HashMap<SsaBlockId, SsaBlockId> = HashMap::new();
+        for (new_idx, block) in self.layout.iter().enumerate() {
+            renumbering.insert(*block, SsaBlockId(new_idx as u32));
+        }
+        let mut old_blocks: Vec<Option<PartialBlock<'a>>> =
+            self.blocks.into_iter().map(Some).collect();
+        let mut blocks = vec![];
+        for block in self.layout {
+            let old_block = old_blocks[block.0 as usize].take().unwrap();
+            let mut terminator = old_block.terminator.expect("unterminated block");
+            for call in terminator.block_calls_mut() {
+                call.block = renumbering[&call.block];
+            }
+            blocks.push(SsaBlock {
+                params: old_block.params,
+                instructions: old_block.instructions,
+                terminator,
+            });
+        }
+
+        SsaFunction {
+            values: self.values,
+            blocks,
+        }
+    }
+
+    /// Remove block parameters which always receive the same value (apart from themselves),
+    /// returning the values by which removed parameters are replaced
+    fn remove_trivial_params(&mut self) -> HashMap<SsaValue, SsaValue> {
+        let mut replacements = HashMap::new();
+        loop {
+            // Arguments received by every block (the entry block doesn't count: nothing jumps to
+            // it, but its parameters are the function inputs)
+            let mut incoming: Vec<Vec<&[SsaValue]>> = vec![vec![]; self.blocks.len()];
+            for block in &self.blocks {
+                if let Some(terminator) = &block.terminator {
+                    for call in terminator.block_calls() {
+                        incoming[call.block.0 as usize].push(&call.arguments);
+                    }
+                }
+            }
+
+            let mut removed: Vec<Vec<bool>> = vec![];
+            let mut changed = false;
+            for (block_idx, block) in self.blocks.iter().enumerate() {
+                let mut block_removed = vec![false; block.params.len()];
+                if block_idx > 0 {
+                    for (param_idx, param) in block.params.iter().enumerate() {
+                        let mut unique = None;
+                        let mut trivial = true;
+                        for arguments in &incoming[block_idx] {
+                            let argument = resolve_value(&replacements, arguments[param_idx]);
+                            if argument == *param || unique == Some(argument) {
+                                continue;
+                            } else if unique.is_none() {
+                                unique = Some(argument);
+                            } else {
+                                trivial = false;
+                                break;
+                            }
+                        }
+                        if let (true, Some(unique)) = (trivial, unique) {
+                            replacements.insert(*param, unique);
+                            block_removed[param_idx] = true;
+                            changed = true;
+                        }
+                    }
+                }
+                removed.push(block_removed);
+            }
+
+            if !changed {
+                return replacements;
+            }
+
+            for (block_idx, block) in self.blocks.iter_mut().enumerate() {
+                retain_unremoved(&mut block.params, &removed[block_idx]);
+                if let Some(terminator) = block.terminator.as_mut() {
+                    for call in terminator.block_calls_mut() {
+                        retain_unremoved(&mut call.arguments, &removed[call.block.0 as usize]);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Remove the zero-initialization of locals which are never read before being set
+    fn remove_unused_zeros(&mut self) {
+        let mut used = vec![false; self.values.len()];
+        for block in &mut self.blocks {
+            for instruction in &block.instructions {
+                for operand in &instruction.operands {
+                    used[operand.0 as usize] = true;
+                }
+            }
+            if let Some(terminator) = block.terminator.as_mut() {
+                for value in terminator.uses_mut() {
+                    used[value.0 as usize] = true;
+                }
+            }
+        }
+        self.blocks[0].instructions.retain(|instruction| {
+            !matches!(instruction.operation, SsaOperation::Zero(_))
+                || instruction
+                    .results
+                    .iter()
+                    .any(|result| used[result.0 as usize])
+        });
+    }
+}
+
+/// Follow the replacements of a value
+fn resolve_value(replacements: &HashMap<SsaValue, SsaValue>, mut value: SsaValue) -> SsaValue {
+    while let Some(replacement) = replacements.get(&value) {
+        value = *replacement;
+    }
+    value
+}
+
+/// Remove the values whose indices are marked as removed
+fn retain_unremoved(values: &mut Vec<SsaValue>, removed: &[bool]) {
+    let mut idx = 0;
+    values.retain(|_| {
+        idx += 1;
+        !removed[idx - 1]
+    });
+}
+
+/// Check if an operator is supported in the SSA form
+fn is_supported(operator: &Operator) -> bool {
+    matches!(
+        operator,
+        Operator::Unreachable
+            | Operator::Nop
+            | Operator::Block { .. }
+            | Operator::Loop { .. }
+            | Operator::If { .. }
+            | Operator::Else
+            | Operator::End
+            | Operator::Br { .. }
+            | Operator::BrIf { .. }
+            | Operator::BrTable { .. }
+            | Operator::Return
+            | Operator::Call { .. }
+            | Operator::CallIndirect { .. }
+            | Operator::Drop
+            | Operator::Select
+            | Operator::TypedSelect { .. }
+            | Operator::LocalGet { .. }
+            | Operator::LocalSet { .. }
+            | Operator::LocalTee { .. }
+            | Operator::GlobalSet { .. }
+            | Operator::I32Store { .. }
+            | Operator::I64Store { .. }
+            | Operator::F32Store { .. }
+            | Operator::F64Store { .. }
+            | Operator::I32Store8 { .. }
+            | Operator::I32Store16 { .. }
+            | Operator::I64Store8 { .. }
+            | Operator::I64Store16 { .. }
+            | Operator::I64Store32 { .. }
+            | Operator::MemoryInit { .. }
+            | Operator::DataDrop { .. }
+            | Operator::MemoryCopy { .. }
+            | Operator::MemoryFill { .. }
+    ) || produces_value(operator)
+}
+
+/// Check if an operator is a supported operator which just produces one value from its operands
+/// (numeric operators, loads, `global.get`, `memory.size`, and `memory.grow`)
+fn produces_value(operator: &Operator) -> bool {
+    matches!(
+        operator,
+        Operator::GlobalGet { .. }
+            | Operator::I32Load { .. }
+            | Operator::I64Load { .. }
+            | Operator::F32Load { .. }
+            | Operator::F64Load { .. }
+            | Operator::I32Load8S { .. }
+            | Operator::I32Load8U { .. }
+            | Operator::I32Load16S { .. }
+            | Operator::I32Load16U { .. }
+            | Operator::I64Load8S { .. }
+            | Operator::I64Load8U { .. }
+            | Operator::I64Load16S { .. }
+            | Operator::I64Load16U { .. }
+            | Operator::I64Load32S { .. }
+            | Operator::I64Load32U { .. }
+            | Operator::MemorySize { .. }
+            | Operator::MemoryGrow { .. }
+            | Operator::I32Const { .. }
+            | Operator::I64Const { .. }
+            | Operator::F32Const { .. }
+            | Operator::F64Const { .. }
+            | Operator::I32Eqz
+            | Operator::I32Eq
+            | Operator::I32Ne
+            | Operator::I32LtS
+            | Operator::I32LtU
+            | Operator::I32GtS
+            | Operator::I32GtU
+            | Operator::I32LeS
+            | Operator::I32LeU
+            | Operator::I32GeS
+            | Operator::I32GeU
+            | Operator::I64Eqz
+            | Operator::I64Eq
+            | Operator::I64Ne
+            | Operator::I64LtS
+            | Operator::I64LtU
+            | Operator::I64GtS
+            | Operator::I64GtU
+            | Operator::I64LeS
+            | Operator::I64LeU
+            | Operator::I64GeS
+            | Operator::I64GeU
+            | Operator::F32Eq
+            | Operator::F32Ne
+            | Operator::F32Lt
+            | Operator::F32Gt
+            | Operator::F32Le
+            | Operator::F32Ge
+            | Operator::F64Eq
+            | Operator::F64Ne
+            | Operator::F64Lt
+            | Operator::F64Gt
+            | Operator::F64Le
+            | Operator::F64Ge
+            | Operator::I32Clz
+            | Operator::I32Ctz
+            | Operator::I32Popcnt
+            | Operator::I32Add
+            | Operator::I32Sub
+            | Operator::I32Mul
+            | Operator::I32DivS
+            | Operator::I32DivU
+            | Operator::I32RemS
+            | Operator::I32RemU
+            | Operator::I32And
+            | Operator::I32Or
+            | Operator::I32Xor
+            | Operator::I32Shl
+            | Operator::I32ShrS
+            | Operator::I32ShrU
+            | Operator::I32Rotl
+            | Operator::I32Rotr
+            | Operator::I64Clz
+            | Operator::I64Ctz
+            | Operator::I64Popcnt
+            | Operator::I64Add
+            | Operator::I64Sub
+            | Operator::I64Mul
+            | Operator::I64DivS
+            | Operator::I64DivU
+            | Operator::I64RemS
+            | Operator::I64RemU
+            | Operator::I64And
+            | Operator::I64Or
+            | Operator::I64Xor
+            | Operator::I64Shl
+            | Operator::I64ShrS
+            | Operator::I64ShrU
+            | Operator::I64Rotl
+            | Operator::I64Rotr
+            | Operator::F32Abs
+            | Operator::F32Neg
+            | Operator::F32Ceil
+            | Operator::F32Floor
+            | Operator::F32Trunc
+            | Operator::F32Nearest
+            | Operator::F32Sqrt
+            | Operator::F32Add
+            | Operator::F32Sub
+            | Operator::F32Mul
+            | Operator::F32Div
+            | Operator::F32Min
+            | Operator::F32Max
+            | Operator::F32Copysign
+            | Operator::F64Abs
+            | Operator::F64Neg
+            | Operator::F64Ceil
+            | Operator::F64Floor
+            | Operator::F64Trunc
+            | Operator::F64Nearest
+            | Operator::F64Sqrt
+            | Operator::F64Add
+            | Operator::F64Sub
+            | Operator::F64Mul
+            | Operator::F64Div
+            | Operator::F64Min
+            | Operator::F64Max
+            | Operator::F64Copysign
+            | Operator::I32WrapI64
+            | Operator::I32TruncF32S
+            | Operator::I32TruncF32U
+            | Operator::I32TruncF64S
+            | Operator::I32TruncF64U
+            | Operator::I64ExtendI32S
+            | Operator::I64ExtendI32U
+            | Operator::I64TruncF32S
+            | Operator::I64TruncF32U
+            | Operator::I64TruncF64S
+            | Operator::I64TruncF64U
+            | Operator::F32ConvertI32S
+            | Operator::F32ConvertI32U
+            | Operator::F32ConvertI64S
+            | Operator::F32ConvertI64U
+            | Operator::F32DemoteF64
+            | Operator::F64ConvertI32S
+            | Operator::F64ConvertI32U
+            | Operator::F64ConvertI64S
+            | Operator::F64ConvertI64U
+            | Operator::F64PromoteF32
+            | Operator::I32ReinterpretF32
+            | Operator::I64ReinterpretF64
+            | Operator::F32ReinterpretI32
+            | Operator::F64ReinterpretI64
+            | Operator::I32Extend8S
+            | Operator::I32Extend16S
+            | Operator::I64Extend8S
+            | Operator::I64Extend16S
+            | Operator::I64Extend32S
+            | Operator::I32TruncSatF32S
+            | Operator::I32TruncSatF32U
+            | Operator::I32TruncSatF64S
+            | Operator::I32TruncSatF64U
+            | Operator::I64TruncSatF32S
+            | Operator::I64TruncSatF32U
+            | Operator::I64TruncSatF64S
+            | Operator::I64TruncSatF64U
+    )
+}
@@ -0,0 +1,59 @@
+use super::{ExportName, ImportName};
+use crate::jvm::class_graph::{AccessMode, ClassId, ConstantData, FieldId, JavaClasses};
+use crate::jvm::code::{CodeBuilder, CodeBuilderExts, Instruction};
+use crate::jvm::{Error, MethodDescriptor};
+use crate::runtime::WasmRuntime;
+use crate::wasm::FunctionType;
+
+/// Exception tag
+///
+/// Every tag gets translated into a final `org.wasm2jar.Tag` field on the main WASM module class.
+/// Tags are compared by identity when catching exceptions, so defined tags are freshly allocated
+/// in the constructor while imported tags are taken as-is from the imports.
+pub struct Tag<'a, 'g> {
+    /// Field in the class which stores the tag
+    pub field: FieldId<'g>,
+
+    /// Tag type (the inputs are the values carried by the exception)
+    pub tag_type: FunctionType,
+
+    /// If the tag is imported, this contains the name under which it is imported.
+    pub import: Option<ImportName<'a>>,
+
+    /// If the tag is exported, this holds the export information
+    pub export: Vec<ExportName<'a>>,
+}
+
+impl<'a, 'g> Tag<'a, 'g> {
+    /// Descriptor whose parameters are the types of the values carried by the exception
+    ///
+    /// This is what gets stored in the `type` field of `org.wasm2jar.Tag`.
+    pub fn payload_descriptor(
+        &self,
+        java: &JavaClasses<'g>,
+        runtime: &WasmRuntime<'g>,
+    ) -> MethodDescriptor<ClassId<'g>> {
+        self.tag_type.method_descriptor(java, &runtime.tuples)
+    }
+
+    /// Load the `org.wasm2jar.Tag` onto the stack
+    ///
+    /// Assumes the top of the stack is the WASM module class
+    pub fn load_tag(&self, code: &mut CodeBuilder<'g>) -> Result<(), Error> {
+        code.access_field(self.field, AccessMode::Read)
+    }
+
+    /// Construct a fresh `org.wasm2jar.Tag` on the stack
+    pub fn new_tag(
+        &self,
+        runtime: &WasmRuntime<'g>,
+        code: &mut CodeBuilder<'g>,
+    ) -> Result<(), Error> {
+        let payload_type = self.payload_descriptor(&code.java.classes, runtime);
+        code.new(runtime.classes.tag)?;
+        code.push_instruction(Instruction::Dup)?;
+        code.push_instruction(Instruction::Ldc(ConstantData::MethodType(payload_type)))?;
+        code.invoke(runtime.members.tag.init)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,129 @@
+//! Trampolined tail calls
+//!
+//! When `TailCallStrategy::Trampoline` is used, a function containing `return_call` or
+//! `return_call_indirect` is translated into its `tailcall_method` instead of its `method`. That
+//! method returns an `Object` which is either the boxed return value or an
+//! `org.wasm2jar.TailCall` wrapping a thunk which makes the tail call. The regular `method` then
+//! just evaluates thunks (using `TailCall.trampoline`) until it gets a return value.
+//!
+//! Functions which are the target of a `return_call` but don't contain any tail calls themselves
+//! get a `tailcall_method` which is just an adapter boxing the return value of `method`.
+//!
+//! Function references to trampolined functions are made out of their `tailcall_method` (see
+//! `Function::load_reference`), so that `return_call_indirect` can find it again at runtime.
+
+use super::{Error, Function};
+use crate::jvm::code::{CodeBuilder, CodeBuilderExts, Instruction};
+use crate::jvm::{FieldType, RefType};
+use crate::runtime::{TupleClasses, WasmRuntime};
+use crate::util::Width;
+use crate::wasm::{v128_ref_type, StackType};
+
+/// Box the return value of a function (which is on the top of the stack) into an `Object`
+///
+/// Functions returning multiple values are already returning a tuple and functions returning
+/// nothing return `null`.
+pub fn box_return_value<'g>(
+    code: &mut CodeBuilder<'g>,
+    outputs: &[StackType],
+) -> Result<(), Error> {
+    match outputs {
+        [] => code.const_null(RefType::Object(code.java.classes.lang.object))?,
+        [StackType::I32] => code.invoke(code.java.members.lang.integer.value_of)?,
+        [StackType::I64] => code.invoke(code.java.members.lang.long.value_of)?,
+        [StackType::F32] => code.invoke(code.java.members.lang.float.value_of)?,
+        [StackType::F64] => code.invoke(code.java.members.lang.double.value_of)?,
+        _ => (), // already references
+    }
+    Ok(())
+}
+
+/// Unbox the `Object` on the top of the stack into the return value of a function
+pub fn unbox_return_value<'g>(
+    code: &mut CodeBuilder<'g>,
+    tuples: &TupleClasses<'g>,
+    outputs: &[StackType],
+) -> Result<(), Error> {
+    match outputs {
+        [] => code.pop()?,
+        [StackType::I32] => {
+            code.checkcast(code.java.classes.lang.integer)?;
+            code.invoke(code.java.members.lang.number.int_value)?;
+        }
+        [StackType::I64] => {
+            code.checkcast(code.java.classes.lang.long)?;
+            code.invoke(code.java.members.lang.number.long_value)?;
+        }
+        [StackType::F32] => {
+            code.checkcast(code.java.classes.lang.float)?;
+            code.invoke(code.java.members.lang.number.float_value)?;
+        }
+        [StackType::F64] => {
+            code.checkcast(code.java.classes.lang.double)?;
+            code.invoke(code.java.members.lang.number.double_value)?;
+        }
+        [StackType::FuncRef] => code.checkcast(code.java.classes.lang.invoke.method_handle)?,
+        [StackType::V128] => code.push_instruction(Instruction::CheckCast(v128_ref_type()))?,
+        [StackType::ExternRef] => (), // already supposed to be `java/lang/Object`
+        _ => code.checkcast(tuples.get(outputs).class)?,
+    }
+    Ok(())
+}
+
+/// Load all of the arguments of a method (including the trailing module argument) onto the stack
+fn load_arguments<'g>(
+    code: &mut CodeBuilder<'g>,
+    function: &Function<'_, 'g>,
+) -> Result<(), Error> {
+    let mut offset = 0;
+    for parameter in &function.method.descriptor.parameters {
+        code.get_local(offset, parameter)?;
+        offset += parameter.width() as u16;
+    }
+    Ok(())
+}
+
+/// Generate the body of `method` for a function which was translated into its `tailcall_method`
+///
+/// Analagous to
+///
+/// ```java
+/// static R func(A₀ arg0, A₁ arg1, .., MyWasmModule module) {
+///   return (R) TailCall.trampoline(func$tail(arg0, arg1, .., module));
+/// }
+/// ```
+pub fn generate_trampoline<'g>(
+    code: &mut CodeBuilder<'g>,
+    runtime: &WasmRuntime<'g>,
+    function: &Function<'_, 'g>,
+) -> Result<(), Error> {
+    let tailcall_method = function
+        .tailcall_method
+        .expect("Trampolined function has no tail call method");
+    load_arguments(code, function)?;
+    code.invoke(tailcall_method)?;
+    code.invoke(runtime.members.tail_call.trampoline)?;
+    unbox_return_value(code, &runtime.tuples, &function.func_type.outputs)?;
+    code.return_(function.method.descriptor.return_type)?;
+    Ok(())
+}
+
+/// Generate the body of `tailcall_method` for a function which doesn't contain any tail calls
+///
+/// Analagous to
+///
+/// ```java
+/// static Object func$tail(A₀ arg0, A₁ arg1, .., MyWasmModule module) {
+///   return (Object) func(arg0, arg1, .., module);
+/// }
+/// ```
+pub fn generate_tailcall_adapter<'g>(
+    code: &mut CodeBuilder<'g>,
+    function: &Function<'_, 'g>,
+) -> Result<(), Error> {
+    load_arguments(code, function)?;
+    code.invoke(function.method)?;
+    box_return_value(code, &function.func_type.outputs)?;
+    code.return_(Some(FieldType::object(code.java.classes.lang.object)))?;
+    Ok(())
+}
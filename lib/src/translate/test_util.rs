@@ -0,0 +1,39 @@
+//! Helpers for testing passes over WASM function bodies written in the text format
+
+use super::{read_operators, OffsetOperator};
+use wasmparser::{FunctionBody, Parser, Payload};
+
+/// Assemble a module in the WASM text format
+pub fn assemble(wat: &str) -> Vec<u8> {
+    let buffer = wast::parser::ParseBuffer::new(wat).expect("Failed to lex module");
+    let mut module: wast::Wat = wast::parser::parse(&buffer).expect("Failed to parse module");
+    module.encode().expect("Failed to encode module")
+}
+
+/// Bodies of the functions defined in a module (in order)
+pub fn function_bodies(wasm: &[u8]) -> Vec<FunctionBody<'_>> {
+    Parser::new(0)
+        .parse_all(wasm)
+        .filter_map(|payload| match payload.expect("Failed to parse module") {
+            Payload::CodeSectionEntry(function_body) => Some(function_body),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Operators of the first function defined in a module
+pub fn first_function_operators(wasm: &[u8]) -> Vec<OffsetOperator<'_>> {
+    let function_body = function_bodies(wasm)
+        .into_iter()
+        .next()
+        .expect("Module has no functions");
+    read_operators(&function_body).expect("Failed to read operators")
+}
+
+/// Render operators (without their offsets), one per line
+pub fn render_operators(operators: &[OffsetOperator]) -> String {
+    operators
+        .iter()
+        .map(|(operator, _)| format!("{:?}\n", operator))
+        .collect()
+}
@@ -1,11 +1,14 @@
-use super::{Error, Memory, MemoryRepr, Settings, Table, TableRepr, UtilitiesStrategy};
+use super::{
+    AtomicOperation, Error, Memory, MemoryBackend, MemoryRepr, Settings, SimdOperation, Table,
+    TableRepr, UtilitiesStrategy,
+};
 use crate::jvm::class_graph::{
     AccessMode, BootstrapMethodData, BootstrapMethodId, ClassData, ClassGraph, ClassId,
     ConstantData, JavaClasses, JavaLibrary, MethodData, MethodId, NestedClassData,
 };
 use crate::jvm::code::{
-    BranchInstruction, CodeBuilder, CodeBuilderExts, CompareMode, Instruction, OrdComparison,
-    ShiftType,
+    BranchInstruction, CodeBuilder, CodeBuilderExts, CompareMode, EqComparison, Instruction,
+    OrdComparison, ShiftType,
 };
 use crate::jvm::model::{Class, Method};
 use crate::jvm::{
@@ -118,6 +121,10 @@ pub enum UtilityMethod {
     /// units of memory pages.
     CopyResizedByteBuffer,
 
+    /// Copy a byte array into a bigger byte array. Return the size of the smaller byte array, in
+    /// units of memory pages.
+    CopyResizedByteArray,
+
     /// Return true if the input is equal to negative one
     IntIsNegativeOne,
 
@@ -127,12 +134,30 @@ pub enum UtilityMethod {
     /// Fill a range of a bytebuffer
     FillByteBufferRange,
 
+    /// Fill a range of a byte array
+    FillByteArrayRange,
+
     /// Convert a number of bytes to a number of memory pages
     BytesToMemoryPages,
 
     /// Convert a number of memory pages into bytes
     MemoryPagesToBytes,
 
+    /// Add a static offset to an address into a 32-bit memory, throwing an
+    /// `IndexOutOfBoundsException` if the unsigned sum is not a valid index
+    EffectiveAddress,
+
+    /// Add a static offset to an address into a 64-bit memory, throwing an
+    /// `IndexOutOfBoundsException` if the unsigned sum overflows
+    EffectiveAddress64,
+
+    /// Check whether a function table slot holds the expected target (the guard of the inline
+    /// caches of `call_indirect`)
+    CallIndirectIsTarget,
+
+    /// Link an inline cache of `call_indirect` to the target being called, then call it
+    CallIndirectMiss,
+
     /// Bootstrap method for internal table utilities
     BootstrapExternalTable,
 
@@ -144,10 +169,19 @@ pub enum UtilityMethod {
 
     /// Bootstrap method for memory utilities
     BootstrapMemory,
+
+    /// Bootstrap method for accessing a `ByteBuffer` through a `VarHandle` view
+    BootstrapVarHandle,
+
+    /// SIMD operation on `v128` values (represented as `long[2]`)
+    Simd(SimdOperation),
+
+    /// Atomic memory operation
+    Atomic(AtomicOperation),
 }
 impl UtilityMethod {
     /// Get the method name
-    pub const fn name(&self) -> UnqualifiedName {
+    pub fn name(&self) -> UnqualifiedName {
         match self {
             UtilityMethod::I32DivS => UnqualifiedName::I32DIVS,
             UtilityMethod::I64DivS => UnqualifiedName::I64DIVS,
@@ -176,20 +210,33 @@ impl UtilityMethod {
             UtilityMethod::NextSize => UnqualifiedName::NEXTSIZE,
             UtilityMethod::CopyResizedArray => UnqualifiedName::COPYRESIZEDARRAY,
             UtilityMethod::CopyResizedByteBuffer => UnqualifiedName::COPYRESIZEDBYTEBUFFER,
+            UtilityMethod::CopyResizedByteArray => UnqualifiedName::COPYRESIZEDBYTEARRAY,
             UtilityMethod::IntIsNegativeOne => UnqualifiedName::INTISNEGATIVEONE,
             UtilityMethod::FillArrayRange => UnqualifiedName::FILLARRAYRANGE,
             UtilityMethod::FillByteBufferRange => UnqualifiedName::FILLBYTEBUFFERRANGE,
+            UtilityMethod::FillByteArrayRange => UnqualifiedName::FILLBYTEARRAYRANGE,
             UtilityMethod::BytesToMemoryPages => UnqualifiedName::BYTESTOPAGES,
             UtilityMethod::MemoryPagesToBytes => UnqualifiedName::PAGESTOBYTES,
+            UtilityMethod::EffectiveAddress => UnqualifiedName::EFFECTIVEADDRESS,
+            UtilityMethod::EffectiveAddress64 => UnqualifiedName::EFFECTIVEADDRESS64,
+            UtilityMethod::CallIndirectIsTarget => UnqualifiedName::CALLINDIRECTISTARGET,
+            UtilityMethod::CallIndirectMiss => UnqualifiedName::CALLINDIRECTMISS,
             UtilityMethod::BootstrapTable => UnqualifiedName::BOOTSTRAPTABLE,
             UtilityMethod::BootstrapExternalTable => UnqualifiedName::BOOTSTRAPEXTERNALTABLE,
             UtilityMethod::BootstrapMemory => UnqualifiedName::BOOTSTRAPMEMORY,
             UtilityMethod::BootstrapExternalMemory => UnqualifiedName::BOOTSTRAPEXTERNALMEMORY,
+            UtilityMethod::BootstrapVarHandle => UnqualifiedName::BOOTSTRAPVARHANDLE,
+            UtilityMethod::Simd(simd) => simd.name(),
+            UtilityMethod::Atomic(atomic) => atomic.name(),
         }
     }
 
-    /// Get the method descriptor
-    pub fn descriptor<'g>(&self, java: &JavaClasses<'g>) -> MethodDescriptor<ClassId<'g>> {
+    /// Get the method descriptor, given the backend of memories
+    pub fn descriptor<'g>(
+        &self,
+        java: &JavaClasses<'g>,
+        memory_backend: MemoryBackend,
+    ) -> MethodDescriptor<ClassId<'g>> {
         match self {
             UtilityMethod::I32DivS => MethodDescriptor {
                 parameters: vec![FieldType::int(), FieldType::int()],
@@ -306,6 +353,13 @@ impl UtilityMethod {
                 ],
                 return_type: Some(FieldType::int()), // old size (in memory pages)
             },
+            UtilityMethod::CopyResizedByteArray => MethodDescriptor {
+                parameters: vec![
+                    FieldType::array(FieldType::byte()), // new bigger byte array
+                    FieldType::array(FieldType::byte()), // old byte array
+                ],
+                return_type: Some(FieldType::int()), // old size (in memory pages)
+            },
             UtilityMethod::IntIsNegativeOne => MethodDescriptor {
                 parameters: vec![FieldType::int()],
                 return_type: Some(FieldType::boolean()),
@@ -328,6 +382,15 @@ impl UtilityMethod {
                 ],
                 return_type: None,
             },
+            UtilityMethod::FillByteArrayRange => MethodDescriptor {
+                parameters: vec![
+                    FieldType::int(), // start index (inclusive)
+                    FieldType::int(), // filler value (as byte)
+                    FieldType::int(), // how many entries to fill
+                    FieldType::array(FieldType::byte()),
+                ],
+                return_type: None,
+            },
             UtilityMethod::BytesToMemoryPages => MethodDescriptor {
                 parameters: vec![FieldType::int()],
                 return_type: Some(FieldType::int()),
@@ -336,6 +399,38 @@ impl UtilityMethod {
                 parameters: vec![FieldType::int()],
                 return_type: Some(FieldType::int()),
             },
+            UtilityMethod::EffectiveAddress => MethodDescriptor {
+                parameters: vec![
+                    FieldType::int(), // address
+                    FieldType::int(), // offset
+                ],
+                return_type: Some(FieldType::int()),
+            },
+            UtilityMethod::EffectiveAddress64 => MethodDescriptor {
+                parameters: vec![
+                    FieldType::long(), // address
+                    FieldType::long(), // offset
+                ],
+                return_type: Some(FieldType::long()),
+            },
+            UtilityMethod::CallIndirectIsTarget => MethodDescriptor {
+                parameters: vec![
+                    FieldType::object(java.lang.invoke.method_handle), // expected target
+                    FieldType::int(),                                  // table index
+                    FieldType::array(FieldType::object(java.lang.invoke.method_handle)),
+                ],
+                return_type: Some(FieldType::boolean()),
+            },
+            UtilityMethod::CallIndirectMiss => MethodDescriptor {
+                parameters: vec![
+                    FieldType::object(java.lang.invoke.mutable_call_site),
+                    FieldType::object(java.lang.invoke.method_handle), // getter on object
+                    FieldType::object(java.lang.invoke.method_handle), // uncached call
+                    FieldType::array(FieldType::int()),                // number of misses
+                    FieldType::array(FieldType::object(java.lang.object)), // arguments
+                ],
+                return_type: Some(FieldType::object(java.lang.object)),
+            },
             UtilityMethod::BootstrapTable => MethodDescriptor {
                 parameters: vec![
                     FieldType::object(java.lang.invoke.method_handles_lookup),
@@ -345,7 +440,7 @@ impl UtilityMethod {
                     FieldType::object(java.lang.invoke.method_handle), // setter on object
                     FieldType::long(),                                 // maximum table size
                 ],
-                return_type: Some(FieldType::object(java.lang.invoke.constant_call_site)),
+                return_type: Some(FieldType::object(java.lang.invoke.call_site)),
             },
             UtilityMethod::BootstrapExternalTable => MethodDescriptor {
                 parameters: vec![
@@ -357,7 +452,7 @@ impl UtilityMethod {
                     FieldType::object(java.lang.invoke.method_handle), // setter on object
                     FieldType::long(),                                 // maximum table size
                 ],
-                return_type: Some(FieldType::object(java.lang.invoke.constant_call_site)),
+                return_type: Some(FieldType::object(java.lang.invoke.call_site)),
             },
             UtilityMethod::BootstrapMemory => MethodDescriptor {
                 parameters: vec![
@@ -384,10 +479,35 @@ impl UtilityMethod {
                 ],
                 return_type: Some(FieldType::object(java.lang.invoke.constant_call_site)),
             },
+            UtilityMethod::BootstrapVarHandle => MethodDescriptor {
+                parameters: vec![
+                    FieldType::object(java.lang.invoke.method_handles_lookup),
+                    FieldType::object(java.lang.string),
+                    FieldType::object(java.lang.invoke.method_type),
+                    FieldType::object(java.lang.class), // view array class
+                ],
+                return_type: Some(FieldType::object(java.lang.invoke.constant_call_site)),
+            },
+            UtilityMethod::Simd(simd) => simd.descriptor(),
+            UtilityMethod::Atomic(atomic) => {
+                atomic.descriptor(memory_backend.bytes_type(java), java.util.map)
+            }
         }
     }
 }
 
+/// Memory backend and utility methods which the memory bootstrap method gets generated with
+#[derive(Copy, Clone)]
+struct MemoryBootstrapMethods<'g> {
+    backend: MemoryBackend,
+    copy_resized_memory: MethodId<'g>,
+    pages_to_bytes: MethodId<'g>,
+    bytes_to_pages: MethodId<'g>,
+    int_is_negative_one: MethodId<'g>,
+    next_size: MethodId<'g>,
+    fill_memory_range: MethodId<'g>,
+}
+
 /// Class that serves a shared carrier of utility methods. In the name of keeping the translation
 /// outputs lean, these features are enumerated so that they can be requested then generated only
 /// on demand.
@@ -402,6 +522,15 @@ enum UtilityClassInner<'g> {
 
         /// Set of the utility methods that have already been generated
         methods: HashMap<UtilityMethod, MethodId<'g>>,
+
+        /// Bootstrap methods for `VarHandle` views, keyed by the view type
+        var_handle_bootstraps: HashMap<BaseType, BootstrapMethodId<'g>>,
+
+        /// Backend of memories (determines what memory utilities operate on)
+        memory_backend: MemoryBackend,
+
+        /// How many targets the inline caches of `call_indirect` hold (`0` for no caching)
+        call_indirect_cache_size: u8,
     },
 }
 
@@ -445,6 +574,9 @@ impl<'g> UtilityClass<'g> {
         Ok(UtilityClass(UtilityClassInner::Internal {
             class: Class::new(utility_id),
             methods: HashMap::new(),
+            var_handle_bootstraps: HashMap::new(),
+            memory_backend: settings.memory_backend,
+            call_indirect_cache_size: settings.call_indirect_cache_size,
         }))
     }
 
@@ -475,6 +607,62 @@ impl<'g> UtilityClass<'g> {
         Ok(())
     }
 
+    /// Get (and create if missing) a bootstrap method for accessing the bytes of memories through
+    /// a little-endian `VarHandle` view of the given type
+    ///
+    /// The `invokedynamic` name should be the `VarHandle.AccessMode` method name and the type
+    /// should be the exact access mode type.
+    pub fn get_var_handle_bootstrap(
+        &mut self,
+        view: BaseType,
+        java: &'g JavaLibrary<'g>,
+        class_graph: &'g ClassGraph<'g>,
+    ) -> Result<BootstrapMethodId<'g>, Error> {
+        match &self.0 {
+            UtilityClassInner::External(_) => todo!(),
+            UtilityClassInner::Internal {
+                var_handle_bootstraps,
+                ..
+            } => {
+                if let Some(bootstrap) = var_handle_bootstraps.get(&view) {
+                    return Ok(*bootstrap);
+                }
+            }
+        }
+
+        let bootstrap_var_handle =
+            self.get_utility_method(UtilityMethod::BootstrapVarHandle, java, class_graph)?;
+        let bootstrap_method = class_graph.add_bootstrap_method(BootstrapMethodData {
+            method: bootstrap_var_handle,
+            arguments: vec![ConstantData::Class(RefType::array(FieldType::Base(view)))],
+        });
+
+        match &mut self.0 {
+            UtilityClassInner::External(_) => todo!(),
+            UtilityClassInner::Internal {
+                var_handle_bootstraps,
+                ..
+            } => {
+                var_handle_bootstraps.insert(view, bootstrap_method);
+            }
+        }
+        Ok(bootstrap_method)
+    }
+
+    /// Utilities for copying memory into a resized memory and for filling a range of memory
+    fn memory_range_utilities(memory_backend: MemoryBackend) -> (UtilityMethod, UtilityMethod) {
+        match memory_backend {
+            MemoryBackend::HeapByteBuffer | MemoryBackend::DirectByteBuffer => (
+                UtilityMethod::CopyResizedByteBuffer,
+                UtilityMethod::FillByteBufferRange,
+            ),
+            MemoryBackend::ByteArray => (
+                UtilityMethod::CopyResizedByteArray,
+                UtilityMethod::FillByteArrayRange,
+            ),
+        }
+    }
+
     /// Add a utility method and return if it was already there
     pub fn get_utility_method(
         &mut self,
@@ -483,14 +671,20 @@ impl<'g> UtilityClass<'g> {
         class_graph: &'g ClassGraph<'g>,
     ) -> Result<MethodId<'g>, Error> {
         // Nothing for external utility classes or if the method is already generated
-        match &mut self.0 {
+        let (memory_backend, call_indirect_cache_size) = match &mut self.0 {
             UtilityClassInner::External(_) => todo!(),
-            UtilityClassInner::Internal { methods, .. } => {
+            UtilityClassInner::Internal {
+                methods,
+                memory_backend,
+                call_indirect_cache_size,
+                ..
+            } => {
                 if let Some(method_data) = methods.get(&method) {
                     return Ok(*method_data);
                 }
+                (*memory_backend, *call_indirect_cache_size)
             }
-        }
+        };
 
         // Dependencies
         match method {
@@ -499,27 +693,42 @@ impl<'g> UtilityClass<'g> {
                 self.get_utility_method(UtilityMethod::CopyResizedArray, java, class_graph)?;
                 self.get_utility_method(UtilityMethod::IntIsNegativeOne, java, class_graph)?;
                 self.get_utility_method(UtilityMethod::FillArrayRange, java, class_graph)?;
+                if call_indirect_cache_size > 0 {
+                    self.get_utility_method(UtilityMethod::CallIndirectMiss, java, class_graph)?;
+                }
+            }
+            UtilityMethod::CallIndirectMiss => {
+                self.get_utility_method(UtilityMethod::CallIndirectIsTarget, java, class_graph)?;
             }
             UtilityMethod::BootstrapExternalTable => {
                 self.get_utility_method(UtilityMethod::BootstrapTable, java, class_graph)?;
             }
             UtilityMethod::BootstrapMemory => {
+                let (copy_resized, fill_range) = Self::memory_range_utilities(memory_backend);
                 self.get_utility_method(UtilityMethod::NextSize, java, class_graph)?;
-                self.get_utility_method(UtilityMethod::CopyResizedByteBuffer, java, class_graph)?;
+                self.get_utility_method(copy_resized, java, class_graph)?;
                 self.get_utility_method(UtilityMethod::IntIsNegativeOne, java, class_graph)?;
-                self.get_utility_method(UtilityMethod::FillByteBufferRange, java, class_graph)?;
+                self.get_utility_method(fill_range, java, class_graph)?;
                 self.get_utility_method(UtilityMethod::BytesToMemoryPages, java, class_graph)?;
                 self.get_utility_method(UtilityMethod::MemoryPagesToBytes, java, class_graph)?;
             }
             UtilityMethod::BootstrapExternalMemory => {
                 self.get_utility_method(UtilityMethod::BootstrapMemory, java, class_graph)?;
             }
+            UtilityMethod::Atomic(atomic) => {
+                self.get_var_handle_bootstrap(atomic.view_type(), java, class_graph)?;
+            }
             _ => (),
         }
 
-        let descriptor = method.descriptor(&java.classes);
-        let (methods, class): (_, &mut Class) = match &mut self.0 {
-            UtilityClassInner::Internal { class, methods } => (methods, class),
+        let descriptor = method.descriptor(&java.classes, memory_backend);
+        let (methods, var_handle_bootstraps, class): (_, _, &mut Class) = match &mut self.0 {
+            UtilityClassInner::Internal {
+                class,
+                methods,
+                var_handle_bootstraps,
+                ..
+            } => (methods, var_handle_bootstraps, class),
             _ => unreachable!("external utility classes should be filtered earlier"),
         };
         let method_id = class_graph.add_method(MethodData {
@@ -560,36 +769,67 @@ impl<'g> UtilityClass<'g> {
             UtilityMethod::CopyResizedByteBuffer => {
                 Self::generate_copy_resized_bytebuffer(&mut code)?
             }
+            UtilityMethod::CopyResizedByteArray => {
+                Self::generate_copy_resized_bytearray(&mut code)?
+            }
             UtilityMethod::IntIsNegativeOne => Self::generate_int_is_negative_one(&mut code)?,
             UtilityMethod::FillArrayRange => Self::generate_fill_array_range(&mut code)?,
             UtilityMethod::FillByteBufferRange => Self::generate_fill_bytebuffer_range(&mut code)?,
+            UtilityMethod::FillByteArrayRange => Self::generate_fill_bytearray_range(&mut code)?,
             UtilityMethod::BytesToMemoryPages => Self::generate_bytes_to_memory_pages(&mut code)?,
             UtilityMethod::MemoryPagesToBytes => Self::generate_memory_pages_to_bytes(&mut code)?,
+            UtilityMethod::EffectiveAddress => Self::generate_effective_address(&mut code)?,
+            UtilityMethod::EffectiveAddress64 => Self::generate_effective_address64(&mut code)?,
 
+            UtilityMethod::CallIndirectIsTarget => {
+                Self::generate_call_indirect_is_target(&mut code)?
+            }
+            UtilityMethod::CallIndirectMiss => Self::generate_call_indirect_miss(
+                &mut code,
+                call_indirect_cache_size,
+                methods[&UtilityMethod::CallIndirectIsTarget],
+            )?,
             UtilityMethod::BootstrapTable => Self::generate_bootstrap_table(
                 &mut code,
                 methods[&UtilityMethod::NextSize],
                 methods[&UtilityMethod::CopyResizedArray],
                 methods[&UtilityMethod::IntIsNegativeOne],
                 methods[&UtilityMethod::FillArrayRange],
+                methods.get(&UtilityMethod::CallIndirectMiss).copied(),
             )?,
             UtilityMethod::BootstrapExternalTable => Self::generate_external_bootstrap_table(
                 &mut code,
                 methods[&UtilityMethod::BootstrapTable],
             )?,
-            UtilityMethod::BootstrapMemory => Self::generate_bootstrap_memory(
-                &mut code,
-                methods[&UtilityMethod::CopyResizedByteBuffer],
-                methods[&UtilityMethod::MemoryPagesToBytes],
-                methods[&UtilityMethod::BytesToMemoryPages],
-                methods[&UtilityMethod::IntIsNegativeOne],
-                methods[&UtilityMethod::NextSize],
-                methods[&UtilityMethod::FillByteBufferRange],
-            )?,
+            UtilityMethod::BootstrapMemory => {
+                let (copy_resized, fill_range) = Self::memory_range_utilities(memory_backend);
+                let memory_methods = MemoryBootstrapMethods {
+                    backend: memory_backend,
+                    copy_resized_memory: methods[&copy_resized],
+                    pages_to_bytes: methods[&UtilityMethod::MemoryPagesToBytes],
+                    bytes_to_pages: methods[&UtilityMethod::BytesToMemoryPages],
+                    int_is_negative_one: methods[&UtilityMethod::IntIsNegativeOne],
+                    next_size: methods[&UtilityMethod::NextSize],
+                    fill_memory_range: methods[&fill_range],
+                };
+                Self::generate_bootstrap_memory(&mut code, memory_methods)?
+            }
             UtilityMethod::BootstrapExternalMemory => Self::generate_external_bootstrap_memory(
                 &mut code,
                 methods[&UtilityMethod::BootstrapMemory],
             )?,
+            UtilityMethod::BootstrapVarHandle => {
+                Self::generate_bootstrap_var_handle(&mut code, memory_backend)?
+            }
+            UtilityMethod::Simd(simd) => simd.generate(&mut code)?,
+            UtilityMethod::Atomic(atomic) => {
+                let bytes_type = memory_backend.bytes_type(&java.classes);
+                atomic.generate(
+                    &mut code,
+                    var_handle_bootstraps[&atomic.view_type()],
+                    bytes_type,
+                )?
+            }
         }
         class.add_method(Method {
             id: method_id,
@@ -1449,6 +1689,39 @@ impl<'g> UtilityClass<'g> {
         Ok(())
     }
 
+    /// Helper method for copying old data into resized new memories
+    ///
+    /// Analagous to
+    ///
+    /// ```java
+    /// static int copyResizedByteArray(byte[] newMemory, byte[] oldMemory) {
+    ///   System.arraycopy(oldMemory, 0, newMemory, 0, oldMemory.length);
+    ///   return oldMemory.length / 65536;
+    /// }
+    /// ```
+    fn generate_copy_resized_bytearray(code: &mut CodeBuilder<'g>) -> Result<(), Error> {
+        let new_memory_argument = 0;
+        let old_memory_argument = 1;
+
+        // System.arraycopy(oldMemory, 0, newMemory, 0, oldMemory.length);
+        code.push_instruction(Instruction::ALoad(old_memory_argument))?;
+        code.push_instruction(Instruction::IConst0)?;
+        code.push_instruction(Instruction::ALoad(new_memory_argument))?;
+        code.push_instruction(Instruction::IConst0)?;
+        code.push_instruction(Instruction::ALoad(old_memory_argument))?;
+        code.push_instruction(Instruction::ArrayLength)?;
+        code.invoke(code.java.members.lang.system.arraycopy)?;
+
+        // return oldMemory.length / 65536;
+        code.push_instruction(Instruction::ALoad(old_memory_argument))?;
+        code.push_instruction(Instruction::ArrayLength)?;
+        code.const_int(16)?;
+        code.push_instruction(Instruction::ISh(ShiftType::ArithmeticRight))?;
+        code.push_branch_instruction(BranchInstruction::IReturn)?;
+
+        Ok(())
+    }
+
     /// Helper method for checking if a value is equal to negative 1
     ///
     /// Analagous to
@@ -1505,20 +1778,24 @@ impl<'g> UtilityClass<'g> {
     ///   if (numToFill < 0) {
     ///     throw new IllegalArgumentException("memory.fill: negative number of bytes");
     ///   }
-    ///   if (Math.addExact(numToFill, from) >= buf.capacity()) {
+    ///   if (from < 0 || Math.addExact(numToFill, from) > buf.limit()) {
     ///     throw new IllegalArgumentException("memory.fill: out-of-bounds fill");
     ///   }
-    ///   buf.position(from);
     ///   byte fillerByte = (byte) filler;
     ///   while (numToFill > 0) {
-    ///     buf.put(fillerByte);
+    ///     buf.put(from, fillerByte);
+    ///     from++;
     ///     numToFill--;
     ///   }
     /// }
     /// ```
+    ///
+    /// The puts are absolute and the bound is the limit (not the capacity) since shared memories
+    /// are filled concurrently and only grow up to their capacity.
     fn generate_fill_bytebuffer_range(code: &mut CodeBuilder<'g>) -> Result<(), Error> {
         let ok_case1 = code.fresh_label();
         let ok_case2 = code.fresh_label();
+        let out_of_bounds = code.fresh_label();
 
         // if (numToFill < 0) {
         code.push_instruction(Instruction::ILoad(2))?;
@@ -1532,15 +1809,18 @@ impl<'g> UtilityClass<'g> {
         code.push_branch_instruction(BranchInstruction::AThrow)?;
         code.place_label(ok_case1)?;
 
-        // if (Math.addExact(numToFill, from) >= buf.capacity()) {
+        // if (from < 0 || Math.addExact(numToFill, from) > buf.limit()) {
+        code.push_instruction(Instruction::ILoad(0))?;
+        code.push_branch_instruction(BranchInstruction::If(OrdComparison::LT, out_of_bounds, ()))?;
         code.push_instruction(Instruction::ILoad(2))?;
         code.push_instruction(Instruction::ILoad(0))?;
         code.invoke(code.java.members.lang.math.add_exact)?;
         code.push_instruction(Instruction::ALoad(3))?;
-        code.invoke(code.java.members.nio.buffer.capacity)?;
+        code.invoke(code.java.members.nio.buffer.limit)?;
         code.push_branch_instruction(BranchInstruction::IfICmp(OrdComparison::LE, ok_case2, ()))?;
 
-        // throw new IllegalArgumentException("memory.fill: negative number of bytes");
+        // throw new IllegalArgumentException("memory.fill: out-of-bounds fill");
+        code.place_label(out_of_bounds)?;
         code.new(code.java.classes.lang.illegal_argument_exception)?;
         code.push_instruction(Instruction::Dup)?;
         code.const_string("memory.fill: out-of-bounds fill")?;
@@ -1548,12 +1828,6 @@ impl<'g> UtilityClass<'g> {
         code.push_branch_instruction(BranchInstruction::AThrow)?;
         code.place_label(ok_case2)?;
 
-        // buf.position(from);
-        code.push_instruction(Instruction::ALoad(3))?;
-        code.push_instruction(Instruction::ILoad(0))?;
-        code.invoke(code.java.members.nio.byte_buffer.position)?;
-        code.push_instruction(Instruction::Pop)?;
-
         // byte fillerByte = (byte) filler;
         code.push_instruction(Instruction::ILoad(1))?;
         code.push_instruction(Instruction::I2B)?;
@@ -1567,12 +1841,16 @@ impl<'g> UtilityClass<'g> {
         code.push_instruction(Instruction::ILoad(2))?;
         code.push_branch_instruction(BranchInstruction::If(OrdComparison::LE, loop_exit, ()))?;
 
-        // buf.put(fillerByte);
+        // buf.put(from, fillerByte);
         code.push_instruction(Instruction::ALoad(3))?;
+        code.push_instruction(Instruction::ILoad(0))?;
         code.push_instruction(Instruction::ILoad(1))?;
-        code.invoke(code.java.members.nio.byte_buffer.put_byte_relative)?;
+        code.invoke(code.java.members.nio.byte_buffer.put_byte)?;
         code.push_instruction(Instruction::Pop)?;
 
+        // from++;
+        code.push_instruction(Instruction::IInc(0, 1))?;
+
         // numToFill--;
         code.push_instruction(Instruction::IInc(2, -1))?;
 
@@ -1583,6 +1861,29 @@ impl<'g> UtilityClass<'g> {
         Ok(())
     }
 
+    /// Helper method for filling a range of bytes in a byte array
+    ///
+    /// Analagous to
+    ///
+    /// ```java
+    /// static void fillByteArrayRange(int from, int filler, int numToFill, byte[] arr) {
+    ///   java.util.Arrays.fill(arr, from, Math.addExact(from, numToFill), (byte) filler);
+    /// }
+    /// ```
+    fn generate_fill_bytearray_range(code: &mut CodeBuilder<'g>) -> Result<(), Error> {
+        code.push_instruction(Instruction::ALoad(3))?;
+        code.push_instruction(Instruction::ILoad(0))?;
+        code.push_instruction(Instruction::ILoad(0))?;
+        code.push_instruction(Instruction::ILoad(2))?;
+        code.invoke(code.java.members.lang.math.add_exact)?;
+        code.push_instruction(Instruction::ILoad(1))?;
+        code.push_instruction(Instruction::I2B)?;
+        code.invoke(code.java.members.util.arrays.fill_bytes)?;
+        code.push_branch_instruction(BranchInstruction::Return)?;
+
+        Ok(())
+    }
+
     /// Helper method for converting a number of bytes into a number of memory pages. This assumes
     /// that the bytes are a multiple of the memory page size.
     ///
@@ -1621,6 +1922,88 @@ impl<'g> UtilityClass<'g> {
         Ok(())
     }
 
+    /// Helper method for adding the static offset of a memory access to an address into a 32-bit
+    /// memory. Both are unsigned, and since memories have at most `Integer.MAX_VALUE` bytes, any
+    /// sum which doesn't fit in a non-negative `int` is out of bounds.
+    ///
+    /// Analagous to
+    ///
+    /// ```java
+    /// static int effectiveAddress(int address, int offset) {
+    ///   if ((address | offset | (address + offset)) < 0) {
+    ///     throw new IndexOutOfBoundsException("out of bounds memory access");
+    ///   }
+    ///   return address + offset;
+    /// }
+    /// ```
+    fn generate_effective_address(code: &mut CodeBuilder<'g>) -> Result<(), Error> {
+        let out_of_bounds = code.fresh_label();
+
+        code.push_instruction(Instruction::ILoad(0))?;
+        code.push_instruction(Instruction::ILoad(1))?;
+        code.push_instruction(Instruction::IOr)?;
+        code.push_instruction(Instruction::ILoad(0))?;
+        code.push_instruction(Instruction::ILoad(1))?;
+        code.push_instruction(Instruction::IAdd)?;
+        code.push_instruction(Instruction::IOr)?;
+        code.push_branch_instruction(BranchInstruction::If(OrdComparison::LT, out_of_bounds, ()))?;
+
+        code.push_instruction(Instruction::ILoad(0))?;
+        code.push_instruction(Instruction::ILoad(1))?;
+        code.push_instruction(Instruction::IAdd)?;
+        code.push_branch_instruction(BranchInstruction::IReturn)?;
+
+        code.place_label(out_of_bounds)?;
+        Self::throw_out_of_bounds(code)?;
+
+        Ok(())
+    }
+
+    /// Helper method for adding the static offset of a memory access to an address into a 64-bit
+    /// memory, checking for unsigned overflow.
+    ///
+    /// Analagous to
+    ///
+    /// ```java
+    /// static long effectiveAddress64(long address, long offset) {
+    ///   if (Long.compareUnsigned(address + offset, address) < 0) {
+    ///     throw new IndexOutOfBoundsException("out of bounds memory access");
+    ///   }
+    ///   return address + offset;
+    /// }
+    /// ```
+    fn generate_effective_address64(code: &mut CodeBuilder<'g>) -> Result<(), Error> {
+        let out_of_bounds = code.fresh_label();
+
+        code.push_instruction(Instruction::LLoad(0))?;
+        code.push_instruction(Instruction::LLoad(2))?;
+        code.push_instruction(Instruction::LAdd)?;
+        code.push_instruction(Instruction::LLoad(0))?;
+        code.invoke(code.java.members.lang.long.compare_unsigned)?;
+        code.push_branch_instruction(BranchInstruction::If(OrdComparison::LT, out_of_bounds, ()))?;
+
+        code.push_instruction(Instruction::LLoad(0))?;
+        code.push_instruction(Instruction::LLoad(2))?;
+        code.push_instruction(Instruction::LAdd)?;
+        code.push_branch_instruction(BranchInstruction::LReturn)?;
+
+        code.place_label(out_of_bounds)?;
+        Self::throw_out_of_bounds(code)?;
+
+        Ok(())
+    }
+
+    /// Throw an `IndexOutOfBoundsException` for an out of bounds memory access
+    fn throw_out_of_bounds(code: &mut CodeBuilder<'g>) -> Result<(), Error> {
+        code.new(code.java.classes.lang.index_out_of_bounds_exception)?;
+        code.push_instruction(Instruction::Dup)?;
+        code.const_string("out of bounds memory access")?;
+        code.invoke(code.java.members.lang.index_out_of_bounds_exception.init)?;
+        code.push_branch_instruction(BranchInstruction::AThrow)?;
+
+        Ok(())
+    }
+
     /// Generate the bootstrap method used for table operators, including indirect calls. Here lie
     /// some dragons. The output is sensible, but the "how" is not obvious.
     fn generate_bootstrap_table(
@@ -1629,6 +2012,7 @@ impl<'g> UtilityClass<'g> {
         copy_resized_array: MethodId<'g>,
         int_is_negative_one: MethodId<'g>,
         fill_array_range: MethodId<'g>,
+        call_indirect_miss: Option<MethodId<'g>>,
     ) -> Result<(), Error> {
         let call_indirect_case = code.fresh_label();
         let table_get_case = code.fresh_label();
@@ -1667,7 +2051,7 @@ impl<'g> UtilityClass<'g> {
         code.const_string("call_indirect")?;
         code.invoke(code.java.members.lang.object.equals)?;
         code.push_branch_instruction(BranchInstruction::If(OrdComparison::EQ, bad_name_case, ()))?;
-        Self::generate_call_indirect_table_case(code)?;
+        Self::generate_call_indirect_table_case(code, call_indirect_miss)?;
 
         // table.get
         code.place_label(table_get_case)?;
@@ -1837,11 +2221,30 @@ impl<'g> UtilityClass<'g> {
     ///   return new ConstantCallSite(handle);
     /// }
     /// ```
-    fn generate_call_indirect_table_case(code: &mut CodeBuilder<'g>) -> Result<(), Error> {
+    ///
+    /// That handle looks up the target and checks its type on every call. With inline caches
+    /// (`callIndirectMiss` is then available), the call site is instead mutable and starts out
+    /// linked to `callIndirectMiss`, which adds a guarded direct call to every target it sees:
+    ///
+    /// ```java
+    ///   MutableCallSite site = new MutableCallSite(handle);
+    ///   site.setTarget(
+    ///     MethodHandles.insertArguments(callIndirectMiss, 0, site, getter, handle, new int[1])
+    ///       .asCollector(Object[].class, paramCount)
+    ///       .asType(type)
+    ///   );
+    ///   return site;
+    /// ```
+    fn generate_call_indirect_table_case(
+        code: &mut CodeBuilder<'g>,
+        call_indirect_miss: Option<MethodId<'g>>,
+    ) -> Result<(), Error> {
         let type_argument = 2;
         let getter_argument = 3;
         let param_count_local = 7;
         let permutation_local = 8;
+        let handle_local = 9;
+        let site_local = 10;
 
         // int paramCount = type.parameterCount();
         // int[] permutation = new int[paramCount];
@@ -1961,11 +2364,345 @@ impl<'g> UtilityClass<'g> {
                 .permute_arguments,
         )?;
 
-        // return new ConstantCallSite(methodhandle);
-        code.new(code.java.classes.lang.invoke.constant_call_site)?;
-        code.push_instruction(Instruction::DupX1)?;
-        code.push_instruction(Instruction::Swap)?;
-        code.invoke(code.java.members.lang.invoke.constant_call_site.init)?;
+        let call_indirect_miss = match call_indirect_miss {
+            Some(call_indirect_miss) => call_indirect_miss,
+            None => {
+                // return new ConstantCallSite(methodhandle);
+                code.new(code.java.classes.lang.invoke.constant_call_site)?;
+                code.push_instruction(Instruction::DupX1)?;
+                code.push_instruction(Instruction::Swap)?;
+                code.invoke(code.java.members.lang.invoke.constant_call_site.init)?;
+                code.push_branch_instruction(BranchInstruction::AReturn)?;
+                return Ok(());
+            }
+        };
+
+        // MutableCallSite site = new MutableCallSite(handle);
+        code.push_instruction(Instruction::AStore(handle_local))?;
+        code.new(code.java.classes.lang.invoke.mutable_call_site)?;
+        code.push_instruction(Instruction::Dup)?;
+        code.push_instruction(Instruction::ALoad(handle_local))?;
+        code.invoke(code.java.members.lang.invoke.mutable_call_site.init)?;
+        code.push_instruction(Instruction::AStore(site_local))?;
+
+        // MethodHandles.insertArguments(callIndirectMiss, 0, site, getter, handle, new int[1])
+        code.push_instruction(Instruction::ALoad(site_local))?;
+        code.const_methodhandle(call_indirect_miss)?;
+        code.push_instruction(Instruction::IConst0)?;
+        code.push_instruction(Instruction::IConst4)?;
+        code.new_ref_array(RefType::Object(code.java.classes.lang.object))?;
+        code.push_instruction(Instruction::Dup)?;
+        code.push_instruction(Instruction::IConst0)?;
+        code.push_instruction(Instruction::ALoad(site_local))?;
+        code.push_instruction(Instruction::AAStore)?;
+        code.push_instruction(Instruction::Dup)?;
+        code.push_instruction(Instruction::IConst1)?;
+        code.push_instruction(Instruction::ALoad(getter_argument))?;
+        code.push_instruction(Instruction::AAStore)?;
+        code.push_instruction(Instruction::Dup)?;
+        code.push_instruction(Instruction::IConst2)?;
+        code.push_instruction(Instruction::ALoad(handle_local))?;
+        code.push_instruction(Instruction::AAStore)?;
+        code.push_instruction(Instruction::Dup)?;
+        code.push_instruction(Instruction::IConst3)?;
+        code.push_instruction(Instruction::IConst1)?;
+        code.push_instruction(Instruction::NewArray(BaseType::Int))?;
+        code.push_instruction(Instruction::AAStore)?;
+        code.invoke(
+            code.java
+                .members
+                .lang
+                .invoke
+                .method_handles
+                .insert_arguments,
+        )?;
+
+        // .asCollector(Object[].class, paramCount).asType(type)
+        code.const_class(FieldType::array(FieldType::object(
+            code.java.classes.lang.object,
+        )))?;
+        code.push_instruction(Instruction::ILoad(param_count_local))?;
+        code.invoke(code.java.members.lang.invoke.method_handle.as_collector)?;
+        code.push_instruction(Instruction::ALoad(type_argument))?;
+        code.invoke(code.java.members.lang.invoke.method_handle.as_type)?;
+
+        // site.setTarget(...); return site;
+        code.invoke(code.java.members.lang.invoke.call_site.set_target)?;
+        code.push_instruction(Instruction::ALoad(site_local))?;
+        code.push_branch_instruction(BranchInstruction::AReturn)?;
+
+        Ok(())
+    }
+
+    /// Guard of the targets cached by `call_indirect` inline caches
+    ///
+    /// Analagous to
+    ///
+    /// ```java
+    /// static boolean callIndirectIsTarget(MethodHandle expected, int index, MethodHandle[] table) {
+    ///   return Integer.compareUnsigned(index, table.length) < 0 && table[index] == expected;
+    /// }
+    /// ```
+    fn generate_call_indirect_is_target(code: &mut CodeBuilder<'g>) -> Result<(), Error> {
+        let not_target = code.fresh_label();
+
+        code.push_instruction(Instruction::ILoad(1))?;
+        code.push_instruction(Instruction::ALoad(2))?;
+        code.push_instruction(Instruction::ArrayLength)?;
+        code.invoke(code.java.members.lang.integer.compare_unsigned)?;
+        code.push_branch_instruction(BranchInstruction::If(OrdComparison::GE, not_target, ()))?;
+        code.push_instruction(Instruction::ALoad(2))?;
+        code.push_instruction(Instruction::ILoad(1))?;
+        code.push_instruction(Instruction::AALoad)?;
+        code.push_instruction(Instruction::ALoad(0))?;
+        code.push_branch_instruction(BranchInstruction::IfACmp(EqComparison::NE, not_target, ()))?;
+        code.push_instruction(Instruction::IConst1)?;
+        code.push_branch_instruction(BranchInstruction::IReturn)?;
+
+        code.place_label(not_target)?;
+        code.push_instruction(Instruction::IConst0)?;
+        code.push_branch_instruction(BranchInstruction::IReturn)?;
+
+        Ok(())
+    }
+
+    /// Fallback of `call_indirect` inline caches, called with all of the arguments to the call
+    /// site (including the table index and module) when none of the cached targets match
+    ///
+    /// The target being called gets cached in front of the existing ones, unless there have
+    /// already been too many misses. Then the call site becomes megamorphic and is just linked to
+    /// the uncached call. Targets which would trap (out of bounds, `null`, or of the wrong type)
+    /// are never cached, but instead left for the uncached call to trap on.
+    ///
+    /// Analagous to
+    ///
+    /// ```java
+    /// static Object callIndirectMiss(
+    ///   MutableCallSite site,
+    ///   MethodHandle getter,                            // (LMyWasmModule;)[LMethodHandle;
+    ///   MethodHandle uncached,                          // (A₀A₁..ILMyWasmModule;)R
+    ///   int[] misses,
+    ///   Object[] arguments
+    /// ) throws Throwable {
+    ///   int argumentCount = arguments.length;
+    ///   MethodType type = site.type();
+    ///   MethodType targetType = type.dropParameterTypes(argumentCount - 2, argumentCount);
+    ///   int index = ((Number) arguments[argumentCount - 2]).intValue();
+    ///   MethodHandle[] table = (MethodHandle[]) getter.invokeWithArguments(
+    ///     new Object[] { arguments[argumentCount - 1] }
+    ///   );
+    ///
+    ///   MethodHandle target;
+    ///   if (Integer.compareUnsigned(index, table.length) < 0
+    ///       && (target = table[index]) != null
+    ///       && target.type().equals(targetType)) {
+    ///     if (misses[0] < CACHE_SIZE) {
+    ///       misses[0]++;
+    ///       site.setTarget(
+    ///         MethodHandles.guardWithTest(
+    ///           MethodHandles.dropArguments(             // (A₀A₁..ILMyWasmModule;)Z
+    ///             MethodHandles.collectArguments(        // (ILMyWasmModule;)Z
+    ///               MethodHandles.insertArguments(callIndirectIsTarget, 0, target),
+    ///               1,
+    ///               getter
+    ///             ),
+    ///             0,
+    ///             targetType.parameterArray()
+    ///           ),
+    ///           MethodHandles.dropArguments(             // (A₀A₁..ILMyWasmModule;)R
+    ///             target,
+    ///             argumentCount - 2,
+    ///             type.dropParameterTypes(0, argumentCount - 2).parameterArray()
+    ///           ),
+    ///           site.getTarget()
+    ///         )
+    ///       );
+    ///     } else {
+    ///       site.setTarget(uncached);
+    ///     }
+    ///   }
+    ///
+    ///   return uncached.invokeWithArguments(arguments);
+    /// }
+    /// ```
+    fn generate_call_indirect_miss(
+        code: &mut CodeBuilder<'g>,
+        cache_size: u8,
+        call_indirect_is_target: MethodId<'g>,
+    ) -> Result<(), Error> {
+        let site_argument = 0;
+        let getter_argument = 1;
+        let uncached_argument = 2;
+        let misses_argument = 3;
+        let arguments_argument = 4;
+        let argument_count_local = 5;
+        let type_local = 6;
+        let target_type_local = 7;
+        let index_local = 8;
+        let table_local = 9;
+        let target_local = 10;
+
+        let megamorphic = code.fresh_label();
+        let target_done = code.fresh_label();
+        let call_uncached = code.fresh_label();
+        let method_handles = &code.java.members.lang.invoke.method_handles;
+        let method_type = &code.java.members.lang.invoke.method_type;
+
+        // int argumentCount = arguments.length;
+        code.push_instruction(Instruction::ALoad(arguments_argument))?;
+        code.push_instruction(Instruction::ArrayLength)?;
+        code.push_instruction(Instruction::IStore(argument_count_local))?;
+
+        // MethodType type = site.type();
+        code.push_instruction(Instruction::ALoad(site_argument))?;
+        code.invoke(code.java.members.lang.invoke.call_site.r#type)?;
+        code.push_instruction(Instruction::AStore(type_local))?;
+
+        // MethodType targetType = type.dropParameterTypes(argumentCount - 2, argumentCount);
+        code.push_instruction(Instruction::ALoad(type_local))?;
+        code.push_instruction(Instruction::ILoad(argument_count_local))?;
+        code.push_instruction(Instruction::IConst2)?;
+        code.push_instruction(Instruction::ISub)?;
+        code.push_instruction(Instruction::ILoad(argument_count_local))?;
+        code.invoke(method_type.drop_parameter_types)?;
+        code.push_instruction(Instruction::AStore(target_type_local))?;
+
+        // int index = ((Number) arguments[argumentCount - 2]).intValue();
+        code.push_instruction(Instruction::ALoad(arguments_argument))?;
+        code.push_instruction(Instruction::ILoad(argument_count_local))?;
+        code.push_instruction(Instruction::IConst2)?;
+        code.push_instruction(Instruction::ISub)?;
+        code.push_instruction(Instruction::AALoad)?;
+        code.checkcast(code.java.classes.lang.number)?;
+        code.invoke(code.java.members.lang.number.int_value)?;
+        code.push_instruction(Instruction::IStore(index_local))?;
+
+        // MethodHandle[] table = (MethodHandle[]) getter.invokeWithArguments(...);
+        code.push_instruction(Instruction::ALoad(getter_argument))?;
+        code.push_instruction(Instruction::IConst1)?;
+        code.new_ref_array(RefType::Object(code.java.classes.lang.object))?;
+        code.push_instruction(Instruction::Dup)?;
+        code.push_instruction(Instruction::IConst0)?;
+        code.push_instruction(Instruction::ALoad(arguments_argument))?;
+        code.push_instruction(Instruction::ILoad(argument_count_local))?;
+        code.push_instruction(Instruction::IConst1)?;
+        code.push_instruction(Instruction::ISub)?;
+        code.push_instruction(Instruction::AALoad)?;
+        code.push_instruction(Instruction::AAStore)?;
+        code.invoke(
+            code.java
+                .members
+                .lang
+                .invoke
+                .method_handle
+                .invoke_with_arguments,
+        )?;
+        code.push_instruction(Instruction::CheckCast(RefType::array(FieldType::object(
+            code.java.classes.lang.invoke.method_handle,
+        ))))?;
+        code.push_instruction(Instruction::AStore(table_local))?;
+
+        // Only cache targets which won't trap
+        code.push_instruction(Instruction::ILoad(index_local))?;
+        code.push_instruction(Instruction::ALoad(table_local))?;
+        code.push_instruction(Instruction::ArrayLength)?;
+        code.invoke(code.java.members.lang.integer.compare_unsigned)?;
+        code.push_branch_instruction(BranchInstruction::If(OrdComparison::GE, call_uncached, ()))?;
+        code.push_instruction(Instruction::ALoad(table_local))?;
+        code.push_instruction(Instruction::ILoad(index_local))?;
+        code.push_instruction(Instruction::AALoad)?;
+        code.push_instruction(Instruction::Dup)?;
+        code.push_instruction(Instruction::AStore(target_local))?;
+        code.push_branch_instruction(BranchInstruction::IfNull(EqComparison::EQ, target_done, ()))?;
+        code.push_instruction(Instruction::ALoad(target_local))?;
+        code.invoke(code.java.members.lang.invoke.method_handle.r#type)?;
+        code.push_instruction(Instruction::ALoad(target_type_local))?;
+        code.invoke(code.java.members.lang.object.equals)?;
+        code.push_branch_instruction(BranchInstruction::If(OrdComparison::EQ, target_done, ()))?;
+
+        // if (misses[0] < CACHE_SIZE) { misses[0]++; ... }
+        code.push_instruction(Instruction::ALoad(misses_argument))?;
+        code.push_instruction(Instruction::IConst0)?;
+        code.push_instruction(Instruction::IALoad)?;
+        code.const_int(cache_size as i32)?;
+        code.push_branch_instruction(BranchInstruction::IfICmp(
+            OrdComparison::GE,
+            megamorphic,
+            (),
+        ))?;
+        code.push_instruction(Instruction::ALoad(misses_argument))?;
+        code.push_instruction(Instruction::IConst0)?;
+        code.push_instruction(Instruction::Dup2)?;
+        code.push_instruction(Instruction::IALoad)?;
+        code.push_instruction(Instruction::IConst1)?;
+        code.push_instruction(Instruction::IAdd)?;
+        code.push_instruction(Instruction::IAStore)?;
+
+        // Guard checking that the table slot still holds the target
+        code.push_instruction(Instruction::ALoad(site_argument))?;
+        code.const_methodhandle(call_indirect_is_target)?;
+        code.push_instruction(Instruction::IConst0)?;
+        code.push_instruction(Instruction::IConst1)?;
+        code.new_ref_array(RefType::Object(code.java.classes.lang.object))?;
+        code.push_instruction(Instruction::Dup)?;
+        code.push_instruction(Instruction::IConst0)?;
+        code.push_instruction(Instruction::ALoad(target_local))?;
+        code.push_instruction(Instruction::AAStore)?;
+        code.invoke(method_handles.insert_arguments)?;
+        code.push_instruction(Instruction::IConst1)?;
+        code.push_instruction(Instruction::ALoad(getter_argument))?;
+        code.invoke(method_handles.collect_arguments)?;
+        code.push_instruction(Instruction::IConst0)?;
+        code.push_instruction(Instruction::ALoad(target_type_local))?;
+        code.invoke(method_type.parameter_array)?;
+        code.invoke(method_handles.drop_arguments)?;
+
+        // Direct call to the target, ignoring the table index and module
+        code.push_instruction(Instruction::ALoad(target_local))?;
+        code.push_instruction(Instruction::ILoad(argument_count_local))?;
+        code.push_instruction(Instruction::IConst2)?;
+        code.push_instruction(Instruction::ISub)?;
+        code.push_instruction(Instruction::ALoad(type_local))?;
+        code.push_instruction(Instruction::IConst0)?;
+        code.push_instruction(Instruction::ILoad(argument_count_local))?;
+        code.push_instruction(Instruction::IConst2)?;
+        code.push_instruction(Instruction::ISub)?;
+        code.invoke(method_type.drop_parameter_types)?;
+        code.invoke(method_type.parameter_array)?;
+        code.invoke(method_handles.drop_arguments)?;
+
+        // Previously cached targets
+        code.push_instruction(Instruction::ALoad(site_argument))?;
+        code.invoke(code.java.members.lang.invoke.call_site.get_target)?;
+
+        code.invoke(method_handles.guard_with_test)?;
+        code.invoke(code.java.members.lang.invoke.call_site.set_target)?;
+        code.push_branch_instruction(BranchInstruction::Goto(target_done))?;
+
+        // site.setTarget(uncached);
+        code.place_label(megamorphic)?;
+        code.push_instruction(Instruction::ALoad(site_argument))?;
+        code.push_instruction(Instruction::ALoad(uncached_argument))?;
+        code.invoke(code.java.members.lang.invoke.call_site.set_target)?;
+
+        // Explicit jump, so `target_done` doesn't share an offset (and a conflicting frame) with
+        // `call_uncached` when the peephole pass doesn't merge the two blocks
+        code.place_label(target_done)?;
+        code.kill_top_local(target_local, None)?;
+        code.push_branch_instruction(BranchInstruction::Goto(call_uncached))?;
+
+        // return uncached.invokeWithArguments(arguments);
+        code.place_label(call_uncached)?;
+        code.push_instruction(Instruction::ALoad(uncached_argument))?;
+        code.push_instruction(Instruction::ALoad(arguments_argument))?;
+        code.invoke(
+            code.java
+                .members
+                .lang
+                .invoke
+                .method_handle
+                .invoke_with_arguments,
+        )?;
         code.push_branch_instruction(BranchInstruction::AReturn)?;
 
         Ok(())
@@ -2555,12 +3292,7 @@ impl<'g> UtilityClass<'g> {
     /// Generate the bootstrap method used for memory operators
     fn generate_bootstrap_memory(
         code: &mut CodeBuilder<'g>,
-        copy_resized_byte_buffer: MethodId<'g>,
-        pages_to_bytes: MethodId<'g>,
-        bytes_to_pages: MethodId<'g>,
-        int_is_negative_one: MethodId<'g>,
-        next_size: MethodId<'g>,
-        fill_byte_buffer_range: MethodId<'g>,
+        memory_methods: MemoryBootstrapMethods<'g>,
     ) -> Result<(), Error> {
         let memory_size_case = code.fresh_label();
         let memory_grow_case = code.fresh_label();
@@ -2589,7 +3321,11 @@ impl<'g> UtilityClass<'g> {
         code.const_string("memory_size")?;
         code.invoke(code.java.members.lang.object.equals)?;
         code.push_branch_instruction(BranchInstruction::If(OrdComparison::EQ, bad_name_case, ()))?;
-        Self::generate_size_memory_case(code, bytes_to_pages)?;
+        Self::generate_size_memory_case(
+            code,
+            memory_methods.backend,
+            memory_methods.bytes_to_pages,
+        )?;
 
         // memory.grow
         code.place_label(memory_grow_case)?;
@@ -2597,14 +3333,7 @@ impl<'g> UtilityClass<'g> {
         code.const_string("memory_grow")?;
         code.invoke(code.java.members.lang.object.equals)?;
         code.push_branch_instruction(BranchInstruction::If(OrdComparison::EQ, bad_name_case, ()))?;
-        Self::generate_grow_memory_case(
-            code,
-            copy_resized_byte_buffer,
-            pages_to_bytes,
-            bytes_to_pages,
-            int_is_negative_one,
-            next_size,
-        )?;
+        Self::generate_grow_memory_case(code, memory_methods)?;
 
         // memory.fill
         code.place_label(memory_fill_case)?;
@@ -2612,7 +3341,7 @@ impl<'g> UtilityClass<'g> {
         code.const_string("memory_fill")?;
         code.invoke(code.java.members.lang.object.equals)?;
         code.push_branch_instruction(BranchInstruction::If(OrdComparison::EQ, bad_name_case, ()))?;
-        Self::generate_fill_memory_case(code, fill_byte_buffer_range)?;
+        Self::generate_fill_memory_case(code, memory_methods.fill_memory_range)?;
 
         // Catch all case
         code.place_label(bad_name_case)?;
@@ -2675,8 +3404,167 @@ impl<'g> UtilityClass<'g> {
         Ok(())
     }
 
+    /// Analagous to
+    ///
+    /// ```java
+    /// static ConstantCallSite bootstrapVarHandle(
+    ///   MethodHandles.Lookup lookup,
+    ///   String accessMode,
+    ///   MethodType type,
+    ///   Class viewArrayClass
+    /// ) {
+    ///   MethodHandle invoker = MethodHandles.varHandleExactInvoker(
+    ///     VarHandle.AccessMode.valueFromMethodName(accessMode),
+    ///     type
+    ///   );
+    ///   VarHandle view = MethodHandles.byteBufferViewVarHandle(
+    ///     viewArrayClass,
+    ///     ByteOrder.LITTLE_ENDIAN
+    ///   );
+    ///   return new ConstantCallSite(invoker.bindTo(view));
+    /// }
+    /// ```
+    ///
+    /// When memories are byte arrays, the view is made with `byteArrayViewVarHandle` instead.
+    fn generate_bootstrap_var_handle(
+        code: &mut CodeBuilder<'g>,
+        memory_backend: MemoryBackend,
+    ) -> Result<(), Error> {
+        let name_argument = 1;
+        let type_argument = 2;
+        let view_argument = 3;
+
+        code.new(code.java.classes.lang.invoke.constant_call_site)?;
+        code.push_instruction(Instruction::Dup)?;
+
+        // MethodHandles.varHandleExactInvoker(AccessMode.valueFromMethodName(name), type)
+        code.push_instruction(Instruction::ALoad(name_argument))?;
+        code.invoke(
+            code.java
+                .members
+                .lang
+                .invoke
+                .var_handle_access_mode
+                .value_from_method_name,
+        )?;
+        code.push_instruction(Instruction::ALoad(type_argument))?;
+        code.invoke(
+            code.java
+                .members
+                .lang
+                .invoke
+                .method_handles
+                .var_handle_exact_invoker,
+        )?;
+
+        // MethodHandles.byteBufferViewVarHandle(viewArrayClass, ByteOrder.LITTLE_ENDIAN)
+        let method_handles = &code.java.members.lang.invoke.method_handles;
+        let view_var_handle = match memory_backend {
+            MemoryBackend::HeapByteBuffer | MemoryBackend::DirectByteBuffer => {
+                method_handles.byte_buffer_view_var_handle
+            }
+            MemoryBackend::ByteArray => method_handles.byte_array_view_var_handle,
+        };
+        code.push_instruction(Instruction::ALoad(view_argument))?;
+        code.access_field(
+            code.java.members.nio.byte_order.little_endian,
+            AccessMode::Read,
+        )?;
+        code.invoke(view_var_handle)?;
+
+        code.invoke(code.java.members.lang.invoke.method_handle.bind_to)?;
+        code.invoke(code.java.members.lang.invoke.constant_call_site.init)?;
+        code.push_branch_instruction(BranchInstruction::AReturn)?;
+
+        Ok(())
+    }
+
+    /// Push a method handle returning the capacity of the bytes of a memory
+    ///
+    /// This is `ByteBuffer.capacity()` or `MethodHandles.arrayLength(byte[].class)`.
+    fn generate_memory_capacity_handle(
+        code: &mut CodeBuilder<'g>,
+        memory_backend: MemoryBackend,
+    ) -> Result<(), Error> {
+        match memory_backend {
+            MemoryBackend::HeapByteBuffer | MemoryBackend::DirectByteBuffer => {
+                code.const_methodhandle(code.java.members.nio.byte_buffer.capacity)?;
+            }
+            MemoryBackend::ByteArray => {
+                code.const_class(memory_backend.bytes_type(&code.java.classes))?;
+                code.invoke(code.java.members.lang.invoke.method_handles.array_length)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Push a method handle allocating the bytes of a memory given its size in bytes
+    ///
+    /// This is `ByteBuffer.allocate` (or `allocateDirect`) followed by setting the byte order to
+    /// little-endian, or `MethodHandles.arrayConstructor(byte[].class)`.
+    fn generate_memory_allocate_handle(
+        code: &mut CodeBuilder<'g>,
+        memory_backend: MemoryBackend,
+    ) -> Result<(), Error> {
+        let allocate = match memory_backend {
+            MemoryBackend::HeapByteBuffer => code.java.members.nio.byte_buffer.allocate,
+            MemoryBackend::DirectByteBuffer => code.java.members.nio.byte_buffer.allocate_direct,
+            MemoryBackend::ByteArray => {
+                code.const_class(memory_backend.bytes_type(&code.java.classes))?;
+                code.invoke(
+                    code.java
+                        .members
+                        .lang
+                        .invoke
+                        .method_handles
+                        .array_constructor,
+                )?;
+                return Ok(());
+            }
+        };
+
+        /* MethodHandles.filterReturnValue(
+         *   bytebufferAllocate,
+         *   MethodHandles.insertArguments(byteBufferByteOrder, 1, new Object[] { ByteOrder.LITTLE_ENDIAN })
+         * )
+         */
+        code.const_methodhandle(allocate)?;
+        code.const_methodhandle(code.java.members.nio.byte_buffer.order)?;
+        code.push_instruction(Instruction::IConst1)?;
+        code.push_instruction(Instruction::IConst1)?;
+        code.push_instruction(Instruction::ANewArray(RefType::Object(
+            code.java.classes.lang.object,
+        )))?;
+        code.push_instruction(Instruction::Dup)?;
+        code.push_instruction(Instruction::IConst0)?;
+        code.access_field(
+            code.java.members.nio.byte_order.little_endian,
+            AccessMode::Read,
+        )?;
+        code.push_instruction(Instruction::AAStore)?;
+        code.invoke(
+            code.java
+                .members
+                .lang
+                .invoke
+                .method_handles
+                .insert_arguments,
+        )?;
+        code.invoke(
+            code.java
+                .members
+                .lang
+                .invoke
+                .method_handles
+                .filter_return_value,
+        )?;
+
+        Ok(())
+    }
+
     fn generate_size_memory_case(
         code: &mut CodeBuilder<'g>,
+        memory_backend: MemoryBackend,
         bytes_to_pages: MethodId<'g>,
     ) -> Result<(), Error> {
         let getter_argument = 3;
@@ -2689,7 +3577,7 @@ impl<'g> UtilityClass<'g> {
          *   bytesToMemoryPagesHandle                         // (I)I
          */
         code.push_instruction(Instruction::ALoad(getter_argument))?;
-        code.const_methodhandle(code.java.members.nio.byte_buffer.capacity)?;
+        Self::generate_memory_capacity_handle(code, memory_backend)?;
         code.invoke(
             code.java
                 .members
@@ -2721,18 +3609,24 @@ impl<'g> UtilityClass<'g> {
     // TODO: avoid allocating a new memory for `memory.grow 0`
     fn generate_grow_memory_case(
         code: &mut CodeBuilder<'g>,
-        copy_resized_byte_buffer: MethodId<'g>,
-        pages_to_bytes: MethodId<'g>,
-        bytes_to_pages: MethodId<'g>,
-        int_is_negative_one: MethodId<'g>,
-        next_size: MethodId<'g>,
+        memory_methods: MemoryBootstrapMethods<'g>,
     ) -> Result<(), Error> {
+        let MemoryBootstrapMethods {
+            backend: memory_backend,
+            copy_resized_memory,
+            pages_to_bytes,
+            bytes_to_pages,
+            int_is_negative_one,
+            next_size,
+            ..
+        } = memory_methods;
         let requested_type_argument = 2; // MethodType
         let getter_argument = 3; // MethodHandle
         let setter_argument = 4; // MethodHandle
         let max_size_argument = 5; // long
         let module_typ = 7; // Class<?>
         let create_and_update_new_memory = 8; // MethodHandle
+        let bytes_type = memory_backend.bytes_type(&code.java.classes);
 
         // Class<?> moduleType = getter.type().parameterType(0);
         code.push_instruction(Instruction::ALoad(getter_argument))?;
@@ -2742,12 +3636,12 @@ impl<'g> UtilityClass<'g> {
         code.push_instruction(Instruction::AStore(module_typ))?;
 
         /* MethodHandle updateEffects = MethodHandles.collectArguments(
-         *   copyResizedByteBuffer,
+         *   copyResizedMemory,
          *   0,
          *   setter
          * );
          */
-        code.const_methodhandle(copy_resized_byte_buffer)?;
+        code.const_methodhandle(copy_resized_memory)?;
         code.push_instruction(Instruction::IConst0)?;
         code.push_instruction(Instruction::ALoad(setter_argument))?;
         code.invoke(
@@ -2779,7 +3673,7 @@ impl<'g> UtilityClass<'g> {
         )))?;
         code.push_instruction(Instruction::Dup)?;
         code.push_instruction(Instruction::IConst0)?;
-        code.const_class(FieldType::object(code.java.classes.nio.byte_buffer))?;
+        code.const_class(bytes_type)?;
         code.push_instruction(Instruction::AAStore)?;
         code.push_instruction(Instruction::Dup)?;
         code.push_instruction(Instruction::IConst1)?;
@@ -2787,7 +3681,7 @@ impl<'g> UtilityClass<'g> {
         code.push_instruction(Instruction::AAStore)?;
         code.push_instruction(Instruction::Dup)?;
         code.push_instruction(Instruction::IConst2)?;
-        code.const_class(FieldType::object(code.java.classes.nio.byte_buffer))?;
+        code.const_class(bytes_type)?;
         code.push_instruction(Instruction::AAStore)?;
         code.invoke(code.java.members.lang.invoke.method_type.method_type)?;
         code.push_instruction(Instruction::IConst4)?;
@@ -2810,44 +3704,12 @@ impl<'g> UtilityClass<'g> {
         /* MethodHandle createAndUpdateNewMemory = MethodHandles.collectArguments(
          *   permutedEffects,
          *   0,
-         *   MethodHandles.filterReturnValue(
-         *     MethodHandles.filterReturnValue(pagesToBytes, bytebufferAllocate),
-         *     MethodHandles.insertArguments(byteBufferByteOrder, 1, new Object[] { ByteOrder.LITTLE_ENDIAN })
-         *   )
+         *   MethodHandles.filterReturnValue(pagesToBytes, allocateMemory)
          * );
          */
         code.push_instruction(Instruction::IConst0)?;
         code.const_methodhandle(pages_to_bytes)?;
-        code.const_methodhandle(code.java.members.nio.byte_buffer.allocate)?;
-        code.invoke(
-            code.java
-                .members
-                .lang
-                .invoke
-                .method_handles
-                .filter_return_value,
-        )?;
-        code.const_methodhandle(code.java.members.nio.byte_buffer.order)?;
-        code.push_instruction(Instruction::IConst1)?;
-        code.push_instruction(Instruction::IConst1)?;
-        code.push_instruction(Instruction::ANewArray(RefType::Object(
-            code.java.classes.lang.object,
-        )))?;
-        code.push_instruction(Instruction::Dup)?;
-        code.push_instruction(Instruction::IConst0)?;
-        code.access_field(
-            code.java.members.nio.byte_order.little_endian,
-            AccessMode::Read,
-        )?;
-        code.push_instruction(Instruction::AAStore)?;
-        code.invoke(
-            code.java
-                .members
-                .lang
-                .invoke
-                .method_handles
-                .insert_arguments,
-        )?;
+        Self::generate_memory_allocate_handle(code, memory_backend)?;
         code.invoke(
             code.java
                 .members
@@ -2899,7 +3761,7 @@ impl<'g> UtilityClass<'g> {
          *       MethodHandles.constant(long.class, maxSize)
          *     ),
          *     0,
-         *     MethodHandles.filterReturnValue(bytebufferCapacity, bytesToPages)
+         *     MethodHandles.filterReturnValue(memoryCapacity, bytesToPages)
          *   )
          * );
          */
@@ -2919,7 +3781,7 @@ impl<'g> UtilityClass<'g> {
                 .collect_arguments,
         )?;
         code.push_instruction(Instruction::IConst0)?;
-        code.const_methodhandle(code.java.members.nio.byte_buffer.capacity)?;
+        Self::generate_memory_capacity_handle(code, memory_backend)?;
         code.const_methodhandle(bytes_to_pages)?;
         code.invoke(
             code.java
@@ -2974,7 +3836,7 @@ impl<'g> UtilityClass<'g> {
         )))?;
         code.push_instruction(Instruction::Dup)?;
         code.push_instruction(Instruction::IConst0)?;
-        code.const_class(FieldType::object(code.java.classes.nio.byte_buffer))?;
+        code.const_class(bytes_type)?;
         code.push_instruction(Instruction::AAStore)?;
         code.push_instruction(Instruction::Dup)?;
         code.push_instruction(Instruction::IConst1)?;
@@ -3041,17 +3903,17 @@ impl<'g> UtilityClass<'g> {
 
     fn generate_fill_memory_case(
         code: &mut CodeBuilder<'g>,
-        fill_byte_buffer_range: MethodId<'g>,
+        fill_memory_range: MethodId<'g>,
     ) -> Result<(), Error> {
         let getter_argument = 3; // MethodHandle
 
         /* MethodHandle fillEffects = MethodHandles.collectArguments(
-         *   fillByteBufferRange,
+         *   fillMemoryRange,
          *   3,
          *   getter
          * );
          */
-        code.const_methodhandle(fill_byte_buffer_range)?;
+        code.const_methodhandle(fill_memory_range)?;
         code.push_instruction(Instruction::IConst3)?;
         code.push_instruction(Instruction::ALoad(getter_argument))?;
         code.invoke(
@@ -3208,6 +4070,7 @@ impl<'g> BootstrapUtilities<'g> {
                 bootstrap_arguments.extend([object_get, memory_get, memory_set]);
                 UtilityMethod::BootstrapExternalMemory
             }
+            MemoryRepr::Memory64 => panic!("64-bit memories don't use bootstrap methods"),
         };
         let memory_bootstrap =
             utilities.get_utility_method(bootstrap_utility, java, class_graph)?;
@@ -3232,3 +4095,46 @@ impl<'g> BootstrapUtilities<'g> {
         Ok(bootstrap_method)
     }
 }
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::jvm::class_file::Version;
+    use crate::jvm::class_graph::ClassGraphArenas;
+    use crate::jvm::Name;
+
+    /// Generate a utility method (and its dependencies), then serialize the utility class without
+    /// running the peephole pass first
+    fn serialize_unoptimized(settings: &Settings, method: UtilityMethod) {
+        let class_graph_arenas = ClassGraphArenas::new();
+        let class_graph = ClassGraph::new(&class_graph_arenas);
+        let java = class_graph.insert_java_library_types();
+
+        let module_class = class_graph.add_class(ClassData::new(
+            BinaryName::from_str("me/MyModule").unwrap(),
+            java.classes.lang.object,
+            ClassAccessFlags::PUBLIC,
+            None,
+        ));
+        let mut utilities = UtilityClass::new(settings, module_class, &class_graph, &java).unwrap();
+        utilities
+            .get_utility_method(method, &java, &class_graph)
+            .unwrap();
+        utilities
+            .into_builder()
+            .unwrap()
+            .serialize(Version::JAVA11)
+            .unwrap();
+    }
+
+    #[test]
+    fn call_indirect_miss_without_peephole() {
+        for cache_size in [1, 4, u8::MAX] {
+            let mut settings = Settings::new("me/MyModule", None).unwrap();
+            settings.peephole_optimization = false;
+            settings.call_indirect_cache_size = cache_size;
+            serialize_unoptimized(&settings, UtilityMethod::CallIndirectMiss);
+        }
+    }
+}
@@ -1,7 +1,9 @@
 mod offset_vec;
 mod ref_id;
 mod segment_tree;
+mod zlib;
 
 pub use offset_vec::*;
 pub use ref_id::*;
 pub use segment_tree::*;
+pub use zlib::*;
@@ -0,0 +1,358 @@
+//! Compression into the zlib format (RFC 1950), as read by `java.util.zip.InflaterInputStream`
+//!
+//! The compressed data is a single deflate block (RFC 1951) using LZ77 and the fixed Huffman
+//! codes. Dynamic codes would compress a bit better, but this is much simpler and still does well
+//! on the long runs of zeros and repeated patterns common in WASM data segments.
+
+/// Size of the window in which LZ77 looks for earlier occurrences of the upcoming bytes
+const WINDOW_SIZE: usize = 32768;
+
+/// Shortest and longest lengths which can be encoded as a back-reference
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+
+/// How many earlier occurrences of the same 3 bytes to try before settling on a match
+const MAX_CHAIN: usize = 128;
+
+const HASH_BITS: u32 = 15;
+
+/// Smallest length encoded by each length symbol (starting at 257), and its number of extra bits
+const LENGTH_BASES: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA_BITS: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+
+/// Smallest distance encoded by each distance symbol, and its number of extra bits
+const DISTANCE_BASES: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA_BITS: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+/// Compress bytes into the zlib format
+pub fn zlib_compress(bytes: &[u8]) -> Vec<u8> {
+    let mut writer = BitWriter {
+        output: vec![0x78, 0x9C],
+        buffer: 0,
+        buffer_length: 0,
+    };
+
+    // Single final block, using the fixed Huffman codes
+    writer.write_bits(1, 1);
+    writer.write_bits(1, 2);
+
+    // Chains of earlier positions starting with the same 3 bytes (most recent first)
+    let mut head: Vec<usize> = vec![usize::MAX; 1 << HASH_BITS];
+    let mut previous: Vec<usize> = vec![usize::MAX; bytes.len()];
+    let insert = |position: usize, head: &mut [usize], previous: &mut [usize]| {
+        if position + MIN_MATCH <= bytes.len() {
+            let hash = hash(&bytes[position..]);
+            previous[position] = head[hash];
+            head[hash] = position;
+        }
+    };
+
+    let mut position = 0;
+    while position < bytes.len() {
+        // Find the longest match among the earlier occurrences of the next 3 bytes
+        let max_length = MAX_MATCH.min(bytes.len() - position);
+        let mut best_length = 0;
+        let mut best_distance = 0;
+        if max_length >= MIN_MATCH {
+            let mut candidate = head[hash(&bytes[position..])];
+            let mut chain = 0;
+            while candidate != usize::MAX
+                && position - candidate <= WINDOW_SIZE
+                && chain < MAX_CHAIN
+            {
+                let length = bytes[candidate..]
+                    .iter()
+                    .zip(&bytes[position..position + max_length])
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                if length > best_length {
+                    best_length = length;
+                    best_distance = position - candidate;
+                    if length == max_length {
+                        break;
+                    }
+                }
+                candidate = previous[candidate];
+                chain += 1;
+            }
+        }
+
+        if best_length >= MIN_MATCH {
+            writer.write_length(best_length);
+            writer.write_distance(best_distance);
+            for matched in position..position + best_length {
+                insert(matched, &mut head, &mut previous);
+            }
+            position += best_length;
+        } else {
+            writer.write_literal_length(bytes[position] as u16);
+            insert(position, &mut head, &mut previous);
+            position += 1;
+        }
+    }
+
+    // End of block, then the checksum
+    writer.write_literal_length(256);
+    writer.flush();
+    let mut output = writer.output;
+    output.extend_from_slice(&adler32(bytes).to_be_bytes());
+    output
+}
+
+/// Hash of the first 3 bytes
+fn hash(bytes: &[u8]) -> usize {
+    let hash = (bytes[0] as usize) << 10 ^ (bytes[1] as usize) << 5 ^ bytes[2] as usize;
+    hash & ((1 << HASH_BITS) - 1)
+}
+
+/// Adler-32 checksum (which ends a zlib stream)
+fn adler32(bytes: &[u8]) -> u32 {
+    const MODULUS: u32 = 65521;
+    let mut a: u32 = 1;
+    let mut b: u32 = 0;
+
+    // Sums over this many bytes can't overflow before taking the modulus
+    for chunk in bytes.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= MODULUS;
+        b %= MODULUS;
+    }
+    b << 16 | a
+}
+
+/// Writer of a deflate bit stream, where bits are packed starting at the least significant bit
+struct BitWriter {
+    output: Vec<u8>,
+    buffer: u32,
+    buffer_length: u32,
+}
+
+impl BitWriter {
+    /// Write a value in the given number of bits, least significant bit first
+    fn write_bits(&mut self, value: u32, length: u32) {
+        self.buffer |= value << self.buffer_length;
+        self.buffer_length += length;
+        while self.buffer_length >= 8 {
+            self.output.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.buffer_length -= 8;
+        }
+    }
+
+    /// Write a Huffman code, which goes most significant bit first
+    fn write_code(&mut self, code: u32, length: u32) {
+        self.write_bits(code.reverse_bits() >> (32 - length), length);
+    }
+
+    /// Write a symbol from the literal/length alphabet, using its fixed code
+    fn write_literal_length(&mut self, symbol: u16) {
+        let symbol = symbol as u32;
+        match symbol {
+            0..=143 => self.write_code(0x30 + symbol, 8),
+            144..=255 => self.write_code(0x190 + symbol - 144, 9),
+            256..=279 => self.write_code(symbol - 256, 7),
+            _ => self.write_code(0xC0 + symbol - 280, 8),
+        }
+    }
+
+    /// Write the length of a back-reference
+    fn write_length(&mut self, length: usize) {
+        let idx = LENGTH_BASES.partition_point(|&base| base as usize <= length) - 1;
+        self.write_literal_length(257 + idx as u16);
+        let extra = length as u32 - LENGTH_BASES[idx] as u32;
+        self.write_bits(extra, LENGTH_EXTRA_BITS[idx] as u32);
+    }
+
+    /// Write the distance of a back-reference (using the fixed 5-bit codes)
+    fn write_distance(&mut self, distance: usize) {
+        let idx = DISTANCE_BASES.partition_point(|&base| base as usize <= distance) - 1;
+        self.write_code(idx as u32, 5);
+        let extra = distance as u32 - DISTANCE_BASES[idx] as u32;
+        self.write_bits(extra, DISTANCE_EXTRA_BITS[idx] as u32);
+    }
+
+    /// Pad the last byte with zeros
+    fn flush(&mut self) {
+        if self.buffer_length > 0 {
+            self.output.push(self.buffer as u8);
+            self.buffer = 0;
+            self.buffer_length = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Decompress a zlib stream made of blocks using the fixed Huffman codes
+    fn zlib_decompress(compressed: &[u8]) -> Vec<u8> {
+        assert_eq!((compressed[0] as u32 * 256 + compressed[1] as u32) % 31, 0);
+        let mut reader = BitReader {
+            input: &compressed[2..],
+            position: 0,
+        };
+        let mut output: Vec<u8> = vec![];
+        loop {
+            let last_block = reader.read_bits(1) == 1;
+            assert_eq!(
+                reader.read_bits(2),
+                1,
+                "only fixed Huffman blocks are supported"
+            );
+            loop {
+                let symbol = reader.read_literal_length();
+                if symbol < 256 {
+                    output.push(symbol as u8);
+                    continue;
+                } else if symbol == 256 {
+                    break;
+                }
+                let idx = symbol as usize - 257;
+                let length = LENGTH_BASES[idx] as usize
+                    + reader.read_bits(LENGTH_EXTRA_BITS[idx] as u32) as usize;
+                let idx = reader.read_code(5) as usize;
+                let distance = DISTANCE_BASES[idx] as usize
+                    + reader.read_bits(DISTANCE_EXTRA_BITS[idx] as u32) as usize;
+                for _ in 0..length {
+                    output.push(output[output.len() - distance]);
+                }
+            }
+            if last_block {
+                break;
+            }
+        }
+        let checksum_start = 2 + (reader.position + 7) / 8;
+        let checksum = &compressed[checksum_start..];
+        assert_eq!(checksum, adler32(&output).to_be_bytes());
+        output
+    }
+
+    struct BitReader<'a> {
+        input: &'a [u8],
+        position: usize,
+    }
+
+    impl<'a> BitReader<'a> {
+        fn read_bits(&mut self, length: u32) -> u32 {
+            let mut value = 0;
+            for bit in 0..length {
+                let byte = self.input[self.position / 8];
+                value |= ((byte >> (self.position % 8)) as u32 & 1) << bit;
+                self.position += 1;
+            }
+            value
+        }
+
+        fn read_code(&mut self, length: u32) -> u32 {
+            (0..length).fold(0, |code, _| code << 1 | self.read_bits(1))
+        }
+
+        fn read_literal_length(&mut self) -> u32 {
+            let code = self.read_code(7);
+            if code <= 0x17 {
+                return code + 256;
+            }
+            let code = code << 1 | self.read_bits(1);
+            match code {
+                0x30..=0xBF => code - 0x30,
+                0xC0..=0xC7 => code - 0xC0 + 280,
+                _ => (code << 1 | self.read_bits(1)) - 0x190 + 144,
+            }
+        }
+    }
+
+    fn pseudo_random_bytes(length: usize, seed: u32) -> Vec<u8> {
+        let mut state = seed;
+        (0..length)
+            .map(|_| {
+                state = state.wrapping_mul(1103515245).wrapping_add(12345);
+                (state >> 16) as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn adler32_checksum() {
+        assert_eq!(adler32(b""), 1);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E60398);
+        assert_eq!(adler32(&[0xFF; 100_000]), 0x149A_302C);
+    }
+
+    #[test]
+    fn empty() {
+        let compressed = zlib_compress(b"");
+        assert_eq!(compressed, [0x78, 0x9C, 0x03, 0x00, 0x00, 0x00, 0x00, 0x01]);
+        assert_eq!(zlib_decompress(&compressed), b"");
+    }
+
+    #[test]
+    fn short() {
+        for bytes in [
+            &b"a"[..],
+            b"ab",
+            b"abc",
+            b"abcabc",
+            b"aaaa",
+            b"hello, hello, hello!",
+        ] {
+            assert_eq!(zlib_decompress(&zlib_compress(bytes)), bytes);
+        }
+    }
+
+    #[test]
+    fn all_byte_values() {
+        let bytes: Vec<u8> = (0..=255).chain((0..=255).rev()).collect();
+        assert_eq!(zlib_decompress(&zlib_compress(&bytes)), bytes);
+    }
+
+    #[test]
+    fn zeros() {
+        let bytes = vec![0; 1_000_000];
+        let compressed = zlib_compress(&bytes);
+        assert!(compressed.len() < 10_000);
+        assert_eq!(zlib_decompress(&compressed), bytes);
+    }
+
+    #[test]
+    fn repeated_pattern() {
+        let pattern = pseudo_random_bytes(1000, 7);
+        let bytes: Vec<u8> = pattern.iter().copied().cycle().take(100_000).collect();
+        let compressed = zlib_compress(&bytes);
+        assert!(compressed.len() < 3_000);
+        assert_eq!(zlib_decompress(&compressed), bytes);
+    }
+
+    #[test]
+    fn random() {
+        for (length, seed) in [(10, 1), (1000, 2), (100_000, 3)] {
+            let bytes = pseudo_random_bytes(length, seed);
+            assert_eq!(zlib_decompress(&zlib_compress(&bytes)), bytes);
+        }
+    }
+
+    #[test]
+    fn distant_matches() {
+        // Matches close to, at, and past the end of the window
+        let mut bytes = pseudo_random_bytes(100, 4);
+        bytes.extend(vec![1; WINDOW_SIZE - 100]);
+        bytes.extend(pseudo_random_bytes(300, 4));
+        bytes.extend(pseudo_random_bytes(WINDOW_SIZE, 5));
+        bytes.extend(pseudo_random_bytes(300, 4));
+        assert_eq!(zlib_decompress(&zlib_compress(&bytes)), bytes);
+    }
+}
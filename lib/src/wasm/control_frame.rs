@@ -59,6 +59,51 @@ pub enum ControlFrame<Lbl> {
         /// Height of WASM operand stack under this (used to decide how far to pop when breaking)
         base_stack_height: u32,
     },
+    Try {
+        /// Label for the exception handler protecting the body of the `try`
+        handler: Lbl,
+
+        /// Label for the end of the block
+        end_block: Lbl,
+
+        /// What to return from the block
+        return_values: Vec<StackType>,
+
+        /// Height of WASM operand stack under this (used to decide how far to pop when breaking)
+        base_stack_height: u32,
+
+        /// Local variable in which the caught exception gets stored
+        exception_local: u16,
+
+        /// Local variable in which the WASM operand stack under this gets saved (the JVM clears
+        /// the operand stack when an exception is caught)
+        saved_stack_local: u16,
+
+        /// Types of the values saved in `saved_stack_local`
+        saved_stack: Vec<StackType>,
+    },
+    Catch {
+        /// Label for the next `catch` clause (`None` for `catch_all`)
+        next_catch: Option<Lbl>,
+
+        /// Label for the end of the block
+        end_block: Lbl,
+
+        /// What to return from the block
+        return_values: Vec<StackType>,
+
+        /// Height of WASM operand stack under this (used to decide how far to pop when breaking)
+        base_stack_height: u32,
+
+        /// Local variable in which the caught exception is stored
+        exception_local: u16,
+
+        /// Local variable in which the WASM operand stack under this is saved
+        saved_stack_local: u16,
+
+        /// Types of the values saved in `saved_stack_local`
+        saved_stack: Vec<StackType>,
+    },
 }
 
 impl<Lbl: Copy> ControlFrame<Lbl> {
@@ -69,6 +114,8 @@ impl<Lbl: Copy> ControlFrame<Lbl> {
             ControlFrame::Else { end_block, .. } => *end_block,
             ControlFrame::Loop { start_loop, .. } => *start_loop,
             ControlFrame::Block { end_block, .. } => *end_block,
+            ControlFrame::Try { end_block, .. } => *end_block,
+            ControlFrame::Catch { end_block, .. } => *end_block,
         }
     }
 
@@ -82,6 +129,8 @@ impl<Lbl: Copy> ControlFrame<Lbl> {
             ControlFrame::Else { return_values, .. } => return_values,
             ControlFrame::Loop { input_values, .. } => input_values,
             ControlFrame::Block { return_values, .. } => return_values,
+            ControlFrame::Try { return_values, .. } => return_values,
+            ControlFrame::Catch { return_values, .. } => return_values,
         }
     }
 
@@ -92,6 +141,8 @@ impl<Lbl: Copy> ControlFrame<Lbl> {
             ControlFrame::Else { end_block, .. } => *end_block,
             ControlFrame::Loop { after_block, .. } => *after_block,
             ControlFrame::Block { end_block, .. } => *end_block,
+            ControlFrame::Try { end_block, .. } => *end_block,
+            ControlFrame::Catch { end_block, .. } => *end_block,
         }
     }
 
@@ -102,6 +153,8 @@ impl<Lbl: Copy> ControlFrame<Lbl> {
             ControlFrame::Else { return_values, .. } => return_values,
             ControlFrame::Loop { return_values, .. } => return_values,
             ControlFrame::Block { return_values, .. } => return_values,
+            ControlFrame::Try { return_values, .. } => return_values,
+            ControlFrame::Catch { return_values, .. } => return_values,
         }
     }
 
@@ -122,6 +175,12 @@ impl<Lbl: Copy> ControlFrame<Lbl> {
             ControlFrame::Block {
                 base_stack_height, ..
             } => *base_stack_height,
+            ControlFrame::Try {
+                base_stack_height, ..
+            } => *base_stack_height,
+            ControlFrame::Catch {
+                base_stack_height, ..
+            } => *base_stack_height,
         }
     }
 }
@@ -1,15 +1,17 @@
 use crate::jvm::class_graph::{ClassId, JavaClasses};
-use crate::jvm::{FieldType, MethodDescriptor, RefType};
+use crate::jvm::{ArrayType, BaseType, FieldType, MethodDescriptor, RefType};
+use crate::runtime::TupleClasses;
 use crate::util::Width;
 use wasmparser::{ValType, WasmFuncType};
 
 /// Subset of WASM types that we know how to put on the WASM stack
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum StackType {
     I32,
     I64,
     F32,
     F64,
+    V128,
     FuncRef,
     ExternRef,
 }
@@ -22,6 +24,7 @@ impl StackType {
             StackType::I64 => FieldType::long(),
             StackType::F32 => FieldType::float(),
             StackType::F64 => FieldType::double(),
+            StackType::V128 => FieldType::Ref(v128_ref_type()),
             StackType::FuncRef => FieldType::object(java.lang.invoke.method_handle),
             StackType::ExternRef => FieldType::object(java.lang.object),
         }
@@ -34,11 +37,34 @@ impl StackType {
             ValType::I64 => StackType::I64,
             ValType::F32 => StackType::F32,
             ValType::F64 => StackType::F64,
+            ValType::V128 => StackType::V128,
             ValType::FuncRef => StackType::FuncRef,
             ValType::ExternRef => StackType::ExternRef,
-            _ => return Err(BadType::UnsupportedType(wasm_type)),
         })
     }
+
+    /// Mapping from stack types back into general types
+    pub const fn to_general(self) -> ValType {
+        match self {
+            StackType::I32 => ValType::I32,
+            StackType::I64 => ValType::I64,
+            StackType::F32 => ValType::F32,
+            StackType::F64 => ValType::F64,
+            StackType::V128 => ValType::V128,
+            StackType::FuncRef => ValType::FuncRef,
+            StackType::ExternRef => ValType::ExternRef,
+        }
+    }
+}
+
+/// JVM type used to represent `v128` values
+///
+/// The low 64 bits are in the first element and the high 64 bits in the second one.
+pub const fn v128_ref_type<'g>() -> RefType<ClassId<'g>> {
+    RefType::PrimitiveArray(ArrayType {
+        additional_dimensions: 0,
+        element_type: BaseType::Long,
+    })
 }
 
 /// Mapping from general types into reference types
@@ -56,14 +82,26 @@ pub const fn ref_type_from_general<'g>(
 impl Width for StackType {
     fn width(&self) -> usize {
         match self {
-            StackType::I32 | StackType::F32 | StackType::FuncRef | StackType::ExternRef => 1,
+            StackType::I32
+            | StackType::F32
+            | StackType::V128
+            | StackType::FuncRef
+            | StackType::ExternRef => 1,
             StackType::I64 | StackType::F64 => 2,
         }
     }
 }
 
+/// Maximum number of JVM parameter slots which the inputs of a function can take up before some
+/// of them get packed into an `Object[]`
+///
+/// The JVM caps methods at 255 parameter slots. Some are kept free since calls pass extra
+/// arguments (the WASM module, the table index for `call_indirect`), and since method handle
+/// combinators (eg. `insertArguments` for tail calls) fail on types close to the limit.
+pub const MAX_UNPACKED_INPUT_SLOTS: usize = 240;
+
 /// WASM type of a function or block
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct FunctionType {
     pub inputs: Vec<StackType>,
     pub outputs: Vec<StackType>,
@@ -85,18 +123,66 @@ impl FunctionType {
         Ok(FunctionType { inputs, outputs })
     }
 
+    /// Number of inputs which are passed as regular parameters
+    ///
+    /// If the inputs take up more than [`MAX_UNPACKED_INPUT_SLOTS`] slots, only a prefix of them
+    /// are passed as regular parameters and the rest get packed into one trailing `Object[]`
+    /// parameter (containing the boxed values).
+    pub fn unpacked_inputs(&self) -> usize {
+        let total_width: usize = self.inputs.iter().map(Width::width).sum();
+        if total_width <= MAX_UNPACKED_INPUT_SLOTS {
+            return self.inputs.len();
+        }
+
+        // Leave one slot for the `Object[]`
+        let mut width = 0;
+        let mut unpacked = 0;
+        for input in &self.inputs {
+            width += input.width();
+            if width >= MAX_UNPACKED_INPUT_SLOTS {
+                break;
+            }
+            unpacked += 1;
+        }
+        unpacked
+    }
+
+    /// Inputs which get packed into a trailing `Object[]` parameter (usually empty)
+    pub fn packed_inputs(&self) -> &[StackType] {
+        &self.inputs[self.unpacked_inputs()..]
+    }
+
+    /// Number of parameters of the method descriptor
+    pub fn parameter_count(&self) -> usize {
+        let unpacked_inputs = self.unpacked_inputs();
+        if unpacked_inputs < self.inputs.len() {
+            unpacked_inputs + 1
+        } else {
+            unpacked_inputs
+        }
+    }
+
     /// Into a method descriptor
-    pub fn method_descriptor<'g>(&self, java: &JavaClasses<'g>) -> MethodDescriptor<ClassId<'g>> {
+    ///
+    /// Multiple outputs are returned in a tuple class (see [`TupleClasses`]).
+    pub fn method_descriptor<'g>(
+        &self,
+        java: &JavaClasses<'g>,
+        tuples: &TupleClasses<'g>,
+    ) -> MethodDescriptor<ClassId<'g>> {
         let return_type = match self.outputs.as_slice() {
             [] => None,
             [output_ty] => Some(output_ty.field_type(java)),
-            _ => Some(FieldType::array(FieldType::object(java.lang.object))),
+            outputs => Some(FieldType::object(tuples.get(outputs).class)),
         };
-        let parameters = self
-            .inputs
+        let unpacked_inputs = self.unpacked_inputs();
+        let mut parameters: Vec<_> = self.inputs[..unpacked_inputs]
             .iter()
             .map(|input| input.field_type(java))
             .collect();
+        if unpacked_inputs < self.inputs.len() {
+            parameters.push(FieldType::array(FieldType::object(java.lang.object)));
+        }
         MethodDescriptor {
             parameters,
             return_type,